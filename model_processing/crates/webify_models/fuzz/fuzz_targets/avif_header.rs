@@ -0,0 +1,10 @@
+//! Reading the header of arbitrary AVIF files
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::image_processing::read_avif_info_bytes;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = read_avif_info_bytes(data);
+});
@@ -0,0 +1,10 @@
+//! Decoding arbitrary DDS files
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::image_processing::decode_dds_bytes;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_dds_bytes(data);
+});
@@ -0,0 +1,12 @@
+//! Marking arbitrary model.config files as processed
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::config::ModelConfigMark;
+use webify_models::model::mark_model_config_contents;
+
+fuzz_target!(|contents: &str| {
+    let _ = mark_model_config_contents(contents, ModelConfigMark::Element);
+    let _ = mark_model_config_contents(contents, ModelConfigMark::Version);
+});
@@ -0,0 +1,10 @@
+//! Decoding arbitrary PSD and PSB files
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::image_processing::decode_psd_bytes;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_psd_bytes(data);
+});
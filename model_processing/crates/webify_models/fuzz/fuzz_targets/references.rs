@@ -0,0 +1,22 @@
+//! References found in arbitrary DAE, SDF and MTL files
+
+#![no_main]
+
+use std::path::Path;
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::mesh_update::find_references;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the parser, the rest is the file
+    let (kind, contents) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let file = match kind % 3 {
+        0 => "model.dae",
+        1 => "model.sdf",
+        _ => "model.mtl",
+    };
+    let _ = find_references(Path::new(file), contents);
+});
@@ -0,0 +1,24 @@
+//! Injecting texture metadata into arbitrary SDF files
+
+#![no_main]
+
+use std::path::{Path, PathBuf};
+
+use libfuzzer_sys::fuzz_target;
+use webify_models::model::inject_texture_metadata_contents;
+use webify_models::report::TextureStats;
+
+fuzz_target!(|contents: &str| {
+    let model_dir = Path::new("model");
+    let texture = TextureStats {
+        path: model_dir.join("materials/textures/wood & <tile>.png"),
+        model: Some(PathBuf::from(model_dir)),
+        width: 512,
+        height: 256,
+        channels: 4,
+        file_bytes: 1024,
+        gpu_bytes: 699_050,
+        encoding: None,
+    };
+    let _ = inject_texture_metadata_contents(contents, model_dir, &[&texture]);
+});
@@ -0,0 +1,25 @@
+//! Layout of a texture atlas, the UV remap table of the textures packed in it
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Size of a texture atlas and where each texture packed in it went
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AtlasMetadata {
+    pub width: u32,
+    pub height: u32,
+    pub regions: Vec<AtlasRegion>,
+}
+
+/// Where a texture went in the atlas, in pixels from its top left corner. A UV
+/// `(u, v)` of the texture, `v` going up as in DAE meshes and OpenGL, becomes
+/// `(x + u * width, y + (1 - v) * height)` in the atlas, in pixels from the top.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AtlasRegion {
+    /// Path the texture had, relative to the model directory
+    pub texture: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
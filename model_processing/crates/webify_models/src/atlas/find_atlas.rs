@@ -0,0 +1,58 @@
+//! Find the texture atlas of a model and its metadata
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::atlas::AtlasMetadata;
+use crate::image_processing::TEXTURE_IMAGE_TYPES;
+
+/// Name of the atlas files of the model, without extension: the model's name
+/// followed by `_atlas`, so it can't clash with the textures of other models in
+/// the shared pool
+pub fn atlas_stem(model_dir: &Path) -> String {
+    let name = model_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("{}_atlas", name)
+}
+
+/// The texture atlas of the model, in whichever format the texture pipeline left
+/// it, and the metadata next to it. `None` when either is missing.
+pub fn find_atlas(model_dir: &Path) -> Result<Option<(PathBuf, AtlasMetadata)>, Error> {
+    let textures_dir = model_dir.join("materials").join("textures");
+    let stem = atlas_stem(model_dir);
+    let metadata_path = textures_dir.join(format!("{}.json", stem));
+    if !metadata_path.is_file() {
+        return Ok(None);
+    }
+
+    let mut atlases = Vec::new();
+    for entry in fs::read_dir(&textures_dir)? {
+        let path = entry?.path();
+        let is_texture = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| TEXTURE_IMAGE_TYPES.contains(&e.to_lowercase().as_str()));
+        if is_texture && path.file_stem().and_then(|s| s.to_str()) == Some(stem.as_str()) {
+            atlases.push(path);
+        }
+    }
+    atlases.sort();
+    let atlas = match atlases.into_iter().next() {
+        Some(atlas) => atlas,
+        None => return Ok(None),
+    };
+
+    let metadata = serde_json::from_str(&fs::read_to_string(&metadata_path)?).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid atlas metadata {:?}: {}", metadata_path, e),
+        )
+    })?;
+    Ok(Some((atlas, metadata)))
+}
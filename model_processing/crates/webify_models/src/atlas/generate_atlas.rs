@@ -0,0 +1,398 @@
+//! Pack the small textures of a model into an atlas
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File},
+    io::{BufReader, Error},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use crate::atlas::{
+    atlas_stem, find_atlas, pack_rectangles, read_dae_texture_uvs, AtlasMetadata, AtlasRegion,
+    DaeTextureUvs, UvArray,
+};
+use crate::dependencies::resolve_reference;
+use crate::image_processing::decode_texture;
+use crate::mesh_update::{find_references, scan_dir_for_reference_files};
+use crate::options::RunOptions;
+use crate::provenance::SIDECAR_EXTENSION;
+use crate::trash::remove_file;
+use crate::xml::{edit_element_texts, XmlElement};
+
+/// Pixels around each texture in the atlas, copies of its edges, so filtering and
+/// the smaller mip levels don't bleed the neighbouring textures in
+const PADDING: u32 = 2;
+
+/// How far out of `[0, 1]` UVs may go and still be taken for rounding errors
+const UV_TOLERANCE: f64 = 1e-3;
+
+/// A DAE mesh of the model, with the files its `<image>`s reference by id
+struct DaeMesh {
+    path: PathBuf,
+    contents: String,
+    images: HashMap<String, PathBuf>,
+    uvs: DaeTextureUvs,
+}
+
+/// Pack the textures of the model no larger than `--atlas-max-texture` into an
+/// atlas, written to its textures directory as `<model>_atlas.png` with the UV
+/// remap table in `<model>_atlas.json`. The UVs of the DAE meshes sampling the
+/// packed textures are remapped to the atlas, their `<image>`s reference it, and
+/// the packed textures are removed. The atlas then goes through the texture
+/// pipeline like any texture. Returns the atlas written, `None` when the model
+/// already has one or has fewer than two textures to pack.
+///
+/// Textures are left alone when they can't be remapped: sampled with UVs out of
+/// `[0, 1]` (tiled), with UVs shared with other textures, along with other
+/// textures in a material, or referenced by the SDFs and MTL materials of the
+/// model, which have no UVs to remap.
+pub fn generate_atlas(
+    model_dir: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    options: &RunOptions,
+) -> Result<Option<PathBuf>, Error> {
+    // Metadata that doesn't parse is rewritten along with the atlas
+    if find_atlas(model_dir).ok().flatten().is_some() {
+        return Ok(None);
+    }
+
+    let mut meshes = Vec::new();
+    let mut sampled = BTreeSet::new();
+    let mut pinned = BTreeSet::new();
+    for file in scan_dir_for_reference_files(model_dir)? {
+        if file.extension().and_then(|e| e.to_str()) != Some("dae") {
+            let references = find_references(&file, BufReader::new(File::open(&file)?))
+                .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", file, e)))?;
+            pinned.extend(
+                references
+                    .iter()
+                    .filter_map(|reference| resolve_reference(&file, reference, models)),
+            );
+            continue;
+        }
+
+        let contents = fs::read_to_string(&file)?;
+        let root = XmlElement::parse(&contents)
+            .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", file, e)))?;
+        let uvs = read_dae_texture_uvs(&root);
+        let images: HashMap<String, PathBuf> = uvs
+            .images
+            .iter()
+            .filter_map(|(id, reference)| {
+                Some((id.clone(), resolve_reference(&file, reference, models)?))
+            })
+            .collect();
+
+        pinned.extend(uvs.pinned.iter().filter_map(|id| images.get(id)).cloned());
+        for array in uvs.uv_arrays.values() {
+            let textures: Vec<&PathBuf> = array
+                .images
+                .iter()
+                .flatten()
+                .filter_map(|id| images.get(id))
+                .collect();
+            match textures.as_slice() {
+                [texture] if array.images.len() == 1 && in_unit_square(array) => {
+                    sampled.insert((*texture).clone());
+                }
+                _ => pinned.extend(textures.into_iter().cloned()),
+            }
+        }
+
+        meshes.push(DaeMesh {
+            path: file,
+            contents,
+            images,
+            uvs,
+        });
+    }
+
+    let max_texture_size = options.atlas.max_texture_size;
+    let mut textures: Vec<(PathBuf, DynamicImage)> = Vec::new();
+    for path in sampled.difference(&pinned) {
+        // Textures of other models and the shared pool may be sampled elsewhere
+        if !path.starts_with(model_dir) {
+            continue;
+        }
+        // Textures the converter can't read are left for it to report
+        if let Ok(image) = decode_texture(path, &options.hdr) {
+            let (width, height) = image.dimensions();
+            if width > 0 && height > 0 && width <= max_texture_size && height <= max_texture_size {
+                textures.push((path.clone(), image));
+            }
+        }
+    }
+
+    let sizes: Vec<(u32, u32)> = textures
+        .iter()
+        .map(|(_, image)| (image.width() + 2 * PADDING, image.height() + 2 * PADDING))
+        .collect();
+    let (placements, (width, height)) = pack_rectangles(&sizes, options.atlas.size);
+    let packed: Vec<(&PathBuf, &DynamicImage, (u32, u32))> = textures
+        .iter()
+        .zip(placements)
+        .filter_map(|((path, image), placement)| Some((path, image, placement?)))
+        .collect();
+    if packed.len() < 2 {
+        return Ok(None);
+    }
+
+    let mut atlas = RgbaImage::new(width, height);
+    let mut metadata = AtlasMetadata {
+        width,
+        height,
+        regions: Vec::new(),
+    };
+    let mut regions: HashMap<&PathBuf, AtlasRegion> = HashMap::new();
+    for (path, image, (x, y)) in packed {
+        let image = image.to_rgba8();
+        let (texture_width, texture_height) = image.dimensions();
+        for dy in 0..texture_height + 2 * PADDING {
+            for dx in 0..texture_width + 2 * PADDING {
+                let source_x = dx.saturating_sub(PADDING).min(texture_width - 1);
+                let source_y = dy.saturating_sub(PADDING).min(texture_height - 1);
+                atlas.put_pixel(x + dx, y + dy, *image.get_pixel(source_x, source_y));
+            }
+        }
+
+        let region = AtlasRegion {
+            texture: relative_reference(path, model_dir),
+            x: x + PADDING,
+            y: y + PADDING,
+            width: texture_width,
+            height: texture_height,
+        };
+        metadata.regions.push(region.clone());
+        regions.insert(path, region);
+    }
+
+    let stem = atlas_stem(model_dir);
+    let mut edited_meshes = Vec::new();
+    for mesh in &meshes {
+        let image_regions: HashMap<&str, &AtlasRegion> = mesh
+            .images
+            .iter()
+            .filter_map(|(id, path)| Some((id.as_str(), regions.get(path)?)))
+            .collect();
+        if image_regions.is_empty() {
+            continue;
+        }
+        let array_regions: HashMap<&str, (&UvArray, &AtlasRegion)> = mesh
+            .uvs
+            .uv_arrays
+            .iter()
+            .filter_map(
+                |(id, array)| match array.images.iter().collect::<Vec<_>>()[..] {
+                    [Some(image)] => {
+                        Some((id.as_str(), (array, *image_regions.get(image.as_str())?)))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+
+        let depth = mesh
+            .path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(model_dir).ok())
+            .map(|dir| dir.components().count())
+            .unwrap_or_default();
+        let atlas_reference = format!("{}materials/textures/{}.png", "../".repeat(depth), stem);
+        let edited = edit_element_texts(&mesh.contents, |ancestors, text| {
+            let element = ancestors.last()?;
+            match element.name.as_str() {
+                "float_array" => {
+                    let (array, region) = array_regions.get(element.attribute("id")?)?;
+                    Some(remap_uvs(text, array, region, (width, height)))
+                }
+                // `<init_from>` of COLLADA 1.4 images, `<init_from><ref>` of 1.5 ones
+                "init_from" | "ref" => {
+                    let parent = if element.name == "ref" { 2 } else { 1 };
+                    let image = ancestors.iter().rev().nth(parent)?;
+                    (image.name == "image" && image_regions.contains_key(image.attribute("id")?))
+                        .then(|| atlas_reference.clone())
+                }
+                _ => None,
+            }
+        })?;
+        edited_meshes.push((&mesh.path, edited));
+    }
+
+    let textures_dir = model_dir.join("materials").join("textures");
+    fs::create_dir_all(&textures_dir)?;
+    let atlas_path = textures_dir.join(format!("{}.png", stem));
+    atlas
+        .save(&atlas_path)
+        .map_err(|e| Error::other(format!("Could not save {:?}: {:?}", atlas_path, e)))?;
+    fs::write(
+        textures_dir.join(format!("{}.json", stem)),
+        serde_json::to_string_pretty(&metadata)?,
+    )?;
+    for (path, edited) in edited_meshes {
+        fs::write(path, edited)?;
+    }
+
+    for path in regions.keys() {
+        remove_file(path, &options.removal)?;
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(format!(".{}", SIDECAR_EXTENSION));
+        let sidecar = PathBuf::from(sidecar);
+        if sidecar.is_file() {
+            remove_file(&sidecar, &options.removal)?;
+        }
+    }
+
+    Ok(Some(atlas_path))
+}
+
+/// Whether every UV of the array is in `[0, 1]`, so the texture isn't tiled
+fn in_unit_square(array: &UvArray) -> bool {
+    array
+        .values
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i >= array.offset && (i - array.offset) % array.stride < 2)
+        .all(|(_, value)| (-UV_TOLERANCE..=1.0 + UV_TOLERANCE).contains(value))
+}
+
+/// The values of a UV `<float_array>` remapped to the region of the atlas
+fn remap_uvs(
+    text: &str,
+    array: &UvArray,
+    region: &AtlasRegion,
+    (width, height): (u32, u32),
+) -> String {
+    text.split_whitespace()
+        .enumerate()
+        .map(|(i, value)| {
+            let component = i.checked_sub(array.offset).map(|i| i % array.stride);
+            match (component, value.parse::<f64>()) {
+                (Some(0), Ok(u)) => {
+                    let u = (f64::from(region.x) + u * f64::from(region.width)) / f64::from(width);
+                    (u as f32).to_string()
+                }
+                (Some(1), Ok(v)) => {
+                    let top = f64::from(region.y) + (1.0 - v) * f64::from(region.height);
+                    ((1.0 - top / f64::from(height)) as f32).to_string()
+                }
+                _ => value.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Path of the file relative to the model directory, with `/` separators
+fn relative_reference(path: &Path, model_dir: &Path) -> String {
+    path.strip_prefix(model_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod generate_atlas_tests {
+    use super::*;
+
+    use crate::dependencies::index_models;
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_packs_the_textures_it_can_remap() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("atlas")
+            .join("test_run_generate_atlas");
+        let model_dir = dir.join("decals");
+        copy_tree(&Path::new("tests").join("atlas").join("decals"), &model_dir)?;
+        let models = index_models(&dir)?;
+        let textures_dir = model_dir.join("materials").join("textures");
+
+        let atlas = generate_atlas(&model_dir, &models, &RunOptions::default())?;
+
+        assert_eq!(atlas, Some(textures_dir.join("decals_atlas.png")));
+        let (_, metadata) = find_atlas(&model_dir)?.unwrap();
+        // The logo is the taller once padded, so it opens the shelf
+        assert_eq!((metadata.width, metadata.height), (32, 12));
+        let textures: Vec<&str> = metadata
+            .regions
+            .iter()
+            .map(|region| region.texture.as_str())
+            .collect();
+        assert_eq!(
+            textures,
+            vec!["materials/textures/stripe.png", "meshes/logo.png"]
+        );
+        assert_eq!(
+            metadata.regions[0],
+            AtlasRegion {
+                texture: String::from("materials/textures/stripe.png"),
+                x: 14,
+                y: 2,
+                width: 16,
+                height: 4
+            }
+        );
+        assert_eq!(
+            image::open(textures_dir.join("decals_atlas.png"))
+                .unwrap()
+                .to_rgba8()
+                .get_pixel(0, 0)
+                .0,
+            [255, 0, 0, 255]
+        );
+
+        assert!(!textures_dir.join("stripe.png").exists());
+        assert!(!model_dir.join("meshes").join("logo.png").exists());
+        // Tiled, too large, and layered textures are left alone
+        assert!(textures_dir.join("floor.png").exists());
+        assert!(textures_dir.join("grime.png").exists());
+        assert!(textures_dir.join("rust.png").exists());
+
+        let mesh = fs::read_to_string(model_dir.join("meshes").join("decals.dae"))?;
+        let root = XmlElement::parse(&mesh).unwrap();
+        let uvs = read_dae_texture_uvs(&root);
+        assert_eq!(
+            uvs.images["logo-image"],
+            "../materials/textures/decals_atlas.png"
+        );
+        assert_eq!(
+            uvs.images["stripe-image"],
+            "../materials/textures/decals_atlas.png"
+        );
+        assert_eq!(uvs.images["floor-image"], "../materials/textures/floor.png");
+        // The logo is 8px wide from x = 2 in a 32px atlas, 8px tall from y = 2 in 12px
+        let logo_uvs: Vec<f32> = uvs.uv_arrays["logo-uv-array"].values[..4]
+            .iter()
+            .map(|&value| value as f32)
+            .collect();
+        assert_eq!(logo_uvs, [2.0 / 32.0, 2.0 / 12.0, 10.0 / 32.0, 2.0 / 12.0]);
+        assert_eq!(
+            uvs.uv_arrays["body-uv-array"].values,
+            vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]
+        );
+
+        assert!(generate_atlas(&model_dir, &models, &RunOptions::default())?.is_none());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_needs_two_textures_to_pack() -> Result<(), Error> {
+        let model_dir = Path::new("tests").join("impostor").join("crate");
+
+        assert!(generate_atlas(&model_dir, &BTreeMap::new(), &RunOptions::default())?.is_none());
+        assert!(!model_dir
+            .join("materials")
+            .join("textures")
+            .join("crate_atlas.json")
+            .exists());
+
+        Ok(())
+    }
+}
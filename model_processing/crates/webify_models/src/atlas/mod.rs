@@ -0,0 +1,15 @@
+//! Texture atlases: the small textures of each model packed into a single image,
+//! so the viewer binds one texture where it would have bound many, with the UVs of
+//! the meshes remapped to where each texture went
+
+mod atlas_metadata;
+mod find_atlas;
+mod generate_atlas;
+mod pack_rectangles;
+mod read_dae_texture_uvs;
+
+pub use self::atlas_metadata::{AtlasMetadata, AtlasRegion};
+pub use self::find_atlas::{atlas_stem, find_atlas};
+pub use self::generate_atlas::generate_atlas;
+pub use self::pack_rectangles::{pack_rectangles, Placement};
+pub use self::read_dae_texture_uvs::{read_dae_texture_uvs, DaeTextureUvs, UvArray};
@@ -0,0 +1,65 @@
+//! Pack rectangles into a bounded area, shelf by shelf
+
+/// Top left corner of a rectangle placed in the area, `None` when it didn't fit
+pub type Placement = Option<(u32, u32)>;
+
+/// Place each rectangle `(width, height)` in an area at most `max_size` pixels on
+/// each side, filling shelves left to right, tallest rectangles first, so shelves
+/// waste little height. Returns the top left corner of each rectangle, `None` for
+/// the ones that didn't fit, and the size of the area actually used.
+pub fn pack_rectangles(sizes: &[(u32, u32)], max_size: u32) -> (Vec<Placement>, (u32, u32)) {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(sizes[i].1), std::cmp::Reverse(sizes[i].0)));
+
+    let mut placements = vec![None; sizes.len()];
+    let (mut used_width, mut used_height) = (0, 0);
+    // Top of the current shelf, its height, and where its next rectangle goes
+    let (mut shelf_y, mut shelf_height, mut shelf_x) = (0, 0, 0);
+    for i in order {
+        let (width, height) = sizes[i];
+        if width > max_size || height > max_size {
+            continue;
+        }
+        if shelf_x + width > max_size {
+            shelf_y += shelf_height;
+            shelf_height = 0;
+            shelf_x = 0;
+        }
+        if shelf_y + height > max_size {
+            continue;
+        }
+
+        placements[i] = Some((shelf_x, shelf_y));
+        shelf_x += width;
+        shelf_height = shelf_height.max(height);
+        used_width = used_width.max(shelf_x);
+        used_height = used_height.max(shelf_y + height);
+    }
+
+    (placements, (used_width, used_height))
+}
+
+#[cfg(test)]
+mod pack_rectangles_tests {
+    use super::*;
+
+    #[test]
+    fn it_fills_shelves_tallest_first() {
+        let (placements, size) = pack_rectangles(&[(30, 10), (40, 40), (40, 20), (50, 5)], 100);
+
+        // The first shelf is full at 80px, the 30px wide texture starts the next one
+        assert_eq!(
+            placements,
+            vec![Some((0, 40)), Some((0, 0)), Some((40, 0)), Some((30, 40))]
+        );
+        assert_eq!(size, (80, 50));
+    }
+
+    #[test]
+    fn it_leaves_out_what_does_not_fit() {
+        let (placements, size) = pack_rectangles(&[(60, 60), (60, 60), (120, 10)], 100);
+
+        assert_eq!(placements, vec![Some((0, 0)), None, None]);
+        assert_eq!(size, (60, 60));
+    }
+}
@@ -0,0 +1,310 @@
+//! Work out which texture each UV array of a DAE mesh is sampled for
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::xml::XmlElement;
+
+/// Element names of the primitives of a `<mesh>`
+const PRIMITIVES: &[&str] = &[
+    "triangles",
+    "polylist",
+    "polygons",
+    "trifans",
+    "tristrips",
+    "lines",
+    "linestrips",
+];
+
+/// Deepest chain of `<newparam>`s followed from a `<texture>` to its `<image>`
+const MAX_PARAM_DEPTH: usize = 4;
+
+/// The textures of a DAE mesh and the UVs they are sampled with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DaeTextureUvs {
+    /// Reference of each `<image>` by id, as its `<init_from>` holds it
+    pub images: HashMap<String, String>,
+    /// UV arrays by the id of their `<float_array>`
+    pub uv_arrays: HashMap<String, UvArray>,
+    /// Images sampled in ways an atlas can't remap: along with other textures in
+    /// a material, without UVs or with several UV sets
+    pub pinned: HashSet<String>,
+}
+
+/// A `<float_array>` of texture coordinates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UvArray {
+    /// Values of the array, `stride` per vertex starting at `offset`, U then V
+    pub values: Vec<f64>,
+    pub stride: usize,
+    pub offset: usize,
+    /// Image of each primitive using the array, `None` for primitives whose
+    /// material doesn't have a single texture
+    pub images: BTreeSet<Option<String>>,
+}
+
+/// Read which `<image>` the primitives of the DAE mesh sample through which UV
+/// array. Primitives are matched to their material through the `<instance_material>`
+/// bindings of the visual scene, or the material with the symbol as id without one.
+pub fn read_dae_texture_uvs(root: &XmlElement) -> DaeTextureUvs {
+    let mut ids = HashMap::new();
+    index_ids(root, &mut ids);
+
+    let images: HashMap<String, String> = root
+        .descendants_named("image")
+        .into_iter()
+        .filter_map(|image| {
+            let init_from = image.child("init_from")?;
+            let reference = init_from.child("ref").unwrap_or(init_from).text.clone();
+            Some((image.attribute("id")?.to_string(), reference))
+        })
+        .collect();
+    let mut uvs = DaeTextureUvs {
+        images,
+        ..DaeTextureUvs::default()
+    };
+
+    // Symbols bound to different materials in different places can't be told apart
+    let mut bindings: HashMap<&str, Option<&str>> = HashMap::new();
+    for binding in root.descendants_named("instance_material") {
+        if let (Some(symbol), Some(target)) =
+            (binding.attribute("symbol"), binding.attribute("target"))
+        {
+            let target = target.trim_start_matches('#');
+            bindings
+                .entry(symbol)
+                .and_modify(|bound| {
+                    if *bound != Some(target) {
+                        *bound = None;
+                    }
+                })
+                .or_insert(Some(target));
+        }
+    }
+
+    for mesh in root.descendants_named("mesh") {
+        for primitive in mesh
+            .children
+            .iter()
+            .filter(|child| PRIMITIVES.contains(&child.name.as_str()))
+        {
+            let image = primitive.attribute("material").and_then(|symbol| {
+                let material = match bindings.get(symbol) {
+                    Some(bound) => (*bound)?,
+                    None => symbol,
+                };
+                material_image(material, &ids, &mut uvs.pinned)
+            });
+
+            let arrays: Vec<Option<(String, UvArray)>> = texcoord_sources(primitive, &ids)
+                .into_iter()
+                .map(|source| uv_array(source, &ids))
+                .collect();
+            match (arrays.as_slice(), &image) {
+                ([Some((id, array))], _) => {
+                    uvs.uv_arrays
+                        .entry(id.clone())
+                        .or_insert_with(|| array.clone())
+                        .images
+                        .insert(image);
+                }
+                (_, Some(image)) => {
+                    uvs.pinned.insert(image.clone());
+                }
+                _ => (),
+            }
+            // Arrays of other UV sets aren't remapped whatever they're sampled for
+            if arrays.len() > 1 {
+                for (id, array) in arrays.into_iter().flatten() {
+                    uvs.uv_arrays.entry(id).or_insert(array).images.insert(None);
+                }
+            }
+        }
+    }
+
+    uvs
+}
+
+/// Index every element with an `id`
+fn index_ids<'a>(element: &'a XmlElement, ids: &mut HashMap<String, &'a XmlElement>) {
+    if let Some(id) = element.attribute("id") {
+        ids.insert(id.to_string(), element);
+    }
+    for child in &element.children {
+        index_ids(child, ids);
+    }
+}
+
+/// The single image the material's effect samples, `None` when it samples none or
+/// several, the latter being pinned
+fn material_image(
+    material: &str,
+    ids: &HashMap<String, &XmlElement>,
+    pinned: &mut HashSet<String>,
+) -> Option<String> {
+    let effect_url = ids
+        .get(material)?
+        .child("instance_effect")?
+        .attribute("url")?;
+    let effect = ids.get(effect_url.trim_start_matches('#'))?;
+
+    let mut images = BTreeSet::new();
+    let mut unresolved = false;
+    for texture in effect.descendants_named("texture") {
+        match texture
+            .attribute("texture")
+            .and_then(|name| resolve_image(name, effect, ids, 0))
+        {
+            Some(image) => {
+                images.insert(image);
+            }
+            None => unresolved = true,
+        }
+    }
+
+    if images.len() == 1 && !unresolved {
+        images.into_iter().next()
+    } else {
+        pinned.extend(images);
+        None
+    }
+}
+
+/// Image a `<texture>` samples, through the `<sampler2D>` and `<surface>` params of
+/// the effect as COLLADA 1.4 has it, or directly as many exporters write it
+fn resolve_image(
+    name: &str,
+    effect: &XmlElement,
+    ids: &HashMap<String, &XmlElement>,
+    depth: usize,
+) -> Option<String> {
+    if depth > MAX_PARAM_DEPTH {
+        return None;
+    }
+
+    let param = effect
+        .descendants_named("newparam")
+        .into_iter()
+        .find(|param| param.attribute("sid") == Some(name));
+    match param {
+        Some(param) => {
+            if let Some(sampler) = param.child("sampler2D") {
+                if let Some(source) = sampler.child("source") {
+                    return resolve_image(&source.text, effect, ids, depth + 1);
+                }
+                let url = sampler.child("instance_image")?.attribute("url")?;
+                return Some(url.trim_start_matches('#').to_string());
+            }
+            let image = &param.child("surface")?.child("init_from")?.text;
+            ids.get(image.as_str())
+                .filter(|element| element.name == "image")
+                .map(|_| image.clone())
+        }
+        None => ids
+            .get(name)
+            .filter(|element| element.name == "image")
+            .map(|_| name.to_string()),
+    }
+}
+
+/// Sources of the primitive's texture coordinates, its own inputs and the ones of
+/// its `<vertices>`
+fn texcoord_sources<'a>(
+    primitive: &'a XmlElement,
+    ids: &HashMap<String, &'a XmlElement>,
+) -> Vec<&'a str> {
+    let mut sources = Vec::new();
+    for input in primitive.children_named("input") {
+        let source = input.attribute("source").unwrap_or_default();
+        match input.attribute("semantic") {
+            Some("TEXCOORD") => sources.push(source),
+            Some("VERTEX") => {
+                if let Some(vertices) = ids.get(source.trim_start_matches('#')) {
+                    sources.extend(
+                        vertices
+                            .children_named("input")
+                            .filter(|input| input.attribute("semantic") == Some("TEXCOORD"))
+                            .filter_map(|input| input.attribute("source")),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+    sources
+}
+
+/// The `<float_array>` behind a UV `<source>`, with its id
+fn uv_array(source: &str, ids: &HashMap<String, &XmlElement>) -> Option<(String, UvArray)> {
+    let accessor = ids
+        .get(source.trim_start_matches('#'))?
+        .find(&["technique_common", "accessor"])?;
+    let array_id = accessor.attribute("source")?.trim_start_matches('#');
+    let array = ids
+        .get(array_id)
+        .filter(|array| array.name == "float_array")?;
+    let stride: usize = accessor
+        .attribute("stride")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+    if stride < 2 {
+        return None;
+    }
+
+    Some((
+        array_id.to_string(),
+        UvArray {
+            values: array.numbers(),
+            stride,
+            offset: accessor
+                .attribute("offset")
+                .and_then(|offset| offset.parse().ok())
+                .unwrap_or(0),
+            images: BTreeSet::new(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod read_dae_texture_uvs_tests {
+    use super::*;
+
+    use std::{fs, path::Path};
+
+    #[test]
+    fn it_maps_uv_arrays_to_the_image_they_are_sampled_for() {
+        let contents = fs::read_to_string(
+            Path::new("tests")
+                .join("atlas")
+                .join("decals")
+                .join("meshes")
+                .join("decals.dae"),
+        )
+        .unwrap();
+        let root = XmlElement::parse(&contents).unwrap();
+
+        let uvs = read_dae_texture_uvs(&root);
+
+        assert_eq!(uvs.images["logo-image"], "logo.png");
+        assert_eq!(
+            uvs.images["stripe-image"],
+            "../materials/textures/stripe.png"
+        );
+        assert_eq!(
+            uvs.uv_arrays["logo-uv-array"].images,
+            BTreeSet::from([Some(String::from("logo-image"))])
+        );
+        assert_eq!(uvs.uv_arrays["logo-uv-array"].stride, 2);
+        assert_eq!(
+            uvs.uv_arrays["stripe-uv-array"].images,
+            BTreeSet::from([Some(String::from("stripe-image"))])
+        );
+        // Sampled along with the rust by the layered material
+        assert_eq!(
+            uvs.uv_arrays["body-uv-array"].images,
+            BTreeSet::from([None])
+        );
+        assert!(uvs.pinned.contains("grime-image"));
+        assert!(uvs.pinned.contains("rust-image"));
+        assert!(!uvs.pinned.contains("logo-image"));
+    }
+}
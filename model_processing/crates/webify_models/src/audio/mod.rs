@@ -0,0 +1,16 @@
+//! Sounds of the models, like door sounds and ambient loops, moved next to their
+//! model and transcoded to a format every browser plays
+
+mod move_to_sounds_dir;
+mod process;
+mod rename_sound_references;
+mod scan_dir_for_sounds;
+mod transcode_to_opus;
+
+pub use self::move_to_sounds_dir::move_to_sounds_dir;
+pub use self::process::process;
+pub use self::rename_sound_references::rename_sound_references;
+pub use self::scan_dir_for_sounds::{
+    is_sound, scan_dir_for_sounds, AUDIO_EXTENSIONS, WEB_AUDIO_EXTENSIONS,
+};
+pub use self::transcode_to_opus::transcode_to_opus;
@@ -0,0 +1,56 @@
+//! Move stray sounds to the sounds directory of their model
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::model::{locate_file, FileLocation};
+
+/// Move the sound to the `sounds` directory of the model it belongs to, or of the
+/// collection sharing it, unless it's already there. Returns where it ended up.
+pub fn move_to_sounds_dir(sound: &Path, base_path: &Path) -> Result<PathBuf, Error> {
+    let file_name = sound
+        .file_name()
+        .ok_or_else(|| Error::other("Path not provided, no work to do"))?;
+    let sounds_dir = match locate_file(sound, base_path)? {
+        FileLocation::Model(model_dir) => model_dir.join("sounds"),
+        FileLocation::Collection(collection_dir) => collection_dir.join("sounds"),
+    };
+    if sound.parent() == Some(sounds_dir.as_path()) {
+        return Ok(sound.to_path_buf());
+    }
+
+    fs::create_dir_all(&sounds_dir)?;
+    let moved = sounds_dir.join(file_name);
+    fs::rename(sound, &moved)?;
+
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod move_to_sounds_dir_tests {
+    use super::*;
+
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_moves_stray_sounds_next_to_the_model() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("audio")
+            .join("test_run_move_to_sounds_dir");
+        copy_tree(&Path::new("tests").join("audio").join("door"), &dir)?;
+
+        let stray = move_to_sounds_dir(&dir.join("creak.WAV"), &dir);
+        let kept = move_to_sounds_dir(&dir.join("sounds").join("ambient.ogg"), &dir);
+        let moved_exists = dir.join("sounds").join("creak.WAV").is_file();
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(stray?, dir.join("sounds").join("creak.WAV"));
+        assert_eq!(kept?, dir.join("sounds").join("ambient.ogg"));
+        assert!(moved_exists);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Orchestrator to move sounds next to their model and transcode them for the web
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use console::style;
+
+use crate::audio::{
+    move_to_sounds_dir, rename_sound_references, scan_dir_for_sounds, transcode_to_opus,
+    WEB_AUDIO_EXTENSIONS,
+};
+use crate::cli::create_progress_bar;
+use crate::model::{locate_file, model_sdfs, FileLocation};
+use crate::options::{RunOptions, Stage};
+use crate::pipeline::CancellationToken;
+use crate::report::{run_step, ErrorCode, RunReport, Warning};
+use crate::walk::prioritize;
+
+/// Orchestrator to move sounds to the `sounds` directory of their model, transcode
+/// them to Ogg Opus and point the SDFs of their model at them
+pub fn process(
+    dir: &Path,
+    options: &RunOptions,
+    cancellation_token: &CancellationToken,
+    report: &mut RunReport,
+) -> std::result::Result<(), std::io::Error> {
+    let mut sounds = scan_dir_for_sounds(dir)?;
+    if sounds.is_empty() {
+        return Ok(());
+    }
+    prioritize(&mut sounds, dir, &options.first, |sound| sound);
+    let sound_bar = create_progress_bar(sounds.len() as u64);
+    // New file name of each sound that moved or changed format, by its former file
+    // name in lowercase, per model
+    let mut renamed: BTreeMap<PathBuf, BTreeMap<String, String>> = BTreeMap::new();
+
+    sound_bar.set_prefix("Sounds");
+    for sound in sounds {
+        if cancellation_token.is_cancelled() {
+            sound_bar.abandon_with_message("Sound processing cancelled.");
+            return Ok(());
+        }
+
+        sound_bar.inc(1);
+        let styled_path = style(sound.to_string_lossy()).dim().to_string();
+        let moved = if options.stages.is_enabled(Stage::Move) {
+            sound_bar.set_message(&format!("Moving {} to sounds directory...", styled_path));
+            match run_step(
+                "Sound Move",
+                ErrorCode::Audio,
+                &sound,
+                options,
+                report,
+                || move_to_sounds_dir(&sound, dir),
+            )? {
+                Some(moved) => moved,
+                None => continue,
+            }
+        } else {
+            sound.clone()
+        };
+
+        let is_web_audio = moved
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| WEB_AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        let final_sound = if !is_web_audio && options.stages.is_enabled(Stage::Convert) {
+            sound_bar.set_message(&format!("Transcoding {}...", styled_path));
+            match run_step(
+                "Sound Transcode",
+                ErrorCode::Audio,
+                &moved,
+                options,
+                report,
+                || transcode_to_opus(&moved, &options.audio, &options.removal),
+            )? {
+                Some((transcoded, None)) => transcoded,
+                Some((kept, Some(reason))) => {
+                    report.warnings.push(Warning::new(
+                        ErrorCode::AudioFallback,
+                        "Sound Transcode",
+                        kept.clone(),
+                        reason,
+                    ));
+                    kept
+                }
+                None => continue,
+            }
+        } else {
+            moved
+        };
+
+        if final_sound != sound {
+            if let (FileLocation::Model(model_dir), Some(from), Some(to)) = (
+                locate_file(&final_sound, dir)?,
+                sound.file_name(),
+                final_sound.file_name(),
+            ) {
+                renamed.entry(model_dir).or_default().insert(
+                    from.to_string_lossy().to_lowercase(),
+                    to.to_string_lossy().to_string(),
+                );
+            }
+        }
+    }
+    sound_bar.finish_with_message("Sounds webified!");
+
+    if options.stages.is_enabled(Stage::Rewrite) {
+        for (model_dir, renamed) in &renamed {
+            for sdf in model_sdfs(model_dir)? {
+                run_step(
+                    "Sound References",
+                    ErrorCode::Audio,
+                    &sdf,
+                    options,
+                    report,
+                    || rename_sound_references(&sdf, model_dir, renamed),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
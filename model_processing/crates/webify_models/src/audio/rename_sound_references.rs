@@ -0,0 +1,79 @@
+//! Point the sound references of an SDF at the moved and transcoded sounds
+
+use std::{collections::BTreeMap, io::Error, path::Path, result::Result};
+
+use crate::audio::is_sound;
+use crate::xml::edit_file_text_nodes;
+
+/// Rewrite the references of the file, an SDF of the model at `model_dir`, to the
+/// sounds in `renamed`, keyed by their former file name in lowercase, as
+/// references often get the case wrong. `model://` URIs stay URIs, relative
+/// references stay relative to the file. Absolute references are left to the
+/// reference repair. Returns whether the file changed.
+pub fn rename_sound_references(
+    file: &Path,
+    model_dir: &Path,
+    renamed: &BTreeMap<String, String>,
+) -> Result<bool, Error> {
+    if renamed.is_empty() {
+        return Ok(false);
+    }
+
+    // From the directory of the file up to the model
+    let depth = file
+        .parent()
+        .and_then(|dir| dir.strip_prefix(model_dir).ok())
+        .map_or(0, |relative| relative.components().count());
+    let prefix = "../".repeat(depth);
+
+    edit_file_text_nodes(file, |value| {
+        if !is_sound(Path::new(value)) {
+            return None;
+        }
+        let file_name = value.rsplit('/').next()?;
+        let new_name = renamed.get(&file_name.to_lowercase())?;
+
+        if let Some(uri) = value.strip_prefix("model://") {
+            let model = uri.split('/').next()?;
+            Some(format!("model://{}/sounds/{}", model, new_name))
+        } else if value.contains("://") || value.starts_with('/') {
+            None
+        } else {
+            Some(format!("{}sounds/{}", prefix, new_name))
+        }
+    })
+}
+
+#[cfg(test)]
+mod rename_sound_references_tests {
+    use super::*;
+
+    use std::fs;
+
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_points_references_at_the_renamed_sounds() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("audio")
+            .join("test_run_rename_sound_references");
+        copy_tree(&Path::new("tests").join("audio").join("door"), &dir)?;
+        let renamed: BTreeMap<String, String> = vec![
+            (String::from("creak.wav"), String::from("creak.opus")),
+            (String::from("ambient.ogg"), String::from("ambient.ogg")),
+        ]
+        .into_iter()
+        .collect();
+
+        let changed = rename_sound_references(&dir.join("model.sdf"), &dir, &renamed);
+        let sdf = fs::read_to_string(dir.join("model.sdf"));
+        fs::remove_dir_all(&dir)?;
+
+        assert!(changed?);
+        let sdf = sdf?;
+        assert!(sdf.contains("<uri>model://door/sounds/creak.opus</uri>"));
+        assert!(sdf.contains("<uri>sounds/ambient.ogg</uri>"));
+        assert!(sdf.contains("<uri>/home/artist/knock.wav</uri>"));
+        Ok(())
+    }
+}
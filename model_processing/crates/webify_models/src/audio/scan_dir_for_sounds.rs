@@ -0,0 +1,47 @@
+//! Scan all files recursively in the specified path for sounds
+
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::walk::walk_dir;
+
+/// Extensions of the sounds models reference, e.g. door sounds and ambient loops
+pub const AUDIO_EXTENSIONS: [&str; 6] = ["wav", "mp3", "flac", "aiff", "ogg", "opus"];
+
+/// Extensions of the sounds browsers play as they are, which aren't transcoded
+pub const WEB_AUDIO_EXTENSIONS: [&str; 2] = ["ogg", "opus"];
+
+/// Recursively list the sounds in the directory, whatever the case of their extension
+pub fn scan_dir_for_sounds(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    walk_dir(dir, &|path: &Path| is_sound(path))
+}
+
+/// Whether the file has one of the audio extensions
+pub fn is_sound(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod scan_dir_for_sounds_tests {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_sounds() -> Result<(), Error> {
+        let dir = Path::new("tests").join("audio").join("door");
+        let sounds = scan_dir_for_sounds(&dir)?;
+
+        assert_eq!(
+            sounds,
+            vec![
+                dir.join("creak.WAV"),
+                dir.join("sounds").join("ambient.ogg")
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Transcode a sound to Ogg Opus, which every browser plays
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    process::Command,
+    result::Result,
+};
+
+use crate::options::{AudioSettings, Removal};
+use crate::trash::remove_file;
+
+/// Transcode the sound to an Ogg Opus `.opus` file next to it with FFmpeg, then
+/// remove the original as asked. The original is kept when FFmpeg isn't installed
+/// or can't transcode it, in which case the reason is returned along with its
+/// unchanged path.
+pub fn transcode_to_opus(
+    sound: &Path,
+    settings: &AudioSettings,
+    removal: &Removal,
+) -> Result<(PathBuf, Option<String>), Error> {
+    let opus = sound.with_extension("opus");
+    let output = Command::new(&settings.ffmpeg)
+        .arg("-nostdin")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(sound)
+        .args(["-vn", "-c:a", "libopus", "-b:a"])
+        .arg(format!("{}k", settings.bitrate))
+        .arg(&opus)
+        .output();
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Ok((
+                sound.to_path_buf(),
+                Some(format!(
+                    "kept as is, FFmpeg wasn't found at {:?} to transcode it",
+                    settings.ffmpeg
+                )),
+            ))
+        }
+        Err(e) => return Err(e),
+    };
+    if !output.status.success() {
+        if opus.is_file() {
+            fs::remove_file(&opus)?;
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Ok((
+            sound.to_path_buf(),
+            Some(format!(
+                "kept as is, FFmpeg couldn't transcode it: {}",
+                stderr.lines().last().unwrap_or("no error message").trim()
+            )),
+        ));
+    }
+
+    remove_file(sound, removal)?;
+
+    Ok((opus, None))
+}
+
+#[cfg(test)]
+mod transcode_to_opus_tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_sound_without_ffmpeg() -> Result<(), Error> {
+        let sound = Path::new("tests")
+            .join("audio")
+            .join("door")
+            .join("creak.WAV");
+        let settings = AudioSettings {
+            ffmpeg: PathBuf::from("webify-missing-ffmpeg"),
+            ..AudioSettings::default()
+        };
+
+        let (kept, reason) = transcode_to_opus(&sound, &settings, &Removal::Delete)?;
+
+        assert_eq!(kept, sound);
+        assert!(reason.unwrap().contains("FFmpeg wasn't found"));
+        assert!(sound.is_file());
+        assert!(!sound.with_extension("opus").exists());
+        Ok(())
+    }
+}
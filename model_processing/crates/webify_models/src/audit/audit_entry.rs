@@ -0,0 +1,41 @@
+//! Structure of the lines of the audit log
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// One operation a run applied to a file, as recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Run the operation belongs to, the same for every entry of a run
+    pub run: String,
+    /// User the run was started by
+    pub user: String,
+    /// When the operation completed, in seconds since the Unix epoch
+    pub time: u64,
+    /// What was done, e.g. `Texture Processing`
+    pub operation: String,
+    /// File the operation started from, relative to the library
+    pub source: PathBuf,
+    /// BLAKE3 hash of the source before the operation
+    pub source_hash: String,
+    /// File the operation produced, relative to the library
+    pub result: PathBuf,
+    /// BLAKE3 hash of the result
+    pub result_hash: String,
+    /// Signature of the previous entry of the log, chaining the entries together
+    pub previous: String,
+    /// Whether the signature is keyed with `WEBIFY_AUDIT_KEY`, rather than a plain
+    /// hash that only shows the chain is intact
+    pub keyed: bool,
+    /// Signature of every other field of the entry
+    pub signature: String,
+}
+
+impl AuditEntry {
+    /// Whether the entry is about the file, relative to the library, before or
+    /// after the operation
+    pub fn concerns(&self, file: &std::path::Path) -> bool {
+        self.source == file || self.result == file
+    }
+}
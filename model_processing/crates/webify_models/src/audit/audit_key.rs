@@ -0,0 +1,17 @@
+//! Read the key the audit log is signed with
+
+use std::env;
+
+/// Environment variable holding the secret the audit log is signed with
+pub const AUDIT_KEY_VARIABLE: &str = "WEBIFY_AUDIT_KEY";
+
+/// Context of the BLAKE3 key derivation, so the secret isn't used as is
+const KEY_CONTEXT: &str = "webify_models 2024 audit log signature";
+
+/// Key derived from the secret in `WEBIFY_AUDIT_KEY`, or `None` when it isn't set
+pub fn audit_key() -> Option<[u8; 32]> {
+    env::var(AUDIT_KEY_VARIABLE)
+        .ok()
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| blake3::derive_key(KEY_CONTEXT, secret.as_bytes()))
+}
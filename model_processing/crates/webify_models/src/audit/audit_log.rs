@@ -0,0 +1,100 @@
+//! Append the operations of a run to the audit log of the library
+
+use std::{
+    env,
+    fs::OpenOptions,
+    io::{Error, Write},
+    path::{Path, PathBuf},
+    process,
+    result::Result,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::audit::{audit_key, read_audit_log, sign_entry, AuditEntry};
+use crate::hashing::hash_file;
+
+/// Name of the audit log, at the root of the library
+pub const AUDIT_LOG_FILE_NAME: &str = "webify-audit.ndjson";
+
+/// Audit log of a library, one signed entry per line (NDJSON) appended after every
+/// operation, kept across runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditLog {
+    /// Library the logged paths are relative to
+    dir: PathBuf,
+    run: String,
+    user: String,
+    /// Signature of the last entry, which the next one chains to
+    last_signature: String,
+}
+
+impl AuditLog {
+    /// Open the audit log of the library in `dir` for a new run, creating it when
+    /// it's the first
+    pub fn open(dir: &Path) -> Result<AuditLog, Error> {
+        let path = dir.join(AUDIT_LOG_FILE_NAME);
+        let last_signature = match path.is_file() {
+            true => read_audit_log(&path)?
+                .pop()
+                .map(|entry| entry.signature)
+                .unwrap_or_default(),
+            false => String::new(),
+        };
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| String::from("unknown"));
+
+        Ok(AuditLog {
+            dir: dir.to_path_buf(),
+            run: format!("{}-{}", started, process::id()),
+            user,
+            last_signature,
+        })
+    }
+
+    /// Record that `operation` turned `source`, whose contents hashed to
+    /// `source_hash` beforehand, into `result`
+    pub fn record(
+        &mut self,
+        operation: &str,
+        source: &Path,
+        source_hash: String,
+        result: &Path,
+    ) -> Result<(), Error> {
+        let key = audit_key();
+        let mut entry = AuditEntry {
+            run: self.run.clone(),
+            user: self.user.clone(),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            operation: String::from(operation),
+            source: self.relative(source),
+            source_hash,
+            result: self.relative(result),
+            result_hash: hash_file(result)?,
+            previous: self.last_signature.clone(),
+            keyed: key.is_some(),
+            signature: String::new(),
+        };
+        entry.signature = sign_entry(&entry, key.as_ref());
+
+        let mut line = serde_json::to_string(&entry).map_err(Error::other)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(AUDIT_LOG_FILE_NAME))?
+            .write_all(line.as_bytes())?;
+        self.last_signature = entry.signature;
+
+        Ok(())
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.dir).unwrap_or(path).to_path_buf()
+    }
+}
@@ -0,0 +1,48 @@
+//! Look up what the runs did to a file
+
+use std::{
+    env,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::audit::{audit_key, read_audit_log, verify_audit_log, AuditEntry, AUDIT_LOG_FILE_NAME};
+
+/// What the audit log says about a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHistory {
+    /// Audit log the history comes from
+    pub log: PathBuf,
+    /// Entries about the file, oldest first
+    pub entries: Vec<AuditEntry>,
+    /// Line of the log from which the entries can't be trusted, when it was
+    /// tampered with or is signed with another key
+    pub tampered: Option<usize>,
+}
+
+/// History of the file in the audit log of the library it belongs to, the closest
+/// one up from it. The file doesn't need to exist anymore, like an original that
+/// was converted.
+pub fn file_history(file: &Path) -> Result<FileHistory, Error> {
+    let file = env::current_dir()?.join(file);
+    let library = file
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(AUDIT_LOG_FILE_NAME).is_file())
+        .ok_or_else(|| Error::other(format!("No audit log found above {:?}.", file)))?;
+    let log = library.join(AUDIT_LOG_FILE_NAME);
+    let relative = file.strip_prefix(library).unwrap_or(&file);
+
+    let entries = read_audit_log(&log)?;
+    let tampered = verify_audit_log(&entries, audit_key().as_ref()).map(|index| index + 1);
+
+    Ok(FileHistory {
+        entries: entries
+            .into_iter()
+            .filter(|entry| entry.concerns(relative))
+            .collect(),
+        log,
+        tampered,
+    })
+}
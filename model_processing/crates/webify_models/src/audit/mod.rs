@@ -0,0 +1,20 @@
+//! Keep an audit trail of what the runs changed in a library: who ran which
+//! operation when, and the hashes of the files before and after
+
+mod audit_entry;
+mod audit_key;
+mod audit_log;
+mod file_history;
+mod print_history;
+mod read_audit_log;
+mod sign_entry;
+mod verify_audit_log;
+
+pub use self::audit_entry::AuditEntry;
+pub use self::audit_key::{audit_key, AUDIT_KEY_VARIABLE};
+pub use self::audit_log::{AuditLog, AUDIT_LOG_FILE_NAME};
+pub use self::file_history::{file_history, FileHistory};
+pub use self::print_history::print_history;
+pub use self::read_audit_log::read_audit_log;
+pub use self::sign_entry::sign_entry;
+pub use self::verify_audit_log::verify_audit_log;
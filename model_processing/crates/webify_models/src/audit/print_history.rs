@@ -0,0 +1,57 @@
+//! Print what `webify_models history` found
+
+use std::{
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use console::style;
+
+use crate::audit::FileHistory;
+use crate::trash::format_utc_date;
+
+/// Print the operations applied to the file, oldest first, and whether the log
+/// can be trusted
+pub fn print_history(file: &Path, history: &FileHistory) {
+    println!(
+        "\n{} of {} ({})",
+        style("History").bold(),
+        file.to_string_lossy(),
+        style(history.log.to_string_lossy()).dim()
+    );
+    if history.entries.is_empty() {
+        println!("  No operation recorded.");
+    }
+    for entry in &history.entries {
+        println!(
+            "  {} UTC {} by {}, run {}",
+            format_utc_date(UNIX_EPOCH + Duration::from_secs(entry.time)),
+            style(&entry.operation).bold(),
+            entry.user,
+            entry.run
+        );
+        println!(
+            "    {} {}",
+            entry.source.to_string_lossy(),
+            style(&entry.source_hash).dim()
+        );
+        println!(
+            "    -> {} {}",
+            entry.result.to_string_lossy(),
+            style(&entry.result_hash).dim()
+        );
+    }
+
+    match history.tampered {
+        Some(line) => println!(
+            "\n{}",
+            style(format!(
+                "The audit log doesn't check out from line {}: it was edited, or signed with another key.",
+                line
+            ))
+            .red()
+            .bold()
+        ),
+        None => println!("\n{}", style("The audit log checks out.").green()),
+    }
+}
@@ -0,0 +1,24 @@
+//! Read the entries of an audit log
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::audit::AuditEntry;
+
+/// Every entry of the audit log, in the order they were appended
+pub fn read_audit_log(path: &Path) -> Result<Vec<AuditEntry>, Error> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::other(format!(
+                    "Invalid entry on line {} of {:?}: {}",
+                    index + 1,
+                    path,
+                    e
+                ))
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,21 @@
+//! Sign the entries of the audit log
+
+use crate::audit::AuditEntry;
+
+/// Signature of the entry, a BLAKE3 hash of its fields but the signature, keyed
+/// when there is a key. Since each entry holds the signature of the previous one,
+/// changing, removing or reordering entries breaks the signatures that follow.
+pub fn sign_entry(entry: &AuditEntry, key: Option<&[u8; 32]>) -> String {
+    let unsigned = AuditEntry {
+        signature: String::new(),
+        ..entry.clone()
+    };
+    // Serializing a struct of strings, paths and numbers can't fail
+    let bytes = serde_json::to_vec(&unsigned).unwrap();
+    let hash = match key {
+        Some(key) => blake3::keyed_hash(key, &bytes),
+        None => blake3::hash(&bytes),
+    };
+
+    hash.to_hex().to_string()
+}
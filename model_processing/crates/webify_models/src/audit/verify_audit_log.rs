@@ -0,0 +1,103 @@
+//! Check that nobody tampered with the audit log
+
+use crate::audit::{sign_entry, AuditEntry};
+
+/// Index of the first entry whose signature doesn't hold, or that doesn't follow
+/// the entry before it, `None` when the whole log checks out. Keyed entries only
+/// check out with the key they were signed with.
+pub fn verify_audit_log(entries: &[AuditEntry], key: Option<&[u8; 32]>) -> Option<usize> {
+    let mut previous = "";
+    for (index, entry) in entries.iter().enumerate() {
+        let signature = match (entry.keyed, key) {
+            (true, Some(key)) => sign_entry(entry, Some(key)),
+            (true, None) => return Some(index),
+            (false, _) => sign_entry(entry, None),
+        };
+        if entry.previous != previous || entry.signature != signature {
+            return Some(index);
+        }
+        previous = &entry.signature;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod verify_audit_log_tests {
+    use super::*;
+
+    use std::{fs, io::Error, path::Path};
+
+    use crate::audit::{read_audit_log, AuditLog, AUDIT_LOG_FILE_NAME};
+
+    #[test]
+    fn it_catches_edited_entries() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("audit")
+            .join("test_run_verify_audit_log");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("wood.png"), "converted wood")?;
+        fs::write(dir.join("desk.dae"), "rewritten desk")?;
+
+        let mut log = AuditLog::open(&dir)?;
+        log.record(
+            "Texture Processing",
+            &dir.join("wood.jpg"),
+            String::from("0a1b"),
+            &dir.join("wood.png"),
+        )?;
+        // A later run chains to the entries of the previous ones
+        let mut log = AuditLog::open(&dir)?;
+        log.record(
+            "Reference Rewrite",
+            &dir.join("desk.dae"),
+            String::from("2c3d"),
+            &dir.join("desk.dae"),
+        )?;
+
+        let mut entries = read_audit_log(&dir.join(AUDIT_LOG_FILE_NAME))?;
+        fs::remove_dir_all(&dir)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source, Path::new("wood.jpg"));
+        assert_eq!(
+            entries[0].result_hash,
+            blake3::hash(b"converted wood").to_hex().to_string()
+        );
+        assert_eq!(entries[1].previous, entries[0].signature);
+        assert_ne!(entries[0].run, String::new());
+        let key = crate::audit::audit_key();
+        assert_eq!(verify_audit_log(&entries, key.as_ref()), None);
+
+        // Dropping an entry breaks the chain
+        assert_eq!(verify_audit_log(&entries[1..], key.as_ref()), Some(0));
+
+        entries[1].source_hash = String::from("ffff");
+        assert_eq!(verify_audit_log(&entries, key.as_ref()), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_trusts_keyed_entries_with_their_key() {
+        let key = blake3::derive_key("webify_models tests", b"secret");
+        let mut entry = AuditEntry {
+            run: String::from("1709210096-42"),
+            user: String::from("lab"),
+            time: 1_709_210_096,
+            operation: String::from("Texture Processing"),
+            source: "wood.jpg".into(),
+            source_hash: String::from("0a1b"),
+            result: "materials/textures/wood.png".into(),
+            result_hash: String::from("2c3d"),
+            previous: String::new(),
+            keyed: true,
+            signature: String::new(),
+        };
+        entry.signature = sign_entry(&entry, Some(&key));
+        let entries = vec![entry];
+
+        assert_eq!(verify_audit_log(&entries, Some(&key)), None);
+        assert_eq!(verify_audit_log(&entries, None), Some(0));
+        let other = blake3::derive_key("webify_models tests", b"guess");
+        assert_eq!(verify_audit_log(&entries, Some(&other)), Some(0));
+    }
+}
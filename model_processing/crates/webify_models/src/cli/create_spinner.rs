@@ -0,0 +1,12 @@
+//! Spinner for work whose length isn't known up front, like scanning a library
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub fn create_spinner() -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner().template("{prefix:15.green.bold} {spinner} {wide_msg}"),
+    );
+
+    spinner
+}
@@ -1,7 +1,11 @@
 //! Set of functions related to the command-line interface for webify_models
 
 mod create_progress_bar;
+mod create_spinner;
 mod parse_args_for_path;
+mod parse_command;
 
 pub use self::create_progress_bar::create_progress_bar;
+pub use self::create_spinner::create_spinner;
 pub use self::parse_args_for_path::parse_args_for_path;
+pub use self::parse_command::{parse_command, Command};
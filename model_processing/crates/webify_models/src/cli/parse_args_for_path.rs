@@ -1,24 +1,16 @@
 //! Extract the path from the arguments provided, or return errors when there is none
 //! or if a file is provided
 
-use std::{
-    io::{Error, ErrorKind},
-    path::Path,
-    result::Result,
-};
+use std::{io::Error, path::Path, result::Result};
 
-pub fn parse_args_for_path<'a>(args: &'a Vec<String>) -> Result<&'a Path, Error> {
+pub fn parse_args_for_path(args: &[String]) -> Result<&Path, Error> {
     if args.len() <= 1 {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "Path not provided, no work to do.",
-        ));
+        return Err(Error::other("Path not provided, no work to do."));
     }
 
     let path = Path::new(&args[1]);
     if !path.is_dir() {
-        return Err(Error::new(
-            ErrorKind::Other,
+        return Err(Error::other(
             "Path provided is a file, please provide a directory.",
         ));
     }
@@ -32,9 +24,10 @@ mod parse_args_for_path_tests {
 
     #[test]
     fn it_returns_the_correct_arg() {
-        let mut args: Vec<String> = Vec::new();
-        args.push(String::from("foo/bar")); // Test base path
-        args.push(String::from("/")); // Test provided arg path
+        let args: Vec<String> = vec![
+            String::from("foo/bar"), // Test base path
+            String::from("/"),       // Test provided arg path
+        ];
 
         // Return the first argument as a path
         let arg = parse_args_for_path(&args).unwrap();
@@ -43,8 +36,7 @@ mod parse_args_for_path_tests {
 
     #[test]
     fn it_errors_on_no_path() {
-        let mut args: Vec<String> = Vec::new();
-        args.push(String::from("foo/bar")); // Test base path
+        let args: Vec<String> = vec![String::from("foo/bar")]; // Test base path
 
         let arg = parse_args_for_path(&args);
         assert!(arg.is_err());
@@ -52,9 +44,10 @@ mod parse_args_for_path_tests {
 
     #[test]
     fn it_errors_when_path_is_file() {
-        let mut args: Vec<String> = Vec::new();
-        args.push(String::from("foo/bar")); // Test base path
-        args.push(String::from("tests/README.md"));
+        let args: Vec<String> = vec![
+            String::from("foo/bar"), // Test base path
+            String::from("tests/README.md"),
+        ];
 
         let arg = parse_args_for_path(&args);
         assert!(arg.is_err());
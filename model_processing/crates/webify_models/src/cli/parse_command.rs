@@ -0,0 +1,1512 @@
+//! Figure out which subcommand was requested from the arguments provided, falling
+//! back to processing a model directory when no subcommand is given
+
+use std::{cmp::Ordering, io::Error, path::PathBuf, result::Result, time::Duration};
+
+use crate::cli::parse_args_for_path;
+use crate::dependencies::GraphFormat;
+use crate::floorplan::FloorplanSettings;
+use crate::options::{Removal, RunOptions, Stage, StageSelection};
+use crate::rotate::Rotation;
+use crate::schema::SCHEMA_NAMES;
+use crate::similarity::DEFAULT_MAX_DISTANCE;
+
+/// A subcommand requested on the command line
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Webify every model found in the directory
+    Process { path: PathBuf, options: RunOptions },
+    /// Diff two processed model trees
+    Compare {
+        tree_a: PathBuf,
+        tree_b: PathBuf,
+        /// Optional path to write the JSON version of the diff to
+        json: Option<PathBuf>,
+    },
+    /// Webify the bundled fixture library and compare it with the golden outputs
+    SelfTest,
+    /// Print the JSON Schema of one of the files webify_models writes, or all of them
+    Schema { name: Option<String> },
+    /// Print the configuration a run on the directory would use, with the same
+    /// options as a run
+    ShowConfig { path: PathBuf, options: RunOptions },
+    /// Report the formats this build supports and check the environment of a run
+    /// with the same options
+    Doctor { path: PathBuf, options: RunOptions },
+    /// Check that the models included through `model://` URIs are there and processed
+    CheckDependencies { library: PathBuf },
+    /// Export the graph of the models, meshes and textures of the library
+    Graph {
+        library: PathBuf,
+        format: GraphFormat,
+        /// File to write the graph to, printed when missing
+        output: Option<PathBuf>,
+    },
+    /// Cluster the textures of the library that look alike
+    SimilarTextures {
+        library: PathBuf,
+        /// Largest perceptual hash distance, out of 64 bits, of textures clustered
+        max_distance: u32,
+        /// Optional path to write the JSON version of the clusters to
+        json: Option<PathBuf>,
+        /// Largest memory the textures decoded at once may take, in bytes
+        memory_budget: Option<u64>,
+    },
+    /// Rebuild the report of a run from its event log, even one that crashed
+    Summarize {
+        event_log: PathBuf,
+        /// Optional path to write the rebuilt JSON report to
+        json_report: Option<PathBuf>,
+    },
+    /// Show what the runs recorded in the audit log did to a file
+    History { file: PathBuf },
+    /// Chart how the library grew across the runs recorded in its trends log
+    Trends {
+        library: PathBuf,
+        /// Optional path to write the metrics to as JSON series
+        json: Option<PathBuf>,
+    },
+    /// Rename a model directory and every reference to it across the library
+    RenameModel {
+        model_dir: PathBuf,
+        new_name: String,
+        library: PathBuf,
+    },
+    /// Rotate a texture by quarter turns, along with its mip chain and manifest
+    /// entries
+    Rotate {
+        texture: PathBuf,
+        rotation: Rotation,
+    },
+    /// Draw a world seen from above, for the minimap
+    Floorplan {
+        world: PathBuf,
+        library: PathBuf,
+        /// Image to write, next to the world when missing
+        output: Option<PathBuf>,
+        settings: FloorplanSettings,
+    },
+}
+
+pub fn parse_command(args: &[String]) -> Result<Command, Error> {
+    match args.get(1).map(String::as_str) {
+        Some("compare") => parse_compare(&args[2..]),
+        Some("self-test") if args.len() == 2 => Ok(Command::SelfTest),
+        Some("self-test") => Err(Error::other("self-test doesn't take any arguments.")),
+        Some("config") => parse_config(&args[1..]),
+        Some("doctor") => match parse_process(&args[1..], Some("."))? {
+            Command::Process { path, options } => Ok(Command::Doctor { path, options }),
+            command => Ok(command),
+        },
+        Some("schema") => parse_schema(&args[2..]),
+        Some("check-dependencies") => match &args[2..] {
+            [_] => Ok(Command::CheckDependencies {
+                library: parse_args_for_path(&args[1..])?.to_path_buf(),
+            }),
+            _ => Err(Error::other(
+                "check-dependencies requires the library directory: check-dependencies <library>",
+            )),
+        },
+        Some("graph") => parse_graph(&args[1..]),
+        Some("similar-textures") => parse_similar_textures(&args[1..]),
+        Some("history") => match &args[2..] {
+            [file] => Ok(Command::History {
+                file: PathBuf::from(file),
+            }),
+            _ => Err(Error::other("history requires a file: history <file>")),
+        },
+        Some("summarize") => parse_summarize(&args[2..]),
+        Some("trends") => parse_trends(&args[1..]),
+        Some("rename-model") => parse_rename_model(&args[2..]),
+        Some("floorplan") => parse_floorplan(&args[2..]),
+        Some("rotate") => parse_rotate(&args[2..]),
+        // `all` is the same as giving the path directly, it reads better next to --skip/--only
+        Some("all") => parse_process(&args[1..], None),
+        _ => parse_process(args, None),
+    }
+}
+
+/// Parse `config show [path] [options]`, the path defaulting to the current directory
+fn parse_config(args: &[String]) -> Result<Command, Error> {
+    if args.get(1).map(String::as_str) != Some("show") {
+        return Err(Error::other(
+            "config requires a subcommand: config show [path] [options]",
+        ));
+    }
+
+    match parse_process(&args[1..], Some("."))? {
+        Command::Process { path, options } => Ok(Command::ShowConfig { path, options }),
+        command => Ok(command),
+    }
+}
+
+/// Parse `schema [name]`
+fn parse_schema(args: &[String]) -> Result<Command, Error> {
+    match args {
+        [] => Ok(Command::Schema { name: None }),
+        [name] if SCHEMA_NAMES.contains(&name.as_str()) => Ok(Command::Schema {
+            name: Some(name.clone()),
+        }),
+        _ => Err(Error::other(format!(
+            "schema takes one of {} or nothing for all of them.",
+            SCHEMA_NAMES.join(", ")
+        ))),
+    }
+}
+
+/// Parse `graph <library> [--format dot|json] [--output <file>]`. The first
+/// argument is skipped, it's the subcommand name.
+fn parse_graph(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<String> = args.iter().take(1).cloned().collect();
+    let mut format = GraphFormat::default();
+    let mut output = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => format = next_value(&mut iter, arg)?.parse()?,
+            "--output" => output = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    Ok(Command::Graph {
+        library: parse_args_for_path(&positional)?.to_path_buf(),
+        format,
+        output,
+    })
+}
+
+/// Parse `similar-textures <library> [--max-distance <0-32>] [--json <file>]
+/// [--memory-budget-mb <N>]`. The first argument is skipped, it's the subcommand name.
+fn parse_similar_textures(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<String> = args.iter().take(1).cloned().collect();
+    let mut max_distance = DEFAULT_MAX_DISTANCE;
+    let mut json = None;
+    let mut memory_budget = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--max-distance" => {
+                max_distance = u32::from(parse_in_range(next_value(&mut iter, arg)?, arg, 0, 32)?)
+            }
+            "--json" => json = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            "--memory-budget-mb" => {
+                memory_budget = Some(parse_memory_budget(next_value(&mut iter, arg)?, arg)?)
+            }
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    Ok(Command::SimilarTextures {
+        library: parse_args_for_path(&positional)?.to_path_buf(),
+        max_distance,
+        json,
+        memory_budget,
+    })
+}
+
+/// Parse `trends [library] [--json <file>]`, the library defaulting to the current
+/// directory
+fn parse_trends(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<String> = args.iter().take(1).cloned().collect();
+    let mut json = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+    if positional.len() == 1 {
+        positional.push(String::from("."));
+    }
+
+    Ok(Command::Trends {
+        library: parse_args_for_path(&positional)?.to_path_buf(),
+        json,
+    })
+}
+
+/// Parse `rename-model <model_dir> <new_name> [--library <dir>]`, the library
+/// defaulting to the current directory
+fn parse_rename_model(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<&String> = Vec::new();
+    let mut library = PathBuf::from(".");
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--library" => library = PathBuf::from(next_value(&mut iter, arg)?),
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    match positional[..] {
+        [model_dir, new_name] => Ok(Command::RenameModel {
+            model_dir: PathBuf::from(model_dir),
+            new_name: new_name.clone(),
+            library,
+        }),
+        _ => Err(Error::other(
+            "rename-model requires a model directory and its new name: rename-model <model_dir> <new_name> [--library <dir>]",
+        )),
+    }
+}
+
+/// Parse `floorplan <world> [--library <dir>] [--output <file>] [--pixels-per-meter
+/// <1-1000>] [--slice <min>,<max>]`, the library defaulting to the current
+/// directory
+fn parse_floorplan(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<&String> = Vec::new();
+    let mut library = PathBuf::from(".");
+    let mut output = None;
+    let mut settings = FloorplanSettings::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--library" => library = PathBuf::from(next_value(&mut iter, arg)?),
+            "--output" => output = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            "--pixels-per-meter" => {
+                settings.pixels_per_meter =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 1.0, 1000.0)?
+            }
+            "--slice" => {
+                let value = next_value(&mut iter, arg)?;
+                settings.slice = match value.split_once(',') {
+                    Some((min, max)) => [
+                        parse_number(min.trim(), arg)?,
+                        parse_number(max.trim(), arg)?,
+                    ],
+                    None => [f64::NAN; 2],
+                };
+                // NaN from a missing comma compares false too
+                if settings.slice[0].partial_cmp(&settings.slice[1]) != Some(Ordering::Less) {
+                    return Err(Error::other(format!(
+                        "{} expects the lowest then the highest height, like 0,2.5, got {:?}.",
+                        arg, value
+                    )));
+                }
+            }
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    match positional[..] {
+        [world] => Ok(Command::Floorplan {
+            world: PathBuf::from(world),
+            library,
+            output,
+            settings,
+        }),
+        _ => Err(Error::other(
+            "floorplan requires a world file: floorplan <world> [--library <dir>] [--output <file>]",
+        )),
+    }
+}
+
+/// Parse `rotate <texture> --deg <degrees>`
+fn parse_rotate(args: &[String]) -> Result<Command, Error> {
+    let mut positional: Vec<&String> = Vec::new();
+    let mut rotation = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--deg" => {
+                let degrees = parse_number(next_value(&mut iter, arg)?, arg)?;
+                rotation = Some(Rotation::from_degrees(degrees).ok_or_else(|| {
+                    Error::other(format!(
+                        "{} expects a multiple of 90 that isn't a whole turn, like 90, 180 or -90, got {}.",
+                        arg, degrees
+                    ))
+                })?);
+            }
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    match (&positional[..], rotation) {
+        ([texture], Some(rotation)) => Ok(Command::Rotate {
+            texture: PathBuf::from(texture),
+            rotation,
+        }),
+        _ => Err(Error::other(
+            "rotate requires a texture and an angle: rotate <texture> --deg <degrees>",
+        )),
+    }
+}
+
+/// Parse `<path> [options]`, see the README for the list of options. The first
+/// argument is skipped, it's the program or subcommand name.
+fn parse_process(args: &[String], default_path: Option<&str>) -> Result<Command, Error> {
+    let mut positional: Vec<String> = args.iter().take(1).cloned().collect();
+    let mut options = RunOptions::default();
+    let mut skipped: Vec<Stage> = Vec::new();
+    let mut only: Vec<Stage> = Vec::new();
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--error-policy" => options.error_policy = next_value(&mut iter, arg)?.parse()?,
+            "--deny-warnings" => options.deny_warnings = true,
+            "--timings" => options.timings = true,
+            "--shared-textures" => options.shared_textures = true,
+            "--dedup-textures" => options.dedup_textures = true,
+            "--hash-names" => options.hash_names = true,
+            "--url-encode-references" => options.url_encode_references = true,
+            "--sdf-texture-metadata" => options.sdf_texture_metadata = true,
+            "--impostors" => options.impostors = true,
+            "--impostor-views" => {
+                options.impostor.views = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 64)?
+            }
+            "--impostor-size" => {
+                options.impostor.frame_size = match parse_number(next_value(&mut iter, arg)?, arg)?
+                {
+                    size if (16..=1024).contains(&size) => size,
+                    size => {
+                        return Err(Error::other(format!(
+                            "{} goes from 16 to 1024, got {}.",
+                            arg, size
+                        )))
+                    }
+                }
+            }
+            "--atlas" => options.atlases = true,
+            "--pack-orm" => options.pack_orm = true,
+            "--atlas-max-texture" => {
+                options.atlas.max_texture_size =
+                    match parse_number(next_value(&mut iter, arg)?, arg)? {
+                        size if (1..=4096).contains(&size) => size,
+                        size => {
+                            return Err(Error::other(format!(
+                                "{} goes from 1 to 4096, got {}.",
+                                arg, size
+                            )))
+                        }
+                    }
+            }
+            "--atlas-size" => {
+                options.atlas.size = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    size if (64..=16384).contains(&size) => size,
+                    size => {
+                        return Err(Error::other(format!(
+                            "{} goes from 64 to 16384, got {}.",
+                            arg, size
+                        )))
+                    }
+                }
+            }
+            "--navmesh" => options.navmeshes = true,
+            "--navmesh-cell-size" => {
+                options.navmesh.cell_size =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.01, 1.0)?
+            }
+            "--navmesh-agent-height" => {
+                options.navmesh.agent_height =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.1, 10.0)?
+            }
+            "--navmesh-agent-radius" => {
+                options.navmesh.agent_radius =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.0, 5.0)?
+            }
+            "--navmesh-max-climb" => {
+                options.navmesh.max_climb =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.0, 5.0)?
+            }
+            "--navmesh-max-slope" => {
+                options.navmesh.max_slope =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.0, 85.0)?
+            }
+            "--terrain" => options.terrains = true,
+            "--terrain-tile-size" => {
+                options.terrain.tile_size = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    size if (8..=256u32).contains(&size) && size.is_power_of_two() => size,
+                    size => {
+                        return Err(Error::other(format!(
+                            "{} is a power of two from 8 to 256, got {}.",
+                            arg, size
+                        )))
+                    }
+                }
+            }
+            "--terrain-lods" => {
+                options.terrain.lods = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 6)?
+            }
+            "--i-know-what-im-doing" => options.allow_system_paths = true,
+            "--use-trash" => options.removal = Removal::Trash,
+            "--trash-dir" => {
+                options.removal = Removal::TrashDir(PathBuf::from(next_value(&mut iter, arg)?))
+            }
+            "--backup-dir" => {
+                options.removal = Removal::BackupDir {
+                    dir: PathBuf::from(next_value(&mut iter, arg)?),
+                    root: PathBuf::new(),
+                }
+            }
+            "--backup-orig" => options.removal = Removal::Orig,
+            "--extensions" => options.extensions.extend(
+                next_value(&mut iter, arg)?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|extension| !extension.is_empty())
+                    .map(String::from),
+            ),
+            "--format" => options.format = next_value(&mut iter, arg)?.parse()?,
+            "--avif-quality" => {
+                options.avif.quality = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 100)?
+            }
+            "--avif-speed" => {
+                options.avif.speed = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 10)?
+            }
+            "--webp-quality" => {
+                options.webp.quality = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 100)?
+            }
+            "--webp-lossless" => options.webp.lossless = true,
+            "--ktx2-codec" => options.ktx2.codec = next_value(&mut iter, arg)?.parse()?,
+            "--ktx2-quality" => {
+                options.ktx2.quality = parse_in_range(next_value(&mut iter, arg)?, arg, 1, 255)?
+            }
+            "--ktx2-uastc-level" => {
+                options.ktx2.uastc_level = parse_in_range(next_value(&mut iter, arg)?, arg, 0, 4)?
+            }
+            "--ktx2-no-mipmaps" => options.ktx2.mipmaps = false,
+            "--tonemap" => options.hdr.tonemap = next_value(&mut iter, arg)?.parse()?,
+            "--exposure" => {
+                options.hdr.exposure = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    stops if (-16.0..=16.0).contains(&stops) => stops,
+                    stops => {
+                        return Err(Error::other(format!(
+                            "{} goes from -16 to 16, got {}.",
+                            arg, stops
+                        )))
+                    }
+                }
+            }
+            "--oversized-textures" => {
+                options.oversized_textures = next_value(&mut iter, arg)?.parse()?
+            }
+            "--gif" => options.gif = next_value(&mut iter, arg)?.parse()?,
+            "--max-dim" => {
+                options.max_dim = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    0 => return Err(Error::other(format!("{} must be at least 1.", arg))),
+                    max_dim => Some(max_dim),
+                }
+            }
+            "--max-bytes" => {
+                options.max_bytes = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    0 => return Err(Error::other(format!("{} must be at least 1.", arg))),
+                    max_bytes => Some(max_bytes),
+                }
+            }
+            "--resize-filter" => options.resize_filter = next_value(&mut iter, arg)?.parse()?,
+            "--audio-bitrate" => {
+                options.audio.bitrate = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    bitrate if (6..=510).contains(&bitrate) => bitrate,
+                    bitrate => {
+                        return Err(Error::other(format!(
+                            "{} goes from 6 to 510, got {}.",
+                            arg, bitrate
+                        )))
+                    }
+                }
+            }
+            "--ffmpeg" => options.audio.ffmpeg = PathBuf::from(next_value(&mut iter, arg)?),
+            "--power-of-two" => options.power_of_two = Some(next_value(&mut iter, arg)?.parse()?),
+            "--mipmaps" => options.mipmaps = true,
+            "--quantize" => options.quantize = true,
+            "--quantize-colors" => {
+                options.palette.colors = match parse_number(next_value(&mut iter, arg)?, arg)? {
+                    colors if (2..=256).contains(&colors) => colors,
+                    colors => {
+                        return Err(Error::other(format!(
+                            "{} goes from 2 to 256, got {}.",
+                            arg, colors
+                        )))
+                    }
+                }
+            }
+            "--quantize-min-psnr" => {
+                options.palette.min_psnr =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 20.0, 60.0)?
+            }
+            "--strip-metadata" => options.strip_metadata = true,
+            "--watermark" => options.watermark = Some(next_value(&mut iter, arg)?.parse()?),
+            "--watermark-placement" => {
+                options.watermark_settings.placement = next_value(&mut iter, arg)?.parse()?
+            }
+            "--watermark-opacity" => {
+                options.watermark_settings.opacity =
+                    parse_decimal_in_range(next_value(&mut iter, arg)?, arg, 0.0, 1.0)?
+            }
+            "--output" => options.output = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            "--dry-run" => options.dry_run = true,
+            "--first" => options
+                .first
+                .push(PathBuf::from(next_value(&mut iter, arg)?)),
+            "--json-report" => {
+                options.json_report = Some(PathBuf::from(next_value(&mut iter, arg)?))
+            }
+            "--html-report" => {
+                options.html_report = Some(PathBuf::from(next_value(&mut iter, arg)?))
+            }
+            "--audit" => options.audit = true,
+            "--git-authors" => options.git_authors = true,
+            "--file-issues" => options.file_issues = true,
+            "--record-trends" => options.record_trends = true,
+            "--event-log" => options.event_log = Some(PathBuf::from(next_value(&mut iter, arg)?)),
+            "--gpu-budget-mb" => {
+                let megabytes: u64 = parse_number(next_value(&mut iter, arg)?, arg)?;
+                options.gpu_budget = Some(megabytes * 1024 * 1024);
+            }
+            "--memory-budget-mb" => {
+                options.memory_budget = Some(parse_memory_budget(next_value(&mut iter, arg)?, arg)?)
+            }
+            "--max-consecutive-failures" => {
+                options.max_consecutive_failures =
+                    Some(parse_number(next_value(&mut iter, arg)?, arg)?)
+            }
+            "--time-budget" => {
+                options.time_budget = Some(parse_duration(next_value(&mut iter, arg)?, arg)?)
+            }
+            "--skip" => skipped.extend(parse_stages(next_value(&mut iter, arg)?)?),
+            "--only" => only.extend(parse_stages(next_value(&mut iter, arg)?)?),
+            _ if arg.starts_with("--") => {
+                return Err(Error::other(format!("Unknown option {:?}.", arg)))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    options.stages = match (skipped.is_empty(), only.is_empty()) {
+        (true, true) => StageSelection::default(),
+        (false, true) => StageSelection::skipping(&skipped)?,
+        (true, false) => StageSelection::only(&only)?,
+        (false, false) => return Err(Error::other("--skip and --only can't be used together.")),
+    };
+
+    if positional.len() == 1 {
+        positional.extend(default_path.map(String::from));
+    }
+    let path = parse_args_for_path(&positional)?.to_path_buf();
+    Ok(Command::Process { path, options })
+}
+
+/// Parse a comma-separated list of stage names
+fn parse_stages(value: &str) -> Result<Vec<Stage>, Error> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Parse the numeric value of a flag
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::other(format!("{} expects a number, got {:?}.", flag, value)))
+}
+
+/// Parse a duration like `25m`, `90s` or `1h30m`, of hours, minutes and seconds
+fn parse_duration(value: &str, flag: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        Error::other(format!(
+            "{} expects a duration like 25m, 90s or 1h30m, got {:?}.",
+            flag, value
+        ))
+    };
+
+    let mut seconds = 0u64;
+    let mut number = String::new();
+    for c in value.trim().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' if !number.is_empty() => {
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                let amount: u64 = number.parse().map_err(|_| invalid())?;
+                seconds = amount
+                    .checked_mul(unit)
+                    .and_then(|amount| seconds.checked_add(amount))
+                    .ok_or_else(invalid)?;
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || seconds == 0 {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parse a memory budget in MiB into bytes. It has to leave room for at least one
+/// texture, so it can't be zero.
+fn parse_memory_budget(value: &str, flag: &str) -> Result<u64, Error> {
+    match parse_number::<u64>(value, flag)? {
+        0 => Err(Error::other(format!("{} must be at least 1.", flag))),
+        megabytes => Ok(megabytes * 1024 * 1024),
+    }
+}
+
+/// Parse the numeric value of a flag that only accepts values from `min` to `max`
+fn parse_in_range(value: &str, flag: &str, min: u8, max: u8) -> Result<u8, Error> {
+    match parse_number(value, flag)? {
+        number if (min..=max).contains(&number) => Ok(number),
+        number => Err(Error::other(format!(
+            "{} goes from {} to {}, got {}.",
+            flag, min, max, number
+        ))),
+    }
+}
+
+/// Parse the decimal value of a flag that only accepts values from `min` to `max`
+fn parse_decimal_in_range(value: &str, flag: &str, min: f64, max: f64) -> Result<f64, Error> {
+    match parse_number(value, flag)? {
+        number if (min..=max).contains(&number) => Ok(number),
+        number => Err(Error::other(format!(
+            "{} goes from {} to {}, got {}.",
+            flag, min, max, number
+        ))),
+    }
+}
+
+/// Grab the value that follows a flag, erroring when it's missing
+fn next_value<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a str, Error> {
+    iter.next()
+        .map(String::as_str)
+        .ok_or_else(|| Error::other(format!("{} requires a value.", flag)))
+}
+
+/// Parse `compare <tree_a> <tree_b> [--json <file>]`
+fn parse_compare(args: &[String]) -> Result<Command, Error> {
+    let mut trees: Vec<PathBuf> = Vec::new();
+    let mut json = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--json" {
+            json = Some(PathBuf::from(next_value(&mut iter, arg)?));
+        } else {
+            trees.push(PathBuf::from(arg));
+        }
+    }
+
+    if trees.len() != 2 {
+        return Err(Error::other(
+            "compare requires exactly two directories: compare <tree_a> <tree_b>",
+        ));
+    }
+    if let Some(tree) = trees.iter().find(|t| !t.is_dir()) {
+        return Err(Error::other(format!("{:?} is not a directory.", tree)));
+    }
+
+    let tree_b = trees.pop().unwrap();
+    let tree_a = trees.pop().unwrap();
+    Ok(Command::Compare {
+        tree_a,
+        tree_b,
+        json,
+    })
+}
+
+/// Parse `summarize <event-log> [--json-report <file>]`
+fn parse_summarize(args: &[String]) -> Result<Command, Error> {
+    let mut event_logs: Vec<PathBuf> = Vec::new();
+    let mut json_report = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--json-report" {
+            json_report = Some(PathBuf::from(next_value(&mut iter, arg)?));
+        } else {
+            event_logs.push(PathBuf::from(arg));
+        }
+    }
+
+    match event_logs.as_slice() {
+        [event_log] if event_log.is_file() => Ok(Command::Summarize {
+            event_log: event_log.clone(),
+            json_report,
+        }),
+        [event_log] => Err(Error::other(format!("{:?} is not a file.", event_log))),
+        _ => Err(Error::other(
+            "summarize requires the event log of a run: summarize <event-log> [--json-report <file>]",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod parse_command_tests {
+    use super::*;
+
+    use crate::options::{
+        AtlasSettings, AudioSettings, AvifSettings, BasisCodec, ErrorPolicy, GifPolicy,
+        HdrSettings, ImpostorSettings, Ktx2Settings, NavmeshSettings, OutputFormat,
+        OversizedTextures, PaletteSettings, PowerOfTwo, ResizeFilter, TerrainSettings, Tonemap,
+        WatermarkMark, WatermarkPlacement, WatermarkSettings, WebpSettings,
+    };
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn it_defaults_to_processing_a_path() {
+        let command = parse_command(&to_args(&["webify_models", "tests"])).unwrap();
+        assert_eq!(
+            command,
+            Command::Process {
+                path: PathBuf::from("tests"),
+                options: RunOptions::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn it_parses_process_options() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "--error-policy",
+            "permissive",
+            "tests",
+            "--deny-warnings",
+            "--timings",
+            "--gpu-budget-mb",
+            "64",
+            "--memory-budget-mb",
+            "2048",
+            "--shared-textures",
+            "--dedup-textures",
+            "--hash-names",
+            "--url-encode-references",
+            "--sdf-texture-metadata",
+            "--impostors",
+            "--impostor-views",
+            "12",
+            "--impostor-size",
+            "256",
+            "--atlas",
+            "--pack-orm",
+            "--atlas-max-texture",
+            "128",
+            "--atlas-size",
+            "1024",
+            "--navmesh",
+            "--navmesh-cell-size",
+            "0.05",
+            "--navmesh-agent-height",
+            "1.2",
+            "--navmesh-agent-radius",
+            "0.2",
+            "--navmesh-max-climb",
+            "0.1",
+            "--navmesh-max-slope",
+            "30",
+            "--terrain",
+            "--terrain-tile-size",
+            "32",
+            "--terrain-lods",
+            "4",
+            "--output",
+            "webified",
+            "--dry-run",
+            "--json-report",
+            "report.json",
+            "--html-report",
+            "report.html",
+            "--event-log",
+            "events.ndjson",
+            "--audit",
+            "--record-trends",
+            "--git-authors",
+            "--file-issues",
+            "--max-consecutive-failures",
+            "20",
+            "--time-budget",
+            "1h30m",
+            "--first",
+            "lamp",
+            "--first",
+            "desk",
+            "--i-know-what-im-doing",
+            "--trash-dir",
+            "trash",
+            "--extensions",
+            "webp, dds",
+            "--format",
+            "avif",
+            "--avif-quality",
+            "70",
+            "--avif-speed",
+            "4",
+            "--webp-quality",
+            "60",
+            "--webp-lossless",
+            "--ktx2-codec",
+            "uastc",
+            "--ktx2-quality",
+            "200",
+            "--ktx2-uastc-level",
+            "4",
+            "--ktx2-no-mipmaps",
+            "--tonemap",
+            "reinhard",
+            "--exposure",
+            "-2.5",
+            "--oversized-textures",
+            "downscale",
+            "--gif",
+            "sprite-sheet",
+            "--max-dim",
+            "2048",
+            "--max-bytes",
+            "262144",
+            "--resize-filter",
+            "catmull-rom",
+            "--power-of-two",
+            "floor",
+            "--mipmaps",
+            "--quantize",
+            "--quantize-colors",
+            "64",
+            "--quantize-min-psnr",
+            "35",
+            "--strip-metadata",
+            "--watermark",
+            "PREVIEW",
+            "--watermark-placement",
+            "tiled",
+            "--watermark-opacity",
+            "0.3",
+            "--audio-bitrate",
+            "64",
+            "--ffmpeg",
+            "/opt/ffmpeg/bin/ffmpeg",
+        ]))
+        .unwrap();
+
+        let (path, options) = match command {
+            Command::Process { path, options } => (path, options),
+            _ => panic!("Expected a process command, got {:?}", command),
+        };
+        assert_eq!(path, PathBuf::from("tests"));
+        assert_eq!(options.error_policy, ErrorPolicy::Permissive);
+        assert!(options.deny_warnings);
+        assert!(options.timings);
+        assert_eq!(options.gpu_budget, Some(64 * 1024 * 1024));
+        assert_eq!(options.memory_budget, Some(2048 * 1024 * 1024));
+        assert!(options.shared_textures);
+        assert!(options.dedup_textures);
+        assert!(options.hash_names);
+        assert!(options.url_encode_references);
+        assert!(options.sdf_texture_metadata);
+        assert!(options.impostors);
+        assert_eq!(
+            options.impostor,
+            ImpostorSettings {
+                views: 12,
+                frame_size: 256
+            }
+        );
+        assert!(options.atlases);
+        assert!(options.pack_orm);
+        assert_eq!(
+            options.atlas,
+            AtlasSettings {
+                max_texture_size: 128,
+                size: 1024
+            }
+        );
+        assert!(options.navmeshes);
+        assert_eq!(
+            options.navmesh,
+            NavmeshSettings {
+                cell_size: 0.05,
+                agent_height: 1.2,
+                agent_radius: 0.2,
+                max_climb: 0.1,
+                max_slope: 30.0
+            }
+        );
+        assert!(options.terrains);
+        assert_eq!(
+            options.terrain,
+            TerrainSettings {
+                tile_size: 32,
+                lods: 4
+            }
+        );
+        assert_eq!(options.output, Some(PathBuf::from("webified")));
+        assert!(options.dry_run);
+        assert_eq!(options.json_report, Some(PathBuf::from("report.json")));
+        assert_eq!(options.html_report, Some(PathBuf::from("report.html")));
+        assert_eq!(options.event_log, Some(PathBuf::from("events.ndjson")));
+        assert!(options.audit);
+        assert!(options.record_trends);
+        assert!(options.git_authors);
+        assert!(options.file_issues);
+        assert_eq!(
+            options.first,
+            vec![PathBuf::from("lamp"), PathBuf::from("desk")]
+        );
+        assert_eq!(options.max_consecutive_failures, Some(20));
+        assert_eq!(options.time_budget, Some(Duration::from_secs(5400)));
+        assert!(options.allow_system_paths);
+        assert_eq!(options.removal, Removal::TrashDir(PathBuf::from("trash")));
+        assert_eq!(options.extensions, vec!["webp", "dds"]);
+        assert_eq!(options.format, OutputFormat::Avif);
+        assert_eq!(
+            options.avif,
+            AvifSettings {
+                quality: 70,
+                speed: 4
+            }
+        );
+        assert_eq!(
+            options.webp,
+            WebpSettings {
+                quality: 60,
+                lossless: true
+            }
+        );
+        assert_eq!(
+            options.ktx2,
+            Ktx2Settings {
+                codec: BasisCodec::Uastc,
+                quality: 200,
+                uastc_level: 4,
+                mipmaps: false
+            }
+        );
+        assert_eq!(
+            options.hdr,
+            HdrSettings {
+                tonemap: Tonemap::Reinhard,
+                exposure: -2.5
+            }
+        );
+        assert_eq!(options.oversized_textures, OversizedTextures::Downscale);
+        assert_eq!(options.gif, GifPolicy::SpriteSheet);
+        assert_eq!(options.max_dim, Some(2048));
+        assert_eq!(options.max_bytes, Some(262144));
+        assert_eq!(options.resize_filter, ResizeFilter::CatmullRom);
+        assert_eq!(options.power_of_two, Some(PowerOfTwo::Floor));
+        assert!(options.mipmaps);
+        assert!(options.quantize);
+        assert!(options.strip_metadata);
+        assert_eq!(
+            options.watermark,
+            Some(WatermarkMark::Text(String::from("PREVIEW")))
+        );
+        assert_eq!(
+            options.watermark_settings,
+            WatermarkSettings {
+                placement: WatermarkPlacement::Tiled,
+                opacity: 0.3
+            }
+        );
+        assert_eq!(
+            options.palette,
+            PaletteSettings {
+                colors: 64,
+                min_psnr: 35.0
+            }
+        );
+        assert_eq!(
+            options.audio,
+            AudioSettings {
+                bitrate: 64,
+                ffmpeg: PathBuf::from("/opt/ffmpeg/bin/ffmpeg")
+            }
+        );
+    }
+
+    #[test]
+    fn it_parses_backup_modes() {
+        let removal = |flags: &[&str]| {
+            let args = [&["webify_models", "all", "tests"], flags].concat();
+            match parse_command(&to_args(&args)).unwrap() {
+                Command::Process { options, .. } => options.removal,
+                command => panic!("Expected a process command, got {:?}", command),
+            }
+        };
+
+        assert_eq!(
+            removal(&["--backup-dir", "backup"]),
+            Removal::BackupDir {
+                dir: PathBuf::from("backup"),
+                root: PathBuf::new()
+            }
+        );
+        assert_eq!(removal(&["--backup-orig"]), Removal::Orig);
+        assert_eq!(removal(&[]), Removal::Delete);
+    }
+
+    #[test]
+    fn it_parses_stage_selection() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "all",
+            "tests",
+            "--skip",
+            "move",
+            "--skip",
+            "rewrite,downscale",
+        ]))
+        .unwrap();
+
+        let options = match command {
+            Command::Process { options, .. } => options,
+            _ => panic!("Expected a process command, got {:?}", command),
+        };
+        assert_eq!(
+            options.stages,
+            StageSelection::skipping(&[Stage::Move, Stage::Rewrite, Stage::Downscale]).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_rejects_impossible_stage_selections() {
+        let skip_and_only = parse_command(&to_args(&[
+            "webify_models",
+            "all",
+            "tests",
+            "--skip",
+            "move",
+            "--only",
+            "convert",
+        ]));
+        assert!(skip_and_only.is_err());
+
+        let missing_dependency = parse_command(&to_args(&[
+            "webify_models",
+            "all",
+            "tests",
+            "--only",
+            "rewrite",
+        ]));
+        assert!(missing_dependency.is_err());
+
+        let unknown_stage = parse_command(&to_args(&[
+            "webify_models",
+            "all",
+            "tests",
+            "--only",
+            "optimize",
+        ]));
+        assert!(unknown_stage.is_err());
+    }
+
+    #[test]
+    fn it_errors_on_invalid_numbers() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "tests",
+            "--gpu-budget-mb",
+            "lots",
+        ]));
+        assert!(command.is_err());
+
+        let command = parse_command(&to_args(&["webify_models", "tests", "--avif-speed", "11"]));
+        assert!(command.is_err());
+
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "tests",
+            "--navmesh-max-slope",
+            "90",
+        ]));
+        assert!(command.is_err());
+
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "tests",
+            "--terrain-tile-size",
+            "48",
+        ]));
+        assert!(command.is_err());
+
+        for budget in ["25", "0m", "m30", "1d"] {
+            let command = parse_command(&to_args(&[
+                "webify_models",
+                "tests",
+                "--time-budget",
+                budget,
+            ]));
+            assert!(command.is_err(), "{:?} parsed", budget);
+        }
+    }
+
+    #[test]
+    fn it_errors_on_unknown_options() {
+        let command = parse_command(&to_args(&["webify_models", "tests", "--frobnicate"]));
+        assert!(command.is_err());
+    }
+
+    #[test]
+    fn it_parses_compare() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "compare",
+            "tests/compare/tree_a",
+            "tests/compare/tree_b",
+            "--json",
+            "diff.json",
+        ]))
+        .unwrap();
+        assert_eq!(
+            command,
+            Command::Compare {
+                tree_a: PathBuf::from("tests/compare/tree_a"),
+                tree_b: PathBuf::from("tests/compare/tree_b"),
+                json: Some(PathBuf::from("diff.json")),
+            }
+        );
+    }
+
+    #[test]
+    fn it_parses_history() {
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "history", "desk/wood.jpg"])).unwrap(),
+            Command::History {
+                file: PathBuf::from("desk/wood.jpg")
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "history"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_trends() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "trends",
+                "tests",
+                "--json",
+                "trends.json"
+            ]))
+            .unwrap(),
+            Command::Trends {
+                library: PathBuf::from("tests"),
+                json: Some(PathBuf::from("trends.json")),
+            }
+        );
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "trends"])).unwrap(),
+            Command::Trends {
+                library: PathBuf::from("."),
+                json: None,
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "trends", "Cargo.toml"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_rotate() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "rotate",
+                "desk/materials/textures/label.png",
+                "--deg",
+                "-90"
+            ]))
+            .unwrap(),
+            Command::Rotate {
+                texture: PathBuf::from("desk/materials/textures/label.png"),
+                rotation: Rotation::ThreeQuarters,
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "rotate", "label.png"])).is_err());
+        assert!(parse_command(&to_args(&[
+            "webify_models",
+            "rotate",
+            "label.png",
+            "--deg",
+            "45"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn it_parses_summarize() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "summarize",
+            "Cargo.toml",
+            "--json-report",
+            "report.json",
+        ]))
+        .unwrap();
+        assert_eq!(
+            command,
+            Command::Summarize {
+                event_log: PathBuf::from("Cargo.toml"),
+                json_report: Some(PathBuf::from("report.json")),
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "summarize"])).is_err());
+        assert!(
+            parse_command(&to_args(&["webify_models", "summarize", "missing.ndjson"])).is_err()
+        );
+    }
+
+    #[test]
+    fn it_parses_config_show() {
+        let command = parse_command(&to_args(&[
+            "webify_models",
+            "config",
+            "show",
+            "tests",
+            "--timings",
+        ]))
+        .unwrap();
+        match command {
+            Command::ShowConfig { path, options } => {
+                assert_eq!(path, PathBuf::from("tests"));
+                assert!(options.timings);
+            }
+            _ => panic!("Expected a config show command, got {:?}", command),
+        }
+
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "config", "show"])).unwrap(),
+            Command::ShowConfig {
+                path: PathBuf::from("."),
+                options: RunOptions::default()
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "config"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_doctor() {
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "doctor"])).unwrap(),
+            Command::Doctor {
+                path: PathBuf::from("."),
+                options: RunOptions::default()
+            }
+        );
+        match parse_command(&to_args(&[
+            "webify_models",
+            "doctor",
+            "tests",
+            "--ffmpeg",
+            "/opt/ffmpeg/bin/ffmpeg",
+        ]))
+        .unwrap()
+        {
+            Command::Doctor { path, options } => {
+                assert_eq!(path, PathBuf::from("tests"));
+                assert_eq!(
+                    options.audio.ffmpeg,
+                    PathBuf::from("/opt/ffmpeg/bin/ffmpeg")
+                );
+            }
+            command => panic!("Expected a doctor command, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_parses_check_dependencies() {
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "check-dependencies", "tests"])).unwrap(),
+            Command::CheckDependencies {
+                library: PathBuf::from("tests")
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "check-dependencies"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_graph() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "graph",
+                "tests",
+                "--format",
+                "json",
+                "--output",
+                "graph.json",
+            ]))
+            .unwrap(),
+            Command::Graph {
+                library: PathBuf::from("tests"),
+                format: GraphFormat::Json,
+                output: Some(PathBuf::from("graph.json")),
+            }
+        );
+        assert!(parse_command(&to_args(&[
+            "webify_models",
+            "graph",
+            "tests",
+            "--format",
+            "svg"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn it_parses_similar_textures() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "similar-textures",
+                "tests",
+                "--max-distance",
+                "6",
+                "--json",
+                "similar.json",
+                "--memory-budget-mb",
+                "512",
+            ]))
+            .unwrap(),
+            Command::SimilarTextures {
+                library: PathBuf::from("tests"),
+                max_distance: 6,
+                json: Some(PathBuf::from("similar.json")),
+                memory_budget: Some(512 * 1024 * 1024),
+            }
+        );
+        assert!(parse_command(&to_args(&[
+            "webify_models",
+            "similar-textures",
+            "tests",
+            "--memory-budget-mb",
+            "0"
+        ]))
+        .is_err());
+        assert!(parse_command(&to_args(&[
+            "webify_models",
+            "similar-textures",
+            "tests",
+            "--max-distance",
+            "40"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn it_parses_floorplan() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "floorplan",
+                "worlds/apartment.world",
+                "--library",
+                "library",
+                "--output",
+                "minimap.png",
+                "--pixels-per-meter",
+                "50",
+                "--slice",
+                "-0.5, 1.5",
+            ]))
+            .unwrap(),
+            Command::Floorplan {
+                world: PathBuf::from("worlds/apartment.world"),
+                library: PathBuf::from("library"),
+                output: Some(PathBuf::from("minimap.png")),
+                settings: FloorplanSettings {
+                    pixels_per_meter: 50.0,
+                    slice: [-0.5, 1.5]
+                },
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "floorplan"])).is_err());
+        for slice in ["2,1", "1", "low,high"] {
+            assert!(parse_command(&to_args(&[
+                "webify_models",
+                "floorplan",
+                "apartment.world",
+                "--slice",
+                slice
+            ]))
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn it_parses_rename_model() {
+        assert_eq!(
+            parse_command(&to_args(&[
+                "webify_models",
+                "rename-model",
+                "models/desk",
+                "office_desk",
+                "--library",
+                "library",
+            ]))
+            .unwrap(),
+            Command::RenameModel {
+                model_dir: PathBuf::from("models/desk"),
+                new_name: String::from("office_desk"),
+                library: PathBuf::from("library"),
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "rename-model", "desk"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_schema() {
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "schema"])).unwrap(),
+            Command::Schema { name: None }
+        );
+        assert_eq!(
+            parse_command(&to_args(&["webify_models", "schema", "report"])).unwrap(),
+            Command::Schema {
+                name: Some(String::from("report"))
+            }
+        );
+        assert!(parse_command(&to_args(&["webify_models", "schema", "cache"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_self_test() {
+        let command = parse_command(&to_args(&["webify_models", "self-test"])).unwrap();
+        assert_eq!(command, Command::SelfTest);
+
+        let command = parse_command(&to_args(&["webify_models", "self-test", "tests"]));
+        assert!(command.is_err());
+    }
+
+    #[test]
+    fn it_errors_when_compare_is_missing_a_tree() {
+        let command = parse_command(&to_args(&["webify_models", "compare", "tests"]));
+        assert!(command.is_err());
+    }
+}
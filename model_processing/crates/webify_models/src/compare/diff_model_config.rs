@@ -0,0 +1,178 @@
+//! Compare two model.config manifests field by field
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Error, Result},
+};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use serde::Serialize;
+
+/// A single field that differs between two model.config files
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestChange {
+    /// Element path of the field, e.g. `model/version` or `model/sdf@version`
+    pub field: String,
+    /// Value in the first tree, if present
+    pub a: Option<String>,
+    /// Value in the second tree, if present
+    pub b: Option<String>,
+}
+
+/// List the fields that were added, removed or changed between two model.config files
+pub fn diff_model_config(a: &str, b: &str) -> Result<Vec<ManifestChange>> {
+    let fields_a = flatten_model_config(a)?;
+    let fields_b = flatten_model_config(b)?;
+
+    let mut changes = Vec::new();
+    for (field, value_a) in &fields_a {
+        let value_b = fields_b.get(field);
+        if value_b != Some(value_a) {
+            changes.push(ManifestChange {
+                field: field.clone(),
+                a: Some(value_a.clone()),
+                b: value_b.cloned(),
+            });
+        }
+    }
+    for (field, value_b) in &fields_b {
+        if !fields_a.contains_key(field) {
+            changes.push(ManifestChange {
+                field: field.clone(),
+                a: None,
+                b: Some(value_b.clone()),
+            });
+        }
+    }
+    changes.sort_by(|x, y| x.field.cmp(&y.field));
+
+    Ok(changes)
+}
+
+/// Flatten the XML into `path -> value` pairs, where attributes are keyed as
+/// `path@attribute` and repeated siblings get an index suffix
+fn flatten_model_config(contents: &str) -> Result<BTreeMap<String, String>> {
+    let mut reader = Reader::from_str(contents);
+    reader.config_mut().trim_text(true);
+
+    let mut fields = BTreeMap::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut stack: Vec<String> = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let key = record_element(&e, &stack, &mut seen, &mut fields)?;
+                stack.push(key);
+            }
+            Ok(Event::Empty(e)) => {
+                // Empty elements have no matching end event, so they never join the stack
+                record_element(&e, &stack, &mut seen, &mut fields)?;
+            }
+            Ok(Event::End(_)) => {
+                stack.pop();
+            }
+            Ok(Event::Text(t)) => {
+                let text = t.unescape().map_err(|e| Error::other(e.to_string()))?;
+                if let Some(key) = stack.last() {
+                    fields.insert(key.clone(), text.to_string());
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Failed to parse model.config at position {}: {:?}",
+                    reader.error_position(),
+                    e
+                )))
+            }
+            _ => (),
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Work out the unique key of an element and record its attributes
+fn record_element(
+    element: &BytesStart,
+    stack: &[String],
+    seen: &mut HashMap<String, usize>,
+    fields: &mut BTreeMap<String, String>,
+) -> Result<String> {
+    let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+    let candidate = match stack.last() {
+        Some(parent) => format!("{}/{}", parent, name),
+        None => name,
+    };
+    let count = seen.entry(candidate.clone()).or_insert(0);
+    let key = if *count == 0 {
+        candidate
+    } else {
+        format!("{}[{}]", candidate, count)
+    };
+    *count += 1;
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| Error::other(e.to_string()))?;
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| Error::other(e.to_string()))?;
+        fields.insert(
+            format!(
+                "{}@{}",
+                key,
+                String::from_utf8_lossy(attribute.key.as_ref())
+            ),
+            value.to_string(),
+        );
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod diff_model_config_tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_changed_added_and_removed_fields() {
+        let a = r#"<model><name>Box</name><version>1.0</version><sdf version="1.5">model.sdf</sdf></model>"#;
+        let b = r#"<model><name>Box</name><version>1.1</version><sdf version="1.6">model.sdf</sdf><sdf version="1.7">model-1_7.sdf</sdf></model>"#;
+
+        let changes = diff_model_config(a, b).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ManifestChange {
+                    field: String::from("model/sdf@version"),
+                    a: Some(String::from("1.5")),
+                    b: Some(String::from("1.6")),
+                },
+                ManifestChange {
+                    field: String::from("model/sdf[1]"),
+                    a: None,
+                    b: Some(String::from("model-1_7.sdf")),
+                },
+                ManifestChange {
+                    field: String::from("model/sdf[1]@version"),
+                    a: None,
+                    b: Some(String::from("1.7")),
+                },
+                ManifestChange {
+                    field: String::from("model/version"),
+                    a: Some(String::from("1.0")),
+                    b: Some(String::from("1.1")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_errors_on_malformed_xml() {
+        assert!(diff_model_config("<model><name>Box</model>", "<model/>").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Work out the differences between two scanned trees
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::compare::{diff_model_config, ManifestChange, TreeEntry};
+
+/// A file present in both trees whose contents differ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedFile {
+    /// Path relative to the tree roots
+    pub path: PathBuf,
+    pub size_a: u64,
+    pub size_b: u64,
+    pub hash_a: String,
+    pub hash_b: String,
+    /// Field-level differences, only filled in for model.config manifests
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub manifest_changes: Vec<ManifestChange>,
+}
+
+/// Full set of differences between two trees
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeDiff {
+    pub tree_a: PathBuf,
+    pub tree_b: PathBuf,
+    /// Files that only exist in the first tree
+    pub only_in_a: Vec<PathBuf>,
+    /// Files that only exist in the second tree
+    pub only_in_b: Vec<PathBuf>,
+    /// Files that exist in both trees but differ
+    pub changed: Vec<ChangedFile>,
+    /// Number of files that are byte-for-byte identical
+    pub identical: usize,
+}
+
+impl TreeDiff {
+    /// Whether the two trees are identical
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Diff two scanned trees, reading model.config manifests to report field differences
+pub fn diff_trees(
+    tree_a: &Path,
+    entries_a: &BTreeMap<PathBuf, TreeEntry>,
+    tree_b: &Path,
+    entries_b: &BTreeMap<PathBuf, TreeEntry>,
+) -> Result<TreeDiff> {
+    let mut diff = TreeDiff {
+        tree_a: tree_a.to_path_buf(),
+        tree_b: tree_b.to_path_buf(),
+        only_in_a: Vec::new(),
+        only_in_b: Vec::new(),
+        changed: Vec::new(),
+        identical: 0,
+    };
+
+    for (path, entry_a) in entries_a {
+        let entry_b = match entries_b.get(path) {
+            Some(entry) => entry,
+            None => {
+                diff.only_in_a.push(path.clone());
+                continue;
+            }
+        };
+
+        if entry_a == entry_b {
+            diff.identical += 1;
+            continue;
+        }
+
+        let manifest_changes = if path.file_name() == Some("model.config".as_ref()) {
+            diff_model_config(
+                &fs::read_to_string(tree_a.join(path))?,
+                &fs::read_to_string(tree_b.join(path))?,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        diff.changed.push(ChangedFile {
+            path: path.clone(),
+            size_a: entry_a.size,
+            size_b: entry_b.size,
+            hash_a: entry_a.hash.clone(),
+            hash_b: entry_b.hash.clone(),
+            manifest_changes,
+        });
+    }
+
+    diff.only_in_b = entries_b
+        .keys()
+        .filter(|path| !entries_a.contains_key(*path))
+        .cloned()
+        .collect();
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod diff_trees_tests {
+    use super::*;
+
+    use crate::compare::scan_tree;
+
+    #[test]
+    fn it_diffs_the_trees() -> Result<()> {
+        let tree_a = Path::new("tests").join("compare").join("tree_a");
+        let tree_b = Path::new("tests").join("compare").join("tree_b");
+
+        let diff = diff_trees(&tree_a, &scan_tree(&tree_a)?, &tree_b, &scan_tree(&tree_b)?)?;
+
+        assert_eq!(
+            diff.only_in_a,
+            vec![Path::new("sphere").join("model.config")]
+        );
+        assert_eq!(
+            diff.only_in_b,
+            vec![Path::new("cylinder").join("model.config")]
+        );
+        assert_eq!(diff.identical, 0);
+        assert_eq!(diff.changed.len(), 2);
+
+        let texture = &diff.changed[0];
+        assert_eq!(
+            texture.path,
+            Path::new("box")
+                .join("materials")
+                .join("textures")
+                .join("box.png")
+        );
+        assert_eq!((texture.size_a, texture.size_b), (17, 24));
+        assert!(texture.manifest_changes.is_empty());
+
+        let manifest = &diff.changed[1];
+        assert_eq!(
+            manifest.manifest_changes,
+            vec![ManifestChange {
+                field: String::from("model/version"),
+                a: Some(String::from("1.0")),
+                b: Some(String::from("1.1")),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_nothing_between_identical_trees() -> Result<()> {
+        let tree = Path::new("tests").join("compare").join("tree_a");
+        let entries = scan_tree(&tree)?;
+
+        let diff = diff_trees(&tree, &entries, &tree, &entries)?;
+        assert!(diff.is_empty());
+        assert_eq!(diff.identical, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Diff two processed model trees (file presence, sizes, hashes and model.config
+//! differences), useful when validating pipeline upgrades
+
+mod diff_model_config;
+mod diff_trees;
+mod print_diff;
+mod process;
+mod scan_tree;
+
+pub use self::diff_model_config::{diff_model_config, ManifestChange};
+pub use self::diff_trees::{diff_trees, TreeDiff};
+pub use self::print_diff::print_diff;
+pub use self::process::process;
+pub use self::scan_tree::{scan_tree, TreeEntry};
@@ -0,0 +1,46 @@
+//! Human-readable output for a tree diff
+
+use console::style;
+
+use crate::compare::TreeDiff;
+
+/// Print the diff to the console
+pub fn print_diff(diff: &TreeDiff) {
+    println!(
+        "\nComparing {} with {}",
+        style(diff.tree_a.to_string_lossy()).bold(),
+        style(diff.tree_b.to_string_lossy()).bold()
+    );
+
+    for path in &diff.only_in_a {
+        println!("{} {}", style("-").red().bold(), path.to_string_lossy());
+    }
+    for path in &diff.only_in_b {
+        println!("{} {}", style("+").green().bold(), path.to_string_lossy());
+    }
+    for file in &diff.changed {
+        println!(
+            "{} {} ({} -> {} bytes)",
+            style("~").yellow().bold(),
+            file.path.to_string_lossy(),
+            file.size_a,
+            file.size_b
+        );
+        for change in &file.manifest_changes {
+            println!(
+                "    {}: {} -> {}",
+                change.field,
+                style(change.a.as_deref().unwrap_or("(missing)")).dim(),
+                change.b.as_deref().unwrap_or("(missing)")
+            );
+        }
+    }
+
+    println!(
+        "\nOnly in first: {}, only in second: {}, changed: {}, identical: {}",
+        style(diff.only_in_a.len()).bold().red(),
+        style(diff.only_in_b.len()).bold().green(),
+        style(diff.changed.len()).bold().yellow(),
+        style(diff.identical).bold().blue()
+    );
+}
@@ -0,0 +1,33 @@
+//! Orchestrator to compare two processed model trees
+
+use std::{fs, io::Error, path::Path};
+
+use console::style;
+
+use crate::compare::{diff_trees, print_diff, scan_tree, TreeDiff};
+
+/// Orchestrator to compare two processed model trees, printing the result and
+/// optionally writing it out as JSON
+pub fn process(
+    tree_a: &Path,
+    tree_b: &Path,
+    json: Option<&Path>,
+) -> std::result::Result<TreeDiff, std::io::Error> {
+    println!("\nScanning trees to compare...");
+    let entries_a = scan_tree(tree_a)?;
+    let entries_b = scan_tree(tree_b)?;
+
+    let diff = diff_trees(tree_a, &entries_a, tree_b, &entries_b)?;
+    print_diff(&diff);
+
+    if let Some(json_path) = json {
+        let contents = serde_json::to_string_pretty(&diff).map_err(Error::other)?;
+        fs::write(json_path, contents)?;
+        println!(
+            "JSON diff written to {}",
+            style(json_path.to_string_lossy()).dim()
+        );
+    }
+
+    Ok(diff)
+}
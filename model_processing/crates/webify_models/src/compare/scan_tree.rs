@@ -0,0 +1,64 @@
+//! Scan a processed tree and record the size and hash of every file in it
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::hashing::hash_file;
+use crate::walk::walk_dir;
+
+/// Size and content hash of a single file in a tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeEntry {
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Hex-encoded BLAKE3 hash of the file contents
+    pub hash: String,
+}
+
+/// Collect every file under `dir`, keyed by its path relative to `dir`. Files are
+/// hashed in parallel.
+pub fn scan_tree(dir: &Path) -> Result<BTreeMap<PathBuf, TreeEntry>> {
+    walk_dir(dir, &|_: &Path| true)?
+        .par_iter()
+        .map(|path| {
+            let relative_path = path.strip_prefix(dir).unwrap().to_path_buf();
+            let entry = TreeEntry {
+                size: fs::metadata(path)?.len(),
+                hash: hash_file(path)?,
+            };
+            Ok((relative_path, entry))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod scan_tree_tests {
+    use super::*;
+
+    #[test]
+    fn it_records_every_file_relative_to_the_root() {
+        let dir = Path::new("tests").join("compare").join("tree_a");
+        let entries = scan_tree(&dir).unwrap();
+
+        let paths: Vec<&PathBuf> = entries.keys().collect();
+        assert_eq!(
+            paths,
+            vec![
+                &Path::new("box")
+                    .join("materials")
+                    .join("textures")
+                    .join("box.png"),
+                &Path::new("box").join("model.config"),
+                &Path::new("sphere").join("model.config"),
+            ]
+        );
+        assert_eq!(entries[&Path::new("box").join("model.config")].size, 120);
+    }
+}
@@ -0,0 +1,12 @@
+//! Texture settings applied to every model of a category
+
+use serde::Deserialize;
+
+/// Texture settings applied to every model of a category, e.g. props only ever
+/// seen from afar can get away with much smaller textures than hero furniture
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryPreset {
+    /// Longest side, in pixels, a texture of this category is allowed to have
+    pub max_size: u32,
+}
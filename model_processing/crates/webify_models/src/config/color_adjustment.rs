@@ -0,0 +1,80 @@
+//! Color corrections of the textures of a directory
+
+use serde::Deserialize;
+
+use crate::options::WhiteBalance;
+
+/// Color corrections applied to the color textures of a directory while they're
+/// converted, e.g. to fix the scans of a vendor that all come out too dark. The
+/// white balance comes first, then the gamma, then the brightness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorAdjustment {
+    /// Gamma, 0.1 to 10, above 1 lifts the shadows and midtones
+    pub gamma: Option<f32>,
+    /// Added to every channel, -1 to 1 of the full range
+    pub brightness: Option<f32>,
+    /// Light the textures were shot under, whose cast is taken out
+    pub white_balance: Option<WhiteBalance>,
+}
+
+impl ColorAdjustment {
+    /// Whether the values are in range
+    pub fn is_valid(&self) -> bool {
+        self.gamma.is_none_or(|gamma| (0.1..=10.0).contains(&gamma))
+            && self
+                .brightness
+                .is_none_or(|brightness| (-1.0..=1.0).contains(&brightness))
+    }
+
+    /// Whether the adjustment leaves the colors as they are
+    pub fn is_identity(&self) -> bool {
+        self.gamma.is_none_or(|gamma| gamma == 1.0)
+            && self.brightness.is_none_or(|brightness| brightness == 0.0)
+            && self.white_balance.is_none()
+    }
+
+    /// What the adjustment does, e.g. `white balance for tungsten, gamma 1.2,
+    /// brightness +0.1`
+    pub fn describe(&self) -> String {
+        let mut operations = Vec::new();
+        if let Some(white_balance) = self.white_balance {
+            operations.push(format!("white balance for {}", white_balance.name()));
+        }
+        if let Some(gamma) = self.gamma {
+            operations.push(format!("gamma {}", gamma));
+        }
+        if let Some(brightness) = self.brightness {
+            operations.push(format!("brightness {:+}", brightness));
+        }
+
+        operations.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod color_adjustment_tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_its_operations_in_order() {
+        let adjustment = ColorAdjustment {
+            gamma: Some(1.2),
+            brightness: Some(0.1),
+            white_balance: Some(WhiteBalance::Tungsten),
+        };
+
+        assert!(adjustment.is_valid());
+        assert!(!adjustment.is_identity());
+        assert_eq!(
+            adjustment.describe(),
+            "white balance for tungsten, gamma 1.2, brightness +0.1"
+        );
+        assert!(ColorAdjustment::default().is_identity());
+        assert!(!ColorAdjustment {
+            gamma: Some(0.0),
+            ..ColorAdjustment::default()
+        }
+        .is_valid());
+    }
+}
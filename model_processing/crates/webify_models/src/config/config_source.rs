@@ -0,0 +1,27 @@
+//! Where a configuration value comes from
+
+use std::{fmt, path::PathBuf};
+
+/// Where a value of the effective configuration comes from, lowest precedence first
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    /// Built into webify_models
+    Default,
+    /// The user configuration file
+    UserConfig(PathBuf),
+    /// The `webify.toml` at the root of the processed directory
+    WebifyToml(PathBuf),
+    /// A flag given on the command line
+    CommandLine,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::UserConfig(path) => write!(f, "user config {}", path.to_string_lossy()),
+            ConfigSource::WebifyToml(path) => write!(f, "{}", path.to_string_lossy()),
+            ConfigSource::CommandLine => f.write_str("command line"),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Structure that represents one value of the effective configuration
+
+use crate::config::ConfigSource;
+
+/// One value of the effective configuration of a run, and where it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValue {
+    /// Name of the setting, dotted for the entries of tables
+    pub key: String,
+    /// The value, formatted as TOML
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+impl ConfigValue {
+    pub fn new(
+        key: impl Into<String>,
+        value: impl Into<toml::Value>,
+        source: ConfigSource,
+    ) -> Self {
+        ConfigValue {
+            key: key.into(),
+            value: value.into().to_string(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! HDR conversion settings of a single texture
+
+use serde::Deserialize;
+
+use crate::options::{HdrSettings, Tonemap};
+
+/// HDR conversion settings of a texture, overriding the command line ones, e.g. a
+/// darker exposure for an emissive texture in a library of environment maps
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HdrOverride {
+    pub tonemap: Option<Tonemap>,
+    /// Stops, from -16 to 16
+    pub exposure: Option<f32>,
+}
+
+impl HdrOverride {
+    /// The settings with the values this override sets replaced
+    pub fn apply(&self, settings: &HdrSettings) -> HdrSettings {
+        HdrSettings {
+            tonemap: self.tonemap.unwrap_or(settings.tonemap),
+            exposure: self.exposure.unwrap_or(settings.exposure),
+        }
+    }
+}
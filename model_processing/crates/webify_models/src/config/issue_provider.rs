@@ -0,0 +1,21 @@
+//! Forge hosting the issue tracker failing models are reported to
+
+use serde::Deserialize;
+
+/// Forge whose API the issues of failing models are filed through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueProvider {
+    Github,
+    Gitlab,
+}
+
+impl IssueProvider {
+    /// Root of the API of the public instance
+    pub fn default_api_url(self) -> &'static str {
+        match self {
+            IssueProvider::Github => "https://api.github.com",
+            IssueProvider::Gitlab => "https://gitlab.com/api/v4",
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Where the issues of models that fail to process are filed
+
+use serde::Deserialize;
+
+use crate::config::IssueProvider;
+
+/// Issue tracker the failures of each model are filed to with `--file-issues`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IssueTracker {
+    pub provider: IssueProvider,
+    /// Repository the issues are opened in, `owner/name` on GitHub and the path of
+    /// the project, groups included, on GitLab
+    pub repository: String,
+    /// Root of the API, for GitHub Enterprise and self-hosted GitLab instances
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// Labels the issues are opened with
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl IssueTracker {
+    /// Root of the API, without a trailing slash
+    pub fn api_url(&self) -> &str {
+        self.api_url
+            .as_deref()
+            .unwrap_or(self.provider.default_api_url())
+            .trim_end_matches('/')
+    }
+
+    /// Whether the repository has an owner or group and a name
+    pub fn is_valid(&self) -> bool {
+        let mut parts = self.repository.split('/');
+        let valid_parts = parts.clone().all(|part| !part.trim().is_empty());
+        valid_parts
+            && match self.provider {
+                IssueProvider::Github => parts.nth(1).is_some() && parts.next().is_none(),
+                IssueProvider::Gitlab => parts.nth(1).is_some(),
+            }
+    }
+}
@@ -0,0 +1,32 @@
+//! KTX2 encoder settings of a single texture
+
+use serde::Deserialize;
+
+use crate::options::{BasisCodec, Colorspace, Ktx2Settings};
+
+/// KTX2 encoder settings of a texture, overriding the command line ones, e.g.
+/// UASTC for a normal map in a library encoded to ETC1S
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ktx2Override {
+    pub codec: Option<BasisCodec>,
+    /// ETC1S quality, 1 to 255
+    pub quality: Option<u8>,
+    /// UASTC effort, 0 to 4
+    pub uastc_level: Option<u8>,
+    pub mipmaps: Option<bool>,
+    /// Colorspace of the texture, overriding the one of its role
+    pub colorspace: Option<Colorspace>,
+}
+
+impl Ktx2Override {
+    /// The settings with the values this override sets replaced
+    pub fn apply(&self, settings: &Ktx2Settings) -> Ktx2Settings {
+        Ktx2Settings {
+            codec: self.codec.unwrap_or(settings.codec),
+            quality: self.quality.unwrap_or(settings.quality),
+            uastc_level: self.uastc_level.unwrap_or(settings.uastc_level),
+            mipmaps: self.mipmaps.unwrap_or(settings.mipmaps),
+        }
+    }
+}
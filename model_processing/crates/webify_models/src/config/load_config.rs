@@ -0,0 +1,234 @@
+//! Read the configuration from the root of the directory being processed
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::config::WebifyConfig;
+
+/// Name of the configuration file looked up at the root of the processed directory
+pub const CONFIG_FILE_NAME: &str = "webify.toml";
+
+/// Load `webify.toml` from the directory, or the default configuration when there is none
+pub fn load_config(dir: &Path) -> Result<WebifyConfig, Error> {
+    let config_path = dir.join(CONFIG_FILE_NAME);
+    if !config_path.is_file() {
+        return Ok(WebifyConfig::default());
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    let config: WebifyConfig = toml::from_str(&contents)
+        .map_err(|e| Error::other(format!("Invalid {:?}: {}", config_path, e)))?;
+
+    for (model, category) in &config.model_categories {
+        if !config.categories.contains_key(category) {
+            return Err(Error::other(format!(
+                "Invalid {:?}: model {:?} uses unknown category {:?}",
+                config_path, model, category
+            )));
+        }
+    }
+    for (texture, ktx2) in &config.ktx2_textures {
+        if ktx2.quality == Some(0) || ktx2.uastc_level.is_some_and(|level| level > 4) {
+            return Err(Error::other(format!(
+                "Invalid {:?}: texture {:?} has KTX2 settings out of range, quality goes from 1 to 255 and uastc_level from 0 to 4",
+                config_path, texture
+            )));
+        }
+    }
+    for (texture, hdr) in &config.hdr_textures {
+        if hdr
+            .exposure
+            .is_some_and(|exposure| !(-16.0..=16.0).contains(&exposure))
+        {
+            return Err(Error::other(format!(
+                "Invalid {:?}: texture {:?} has an exposure out of range, it goes from -16 to 16",
+                config_path, texture
+            )));
+        }
+    }
+    for (directory, adjustment) in &config.color_adjustments {
+        if !adjustment.is_valid() {
+            return Err(Error::other(format!(
+                "Invalid {:?}: directory {:?} has a color adjustment out of range, gamma goes from 0.1 to 10 and brightness from -1 to 1",
+                config_path, directory
+            )));
+        }
+    }
+
+    for (role, rule) in &config.formats {
+        if !rule.is_valid() {
+            return Err(Error::other(format!(
+                "Invalid {:?}: the {} format has settings out of range, quality goes from 1 to 100 and channels from 1 to 4, for PNG only",
+                config_path,
+                role.name()
+            )));
+        }
+    }
+    for (sensor, rule) in &config.sensor_visuals {
+        if !rule.is_valid() {
+            return Err(Error::other(format!(
+                "Invalid {:?}: sensor {:?} has a visual rule out of range, sizes are positive and meshes named",
+                config_path, sensor
+            )));
+        }
+    }
+
+    if let Some(issues) = config.issues.as_ref().filter(|issues| !issues.is_valid()) {
+        return Err(Error::other(format!(
+            "Invalid {:?}: issue repository {:?} isn't `owner/name`, or `group/project` on GitLab",
+            config_path, issues.repository
+        )));
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod load_config_tests {
+    use super::*;
+
+    use crate::config::{
+        CategoryPreset, ColorAdjustment, HdrOverride, IssueProvider, IssueTracker, Ktx2Override,
+        ModelConfigMark, RoleFormat, SensorVisualRule,
+    };
+    use crate::image_processing::TextureRole;
+    use crate::options::{BasisCodec, Colorspace, OutputFormat, Tonemap, WhiteBalance};
+
+    #[test]
+    fn it_loads_the_config() {
+        let config = load_config(&Path::new("tests").join("config").join("valid")).unwrap();
+
+        assert_eq!(
+            config.categories.get("ceiling_fixture"),
+            Some(&CategoryPreset { max_size: 512 })
+        );
+        assert_eq!(
+            config.model_categories.get("kitchen_table"),
+            Some(&String::from("hero"))
+        );
+        assert_eq!(config.mark_model_config, ModelConfigMark::Version);
+        assert_eq!(config.extensions, vec!["webp"]);
+        assert_eq!(config.material_variants, vec!["snowy"]);
+        assert_eq!(
+            config.ktx2_textures.get("wall_normal"),
+            Some(&Ktx2Override {
+                codec: Some(BasisCodec::Uastc),
+                uastc_level: Some(3),
+                ..Ktx2Override::default()
+            })
+        );
+        assert_eq!(
+            config
+                .ktx2_textures
+                .get("lightmap")
+                .and_then(|ktx2| ktx2.colorspace),
+            Some(Colorspace::Linear)
+        );
+        assert_eq!(
+            config.hdr_textures.get("screen_emissive"),
+            Some(&HdrOverride {
+                tonemap: Some(Tonemap::Reinhard),
+                exposure: Some(-1.5),
+            })
+        );
+        assert_eq!(
+            config.colorspaces.get(&TextureRole::Specular),
+            Some(&Colorspace::Linear)
+        );
+        assert_eq!(
+            config.color_adjustments.get("vendor_scans"),
+            Some(&ColorAdjustment {
+                gamma: Some(1.2),
+                brightness: None,
+                white_balance: Some(WhiteBalance::Tungsten),
+            })
+        );
+        assert_eq!(
+            config.formats.get(&TextureRole::Diffuse),
+            Some(&RoleFormat {
+                format: Some(OutputFormat::Webp),
+                quality: Some(85),
+                ..RoleFormat::default()
+            })
+        );
+        assert_eq!(
+            config.formats.get(&TextureRole::Roughness),
+            Some(&RoleFormat {
+                format: Some(OutputFormat::Png),
+                channels: Some(1),
+                ..RoleFormat::default()
+            })
+        );
+        assert_eq!(
+            config.sensor_visuals.get("ray"),
+            Some(&SensorVisualRule::Strip)
+        );
+        assert_eq!(
+            config.sensor_visuals.get("camera"),
+            Some(&SensorVisualRule::Box([0.05, 0.05, 0.05]))
+        );
+        assert_eq!(
+            config.issues,
+            Some(IssueTracker {
+                provider: IssueProvider::Github,
+                repository: String::from("HabitatZero/models"),
+                api_url: None,
+                labels: vec![String::from("webify")],
+            })
+        );
+    }
+
+    #[test]
+    fn it_defaults_when_there_is_no_config() {
+        let config = load_config(&Path::new("tests").join("compare")).unwrap();
+        assert_eq!(config, WebifyConfig::default());
+    }
+
+    #[test]
+    fn it_errors_on_unknown_categories() {
+        assert!(load_config(&Path::new("tests").join("config").join("unknown_category")).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_out_of_range_ktx2_settings() {
+        let dir = Path::new("tests")
+            .join("config")
+            .join("invalid_ktx2_override");
+        assert!(load_config(&dir).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_out_of_range_exposures() {
+        let dir = Path::new("tests")
+            .join("config")
+            .join("invalid_hdr_override");
+        assert!(load_config(&dir).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_out_of_range_color_adjustments() {
+        let dir = Path::new("tests")
+            .join("config")
+            .join("invalid_color_adjustment");
+        assert!(load_config(&dir).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_out_of_range_formats() {
+        let dir = Path::new("tests").join("config").join("invalid_format");
+        assert!(load_config(&dir).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_out_of_range_sensor_visuals() {
+        let dir = Path::new("tests")
+            .join("config")
+            .join("invalid_sensor_visual");
+        assert!(load_config(&dir).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_issue_repositories_without_an_owner() {
+        let dir = Path::new("tests").join("config").join("invalid_issues");
+        assert!(load_config(&dir).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Read the user configuration, which applies to every directory processed
+
+use std::{
+    env, fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::config::UserConfig;
+
+/// Load the user configuration file, or the default configuration when there is none
+pub fn load_user_config(config_path: &Path) -> Result<UserConfig, Error> {
+    if !config_path.is_file() {
+        return Ok(UserConfig::default());
+    }
+
+    let contents = fs::read_to_string(config_path)?;
+    let mut config: UserConfig = toml::from_str(&contents)
+        .map_err(|e| Error::other(format!("Invalid {:?}: {}", config_path, e)))?;
+
+    let config_dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+    config.allowed_roots = config
+        .allowed_roots
+        .iter()
+        .map(|root| expand_home(root))
+        .map(|root| config_dir.join(root))
+        .collect();
+
+    Ok(config)
+}
+
+/// Where the user configuration lives: `$XDG_CONFIG_HOME/webify_models/config.toml`
+/// (`~/.config` by default) or `%APPDATA%\webify_models\config.toml` on Windows
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    }?;
+
+    Some(config_dir.join("webify_models").join("config.toml"))
+}
+
+/// Replace a leading `~` with the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod load_user_config_tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_roots_relative_to_the_config() {
+        let dir = Path::new("tests").join("config").join("user");
+        let config = load_user_config(&dir.join("config.toml")).unwrap();
+
+        assert_eq!(
+            config.allowed_roots,
+            vec![dir.join("models"), PathBuf::from("/srv/gazebo/models")]
+        );
+    }
+
+    #[test]
+    fn it_defaults_when_there_is_no_config() {
+        let config = load_user_config(&Path::new("tests").join("config").join("none.toml"));
+        assert_eq!(config.unwrap(), UserConfig::default());
+    }
+}
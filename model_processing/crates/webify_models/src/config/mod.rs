@@ -0,0 +1,38 @@
+//! Library-level configuration, read from a `webify.toml` at the root of the
+//! directory being processed, and the user configuration shared by every run
+
+mod category_preset;
+mod color_adjustment;
+mod config_source;
+mod config_value;
+mod hdr_override;
+mod issue_provider;
+mod issue_tracker;
+mod ktx2_override;
+mod load_config;
+mod load_user_config;
+mod model_config_mark;
+mod print_config;
+mod resolve_config;
+mod role_format;
+mod sensor_visual_rule;
+mod user_config;
+mod webify_config;
+
+pub use self::category_preset::CategoryPreset;
+pub use self::color_adjustment::ColorAdjustment;
+pub use self::config_source::ConfigSource;
+pub use self::config_value::ConfigValue;
+pub use self::hdr_override::HdrOverride;
+pub use self::issue_provider::IssueProvider;
+pub use self::issue_tracker::IssueTracker;
+pub use self::ktx2_override::Ktx2Override;
+pub use self::load_config::{load_config, CONFIG_FILE_NAME};
+pub use self::load_user_config::{load_user_config, user_config_path};
+pub use self::model_config_mark::ModelConfigMark;
+pub use self::print_config::print_config;
+pub use self::resolve_config::resolve_config;
+pub use self::role_format::RoleFormat;
+pub use self::sensor_visual_rule::SensorVisualRule;
+pub use self::user_config::UserConfig;
+pub use self::webify_config::WebifyConfig;
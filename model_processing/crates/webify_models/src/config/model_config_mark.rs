@@ -0,0 +1,18 @@
+//! How processed models are marked in their model.config
+
+use serde::Deserialize;
+
+/// How processed models are marked in their model.config, so downstream tooling
+/// can tell processed from raw models
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelConfigMark {
+    /// Add a `<webified version="..."/>` element
+    #[default]
+    Element,
+    /// Bump the model's `<version>` once, and add the `<webified>` element so
+    /// reruns don't bump it again
+    Version,
+    /// Leave model.config untouched
+    None,
+}
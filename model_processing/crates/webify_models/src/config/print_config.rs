@@ -0,0 +1,30 @@
+//! Print the effective configuration of a run
+
+use std::path::Path;
+
+use console::style;
+
+use crate::config::ConfigValue;
+
+/// Print every value of the effective configuration with its source, aligned
+pub fn print_config(dir: &Path, values: &[ConfigValue]) {
+    println!(
+        "\n{} for {}",
+        style("Effective configuration").bold(),
+        dir.to_string_lossy()
+    );
+
+    let settings: Vec<String> = values
+        .iter()
+        .map(|value| format!("{} = {}", value.key, value.value))
+        .collect();
+    let width = settings.iter().map(String::len).max().unwrap_or(0);
+    for (setting, value) in settings.iter().zip(values) {
+        println!(
+            "  {:<width$}  {}",
+            setting,
+            style(format!("# {}", value.source)).dim(),
+            width = width
+        );
+    }
+}
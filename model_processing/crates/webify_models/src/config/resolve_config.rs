@@ -0,0 +1,686 @@
+//! Work out the effective configuration of a run, and where each value comes from
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use toml::Value;
+
+use crate::config::{
+    load_config, load_user_config, ConfigSource, ConfigValue, SensorVisualRule, CONFIG_FILE_NAME,
+};
+use crate::image_processing::TEXTURE_IMAGE_TYPES;
+use crate::manifest::MATERIAL_VARIANTS;
+use crate::options::{Removal, RunOptions, Stage, WatermarkMark};
+
+/// Every setting a run on `dir` would use once the defaults, the user configuration,
+/// `webify.toml` and the command line are merged, each with the source it comes
+/// from. Lists that merge, like `extensions`, get an entry per source.
+pub fn resolve_config(
+    dir: &Path,
+    options: &RunOptions,
+    user_config_path: Option<&Path>,
+) -> Result<Vec<ConfigValue>, Error> {
+    let mut values = Vec::new();
+
+    let config_path = dir.join(CONFIG_FILE_NAME);
+    let config = load_config(dir)?;
+    let keys = if config_path.is_file() {
+        fs::read_to_string(&config_path)?
+            .parse::<toml::Table>()
+            .map_err(|e| Error::other(format!("Invalid {:?}: {}", config_path, e)))?
+    } else {
+        toml::Table::new()
+    };
+    let from_toml = |key: &str| match keys.contains_key(key) {
+        true => ConfigSource::WebifyToml(config_path.clone()),
+        false => ConfigSource::Default,
+    };
+
+    values.push(ConfigValue::new(
+        "mark_model_config",
+        format!("{:?}", config.mark_model_config).to_lowercase(),
+        from_toml("mark_model_config"),
+    ));
+    values.push(ConfigValue::new(
+        "extensions",
+        TEXTURE_IMAGE_TYPES.to_vec(),
+        ConfigSource::Default,
+    ));
+    if !config.extensions.is_empty() {
+        values.push(ConfigValue::new(
+            "extensions",
+            config.extensions.clone(),
+            from_toml("extensions"),
+        ));
+    }
+    if !options.extensions.is_empty() {
+        values.push(ConfigValue::new(
+            "extensions",
+            options.extensions.clone(),
+            ConfigSource::CommandLine,
+        ));
+    }
+    values.push(ConfigValue::new(
+        "material_variants",
+        MATERIAL_VARIANTS.to_vec(),
+        ConfigSource::Default,
+    ));
+    if !config.material_variants.is_empty() {
+        values.push(ConfigValue::new(
+            "material_variants",
+            config.material_variants.clone(),
+            from_toml("material_variants"),
+        ));
+    }
+    for (category, preset) in &config.categories {
+        let mut table = toml::Table::new();
+        table.insert(String::from("max_size"), Value::from(preset.max_size));
+        values.push(ConfigValue::new(
+            format!("categories.{}", category),
+            table,
+            from_toml("categories"),
+        ));
+    }
+    for (model, category) in &config.model_categories {
+        values.push(ConfigValue::new(
+            format!("model_categories.{}", model),
+            category.as_str(),
+            from_toml("model_categories"),
+        ));
+    }
+
+    for (texture, ktx2) in &config.ktx2_textures {
+        let mut table = toml::Table::new();
+        if let Some(codec) = ktx2.codec {
+            table.insert(String::from("codec"), Value::from(codec.name()));
+        }
+        if let Some(quality) = ktx2.quality {
+            table.insert(String::from("quality"), Value::from(quality));
+        }
+        if let Some(uastc_level) = ktx2.uastc_level {
+            table.insert(String::from("uastc_level"), Value::from(uastc_level));
+        }
+        if let Some(mipmaps) = ktx2.mipmaps {
+            table.insert(String::from("mipmaps"), Value::from(mipmaps));
+        }
+        if let Some(colorspace) = ktx2.colorspace {
+            table.insert(String::from("colorspace"), Value::from(colorspace.name()));
+        }
+        values.push(ConfigValue::new(
+            format!("ktx2_textures.{}", texture),
+            table,
+            from_toml("ktx2_textures"),
+        ));
+    }
+    for (texture, hdr) in &config.hdr_textures {
+        let mut table = toml::Table::new();
+        if let Some(tonemap) = hdr.tonemap {
+            table.insert(String::from("tonemap"), Value::from(tonemap.name()));
+        }
+        if let Some(exposure) = hdr.exposure {
+            table.insert(String::from("exposure"), Value::from(f64::from(exposure)));
+        }
+        values.push(ConfigValue::new(
+            format!("hdr_textures.{}", texture),
+            table,
+            from_toml("hdr_textures"),
+        ));
+    }
+    for (role, colorspace) in &config.colorspaces {
+        values.push(ConfigValue::new(
+            format!("colorspaces.{}", role.name()),
+            colorspace.name(),
+            from_toml("colorspaces"),
+        ));
+    }
+    for (role, rule) in &config.formats {
+        let mut table = toml::Table::new();
+        if let Some(format) = rule.format {
+            table.insert(String::from("format"), Value::from(format.extension()));
+        }
+        if let Some(quality) = rule.quality {
+            table.insert(String::from("quality"), Value::from(quality));
+        }
+        if let Some(lossless) = rule.lossless {
+            table.insert(String::from("lossless"), Value::from(lossless));
+        }
+        if let Some(channels) = rule.channels {
+            table.insert(String::from("channels"), Value::from(channels));
+        }
+        values.push(ConfigValue::new(
+            format!("formats.{}", role.name()),
+            table,
+            from_toml("formats"),
+        ));
+    }
+    for (sensor, rule) in &config.sensor_visuals {
+        let value = match rule {
+            SensorVisualRule::Keep => Value::from("keep"),
+            SensorVisualRule::Strip => Value::from("strip"),
+            SensorVisualRule::Box(size) => {
+                let mut table = toml::Table::new();
+                table.insert(String::from("box"), Value::from(size.to_vec()));
+                Value::from(table)
+            }
+            SensorVisualRule::Sphere(radius) => {
+                let mut table = toml::Table::new();
+                table.insert(String::from("sphere"), Value::from(*radius));
+                Value::from(table)
+            }
+            SensorVisualRule::Mesh(uri) => {
+                let mut table = toml::Table::new();
+                table.insert(String::from("mesh"), Value::from(uri.as_str()));
+                Value::from(table)
+            }
+        };
+        values.push(ConfigValue::new(
+            format!("sensor_visuals.{}", sensor),
+            value,
+            from_toml("sensor_visuals"),
+        ));
+    }
+
+    match user_config_path.filter(|path| path.is_file()) {
+        Some(path) => {
+            let user_config = load_user_config(path)?;
+            let roots: Vec<String> = user_config
+                .allowed_roots
+                .iter()
+                .map(|root| root.to_string_lossy().into_owned())
+                .collect();
+            let source = ConfigSource::UserConfig(path.to_path_buf());
+            values.push(ConfigValue::new("allowed_roots", roots, source));
+        }
+        None => values.push(ConfigValue::new(
+            "allowed_roots",
+            Vec::<String>::new(),
+            ConfigSource::Default,
+        )),
+    }
+
+    values.extend(resolve_options(options));
+    Ok(values)
+}
+
+/// The run options, which can only be changed from the command line
+fn resolve_options(options: &RunOptions) -> Vec<ConfigValue> {
+    let defaults = RunOptions::default();
+    let source = |is_default: bool| match is_default {
+        true => ConfigSource::Default,
+        false => ConfigSource::CommandLine,
+    };
+    let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("none"));
+
+    let stages: Vec<&str> = Stage::ALL
+        .iter()
+        .filter(|stage| options.stages.is_enabled(**stage))
+        .map(|stage| stage.name())
+        .collect();
+    let removal = match &options.removal {
+        Removal::Delete => String::from("delete"),
+        Removal::Trash => String::from("trash"),
+        Removal::TrashDir(dir) => format!("trash-dir {}", dir.to_string_lossy()),
+        Removal::BackupDir { dir, .. } => format!("backup-dir {}", dir.to_string_lossy()),
+        Removal::Orig => String::from("backup-orig"),
+    };
+
+    vec![
+        ConfigValue::new(
+            "error_policy",
+            format!("{:?}", options.error_policy).to_lowercase(),
+            source(options.error_policy == defaults.error_policy),
+        ),
+        ConfigValue::new(
+            "deny_warnings",
+            options.deny_warnings,
+            source(options.deny_warnings == defaults.deny_warnings),
+        ),
+        ConfigValue::new(
+            "timings",
+            options.timings,
+            source(options.timings == defaults.timings),
+        ),
+        ConfigValue::new(
+            "gpu_budget_mb",
+            optional(options.gpu_budget.map(|b| (b / 1024 / 1024).to_string())),
+            source(options.gpu_budget == defaults.gpu_budget),
+        ),
+        ConfigValue::new(
+            "memory_budget_mb",
+            optional(options.memory_budget.map(|b| (b / 1024 / 1024).to_string())),
+            source(options.memory_budget == defaults.memory_budget),
+        ),
+        ConfigValue::new("stages", stages, source(options.stages == defaults.stages)),
+        ConfigValue::new(
+            "shared_textures",
+            options.shared_textures,
+            source(options.shared_textures == defaults.shared_textures),
+        ),
+        ConfigValue::new(
+            "dedup_textures",
+            options.dedup_textures,
+            source(options.dedup_textures == defaults.dedup_textures),
+        ),
+        ConfigValue::new(
+            "hash_names",
+            options.hash_names,
+            source(options.hash_names == defaults.hash_names),
+        ),
+        ConfigValue::new(
+            "url_encode_references",
+            options.url_encode_references,
+            source(options.url_encode_references == defaults.url_encode_references),
+        ),
+        ConfigValue::new(
+            "sdf_texture_metadata",
+            options.sdf_texture_metadata,
+            source(options.sdf_texture_metadata == defaults.sdf_texture_metadata),
+        ),
+        ConfigValue::new(
+            "impostors",
+            options.impostors,
+            source(options.impostors == defaults.impostors),
+        ),
+        ConfigValue::new(
+            "impostor_views",
+            i64::from(options.impostor.views),
+            source(options.impostor.views == defaults.impostor.views),
+        ),
+        ConfigValue::new(
+            "impostor_size",
+            i64::from(options.impostor.frame_size),
+            source(options.impostor.frame_size == defaults.impostor.frame_size),
+        ),
+        ConfigValue::new(
+            "atlas",
+            options.atlases,
+            source(options.atlases == defaults.atlases),
+        ),
+        ConfigValue::new(
+            "atlas_max_texture",
+            i64::from(options.atlas.max_texture_size),
+            source(options.atlas.max_texture_size == defaults.atlas.max_texture_size),
+        ),
+        ConfigValue::new(
+            "atlas_size",
+            i64::from(options.atlas.size),
+            source(options.atlas.size == defaults.atlas.size),
+        ),
+        ConfigValue::new(
+            "pack_orm",
+            options.pack_orm,
+            source(options.pack_orm == defaults.pack_orm),
+        ),
+        ConfigValue::new(
+            "navmesh",
+            options.navmeshes,
+            source(options.navmeshes == defaults.navmeshes),
+        ),
+        ConfigValue::new(
+            "navmesh_cell_size",
+            options.navmesh.cell_size,
+            source(options.navmesh.cell_size == defaults.navmesh.cell_size),
+        ),
+        ConfigValue::new(
+            "navmesh_agent_height",
+            options.navmesh.agent_height,
+            source(options.navmesh.agent_height == defaults.navmesh.agent_height),
+        ),
+        ConfigValue::new(
+            "navmesh_agent_radius",
+            options.navmesh.agent_radius,
+            source(options.navmesh.agent_radius == defaults.navmesh.agent_radius),
+        ),
+        ConfigValue::new(
+            "navmesh_max_climb",
+            options.navmesh.max_climb,
+            source(options.navmesh.max_climb == defaults.navmesh.max_climb),
+        ),
+        ConfigValue::new(
+            "navmesh_max_slope",
+            options.navmesh.max_slope,
+            source(options.navmesh.max_slope == defaults.navmesh.max_slope),
+        ),
+        ConfigValue::new(
+            "terrain",
+            options.terrains,
+            source(options.terrains == defaults.terrains),
+        ),
+        ConfigValue::new(
+            "terrain_tile_size",
+            i64::from(options.terrain.tile_size),
+            source(options.terrain.tile_size == defaults.terrain.tile_size),
+        ),
+        ConfigValue::new(
+            "terrain_lods",
+            i64::from(options.terrain.lods),
+            source(options.terrain.lods == defaults.terrain.lods),
+        ),
+        ConfigValue::new(
+            "output",
+            optional(
+                options
+                    .output
+                    .as_ref()
+                    .map(|o| o.to_string_lossy().into_owned()),
+            ),
+            source(options.output == defaults.output),
+        ),
+        ConfigValue::new(
+            "dry_run",
+            options.dry_run,
+            source(options.dry_run == defaults.dry_run),
+        ),
+        ConfigValue::new(
+            "json_report",
+            optional(
+                options
+                    .json_report
+                    .as_ref()
+                    .map(|o| o.to_string_lossy().into_owned()),
+            ),
+            source(options.json_report == defaults.json_report),
+        ),
+        ConfigValue::new(
+            "html_report",
+            optional(
+                options
+                    .html_report
+                    .as_ref()
+                    .map(|o| o.to_string_lossy().into_owned()),
+            ),
+            source(options.html_report == defaults.html_report),
+        ),
+        ConfigValue::new(
+            "event_log",
+            optional(
+                options
+                    .event_log
+                    .as_ref()
+                    .map(|o| o.to_string_lossy().into_owned()),
+            ),
+            source(options.event_log == defaults.event_log),
+        ),
+        ConfigValue::new(
+            "audit",
+            options.audit,
+            source(options.audit == defaults.audit),
+        ),
+        ConfigValue::new(
+            "record_trends",
+            options.record_trends,
+            source(options.record_trends == defaults.record_trends),
+        ),
+        ConfigValue::new(
+            "git_authors",
+            options.git_authors,
+            source(options.git_authors == defaults.git_authors),
+        ),
+        ConfigValue::new(
+            "file_issues",
+            options.file_issues,
+            source(options.file_issues == defaults.file_issues),
+        ),
+        ConfigValue::new(
+            "first",
+            options
+                .first
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>(),
+            source(options.first == defaults.first),
+        ),
+        ConfigValue::new(
+            "allow_system_paths",
+            options.allow_system_paths,
+            source(options.allow_system_paths == defaults.allow_system_paths),
+        ),
+        ConfigValue::new(
+            "max_consecutive_failures",
+            optional(options.max_consecutive_failures.map(|n| n.to_string())),
+            source(options.max_consecutive_failures == defaults.max_consecutive_failures),
+        ),
+        ConfigValue::new(
+            "time_budget",
+            optional(options.time_budget.map(|b| format!("{}s", b.as_secs()))),
+            source(options.time_budget == defaults.time_budget),
+        ),
+        ConfigValue::new(
+            "removal",
+            removal,
+            source(options.removal == defaults.removal),
+        ),
+        ConfigValue::new(
+            "format",
+            options.format.extension(),
+            source(options.format == defaults.format),
+        ),
+        ConfigValue::new(
+            "avif_quality",
+            i64::from(options.avif.quality),
+            source(options.avif.quality == defaults.avif.quality),
+        ),
+        ConfigValue::new(
+            "avif_speed",
+            i64::from(options.avif.speed),
+            source(options.avif.speed == defaults.avif.speed),
+        ),
+        ConfigValue::new(
+            "webp_quality",
+            i64::from(options.webp.quality),
+            source(options.webp.quality == defaults.webp.quality),
+        ),
+        ConfigValue::new(
+            "webp_lossless",
+            options.webp.lossless,
+            source(options.webp.lossless == defaults.webp.lossless),
+        ),
+        ConfigValue::new(
+            "ktx2_codec",
+            options.ktx2.codec.name(),
+            source(options.ktx2.codec == defaults.ktx2.codec),
+        ),
+        ConfigValue::new(
+            "ktx2_quality",
+            i64::from(options.ktx2.quality),
+            source(options.ktx2.quality == defaults.ktx2.quality),
+        ),
+        ConfigValue::new(
+            "ktx2_uastc_level",
+            i64::from(options.ktx2.uastc_level),
+            source(options.ktx2.uastc_level == defaults.ktx2.uastc_level),
+        ),
+        ConfigValue::new(
+            "ktx2_mipmaps",
+            options.ktx2.mipmaps,
+            source(options.ktx2.mipmaps == defaults.ktx2.mipmaps),
+        ),
+        ConfigValue::new(
+            "tonemap",
+            options.hdr.tonemap.name(),
+            source(options.hdr.tonemap == defaults.hdr.tonemap),
+        ),
+        ConfigValue::new(
+            "exposure",
+            f64::from(options.hdr.exposure),
+            source(options.hdr.exposure == defaults.hdr.exposure),
+        ),
+        ConfigValue::new(
+            "oversized_textures",
+            options.oversized_textures.name(),
+            source(options.oversized_textures == defaults.oversized_textures),
+        ),
+        ConfigValue::new(
+            "gif",
+            options.gif.name(),
+            source(options.gif == defaults.gif),
+        ),
+        ConfigValue::new(
+            "max_dim",
+            optional(options.max_dim.map(|max_dim| max_dim.to_string())),
+            source(options.max_dim == defaults.max_dim),
+        ),
+        ConfigValue::new(
+            "max_bytes",
+            optional(options.max_bytes.map(|max_bytes| max_bytes.to_string())),
+            source(options.max_bytes == defaults.max_bytes),
+        ),
+        ConfigValue::new(
+            "resize_filter",
+            options.resize_filter.name(),
+            source(options.resize_filter == defaults.resize_filter),
+        ),
+        ConfigValue::new(
+            "power_of_two",
+            optional(options.power_of_two.map(|mode| mode.name().to_string())),
+            source(options.power_of_two == defaults.power_of_two),
+        ),
+        ConfigValue::new(
+            "mipmaps",
+            options.mipmaps,
+            source(options.mipmaps == defaults.mipmaps),
+        ),
+        ConfigValue::new(
+            "quantize",
+            options.quantize,
+            source(options.quantize == defaults.quantize),
+        ),
+        ConfigValue::new(
+            "quantize_colors",
+            i64::from(options.palette.colors),
+            source(options.palette.colors == defaults.palette.colors),
+        ),
+        ConfigValue::new(
+            "quantize_min_psnr",
+            options.palette.min_psnr,
+            source(options.palette.min_psnr == defaults.palette.min_psnr),
+        ),
+        ConfigValue::new(
+            "strip_metadata",
+            options.strip_metadata,
+            source(options.strip_metadata == defaults.strip_metadata),
+        ),
+        ConfigValue::new(
+            "watermark",
+            optional(options.watermark.as_ref().map(WatermarkMark::describe)),
+            source(options.watermark == defaults.watermark),
+        ),
+        ConfigValue::new(
+            "watermark_placement",
+            options.watermark_settings.placement.name(),
+            source(options.watermark_settings.placement == defaults.watermark_settings.placement),
+        ),
+        ConfigValue::new(
+            "watermark_opacity",
+            options.watermark_settings.opacity,
+            source(options.watermark_settings.opacity == defaults.watermark_settings.opacity),
+        ),
+        ConfigValue::new(
+            "audio_bitrate",
+            i64::from(options.audio.bitrate),
+            source(options.audio.bitrate == defaults.audio.bitrate),
+        ),
+        ConfigValue::new(
+            "ffmpeg",
+            options.audio.ffmpeg.to_string_lossy().to_string(),
+            source(options.audio.ffmpeg == defaults.audio.ffmpeg),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod resolve_config_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::options::OutputFormat;
+
+    fn find<'a>(values: &'a [ConfigValue], key: &str) -> Vec<&'a ConfigValue> {
+        values.iter().filter(|value| value.key == key).collect()
+    }
+
+    #[test]
+    fn it_records_where_each_value_comes_from() -> Result<(), Error> {
+        let dir = Path::new("tests").join("config").join("valid");
+        let user_config = Path::new("tests")
+            .join("config")
+            .join("user")
+            .join("config.toml");
+        let options = RunOptions {
+            extensions: vec![String::from("dds")],
+            format: OutputFormat::Avif,
+            ..RunOptions::default()
+        };
+        let values = resolve_config(&dir, &options, Some(&user_config))?;
+
+        let webify_toml = ConfigSource::WebifyToml(dir.join(CONFIG_FILE_NAME));
+        assert_eq!(
+            find(&values, "mark_model_config"),
+            vec![&ConfigValue::new(
+                "mark_model_config",
+                "version",
+                webify_toml.clone()
+            )]
+        );
+        let extensions = find(&values, "extensions");
+        assert_eq!(extensions.len(), 3);
+        assert_eq!(extensions[0].source, ConfigSource::Default);
+        assert_eq!(extensions[1].value, r#"["webp"]"#);
+        assert_eq!(extensions[1].source, webify_toml);
+        assert_eq!(extensions[2].value, r#"["dds"]"#);
+        assert_eq!(extensions[2].source, ConfigSource::CommandLine);
+        let variants = find(&values, "material_variants");
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1].value, r#"["snowy"]"#);
+        assert_eq!(
+            find(&values, "categories.ceiling_fixture")[0].value,
+            "{ max_size = 512 }"
+        );
+        assert_eq!(
+            find(&values, "ktx2_textures.wall_normal")[0].value,
+            r#"{ codec = "uastc", uastc_level = 3 }"#
+        );
+        assert_eq!(
+            find(&values, "colorspaces.specular")[0].value,
+            r#""linear""#
+        );
+        assert_eq!(
+            find(&values, "formats.roughness")[0].value,
+            r#"{ channels = 1, format = "png" }"#
+        );
+        assert_eq!(
+            find(&values, "allowed_roots")[0].source,
+            ConfigSource::UserConfig(user_config)
+        );
+        assert_eq!(
+            find(&values, "format"),
+            vec![&ConfigValue::new(
+                "format",
+                "avif",
+                ConfigSource::CommandLine
+            )]
+        );
+        assert_eq!(
+            find(&values, "error_policy"),
+            vec![&ConfigValue::new(
+                "error_policy",
+                "strict",
+                ConfigSource::Default
+            )]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_falls_back_to_the_defaults() -> Result<(), Error> {
+        let dir = Path::new("tests").join("compare");
+        let missing_user_config = PathBuf::from("tests").join("config").join("missing.toml");
+        let values = resolve_config(&dir, &RunOptions::default(), Some(&missing_user_config))?;
+
+        assert!(values.iter().all(|v| v.source == ConfigSource::Default));
+        assert_eq!(find(&values, "mark_model_config")[0].value, r#""element""#);
+
+        Ok(())
+    }
+}
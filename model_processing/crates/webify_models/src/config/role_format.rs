@@ -0,0 +1,105 @@
+//! Output format of the textures of a role
+
+use serde::Deserialize;
+
+use crate::options::{AvifSettings, OutputFormat, WebpSettings};
+
+/// Output format of the textures of a role, overriding `--format`, e.g. lossy WebP
+/// for diffuse textures and lossless PNG for normal maps
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoleFormat {
+    pub format: Option<OutputFormat>,
+    /// WebP or AVIF quality, 1 to 100
+    pub quality: Option<u8>,
+    /// Whether WebP keeps the pixels exactly
+    pub lossless: Option<bool>,
+    /// Channels PNG textures are written with, 1 to 4: grayscale, grayscale and
+    /// alpha, RGB or RGBA
+    pub channels: Option<u8>,
+}
+
+impl RoleFormat {
+    /// Whether the values are in range, and channels only set for PNG textures
+    pub fn is_valid(&self) -> bool {
+        self.quality
+            .is_none_or(|quality| (1..=100).contains(&quality))
+            && self.channels.is_none_or(|channels| {
+                (1..=4).contains(&channels) && self.format == Some(OutputFormat::Png)
+            })
+    }
+
+    /// The format and encoder settings with the values this rule sets replaced
+    pub fn apply(
+        &self,
+        format: OutputFormat,
+        avif: &AvifSettings,
+        webp: &WebpSettings,
+    ) -> (OutputFormat, AvifSettings, WebpSettings) {
+        (
+            self.format.unwrap_or(format),
+            AvifSettings {
+                quality: self.quality.unwrap_or(avif.quality),
+                ..*avif
+            },
+            WebpSettings {
+                quality: self.quality.unwrap_or(webp.quality),
+                lossless: self.lossless.unwrap_or(webp.lossless),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod role_format_tests {
+    use super::*;
+
+    #[test]
+    fn it_replaces_the_values_it_sets() {
+        let rule = RoleFormat {
+            format: Some(OutputFormat::Webp),
+            quality: Some(85),
+            ..RoleFormat::default()
+        };
+        let (format, avif, webp) = rule.apply(
+            OutputFormat::Png,
+            &AvifSettings::default(),
+            &WebpSettings::default(),
+        );
+        assert_eq!(format, OutputFormat::Webp);
+        assert_eq!(avif.quality, 85);
+        assert_eq!(avif.speed, AvifSettings::default().speed);
+        assert_eq!(
+            webp,
+            WebpSettings {
+                quality: 85,
+                lossless: false,
+            }
+        );
+    }
+
+    #[test]
+    fn it_only_sets_channels_of_png_textures() {
+        let png = RoleFormat {
+            format: Some(OutputFormat::Png),
+            channels: Some(1),
+            ..RoleFormat::default()
+        };
+        assert!(png.is_valid());
+        assert!(!RoleFormat {
+            channels: Some(5),
+            ..png.clone()
+        }
+        .is_valid());
+        assert!(!RoleFormat {
+            format: Some(OutputFormat::Webp),
+            ..png
+        }
+        .is_valid());
+        assert!(!RoleFormat {
+            quality: Some(0),
+            ..RoleFormat::default()
+        }
+        .is_valid());
+    }
+}
@@ -0,0 +1,52 @@
+//! What becomes of the visuals of a sensor
+
+use serde::Deserialize;
+
+/// What becomes of the visuals of the links holding a sensor of some type, whose
+/// lidar heads and camera housings clutter the web scene
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum SensorVisualRule {
+    /// Leave the visuals as they are, e.g. to exempt a type from the `"*"` rule
+    Keep,
+    /// Remove the visuals
+    Strip,
+    /// Replace the geometry of the visuals with a box of this size, in meters
+    Box([f64; 3]),
+    /// Replace the geometry of the visuals with a sphere of this radius, in meters
+    Sphere(f64),
+    /// Replace the geometry of the visuals with this mesh, e.g. an icon
+    Mesh(String),
+}
+
+impl SensorVisualRule {
+    /// The geometry the rule puts in place of the visuals' own, `None` for the
+    /// rules that don't
+    pub fn geometry(&self) -> Option<String> {
+        match self {
+            SensorVisualRule::Keep | SensorVisualRule::Strip => None,
+            SensorVisualRule::Box([x, y, z]) => Some(format!(
+                "<geometry><box><size>{} {} {}</size></box></geometry>",
+                x, y, z
+            )),
+            SensorVisualRule::Sphere(radius) => Some(format!(
+                "<geometry><sphere><radius>{}</radius></sphere></geometry>",
+                radius
+            )),
+            SensorVisualRule::Mesh(uri) => Some(format!(
+                "<geometry><mesh><uri>{}</uri></mesh></geometry>",
+                quick_xml::escape::escape(uri.as_str())
+            )),
+        }
+    }
+
+    /// Whether the sizes of the rule are positive and its mesh is named
+    pub fn is_valid(&self) -> bool {
+        match self {
+            SensorVisualRule::Keep | SensorVisualRule::Strip => true,
+            SensorVisualRule::Box(size) => size.iter().all(|&side| side > 0.0),
+            SensorVisualRule::Sphere(radius) => *radius > 0.0,
+            SensorVisualRule::Mesh(uri) => !uri.trim().is_empty(),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Structure that represents the user's own configuration, shared by every run
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Contents of the user configuration file
+///
+/// ```toml
+/// allowed_roots = ["~/gazebo/models", "/srv/gazebo/models"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    /// Library roots destructive runs are limited to. Relative roots are relative
+    /// to the configuration file, and an empty list allows every directory.
+    pub allowed_roots: Vec<PathBuf>,
+}
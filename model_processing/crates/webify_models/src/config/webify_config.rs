@@ -0,0 +1,88 @@
+//! Structure that represents the contents of a `webify.toml`
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::config::{
+    CategoryPreset, ColorAdjustment, HdrOverride, IssueTracker, Ktx2Override, ModelConfigMark,
+    RoleFormat, SensorVisualRule,
+};
+use crate::image_processing::TextureRole;
+use crate::options::Colorspace;
+
+/// Contents of a `webify.toml`
+///
+/// ```toml
+/// mark_model_config = "version"
+/// extensions = ["ktx", "basis"]
+/// material_variants = ["snowy"]
+///
+/// [categories]
+/// ceiling_fixture = { max_size = 512 }
+/// hero = { max_size = 4096 }
+///
+/// [model_categories]
+/// kitchen_table = "hero"
+///
+/// [ktx2_textures]
+/// wall_normal = { codec = "uastc", uastc_level = 3 }
+/// lightmap = { colorspace = "linear" }
+///
+/// [hdr_textures]
+/// screen_emissive = { tonemap = "reinhard", exposure = -1.5 }
+///
+/// [colorspaces]
+/// specular = "linear"
+///
+/// [color_adjustments]
+/// vendor_scans = { gamma = 1.2, brightness = 0.05, white_balance = "tungsten" }
+///
+/// [formats]
+/// diffuse = { format = "webp", quality = 85 }
+/// normal = { format = "png" }
+/// roughness = { format = "png", channels = 1 }
+///
+/// [sensor_visuals]
+/// ray = "strip"
+/// camera = { box = [0.05, 0.05, 0.05] }
+///
+/// [issues]
+/// provider = "github"
+/// repository = "HabitatZero/models"
+/// labels = ["webify"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebifyConfig {
+    /// Presets keyed by category name
+    pub categories: BTreeMap<String, CategoryPreset>,
+    /// Category of a model, keyed by model directory name. Takes precedence
+    /// over the tags found in the model's model.config.
+    pub model_categories: BTreeMap<String, String>,
+    /// How processed models are marked in their model.config
+    pub mark_model_config: ModelConfigMark,
+    /// Extensions scanned as textures on top of the defaults
+    pub extensions: Vec<String>,
+    /// Words naming the texture sets of material variants on top of the defaults
+    pub material_variants: Vec<String>,
+    /// KTX2 encoder settings of single textures, keyed by file name without
+    /// the extension, with `--format ktx2`
+    pub ktx2_textures: BTreeMap<String, Ktx2Override>,
+    /// Tonemapping settings of single EXR and HDR textures, keyed by file name
+    /// without the extension
+    pub hdr_textures: BTreeMap<String, HdrOverride>,
+    /// Colorspace of the textures of a role, overriding the sRGB of colors and the
+    /// linear of data
+    pub colorspaces: BTreeMap<TextureRole, Colorspace>,
+    /// Output format of the textures of a role, overriding `--format`
+    pub formats: BTreeMap<TextureRole, RoleFormat>,
+    /// Color corrections of the color textures under a directory, keyed by
+    /// directory name, a model's or any above it, the closest one applying
+    pub color_adjustments: BTreeMap<String, ColorAdjustment>,
+    /// What becomes of the visuals of the links holding a sensor, keyed by sensor
+    /// type, `"*"` for the types not listed
+    pub sensor_visuals: BTreeMap<String, SensorVisualRule>,
+    /// Issue tracker the failures of each model are filed to with `--file-issues`
+    pub issues: Option<IssueTracker>,
+}
@@ -0,0 +1,227 @@
+//! The models of a library with the meshes and textures they use
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{BufReader, Error},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::dependencies::{index_models, owning_model, resolve_reference, DependencyGraph};
+use crate::image_processing::TEXTURE_IMAGE_TYPES;
+use crate::mesh_update::{find_references, scan_dir_for_reference_files};
+use crate::walk::walk_dir;
+
+/// Kind of file a model uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    /// A mesh or a material
+    Mesh,
+    Texture,
+}
+
+/// A model of the library
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ModelNode {
+    /// Model directory, relative to the library
+    pub path: PathBuf,
+    /// Total size of the files under the model directory
+    pub bytes: u64,
+    /// Models it includes through `model://` URIs
+    pub includes: Vec<PathBuf>,
+    /// Meshes and textures its SDFs reference directly
+    pub uses: Vec<PathBuf>,
+}
+
+/// A mesh or texture referenced by a model or a mesh
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct AssetNode {
+    /// File, relative to the library
+    pub path: PathBuf,
+    pub kind: AssetKind,
+    pub bytes: u64,
+    /// Textures it references, for meshes and materials
+    pub uses: Vec<PathBuf>,
+    /// Models using it, directly or through one of their meshes. Assets used by
+    /// more than one model are shared.
+    pub used_by: Vec<PathBuf>,
+}
+
+/// The models of a library, the models they include and the meshes and textures
+/// they use, for curators to see what's shared, what's heavy and what's circular
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct AssetGraph {
+    pub models: Vec<ModelNode>,
+    pub assets: Vec<AssetNode>,
+    /// Models including themselves through other models
+    pub cycles: Vec<Vec<PathBuf>>,
+}
+
+impl AssetGraph {
+    /// Build the graph of the library from the references of its SDFs, meshes and
+    /// materials. References to files that don't exist are left out.
+    pub fn build(library: &Path) -> Result<AssetGraph, Error> {
+        let relative = |path: &Path| path.strip_prefix(library).unwrap_or(path).to_path_buf();
+        let models = index_models(library)?;
+        let dependencies = DependencyGraph::build(library)?;
+
+        // Files each reference file points at, and who owns the reference file
+        let mut references: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for file in scan_dir_for_reference_files(library)? {
+            let found = find_references(&file, BufReader::new(File::open(&file)?))
+                .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", file, e)))?;
+            let resolved: BTreeSet<PathBuf> = found
+                .iter()
+                .filter_map(|reference| resolve_reference(&file, reference, &models))
+                .filter(|target| target != &file)
+                .collect();
+            references.insert(file, resolved);
+        }
+
+        let mut model_nodes = Vec::new();
+        let mut used_by: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for model_dir in models.values().collect::<BTreeSet<_>>() {
+            let files = walk_dir(model_dir, &|_: &Path| true)?;
+            let mut bytes = 0;
+            for file in &files {
+                bytes += fs::metadata(file)?.len();
+            }
+
+            let mut uses = BTreeSet::new();
+            for (file, targets) in &references {
+                if owning_model(file, library).as_ref() != Some(model_dir) {
+                    continue;
+                }
+                for target in targets {
+                    used_by
+                        .entry(target.clone())
+                        .or_default()
+                        .insert(relative(model_dir));
+                    if !is_mesh(file) {
+                        uses.insert(relative(target));
+                    }
+                }
+            }
+
+            model_nodes.push(ModelNode {
+                path: relative(model_dir),
+                bytes,
+                includes: dependencies
+                    .dependencies(model_dir)
+                    .map(|m| relative(m))
+                    .collect(),
+                uses: uses.into_iter().collect(),
+            });
+        }
+
+        // Models using a mesh use its textures too
+        for (file, targets) in &references {
+            if !is_mesh(file) {
+                continue;
+            }
+            let users = used_by.get(file).cloned().unwrap_or_default();
+            for target in targets {
+                used_by
+                    .entry(target.clone())
+                    .or_default()
+                    .extend(users.clone());
+            }
+        }
+
+        let mut assets = Vec::new();
+        for (asset, users) in &used_by {
+            let kind = match is_texture(asset) {
+                true => AssetKind::Texture,
+                false => AssetKind::Mesh,
+            };
+            let uses = match references.get(asset) {
+                Some(targets) if is_mesh(asset) => targets.iter().map(|t| relative(t)).collect(),
+                _ => Vec::new(),
+            };
+            assets.push(AssetNode {
+                path: relative(asset),
+                kind,
+                bytes: fs::metadata(asset)?.len(),
+                uses,
+                used_by: users.iter().cloned().collect(),
+            });
+        }
+
+        let cycles = dependencies
+            .cycles()
+            .iter()
+            .map(|cycle| cycle.iter().map(|m| relative(m)).collect())
+            .collect();
+
+        Ok(AssetGraph {
+            models: model_nodes,
+            assets,
+            cycles,
+        })
+    }
+}
+
+/// Meshes and materials reference textures, SDFs and worlds reference anything
+fn is_mesh(file: &Path) -> bool {
+    !matches!(
+        file.extension().and_then(|e| e.to_str()),
+        Some("sdf") | Some("world") | Some("config")
+    ) && !is_texture(file)
+}
+
+fn is_texture(file: &Path) -> bool {
+    file.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TEXTURE_IMAGE_TYPES.contains(&e.to_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod asset_graph_tests {
+    use super::*;
+
+    #[test]
+    fn it_lists_shared_assets_and_includes() -> Result<(), Error> {
+        let library = Path::new("tests").join("dependencies").join("graph");
+        let graph = AssetGraph::build(&library)?;
+
+        let models: Vec<(&Path, Vec<PathBuf>, Vec<PathBuf>)> = graph
+            .models
+            .iter()
+            .map(|m| (m.path.as_path(), m.includes.clone(), m.uses.clone()))
+            .collect();
+        assert_eq!(
+            models,
+            vec![
+                (
+                    Path::new("chair"),
+                    vec![],
+                    vec![PathBuf::from("chair/meshes/chair.dae")]
+                ),
+                (
+                    Path::new("office"),
+                    vec![PathBuf::from("chair")],
+                    vec![PathBuf::from("chair/meshes/chair.dae")]
+                ),
+            ]
+        );
+
+        let texture = graph
+            .assets
+            .iter()
+            .find(|a| a.kind == AssetKind::Texture)
+            .unwrap();
+        assert_eq!(texture.path, Path::new("chair/materials/textures/wood.png"));
+        assert_eq!(
+            texture.used_by,
+            vec![PathBuf::from("chair"), PathBuf::from("office")]
+        );
+        assert!(graph.cycles.is_empty());
+
+        Ok(())
+    }
+}
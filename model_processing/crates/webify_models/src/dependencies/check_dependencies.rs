@@ -0,0 +1,75 @@
+//! Check that every model included through a `model://` URI is there and processed
+
+use std::{
+    collections::HashMap,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::{
+    index_models, scan_model_references, DependencyIssue, DependencyProblem,
+};
+use crate::model::read_webified_version;
+
+/// Resolve the `model://` URIs of the library against its models by directory
+/// name, as Gazebo does, and list the ones pointing at a model that doesn't exist
+/// or isn't marked as processed
+pub fn check_dependencies(library: &Path) -> Result<Vec<DependencyIssue>, Error> {
+    let models = index_models(library)?;
+
+    let mut processed: HashMap<PathBuf, bool> = HashMap::new();
+    let mut issues = Vec::new();
+    for reference in scan_model_references(library)? {
+        let problem = match models.get(&reference.name) {
+            None => Some(DependencyProblem::Missing),
+            Some(model_dir) => {
+                let is_processed = match processed.get(model_dir) {
+                    Some(is_processed) => *is_processed,
+                    None => {
+                        let is_processed = read_webified_version(model_dir)?.is_some();
+                        processed.insert(model_dir.clone(), is_processed);
+                        is_processed
+                    }
+                };
+                match is_processed {
+                    true => None,
+                    false => Some(DependencyProblem::Unprocessed),
+                }
+            }
+        };
+        if let Some(problem) = problem {
+            issues.push(DependencyIssue { reference, problem });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod check_dependencies_tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_missing_and_unprocessed_models() -> Result<(), Error> {
+        let library = Path::new("tests").join("dependencies").join("library");
+        let issues = check_dependencies(&library)?;
+
+        let summary: Vec<(&str, DependencyProblem)> = issues
+            .iter()
+            .map(|issue| (issue.reference.uri.as_str(), issue.problem))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "model://chair/meshes/chair.dae",
+                    DependencyProblem::Unprocessed
+                ),
+                ("model://lamp", DependencyProblem::Missing),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Which models of a library include which, and the order to process them in
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::{index_models, scan_model_references};
+
+/// Models of a library including other models through `model://` URIs. Models that
+/// neither include nor are included by another model aren't part of the graph.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+    /// Every model of the graph, with the models it includes
+    dependencies: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> DependencyGraph {
+        DependencyGraph::default()
+    }
+
+    /// Build the graph of the library from the `model://` URIs of its models. URIs
+    /// to models that aren't in the library are left out, see `check_dependencies`.
+    pub fn build(library: &Path) -> Result<DependencyGraph, Error> {
+        let models = index_models(library)?;
+        let mut graph = DependencyGraph::new();
+        for reference in scan_model_references(library)? {
+            if let (Some(model), Some(dependency)) = (&reference.model, models.get(&reference.name))
+            {
+                graph.add_dependency(model, dependency);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Record that `model` includes `dependency`
+    pub fn add_dependency(&mut self, model: &Path, dependency: &Path) {
+        self.dependencies
+            .entry(dependency.to_path_buf())
+            .or_default();
+        self.dependencies
+            .entry(model.to_path_buf())
+            .or_default()
+            .insert(dependency.to_path_buf());
+    }
+
+    /// Models included by the model
+    pub fn dependencies(&self, model: &Path) -> impl Iterator<Item = &PathBuf> {
+        self.dependencies.get(model).into_iter().flatten()
+    }
+
+    /// Every model of the graph
+    pub fn models(&self) -> impl Iterator<Item = &PathBuf> {
+        self.dependencies.keys()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Every model of the graph, each after the models it includes, in path order
+    /// otherwise. Models including each other can't all come after one another;
+    /// the cycle is broken where it's found, see `cycles`.
+    pub fn processing_order(&self) -> Vec<PathBuf> {
+        self.walk().0
+    }
+
+    /// Models including themselves through other models, each cycle listed from
+    /// the model it was found at back to that model
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        self.walk().1
+    }
+
+    /// Depth-first walk of the graph in path order, listing the models in post
+    /// order and the cycles found on the way
+    fn walk(&self) -> (Vec<PathBuf>, Vec<Vec<PathBuf>>) {
+        let mut order: Vec<PathBuf> = Vec::new();
+        let mut cycles: Vec<Vec<PathBuf>> = Vec::new();
+        let mut done: BTreeSet<&PathBuf> = BTreeSet::new();
+        let mut stack: Vec<&PathBuf> = Vec::new();
+
+        for model in self.models() {
+            self.visit(model, &mut done, &mut stack, &mut order, &mut cycles);
+        }
+
+        (order, cycles)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        model: &'a PathBuf,
+        done: &mut BTreeSet<&'a PathBuf>,
+        stack: &mut Vec<&'a PathBuf>,
+        order: &mut Vec<PathBuf>,
+        cycles: &mut Vec<Vec<PathBuf>>,
+    ) {
+        if done.contains(model) {
+            return;
+        }
+        if let Some(start) = stack.iter().position(|m| *m == model) {
+            let mut cycle: Vec<PathBuf> = stack[start..].iter().map(|m| m.to_path_buf()).collect();
+            cycle.push(model.clone());
+            cycles.push(cycle);
+            return;
+        }
+
+        stack.push(model);
+        for dependency in self.dependencies(model) {
+            self.visit(dependency, done, stack, order, cycles);
+        }
+        stack.pop();
+        done.insert(model);
+        order.push(model.clone());
+    }
+}
+
+#[cfg(test)]
+mod dependency_graph_tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_graph_of_the_library() -> Result<(), Error> {
+        let library = Path::new("tests").join("dependencies").join("library");
+        let graph = DependencyGraph::build(&library)?;
+
+        let models = library.join("models");
+        assert_eq!(
+            graph
+                .dependencies(&models.join("office"))
+                .collect::<Vec<_>>(),
+            vec![&models.join("chair"), &models.join("desk")]
+        );
+        assert_eq!(
+            graph.processing_order(),
+            vec![
+                models.join("chair"),
+                models.join("desk"),
+                models.join("office")
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_puts_dependencies_first() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(Path::new("a"), Path::new("b"));
+        graph.add_dependency(Path::new("b"), Path::new("c"));
+        graph.add_dependency(Path::new("d"), Path::new("a"));
+
+        assert_eq!(
+            graph.processing_order(),
+            vec![
+                PathBuf::from("c"),
+                PathBuf::from("b"),
+                PathBuf::from("a"),
+                PathBuf::from("d")
+            ]
+        );
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn it_breaks_cycles() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(Path::new("a"), Path::new("b"));
+        graph.add_dependency(Path::new("b"), Path::new("a"));
+
+        assert_eq!(
+            graph.processing_order(),
+            vec![PathBuf::from("b"), PathBuf::from("a")]
+        );
+        assert_eq!(
+            graph.cycles(),
+            vec![vec![
+                PathBuf::from("a"),
+                PathBuf::from("b"),
+                PathBuf::from("a")
+            ]]
+        );
+    }
+}
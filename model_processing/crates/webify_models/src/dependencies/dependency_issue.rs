@@ -0,0 +1,22 @@
+//! A `model://` URI that points at a model that isn't usable
+
+use serde::Serialize;
+
+use crate::dependencies::ModelReference;
+
+/// What's wrong with the model a URI points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyProblem {
+    /// No model of that name in the library
+    Missing,
+    /// The model is there but isn't marked as processed in its model.config
+    Unprocessed,
+}
+
+/// A URI pointing at a model that's missing or unprocessed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DependencyIssue {
+    pub reference: ModelReference,
+    pub problem: DependencyProblem,
+}
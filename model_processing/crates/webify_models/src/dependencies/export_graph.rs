@@ -0,0 +1,34 @@
+//! Export the asset graph of a library
+
+use std::{fs, io::Error, path::Path};
+
+use console::style;
+
+use crate::dependencies::{render_dot, AssetGraph, GraphFormat};
+
+/// Build the asset graph of the library and write it in the format to `output`,
+/// or print it when there's no output so it can be piped to Graphviz
+pub fn export_graph(
+    library: &Path,
+    format: GraphFormat,
+    output: Option<&Path>,
+) -> std::result::Result<AssetGraph, std::io::Error> {
+    let graph = AssetGraph::build(library)?;
+    let contents = match format {
+        GraphFormat::Dot => render_dot(&graph),
+        GraphFormat::Json => serde_json::to_string_pretty(&graph).map_err(Error::other)? + "\n",
+    };
+
+    match output {
+        Some(output) => {
+            fs::write(output, contents)?;
+            println!(
+                "Asset graph written to {}",
+                style(output.to_string_lossy()).dim()
+            );
+        }
+        None => print!("{}", contents),
+    }
+
+    Ok(graph)
+}
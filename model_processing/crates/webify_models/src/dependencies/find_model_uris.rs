@@ -0,0 +1,59 @@
+//! Find the `model://` URIs of an SDF, world or mesh
+
+use std::{
+    io::{self, BufRead, Error},
+    result::Result,
+};
+
+use crate::xml::edit_text_nodes_streaming;
+
+/// Every distinct `model://` URI among the text values of the XML document, in
+/// document order. The contents are streamed, so large worlds never have to be
+/// loaded whole.
+pub fn find_model_uris(contents: impl BufRead) -> Result<Vec<String>, Error> {
+    let mut uris: Vec<String> = Vec::new();
+    edit_text_nodes_streaming(contents, io::empty(), io::sink(), |text| {
+        if model_uri_name(text).is_some() && !uris.iter().any(|uri| uri == text) {
+            uris.push(text.to_string());
+        }
+        None
+    })?;
+
+    Ok(uris)
+}
+
+/// Name of the model a `model://` URI points into, e.g. `desk` for
+/// `model://desk/meshes/desk.dae`
+pub fn model_uri_name(uri: &str) -> Option<&str> {
+    let path = uri.strip_prefix("model://")?;
+    let name = path.split('/').next().unwrap_or(path);
+
+    match name.is_empty() {
+        true => None,
+        false => Some(name),
+    }
+}
+
+#[cfg(test)]
+mod find_model_uris_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_each_uri_once() -> Result<(), Error> {
+        let contents = "<world>\n  <include><uri>model://desk</uri></include>\n  <include><uri>model://desk</uri></include>\n  <mesh><uri>model://lamp/meshes/lamp.dae</uri></mesh>\n  <mesh><uri>meshes/local.dae</uri></mesh>\n</world>";
+
+        assert_eq!(
+            find_model_uris(contents.as_bytes())?,
+            vec!["model://desk", "model://lamp/meshes/lamp.dae"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_the_model_name() {
+        assert_eq!(model_uri_name("model://desk"), Some("desk"));
+        assert_eq!(model_uri_name("model://lamp/meshes/lamp.dae"), Some("lamp"));
+        assert_eq!(model_uri_name("model://"), None);
+        assert_eq!(model_uri_name("meshes/lamp.dae"), None);
+    }
+}
@@ -0,0 +1,27 @@
+//! Format the asset graph is exported in
+
+use std::{io::Error, str::FromStr};
+
+/// Format the asset graph is exported in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz, to render with `dot -Tsvg`
+    #[default]
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(Error::other(format!(
+                "Unknown graph format {:?}, expected dot or json.",
+                s
+            ))),
+        }
+    }
+}
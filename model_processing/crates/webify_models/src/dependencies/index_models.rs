@@ -0,0 +1,26 @@
+//! Find the models of a library by the name `model://` URIs use for them
+
+use std::{
+    collections::BTreeMap,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::model::find_model_dirs;
+
+/// Every model of the library by directory name, which is how Gazebo resolves
+/// `model://<name>`. When two collections have a model of the same name, the first
+/// one in path order wins.
+pub fn index_models(library: &Path) -> Result<BTreeMap<String, PathBuf>, Error> {
+    let mut models = BTreeMap::new();
+    for model_dir in find_model_dirs(library)? {
+        if let Some(name) = model_dir.file_name().and_then(|n| n.to_str()) {
+            models
+                .entry(name.to_string())
+                .or_insert_with(|| model_dir.clone());
+        }
+    }
+
+    Ok(models)
+}
@@ -0,0 +1,34 @@
+//! Models including other models through `model://` URIs, whether what they
+//! include is there and processed, and the graph of the assets they use
+
+mod asset_graph;
+mod check_dependencies;
+mod dependency_graph;
+mod dependency_issue;
+mod export_graph;
+mod find_model_uris;
+mod graph_format;
+mod index_models;
+mod model_reference;
+mod owning_model;
+mod print_dependency_issues;
+mod process;
+mod render_dot;
+mod resolve_reference;
+mod scan_model_references;
+
+pub use self::asset_graph::{AssetGraph, AssetKind, AssetNode, ModelNode};
+pub use self::check_dependencies::check_dependencies;
+pub use self::dependency_graph::DependencyGraph;
+pub use self::dependency_issue::{DependencyIssue, DependencyProblem};
+pub use self::export_graph::export_graph;
+pub use self::find_model_uris::{find_model_uris, model_uri_name};
+pub use self::graph_format::GraphFormat;
+pub use self::index_models::index_models;
+pub use self::model_reference::ModelReference;
+pub use self::owning_model::owning_model;
+pub use self::print_dependency_issues::print_dependency_issues;
+pub use self::process::process;
+pub use self::render_dot::render_dot;
+pub use self::resolve_reference::resolve_reference;
+pub use self::scan_model_references::scan_model_references;
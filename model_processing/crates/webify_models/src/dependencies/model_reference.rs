@@ -0,0 +1,17 @@
+//! A `model://` URI found in the library
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// A `model://` URI pointing at another model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelReference {
+    /// File the URI was found in
+    pub file: PathBuf,
+    /// Model the file belongs to, `None` for files outside any model, like worlds
+    pub model: Option<PathBuf>,
+    pub uri: String,
+    /// Name of the model the URI points at
+    pub name: String,
+}
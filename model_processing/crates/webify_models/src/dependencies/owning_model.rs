@@ -0,0 +1,13 @@
+//! Find the model a file of the library belongs to
+
+use std::path::{Path, PathBuf};
+
+/// Closest directory above the file, inside the library, with a model.config.
+/// `None` for files outside any model, like worlds.
+pub fn owning_model(file: &Path, library: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(library))
+        .find(|dir| dir.join("model.config").is_file())
+        .map(Path::to_path_buf)
+}
@@ -0,0 +1,21 @@
+//! Human-readable output for the dependency check
+
+use console::style;
+
+use crate::dependencies::{DependencyIssue, DependencyProblem};
+
+/// Print the issues to the console, grouped by the file holding the URI
+pub fn print_dependency_issues(issues: &[DependencyIssue]) {
+    let mut current_file = None;
+    for issue in issues {
+        if current_file != Some(&issue.reference.file) {
+            current_file = Some(&issue.reference.file);
+            println!("\n{}", style(issue.reference.file.to_string_lossy()).bold());
+        }
+        let problem = match issue.problem {
+            DependencyProblem::Missing => style("missing").red().bold(),
+            DependencyProblem::Unprocessed => style("unprocessed").yellow().bold(),
+        };
+        println!("  {} {}", problem, issue.reference.uri);
+    }
+}
@@ -0,0 +1,31 @@
+//! Orchestrator to check the dependencies between the models of a library
+
+use std::path::Path;
+
+use console::style;
+
+use crate::dependencies::{check_dependencies, print_dependency_issues, DependencyIssue};
+
+/// Check that every model included by another model or a world is in the library
+/// and processed, printing the result
+pub fn process(library: &Path) -> std::result::Result<Vec<DependencyIssue>, std::io::Error> {
+    println!("\nChecking model dependencies...");
+    let issues = check_dependencies(library)?;
+    print_dependency_issues(&issues);
+
+    match issues.is_empty() {
+        true => println!(
+            "\n{}",
+            style("Every included model is there and processed.")
+                .green()
+                .bold()
+        ),
+        false => println!(
+            "\n{} {} URI(s) point at missing or unprocessed models.",
+            style("Dependency check failed,").red().bold(),
+            issues.len()
+        ),
+    }
+
+    Ok(issues)
+}
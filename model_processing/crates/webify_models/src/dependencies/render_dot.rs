@@ -0,0 +1,156 @@
+//! Render the asset graph for Graphviz
+
+use std::{collections::BTreeSet, fmt::Write, path::Path};
+
+use crate::dependencies::{AssetGraph, AssetKind};
+use crate::report::format_bytes;
+
+/// Render the graph in the DOT language. Models are boxes labelled with their
+/// size, meshes ellipses and textures notes; assets shared by several models are
+/// filled, and includes that are part of a cycle are red.
+pub fn render_dot(graph: &AssetGraph) -> String {
+    let mut dot =
+        String::from("digraph assets {\n  rankdir=LR;\n  node [fontname=\"Helvetica\"];\n");
+
+    for model in &graph.models {
+        let _ = writeln!(
+            dot,
+            "  {} [shape=box, style=bold, label={}];",
+            quote(&model.path),
+            quote_str(&format!(
+                "{}\\n{}",
+                name(&model.path),
+                format_bytes(model.bytes)
+            ))
+        );
+    }
+    for asset in &graph.assets {
+        let shape = match asset.kind {
+            AssetKind::Mesh => "ellipse",
+            AssetKind::Texture => "note",
+        };
+        let style = match asset.used_by.len() > 1 {
+            true => ", style=filled, fillcolor=\"#ffe08a\"",
+            false => "",
+        };
+        let _ = writeln!(
+            dot,
+            "  {} [shape={}{}, label={}];",
+            quote(&asset.path),
+            shape,
+            style,
+            quote_str(&format!(
+                "{}\\n{}",
+                name(&asset.path),
+                format_bytes(asset.bytes)
+            ))
+        );
+    }
+
+    let cyclic: BTreeSet<(&Path, &Path)> = graph
+        .cycles
+        .iter()
+        .flat_map(|cycle| cycle.windows(2).map(|w| (w[0].as_path(), w[1].as_path())))
+        .collect();
+    for model in &graph.models {
+        for included in &model.includes {
+            let color = match cyclic.contains(&(model.path.as_path(), included.as_path())) {
+                true => ", color=red",
+                false => "",
+            };
+            let _ = writeln!(
+                dot,
+                "  {} -> {} [style=dashed, label=\"includes\"{}];",
+                quote(&model.path),
+                quote(included),
+                color
+            );
+        }
+        for used in &model.uses {
+            let _ = writeln!(dot, "  {} -> {};", quote(&model.path), quote(used));
+        }
+    }
+    for asset in &graph.assets {
+        for used in &asset.uses {
+            let _ = writeln!(dot, "  {} -> {};", quote(&asset.path), quote(used));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Last component of the path, for labels
+fn name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// DOT identifier for the path, with forward slashes whatever the platform
+fn quote(path: &Path) -> String {
+    let components: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    quote_str(&components.join("/"))
+}
+
+/// Quoted DOT string. Backslashes are kept as they are, so `\n` in labels still
+/// breaks the line.
+fn quote_str(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod render_dot_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::dependencies::{AssetNode, ModelNode};
+
+    #[test]
+    fn it_renders_models_assets_and_cycles() {
+        let graph = AssetGraph {
+            models: vec![
+                ModelNode {
+                    path: PathBuf::from("desk"),
+                    bytes: 2048,
+                    includes: vec![PathBuf::from("drawer")],
+                    uses: vec![PathBuf::from("desk/meshes/desk.dae")],
+                },
+                ModelNode {
+                    path: PathBuf::from("drawer"),
+                    bytes: 100,
+                    includes: vec![PathBuf::from("desk")],
+                    uses: vec![],
+                },
+            ],
+            assets: vec![AssetNode {
+                path: PathBuf::from("desk/meshes/desk.dae"),
+                kind: AssetKind::Mesh,
+                bytes: 100,
+                uses: vec![],
+                used_by: vec![PathBuf::from("desk"), PathBuf::from("drawer")],
+            }],
+            cycles: vec![vec![
+                PathBuf::from("desk"),
+                PathBuf::from("drawer"),
+                PathBuf::from("desk"),
+            ]],
+        };
+        let dot = render_dot(&graph);
+
+        assert!(dot.starts_with("digraph assets {\n"));
+        assert!(dot.contains("  \"desk\" [shape=box, style=bold, label=\"desk\\n2.0 KiB\"];\n"));
+        assert!(dot.contains(
+            "  \"desk/meshes/desk.dae\" [shape=ellipse, style=filled, fillcolor=\"#ffe08a\""
+        ));
+        assert!(dot
+            .contains("  \"desk\" -> \"drawer\" [style=dashed, label=\"includes\", color=red];\n"));
+        assert!(dot.contains("  \"desk\" -> \"desk/meshes/desk.dae\";\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
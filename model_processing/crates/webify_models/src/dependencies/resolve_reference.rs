@@ -0,0 +1,91 @@
+//! Work out which file of the library a reference points at
+
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::dependencies::model_uri_name;
+
+/// The file a reference found in `file` points at: `model://<name>/...` URIs
+/// resolve inside the model of that name, other references relative to the
+/// directory of `file`. `..` and `.` are resolved without following links, so a
+/// file reached through different references gets the same path. `None` when the
+/// file doesn't exist.
+pub fn resolve_reference(
+    file: &Path,
+    reference: &str,
+    models: &BTreeMap<String, PathBuf>,
+) -> Option<PathBuf> {
+    let resolved = match model_uri_name(reference) {
+        Some(name) => {
+            let rest = reference["model://".len() + name.len()..].trim_start_matches('/');
+            models.get(name)?.join(rest)
+        }
+        None => file
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(reference.trim_start_matches("file://")),
+    };
+
+    let resolved = normalize(&resolved);
+    match resolved.is_file() {
+        true => Some(resolved),
+        false => None,
+    }
+}
+
+/// Drop the `.` components of the path and the directories `..` goes back from
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod resolve_reference_tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_model_uris_and_relative_references() {
+        let models_dir = Path::new("tests")
+            .join("dependencies")
+            .join("library")
+            .join("models");
+        let mut models = BTreeMap::new();
+        models.insert(String::from("desk"), models_dir.join("desk"));
+        let sdf = models_dir.join("office").join("model.sdf");
+
+        assert_eq!(
+            resolve_reference(&sdf, "model://desk/model.sdf", &models),
+            Some(models_dir.join("desk").join("model.sdf"))
+        );
+        assert_eq!(
+            resolve_reference(&sdf, "model.config", &models),
+            Some(models_dir.join("office").join("model.config"))
+        );
+        assert_eq!(
+            resolve_reference(&sdf, "model://lamp/model.sdf", &models),
+            None
+        );
+        assert_eq!(
+            resolve_reference(&sdf, "../desk/./model.sdf", &models),
+            Some(models_dir.join("desk").join("model.sdf"))
+        );
+        assert_eq!(resolve_reference(&sdf, "missing.png", &models), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! List the `model://` URIs pointing from one model to another across a library
+
+use std::{
+    fs::File,
+    io::{BufReader, Error},
+    path::Path,
+    result::Result,
+};
+
+use crate::dependencies::{find_model_uris, model_uri_name, owning_model, ModelReference};
+use crate::rename::MODEL_URI_FILE_EXTENSIONS;
+use crate::walk::walk_dir;
+
+/// Every `model://` URI in the SDFs, worlds and meshes of the library, in path
+/// order. URIs from a model into itself aren't dependencies and are left out.
+pub fn scan_model_references(library: &Path) -> Result<Vec<ModelReference>, Error> {
+    let files = walk_dir(library, &|path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MODEL_URI_FILE_EXTENSIONS.contains(&ext))
+    })?;
+
+    let mut references = Vec::new();
+    for file in files {
+        let uris = find_model_uris(BufReader::new(File::open(&file)?))
+            .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", file, e)))?;
+        let model = owning_model(&file, library);
+        for uri in uris {
+            let name = model_uri_name(&uri).unwrap_or_default().to_string();
+            let own_name = model
+                .as_deref()
+                .and_then(Path::file_name)
+                .and_then(|n| n.to_str());
+            if own_name == Some(name.as_str()) {
+                continue;
+            }
+            references.push(ModelReference {
+                file: file.clone(),
+                model: model.clone(),
+                uri,
+                name,
+            });
+        }
+    }
+
+    Ok(references)
+}
+
+#[cfg(test)]
+mod scan_model_references_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_lists_references_between_models() -> Result<(), Error> {
+        let library = Path::new("tests").join("dependencies").join("library");
+        let references = scan_model_references(&library)?;
+
+        let summary: Vec<(PathBuf, Option<PathBuf>, &str)> = references
+            .iter()
+            .map(|r| (r.file.clone(), r.model.clone(), r.name.as_str()))
+            .collect();
+        let models = library.join("models");
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    models.join("office").join("model.sdf"),
+                    Some(models.join("office")),
+                    "desk"
+                ),
+                (
+                    models.join("office").join("model.sdf"),
+                    Some(models.join("office")),
+                    "chair"
+                ),
+                (
+                    models.join("office").join("model.sdf"),
+                    Some(models.join("office")),
+                    "lamp"
+                ),
+                (library.join("worlds").join("office.world"), None, "office"),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! SIMD extensions of the CPU the encoders can use
+
+/// SIMD extensions the AVIF, WebP and Basis Universal encoders speed up with, and
+/// whether this CPU has each. Other architectures have none to report.
+pub fn cpu_features() -> Vec<(&'static str, bool)> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    return vec![
+        ("sse2", is_x86_feature_detected!("sse2")),
+        ("sse4.1", is_x86_feature_detected!("sse4.1")),
+        ("avx2", is_x86_feature_detected!("avx2")),
+    ];
+    #[cfg(target_arch = "aarch64")]
+    return vec![("neon", std::arch::is_aarch64_feature_detected!("neon"))];
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    return Vec::new();
+}
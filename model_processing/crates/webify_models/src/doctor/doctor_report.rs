@@ -0,0 +1,66 @@
+//! Structure that represents what `webify_models doctor` found
+
+/// How a check of the doctor went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoctorStatus {
+    Ok,
+    /// Runs work, with something missing, e.g. sounds left untranscoded
+    Warning,
+    /// Runs would fail or lose work
+    Error,
+}
+
+/// A format, tool or part of the environment, and how it checked out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: DoctorStatus,
+    pub details: String,
+}
+
+impl DoctorCheck {
+    pub fn new(
+        name: impl Into<String>,
+        status: DoctorStatus,
+        details: impl Into<String>,
+    ) -> DoctorCheck {
+        DoctorCheck {
+            name: name.into(),
+            status,
+            details: details.into(),
+        }
+    }
+}
+
+/// What this build supports and how the environment checked out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    /// Texture extensions scanned, and whether each can be decoded
+    pub inputs: Vec<DoctorCheck>,
+    /// Texture formats written, each probed by encoding a small texture
+    pub outputs: Vec<DoctorCheck>,
+    /// Mesh formats whose references and textures are updated
+    pub meshes: Vec<DoctorCheck>,
+    /// Sound extensions scanned, and how they're transcoded
+    pub sounds: Vec<DoctorCheck>,
+    /// Permissions, disk space, CPU features and external tools
+    pub environment: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Every check, section after section
+    pub fn checks(&self) -> impl Iterator<Item = &DoctorCheck> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .chain(&self.meshes)
+            .chain(&self.sounds)
+            .chain(&self.environment)
+    }
+
+    /// Whether nothing would make a run fail
+    pub fn is_healthy(&self) -> bool {
+        self.checks()
+            .all(|check| check.status != DoctorStatus::Error)
+    }
+}
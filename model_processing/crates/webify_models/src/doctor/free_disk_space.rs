@@ -0,0 +1,40 @@
+//! Space left on the filesystem of a directory
+
+use std::path::Path;
+
+/// Bytes available to this user on the filesystem holding the directory, `None`
+/// when the platform can't tell
+#[cfg(unix)]
+pub fn free_disk_space(dir: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // Safe, the path is NUL terminated and statvfs fills the stats on success
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stats.assume_init()
+    };
+
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_disk_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod free_disk_space_tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn it_tells_the_space_left() {
+        assert!(free_disk_space(Path::new("tests")).is_some());
+        assert_eq!(free_disk_space(Path::new("tests/does_not_exist")), None);
+    }
+}
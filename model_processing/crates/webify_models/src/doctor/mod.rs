@@ -0,0 +1,16 @@
+//! Report what this build of webify_models can read and write, and whether the
+//! machine it runs on is fit for a run
+
+mod cpu_features;
+mod doctor_report;
+mod free_disk_space;
+mod print_doctor_report;
+mod probe_encoders;
+mod run_doctor;
+
+pub use self::cpu_features::cpu_features;
+pub use self::doctor_report::{DoctorCheck, DoctorReport, DoctorStatus};
+pub use self::free_disk_space::free_disk_space;
+pub use self::print_doctor_report::print_doctor_report;
+pub use self::probe_encoders::probe_encoders;
+pub use self::run_doctor::{run_doctor, MIN_FREE_DISK_SPACE};
@@ -0,0 +1,45 @@
+//! Print what `webify_models doctor` found
+
+use std::path::Path;
+
+use console::style;
+
+use crate::doctor::{DoctorCheck, DoctorReport, DoctorStatus};
+
+/// Print every section of the report, one check per line, aligned
+pub fn print_doctor_report(dir: &Path, report: &DoctorReport) {
+    println!("\n{} for {}", style("Doctor").bold(), dir.to_string_lossy());
+
+    let width = report
+        .checks()
+        .map(|check| check.name.len())
+        .max()
+        .unwrap_or(0);
+    for (title, checks) in [
+        ("Texture inputs", &report.inputs),
+        ("Texture outputs", &report.outputs),
+        ("Meshes and models", &report.meshes),
+        ("Sounds", &report.sounds),
+        ("Environment", &report.environment),
+    ] {
+        println!("\n{}", style(title).underlined());
+        for check in checks {
+            print_check(check, width);
+        }
+    }
+}
+
+fn print_check(check: &DoctorCheck, width: usize) {
+    let status = match check.status {
+        DoctorStatus::Ok => style("ok").green(),
+        DoctorStatus::Warning => style("warning").yellow(),
+        DoctorStatus::Error => style("error").red(),
+    };
+    println!(
+        "  {:<width$}  {:<7}  {}",
+        check.name,
+        status,
+        style(&check.details).dim(),
+        width = width
+    );
+}
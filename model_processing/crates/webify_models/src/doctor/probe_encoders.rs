@@ -0,0 +1,93 @@
+//! Encode a small texture with every encoder of this build
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use image::{Rgba, RgbaImage};
+
+use crate::doctor::{DoctorCheck, DoctorStatus};
+use crate::image_processing::{convert_to_webp, encode_avif, encode_ktx2, DecodeCache, Image};
+use crate::options::{AvifSettings, Colorspace, Ktx2Settings, OutputFormat, Removal, WebpSettings};
+
+/// Side of the texture the encoders are probed with
+const PROBE_SIZE: u32 = 64;
+
+/// Encode a small texture to every output format in `dir` with the default
+/// settings, through the same functions a run uses. A format whose encoder fails
+/// is an error, since a run to it would keep every texture as PNG.
+pub fn probe_encoders(dir: &Path) -> Result<Vec<DoctorCheck>, Error> {
+    fs::create_dir_all(dir)?;
+
+    OutputFormat::ALL
+        .iter()
+        .map(|&format| {
+            let path = dir.join(format!("probe_{}.png", format.extension()));
+            // Noise, which PNG can't compress, so AVIF is always worth it
+            let mut state = 1u32;
+            RgbaImage::from_fn(PROBE_SIZE, PROBE_SIZE, |_, _| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let [r, g, b, _] = (state >> 8).to_le_bytes();
+                Rgba([r, g, b, u8::MAX])
+            })
+            .save(&path)
+            .map_err(Error::other)?;
+
+            let image = Image {
+                path,
+                extension: String::from("png"),
+            };
+            let removal = Removal::Delete;
+            let mut cache = DecodeCache::default();
+            let encoded = match format {
+                OutputFormat::Png => Ok((image, None)),
+                OutputFormat::Avif => {
+                    encode_avif(image, &AvifSettings::default(), &removal, &mut cache)
+                }
+                OutputFormat::Webp => {
+                    convert_to_webp(image, &WebpSettings::default(), &removal, &mut cache)
+                }
+                OutputFormat::Ktx2 => encode_ktx2(
+                    image,
+                    &Ktx2Settings::default(),
+                    false,
+                    Colorspace::Srgb,
+                    &removal,
+                    &mut cache,
+                ),
+            };
+
+            Ok(match encoded {
+                Ok((_, None)) => DoctorCheck::new(format.extension(), DoctorStatus::Ok, "encodes"),
+                Ok((_, Some(reason))) => {
+                    DoctorCheck::new(format.extension(), DoctorStatus::Error, reason)
+                }
+                Err(e) => DoctorCheck::new(format.extension(), DoctorStatus::Error, e.to_string()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod probe_encoders_tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_to_every_output_format() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("doctor")
+            .join("test_run_probe_encoders");
+        let checks = probe_encoders(&dir)?;
+
+        assert_eq!(checks.len(), OutputFormat::ALL.len());
+        for check in &checks {
+            assert_eq!(check.status, DoctorStatus::Ok, "{:?}", check);
+        }
+        for extension in ["png", "avif", "webp", "ktx2"] {
+            assert!(dir
+                .join(format!("probe_{}.{}", extension, extension))
+                .is_file());
+        }
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
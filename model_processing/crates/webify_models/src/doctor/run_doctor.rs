@@ -0,0 +1,234 @@
+//! Check what this build supports and the environment it runs in
+
+use std::{
+    env, fs,
+    io::Error,
+    path::Path,
+    process::{self, Command},
+    result::Result,
+    thread,
+};
+
+use crate::audio::{AUDIO_EXTENSIONS, WEB_AUDIO_EXTENSIONS};
+use crate::config::load_config;
+use crate::doctor::{
+    cpu_features, free_disk_space, probe_encoders, DoctorCheck, DoctorReport, DoctorStatus,
+};
+use crate::image_processing::{is_decodable, texture_extensions};
+use crate::mesh_update::REFERENCE_FILE_EXTENSIONS;
+use crate::options::{OutputFormat, RunOptions};
+use crate::preflight::check_writable;
+use crate::report::format_bytes;
+
+/// Free disk space below which a run may fill the disk, 1 GiB. Textures are
+/// written next to their original before it's removed.
+pub const MIN_FREE_DISK_SPACE: u64 = 1024 * 1024 * 1024;
+
+/// Check the formats this build reads and writes, probing every encoder in a
+/// temporary directory, and the environment a run in `dir` with these options
+/// would have: permissions, disk space, CPU features and FFmpeg
+pub fn run_doctor(dir: &Path, options: &RunOptions) -> Result<DoctorReport, Error> {
+    let config = load_config(dir)?;
+    let ffmpeg = ffmpeg_version(&options.audio.ffmpeg);
+
+    let web_formats: Vec<&str> = OutputFormat::ALL.iter().map(|f| f.extension()).collect();
+    let inputs = texture_extensions(options, &config)
+        .into_iter()
+        .map(|extension| {
+            let (status, details) = if is_decodable(&extension) {
+                (DoctorStatus::Ok, "decoded and converted")
+            } else if web_formats.contains(&extension.as_str()) {
+                (DoctorStatus::Ok, "kept, already a web format")
+            } else {
+                (DoctorStatus::Warning, "moved, left in its format")
+            };
+            DoctorCheck::new(extension, status, details)
+        })
+        .collect();
+
+    let probe_dir = env::temp_dir().join(format!("webify_doctor_{}", process::id()));
+    let outputs = probe_encoders(&probe_dir);
+    if probe_dir.exists() {
+        fs::remove_dir_all(&probe_dir)?;
+    }
+
+    let meshes = REFERENCE_FILE_EXTENSIONS
+        .iter()
+        .map(|&extension| {
+            let details = match extension {
+                "dae" => "texture references updated, measured and atlased",
+                _ => "texture references updated",
+            };
+            DoctorCheck::new(extension, DoctorStatus::Ok, details)
+        })
+        .collect();
+
+    let sounds = AUDIO_EXTENSIONS
+        .iter()
+        .map(
+            |&extension| match (WEB_AUDIO_EXTENSIONS.contains(&extension), &ffmpeg) {
+                (true, _) => {
+                    DoctorCheck::new(extension, DoctorStatus::Ok, "kept, browsers play it")
+                }
+                (false, Some(_)) => {
+                    DoctorCheck::new(extension, DoctorStatus::Ok, "transcoded to Opus")
+                }
+                (false, None) => DoctorCheck::new(
+                    extension,
+                    DoctorStatus::Warning,
+                    "kept as is, FFmpeg isn't there to transcode it",
+                ),
+            },
+        )
+        .collect();
+
+    Ok(DoctorReport {
+        inputs,
+        outputs: outputs?,
+        meshes,
+        sounds,
+        environment: check_environment(dir, options, ffmpeg),
+    })
+}
+
+/// Permissions, disk space, CPU features and FFmpeg of a run in `dir`
+fn check_environment(dir: &Path, options: &RunOptions, ffmpeg: Option<String>) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+
+    checks.push(match check_writable(dir) {
+        Ok(()) => DoctorCheck::new("permissions", DoctorStatus::Ok, "writable"),
+        Err(e) => DoctorCheck::new("permissions", DoctorStatus::Error, e.to_string()),
+    });
+
+    checks.push(match free_disk_space(dir) {
+        Some(free) if free >= MIN_FREE_DISK_SPACE => DoctorCheck::new(
+            "disk space",
+            DoctorStatus::Ok,
+            format!("{} free", format_bytes(free)),
+        ),
+        Some(free) => DoctorCheck::new(
+            "disk space",
+            DoctorStatus::Warning,
+            format!(
+                "{} free, less than the {} a run may need",
+                format_bytes(free),
+                format_bytes(MIN_FREE_DISK_SPACE)
+            ),
+        ),
+        None => DoctorCheck::new(
+            "disk space",
+            DoctorStatus::Warning,
+            "unknown on this platform",
+        ),
+    });
+
+    let features = cpu_features();
+    let missing: Vec<&str> = features
+        .iter()
+        .filter(|(_, detected)| !detected)
+        .map(|(name, _)| *name)
+        .collect();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let detected: Vec<&str> = features
+        .iter()
+        .filter(|(_, detected)| *detected)
+        .map(|(name, _)| *name)
+        .collect();
+    let cores = format!(
+        "{} thread(s), {}",
+        threads,
+        match detected.is_empty() {
+            true => String::from("no SIMD extensions detected"),
+            false => detected.join(", "),
+        }
+    );
+    checks.push(match missing.is_empty() {
+        true => DoctorCheck::new("cpu", DoctorStatus::Ok, cores),
+        false => DoctorCheck::new(
+            "cpu",
+            DoctorStatus::Warning,
+            format!(
+                "{}, without {} the encoders run slower",
+                cores,
+                missing.join(", ")
+            ),
+        ),
+    });
+
+    checks.push(match ffmpeg {
+        Some(version) => DoctorCheck::new("ffmpeg", DoctorStatus::Ok, version),
+        None => DoctorCheck::new(
+            "ffmpeg",
+            DoctorStatus::Warning,
+            format!(
+                "not found at {:?}, sounds are kept as is",
+                options.audio.ffmpeg
+            ),
+        ),
+    });
+
+    checks
+}
+
+/// First line of `ffmpeg -version`, `None` when FFmpeg can't be run
+fn ffmpeg_version(ffmpeg: &Path) -> Option<String> {
+    let output = Command::new(ffmpeg).arg("-version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+#[cfg(test)]
+mod run_doctor_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::options::AudioSettings;
+
+    #[test]
+    fn it_reports_the_formats_and_the_environment() -> Result<(), Error> {
+        let dir = Path::new("tests").join("config").join("valid");
+        let options = RunOptions {
+            audio: AudioSettings {
+                ffmpeg: PathBuf::from("does_not_exist_ffmpeg"),
+                ..AudioSettings::default()
+            },
+            ..RunOptions::default()
+        };
+        let report = run_doctor(&dir, &options)?;
+
+        let status = |checks: &[DoctorCheck], name: &str| {
+            checks
+                .iter()
+                .find(|check| check.name == name)
+                .map(|check| check.status)
+        };
+        assert_eq!(status(&report.inputs, "psd"), Some(DoctorStatus::Ok));
+        // Scanned through the webify.toml of the directory
+        assert_eq!(status(&report.inputs, "webp"), Some(DoctorStatus::Ok));
+        assert!(report
+            .outputs
+            .iter()
+            .all(|check| check.status == DoctorStatus::Ok));
+        assert_eq!(status(&report.meshes, "dae"), Some(DoctorStatus::Ok));
+        assert_eq!(status(&report.sounds, "ogg"), Some(DoctorStatus::Ok));
+        assert_eq!(status(&report.sounds, "wav"), Some(DoctorStatus::Warning));
+        assert_eq!(
+            status(&report.environment, "permissions"),
+            Some(DoctorStatus::Ok)
+        );
+        assert_eq!(
+            status(&report.environment, "ffmpeg"),
+            Some(DoctorStatus::Warning)
+        );
+        assert!(report.is_healthy());
+
+        Ok(())
+    }
+}
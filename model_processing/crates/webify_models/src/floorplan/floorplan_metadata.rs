@@ -0,0 +1,20 @@
+//! Where a floorplan image lies in its world, for the minimap placing it
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Where a floorplan image lies in the frame of its world. Column 0 is at the
+/// lowest X and row 0 at the highest Y, so +Y points up in the image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FloorplanMetadata {
+    /// Size of the image, in pixels
+    pub width: u32,
+    pub height: u32,
+    pub pixels_per_meter: f64,
+    /// Corner of the image at the lowest X and Y, in meters
+    pub min: [f64; 2],
+    /// Corner of the image at the highest X and Y, in meters
+    pub max: [f64; 2],
+    /// Heights shown, in meters
+    pub slice: [f64; 2],
+}
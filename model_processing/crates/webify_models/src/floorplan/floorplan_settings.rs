@@ -0,0 +1,20 @@
+//! Knobs of the floorplan
+
+/// Knobs of the floorplan of a world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloorplanSettings {
+    /// Resolution of the image
+    pub pixels_per_meter: f64,
+    /// Heights the floorplan shows, in meters: what's lower is left out, what rises
+    /// higher is cut through, like walls under a ceiling
+    pub slice: [f64; 2],
+}
+
+impl Default for FloorplanSettings {
+    fn default() -> FloorplanSettings {
+        FloorplanSettings {
+            pixels_per_meter: 20.0,
+            slice: [-1.0, 2.0],
+        }
+    }
+}
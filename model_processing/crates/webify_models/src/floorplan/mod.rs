@@ -0,0 +1,11 @@
+//! Floorplans: a world seen from above, for the minimap of the web UI
+
+mod floorplan_metadata;
+mod floorplan_settings;
+mod process;
+mod render_floorplan;
+
+pub use self::floorplan_metadata::FloorplanMetadata;
+pub use self::floorplan_settings::FloorplanSettings;
+pub use self::process::{floorplan_paths, process};
+pub use self::render_floorplan::render_floorplan;
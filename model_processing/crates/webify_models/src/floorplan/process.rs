@@ -0,0 +1,134 @@
+//! Orchestrator to draw the floorplan of a world
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use console::style;
+
+use crate::dependencies::index_models;
+use crate::floorplan::{render_floorplan, FloorplanSettings};
+use crate::impostor::ShapeKind;
+use crate::world::compose_world;
+
+/// Files the floorplan is written to: the PNG image, by default
+/// `<world>.floorplan.png` next to the world, and its metadata, the same path
+/// with a `.json` extension
+pub fn floorplan_paths(world: &Path, output: Option<&Path>) -> (PathBuf, PathBuf) {
+    let image = match output {
+        Some(output) => output.to_path_buf(),
+        None => {
+            let stem = world
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            world.with_file_name(format!("{}.floorplan.png", stem))
+        }
+    };
+    let metadata = image.with_extension("json");
+    (image, metadata)
+}
+
+/// Compose the world out of the visuals of its models, the included ones found in
+/// the library, and write its floorplan seen from above along with where it lies
+/// in the world. Returns the image written.
+pub fn process(
+    world: &Path,
+    library: &Path,
+    output: Option<&Path>,
+    settings: &FloorplanSettings,
+) -> std::result::Result<PathBuf, std::io::Error> {
+    let models = index_models(library)?;
+    let triangles = compose_world(world, &models, ShapeKind::Visual)?;
+    let (image, metadata) = match render_floorplan(&triangles, settings)? {
+        Some(rendered) => rendered,
+        None => {
+            return Err(Error::other(format!(
+                "{:?} has nothing between {} and {} m high to draw.",
+                world, settings.slice[0], settings.slice[1]
+            )))
+        }
+    };
+
+    let (image_path, metadata_path) = floorplan_paths(world, output);
+    image
+        .save(&image_path)
+        .map_err(|e| Error::other(format!("Could not save {:?}: {:?}", image_path, e)))?;
+    fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+    println!(
+        "Floorplan of {:.1} m by {:.1} m written to {}",
+        metadata.max[0] - metadata.min[0],
+        metadata.max[1] - metadata.min[1],
+        style(image_path.to_string_lossy()).dim()
+    );
+
+    Ok(image_path)
+}
+
+#[cfg(test)]
+mod process_tests {
+    use super::*;
+
+    use crate::floorplan::FloorplanMetadata;
+
+    #[test]
+    fn it_draws_the_world_from_above() -> Result<(), Error> {
+        let library = Path::new("tests").join("world");
+        let world = library.join("worlds").join("apartment.world");
+        let output = Path::new("tests")
+            .join("floorplan")
+            .join("test_run_floorplan")
+            .join("apartment.png");
+        fs::create_dir_all(output.parent().unwrap())?;
+
+        let written = process(
+            &world,
+            &library,
+            Some(&output),
+            &FloorplanSettings::default(),
+        )?;
+
+        assert_eq!(written, output);
+        let image = image::open(&output).unwrap().to_rgba8();
+        let metadata: FloorplanMetadata =
+            serde_json::from_str(&fs::read_to_string(output.with_extension("json"))?)?;
+        // The 6 by 4 m ground at 20 pixels a meter
+        assert_eq!((image.width(), image.height()), (120, 80));
+        assert_eq!((metadata.width, metadata.height), (120, 80));
+        assert_eq!(metadata.min, [-3.0, -2.0]);
+        // The wall, cut through, in the middle
+        assert_eq!(image.get_pixel(60, 40).0, [40, 40, 40, 255]);
+        // The table top, drawn in its color over the ground
+        assert_eq!(image.get_pixel(30, 40).0, [153, 102, 51, 255]);
+        assert_ne!(image.get_pixel(90, 40).0, image.get_pixel(30, 40).0);
+
+        // Nothing in a slice above the wall
+        let settings = FloorplanSettings {
+            slice: [3.0, 4.0],
+            ..FloorplanSettings::default()
+        };
+        assert!(process(&world, &library, Some(&output), &settings).is_err());
+
+        fs::remove_dir_all(output.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_next_to_the_world_by_default() {
+        let world = Path::new("worlds").join("apartment.world");
+
+        assert_eq!(
+            floorplan_paths(&world, None),
+            (
+                Path::new("worlds").join("apartment.floorplan.png"),
+                Path::new("worlds").join("apartment.floorplan.json")
+            )
+        );
+        assert_eq!(
+            floorplan_paths(&world, Some(Path::new("minimap.png"))).1,
+            PathBuf::from("minimap.json")
+        );
+    }
+}
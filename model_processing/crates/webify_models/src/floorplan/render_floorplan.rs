@@ -0,0 +1,166 @@
+//! Project a scene from above
+
+use std::io::{Error, ErrorKind};
+
+use image::{Rgba, RgbaImage};
+
+use crate::floorplan::{FloorplanMetadata, FloorplanSettings};
+use crate::impostor::Triangle;
+use crate::navmesh::triangle_cells;
+
+/// Most pixels along each side of a floorplan
+const MAX_SIDE: f64 = 16384.0;
+
+/// Color of the solids the top of the slice cuts through, like walls
+const CUT_COLOR: Rgba<u8> = Rgba([40, 40, 40, 255]);
+
+/// Surfaces steeper than this, by the vertical part of their normal, are walls
+/// rather than floors or ceilings when telling the inside of solids
+const MIN_FLATNESS: f64 = 0.1;
+
+/// What the top of the slice sees in a pixel
+#[derive(Debug, Clone, Copy)]
+struct Pixel {
+    /// Height of the highest surface below the top of the slice
+    top: f64,
+    color: Option<[f32; 3]>,
+    /// Whether something rises through the top of the slice
+    cut: bool,
+    /// Height of the lowest flat surface above the slice, and whether it faces up,
+    /// which it does when the top of the slice is inside a solid
+    above: f64,
+    above_faces_up: bool,
+}
+
+/// Orthographic projection from above of the triangles within the height slice,
+/// each pixel showing the highest surface in the slice, in its color shaded by
+/// its slope, or the cut through the solids the top of the slice goes through in
+/// dark gray. A solid is gone through when a surface crosses the top of the slice
+/// there, or when the first flat surface above faces up, as the top of a wall
+/// does and the underside of a ceiling doesn't. Places with nothing in the slice
+/// are transparent. `None` when no triangle reaches the slice.
+pub fn render_floorplan(
+    triangles: &[Triangle],
+    settings: &FloorplanSettings,
+) -> Result<Option<(RgbaImage, FloorplanMetadata)>, Error> {
+    let [bottom, top] = settings.slice;
+    let in_slice = |triangle: &&Triangle| {
+        let heights = triangle.vertices.map(|v| v[2]);
+        heights.iter().any(|&z| z >= bottom) && heights.iter().any(|&z| z <= top)
+    };
+    if !triangles.iter().any(|triangle| in_slice(&triangle)) {
+        return Ok(None);
+    }
+
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for vertex in triangles
+        .iter()
+        .filter(in_slice)
+        .flat_map(|triangle| triangle.vertices.iter())
+    {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(vertex[axis]);
+            max[axis] = max[axis].max(vertex[axis]);
+        }
+    }
+    let pixel_size = 1.0 / settings.pixels_per_meter;
+    let pixels = |axis: usize| ((max[axis] - min[axis]) / pixel_size).ceil().max(1.0);
+    let (width, height) = (pixels(0), pixels(1));
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "A {:.0} by {:.0} pixel floorplan is larger than the {} pixels a side allowed, lower --pixels-per-meter",
+                width, height, MAX_SIDE
+            ),
+        ));
+    }
+    let (width, height) = (width as usize, height as usize);
+
+    let mut pixels = vec![
+        Pixel {
+            top: f64::NEG_INFINITY,
+            color: None,
+            cut: false,
+            above: f64::INFINITY,
+            above_faces_up: false,
+        };
+        width * height
+    ];
+    // What's under the slice is hidden by it
+    for triangle in triangles
+        .iter()
+        .filter(|triangle| triangle.vertices.iter().any(|v| v[2] >= bottom))
+    {
+        let [a, b, c] = triangle.vertices;
+        let normal = cross(
+            [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+            [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+        );
+        let length = (normal[0].powi(2) + normal[1].powi(2) + normal[2].powi(2)).sqrt();
+        let facing = match length > 0.0 {
+            true => normal[2] / length,
+            false => 0.0,
+        };
+        let color = triangle
+            .color
+            .map(|channel| channel * (0.55 + 0.45 * facing.abs() as f32));
+
+        triangle_cells(
+            &triangle.vertices,
+            min,
+            pixel_size,
+            (width, height),
+            |x, y, low, high| {
+                let pixel = &mut pixels[x + y * width];
+                if low > top {
+                    if facing.abs() >= MIN_FLATNESS && low < pixel.above {
+                        pixel.above = low;
+                        pixel.above_faces_up = facing > 0.0;
+                    }
+                } else if high > top {
+                    pixel.cut = true;
+                } else if high >= bottom && high > pixel.top {
+                    pixel.top = high;
+                    pixel.color = Some(color);
+                }
+            },
+        );
+    }
+
+    let image = RgbaImage::from_fn(width as u32, height as u32, |column, row| {
+        // Rows go down from the highest Y
+        let pixel = pixels[column as usize + (height - 1 - row as usize) * width];
+        match (pixel.cut || pixel.above_faces_up, pixel.color) {
+            (true, _) => CUT_COLOR,
+            (false, Some(color)) => {
+                let [r, g, b] =
+                    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+                Rgba([r, g, b, 255])
+            }
+            (false, None) => Rgba([0, 0, 0, 0]),
+        }
+    });
+    let metadata = FloorplanMetadata {
+        width: width as u32,
+        height: height as u32,
+        pixels_per_meter: settings.pixels_per_meter,
+        min,
+        max: [
+            min[0] + width as f64 * pixel_size,
+            min[1] + height as f64 * pixel_size,
+        ],
+        slice: settings.slice,
+    };
+
+    Ok(Some((image, metadata)))
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
@@ -0,0 +1,34 @@
+//! Hash the contents of a file with BLAKE3
+
+use std::{io::Error, path::Path, result::Result};
+
+/// Hex-encoded BLAKE3 hash of the file contents. Large files are memory-mapped and
+/// hashed on every core, small ones are simply read.
+pub fn hash_file(path: &Path) -> Result<String, Error> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_mmap_rayon(path)?;
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod hash_file_tests {
+    use super::*;
+
+    #[test]
+    fn it_hashes_the_file_contents() -> Result<(), Error> {
+        let path = Path::new("tests")
+            .join("mesh_update")
+            .join("resolved")
+            .join("materials")
+            .join("textures")
+            .join("resolved.png");
+
+        assert_eq!(
+            hash_file(&path)?,
+            blake3::hash(b"not really a png\n").to_hex().to_string()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+//! Content hashing shared by the compare, dedupe and manifest features
+
+mod hash_file;
+
+pub use self::hash_file::hash_file;
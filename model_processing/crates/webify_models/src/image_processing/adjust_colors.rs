@@ -0,0 +1,161 @@
+//! Correct the colors of a texture, e.g. the scans of a vendor that come out too dark
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::{DynamicImage, ImageBuffer, Pixel};
+
+use crate::config::ColorAdjustment;
+use crate::image_processing::DecodeCache;
+
+/// Largest side of the previews of an adjusted texture
+const PREVIEW_SIZE: u32 = 128;
+
+/// Apply the adjustment to the 8-bit texture in place: the white balance gains to
+/// its linear light, then the gamma and the brightness to its sRGB values, alpha
+/// left as it is. Gray textures have no cast to take out and only get the gamma and
+/// the brightness. Returns previews of the texture before and after, or `None` for
+/// 16-bit textures, which are left as they are.
+pub fn adjust_colors(
+    path: &Path,
+    adjustment: &ColorAdjustment,
+    cache: &mut DecodeCache,
+) -> Result<Option<(DynamicImage, DynamicImage)>, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to adjust its colors: {:?}",
+            path, e
+        ))
+    })?;
+
+    let gains = adjustment
+        .white_balance
+        .map_or([1.0; 3], |white_balance| white_balance.gains());
+    let tables = [
+        lookup_table(adjustment, gains[0]),
+        lookup_table(adjustment, gains[1]),
+        lookup_table(adjustment, gains[2]),
+    ];
+    let gray = [lookup_table(adjustment, 1.0)];
+    let adjusted = match &img {
+        DynamicImage::ImageLuma8(buffer) => DynamicImage::ImageLuma8(map_channels(buffer, &gray)),
+        DynamicImage::ImageLumaA8(buffer) => DynamicImage::ImageLumaA8(map_channels(buffer, &gray)),
+        DynamicImage::ImageRgb8(buffer) => DynamicImage::ImageRgb8(map_channels(buffer, &tables)),
+        DynamicImage::ImageRgba8(buffer) => DynamicImage::ImageRgba8(map_channels(buffer, &tables)),
+        _ => {
+            cache.insert(path, img);
+            return Ok(None);
+        }
+    };
+    adjusted
+        .save(path)
+        .map_err(|e| Error::other(format!("Could not save color adjusted {:?}: {:?}", path, e)))?;
+
+    let before = img.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE);
+    let after = adjusted.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE);
+    cache.insert(path, adjusted);
+
+    Ok(Some((before, after)))
+}
+
+/// Adjusted value of every 8-bit sRGB value of a channel with this white balance gain
+fn lookup_table(adjustment: &ColorAdjustment, gain: f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (value, adjusted) in table.iter_mut().enumerate() {
+        let mut value = value as f32 / 255.0;
+        if gain != 1.0 {
+            value = linear_to_srgb((srgb_to_linear(value) * gain).min(1.0));
+        }
+        if let Some(gamma) = adjustment.gamma {
+            value = value.powf(1.0 / gamma);
+        }
+        if let Some(brightness) = adjustment.brightness {
+            value += brightness;
+        }
+        *adjusted = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+
+    table
+}
+
+/// The buffer with each color channel looked up in its table, the channels past the
+/// tables, like alpha, copied as they are
+fn map_channels<P: Pixel<Subpixel = u8> + 'static>(
+    buffer: &ImageBuffer<P, Vec<u8>>,
+    tables: &[[u8; 256]],
+) -> ImageBuffer<P, Vec<u8>> {
+    let mut adjusted = buffer.clone();
+    for pixel in adjusted.pixels_mut() {
+        for (channel, table) in pixel.channels_mut().iter_mut().zip(tables) {
+            *channel = table[*channel as usize];
+        }
+    }
+
+    adjusted
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+#[cfg(test)]
+mod adjust_colors_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{GenericImageView, Rgba};
+
+    use crate::options::WhiteBalance;
+
+    #[test]
+    fn it_brightens_dark_scans_and_keeps_alpha() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_adjust_colors");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("scan.png");
+        ImageBuffer::from_pixel(256, 64, Rgba([64u8, 64, 64, 100]))
+            .save(&path)
+            .map_err(Error::other)?;
+
+        let adjustment = ColorAdjustment {
+            gamma: Some(2.0),
+            brightness: Some(0.1),
+            white_balance: None,
+        };
+        let previews = adjust_colors(&path, &adjustment, &mut DecodeCache::default());
+        let adjusted = image::open(&path).map_err(Error::other)?.to_rgba8();
+        fs::remove_dir_all(dir)?;
+
+        let (before, after) = previews?.unwrap();
+        assert_eq!(before.dimensions(), (128, 32));
+        assert_eq!(after.dimensions(), (128, 32));
+        // sqrt(64 / 255) + 0.1
+        assert_eq!(adjusted.get_pixel(0, 0), &Rgba([153, 153, 153, 100]));
+        Ok(())
+    }
+
+    #[test]
+    fn it_takes_out_the_cast_of_tungsten_light() {
+        let adjustment = ColorAdjustment {
+            white_balance: Some(WhiteBalance::Tungsten),
+            ..ColorAdjustment::default()
+        };
+        let gains = WhiteBalance::Tungsten.gains();
+
+        assert!(lookup_table(&adjustment, gains[0])[200] < 200);
+        assert_eq!(lookup_table(&adjustment, gains[1])[200], 200);
+        assert!(lookup_table(&adjustment, gains[2])[100] > 100);
+        assert_eq!(lookup_table(&adjustment, gains[2])[255], 255);
+    }
+}
@@ -0,0 +1,270 @@
+//! Repackage a Basis Universal file as a KTX2 file
+
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind},
+    result::Result,
+};
+
+/// Identifier every KTX2 file starts with
+pub const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// Size of the `.basis` header and of each of its slice descriptions
+const BASIS_HEADER_LEN: usize = 77;
+const BASIS_SLICE_DESC_LEN: usize = 23;
+
+/// `.basis` header flags
+const BASIS_FLAG_ETC1S: u64 = 1;
+/// `.basis` slice description flag of the slices holding the alpha of ETC1S, and
+/// of the UASTC slices with transparency
+const BASIS_SLICE_HAS_ALPHA: u64 = 1;
+
+/// Size of the KTX2 header and of each of its level index entries
+const KTX2_HEADER_LEN: usize = 80;
+const KTX2_LEVEL_INDEX_LEN: usize = 24;
+
+/// KTX2 supercompression schemes
+const KTX2_SS_NONE: u32 = 0;
+const KTX2_SS_BASISLZ: u32 = 1;
+
+/// Data format descriptors of the four flavors, as written by the basisu tool. The
+/// transfer function and UASTC channels are fixed up once the file is known.
+const ETC1S_DFD: [u8; 44] = [
+    0x2C, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0x0, 0x28, 0x0, 0xA3, 0x1, 0x2, 0x0, 0x3, 0x3,
+    0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x3F, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+    0x0, 0x0, 0x0, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+const ETC1S_ALPHA_DFD: [u8; 60] = [
+    0x3C, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0x0, 0x38, 0x0, 0xA3, 0x1, 0x2, 0x0, 0x3, 0x3,
+    0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x3F, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+    0x0, 0x0, 0x0, 0xFF, 0xFF, 0xFF, 0xFF, 0x40, 0x0, 0x3F, 0xF, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+    0x0, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+const UASTC_DFD: [u8; 44] = [
+    0x2C, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0x0, 0x28, 0x0, 0xA6, 0x1, 0x2, 0x0, 0x3, 0x3,
+    0x0, 0x0, 0x10, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x7F, 0x4, 0x0, 0x0, 0x0, 0x0,
+    0x0, 0x0, 0x0, 0x0, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+/// UASTC channel ids of the first DFD sample
+const UASTC_CHANNEL_RGB: u32 = 0;
+const UASTC_CHANNEL_RGBA: u32 = 3;
+
+/// Repackage the `.basis` file of a single 2D texture, with or without mipmaps,
+/// as a KTX2 file: ETC1S slices become BasisLZ supercompressed levels sharing the
+/// codebooks as global data, UASTC slices are stored as they are. This is what the
+/// basisu tool does, which the bindings don't expose. `srgb` is the transfer
+/// function of the texture, which the `.basis` header of UASTC files doesn't tell:
+/// Basis Universal flags them sRGB whatever they hold.
+pub fn basis_to_ktx2(basis: &[u8], srgb: bool, writer: &str) -> Result<Vec<u8>, Error> {
+    let invalid =
+        |what: &str| Error::new(ErrorKind::InvalidData, format!("Invalid .basis {}", what));
+    let read = |offset: usize, len: usize| {
+        basis
+            .get(offset..offset + len)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .rev()
+                    .fold(0u64, |value, byte| (value << 8) | u64::from(*byte))
+            })
+            .ok_or_else(|| invalid("header"))
+    };
+    let section = |offset: u64, len: u64| {
+        basis
+            .get(offset as usize..(offset + len) as usize)
+            .ok_or_else(|| invalid("section"))
+    };
+
+    if basis.len() < BASIS_HEADER_LEN || read(0, 2)? != u64::from(u16::from_le_bytes(*b"sB")) {
+        return Err(invalid("header"));
+    }
+    let total_slices = read(14, 3)? as usize;
+    let total_images = read(17, 3)?;
+    let flags = read(21, 2)?;
+    let etc1s = flags & BASIS_FLAG_ETC1S != 0;
+    if total_images != 1 || read(23, 1)? != 0 {
+        return Err(invalid("texture, only single 2D textures are supported"));
+    }
+
+    struct Slice<'a> {
+        level: usize,
+        alpha: bool,
+        width: u32,
+        height: u32,
+        data: &'a [u8],
+    }
+    let slice_descs = read(65, 4)? as usize;
+    let slices = (0..total_slices)
+        .map(|index| {
+            let desc = slice_descs + index * BASIS_SLICE_DESC_LEN;
+            Ok(Slice {
+                level: read(desc + 3, 1)? as usize,
+                alpha: read(desc + 4, 1)? & BASIS_SLICE_HAS_ALPHA != 0,
+                width: read(desc + 5, 2)? as u32,
+                height: read(desc + 7, 2)? as u32,
+                data: section(read(desc + 13, 4)?, read(desc + 17, 4)?)?,
+            })
+        })
+        .collect::<Result<Vec<Slice>, Error>>()?;
+    let base = slices
+        .iter()
+        .find(|slice| slice.level == 0)
+        .ok_or_else(|| invalid("texture, it has no base level"))?;
+    // UASTC slices of textures with transparency are flagged too
+    let has_alpha = slices.iter().any(|slice| slice.alpha);
+    let level_count = slices
+        .iter()
+        .map(|slice| slice.level + 1)
+        .max()
+        .unwrap_or(1);
+
+    // Slices of a level one after the other, RGB then alpha for ETC1S
+    let mut levels: Vec<Vec<u8>> = vec![Vec::new(); level_count];
+    // Offsets and lengths of the RGB and alpha slices in their level, for the
+    // ETC1S image descriptions
+    let mut image_descs = vec![[0u32; 5]; level_count];
+    for slice in &slices {
+        let level = &mut levels[slice.level];
+        let (offset, length) = (level.len() as u32, slice.data.len() as u32);
+        let desc = &mut image_descs[slice.level];
+        if etc1s && slice.alpha {
+            desc[3] = offset;
+            desc[4] = length;
+        } else {
+            desc[1] = offset;
+            desc[2] = length;
+        }
+        level.extend_from_slice(slice.data);
+    }
+
+    let mut global_data = Vec::new();
+    if etc1s {
+        let endpoints = section(read(41, 4)?, read(45, 3)?)?;
+        let selectors = section(read(50, 4)?, read(54, 3)?)?;
+        let tables = section(read(57, 4)?, read(61, 4)?)?;
+        global_data.extend_from_slice(&(read(39, 2)? as u16).to_le_bytes());
+        global_data.extend_from_slice(&(read(48, 2)? as u16).to_le_bytes());
+        for len in [endpoints.len(), selectors.len(), tables.len(), 0].iter() {
+            global_data.extend_from_slice(&(*len as u32).to_le_bytes());
+        }
+        for desc in &image_descs {
+            for value in desc.iter() {
+                global_data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        global_data.extend_from_slice(endpoints);
+        global_data.extend_from_slice(selectors);
+        global_data.extend_from_slice(tables);
+    }
+    let supercompression = if etc1s { KTX2_SS_BASISLZ } else { KTX2_SS_NONE };
+
+    let mut dfd = match (etc1s, has_alpha) {
+        (true, true) => ETC1S_ALPHA_DFD.to_vec(),
+        (true, false) => ETC1S_DFD.to_vec(),
+        (false, _) => UASTC_DFD.to_vec(),
+    };
+    let transfer = if srgb { 2 } else { 1 };
+    update_u32(&mut dfd, 12, |bits| {
+        (bits & !(0xFF << 16)) | (transfer << 16)
+    });
+    if supercompression != KTX2_SS_NONE {
+        // Supercompressed data has no fixed bytes per plane
+        update_u32(&mut dfd, 20, |bits| bits & !0xFF);
+    }
+    if !etc1s {
+        let channel = if has_alpha {
+            UASTC_CHANNEL_RGBA
+        } else {
+            UASTC_CHANNEL_RGB
+        };
+        update_u32(&mut dfd, 28, |bits| (bits & !(0xF << 24)) | (channel << 24));
+    }
+
+    let mut key_values = Vec::new();
+    let entry = [b"KTXwriter\0".as_ref(), writer.as_bytes(), b"\0"].concat();
+    key_values.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+    key_values.extend_from_slice(&entry);
+    pad(&mut key_values, 4);
+
+    let mut ktx2 = vec![0; KTX2_HEADER_LEN + KTX2_LEVEL_INDEX_LEN * level_count];
+    let dfd_offset = ktx2.len();
+    ktx2.extend_from_slice(&dfd);
+    let kvd_offset = ktx2.len();
+    ktx2.extend_from_slice(&key_values);
+    let mut sgd_offset = 0;
+    if !global_data.is_empty() {
+        pad(&mut ktx2, 8);
+        sgd_offset = ktx2.len();
+        ktx2.extend_from_slice(&global_data);
+    }
+    if supercompression == KTX2_SS_NONE {
+        // Levels start on a UASTC block
+        pad(&mut ktx2, 16);
+    }
+
+    // Smallest level first, so the file can be streamed
+    let mut level_index = vec![(0, 0, 0); level_count];
+    for (level, data) in levels.iter().enumerate().rev() {
+        let uncompressed = if etc1s { 0 } else { data.len() };
+        level_index[level] = (ktx2.len(), data.len(), uncompressed);
+        ktx2.extend_from_slice(data);
+    }
+
+    let header: Vec<u8> = [
+        &KTX2_IDENTIFIER[..],
+        &0u32.to_le_bytes(), // vkFormat, undefined for Basis Universal
+        &1u32.to_le_bytes(), // typeSize
+        &base.width.to_le_bytes(),
+        &base.height.to_le_bytes(),
+        &0u32.to_le_bytes(), // pixelDepth
+        &0u32.to_le_bytes(), // layerCount
+        &1u32.to_le_bytes(), // faceCount
+        &to_u32(level_count)?.to_le_bytes(),
+        &supercompression.to_le_bytes(),
+        &to_u32(dfd_offset)?.to_le_bytes(),
+        &to_u32(dfd.len())?.to_le_bytes(),
+        &to_u32(kvd_offset)?.to_le_bytes(),
+        &to_u32(key_values.len())?.to_le_bytes(),
+        &(sgd_offset as u64).to_le_bytes(),
+        &(global_data.len() as u64).to_le_bytes(),
+    ]
+    .concat();
+    ktx2[..KTX2_HEADER_LEN].copy_from_slice(&header);
+    for (level, (offset, length, uncompressed)) in level_index.into_iter().enumerate() {
+        let entry = KTX2_HEADER_LEN + level * KTX2_LEVEL_INDEX_LEN;
+        ktx2[entry..entry + 8].copy_from_slice(&(offset as u64).to_le_bytes());
+        ktx2[entry + 8..entry + 16].copy_from_slice(&(length as u64).to_le_bytes());
+        ktx2[entry + 16..entry + 24].copy_from_slice(&(uncompressed as u64).to_le_bytes());
+    }
+
+    Ok(ktx2)
+}
+
+fn update_u32(bytes: &mut [u8], offset: usize, update: impl Fn(u32) -> u32) {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    bytes[offset..offset + 4].copy_from_slice(&update(u32::from_le_bytes(word)).to_le_bytes());
+}
+
+fn pad(bytes: &mut Vec<u8>, alignment: usize) {
+    let padding = (alignment - bytes.len() % alignment) % alignment;
+    bytes.resize(bytes.len() + padding, 0);
+}
+
+fn to_u32(value: usize) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| Error::new(ErrorKind::InvalidData, "KTX2 file too large"))
+}
+
+#[cfg(test)]
+mod basis_to_ktx2_tests {
+    use super::*;
+
+    #[test]
+    fn it_errors_on_truncated_basis_files() {
+        assert!(basis_to_ktx2(b"sB", true, "test").is_err());
+        assert!(basis_to_ktx2(&[0; 77], true, "test").is_err());
+    }
+}
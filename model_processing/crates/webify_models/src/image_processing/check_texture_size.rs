@@ -0,0 +1,68 @@
+//! Flag textures that are too big to be comfortable on the web
+
+use std::{io::Error, path::Path, result::Result};
+
+use crate::image_processing::{read_avif_info, read_ktx2_info, read_webp_info};
+
+/// Largest width or height we expect a web texture to have
+pub const MAX_TEXTURE_DIMENSION: u32 = 4096;
+
+/// Largest width or height every GPU can upload, larger textures fail on some
+pub const WEBGL_MAX_TEXTURE_DIMENSION: u32 = 8192;
+
+/// Check the dimensions of a texture, returning a warning message when it's oversized
+pub fn check_texture_size(path: &Path) -> Result<Option<String>, Error> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    let (width, height) = if extension == Some("avif") {
+        let (width, height, _) = read_avif_info(path)?;
+        (width, height)
+    } else if extension == Some("webp") {
+        let (width, height, _) = read_webp_info(path)?;
+        (width, height)
+    } else if extension == Some("ktx2") {
+        let info = read_ktx2_info(path)?;
+        (info.width, info.height)
+    } else {
+        image::image_dimensions(path).map_err(|e| {
+            Error::other(format!("Could not read dimensions of {:?}: {:?}", path, e))
+        })?
+    };
+
+    if width > WEBGL_MAX_TEXTURE_DIMENSION || height > WEBGL_MAX_TEXTURE_DIMENSION {
+        return Ok(Some(format!(
+            "texture is {}x{}, larger than the {}px some GPUs can upload, see --oversized-textures",
+            width, height, WEBGL_MAX_TEXTURE_DIMENSION
+        )));
+    }
+    if width > MAX_TEXTURE_DIMENSION || height > MAX_TEXTURE_DIMENSION {
+        return Ok(Some(format!(
+            "texture is {}x{}, larger than {}px",
+            width, height, MAX_TEXTURE_DIMENSION
+        )));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod check_texture_size_tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_small_textures() {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("example.jpg");
+        assert_eq!(check_texture_size(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn it_errors_on_non_images() {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("README.md");
+        assert!(check_texture_size(&path).is_err());
+    }
+}
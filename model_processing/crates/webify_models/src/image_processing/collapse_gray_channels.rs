@@ -0,0 +1,83 @@
+//! Write a texture whose color channels are identical as grayscale
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::DynamicImage;
+
+use crate::image_processing::DecodeCache;
+
+/// Rewrite the 8 bit RGB texture in place as 8 bit grayscale when its red, green
+/// and blue are the same in every pixel, as data maps like roughness, occlusion
+/// and masks often are. The pixels are kept exactly, in a third of the channels.
+/// Returns whether it was rewritten.
+pub fn collapse_gray_channels(path: &Path, cache: &mut DecodeCache) -> Result<bool, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to check its channels: {:?}",
+            path, e
+        ))
+    })?;
+
+    let gray = match &img {
+        DynamicImage::ImageRgb8(rgb) if rgb.pixels().all(|p| p[0] == p[1] && p[1] == p[2]) => {
+            DynamicImage::ImageLuma8(image::GrayImage::from_fn(
+                rgb.width(),
+                rgb.height(),
+                |x, y| image::Luma([rgb.get_pixel(x, y)[0]]),
+            ))
+        }
+        _ => {
+            cache.insert(path, img);
+            return Ok(false);
+        }
+    };
+
+    gray.save(path)
+        .map_err(|e| Error::other(format!("Could not save {:?} as grayscale: {:?}", path, e)))?;
+    cache.insert(path, gray);
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod collapse_gray_channels_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ColorType, ImageBuffer, Rgb};
+
+    #[test]
+    fn it_writes_textures_with_identical_channels_as_grayscale() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_collapse_gray_channels");
+        fs::create_dir_all(&dir)?;
+        let occlusion = dir.join("crate_ao.png");
+        let gray = ImageBuffer::from_fn(8, 4, |x, y| Rgb([(x * 30 + y) as u8; 3]));
+        gray.save(&occlusion).map_err(Error::other)?;
+        let tinted = dir.join("crate_diffuse.png");
+        ImageBuffer::from_fn(8, 4, |x, _| Rgb([x as u8 * 30, x as u8 * 30, 200]))
+            .save(&tinted)
+            .map_err(Error::other)?;
+
+        let mut cache = DecodeCache::default();
+        assert!(collapse_gray_channels(&occlusion, &mut cache)?);
+        assert!(!collapse_gray_channels(&tinted, &mut cache)?);
+        assert!(!collapse_gray_channels(
+            &occlusion,
+            &mut DecodeCache::default()
+        )?);
+
+        let collapsed = image::open(&occlusion).map_err(Error::other)?;
+        assert_eq!(collapsed.color(), ColorType::L8);
+        assert_eq!(collapsed.to_rgb8(), gray);
+        assert_eq!(
+            image::open(&tinted).map_err(Error::other)?.color(),
+            ColorType::Rgb8
+        );
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
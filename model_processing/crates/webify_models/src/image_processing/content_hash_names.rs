@@ -0,0 +1,101 @@
+//! Work out the names of the processed textures once named after their contents
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::image_processing::find_mip_levels;
+use crate::report::TextureStats;
+
+/// Where each texture and its mip levels go once named `<sha256>.<ext>` after the
+/// contents of the texture, in the same directory, for CDNs to cache them for
+/// good. Atlases and impostors keep their names, their metadata finds them by it,
+/// and textures already named after their contents are left out. Nothing is
+/// changed on disk.
+pub fn content_hash_names(textures: &[TextureStats]) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    let paths: BTreeSet<&Path> = textures.iter().map(|t| t.path.as_path()).collect();
+
+    let mut renames = Vec::new();
+    for path in paths {
+        if path.with_extension("json").is_file() {
+            continue;
+        }
+        let hash = format!("{:x}", Sha256::digest(fs::read(path)?));
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let renamed = path.with_file_name(format!("{}.{}", hash, extension));
+        if renamed == path {
+            continue;
+        }
+
+        // Levels are named after their texture, `<stem>_mip<level>.png`
+        let stem_len = path.file_stem().map_or(0, |s| s.len());
+        for level in find_mip_levels(path) {
+            let name = level.file_name().unwrap_or_default().to_string_lossy();
+            let renamed_level = level.with_file_name(format!("{}{}", hash, &name[stem_len..]));
+            renames.push((level, renamed_level));
+        }
+        renames.push((path.to_path_buf(), renamed));
+    }
+
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod content_hash_names_tests {
+    use super::*;
+
+    fn texture(path: PathBuf) -> TextureStats {
+        TextureStats {
+            path,
+            model: None,
+            width: 1,
+            height: 1,
+            channels: 3,
+            file_bytes: 1,
+            gpu_bytes: 4,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn it_names_textures_and_their_levels_after_their_contents() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_content_hash_names");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("wood.PNG"), "wood")?;
+        fs::write(dir.join("wood_mip1.png"), "wood level 1")?;
+        fs::write(dir.join("desk_atlas.png"), "atlas")?;
+        fs::write(dir.join("desk_atlas.json"), "{}")?;
+        let hash = format!("{:x}", Sha256::digest(b"wood"));
+        fs::write(dir.join(format!("{}.png", hash)), "wood")?;
+
+        let renames = content_hash_names(&[
+            texture(dir.join("wood.PNG")),
+            texture(dir.join("desk_atlas.png")),
+            texture(dir.join(format!("{}.png", hash))),
+        ])?;
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(
+            renames,
+            vec![
+                (
+                    dir.join("wood_mip1.png"),
+                    dir.join(format!("{}_mip1.png", hash))
+                ),
+                (dir.join("wood.PNG"), dir.join(format!("{}.png", hash))),
+            ]
+        );
+        Ok(())
+    }
+}
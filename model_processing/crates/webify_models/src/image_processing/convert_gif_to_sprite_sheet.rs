@@ -0,0 +1,156 @@
+//! Convert an animated GIF to a PNG sprite sheet of its frames
+
+use std::{fs, fs::File, io::BufReader, io::Error, result::Result};
+
+use image::{codecs::gif::GifDecoder, imageops, AnimationDecoder, DynamicImage, Frame, RgbaImage};
+
+use crate::image_processing::{DecodeCache, Image, SpriteSheetMetadata};
+use crate::options::Removal;
+use crate::trash::remove_file;
+
+/// Convert the GIF to a PNG of every frame, as composited on the full canvas, in a
+/// grid as close to square as fits them, with its `SpriteSheetMetadata` in a JSON
+/// file of the same name. A GIF of a single frame becomes a plain PNG. The
+/// original is removed as asked.
+pub fn convert_gif_to_sprite_sheet(
+    mut image: Image,
+    removal: &Removal,
+    cache: &mut DecodeCache,
+) -> Result<Image, Error> {
+    let path = image.path.clone();
+    let decode_error = |e| Error::other(format!("Could not decode {:?}: {:?}", path, e));
+    let frames: Vec<Frame> = GifDecoder::new(BufReader::new(File::open(&path)?))
+        .and_then(|decoder| decoder.into_frames().collect_frames())
+        .map_err(decode_error)?;
+    let first = frames
+        .first()
+        .ok_or_else(|| Error::other(format!("{:?} has no frames", path)))?;
+    let (width, height) = first.buffer().dimensions();
+
+    let count = frames.len() as u32;
+    let columns = (count as f64).sqrt().ceil() as u32;
+    let rows = count.div_ceil(columns);
+    let mut sheet = RgbaImage::new(width * columns, height * rows);
+    for (index, frame) in frames.iter().enumerate() {
+        let index = index as u32;
+        imageops::replace(
+            &mut sheet,
+            frame.buffer(),
+            index % columns * width,
+            index / columns * height,
+        );
+    }
+
+    let png_path = path.with_extension("png");
+    let sheet = DynamicImage::ImageRgba8(sheet);
+    sheet
+        .save(&png_path)
+        .map_err(|e| Error::other(format!("Could not convert {:?} to PNG: {:?}", path, e)))?;
+    if count > 1 {
+        let metadata = SpriteSheetMetadata {
+            columns,
+            rows,
+            frames: count,
+            delays: frames
+                .iter()
+                .map(|frame| {
+                    let (numerator, denominator) = frame.delay().numer_denom_ms();
+                    numerator / denominator.max(1)
+                })
+                .collect(),
+        };
+        fs::write(
+            path.with_extension("json"),
+            serde_json::to_string_pretty(&metadata)?,
+        )?;
+    }
+
+    remove_file(&path, removal)?;
+    cache.insert(&png_path, sheet);
+    image.path = png_path;
+    image.extension = String::from("png");
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod convert_gif_to_sprite_sheet_tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use image::{codecs::gif::GifEncoder, Delay, GenericImageView, Rgba};
+
+    fn write_gif(path: &Path, colors: &[[u8; 4]]) -> Result<(), Error> {
+        let frames = colors.iter().map(|color| {
+            Frame::from_parts(
+                RgbaImage::from_pixel(4, 2, Rgba(*color)),
+                0,
+                0,
+                Delay::from_numer_denom_ms(80, 1),
+            )
+        });
+        GifEncoder::new(File::create(path)?)
+            .encode_frames(frames)
+            .map_err(Error::other)
+    }
+
+    #[test]
+    fn it_lays_out_every_frame_in_a_grid() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_convert_gif_to_sprite_sheet");
+        fs::create_dir_all(&dir)?;
+        write_gif(
+            &dir.join("torch.gif"),
+            &[[200, 0, 0, 255], [0, 200, 0, 255], [0, 0, 200, 255]],
+        )?;
+        write_gif(&dir.join("sign.gif"), &[[200, 0, 0, 255]])?;
+
+        let mut cache = DecodeCache::default();
+        let sheet = convert_gif_to_sprite_sheet(
+            Image {
+                path: dir.join("torch.gif"),
+                extension: String::from("gif"),
+            },
+            &Removal::Delete,
+            &mut cache,
+        )?;
+        assert_eq!(sheet.path, dir.join("torch.png"));
+        assert!(!dir.join("torch.gif").exists());
+        assert_eq!(cache.len(), 1);
+        let png = image::open(dir.join("torch.png")).unwrap();
+        assert_eq!(png.dimensions(), (8, 4));
+        assert_eq!(png.get_pixel(5, 0), Rgba([0, 200, 0, 255]));
+        assert_eq!(png.get_pixel(1, 3), Rgba([0, 0, 200, 255]));
+        assert_eq!(png.get_pixel(5, 3), Rgba([0, 0, 0, 0]));
+        let metadata: SpriteSheetMetadata =
+            serde_json::from_str(&fs::read_to_string(dir.join("torch.json"))?)?;
+        assert_eq!(
+            metadata,
+            SpriteSheetMetadata {
+                columns: 2,
+                rows: 2,
+                frames: 3,
+                delays: vec![80, 80, 80],
+            }
+        );
+
+        convert_gif_to_sprite_sheet(
+            Image {
+                path: dir.join("sign.gif"),
+                extension: String::from("gif"),
+            },
+            &Removal::Delete,
+            &mut cache,
+        )?;
+        assert_eq!(
+            image::open(dir.join("sign.png")).unwrap().dimensions(),
+            (4, 2)
+        );
+        assert!(!dir.join("sign.json").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -1,41 +1,38 @@
 //! Converts an image file to PNG, or skips if it's already PNG
 
-use std::{fs, io::Error, panic, result::Result};
-
-use image::io::Reader as ImageReader;
-use image::ImageFormat::Tiff;
-
-use crate::image_processing::Image;
-
-/// Convert the specified image to a PNG version
-pub fn convert_to_png(mut image: Image) -> Result<Image, Error> {
-    let Image { ref extension, ref path } = image;
-
-    if extension == "tif" {
-        return Ok(image); // Skip tif!
+use std::{io::Error, result::Result};
+
+use crate::image_processing::{decode_texture, is_decodable, DecodeCache, Image};
+use crate::options::{HdrSettings, Removal};
+use crate::trash::remove_file;
+
+/// Convert the specified image to a PNG version, removing the original as asked. EXR
+/// and HDR images are tonemapped with `hdr`.
+pub fn convert_to_png(
+    mut image: Image,
+    hdr: &HdrSettings,
+    removal: &Removal,
+    cache: &mut DecodeCache,
+) -> Result<Image, Error> {
+    let Image {
+        ref extension,
+        ref path,
+    } = image;
+
+    // Formats added with --extensions that can't be decoded, like KTX2, are left as is
+    if !is_decodable(extension) {
+        return Ok(image);
     }
-    let image_reader = match ImageReader::open(path) {
-        Ok(img) => img,
-        Err(e) => panic!("Failed to open image during PNG conversion: {:?}", e),
-    };
-
-    // Somehow, Tiff conversion is problematic, so we'll skip that
-    if image_reader.format().is_some() && image_reader.format() != Some(Tiff) {
-        let img = match image_reader.decode() {
-            Ok(i) => i,
-            Err(e) => panic!("Failed to open image during PNG conversion: {:?}", e),
-        };
 
-        match img.save(path.with_extension("png")) {
-            Ok(_) => "",
-            Err(e) => panic!("Could not convert {:?} to PNG: {:?}", path, e),
-        };
+    let img = decode_texture(path, hdr)?;
 
-        fs::remove_file(path)?;
-        image.path = path.with_extension("png");
-    } else {
-        panic!("Failed to convert provided image: {:?}", path);
-    }
+    img.save(path.with_extension("png"))
+        .map_err(|e| Error::other(format!("Could not convert {:?} to PNG: {:?}", path, e)))?;
+
+    remove_file(path, removal)?;
+    cache.insert(&path.with_extension("png"), img);
+    image.path = path.with_extension("png");
+    image.extension = String::from("png");
 
     Ok(image)
 }
@@ -44,7 +41,7 @@ pub fn convert_to_png(mut image: Image) -> Result<Image, Error> {
 mod convert_tests {
     use super::*;
 
-    use std::path::Path;
+    use std::{fs, path::Path};
 
     fn setup(test_run_id: &str) -> Result<(), Error> {
         let example_image_path = Path::new("tests").join("image_processing").join("images");
@@ -54,11 +51,11 @@ mod convert_tests {
         fs::create_dir_all(&destination_path)?;
         fs::copy(
             example_image_path.join("example.jpg"),
-            &destination_path.join("example.jpg"),
+            destination_path.join("example.jpg"),
         )?;
         fs::copy(
             example_image_path.join("README.md"),
-            &destination_path.join("README.md"),
+            destination_path.join("README.md"),
         )?;
 
         Ok(())
@@ -89,7 +86,9 @@ mod convert_tests {
             extension: String::from("jpg"),
         };
 
-        convert_to_png(image)?;
+        let mut cache = DecodeCache::default();
+        convert_to_png(image, &HdrSettings::default(), &Removal::Delete, &mut cache)?;
+        assert_eq!(cache.len(), 1);
         // Check that previous test image was deleted
         assert!(!Path::exists(
             &Path::new("tests")
@@ -110,8 +109,78 @@ mod convert_tests {
     }
 
     #[test]
-    fn it_panics_on_non_images() {
-        let test_run_name = "test_run_it_panics_on_non_images";
+    fn it_converts_a_16_bit_tif_to_png() -> Result<(), Error> {
+        let test_run_name = "test_run_it_converts_a_16_bit_tif_to_png";
+        setup(test_run_name)?;
+
+        let test_run_path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_name);
+        let samples: Vec<u16> = (0..4 * 4).map(|i| i * 4000).collect();
+        tiff::encoder::TiffEncoder::new(fs::File::create(test_run_path.join("scan.tif"))?)
+            .and_then(|mut tiff| {
+                tiff.write_image::<tiff::encoder::colortype::Gray16>(4, 4, &samples)
+            })
+            .map_err(Error::other)?;
+
+        let image = Image {
+            path: test_run_path.join("scan.tif"),
+            extension: String::from("tif"),
+        };
+        let converted = convert_to_png(
+            image,
+            &HdrSettings::default(),
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+        assert_eq!(converted.path, test_run_path.join("scan.png"));
+        assert!(!test_run_path.join("scan.tif").exists());
+        let png = image::open(test_run_path.join("scan.png")).unwrap();
+        assert_eq!(png.as_luma16().unwrap().as_raw(), &samples);
+
+        teardown(test_run_name)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_a_dds_to_png() -> Result<(), Error> {
+        let test_run_name = "test_run_it_converts_a_dds_to_png";
+        setup(test_run_name)?;
+
+        let test_run_path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_name);
+        // A 4x4 DXT1 block of red
+        let mut dds = vec![0u8; 128];
+        dds[..4].copy_from_slice(b"DDS ");
+        dds[12] = 4;
+        dds[16] = 4;
+        dds[84..88].copy_from_slice(b"DXT1");
+        dds.extend_from_slice(&[0x00, 0xf8, 0x00, 0xf8, 0, 0, 0, 0]);
+        fs::write(test_run_path.join("crate.dds"), dds)?;
+
+        let image = Image {
+            path: test_run_path.join("crate.dds"),
+            extension: String::from("dds"),
+        };
+        let converted = convert_to_png(
+            image,
+            &HdrSettings::default(),
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+        assert_eq!(converted.path, test_run_path.join("crate.png"));
+        assert!(!test_run_path.join("crate.dds").exists());
+        let png = image::open(test_run_path.join("crate.png")).unwrap();
+        assert_eq!(png.as_rgb8().unwrap().get_pixel(3, 3).0, [255, 0, 0]);
+
+        teardown(test_run_name)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_non_images() {
+        let test_run_name = "test_run_it_errors_on_non_images";
         setup(test_run_name).unwrap();
 
         let test_image_path = Path::new("tests")
@@ -125,13 +194,42 @@ mod convert_tests {
             extension: String::from("jpg"),
         };
 
-        // Catch the panic here so we can teardown after, otherwise
-        // just using should_panic will leave use with no teardown
-        let result = panic::catch_unwind(|| {
-            convert_to_png(non_image).unwrap();
-        });
+        let result = convert_to_png(
+            non_image,
+            &HdrSettings::default(),
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        );
         assert!(result.is_err());
+        // The original must be left alone when conversion fails
+        assert!(Path::exists(
+            &Path::new("tests")
+                .join("image_processing")
+                .join(test_run_name)
+                .join("README.md")
+        ));
 
         teardown(test_run_name).unwrap();
     }
+
+    #[test]
+    fn it_leaves_undecodable_formats_as_is() -> Result<(), Error> {
+        let image = Image {
+            path: Path::new("tests")
+                .join("image_processing")
+                .join("texture.ktx2"),
+            extension: String::from("ktx2"),
+        };
+
+        assert_eq!(
+            convert_to_png(
+                image.clone(),
+                &HdrSettings::default(),
+                &Removal::Delete,
+                &mut DecodeCache::default()
+            )?,
+            image
+        );
+        Ok(())
+    }
 }
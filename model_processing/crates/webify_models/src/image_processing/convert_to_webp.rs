@@ -0,0 +1,166 @@
+//! Convert a PNG texture to lossy or lossless WebP
+
+use std::{fs, io::Error, result::Result};
+
+use image::GenericImageView;
+use webp::Encoder;
+
+use crate::image_processing::{DecodeCache, Image};
+use crate::options::{Removal, WebpSettings};
+use crate::provenance::sidecar_path;
+use crate::trash::remove_file;
+
+/// Encode the PNG texture to WebP and replace the PNG with it, removing the PNG as
+/// asked. The PNG is kept when libwebp can't encode it, e.g. beyond the 16383px
+/// WebP allows, in which case the reason is returned along with the unchanged
+/// image.
+pub fn convert_to_webp(
+    mut image: Image,
+    settings: &WebpSettings,
+    removal: &Removal,
+    cache: &mut DecodeCache,
+) -> Result<(Image, Option<String>), Error> {
+    if !(1..=100).contains(&settings.quality) {
+        return Err(Error::other(format!(
+            "Invalid WebP settings {:?}, quality goes from 1 to 100.",
+            settings
+        )));
+    }
+
+    let decoded = cache
+        .take_or_open(&image.path)
+        .map_err(|e| Error::other(format!("Could not open {:?}: {:?}", image.path, e)))?;
+    let (width, height) = decoded.dimensions();
+    // Opaque textures are encoded without an alpha channel, which lossy WebP
+    // would otherwise store alongside
+    let (pixels, has_alpha) = if decoded.color().has_alpha() {
+        (decoded.to_rgba8().into_raw(), true)
+    } else {
+        (decoded.to_rgb8().into_raw(), false)
+    };
+    let encoder = if has_alpha {
+        Encoder::from_rgba(&pixels, width, height)
+    } else {
+        Encoder::from_rgb(&pixels, width, height)
+    };
+
+    let webp = match encoder.encode_simple(settings.lossless, settings.quality as f32) {
+        Ok(webp) => webp,
+        Err(e) => {
+            return Ok((
+                image,
+                Some(format!("kept as PNG, WebP encoding failed: {:?}", e)),
+            ))
+        }
+    };
+
+    let webp_path = image.path.with_extension("webp");
+    fs::write(&webp_path, &*webp)?;
+    remove_file(&image.path, removal)?;
+    // A previous run may have marked the PNG
+    if sidecar_path(&image.path).is_file() {
+        fs::remove_file(sidecar_path(&image.path))?;
+    }
+    image.path = webp_path;
+    image.extension = String::from("webp");
+
+    Ok((image, None))
+}
+
+#[cfg(test)]
+mod convert_to_webp_tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use crate::image_processing::{decode_webp, read_webp_info};
+
+    fn write_example_png(dir: &Path) -> Result<Image, Error> {
+        fs::create_dir_all(dir)?;
+        image::open(
+            Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg"),
+        )
+        .unwrap()
+        .save(dir.join("example.png"))
+        .unwrap();
+
+        Ok(Image {
+            path: dir.join("example.png"),
+            extension: String::from("png"),
+        })
+    }
+
+    #[test]
+    fn it_replaces_the_png_with_a_lossy_webp() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_convert_to_webp");
+        let image = write_example_png(&dir)?;
+
+        let (converted, fallback) = convert_to_webp(
+            image,
+            &WebpSettings::default(),
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(fallback, None);
+        assert_eq!(converted.path, dir.join("example.webp"));
+        assert_eq!(converted.extension, "webp");
+        assert_eq!(read_webp_info(&converted.path)?.2, 3);
+        assert!(!dir.join("example.png").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_the_pixels_when_lossless() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_convert_to_webp_lossless");
+        let image = write_example_png(&dir)?;
+        let png = image::open(&image.path).unwrap().to_rgb8();
+
+        let settings = WebpSettings {
+            lossless: true,
+            ..WebpSettings::default()
+        };
+        let (converted, fallback) = convert_to_webp(
+            image,
+            &settings,
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(fallback, None);
+        let decoded = decode_webp(&converted.path)?;
+        assert_eq!(decoded.to_rgb8().into_raw(), png.into_raw());
+        assert!(!dir.join("example.png").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_out_of_range_settings() {
+        let image = Image {
+            path: Path::new("tests").join("missing.png"),
+            extension: String::from("png"),
+        };
+        let settings = WebpSettings {
+            quality: 101,
+            lossless: false,
+        };
+        assert!(convert_to_webp(
+            image,
+            &settings,
+            &Removal::Delete,
+            &mut DecodeCache::default()
+        )
+        .is_err());
+    }
+}
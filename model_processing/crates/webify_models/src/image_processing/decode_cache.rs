@@ -0,0 +1,133 @@
+//! Keep decoded textures in memory between the stages of a run
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use image::{DynamicImage, ImageResult};
+
+/// Default memory budget for decoded textures, 512 MiB
+pub const DEFAULT_DECODE_CACHE_BUDGET: u64 = 512 * 1024 * 1024;
+
+/// Decoded textures, keyed by the path of the file they were decoded from or written
+/// to. Every stage given the cache takes its texture out of it, decoding the file
+/// only when it isn't kept, and puts back what it leaves on disk for the next stage,
+/// instead of every stage decoding the file the previous one just wrote. The oldest
+/// entries are evicted once the budget is exceeded, and textures larger than the
+/// whole budget are never kept, the stages decode them from disk instead.
+#[derive(Clone)]
+pub struct DecodeCache {
+    budget: u64,
+    used: u64,
+    entries: VecDeque<(PathBuf, DynamicImage)>,
+}
+
+impl DecodeCache {
+    pub fn new(budget: u64) -> DecodeCache {
+        DecodeCache {
+            budget,
+            used: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Keep the decoded image of the file at `path`, replacing any previous one
+    pub fn insert(&mut self, path: &Path, image: DynamicImage) {
+        self.take(path);
+        let size = image.as_bytes().len() as u64;
+        if size > self.budget {
+            return;
+        }
+        while self.used + size > self.budget {
+            match self.entries.pop_front() {
+                Some((_, evicted)) => self.used -= evicted.as_bytes().len() as u64,
+                None => break,
+            }
+        }
+        self.used += size;
+        self.entries.push_back((path.to_path_buf(), image));
+    }
+
+    /// Remove and return the decoded image of the file at `path`, if it's kept
+    pub fn take(&mut self, path: &Path) -> Option<DynamicImage> {
+        let index = self.entries.iter().position(|(p, _)| p == path)?;
+        let (_, image) = self.entries.remove(index)?;
+        self.used -= image.as_bytes().len() as u64;
+        Some(image)
+    }
+
+    /// Take the decoded image of the file at `path`, decoding the file when it isn't kept
+    pub fn take_or_open(&mut self, path: &Path) -> ImageResult<DynamicImage> {
+        match self.take(path) {
+            Some(image) => Ok(image),
+            None => image::open(path),
+        }
+    }
+
+    /// Drop every decoded image
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+
+    /// Number of decoded images kept
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for DecodeCache {
+    fn default() -> DecodeCache {
+        DecodeCache::new(DEFAULT_DECODE_CACHE_BUDGET)
+    }
+}
+
+#[cfg(test)]
+mod decode_cache_tests {
+    use super::*;
+
+    use image::{ImageBuffer, Rgba};
+
+    fn square(size: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(ImageBuffer::from_pixel(size, size, Rgba([1, 2, 3, 4])))
+    }
+
+    #[test]
+    fn it_hands_back_kept_images_once() {
+        let mut cache = DecodeCache::default();
+        cache.insert(Path::new("a.png"), square(4));
+
+        assert_eq!(
+            cache.take(Path::new("a.png")).map(|image| image.to_bytes()),
+            Some(square(4).to_bytes())
+        );
+        assert!(cache.take(Path::new("a.png")).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn it_evicts_the_oldest_images_over_budget() {
+        // Each 4x4 RGBA image takes 64 bytes
+        let mut cache = DecodeCache::new(128);
+        cache.insert(Path::new("a.png"), square(4));
+        cache.insert(Path::new("b.png"), square(4));
+        cache.insert(Path::new("c.png"), square(4));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.take(Path::new("a.png")).is_none());
+        assert!(cache.take(Path::new("c.png")).is_some());
+    }
+
+    #[test]
+    fn it_skips_images_larger_than_the_budget() {
+        let mut cache = DecodeCache::new(32);
+        cache.insert(Path::new("a.png"), square(4));
+
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! Decompress the block-compressed textures of DirectDraw Surface files
+
+use std::{
+    convert::TryInto,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+
+/// Size of the magic number and header before the pixel data
+const HEADER_LEN: usize = 128;
+/// Size of the extended header of DX10 files, after the header
+const DX10_HEADER_LEN: usize = 20;
+
+/// Largest width or height read, the Direct3D 11 limit
+const MAX_DDS_SIDE: usize = 16_384;
+
+/// Pixel format flag telling the format is uncompressed RGB
+const DDPF_RGB: u32 = 0x40;
+/// Pixel format flag telling uncompressed pixels have an alpha mask
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+
+/// Compression of the pixel data
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DdsFormat {
+    /// DXT1, RGB with optional 1 bit alpha
+    Bc1,
+    /// DXT3, RGB with explicit 4 bit alpha
+    Bc2,
+    /// DXT5, RGB with interpolated alpha
+    Bc3,
+    /// A single channel
+    Bc4,
+    /// Two channels, the X and Y of normal maps
+    Bc5,
+    /// Uncompressed pixels of 3 or 4 bytes, with the masks of each channel
+    Uncompressed { bytes: usize, masks: [u32; 4] },
+}
+
+/// Decode the first mip level of a DDS file: the first face of cube maps, the
+/// first layer of arrays. Supports BC1 to BC5 (DXT1, DXT3, DXT5, ATI1 and ATI2)
+/// and uncompressed 24 and 32 bit pixels.
+pub fn decode_dds(path: &Path) -> Result<DynamicImage, Error> {
+    let bytes = fs::read(path)?;
+    decode_dds_bytes(&bytes).map_err(|e| Error::new(e.kind(), format!("{:?}: {}", path, e)))
+}
+
+/// Decode the first mip level of DDS file contents. BC1, BC2 and BC3 give RGBA, or
+/// RGB when every pixel is opaque, BC4 gives grayscale and BC5 gives an RGB normal
+/// map, its Z computed from the X and Y it stores.
+pub fn decode_dds_bytes(bytes: &[u8]) -> Result<DynamicImage, Error> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != b"DDS " {
+        return Err(invalid("not a DDS file"));
+    }
+    let height = u32_at(bytes, 12) as usize;
+    let width = u32_at(bytes, 16) as usize;
+    if width == 0 || height == 0 || width > MAX_DDS_SIDE || height > MAX_DDS_SIDE {
+        return Err(invalid(&format!(
+            "unsupported size of {}x{}",
+            width, height
+        )));
+    }
+
+    let flags = u32_at(bytes, 80);
+    let four_cc = &bytes[84..88];
+    let (format, data_offset) = match four_cc {
+        b"DX10" => {
+            if bytes.len() < HEADER_LEN + DX10_HEADER_LEN {
+                return Err(invalid("unexpected end of file"));
+            }
+            (
+                dxgi_format(u32_at(bytes, HEADER_LEN))?,
+                HEADER_LEN + DX10_HEADER_LEN,
+            )
+        }
+        b"DXT1" => (DdsFormat::Bc1, HEADER_LEN),
+        b"DXT2" | b"DXT3" => (DdsFormat::Bc2, HEADER_LEN),
+        b"DXT4" | b"DXT5" => (DdsFormat::Bc3, HEADER_LEN),
+        b"ATI1" | b"BC4U" => (DdsFormat::Bc4, HEADER_LEN),
+        b"ATI2" | b"BC5U" => (DdsFormat::Bc5, HEADER_LEN),
+        _ if flags & DDPF_RGB != 0 => {
+            let bits = u32_at(bytes, 88);
+            let alpha = match flags & DDPF_ALPHAPIXELS {
+                0 => 0,
+                _ => u32_at(bytes, 104),
+            };
+            let masks = [
+                u32_at(bytes, 92),
+                u32_at(bytes, 96),
+                u32_at(bytes, 100),
+                alpha,
+            ];
+            match bits {
+                24 | 32 => (
+                    DdsFormat::Uncompressed {
+                        bytes: bits as usize / 8,
+                        masks,
+                    },
+                    HEADER_LEN,
+                ),
+                _ => return Err(invalid(&format!("unsupported {} bit pixels", bits))),
+            }
+        }
+        _ => {
+            return Err(invalid(&format!(
+                "unsupported format {:?}",
+                String::from_utf8_lossy(four_cc)
+            )))
+        }
+    };
+    let data = &bytes[data_offset..];
+
+    match format {
+        DdsFormat::Uncompressed { bytes, masks } => {
+            decode_uncompressed(data, width, height, bytes, masks)
+        }
+        _ => decode_blocks(data, width, height, format),
+    }
+}
+
+/// Compression of a DXGI format of the DX10 header
+fn dxgi_format(dxgi: u32) -> Result<DdsFormat, Error> {
+    let rgba = [0xff, 0xff00, 0xff_0000, 0xff00_0000];
+    let bgra = [0xff_0000, 0xff00, 0xff, 0xff00_0000];
+    match dxgi {
+        28 | 29 => Ok(DdsFormat::Uncompressed {
+            bytes: 4,
+            masks: rgba,
+        }),
+        87 | 91 => Ok(DdsFormat::Uncompressed {
+            bytes: 4,
+            masks: bgra,
+        }),
+        70..=72 => Ok(DdsFormat::Bc1),
+        73..=75 => Ok(DdsFormat::Bc2),
+        76..=78 => Ok(DdsFormat::Bc3),
+        79 | 80 => Ok(DdsFormat::Bc4),
+        82 | 83 => Ok(DdsFormat::Bc5),
+        _ => Err(invalid(&format!("unsupported DXGI format {}", dxgi))),
+    }
+}
+
+/// Decompress the 4x4 blocks of the image, cropping the blocks past its edges
+fn decode_blocks(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    format: DdsFormat,
+) -> Result<DynamicImage, Error> {
+    let block_len = match format {
+        DdsFormat::Bc1 | DdsFormat::Bc4 => 8,
+        _ => 16,
+    };
+    let (blocks_wide, blocks_high) = (width.div_ceil(4), height.div_ceil(4));
+    if data.len() < blocks_wide * blocks_high * block_len {
+        return Err(invalid("truncated image data"));
+    }
+
+    let mut pixels = vec![[0u8, 0, 0, 255]; width * height];
+    for (index, block) in data
+        .chunks_exact(block_len)
+        .take(blocks_wide * blocks_high)
+        .enumerate()
+    {
+        let texels = match format {
+            DdsFormat::Bc1 => decode_color_block(block, true),
+            DdsFormat::Bc2 => {
+                let mut texels = decode_color_block(&block[8..], false);
+                let alphas = u64::from_le_bytes(block[..8].try_into().unwrap());
+                for (i, texel) in texels.iter_mut().enumerate() {
+                    texel[3] = ((alphas >> (4 * i)) & 0xf) as u8 * 17;
+                }
+                texels
+            }
+            DdsFormat::Bc3 => {
+                let mut texels = decode_color_block(&block[8..], false);
+                for (texel, alpha) in texels
+                    .iter_mut()
+                    .zip(decode_channel_block(&block[..8]).iter())
+                {
+                    texel[3] = *alpha;
+                }
+                texels
+            }
+            DdsFormat::Bc4 => {
+                let mut texels = [[0, 0, 0, 255]; 16];
+                for (texel, red) in texels.iter_mut().zip(decode_channel_block(block).iter()) {
+                    texel[0] = *red;
+                }
+                texels
+            }
+            _ => {
+                let mut texels = [[0, 0, 0, 255]; 16];
+                let reds = decode_channel_block(&block[..8]);
+                let greens = decode_channel_block(&block[8..]);
+                for (i, texel) in texels.iter_mut().enumerate() {
+                    *texel = normal(reds[i], greens[i]);
+                }
+                texels
+            }
+        };
+
+        let (block_x, block_y) = (index % blocks_wide * 4, index / blocks_wide * 4);
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            if x < width && y < height {
+                pixels[y * width + x] = *texel;
+            }
+        }
+    }
+
+    let (width, height) = (width as u32, height as u32);
+    let image = match format {
+        DdsFormat::Bc4 => GrayImage::from_raw(width, height, pixels.iter().map(|p| p[0]).collect())
+            .map(DynamicImage::ImageLuma8),
+        DdsFormat::Bc5 => {
+            RgbImage::from_raw(width, height, rgb(&pixels)).map(DynamicImage::ImageRgb8)
+        }
+        _ if pixels.iter().all(|p| p[3] == 255) => {
+            RgbImage::from_raw(width, height, rgb(&pixels)).map(DynamicImage::ImageRgb8)
+        }
+        _ => RgbaImage::from_raw(width, height, pixels.concat()).map(DynamicImage::ImageRgba8),
+    };
+
+    image.ok_or_else(|| invalid("truncated image data"))
+}
+
+/// Read uncompressed pixels of `bytes` bytes, each channel picked by its mask
+fn decode_uncompressed(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    bytes: usize,
+    masks: [u32; 4],
+) -> Result<DynamicImage, Error> {
+    // Rows are padded to a multiple of 4 bytes
+    let pitch = (width * bytes).div_ceil(4) * 4;
+    if data.len() < pitch * (height - 1) + width * bytes {
+        return Err(invalid("truncated image data"));
+    }
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let offset = y * pitch + x * bytes;
+            let mut value = [0u8; 4];
+            value[..bytes].copy_from_slice(&data[offset..offset + bytes]);
+            let value = u32::from_le_bytes(value);
+            let channel = |mask: u32| match mask {
+                0 => 255,
+                _ => {
+                    let max = mask >> mask.trailing_zeros();
+                    (u64::from((value & mask) >> mask.trailing_zeros()) * 255 / u64::from(max))
+                        as u8
+                }
+            };
+            pixels.push([
+                channel(masks[0]),
+                channel(masks[1]),
+                channel(masks[2]),
+                channel(masks[3]),
+            ]);
+        }
+    }
+
+    let (width, height) = (width as u32, height as u32);
+    let image = match masks[3] {
+        0 => RgbImage::from_raw(width, height, rgb(&pixels)).map(DynamicImage::ImageRgb8),
+        _ => RgbaImage::from_raw(width, height, pixels.concat()).map(DynamicImage::ImageRgba8),
+    };
+    image.ok_or_else(|| invalid("truncated image data"))
+}
+
+/// Texels of a BC1 color block, in rows. Blocks whose first color isn't larger
+/// than the second have 3 colors and, for BC1 alone, transparent black.
+fn decode_color_block(block: &[u8], bc1: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: u16, wb: u16| {
+        let channel = |i: usize| ((u16::from(a[i]) * wa + u16::from(b[i]) * wb) / (wa + wb)) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+    let palette = match c0 > c1 || !bc1 {
+        true => [a, b, mix(2, 1), mix(1, 2)],
+        false => [a, b, mix(1, 1), [0, 0, 0, 0]],
+    };
+
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    let mut texels = [[0u8; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[((indices >> (2 * i)) & 0b11) as usize];
+    }
+    texels
+}
+
+/// Values of a BC4 block, the alpha block of BC3, in rows
+fn decode_channel_block(block: &[u8]) -> [u8; 16] {
+    let (v0, v1) = (u32::from(block[0]), u32::from(block[1]));
+    let mut palette = [0u8; 8];
+    palette[0] = block[0];
+    palette[1] = block[1];
+    if v0 > v1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * v0 + i as u32 * v1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * v0 + i as u32 * v1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    let mut values = [0u8; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * i)) & 0b111) as usize];
+    }
+    values
+}
+
+/// Normal map texel of the X and Y a BC5 block stores, with the Z of a unit normal
+fn normal(x: u8, y: u8) -> [u8; 4] {
+    let (nx, ny) = (f32::from(x) / 127.5 - 1.0, f32::from(y) / 127.5 - 1.0);
+    let nz = (1.0 - nx * nx - ny * ny).max(0.0).sqrt();
+    [x, y, ((nz + 1.0) * 127.5).round() as u8, 255]
+}
+
+/// Expand a 5:6:5 color to 8 bits per channel
+fn rgb565(color: u16) -> [u8; 4] {
+    let (r, g, b) = (
+        u32::from(color >> 11),
+        u32::from((color >> 5) & 0x3f),
+        u32::from(color & 0x1f),
+    );
+    [
+        ((r * 255 + 15) / 31) as u8,
+        ((g * 255 + 31) / 63) as u8,
+        ((b * 255 + 15) / 31) as u8,
+        255,
+    ]
+}
+
+fn rgb(pixels: &[[u8; 4]]) -> Vec<u8> {
+    pixels.iter().flat_map(|p| p[..3].to_vec()).collect()
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod decode_dds_tests {
+    use super::*;
+
+    use image::GenericImageView;
+
+    /// DDS file of the size, with the four-CC, then the data
+    fn dds(width: u32, height: u32, four_cc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_LEN];
+        bytes[..4].copy_from_slice(b"DDS ");
+        bytes[4..8].copy_from_slice(&124u32.to_le_bytes());
+        bytes[12..16].copy_from_slice(&height.to_le_bytes());
+        bytes[16..20].copy_from_slice(&width.to_le_bytes());
+        bytes[76..80].copy_from_slice(&32u32.to_le_bytes());
+        bytes[80..84].copy_from_slice(&4u32.to_le_bytes());
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// BC1 block of pure red and pure blue, red on the left half
+    const RED_BLUE: [u8; 8] = [0x00, 0xf8, 0x1f, 0x00, 0x50, 0x50, 0x50, 0x50];
+
+    #[test]
+    fn it_decodes_bc1_and_crops_to_the_size() {
+        let blocks: Vec<u8> = RED_BLUE.iter().cycle().take(8 * 4).copied().collect();
+
+        let image = decode_dds_bytes(&dds(6, 5, b"DXT1", &blocks)).unwrap();
+
+        assert_eq!(image.dimensions(), (6, 5));
+        let image = image.as_rgb8().unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(3, 0).0, [0, 0, 255]);
+        assert_eq!(image.get_pixel(4, 4).0, [255, 0, 0]);
+    }
+
+    #[test]
+    fn it_decodes_bc1_transparency() {
+        // The first color isn't larger, index 3 is transparent
+        let block = [0x1f, 0x00, 0x00, 0xf8, 0xff, 0x00, 0x00, 0x00];
+
+        let image = decode_dds_bytes(&dds(4, 4, b"DXT1", &block)).unwrap();
+
+        let image = image.as_rgba8().unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(0, 1).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn it_decodes_bc3_alpha() {
+        // Alpha from 255 to 0 over 8 steps: index 0, 1 then 2 to 7
+        let mut block = vec![255, 0];
+        let indices: u64 = (0..16).map(|i| (i as u64 % 8) << (3 * i)).sum();
+        block.extend_from_slice(&indices.to_le_bytes()[..6]);
+        block.extend_from_slice(&RED_BLUE);
+
+        let image = decode_dds_bytes(&dds(4, 4, b"DXT5", &block)).unwrap();
+
+        let image = image.as_rgba8().unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0[3], 0);
+        assert_eq!(image.get_pixel(2, 0).0[3], 218);
+        assert_eq!(image.get_pixel(3, 1).0[3], 36);
+    }
+
+    #[test]
+    fn it_rebuilds_the_z_of_bc5_normals() {
+        // X and Y both at the middle, a normal facing straight out
+        let flat = [128, 128, 0, 0, 0, 0, 0, 0];
+        let block = [flat, flat].concat();
+
+        let image = decode_dds_bytes(&dds(4, 4, b"ATI2", &block)).unwrap();
+
+        assert_eq!(image.as_rgb8().unwrap().get_pixel(2, 2).0, [128, 128, 255]);
+    }
+
+    #[test]
+    fn it_reads_the_dx10_header() {
+        let mut dx10 = vec![0u8; DX10_HEADER_LEN];
+        dx10[..4].copy_from_slice(&72u32.to_le_bytes());
+        dx10.extend_from_slice(&RED_BLUE);
+
+        let image = decode_dds_bytes(&dds(4, 4, b"DX10", &dx10)).unwrap();
+
+        assert_eq!(image.as_rgb8().unwrap().get_pixel(0, 3).0, [255, 0, 0]);
+    }
+
+    #[test]
+    fn it_decodes_uncompressed_pixels() {
+        let mut bytes = dds(2, 1, b"\0\0\0\0", &[10, 20, 30, 40, 50, 60, 70, 80]);
+        bytes[80..84].copy_from_slice(&(DDPF_RGB | DDPF_ALPHAPIXELS).to_le_bytes());
+        bytes[88..92].copy_from_slice(&32u32.to_le_bytes());
+        for (i, mask) in [0xff_0000u32, 0xff00, 0xff, 0xff00_0000].iter().enumerate() {
+            bytes[92 + 4 * i..96 + 4 * i].copy_from_slice(&mask.to_le_bytes());
+        }
+
+        let image = decode_dds_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            image.as_rgba8().unwrap().get_pixel(1, 0).0,
+            [70, 60, 50, 80]
+        );
+    }
+
+    #[test]
+    fn it_rejects_what_it_cant_decode() {
+        assert!(decode_dds_bytes(b"PNG").is_err());
+        assert!(decode_dds_bytes(&dds(4, 4, b"DXT1", &RED_BLUE[..4])).is_err());
+        assert!(decode_dds_bytes(&dds(4, 4, b"BC6H", &RED_BLUE)).is_err());
+        assert!(decode_dds_bytes(&dds(0, 4, b"DXT1", &RED_BLUE)).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Decode the first frame of a GIF texture, animated or not
+
+use std::{fs::File, io::BufReader, io::Error, path::Path, result::Result};
+
+use image::{codecs::gif::GifDecoder, AnimationDecoder, DynamicImage};
+
+/// Decode the first frame of a GIF file, composited on the full canvas like it's
+/// shown before the animation starts, rather than the frame's own rectangle
+pub fn decode_gif(path: &Path) -> Result<DynamicImage, Error> {
+    let decode_error = |e| Error::other(format!("Could not decode {:?}: {:?}", path, e));
+    let decoder = GifDecoder::new(BufReader::new(File::open(path)?)).map_err(decode_error)?;
+    let frame = decoder
+        .into_frames()
+        .next()
+        .ok_or_else(|| Error::other(format!("{:?} has no frames", path)))?
+        .map_err(decode_error)?;
+
+    Ok(DynamicImage::ImageRgba8(frame.into_buffer()))
+}
+
+#[cfg(test)]
+mod decode_gif_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{codecs::gif::GifEncoder, Delay, Frame, GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn it_decodes_the_first_frame_of_an_animation() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_gif");
+        fs::create_dir_all(&dir)?;
+        let frames = [[200, 0, 0, 255], [0, 0, 200, 255]].iter().map(|color| {
+            Frame::from_parts(
+                RgbaImage::from_pixel(4, 3, Rgba(*color)),
+                0,
+                0,
+                Delay::from_numer_denom_ms(100, 1),
+            )
+        });
+        GifEncoder::new(File::create(dir.join("blink.gif"))?)
+            .encode_frames(frames)
+            .map_err(Error::other)?;
+
+        let decoded = decode_gif(&dir.join("blink.gif"))?;
+        assert_eq!(decoded.dimensions(), (4, 3));
+        assert_eq!(decoded.get_pixel(2, 1), Rgba([200, 0, 0, 255]));
+        assert!(decode_gif(
+            &Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg")
+        )
+        .is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
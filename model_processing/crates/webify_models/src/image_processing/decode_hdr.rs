@@ -0,0 +1,202 @@
+//! Decode EXR and Radiance HDR textures, tonemapped down to 8 bits
+
+use std::{
+    fs::File,
+    io::{BufReader, Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use image::{codecs::hdr::HdrDecoder, DynamicImage, RgbImage, RgbaImage};
+
+use crate::options::{HdrSettings, Tonemap};
+
+/// Decode the light of the EXR (first layer, largest resolution) or Radiance HDR
+/// texture, scale it by the exposure and tonemap it to 8 bit sRGB. EXR alpha is
+/// kept, when the file has any.
+pub fn decode_hdr(path: &Path, settings: &HdrSettings) -> Result<DynamicImage, Error> {
+    let is_exr = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("exr"));
+    let (width, height, pixels, has_alpha) = match is_exr {
+        true => read_exr(path)?,
+        false => read_radiance(path)?,
+    };
+
+    let scale = 2f32.powf(settings.exposure);
+    let mut samples = Vec::with_capacity(pixels.len() * 4);
+    for pixel in &pixels {
+        for channel in &pixel[..3] {
+            samples.push(to_srgb8(tonemap(channel * scale, settings.tonemap)));
+        }
+        if has_alpha {
+            samples.push((pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+
+    let image = match has_alpha {
+        true => RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8),
+        false => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+    };
+    image.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?}: truncated image data", path),
+        )
+    })
+}
+
+/// Width, height, linear RGBA pixels and whether the alpha is meaningful
+type LinearImage = (u32, u32, Vec<[f32; 4]>, bool);
+
+/// Linear RGBA pixels of the EXR file
+fn read_exr(path: &Path) -> Result<LinearImage, Error> {
+    let image = exr::prelude::read_first_rgba_layer_from_file(
+        path,
+        |resolution, channels: &exr::prelude::RgbaChannels| {
+            (
+                resolution.width(),
+                resolution.height(),
+                vec![[0f32; 4]; resolution.area()],
+                channels.3.is_some(),
+            )
+        },
+        |(width, _, pixels, _), position, (r, g, b, a): (f32, f32, f32, f32)| {
+            pixels[position.y() * *width + position.x()] = [r, g, b, a];
+        },
+    )
+    .map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not decode {:?}: {}", path, e),
+        )
+    })?;
+
+    let (width, height, pixels, has_alpha) = image.layer_data.channel_data.pixels;
+    Ok((width as u32, height as u32, pixels, has_alpha))
+}
+
+/// Linear RGB pixels of the Radiance HDR file, which has no alpha
+fn read_radiance(path: &Path) -> Result<LinearImage, Error> {
+    let error = |e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not decode {:?}: {:?}", path, e),
+        )
+    };
+    let decoder = HdrDecoder::new(BufReader::new(File::open(path)?)).map_err(error)?;
+    let metadata = decoder.metadata();
+    let pixels = decoder
+        .read_image_hdr()
+        .map_err(error)?
+        .into_iter()
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], 1.0])
+        .collect();
+
+    Ok((metadata.width, metadata.height, pixels, false))
+}
+
+/// Map linear light, 0 and up, to the 0 to 1 range
+fn tonemap(value: f32, tonemap: Tonemap) -> f32 {
+    // Negative light and NaN, which some renderers write, are black
+    let value = if value > 0.0 { value } else { 0.0 };
+    match tonemap {
+        Tonemap::Reinhard => value / (1.0 + value),
+        // Krzysztof Narkowicz's fit of the ACES filmic curve
+        Tonemap::Aces => ((value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14))
+            .clamp(0.0, 1.0),
+    }
+}
+
+/// Encode linear light, 0 to 1, to 8 bit sRGB
+fn to_srgb8(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = match linear <= 0.003_130_8 {
+        true => linear * 12.92,
+        false => 1.055 * linear.powf(1.0 / 2.4) - 0.055,
+    };
+    (encoded * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod decode_hdr_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{codecs::hdr::HdrEncoder, Rgb};
+
+    #[test]
+    fn it_tonemaps_light_into_range() {
+        assert_eq!(tonemap(1.0, Tonemap::Reinhard), 0.5);
+        assert_eq!(tonemap(f32::NAN, Tonemap::Aces), 0.0);
+        assert_eq!(tonemap(-3.0, Tonemap::Reinhard), 0.0);
+        assert_eq!(tonemap(1000.0, Tonemap::Aces), 1.0);
+        assert!(tonemap(1000.0, Tonemap::Reinhard) < 1.0);
+        // ACES has more contrast: darker shadows, brighter highlights
+        assert!(tonemap(0.05, Tonemap::Aces) < tonemap(0.05, Tonemap::Reinhard));
+        assert!(tonemap(2.0, Tonemap::Aces) > tonemap(2.0, Tonemap::Reinhard));
+
+        assert_eq!(to_srgb8(0.0), 0);
+        assert_eq!(to_srgb8(0.22), 129);
+        assert_eq!(to_srgb8(7.0), 255);
+    }
+
+    #[test]
+    fn it_decodes_exr_with_alpha_and_exposure() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_exr");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("sky.exr");
+        exr::prelude::write_rgba_file(&path, 4, 2, |x, _| (x as f32, 1.0f32, 0.0f32, 0.5f32))
+            .map_err(Error::other)?;
+
+        let settings = HdrSettings {
+            tonemap: Tonemap::Reinhard,
+            exposure: 1.0,
+        };
+        let image = decode_hdr(&path, &settings)?;
+
+        let image = image.as_rgba8().unwrap();
+        assert_eq!(image.dimensions(), (4, 2));
+        // 1 doubled by the exposure is 2/3 after Reinhard
+        assert_eq!(
+            image.get_pixel(1, 1).0,
+            [to_srgb8(2.0 / 3.0), to_srgb8(2.0 / 3.0), 0, 128]
+        );
+        assert_eq!(image.get_pixel(0, 0).0[0], 0);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_decodes_radiance_hdr() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_radiance_hdr");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("studio.hdr");
+        let pixels: Vec<Rgb<f32>> = (0..6).map(|i| Rgb([i as f32, 0.5, 0.0])).collect();
+        HdrEncoder::new(File::create(&path)?)
+            .encode(&pixels, 3, 2)
+            .map_err(Error::other)?;
+
+        let image = decode_hdr(&path, &HdrSettings::default())?;
+
+        let image = image.as_rgb8().unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 0).0[0], 0);
+        assert!(image.get_pixel(2, 1).0[0] > image.get_pixel(1, 0).0[0]);
+        assert_eq!(image.get_pixel(1, 1).0[2], 0);
+
+        assert!(decode_hdr(&dir.join("missing.exr"), &HdrSettings::default()).is_err());
+        fs::write(dir.join("broken.hdr"), "#?RADIANCE")?;
+        assert!(decode_hdr(&dir.join("broken.hdr"), &HdrSettings::default()).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
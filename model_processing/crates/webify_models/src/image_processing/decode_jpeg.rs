@@ -0,0 +1,41 @@
+//! Decode a JPEG texture into sRGB
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use image::{DynamicImage, ImageFormat};
+
+use crate::image_processing::{read_jpeg_icc_profile, IccProfile};
+
+/// Decode the JPEG, converting its pixels to sRGB from the colorspace of the ICC
+/// profile it embeds, like Adobe RGB or ProPhoto, which otherwise look washed out
+/// once read as sRGB. JPEGs without a profile, with an sRGB one, or with one that
+/// isn't an RGB matrix profile are decoded as they are.
+pub fn decode_jpeg(path: &Path) -> Result<DynamicImage, Error> {
+    let jpeg = fs::read(path)?;
+    let image = image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg)
+        .map_err(|e| Error::other(format!("Could not decode {:?}: {:?}", path, e)))?;
+
+    match read_jpeg_icc_profile(&jpeg).and_then(|profile| IccProfile::parse(&profile).ok()) {
+        Some(profile) if !profile.is_srgb() => Ok(profile.convert_to_srgb(image)),
+        _ => Ok(image),
+    }
+}
+
+#[cfg(test)]
+mod decode_jpeg_tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_a_jpeg_without_a_profile_as_it_is() -> Result<(), Error> {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("example.jpg");
+
+        let decoded = decode_jpeg(&path)?;
+
+        assert_eq!(read_jpeg_icc_profile(&fs::read(&path)?), None);
+        assert!(decoded.to_rgb8() == image::open(&path).unwrap().to_rgb8());
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+//! Read the flattened composite image stored at the end of a Photoshop file
+
+use std::{
+    convert::TryInto,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, RgbImage, RgbaImage};
+
+/// Color modes of the PSD header that can be read
+const GRAYSCALE: u16 = 1;
+const RGB: u16 = 3;
+
+/// Largest width or height Photoshop allows, for PSD and PSB files
+const MAX_PSD_SIDE: usize = 30_000;
+const MAX_PSB_SIDE: usize = 300_000;
+
+/// Decode the composite image of a PSD (or PSB) file, the flattened result of every
+/// layer that Photoshop saves for compatibility. Layers themselves are ignored.
+/// Supports 8 and 16 bit grayscale and RGB, with or without alpha; 16 bit samples
+/// are kept.
+pub fn decode_psd(path: &Path) -> Result<DynamicImage, Error> {
+    let bytes = fs::read(path)?;
+    decode_psd_bytes(&bytes).map_err(|e| Error::new(e.kind(), format!("{:?}: {}", path, e)))
+}
+
+/// Decode the composite image of PSD (or PSB) file contents. Sizes read from the
+/// file are checked against what's left of it before anything is allocated, so a
+/// corrupted file is an error rather than a crash.
+pub fn decode_psd_bytes(bytes: &[u8]) -> Result<DynamicImage, Error> {
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.take(4)? != b"8BPS" {
+        return Err(invalid("not a Photoshop file"));
+    }
+    let large = match reader.u16()? {
+        1 => false,
+        2 => true,
+        version => return Err(invalid(&format!("unknown PSD version {}", version))),
+    };
+    reader.take(6)?;
+    let channels = reader.u16()? as usize;
+    let height = reader.u32()? as usize;
+    let width = reader.u32()? as usize;
+    let depth = reader.u16()?;
+    let color_mode = reader.u16()?;
+
+    let color_channels = match color_mode {
+        GRAYSCALE => 1,
+        RGB => 3,
+        _ => return Err(invalid(&format!("unsupported color mode {}", color_mode))),
+    };
+    if depth != 8 && depth != 16 {
+        return Err(invalid(&format!("unsupported depth of {} bits", depth)));
+    }
+    if channels < color_channels {
+        return Err(invalid("missing color channels"));
+    }
+    let max_side = if large { MAX_PSB_SIDE } else { MAX_PSD_SIDE };
+    if width == 0 || height == 0 || width > max_side || height > max_side {
+        return Err(invalid(&format!(
+            "unsupported size of {}x{}",
+            width, height
+        )));
+    }
+
+    // Color mode data, image resources, then layers and masks, none of which are needed
+    let color_mode_data = reader.u32()? as usize;
+    reader.take(color_mode_data)?;
+    let image_resources = reader.u32()? as usize;
+    reader.take(image_resources)?;
+    let layers = if large {
+        reader.u64()? as usize
+    } else {
+        reader.u32()? as usize
+    };
+    reader.take(layers)?;
+
+    let bytes_per_sample = depth as usize / 8;
+    let plane_len = width * height * bytes_per_sample;
+    // The first extra channel is the transparency of the composite, the others
+    // aren't needed
+    let output_channels = (color_channels + 1).min(channels);
+    let planes = match reader.u16()? {
+        0 => {
+            let len = plane_len
+                .checked_mul(output_channels)
+                .ok_or_else(|| invalid("unexpected end of file"))?;
+            reader.take(len)?.to_vec()
+        }
+        1 => decode_rle(
+            &mut reader,
+            channels,
+            output_channels,
+            height,
+            width * bytes_per_sample,
+            large,
+        )?,
+        compression => {
+            return Err(invalid(&format!(
+                "unsupported compression method {}",
+                compression
+            )))
+        }
+    };
+
+    let (width, height) = (width as u32, height as u32);
+    let pixels = width as usize * height as usize;
+    let image = if depth == 16 {
+        // 16 bit samples are kept, heightmaps would lose their precision otherwise
+        let mut samples = Vec::with_capacity(pixels * output_channels);
+        for pixel in 0..pixels {
+            for channel in 0..output_channels {
+                let offset = channel * plane_len + pixel * bytes_per_sample;
+                samples.push(u16::from_be_bytes([planes[offset], planes[offset + 1]]));
+            }
+        }
+        match output_channels {
+            1 => ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLuma16),
+            2 => ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLumaA16),
+            3 => ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgb16),
+            _ => ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgba16),
+        }
+    } else {
+        let mut samples = Vec::with_capacity(pixels * output_channels);
+        for pixel in 0..pixels {
+            for channel in 0..output_channels {
+                samples.push(planes[channel * plane_len + pixel]);
+            }
+        }
+        match output_channels {
+            1 => GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+            2 => GrayAlphaImage::from_raw(width, height, samples).map(DynamicImage::ImageLumaA8),
+            3 => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+            _ => RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8),
+        }
+    };
+
+    image.ok_or_else(|| invalid("truncated image data"))
+}
+
+/// Decode the first `output_channels` of the PackBits-compressed planes, each
+/// scanline compressed on its own
+fn decode_rle(
+    reader: &mut Reader,
+    channels: usize,
+    output_channels: usize,
+    height: usize,
+    row_len: usize,
+    large: bool,
+) -> Result<Vec<u8>, Error> {
+    // Take the whole table first, so its length is checked against the file
+    let count_len = if large { 4 } else { 2 };
+    let table_len = (channels * height)
+        .checked_mul(count_len)
+        .ok_or_else(|| invalid("unexpected end of file"))?;
+    let mut table = Reader {
+        bytes: reader.take(table_len)?,
+        position: 0,
+    };
+    let mut row_lengths = Vec::with_capacity(output_channels * height);
+    for _ in 0..output_channels * height {
+        row_lengths.push(if large {
+            table.u32()? as usize
+        } else {
+            table.u16()? as usize
+        });
+    }
+
+    let mut planes = Vec::new();
+    for row_length in row_lengths {
+        let packed = reader.take(row_length)?;
+        let row_start = planes.len();
+        let mut i = 0;
+        while i < packed.len() {
+            let header = packed[i] as i8;
+            i += 1;
+            match header {
+                0..=127 => {
+                    let count = header as usize + 1;
+                    let literal = packed
+                        .get(i..i + count)
+                        .ok_or_else(|| invalid("truncated image data"))?;
+                    planes.extend_from_slice(literal);
+                    i += count;
+                }
+                -127..=-1 => {
+                    let value = *packed
+                        .get(i)
+                        .ok_or_else(|| invalid("truncated image data"))?;
+                    let count = (1 - header as isize) as usize;
+                    planes.extend(std::iter::repeat_n(value, count));
+                    i += 1;
+                }
+                -128 => {}
+            }
+            if planes.len() - row_start > row_len {
+                return Err(invalid("corrupted image data"));
+            }
+        }
+        if planes.len() - row_start != row_len {
+            return Err(invalid("corrupted image data"));
+        }
+    }
+
+    Ok(planes)
+}
+
+/// Big-endian reader over the file contents
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("unexpected end of file"))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid PSD, {}", message))
+}
+
+#[cfg(test)]
+mod decode_psd_tests {
+    use super::*;
+
+    /// Header of a 2x1 PSD with the given channels, color mode and compression
+    fn psd(channels: u16, color_mode: u16, compression: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"8BPS".to_vec();
+        bytes.extend(&1u16.to_be_bytes());
+        bytes.extend(&[0; 6]);
+        bytes.extend(&channels.to_be_bytes());
+        bytes.extend(&1u32.to_be_bytes());
+        bytes.extend(&2u32.to_be_bytes());
+        bytes.extend(&8u16.to_be_bytes());
+        bytes.extend(&color_mode.to_be_bytes());
+        bytes.extend(&[0; 12]);
+        bytes.extend(&compression.to_be_bytes());
+        bytes.extend(data);
+
+        bytes
+    }
+
+    #[test]
+    fn it_decodes_raw_rgb() {
+        let bytes = psd(3, RGB, 0, &[255, 0, 10, 20, 30, 40]);
+        let image = decode_psd_bytes(&bytes).unwrap().to_rgb8();
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 10, 30]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 20, 40]);
+    }
+
+    #[test]
+    fn it_decodes_rle_rgba() {
+        // One row per channel: a repeat run, a literal run, a repeat run, a repeat run
+        let mut data = vec![0, 2, 0, 3, 0, 2, 0, 2];
+        data.extend(&[0xFF, 7, 1, 1, 2, 0xFF, 0, 0xFF, 128]);
+        let bytes = psd(4, RGB, 1, &data);
+        let image = decode_psd_bytes(&bytes).unwrap();
+
+        assert_eq!(image.color(), image::ColorType::Rgba8);
+        let image = image.to_rgba8();
+        assert_eq!(image.get_pixel(0, 0).0, [7, 1, 0, 128]);
+        assert_eq!(image.get_pixel(1, 0).0, [7, 2, 0, 128]);
+    }
+
+    #[test]
+    fn it_keeps_16_bit_grayscale_samples() {
+        let mut bytes = psd(1, GRAYSCALE, 0, &[0x12, 0x34, 0xAB, 0xCD]);
+        bytes[22..24].copy_from_slice(&16u16.to_be_bytes());
+        let image = decode_psd_bytes(&bytes).unwrap();
+
+        assert_eq!(image.color(), image::ColorType::L16);
+        let image = image.to_luma16();
+        assert_eq!(image.get_pixel(0, 0).0, [0x1234]);
+        assert_eq!(image.get_pixel(1, 0).0, [0xABCD]);
+    }
+
+    #[test]
+    fn it_errors_on_unsupported_files() {
+        assert!(decode_psd_bytes(b"GIF89a").is_err());
+        assert!(decode_psd_bytes(&psd(4, 4, 0, &[0; 8])).is_err());
+        assert!(decode_psd_bytes(&psd(3, RGB, 0, &[0; 2])).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_sizes_larger_than_the_file() {
+        let mut bytes = psd(3, RGB, 1, &[]);
+        bytes[14..18].copy_from_slice(&30_000u32.to_be_bytes());
+        bytes[18..22].copy_from_slice(&30_000u32.to_be_bytes());
+        assert!(decode_psd_bytes(&bytes).is_err());
+
+        bytes[14..18].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(decode_psd_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_every_truncation_of_a_valid_file() {
+        let mut data = vec![0, 2, 0, 3, 0, 2, 0, 2];
+        data.extend(&[0xFF, 7, 1, 1, 2, 0xFF, 0, 0xFF, 128]);
+        let bytes = psd(4, RGB, 1, &data);
+
+        for len in 0..bytes.len() {
+            assert!(decode_psd_bytes(&bytes[..len]).is_err());
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Decode a texture of any format the converter reads
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::{io::Reader as ImageReader, DynamicImage};
+
+use crate::image_processing::{
+    decode_dds, decode_gif, decode_hdr, decode_jpeg, decode_psd, decode_tiff, decode_webp,
+};
+use crate::options::HdrSettings;
+
+/// Decode the texture with the decoder of its extension: ours for Photoshop, WebP,
+/// TIFF, DDS, EXR and HDR files, JPEGs with an ICC profile and the first frame of
+/// GIFs, the image crate's for the rest. EXR and HDR
+/// textures are tonemapped with `hdr`.
+pub fn decode_texture(path: &Path, hdr: &HdrSettings) -> Result<DynamicImage, Error> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match extension.as_str() {
+        "psd" => decode_psd(path),
+        "webp" => decode_webp(path),
+        "tif" | "tiff" => decode_tiff(path),
+        "dds" => decode_dds(path),
+        "gif" => decode_gif(path),
+        "jpg" | "jpeg" => decode_jpeg(path),
+        "exr" | "hdr" => decode_hdr(path, hdr),
+        _ => {
+            let image_reader = ImageReader::open(path)?;
+            if image_reader.format().is_none() {
+                return Err(Error::other(format!(
+                    "Failed to convert provided image: {:?}",
+                    path
+                )));
+            }
+            image_reader
+                .decode()
+                .map_err(|e| Error::other(format!("Could not decode {:?}: {:?}", path, e)))
+        }
+    }
+}
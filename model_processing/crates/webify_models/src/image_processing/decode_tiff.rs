@@ -0,0 +1,183 @@
+//! Read the main image of a TIFF file, whatever the number of pages
+
+use std::{
+    fs::File,
+    io::{BufReader, Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, RgbImage, RgbaImage};
+use tiff::{
+    decoder::{Decoder, DecodingResult, Limits},
+    ColorType,
+};
+
+/// Decode the largest page of a TIFF file. Multi-page TIFFs usually hold the image
+/// along with thumbnails or reduced resolution copies, which are ignored. Supports 8
+/// and 16 bit grayscale and RGB, with or without alpha, and 8 bit CMYK; 16 bit
+/// samples are kept.
+pub fn decode_tiff(path: &Path) -> Result<DynamicImage, Error> {
+    let error = |e: tiff::TiffError| invalid(path, &e.to_string());
+
+    let mut decoder = open(path)?;
+    let mut largest = (0, 0);
+    let mut page = 0;
+    loop {
+        let (width, height) = decoder.dimensions().map_err(error)?;
+        let pixels = u64::from(width) * u64::from(height);
+        if pixels > largest.1 {
+            largest = (page, pixels);
+        }
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image().map_err(error)?;
+        page += 1;
+    }
+
+    // The decoder can't go back, so the file is read again up to the largest page
+    if page != largest.0 {
+        decoder = open(path)?;
+        for _ in 0..largest.0 {
+            decoder.next_image().map_err(error)?;
+        }
+    }
+
+    let (width, height) = decoder.dimensions().map_err(error)?;
+    let color_type = decoder.colortype().map_err(error)?;
+    let image = match (color_type, decoder.read_image().map_err(error)?) {
+        (ColorType::Gray(8), DecodingResult::U8(samples)) => {
+            GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(samples)) => {
+            ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(samples)) => {
+            GrayAlphaImage::from_raw(width, height, samples).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(samples)) => {
+            ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(samples)) => {
+            RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(samples)) => {
+            ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(samples)) => {
+            RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(samples)) => {
+            ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgba16)
+        }
+        (ColorType::CMYK(8), DecodingResult::U8(samples)) => {
+            RgbImage::from_raw(width, height, cmyk_to_rgb(&samples)).map(DynamicImage::ImageRgb8)
+        }
+        (color_type, _) => {
+            return Err(invalid(
+                path,
+                &format!("unsupported color type {:?}", color_type),
+            ))
+        }
+    };
+
+    image.ok_or_else(|| invalid(path, "truncated image data"))
+}
+
+/// Decoder positioned on the first page. Scans can be much larger than the default
+/// limits allow, and are decoded whole anyway.
+fn open(path: &Path) -> Result<Decoder<BufReader<File>>, Error> {
+    Decoder::new(BufReader::new(File::open(path)?))
+        .map(|decoder| decoder.with_limits(Limits::unlimited()))
+        .map_err(|e| invalid(path, &e.to_string()))
+}
+
+/// RGB of CMYK samples, without a color profile
+fn cmyk_to_rgb(samples: &[u8]) -> Vec<u8> {
+    samples
+        .chunks_exact(4)
+        .flat_map(|cmyk| {
+            let white = 255 - u32::from(cmyk[3]);
+            let channel = |ink: u8| ((255 - u32::from(ink)) * white / 255) as u8;
+            vec![channel(cmyk[0]), channel(cmyk[1]), channel(cmyk[2])]
+        })
+        .collect()
+}
+
+fn invalid(path: &Path, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("{:?}: invalid TIFF file, {}", path, message),
+    )
+}
+
+#[cfg(test)]
+mod decode_tiff_tests {
+    use super::*;
+
+    use std::{fs, io::BufWriter};
+
+    use image::GenericImageView;
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    #[test]
+    fn it_keeps_16_bit_samples() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_tiff_16_bit");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("scan.tif");
+        let samples: Vec<u16> = (0..8 * 4 * 3).map(|i| i * 600).collect();
+        TiffEncoder::new(BufWriter::new(File::create(&path)?))
+            .and_then(|mut tiff| tiff.write_image::<colortype::RGB16>(8, 4, &samples))
+            .map_err(Error::other)?;
+
+        let image = decode_tiff(&path)?;
+        assert_eq!(image.dimensions(), (8, 4));
+        assert_eq!(image.as_rgb16().unwrap().as_raw(), &samples);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_decodes_the_largest_page() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_tiff_pages");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("scan.tiff");
+        TiffEncoder::new(BufWriter::new(File::create(&path)?))
+            .and_then(|mut tiff| {
+                tiff.write_image::<colortype::Gray8>(2, 2, &[0; 4])?;
+                tiff.write_image::<colortype::Gray8>(16, 8, &[200; 128])?;
+                tiff.write_image::<colortype::Gray8>(4, 2, &[0; 8])
+            })
+            .map_err(Error::other)?;
+
+        let image = decode_tiff(&path)?;
+        assert_eq!(image.dimensions(), (16, 8));
+        assert!(image.as_luma8().unwrap().pixels().all(|p| p[0] == 200));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_other_formats() {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("example.jpg");
+        assert!(decode_tiff(&path).is_err());
+    }
+
+    #[test]
+    fn it_converts_cmyk_to_rgb() {
+        assert_eq!(
+            cmyk_to_rgb(&[0, 255, 255, 0, 0, 0, 0, 255]),
+            vec![255, 0, 0, 0, 0, 0]
+        );
+    }
+}
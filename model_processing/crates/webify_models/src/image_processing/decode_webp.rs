@@ -0,0 +1,69 @@
+//! Decode a WebP texture, lossy or lossless
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use image::{DynamicImage, RgbImage, RgbaImage};
+use webp::Decoder;
+
+/// Decode a WebP file with libwebp, since the image crate only decodes lossy WebP
+pub fn decode_webp(path: &Path) -> Result<DynamicImage, Error> {
+    let data = fs::read(path)?;
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not decode {:?} as WebP", path),
+        )
+    };
+    let decoded = Decoder::new(&data).decode().ok_or_else(invalid)?;
+    let (width, height) = (decoded.width(), decoded.height());
+
+    if decoded.is_alpha() {
+        RgbaImage::from_raw(width, height, decoded.to_vec())
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(invalid)
+    } else {
+        RgbImage::from_raw(width, height, decoded.to_vec())
+            .map(DynamicImage::ImageRgb8)
+            .ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod decode_webp_tests {
+    use super::*;
+
+    use image::GenericImageView;
+    use webp::Encoder;
+
+    #[test]
+    fn it_decodes_lossless_webp() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_decode_webp");
+        fs::create_dir_all(&dir)?;
+        let pixels = [10, 20, 30, 128].repeat(6 * 4);
+        fs::write(
+            dir.join("lossless.webp"),
+            &*Encoder::from_rgba(&pixels, 6, 4).encode_lossless(),
+        )?;
+
+        let decoded = decode_webp(&dir.join("lossless.webp"))?;
+        assert_eq!(decoded.dimensions(), (6, 4));
+        assert_eq!(decoded.to_rgba8().into_raw(), pixels);
+        assert!(decode_webp(
+            &Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg")
+        )
+        .is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Shrink a texture so its longest side fits within a maximum size
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::GenericImageView;
+
+use crate::image_processing::DecodeCache;
+use crate::options::ResizeFilter;
+
+/// Width and height of a texture, in pixels
+pub type Dimensions = (u32, u32);
+
+/// Shrink the texture in place so its longest side is at most `max_size` pixels,
+/// keeping its aspect ratio, resampled with `filter`. Returns the original and new
+/// dimensions when it was resized.
+pub fn downscale_texture(
+    path: &Path,
+    max_size: u32,
+    filter: ResizeFilter,
+    cache: &mut DecodeCache,
+) -> Result<Option<(Dimensions, Dimensions)>, Error> {
+    let img = cache
+        .take_or_open(path)
+        .map_err(|e| Error::other(format!("Failed to open {:?} to downscale: {:?}", path, e)))?;
+    let (width, height) = img.dimensions();
+    if width <= max_size && height <= max_size {
+        cache.insert(path, img);
+        return Ok(None);
+    }
+
+    let resized = img.resize(max_size, max_size, filter.filter_type());
+    resized
+        .save(path)
+        .map_err(|e| Error::other(format!("Could not save downscaled {:?}: {:?}", path, e)))?;
+
+    let dimensions = resized.dimensions();
+    cache.insert(path, resized);
+
+    Ok(Some(((width, height), dimensions)))
+}
+
+#[cfg(test)]
+mod downscale_texture_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ImageBuffer, Rgb};
+
+    fn setup(test_run_id: &str) -> Result<(), Error> {
+        let destination_path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id);
+        fs::create_dir_all(&destination_path)?;
+        ImageBuffer::from_pixel(64, 32, Rgb([200u8, 100, 50]))
+            .save(destination_path.join("wide.png"))
+            .map_err(Error::other)?;
+
+        Ok(())
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(
+            Path::new("tests")
+                .join("image_processing")
+                .join(test_run_id),
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_the_aspect_ratio() -> Result<(), Error> {
+        let test_run_id = "test_run_it_downscales_textures";
+        setup(test_run_id)?;
+
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id)
+            .join("wide.png");
+        let resized = downscale_texture(
+            &path,
+            16,
+            ResizeFilter::Lanczos3,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(resized, Some(((64, 32), (16, 8))));
+        assert_eq!(image::image_dimensions(&path).unwrap(), (16, 8));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_small_textures_alone() -> Result<(), Error> {
+        let test_run_id = "test_run_it_leaves_small_textures_alone";
+        setup(test_run_id)?;
+
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id)
+            .join("wide.png");
+        assert_eq!(
+            downscale_texture(
+                &path,
+                64,
+                ResizeFilter::Lanczos3,
+                &mut DecodeCache::default()
+            )?,
+            None
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_uses_the_decoded_texture_from_the_cache() -> Result<(), Error> {
+        let test_run_id = "test_run_it_uses_the_decoded_texture_from_the_cache";
+        setup(test_run_id)?;
+
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id)
+            .join("wide.png");
+        // Stands for what a previous stage decoded, the file on disk is 64x32
+        let mut cache = DecodeCache::default();
+        cache.insert(
+            &path,
+            image::DynamicImage::ImageRgb8(ImageBuffer::from_pixel(32, 64, Rgb([0u8, 0, 0]))),
+        );
+        let resized = downscale_texture(&path, 16, ResizeFilter::Lanczos3, &mut cache)?;
+
+        assert_eq!(resized, Some(((32, 64), (8, 16))));
+        assert_eq!(cache.take(&path).map(|img| img.dimensions()), Some((8, 16)));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
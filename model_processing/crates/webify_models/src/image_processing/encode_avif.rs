@@ -0,0 +1,175 @@
+//! Encode a PNG texture to AVIF, keeping the PNG when AVIF isn't worth it
+
+use std::{fs, io::Error, result::Result};
+
+use ravif::{Encoder, Img, RGBA8};
+
+use crate::image_processing::{DecodeCache, Image};
+use crate::options::{AvifSettings, Removal};
+use crate::provenance::sidecar_path;
+use crate::trash::remove_file;
+
+/// Smallest share of the PNG size the AVIF has to save to replace it
+const MIN_AVIF_SAVING: f64 = 0.1;
+
+/// Encode the PNG texture to AVIF and replace the PNG with it. The PNG is kept
+/// when encoding fails or the AVIF doesn't save at least 10%, in which case the
+/// reason is returned along with the unchanged image.
+pub fn encode_avif(
+    mut image: Image,
+    settings: &AvifSettings,
+    removal: &Removal,
+    cache: &mut DecodeCache,
+) -> Result<(Image, Option<String>), Error> {
+    if !(1..=100).contains(&settings.quality) || !(1..=10).contains(&settings.speed) {
+        return Err(Error::other(format!(
+            "Invalid AVIF settings {:?}, quality goes from 1 to 100 and speed from 1 to 10.",
+            settings
+        )));
+    }
+
+    let decoded = cache
+        .take_or_open(&image.path)
+        .map_err(|e| Error::other(format!("Could not open {:?}: {:?}", image.path, e)))?
+        .to_rgba8();
+    let (width, height) = decoded.dimensions();
+    let pixels: Vec<RGBA8> = decoded
+        .pixels()
+        .map(|p| RGBA8::new(p[0], p[1], p[2], p[3]))
+        .collect();
+
+    let encoded = Encoder::new()
+        .with_quality(settings.quality as f32)
+        .with_alpha_quality(settings.quality as f32)
+        .with_speed(settings.speed)
+        .encode_rgba(Img::new(&pixels[..], width as usize, height as usize));
+    let avif = match encoded {
+        Ok(encoded) => encoded.avif_file,
+        Err(e) => {
+            return Ok((
+                image,
+                Some(format!("kept as PNG, AVIF encoding failed: {}", e)),
+            ))
+        }
+    };
+
+    let png_bytes = fs::metadata(&image.path)?.len();
+    if avif.len() as f64 > png_bytes as f64 * (1.0 - MIN_AVIF_SAVING) {
+        return Ok((
+            image,
+            Some(format!(
+                "kept as PNG, AVIF would be {} bytes for {} as PNG",
+                avif.len(),
+                png_bytes
+            )),
+        ));
+    }
+
+    let avif_path = image.path.with_extension("avif");
+    fs::write(&avif_path, avif)?;
+    remove_file(&image.path, removal)?;
+    // A previous run may have marked the PNG
+    if sidecar_path(&image.path).is_file() {
+        fs::remove_file(sidecar_path(&image.path))?;
+    }
+    image.path = avif_path;
+    image.extension = String::from("avif");
+
+    Ok((image, None))
+}
+
+#[cfg(test)]
+mod encode_avif_tests {
+    use super::*;
+
+    use std::path::Path;
+
+    #[test]
+    fn it_replaces_the_png_with_an_avif() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_encode_avif");
+        fs::create_dir_all(&dir)?;
+        image::open(
+            Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg"),
+        )
+        .unwrap()
+        .save(dir.join("example.png"))
+        .unwrap();
+
+        let image = Image {
+            path: dir.join("example.png"),
+            extension: String::from("png"),
+        };
+        let settings = AvifSettings {
+            quality: 60,
+            speed: 10,
+        };
+        let (encoded, fallback) = encode_avif(
+            image,
+            &settings,
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(fallback, None);
+        assert_eq!(encoded.path, dir.join("example.avif"));
+        assert!(dir.join("example.avif").is_file());
+        assert!(!dir.join("example.png").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_the_png_when_avif_saves_too_little() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_encode_avif_tiny");
+        fs::create_dir_all(&dir)?;
+        image::RgbaImage::new(1, 1)
+            .save(dir.join("tiny.png"))
+            .unwrap();
+
+        let image = Image {
+            path: dir.join("tiny.png"),
+            extension: String::from("png"),
+        };
+        let (kept, fallback) = encode_avif(
+            image.clone(),
+            &AvifSettings::default(),
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(kept, image);
+        assert!(fallback.is_some());
+        assert!(dir.join("tiny.png").is_file());
+        assert!(!dir.join("tiny.avif").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_out_of_range_settings() {
+        let image = Image {
+            path: Path::new("tests").join("missing.png"),
+            extension: String::from("png"),
+        };
+        let settings = AvifSettings {
+            quality: 0,
+            speed: 6,
+        };
+        assert!(encode_avif(
+            image,
+            &settings,
+            &Removal::Delete,
+            &mut DecodeCache::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Encode a PNG texture to KTX2 with Basis Universal
+
+use std::{fs, io::Error, result::Result, thread};
+
+use basis_universal::{sys, BasisTextureFormat, ColorSpace, Compressor, CompressorParams};
+
+use crate::image_processing::{basis_to_ktx2, DecodeCache, Image};
+use crate::options::{BasisCodec, Colorspace, Ktx2Settings, Removal};
+use crate::provenance::sidecar_path;
+use crate::trash::remove_file;
+
+/// Bits of the UASTC pack flags holding the effort level
+const UASTC_LEVEL_MASK: i32 = 0xF;
+
+/// Encode the PNG texture to KTX2, ETC1S or UASTC with its mip chain as asked, and
+/// replace the PNG with it, removing the PNG as asked. The colorspace is written to
+/// the data format descriptor, and linear textures are encoded in linear space; normal
+/// maps are tuned as `basisu -normal_map` tunes them too. The PNG is kept when Basis
+/// Universal can't encode it, in which case the reason is returned along with the
+/// unchanged image.
+pub fn encode_ktx2(
+    mut image: Image,
+    settings: &Ktx2Settings,
+    normal_map: bool,
+    colorspace: Colorspace,
+    removal: &Removal,
+    cache: &mut DecodeCache,
+) -> Result<(Image, Option<String>), Error> {
+    if settings.quality == 0 || settings.uastc_level > 4 {
+        return Err(Error::other(format!(
+            "Invalid KTX2 settings {:?}, quality goes from 1 to 255 and uastc_level from 0 to 4.",
+            settings
+        )));
+    }
+
+    let decoded = cache
+        .take_or_open(&image.path)
+        .map_err(|e| Error::other(format!("Could not open {:?}: {:?}", image.path, e)))?
+        .to_rgba8();
+    let (width, height) = decoded.dimensions();
+
+    let mut params = CompressorParams::new();
+    match settings.codec {
+        BasisCodec::Etc1s => {
+            params.set_basis_format(BasisTextureFormat::ETC1S);
+            params.set_etc1s_quality_level(u32::from(settings.quality));
+        }
+        BasisCodec::Uastc => {
+            params.set_basis_format(BasisTextureFormat::UASTC4x4);
+            // set_uastc_quality_level ORs the level into the default one
+            unsafe {
+                let flags = sys::compressor_params_get_pack_uastc_flags(params.0);
+                sys::compressor_params_set_pack_uastc_flags(
+                    params.0,
+                    (flags & !UASTC_LEVEL_MASK) | i32::from(settings.uastc_level),
+                );
+            }
+        }
+    }
+    match colorspace {
+        Colorspace::Srgb => params.set_color_space(ColorSpace::Srgb),
+        Colorspace::Linear => {
+            params.set_color_space(ColorSpace::Linear);
+            params.set_mip_color_space(ColorSpace::Linear);
+        }
+    }
+    if normal_map {
+        params.tune_for_normal_maps();
+    }
+    params.set_generate_mipmaps(settings.mipmaps);
+    params
+        .source_image_mut(0)
+        .init(decoded.as_raw(), width, height, 4);
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let mut compressor = Compressor::new(threads);
+    // The settings were checked above, and the source image matches its dimensions
+    let encoded = unsafe {
+        if compressor.init(&params) {
+            compressor.process().map_err(|e| format!("{:?}", e))
+        } else {
+            Err(String::from("invalid parameters"))
+        }
+    };
+    if let Err(e) = encoded {
+        return Ok((
+            image,
+            Some(format!("kept as PNG, KTX2 encoding failed: {}", e)),
+        ));
+    }
+
+    let writer = format!(
+        "webify_models {} (Basis Universal 1.16)",
+        env!("CARGO_PKG_VERSION")
+    );
+    let ktx2 = basis_to_ktx2(
+        compressor.basis_file(),
+        colorspace == Colorspace::Srgb,
+        &writer,
+    )?;
+
+    let ktx2_path = image.path.with_extension("ktx2");
+    fs::write(&ktx2_path, ktx2)?;
+    remove_file(&image.path, removal)?;
+    // A previous run may have marked the PNG
+    if sidecar_path(&image.path).is_file() {
+        fs::remove_file(sidecar_path(&image.path))?;
+    }
+    image.path = ktx2_path;
+    image.extension = String::from("ktx2");
+
+    Ok((image, None))
+}
+
+#[cfg(test)]
+mod encode_ktx2_tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use crate::image_processing::{read_ktx2_info, Ktx2Info};
+
+    const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+    const KHR_DF_TRANSFER_SRGB: u8 = 2;
+
+    /// Transfer function of the KTX2 file, from its data format descriptor
+    fn transfer_function(ktx2: &[u8]) -> u8 {
+        let dfd_offset = u32::from_le_bytes([ktx2[48], ktx2[49], ktx2[50], ktx2[51]]) as usize;
+        ktx2[dfd_offset + 14]
+    }
+
+    fn write_png(dir: &Path, alpha: u8) -> Result<Image, Error> {
+        fs::create_dir_all(dir)?;
+        image::RgbaImage::from_fn(64, 32, |x, y| {
+            image::Rgba([x as u8 * 4, y as u8 * 8, 90, alpha])
+        })
+        .save(dir.join("gradient.png"))
+        .unwrap();
+
+        Ok(Image {
+            path: dir.join("gradient.png"),
+            extension: String::from("png"),
+        })
+    }
+
+    #[test]
+    fn it_replaces_the_png_with_an_etc1s_ktx2() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_encode_ktx2_etc1s");
+        let image = write_png(&dir, 128)?;
+
+        let (encoded, fallback) = encode_ktx2(
+            image,
+            &Ktx2Settings::default(),
+            false,
+            Colorspace::Srgb,
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(fallback, None);
+        assert_eq!(encoded.path, dir.join("gradient.ktx2"));
+        assert!(!dir.join("gradient.png").exists());
+        assert_eq!(
+            read_ktx2_info(&encoded.path)?,
+            Ktx2Info {
+                width: 64,
+                height: 32,
+                channels: 4,
+                levels: 7
+            }
+        );
+        assert_eq!(
+            transfer_function(&fs::read(&encoded.path)?),
+            KHR_DF_TRANSFER_SRGB
+        );
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_normal_maps_to_linear_uastc_without_mipmaps() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_encode_ktx2_uastc");
+        let image = write_png(&dir, 255)?;
+
+        let settings = Ktx2Settings {
+            codec: BasisCodec::Uastc,
+            uastc_level: 0,
+            mipmaps: false,
+            ..Ktx2Settings::default()
+        };
+        let (encoded, fallback) = encode_ktx2(
+            image,
+            &settings,
+            true,
+            Colorspace::Linear,
+            &Removal::Delete,
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(fallback, None);
+        assert_eq!(
+            read_ktx2_info(&encoded.path)?,
+            Ktx2Info {
+                width: 64,
+                height: 32,
+                channels: 3,
+                levels: 1
+            }
+        );
+        // 16 bytes per 4x4 block, stored as they are
+        let ktx2 = fs::read(&encoded.path)?;
+        assert!(ktx2.len() > 16 * 16 * 8);
+        assert_eq!(transfer_function(&ktx2), KHR_DF_TRANSFER_LINEAR);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_out_of_range_settings() {
+        let image = Image {
+            path: Path::new("tests").join("missing.png"),
+            extension: String::from("png"),
+        };
+        let settings = Ktx2Settings {
+            uastc_level: 5,
+            ..Ktx2Settings::default()
+        };
+        assert!(encode_ktx2(
+            image,
+            &settings,
+            false,
+            Colorspace::Srgb,
+            &Removal::Delete,
+            &mut DecodeCache::default()
+        )
+        .is_err());
+    }
+}
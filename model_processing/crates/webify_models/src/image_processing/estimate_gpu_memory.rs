@@ -0,0 +1,48 @@
+//! Estimate how much GPU memory a texture takes once uploaded
+
+/// Bytes per pixel of an uncompressed texture once uploaded. Browsers upload
+/// PNGs as RGBA8, and drivers pad RGB8 to RGBA8 anyway.
+pub const UNCOMPRESSED_BYTES_PER_PIXEL: u64 = 4;
+
+/// Bytes per pixel of a KTX2 texture once transcoded, BC7 and ASTC 4x4 store a
+/// 4x4 block in 16 bytes
+pub const TRANSCODED_BYTES_PER_PIXEL: u64 = 1;
+
+/// Estimate the GPU memory of a texture with a full mip chain, which is what the
+/// web renderer generates for every texture at load time
+pub fn estimate_gpu_memory(width: u32, height: u32, bytes_per_pixel: u64) -> u64 {
+    let (mut width, mut height) = (u64::from(width.max(1)), u64::from(height.max(1)));
+    let mut total = width * height * bytes_per_pixel;
+
+    while width > 1 || height > 1 {
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+        total += width * height * bytes_per_pixel;
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod estimate_gpu_memory_tests {
+    use super::*;
+
+    #[test]
+    fn it_includes_the_mip_chain() {
+        // 4x4 + 2x2 + 1x1 pixels
+        assert_eq!(estimate_gpu_memory(4, 4, 4), (16 + 4 + 1) * 4);
+    }
+
+    #[test]
+    fn it_handles_non_square_textures() {
+        // 4x1 + 2x1 + 1x1 pixels
+        assert_eq!(estimate_gpu_memory(4, 1, 4), (4 + 2 + 1) * 4);
+    }
+
+    #[test]
+    fn it_is_about_a_third_more_than_the_base_level() {
+        let base = 1024 * 1024 * UNCOMPRESSED_BYTES_PER_PIXEL;
+        let estimate = estimate_gpu_memory(1024, 1024, UNCOMPRESSED_BYTES_PER_PIXEL);
+        assert_eq!(estimate, (base * 4 - 4) / 3);
+    }
+}
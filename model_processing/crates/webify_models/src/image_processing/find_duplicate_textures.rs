@@ -0,0 +1,101 @@
+//! Find the processed textures that are byte-identical copies of each other
+
+use std::{collections::BTreeMap, io::Error, path::PathBuf, result::Result};
+
+use rayon::prelude::*;
+
+use crate::hashing::hash_file;
+use crate::report::{DuplicateTexture, TextureStats};
+
+/// Group the textures by the hash of their contents and, in every group of more
+/// than one, keep a canonical copy: a texture shared by a collection over the ones
+/// of a model, then the first path. The other copies of each group are returned
+/// along with their canonical copy. Nothing is changed on disk.
+pub fn find_duplicate_textures(textures: &[TextureStats]) -> Result<Vec<DuplicateTexture>, Error> {
+    let hashes = textures
+        .par_iter()
+        .map(|texture| hash_file(&texture.path))
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    let mut groups: BTreeMap<&str, Vec<&TextureStats>> = BTreeMap::new();
+    for (texture, hash) in textures.iter().zip(&hashes) {
+        groups.entry(hash).or_default().push(texture);
+    }
+
+    let mut duplicates = Vec::new();
+    for mut copies in groups.into_values().filter(|copies| copies.len() > 1) {
+        copies.sort_by_key(|texture| (texture.model.is_some(), texture.path.clone()));
+        copies.dedup_by_key(|texture| texture.path.clone());
+        let canonical: PathBuf = copies[0].path.clone();
+        duplicates.extend(copies[1..].iter().map(|texture| DuplicateTexture {
+            path: texture.path.clone(),
+            canonical: canonical.clone(),
+            file_bytes: texture.file_bytes,
+        }));
+    }
+    duplicates.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod find_duplicate_textures_tests {
+    use super::*;
+
+    use std::{fs, path::Path};
+
+    fn texture(path: PathBuf, model: Option<&str>) -> TextureStats {
+        TextureStats {
+            file_bytes: fs::metadata(&path).unwrap().len(),
+            path,
+            model: model.map(PathBuf::from),
+            width: 1,
+            height: 1,
+            channels: 3,
+            gpu_bytes: 4,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn it_keeps_one_copy_of_identical_textures() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_find_duplicate_textures");
+        fs::create_dir_all(&dir)?;
+        for (name, contents) in [
+            ("oak.png", "oak"),
+            ("wood.png", "oak"),
+            ("pine.png", "pine"),
+            ("shared_oak.png", "oak"),
+        ] {
+            fs::write(dir.join(name), contents)?;
+        }
+
+        let duplicates = find_duplicate_textures(&[
+            texture(dir.join("wood.png"), Some("desk")),
+            texture(dir.join("oak.png"), Some("lamp")),
+            texture(dir.join("pine.png"), Some("chair")),
+        ])?;
+        assert_eq!(
+            duplicates,
+            vec![DuplicateTexture {
+                path: dir.join("wood.png"),
+                canonical: dir.join("oak.png"),
+                file_bytes: 3,
+            }]
+        );
+
+        // A texture shared by the collection wins over the copies of its models
+        let duplicates = find_duplicate_textures(&[
+            texture(dir.join("oak.png"), Some("lamp")),
+            texture(dir.join("shared_oak.png"), None),
+        ])?;
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].path, dir.join("oak.png"));
+        assert_eq!(duplicates[0].canonical, dir.join("shared_oak.png"));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
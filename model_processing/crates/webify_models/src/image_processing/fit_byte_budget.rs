@@ -0,0 +1,272 @@
+//! Shrink an encoded texture until its file fits within a byte budget
+
+use std::{fs, io::Error, iter, path::Path, result::Result};
+
+use image::{
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    DynamicImage, GenericImageView,
+};
+
+use crate::image_processing::{Dimensions, Image};
+use crate::options::ResizeFilter;
+
+/// Steps the quality of lossy formats is lowered by, in eighths of the quality it
+/// was encoded at, down to half of it
+const QUALITY_STEPS: u16 = 4;
+
+/// What was done to a texture to fit its byte budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteBudgetFit {
+    /// The texture once it fits, which may be in another format when the encoder
+    /// fell back to PNG
+    pub image: Image,
+    /// File size before and after, in bytes
+    pub bytes: (u64, u64),
+    /// Dimensions before and after
+    pub dimensions: (Dimensions, Dimensions),
+    /// Quality of lossy formats before and after
+    pub quality: (Option<u8>, Option<u8>),
+}
+
+impl ByteBudgetFit {
+    /// What was done, e.g. `quality lowered from 80 to 60 and downscaled from
+    /// 2048x2048 to 1152x1152`
+    pub fn describe(&self) -> String {
+        let mut changes = Vec::new();
+        match self.quality {
+            (Some(from), Some(to)) if from != to => {
+                changes.push(format!("quality lowered from {} to {}", from, to))
+            }
+            _ => (),
+        }
+        let ((from_width, from_height), (to_width, to_height)) = self.dimensions;
+        if self.dimensions.0 != self.dimensions.1 {
+            changes.push(format!(
+                "downscaled from {}x{} to {}x{}",
+                from_width, from_height, to_width, to_height
+            ));
+        }
+        if changes.is_empty() {
+            changes.push(String::from("recompressed"));
+        }
+
+        changes.join(" and ")
+    }
+}
+
+/// Bring the encoded texture within `max_bytes`, encoding `source`, the pixels it
+/// was encoded from, again and again with `encode` until it fits: at a lower
+/// quality first, an eighth at a time down to half of `quality` for lossy formats
+/// or at the strongest PNG compression for lossless ones, then downscaled by a
+/// quarter at a time at that quality, resampled with `filter`. `encode` is handed
+/// the PNG written next to the texture and the quality to encode it at. Returns
+/// `None` when the texture already fits, and errors when it doesn't even at 1x1.
+pub fn fit_byte_budget<F>(
+    image: Image,
+    source: &DynamicImage,
+    max_bytes: u64,
+    quality: Option<u8>,
+    filter: ResizeFilter,
+    mut encode: F,
+) -> Result<Option<ByteBudgetFit>, Error>
+where
+    F: FnMut(Image, Option<u8>) -> Result<Image, Error>,
+{
+    let from_bytes = fs::metadata(&image.path)?.len();
+    if from_bytes <= max_bytes {
+        return Ok(None);
+    }
+
+    let from_dimensions = source.dimensions();
+    let qualities: Vec<Option<u8>> = match quality {
+        Some(quality) => (1..=QUALITY_STEPS)
+            .map(|step| {
+                let lowered = u16::from(quality) * (8 - step) / 8;
+                Some(lowered.max(1) as u8)
+            })
+            .collect(),
+        None => vec![None],
+    };
+    let lowest_quality = qualities[qualities.len() - 1];
+    let downscales = iter::successors(Some(from_dimensions), |&(width, height)| {
+        match (width, height) {
+            (1, 1) => None,
+            _ => Some(((width * 3 / 4).max(1), (height * 3 / 4).max(1))),
+        }
+    })
+    .skip(1);
+    let attempts = qualities
+        .into_iter()
+        .map(|quality| (from_dimensions, quality))
+        .chain(downscales.map(|dimensions| (dimensions, lowest_quality)));
+
+    let png_path = image.path.with_extension("png");
+    let mut current = image;
+    for ((width, height), attempt_quality) in attempts {
+        let resized = match (width, height) == from_dimensions {
+            true => None,
+            false => Some(source.resize_exact(width, height, filter.filter_type())),
+        };
+        save_compressed_png(resized.as_ref().unwrap_or(source), &png_path)?;
+
+        let previous = current.path.clone();
+        let png = Image {
+            path: png_path.clone(),
+            extension: String::from("png"),
+        };
+        current = encode(png, attempt_quality)?;
+        // The encoder kept the PNG this time, or encoded the one it kept before
+        if current.path != previous && previous.is_file() {
+            fs::remove_file(&previous)?;
+        }
+
+        let to_bytes = fs::metadata(&current.path)?.len();
+        if to_bytes <= max_bytes {
+            return Ok(Some(ByteBudgetFit {
+                image: current,
+                bytes: (from_bytes, to_bytes),
+                dimensions: (from_dimensions, (width, height)),
+                quality: (quality, attempt_quality),
+            }));
+        }
+    }
+
+    Err(Error::other(format!(
+        "{:?} is still {} bytes at 1x1, over the budget of {} bytes",
+        current.path,
+        fs::metadata(&current.path)?.len(),
+        max_bytes
+    )))
+}
+
+/// Save the image as a PNG at the strongest compression, slower to write but the
+/// smallest the image crate makes
+fn save_compressed_png(image: &DynamicImage, path: &Path) -> Result<(), Error> {
+    let file = fs::File::create(path)?;
+    let (width, height) = image.dimensions();
+    PngEncoder::new_with_quality(file, CompressionType::Best, FilterType::Paeth)
+        .encode(image.as_bytes(), width, height, image.color())
+        .map_err(|e| Error::other(format!("Could not save {:?}: {:?}", path, e)))
+}
+
+#[cfg(test)]
+mod fit_byte_budget_tests {
+    use super::*;
+
+    use image::{ImageBuffer, Rgb};
+
+    fn noise(size: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(size, size, |x, y| {
+            let seed = x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503);
+            let value = seed.wrapping_mul(seed) >> 8;
+            Rgb([value as u8, (value >> 8) as u8, (value >> 16) as u8])
+        }))
+    }
+
+    fn setup(test_run_id: &str, source: &DynamicImage) -> Result<Image, Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("noise.png");
+        source.save(&path).map_err(Error::other)?;
+
+        Ok(Image {
+            path,
+            extension: String::from("png"),
+        })
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(
+            Path::new("tests")
+                .join("image_processing")
+                .join(test_run_id),
+        )
+    }
+
+    #[test]
+    fn it_leaves_textures_within_the_budget_alone() -> Result<(), Error> {
+        let test_run_id = "test_run_fit_byte_budget_within";
+        let source = noise(16);
+        let image = setup(test_run_id, &source)?;
+
+        let fit = fit_byte_budget(
+            image,
+            &source,
+            u64::MAX,
+            None,
+            ResizeFilter::Triangle,
+            |_, _| panic!("no need to encode again"),
+        );
+
+        teardown(test_run_id)?;
+        assert_eq!(fit?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_lowers_the_quality_before_downscaling() -> Result<(), Error> {
+        let test_run_id = "test_run_fit_byte_budget_quality";
+        let source = noise(64);
+        let image = setup(test_run_id, &source)?;
+        let mut attempts = Vec::new();
+
+        // Every attempt above quality 40 or larger than 32px is over the budget
+        let fit = fit_byte_budget(
+            image,
+            &source,
+            4096,
+            Some(80),
+            ResizeFilter::Triangle,
+            |png, quality| {
+                let (width, _) = image::image_dimensions(&png.path).map_err(Error::other)?;
+                attempts.push((width, quality));
+                let bytes = match quality == Some(40) && width <= 32 {
+                    true => 1024,
+                    false => 8192,
+                };
+                fs::write(&png.path, vec![0; bytes])?;
+                Ok(png)
+            },
+        );
+
+        teardown(test_run_id)?;
+        let fit = fit?.unwrap();
+        assert_eq!(
+            attempts,
+            vec![
+                (64, Some(70)),
+                (64, Some(60)),
+                (64, Some(50)),
+                (64, Some(40)),
+                (48, Some(40)),
+                (36, Some(40)),
+                (27, Some(40)),
+            ]
+        );
+        assert_eq!(fit.quality, (Some(80), Some(40)));
+        assert_eq!(fit.dimensions, ((64, 64), (27, 27)));
+        assert_eq!(fit.bytes.1, 1024);
+        assert_eq!(
+            fit.describe(),
+            "quality lowered from 80 to 40 and downscaled from 64x64 to 27x27"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_when_even_a_pixel_is_over_the_budget() -> Result<(), Error> {
+        let test_run_id = "test_run_fit_byte_budget_over";
+        let source = noise(8);
+        let image = setup(test_run_id, &source)?;
+
+        let fit = fit_byte_budget(image, &source, 1, None, ResizeFilter::Triangle, |png, _| {
+            Ok(png)
+        });
+
+        teardown(test_run_id)?;
+        assert!(fit.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Write the mip chain of a texture as a pyramid of PNGs next to it
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use image::GenericImageView;
+
+use crate::image_processing::DecodeCache;
+use crate::options::ResizeFilter;
+
+/// Suffix of the file stem of a mip level, followed by the level
+const MIP_SUFFIX: &str = "_mip";
+
+/// Write every level of the texture's mip chain below the full size one, halving
+/// each side until both are 1 pixel, as `name_mip1.png`, `name_mip2.png`… next to
+/// it. Each level is resampled from the previous one with `filter`. Levels left by
+/// a previous run of a larger texture are removed. Returns the levels written.
+pub fn generate_mip_chain(
+    path: &Path,
+    filter: ResizeFilter,
+    cache: &mut DecodeCache,
+) -> Result<Vec<PathBuf>, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to generate its mip chain: {:?}",
+            path, e
+        ))
+    })?;
+
+    let mut levels = Vec::new();
+    let mut level = img.clone();
+    while level.width() > 1 || level.height() > 1 {
+        level = level.resize_exact(
+            (level.width() / 2).max(1),
+            (level.height() / 2).max(1),
+            filter.filter_type(),
+        );
+        let level_path = mip_level_path(path, levels.len() as u32 + 1);
+        level.save(&level_path).map_err(|e| {
+            Error::other(format!(
+                "Could not save mip level {:?}: {:?}",
+                level_path, e
+            ))
+        })?;
+        levels.push(level_path);
+    }
+    cache.insert(path, img);
+
+    for stale in find_mip_levels(path).iter().skip(levels.len()) {
+        fs::remove_file(stale)?;
+    }
+
+    Ok(levels)
+}
+
+/// Levels of the texture's mip chain found next to it, from the largest
+pub fn find_mip_levels(path: &Path) -> Vec<PathBuf> {
+    (1..)
+        .map(|level| mip_level_path(path, level))
+        .take_while(|level_path| level_path.is_file())
+        .collect()
+}
+
+/// Path of the texture the file is a mip level of, without its extension since the
+/// texture may have been encoded to another format than the PNG levels, and the
+/// level. `None` when the file isn't named like a mip level.
+pub fn mip_level(path: &Path) -> Option<(PathBuf, u32)> {
+    let stem = path.file_stem()?.to_str()?;
+    let (base, level) = stem.rsplit_once(MIP_SUFFIX)?;
+    if base.is_empty() || level.is_empty() || !level.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((path.with_file_name(base), level.parse().ok()?))
+}
+
+/// Path of a level of the texture's mip chain
+fn mip_level_path(path: &Path, level: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}{}{}.png", stem, MIP_SUFFIX, level))
+}
+
+#[cfg(test)]
+mod generate_mip_chain_tests {
+    use super::*;
+
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn it_halves_the_texture_down_to_a_pixel() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_generate_mip_chain");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("wood.png");
+        ImageBuffer::from_pixel(8, 2, Rgb([200u8, 100, 50]))
+            .save(&path)
+            .map_err(Error::other)?;
+        // Left by a run before the texture was downscaled
+        fs::write(dir.join("wood_mip4.png"), "stale")?;
+        fs::write(dir.join("wood_mip5.png"), "stale")?;
+
+        let mut cache = DecodeCache::default();
+        let levels = generate_mip_chain(&path, ResizeFilter::Lanczos3, &mut cache)?;
+
+        assert_eq!(
+            levels,
+            vec![
+                dir.join("wood_mip1.png"),
+                dir.join("wood_mip2.png"),
+                dir.join("wood_mip3.png")
+            ]
+        );
+        let dimensions: Vec<(u32, u32)> = levels
+            .iter()
+            .map(|level| image::image_dimensions(level).unwrap())
+            .collect();
+        assert_eq!(dimensions, vec![(4, 1), (2, 1), (1, 1)]);
+        assert_eq!(find_mip_levels(&path), levels);
+        assert!(!dir.join("wood_mip4.png").exists());
+        assert!(!dir.join("wood_mip5.png").exists());
+        // Kept for the encoder
+        assert_eq!(cache.take(&path).map(|img| img.dimensions()), Some((8, 2)));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_recognizes_mip_levels_by_name() {
+        let textures = Path::new("materials").join("textures");
+        assert_eq!(
+            mip_level(&textures.join("wood_mip12.png")),
+            Some((textures.join("wood"), 12))
+        );
+        assert_eq!(
+            mip_level(&textures.join("wood_mip_mip1.png")),
+            Some((textures.join("wood_mip"), 1))
+        );
+        assert_eq!(mip_level(&textures.join("wood.png")), None);
+        assert_eq!(mip_level(&textures.join("wood_mip.png")), None);
+        assert_eq!(mip_level(&textures.join("_mip1.png")), None);
+        assert_eq!(mip_level(&textures.join("wood_mipmap.png")), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Keep track of the names textures had before being named after their contents
+
+use std::{collections::BTreeMap, fs, io::Error, path::Path, path::PathBuf, result::Result};
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{Metadata, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::mesh_update::relative_path;
+
+/// Name of the file mapping the textures to their content hash names, at the root
+/// of the library
+pub const HASHED_NAMES_FILE_NAME: &str = "webify-hashes.json";
+
+/// Paths of the textures named after their contents by `--hash-names`, relative
+/// to the library, keyed by the paths they had before, so a CDN can serve them
+/// with immutable cache headers and tools can still find them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HashedNames(pub BTreeMap<String, String>);
+
+impl JsonSchema for HashedNames {
+    fn schema_name() -> String {
+        String::from("HashedNames")
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut schema: SchemaObject = <BTreeMap<String, String>>::json_schema(gen).into();
+        schema.metadata = Some(Box::new(Metadata {
+            description: Some(String::from(
+                "Paths of the textures named after their contents, relative to the library, \
+                 keyed by the paths they had before",
+            )),
+            ..Default::default()
+        }));
+        schema.into()
+    }
+}
+
+impl HashedNames {
+    /// The names recorded by the previous runs on the library in `dir`, if any
+    pub fn read(dir: &Path) -> Result<HashedNames, Error> {
+        let path = dir.join(HASHED_NAMES_FILE_NAME);
+        match path.is_file() {
+            true => serde_json::from_str(&fs::read_to_string(path)?).map_err(Error::other),
+            false => Ok(HashedNames::default()),
+        }
+    }
+
+    /// Record the renames of a run. Textures renamed again, since their contents
+    /// changed, keep the path they had before any rename.
+    pub fn record(&mut self, dir: &Path, renames: &[(PathBuf, PathBuf)]) {
+        for (from, to) in renames {
+            let from = relative_path(dir, from);
+            let to = relative_path(dir, to);
+            let mut renamed_again = false;
+            for name in self.0.values_mut().filter(|name| **name == from) {
+                *name = to.clone();
+                renamed_again = true;
+            }
+            if !renamed_again {
+                self.0.insert(from, to);
+            }
+        }
+    }
+
+    /// Write the names to the library in `dir`
+    pub fn write(&self, dir: &Path) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(self).map_err(Error::other)?;
+        fs::write(dir.join(HASHED_NAMES_FILE_NAME), contents)
+    }
+}
+
+#[cfg(test)]
+mod hashed_names_tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_original_names_across_runs() {
+        let dir = Path::new("library");
+        let mut names = HashedNames::default();
+        names.record(
+            dir,
+            &[(
+                dir.join("desk").join("wood.png"),
+                dir.join("desk").join("0a1b.png"),
+            )],
+        );
+        names.record(
+            dir,
+            &[(
+                dir.join("desk").join("0a1b.png"),
+                dir.join("desk").join("2c3d.png"),
+            )],
+        );
+
+        let mut expected = BTreeMap::new();
+        expected.insert(String::from("desk/wood.png"), String::from("desk/2c3d.png"));
+        assert_eq!(names, HashedNames(expected));
+    }
+}
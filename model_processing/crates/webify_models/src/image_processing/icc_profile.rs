@@ -0,0 +1,382 @@
+//! The colorspace of an ICC profile, to convert pixels to sRGB
+
+use std::{
+    io::{Error, ErrorKind},
+    result::Result,
+};
+
+use image::{DynamicImage, Pixel};
+
+/// XYZ, relative to D50 as profiles are, to linear sRGB, Bradford adapted
+const XYZ_D50_TO_SRGB: [[f64; 3]; 3] = [
+    [3.1338561, -1.6168667, -0.4906146],
+    [-0.9787684, 1.9161415, 0.0334540],
+    [0.0719453, -0.2289914, 1.4052427],
+];
+
+/// The red, green and blue colorants of sRGB, in XYZ relative to D50
+const SRGB_COLORANTS: [[f64; 3]; 3] = [
+    [0.4361, 0.2225, 0.0139],
+    [0.3851, 0.7169, 0.0971],
+    [0.1431, 0.0606, 0.7141],
+];
+
+/// Entries of the table encoding linear light to sRGB
+const SRGB_TABLE_SIZE: usize = 4096;
+
+/// An RGB matrix/TRC ICC profile, like sRGB, Adobe RGB, ProPhoto or Display P3: a
+/// tone curve per channel followed by a matrix to XYZ
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    /// The red, green and blue colorants, in XYZ relative to D50
+    pub colorants: [[f64; 3]; 3],
+    /// The red, green and blue tone curves, from encoded values to linear light
+    curves: [ToneCurve; 3],
+}
+
+/// A tone curve of an ICC profile, from its `curv` or `para` tag
+#[derive(Debug, Clone, PartialEq)]
+enum ToneCurve {
+    /// Values sampled evenly over 0 to 1
+    Table(Vec<f64>),
+    /// The parametric function of the ICC specification, with its parameters g, a,
+    /// b, c, d, e and f; the simpler ones have `a` of 1 and the rest 0
+    Parametric([f64; 7]),
+}
+
+impl ToneCurve {
+    fn linearize(&self, value: f64) -> f64 {
+        match self {
+            ToneCurve::Table(table) if table.is_empty() => value,
+            ToneCurve::Table(table) if table.len() == 1 => value.powf(table[0]),
+            ToneCurve::Table(table) => {
+                let position = value.clamp(0.0, 1.0) * (table.len() - 1) as f64;
+                let below = position.floor() as usize;
+                let above = (below + 1).min(table.len() - 1);
+                let weight = position - below as f64;
+                table[below] * (1.0 - weight) + table[above] * weight
+            }
+            ToneCurve::Parametric([g, a, b, c, d, e, f]) => {
+                if value >= *d {
+                    (a * value + b).max(0.0).powf(*g) + e
+                } else {
+                    c * value + f
+                }
+            }
+        }
+    }
+}
+
+impl IccProfile {
+    /// Parse an ICC profile. Profiles of other data than RGB, or built on lookup
+    /// tables rather than tone curves and colorants, aren't supported.
+    pub fn parse(bytes: &[u8]) -> Result<IccProfile, Error> {
+        let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, reason.to_string());
+        if bytes.len() < 132 || &bytes[36..40] != b"acsp" {
+            return Err(invalid("not an ICC profile"));
+        }
+        if &bytes[16..20] != b"RGB " {
+            return Err(invalid("not an RGB profile"));
+        }
+
+        let tag_count = read_u32(bytes, 128) as usize;
+        let tag = |signature: &[u8]| {
+            (0..tag_count)
+                .map(|i| 132 + i * 12)
+                .take_while(|&entry| entry + 12 <= bytes.len())
+                .find(|&entry| &bytes[entry..entry + 4] == signature)
+                .map(|entry| {
+                    let offset = read_u32(bytes, entry + 4) as usize;
+                    let size = read_u32(bytes, entry + 8) as usize;
+                    bytes.get(offset..offset.saturating_add(size))
+                })
+        };
+
+        let mut colorants = [[0.0; 3]; 3];
+        for (colorant, signature) in colorants.iter_mut().zip([b"rXYZ", b"gXYZ", b"bXYZ"].iter()) {
+            let data = tag(*signature)
+                .ok_or_else(|| invalid("no colorants, a lookup table profile"))?
+                .filter(|data| data.len() >= 20 && &data[..4] == b"XYZ ")
+                .ok_or_else(|| invalid("invalid colorant"))?;
+            for (i, value) in colorant.iter_mut().enumerate() {
+                *value = read_s15_fixed16(data, 8 + i * 4);
+            }
+        }
+
+        let mut curves = Vec::with_capacity(3);
+        for signature in [b"rTRC", b"gTRC", b"bTRC"].iter() {
+            let data = tag(*signature)
+                .ok_or_else(|| invalid("no tone curves, a lookup table profile"))?
+                .ok_or_else(|| invalid("invalid tone curve"))?;
+            curves.push(read_tone_curve(data).ok_or_else(|| invalid("invalid tone curve"))?);
+        }
+        let blue = curves.pop().unwrap_or(ToneCurve::Table(Vec::new()));
+        let green = curves.pop().unwrap_or(ToneCurve::Table(Vec::new()));
+        let red = curves.pop().unwrap_or(ToneCurve::Table(Vec::new()));
+
+        Ok(IccProfile {
+            colorants,
+            curves: [red, green, blue],
+        })
+    }
+
+    /// Whether the profile is sRGB, give or take the rounding of its tags, so its
+    /// pixels are sRGB already
+    pub fn is_srgb(&self) -> bool {
+        let same_colorants = self
+            .colorants
+            .iter()
+            .flatten()
+            .zip(SRGB_COLORANTS.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 0.002);
+        let same_curves = self.curves.iter().all(|curve| {
+            (0..=16).all(|i| {
+                let value = f64::from(i) / 16.0;
+                (curve.linearize(value) - srgb_to_linear(value)).abs() < 0.002
+            })
+        });
+
+        same_colorants && same_curves
+    }
+
+    /// Convert the 8 bit RGB pixels of the image from the profile's colorspace to
+    /// sRGB, colors out of the sRGB gamut being clipped. Alpha is kept, and other
+    /// images are returned as they are.
+    pub fn convert_to_srgb(&self, image: DynamicImage) -> DynamicImage {
+        // Encoded value to linear light, per channel
+        let mut linear = [[0.0; 256]; 3];
+        for (table, curve) in linear.iter_mut().zip(self.curves.iter()) {
+            for (value, entry) in table.iter_mut().enumerate() {
+                *entry = curve.linearize(value as f64 / 255.0);
+            }
+        }
+        // Profile RGB to XYZ to linear sRGB
+        let mut matrix = [[0.0; 3]; 3];
+        for (row, xyz_row) in matrix.iter_mut().zip(XYZ_D50_TO_SRGB.iter()) {
+            for (column, entry) in row.iter_mut().enumerate() {
+                *entry = (0..3).map(|i| xyz_row[i] * self.colorants[column][i]).sum();
+            }
+        }
+        let encode: Vec<u8> = (0..SRGB_TABLE_SIZE)
+            .map(|i| {
+                let value = linear_to_srgb(i as f64 / (SRGB_TABLE_SIZE - 1) as f64);
+                (value * 255.0).round() as u8
+            })
+            .collect();
+
+        let convert = |rgb: &mut [u8]| {
+            let light = [
+                linear[0][rgb[0] as usize],
+                linear[1][rgb[1] as usize],
+                linear[2][rgb[2] as usize],
+            ];
+            for (channel, row) in rgb.iter_mut().zip(matrix.iter()) {
+                let value = row[0] * light[0] + row[1] * light[1] + row[2] * light[2];
+                let index = (value.clamp(0.0, 1.0) * (SRGB_TABLE_SIZE - 1) as f64).round();
+                *channel = encode[index as usize];
+            }
+        };
+
+        match image {
+            DynamicImage::ImageRgb8(mut rgb) => {
+                rgb.pixels_mut()
+                    .for_each(|pixel| convert(pixel.channels_mut()));
+                DynamicImage::ImageRgb8(rgb)
+            }
+            DynamicImage::ImageRgba8(mut rgba) => {
+                rgba.pixels_mut()
+                    .for_each(|pixel| convert(&mut pixel.channels_mut()[..3]));
+                DynamicImage::ImageRgba8(rgba)
+            }
+            image => image,
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    bytes
+        .get(offset..offset + 4)
+        .map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_s15_fixed16(bytes: &[u8], offset: usize) -> f64 {
+    f64::from(read_u32(bytes, offset) as i32) / 65536.0
+}
+
+/// Read a `curv` or `para` tag
+fn read_tone_curve(data: &[u8]) -> Option<ToneCurve> {
+    match data.get(..4)? {
+        b"curv" => {
+            let count = read_u32(data, 8) as usize;
+            let values = data.get(12..12 + count * 2)?;
+            let values = values
+                .chunks_exact(2)
+                .map(|v| u16::from_be_bytes([v[0], v[1]]));
+            Some(ToneCurve::Table(if count == 1 {
+                // A single value is a gamma, in u8Fixed8
+                values.map(|gamma| f64::from(gamma) / 256.0).collect()
+            } else {
+                values.map(|value| f64::from(value) / 65535.0).collect()
+            }))
+        }
+        b"para" => {
+            let function = u16::from_be_bytes([*data.get(8)?, *data.get(9)?]);
+            let count = [1, 3, 4, 5, 7].get(usize::from(function)).copied()?;
+            if data.len() < 12 + count * 4 {
+                return None;
+            }
+            let p: Vec<f64> = (0..count)
+                .map(|i| read_s15_fixed16(data, 12 + i * 4))
+                .collect();
+            Some(ToneCurve::Parametric(match function {
+                0 => [p[0], 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                // Y = (aX + b)^g past -b/a, 0 before
+                1 => [p[0], p[1], p[2], 0.0, -p[2] / p[1], 0.0, 0.0],
+                // Y = (aX + b)^g + c past -b/a, c before
+                2 => [p[0], p[1], p[2], 0.0, -p[2] / p[1], p[3], p[3]],
+                3 => [p[0], p[1], p[2], p[3], p[4], 0.0, 0.0],
+                _ => [p[0], p[1], p[2], p[3], p[4], p[5], p[6]],
+            }))
+        }
+        _ => None,
+    }
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod icc_profile_tests {
+    use super::*;
+
+    use image::{Rgb, RgbImage};
+
+    /// Adobe RGB (1998) colorants, in XYZ relative to D50
+    const ADOBE_RGB_COLORANTS: [[f64; 3]; 3] = [
+        [0.6097, 0.3111, 0.0195],
+        [0.2053, 0.6257, 0.0609],
+        [0.1492, 0.0632, 0.7446],
+    ];
+
+    fn s15_fixed16(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    /// A matrix/TRC profile of the colorants, with a gamma tone curve
+    fn write_icc_profile(colorants: [[f64; 3]; 3], gamma: f64) -> Vec<u8> {
+        let mut tags: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+        for (signature, colorant) in [b"rXYZ", b"gXYZ", b"bXYZ"].iter().zip(colorants.iter()) {
+            let mut data = b"XYZ \0\0\0\0".to_vec();
+            colorant
+                .iter()
+                .for_each(|value| data.extend_from_slice(&s15_fixed16(*value)));
+            tags.push((*signature, data));
+        }
+        for signature in [b"rTRC", b"gTRC", b"bTRC"].iter() {
+            let mut data = b"curv\0\0\0\0".to_vec();
+            data.extend_from_slice(&1u32.to_be_bytes());
+            data.extend_from_slice(&((gamma * 256.0).round() as u16).to_be_bytes());
+            data.extend_from_slice(&[0, 0]);
+            tags.push((*signature, data));
+        }
+
+        let mut profile = vec![0u8; 128];
+        profile[12..16].copy_from_slice(b"mntr");
+        profile[16..20].copy_from_slice(b"RGB ");
+        profile[20..24].copy_from_slice(b"XYZ ");
+        profile[36..40].copy_from_slice(b"acsp");
+        profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + tags.len() * 12;
+        let mut data = Vec::new();
+        for (signature, tag) in &tags {
+            profile.extend_from_slice(*signature);
+            profile.extend_from_slice(&(offset as u32).to_be_bytes());
+            profile.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+            data.extend_from_slice(tag);
+        }
+        profile.extend(data);
+        let size = profile.len() as u32;
+        profile[..4].copy_from_slice(&size.to_be_bytes());
+        profile
+    }
+
+    #[test]
+    fn it_converts_adobe_rgb_to_srgb() -> Result<(), Error> {
+        let profile = IccProfile::parse(&write_icc_profile(ADOBE_RGB_COLORANTS, 2.2))?;
+        assert!(!profile.is_srgb());
+
+        let image = RgbImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgb([128, 128, 128])
+            } else {
+                Rgb([60, 200, 60])
+            }
+        });
+        let converted = profile
+            .convert_to_srgb(DynamicImage::ImageRgb8(image))
+            .to_rgb8();
+
+        // Grays stay gray, saturated colors get more saturated
+        let gray = converted.get_pixel(0, 0);
+        assert!(gray.0.iter().all(|&v| (v as i32 - 128).abs() <= 2));
+        let green = converted.get_pixel(1, 0);
+        assert!(green[0] < 30 && green[1] > 200 && green[2] < 60);
+        Ok(())
+    }
+
+    #[test]
+    fn it_recognizes_srgb_and_refuses_other_profiles() {
+        let srgb = write_icc_profile(SRGB_COLORANTS, 2.2);
+        let mut profile = IccProfile::parse(&srgb).unwrap();
+        assert!(!profile.is_srgb());
+        profile.curves = [
+            ToneCurve::Parametric([
+                2.4,
+                1.0 / 1.055,
+                0.055 / 1.055,
+                1.0 / 12.92,
+                0.04045,
+                0.0,
+                0.0,
+            ]),
+            ToneCurve::Parametric([
+                2.4,
+                1.0 / 1.055,
+                0.055 / 1.055,
+                1.0 / 12.92,
+                0.04045,
+                0.0,
+                0.0,
+            ]),
+            ToneCurve::Parametric([
+                2.4,
+                1.0 / 1.055,
+                0.055 / 1.055,
+                1.0 / 12.92,
+                0.04045,
+                0.0,
+                0.0,
+            ]),
+        ];
+        assert!(profile.is_srgb());
+
+        let mut cmyk = srgb;
+        cmyk[16..20].copy_from_slice(b"CMYK");
+        assert!(IccProfile::parse(&cmyk).is_err());
+        assert!(IccProfile::parse(b"not a profile").is_err());
+    }
+}
@@ -1,7 +1,7 @@
 //! Structure that represents an image reference
 
 use std::path::PathBuf;
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Image {
     /// File path of the image
     pub path: PathBuf,
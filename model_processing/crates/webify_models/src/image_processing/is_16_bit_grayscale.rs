@@ -0,0 +1,56 @@
+//! Tell 16 bit grayscale PNGs, like terrain heightmaps, from other textures
+
+use std::{fs::File, io::Error, path::Path, result::Result};
+
+use image::{codecs::png::PngDecoder, ColorType, ImageDecoder};
+
+/// Whether the PNG holds 16 bit grayscale samples, with or without alpha, read from
+/// its header. Those are usually heightmaps, which WebP, AVIF and KTX2 would squash
+/// to 8 bits, so they're kept as 16 bit PNGs.
+pub fn is_16_bit_grayscale(path: &Path) -> Result<bool, Error> {
+    let decoder = PngDecoder::new(File::open(path)?)
+        .map_err(|e| Error::other(format!("Could not read color type of {:?}: {:?}", path, e)))?;
+
+    Ok(matches!(
+        decoder.color_type(),
+        ColorType::L16 | ColorType::La16
+    ))
+}
+
+#[cfg(test)]
+mod is_16_bit_grayscale_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ImageBuffer, Luma, Rgb};
+
+    #[test]
+    fn it_tells_16_bit_grayscale_from_other_pngs() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_is_16_bit_grayscale");
+        fs::create_dir_all(&dir)?;
+        ImageBuffer::from_pixel(4, 4, Luma([40_000u16]))
+            .save(dir.join("heightmap.png"))
+            .map_err(Error::other)?;
+        ImageBuffer::from_pixel(4, 4, Luma([200u8]))
+            .save(dir.join("gray.png"))
+            .map_err(Error::other)?;
+        ImageBuffer::from_pixel(4, 4, Rgb([40_000u16, 0, 0]))
+            .save(dir.join("color.png"))
+            .map_err(Error::other)?;
+
+        let heightmap = is_16_bit_grayscale(&dir.join("heightmap.png"));
+        let gray = is_16_bit_grayscale(&dir.join("gray.png"));
+        let color = is_16_bit_grayscale(&dir.join("color.png"));
+        let missing = is_16_bit_grayscale(&dir.join("missing.png"));
+        fs::remove_dir_all(&dir)?;
+
+        assert!(heightmap?);
+        assert!(!gray?);
+        assert!(!color?);
+        assert!(missing.is_err());
+        Ok(())
+    }
+}
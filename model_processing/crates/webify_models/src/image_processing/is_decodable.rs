@@ -0,0 +1,26 @@
+//! Whether a texture format can be read, and so converted and measured
+
+use image::ImageFormat;
+
+/// Whether textures with the extension can be decoded, by the image crate or, for
+/// Photoshop, DDS and EXR files, by `decode_psd`, `decode_dds` and `decode_hdr`
+pub fn is_decodable(extension: &str) -> bool {
+    let extension = extension.to_lowercase();
+    ["psd", "dds", "exr"].contains(&extension.as_str())
+        || ImageFormat::from_extension(extension).is_some()
+}
+
+#[cfg(test)]
+mod is_decodable_tests {
+    use super::*;
+
+    #[test]
+    fn it_knows_the_decodable_formats() {
+        assert!(is_decodable("bmp"));
+        assert!(is_decodable("PSD"));
+        assert!(is_decodable("dds"));
+        assert!(is_decodable("exr"));
+        assert!(is_decodable("hdr"));
+        assert!(!is_decodable("ktx2"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Tell tangent space normal maps from color textures
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::RgbaImage;
+
+use crate::image_processing::DecodeCache;
+
+/// Last words of the file stems of normal maps, e.g. `wall_normal` or `wall-nrm`
+pub const NORMAL_MAP_SUFFIXES: [&str; 8] = [
+    "normal",
+    "normals",
+    "normalmap",
+    "nrm",
+    "nrml",
+    "norm",
+    "nor",
+    "n",
+];
+
+/// Most pixels sampled to measure a texture, the others are skipped evenly
+const MAX_SAMPLES: u32 = 65_536;
+
+/// Share of the sampled pixels that must hold a unit vector facing out
+const MIN_UNIT_SHARE: f64 = 0.9;
+
+/// Whether the file is named like a normal map: its stem ends with one of the
+/// `NORMAL_MAP_SUFFIXES` after a `_`, `-`, `.` or space, whatever the case
+pub fn has_normal_map_name(path: &Path) -> bool {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut words = stem.split(['_', '-', '.', ' ']);
+    let last = words.next_back().unwrap_or_default();
+    // A stem of a single word is only a suffix when it's spelled out
+    let has_prefix = words.next().is_some();
+    NORMAL_MAP_SUFFIXES.contains(&last) && (has_prefix || last.starts_with("normal"))
+}
+
+/// Whether the texture is a tangent space normal map, by its name or, for the
+/// ones named otherwise, by its pixels: red and green centered on the middle of
+/// their range, blue high, and most pixels decoding to a unit vector facing out of
+/// the surface. Normal maps hold directions rather than colors, so they must stay
+/// out of sRGB conversions and lossy color quantization.
+pub fn is_normal_map(path: &Path, cache: &mut DecodeCache) -> Result<bool, Error> {
+    if has_normal_map_name(path) {
+        return Ok(true);
+    }
+
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to tell if it's a normal map: {:?}",
+            path, e
+        ))
+    })?;
+    let is_normal_map = looks_like_normal_map(&img.to_rgba8());
+    cache.insert(path, img);
+
+    Ok(is_normal_map)
+}
+
+/// Whether the channel statistics of the pixels are the ones of a normal map
+fn looks_like_normal_map(img: &RgbaImage) -> bool {
+    let pixels = u64::from(img.width()) * u64::from(img.height());
+    if pixels == 0 {
+        return false;
+    }
+    let step = (pixels / u64::from(MAX_SAMPLES)).max(1) as usize;
+
+    let (mut samples, mut units) = (0.0, 0.0);
+    let mut sums = [0.0; 3];
+    for pixel in img.pixels().step_by(step) {
+        // Channels decoded from 0..255 to the -1..1 of the vector
+        let [x, y, z] = [0, 1, 2].map(|i| f64::from(pixel.0[i]) / 127.5 - 1.0);
+        let length = (x * x + y * y + z * z).sqrt();
+        if (0.8..=1.2).contains(&length) && z > -0.05 {
+            units += 1.0;
+        }
+        for (sum, value) in sums.iter_mut().zip([x, y, z]) {
+            *sum += value;
+        }
+        samples += 1.0;
+    }
+
+    let [x, y, z] = sums.map(|sum| sum / samples);
+    x.abs() < 0.2 && y.abs() < 0.2 && z > 0.4 && units / samples >= MIN_UNIT_SHARE
+}
+
+#[cfg(test)]
+mod is_normal_map_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use image::Rgba;
+
+    #[test]
+    fn it_tells_normal_maps_by_their_name() {
+        for name in [
+            "wall_normal.png",
+            "Wall_NRM.png",
+            "wall-n.jpg",
+            "wall_normals.tga",
+            "normal.png",
+            "NormalMap.png",
+        ] {
+            assert!(has_normal_map_name(Path::new(name)), "{}", name);
+        }
+        for name in [
+            "wall.png",
+            "abnormal.png",
+            "n.png",
+            "wall_nails.png",
+            "normalize.png",
+        ] {
+            assert!(!has_normal_map_name(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_tells_normal_maps_by_their_pixels() {
+        // A bumpy surface: normals leaning back and forth along X
+        let bumps = RgbaImage::from_fn(64, 64, |x, _| {
+            let angle = (f64::from(x) / 8.0).sin() * 0.5;
+            let encode = |value: f64| ((value + 1.0) * 127.5).round() as u8;
+            Rgba([encode(angle.sin()), 128, encode(angle.cos()), 255])
+        });
+        assert!(looks_like_normal_map(&bumps));
+
+        let flat = RgbaImage::from_pixel(8, 8, Rgba([128, 128, 255, 255]));
+        assert!(looks_like_normal_map(&flat));
+
+        let wood = RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([150 + (x % 40) as u8, 90, (y % 30) as u8, 255])
+        });
+        assert!(!looks_like_normal_map(&wood));
+        let gray = RgbaImage::from_pixel(8, 8, Rgba([200, 200, 200, 255]));
+        assert!(!looks_like_normal_map(&gray));
+        let sky = RgbaImage::from_fn(64, 64, |x, _| Rgba([40 + x as u8, 120, 230, 255]));
+        assert!(!looks_like_normal_map(&sky));
+    }
+
+    #[test]
+    fn it_reads_the_pixels_of_textures_named_otherwise() -> Result<(), Error> {
+        let dir = PathBuf::from("tests")
+            .join("image_processing")
+            .join("test_run_is_normal_map");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("bricks_bump.png");
+        RgbaImage::from_pixel(4, 4, Rgba([128, 128, 255, 255]))
+            .save(&path)
+            .map_err(Error::other)?;
+
+        let mut cache = DecodeCache::default();
+        let is_normal = is_normal_map(&path, &mut cache);
+        let missing = is_normal_map(&dir.join("missing.png"), &mut DecodeCache::default());
+        std::fs::remove_dir_all(&dir)?;
+
+        assert!(is_normal?);
+        assert_eq!(cache.len(), 1);
+        assert!(missing.is_err());
+        Ok(())
+    }
+}
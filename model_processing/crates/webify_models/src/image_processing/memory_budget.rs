@@ -0,0 +1,142 @@
+//! Gate concurrent decodes so their images fit in a memory budget together
+
+use std::{
+    fs,
+    path::Path,
+    sync::{Condvar, Mutex},
+};
+
+/// Bytes a decoded pixel takes in the worst case, RGBA with 32-bit float channels
+/// as HDR and EXR textures decode to
+const HDR_BYTES_PER_PIXEL: u64 = 16;
+
+/// Bytes a decoded pixel takes for every other texture, RGBA8
+const BYTES_PER_PIXEL: u64 = 4;
+
+/// Copies of a texture alive at once while it's processed: the decoded image and
+/// the one converted or resized from it
+const WORKING_COPIES: u64 = 2;
+
+/// Estimate how much memory processing the texture at `path` peaks at, from the
+/// dimensions in its header. Files whose header can't be read, like EXR, count as
+/// ten times their size, about as much as compression ever shrinks a texture.
+pub fn estimate_decode_memory(path: &Path) -> u64 {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let bytes_per_pixel = match extension.as_str() {
+        "exr" | "hdr" => HDR_BYTES_PER_PIXEL,
+        _ => BYTES_PER_PIXEL,
+    };
+    let decoded = match image::image_dimensions(path) {
+        Ok((width, height)) => u64::from(width) * u64::from(height) * bytes_per_pixel,
+        Err(_) => fs::metadata(path).map_or(0, |m| m.len()) * 10,
+    };
+    decoded * WORKING_COPIES
+}
+
+/// Memory budget shared by the workers of a pool. Each worker reserves the estimate
+/// of what it's about to decode and blocks until the reservations in flight leave
+/// room for it, so the pool never holds more than the budget at once. A single
+/// texture larger than the whole budget is still let through, alone, rather than
+/// never processed.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    budget: u64,
+    used: Mutex<u64>,
+    released: Condvar,
+}
+
+/// Memory reserved from a `MemoryBudget`, given back when dropped
+#[derive(Debug)]
+pub struct MemoryReservation<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl MemoryBudget {
+    pub fn new(budget: u64) -> MemoryBudget {
+        MemoryBudget {
+            budget,
+            used: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Reserve `bytes`, waiting for other reservations to be dropped until they fit
+    pub fn reserve(&self, bytes: u64) -> MemoryReservation<'_> {
+        let mut used = self.used.lock().unwrap();
+        while *used > 0 && *used + bytes > self.budget {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += bytes;
+        MemoryReservation {
+            budget: self,
+            bytes,
+        }
+    }
+
+    /// Bytes currently reserved
+    pub fn used(&self) -> u64 {
+        *self.used.lock().unwrap()
+    }
+}
+
+impl Drop for MemoryReservation<'_> {
+    fn drop(&mut self) {
+        *self.budget.used.lock().unwrap() -= self.bytes;
+        self.budget.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod memory_budget_tests {
+    use super::*;
+
+    use std::{
+        sync::atomic::{AtomicU64, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn it_estimates_from_the_dimensions() {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("example.jpg");
+        let (width, height) = image::image_dimensions(&path).unwrap();
+        assert_eq!(
+            estimate_decode_memory(&path),
+            u64::from(width) * u64::from(height) * 4 * 2
+        );
+    }
+
+    #[test]
+    fn it_never_exceeds_the_budget() {
+        let budget = MemoryBudget::new(100);
+        let peak = AtomicU64::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _reservation = budget.reserve(40);
+                    peak.fetch_max(budget.used(), Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(5));
+                });
+            }
+        });
+        assert!(peak.load(Ordering::SeqCst) <= 80);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn it_lets_oversized_reservations_through_alone() {
+        let budget = MemoryBudget::new(100);
+        let reservation = budget.reserve(500);
+        assert_eq!(budget.used(), 500);
+        drop(reservation);
+        assert_eq!(budget.used(), 0);
+    }
+}
@@ -1,14 +1,118 @@
 //! Converts all texture images in a model to be PNG, and update the relevant paths
 
+pub mod adjust_colors;
+pub mod basis_to_ktx2;
+pub mod check_texture_size;
+pub mod collapse_gray_channels;
+pub mod content_hash_names;
+pub mod convert_gif_to_sprite_sheet;
 pub mod convert_to_png;
+pub mod convert_to_webp;
+pub mod decode_cache;
+pub mod decode_dds;
+pub mod decode_gif;
+pub mod decode_hdr;
+pub mod decode_jpeg;
+pub mod decode_psd;
+pub mod decode_texture;
+pub mod decode_tiff;
+pub mod decode_webp;
+pub mod downscale_texture;
+pub mod encode_avif;
+pub mod encode_ktx2;
+pub mod estimate_gpu_memory;
+pub mod find_duplicate_textures;
+pub mod fit_byte_budget;
+pub mod generate_mip_chain;
+pub mod hashed_names;
+pub mod icc_profile;
 pub mod image;
+pub mod is_16_bit_grayscale;
+pub mod is_decodable;
+pub mod is_normal_map;
+pub mod memory_budget;
 pub mod move_to_textures_dir;
+pub mod pool_shared_textures;
 pub mod process;
+pub mod quantize_palette;
+pub mod read_avif_info;
+pub mod read_jpeg_icc_profile;
+pub mod read_ktx2_info;
+pub mod read_sprite_sheet;
+pub mod read_webp_info;
+pub mod record_texture_stats;
+pub mod reduce_channels;
+pub mod remove_duplicate_textures;
+pub mod rename_textures;
+pub mod render_watermark;
+pub mod resize_to_power_of_two;
 pub mod scan_dir_for_images;
+pub mod sprite_sheet_metadata;
+pub mod strip_metadata;
+pub mod strip_opaque_alpha;
+pub mod tag_colorspace;
+pub mod texture_extensions;
+pub mod texture_pool;
+pub mod texture_role;
+pub mod thumbnail_data_uri;
+pub mod watermark_texture;
 
 pub use self::image::Image;
 
+pub use self::adjust_colors::adjust_colors;
+pub use self::basis_to_ktx2::{basis_to_ktx2, KTX2_IDENTIFIER};
+pub use self::check_texture_size::{check_texture_size, WEBGL_MAX_TEXTURE_DIMENSION};
+pub use self::collapse_gray_channels::collapse_gray_channels;
+pub use self::content_hash_names::content_hash_names;
+pub use self::convert_gif_to_sprite_sheet::convert_gif_to_sprite_sheet;
 pub use self::convert_to_png::convert_to_png;
+pub use self::convert_to_webp::convert_to_webp;
+pub use self::decode_cache::{DecodeCache, DEFAULT_DECODE_CACHE_BUDGET};
+pub use self::decode_dds::{decode_dds, decode_dds_bytes};
+pub use self::decode_gif::decode_gif;
+pub use self::decode_hdr::decode_hdr;
+pub use self::decode_jpeg::decode_jpeg;
+pub use self::decode_psd::{decode_psd, decode_psd_bytes};
+pub use self::decode_texture::decode_texture;
+pub use self::decode_tiff::decode_tiff;
+pub use self::decode_webp::decode_webp;
+pub use self::downscale_texture::{downscale_texture, Dimensions};
+pub use self::encode_avif::encode_avif;
+pub use self::encode_ktx2::encode_ktx2;
+pub use self::estimate_gpu_memory::{
+    estimate_gpu_memory, TRANSCODED_BYTES_PER_PIXEL, UNCOMPRESSED_BYTES_PER_PIXEL,
+};
+pub use self::find_duplicate_textures::find_duplicate_textures;
+pub use self::fit_byte_budget::{fit_byte_budget, ByteBudgetFit};
+pub use self::generate_mip_chain::{find_mip_levels, generate_mip_chain, mip_level};
+pub use self::hashed_names::{HashedNames, HASHED_NAMES_FILE_NAME};
+pub use self::icc_profile::IccProfile;
+pub use self::is_16_bit_grayscale::is_16_bit_grayscale;
+pub use self::is_decodable::is_decodable;
+pub use self::is_normal_map::{has_normal_map_name, is_normal_map, NORMAL_MAP_SUFFIXES};
+pub use self::memory_budget::{estimate_decode_memory, MemoryBudget, MemoryReservation};
 pub use self::move_to_textures_dir::move_to_textures_dir;
+pub use self::pool_shared_textures::pool_shared_textures;
 pub use self::process::process;
+pub use self::quantize_palette::quantize_palette;
+pub use self::read_avif_info::{read_avif_info, read_avif_info_bytes};
+pub use self::read_jpeg_icc_profile::read_jpeg_icc_profile;
+pub use self::read_ktx2_info::{read_ktx2_info, read_ktx2_info_bytes, Ktx2Info};
+pub use self::read_sprite_sheet::read_sprite_sheet;
+pub use self::read_webp_info::read_webp_info;
+pub use self::record_texture_stats::record_texture_stats;
+pub use self::reduce_channels::reduce_channels;
+pub use self::remove_duplicate_textures::remove_duplicate_textures;
+pub use self::rename_textures::rename_textures;
+pub use self::render_watermark::render_watermark;
+pub use self::resize_to_power_of_two::resize_to_power_of_two;
 pub use self::scan_dir_for_images::scan_dir_for_images;
+pub use self::sprite_sheet_metadata::SpriteSheetMetadata;
+pub use self::strip_metadata::strip_metadata;
+pub use self::strip_opaque_alpha::strip_opaque_alpha;
+pub use self::tag_colorspace::tag_colorspace;
+pub use self::texture_extensions::{texture_extensions, TEXTURE_IMAGE_TYPES};
+pub use self::texture_pool::TexturePool;
+pub use self::texture_role::TextureRole;
+pub use self::thumbnail_data_uri::thumbnail_data_uri;
+pub use self::watermark_texture::watermark_texture;
@@ -2,14 +2,15 @@
 
 use std::{
     fs,
-    io::{Error, ErrorKind},
+    io::Error,
     path::{Path, PathBuf},
 };
 
 use crate::image_processing::Image;
+use crate::model::{locate_file, shared_textures_dir, FileLocation};
 
 /// Move any stray textures to the textures path (typically materials/textures)
-pub fn move_to_textures_dir<'a>(
+pub fn move_to_textures_dir(
     mut image: Image,
     base_path: &Path,
 ) -> std::result::Result<Image, std::io::Error> {
@@ -17,7 +18,7 @@ pub fn move_to_textures_dir<'a>(
         .path
         .file_name()
         .and_then(|f| f.to_str())
-        .ok_or(Error::new(ErrorKind::Other, "Path not provided, no work to do"))?;
+        .ok_or_else(|| Error::other("Path not provided, no work to do"))?;
     let textures_path: PathBuf = Path::new("materials").join("textures").join(file_name);
     let meshes_path: PathBuf = Path::new("meshes").join(file_name);
 
@@ -35,30 +36,61 @@ pub fn move_to_textures_dir<'a>(
     Ok(image)
 }
 
-/// Get the root path of the model and tack on the texture path
+/// Get the root path of the model and tack on the texture path. Textures shared
+/// by a collection go to the collection's shared textures directory instead, so
+/// they aren't duplicated into every model of the collection.
 fn get_new_textures_path(
     image: &Image,
     base_path: &Path,
 ) -> std::result::Result<PathBuf, std::io::Error> {
-    let mut model_path_ancestors = image.path.strip_prefix(base_path).unwrap().ancestors();
-    let model_path = model_path_ancestors
-        .nth(model_path_ancestors.count() - 2)
-        .unwrap(); // There must be a better way to do this...
-    let new_textures_path = base_path
-        .join(model_path)
-        .join("materials")
-        .join("textures");
+    let new_textures_path = match locate_file(&image.path, base_path)? {
+        FileLocation::Model(model_dir) => model_dir.join("materials").join("textures"),
+        FileLocation::Collection(collection_dir) => shared_textures_dir(&collection_dir),
+    };
 
     Ok(new_textures_path)
 }
 
 #[cfg(test)]
 mod move_to_textures_dir_tests {
+    use super::*;
 
     #[test]
-    #[ignore = "not yet implemented"]
-    fn it_moves_the_files_to_the_textures_dir() {
-        assert!(false);
+    fn it_moves_the_files_to_the_textures_dir() -> std::result::Result<(), std::io::Error> {
+        let base_path = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_move_to_textures_dir");
+        let model_dir = base_path.join("box");
+        let textures_dir = model_dir.join("materials").join("textures");
+        fs::create_dir_all(&textures_dir)?;
+        fs::write(model_dir.join("model.config"), "<model/>")?;
+        fs::write(model_dir.join("box_diffuse.png"), "stray")?;
+        fs::write(textures_dir.join("box_normal.png"), "in place")?;
+
+        let stray = move_to_textures_dir(
+            Image {
+                path: model_dir.join("box_diffuse.png"),
+                extension: String::from("png"),
+            },
+            &base_path,
+        );
+        let in_place = move_to_textures_dir(
+            Image {
+                path: textures_dir.join("box_normal.png"),
+                extension: String::from("png"),
+            },
+            &base_path,
+        );
+        let moved = fs::read_to_string(textures_dir.join("box_diffuse.png"));
+        let left_behind = model_dir.join("box_diffuse.png").exists();
+        fs::remove_dir_all(&base_path)?;
+
+        assert_eq!(stray?.path, textures_dir.join("box_diffuse.png"));
+        assert_eq!(in_place?.path, textures_dir.join("box_normal.png"));
+        assert_eq!(moved?, "stray");
+        assert!(!left_behind);
+
+        Ok(())
     }
 }
 
@@ -82,4 +114,34 @@ mod get_new_textures_path_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn it_keeps_collection_textures_shared() -> std::result::Result<(), std::io::Error> {
+        let base_path = Path::new("tests").join("model");
+        let img = Image {
+            path: base_path.join("collection").join("media").join("wood.jpg"),
+            extension: String::from("jpg"),
+        };
+        let result = get_new_textures_path(&img, &base_path)?;
+        assert_eq!(
+            result,
+            base_path
+                .join("collection")
+                .join("media")
+                .join("materials")
+                .join("textures")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_when_the_image_is_not_in_a_model() {
+        let base_path = Path::new("some").join("random").join("path");
+        let img = Image {
+            path: base_path.join("foo.jpg"),
+            extension: String::from("jpg"),
+        };
+        assert!(get_new_textures_path(&img, &base_path).is_err());
+    }
 }
@@ -0,0 +1,181 @@
+//! Move textures duplicated across the models of a collection to its shared pool
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::hashing::hash_file;
+use crate::image_processing::texture_pool::TexturePool;
+use crate::model::{find_model_dirs, shared_textures_dir};
+use crate::options::Removal;
+use crate::trash::remove_file;
+
+/// For every collection under `dir`, move the textures its models have identical
+/// copies of to the collection's shared textures directory, and delete the per-model
+/// duplicates. A model's copy that differs from the rest is left alone, so the model
+/// keeps using its own version.
+pub fn pool_shared_textures(
+    dir: &Path,
+    removal: &Removal,
+) -> std::result::Result<TexturePool, std::io::Error> {
+    let mut pool = TexturePool::new();
+
+    for (collection_dir, model_dirs) in find_collections(dir)? {
+        let pool_dir = shared_textures_dir(&collection_dir);
+
+        // Texture file name -> models having a copy of it
+        let mut candidates: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for model_dir in &model_dirs {
+            let textures_dir = model_dir.join("materials").join("textures");
+            if !textures_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(textures_dir)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+                    candidates
+                        .entry(file_name)
+                        .or_default()
+                        .push(model_dir.clone());
+                }
+            }
+        }
+
+        for (file_name, models) in candidates {
+            let pooled = pool_dir.join(&file_name);
+            let texture_path = |model_dir: &Path| {
+                model_dir
+                    .join("materials")
+                    .join("textures")
+                    .join(&file_name)
+            };
+
+            let mut copies: Vec<(&PathBuf, String)> = Vec::new();
+            for model_dir in &models {
+                copies.push((model_dir, hash_file(&texture_path(model_dir))?));
+            }
+
+            // Pool the contents most models agree on, unless the pool already has its own
+            let pooled_hash = if pooled.is_file() {
+                hash_file(&pooled)?
+            } else {
+                let count = |hash: &String| copies.iter().filter(|(_, h)| h == hash).count();
+                match copies.iter().map(|(_, h)| h).max_by_key(|h| count(h)) {
+                    Some(hash) if count(hash) > 1 => hash.clone(),
+                    _ => continue,
+                }
+            };
+
+            let duplicates: Vec<&PathBuf> = copies
+                .iter()
+                .filter(|(_, hash)| *hash == pooled_hash)
+                .map(|(model_dir, _)| *model_dir)
+                .collect();
+            if duplicates.is_empty() {
+                continue;
+            }
+
+            if !pooled.is_file() {
+                fs::create_dir_all(&pool_dir)?;
+                fs::copy(texture_path(duplicates[0]), &pooled)?;
+            }
+
+            // Reference the pooled copy before deleting anything, so a failure half way
+            // through never leaves a model without its texture
+            for model_dir in &duplicates {
+                pool.add_reference(&pooled, model_dir);
+            }
+            for model_dir in duplicates {
+                remove_file(&texture_path(model_dir), removal)?;
+            }
+        }
+    }
+
+    Ok(pool)
+}
+
+/// Every collection under `dir`, with the models it groups
+fn find_collections(
+    dir: &Path,
+) -> std::result::Result<BTreeMap<PathBuf, Vec<PathBuf>>, std::io::Error> {
+    let mut collections: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for model_dir in find_model_dirs(dir)? {
+        if let Some(collection_dir) = model_dir.parent().filter(|p| p.starts_with(dir)) {
+            collections
+                .entry(collection_dir.to_path_buf())
+                .or_default()
+                .push(model_dir);
+        }
+    }
+
+    Ok(collections)
+}
+
+#[cfg(test)]
+mod pool_shared_textures_tests {
+    use super::*;
+
+    fn setup(test_run_id: &str) -> std::result::Result<PathBuf, std::io::Error> {
+        let collection_dir = Path::new("tests")
+            .join("image_processing")
+            .join(test_run_id);
+
+        for (model, wood) in &[("lamp", "oak"), ("desk", "oak"), ("chair", "pine")] {
+            let model_dir = collection_dir.join(model);
+            let textures_dir = model_dir.join("materials").join("textures");
+            fs::create_dir_all(&textures_dir)?;
+            fs::write(model_dir.join("model.config"), "<model></model>")?;
+            fs::write(textures_dir.join("wood.png"), wood)?;
+            fs::write(textures_dir.join(format!("{}.png", model)), model)?;
+        }
+
+        Ok(collection_dir)
+    }
+
+    fn teardown(test_run_id: &str) -> std::result::Result<(), std::io::Error> {
+        fs::remove_dir_all(
+            Path::new("tests")
+                .join("image_processing")
+                .join(test_run_id),
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_pools_identical_textures_of_a_collection() -> std::result::Result<(), std::io::Error> {
+        let test_run_id = "test_run_pool_shared_textures";
+        let collection_dir = setup(test_run_id)?;
+        let textures = |model: &str| {
+            collection_dir
+                .join(model)
+                .join("materials")
+                .join("textures")
+        };
+
+        let pool = pool_shared_textures(&collection_dir, &Removal::Delete)?;
+
+        let pooled = shared_textures_dir(&collection_dir).join("wood.png");
+        assert_eq!(fs::read_to_string(&pooled)?, "oak");
+        assert_eq!(pool.reference_count(&pooled), 2);
+        assert!(!textures("lamp").join("wood.png").exists());
+        assert!(!textures("desk").join("wood.png").exists());
+
+        // Different contents, the chair keeps its own wood
+        assert_eq!(
+            fs::read_to_string(textures("chair").join("wood.png"))?,
+            "pine"
+        );
+        // Only used by a single model, nothing to share
+        assert!(textures("lamp").join("lamp.png").exists());
+        assert!(!shared_textures_dir(&collection_dir)
+            .join("lamp.png")
+            .exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
@@ -1,37 +1,1220 @@
 //! Orchestrator to convert texture images from whatever format they're in to PNG
 
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use console::style;
+use image::ImageFormat;
 
 use crate::cli::create_progress_bar;
-use crate::image_processing::{convert_to_png, move_to_textures_dir, scan_dir_for_images};
+use crate::config::{ColorAdjustment, WebifyConfig};
+use crate::hashing::hash_file;
+use crate::image_processing::{
+    adjust_colors, check_texture_size, collapse_gray_channels, convert_gif_to_sprite_sheet,
+    convert_to_png, convert_to_webp, downscale_texture, encode_avif, encode_ktx2, find_mip_levels,
+    fit_byte_budget, generate_mip_chain, has_normal_map_name, is_16_bit_grayscale, is_normal_map,
+    mip_level, move_to_textures_dir, quantize_palette, record_texture_stats, reduce_channels,
+    render_watermark, resize_to_power_of_two, scan_dir_for_images, strip_metadata,
+    strip_opaque_alpha, tag_colorspace, texture_extensions, thumbnail_data_uri, watermark_texture,
+    DecodeCache, Image, TextureRole, DEFAULT_DECODE_CACHE_BUDGET, WEBGL_MAX_TEXTURE_DIMENSION,
+};
+use crate::model::{locate_file, resolve_category, FileLocation};
+use crate::options::{
+    AvifSettings, BasisCodec, Colorspace, GifPolicy, HdrSettings, Ktx2Settings, OutputFormat,
+    OversizedTextures, Removal, RunOptions, Stage, WebpSettings,
+};
+use crate::pipeline::CancellationToken;
+use crate::provenance::{read_marker, write_marker, Encoding, ProcessedMarker};
+use crate::report::{
+    format_bytes, handle_failure, run_step, suggest_downscales, ColorAdjustmentPreview, ErrorCode,
+    Failure, RunReport, ScanStats, TextureStats, Warning,
+};
+use crate::walk::prioritize;
 
-/// Orchestrator to convert texture images from whatever format they're in to PNG
-pub fn process(dir: &Path) -> std::result::Result<(), std::io::Error> {
-    let images = scan_dir_for_images(dir).unwrap();
+/// Orchestrator to convert texture images from whatever format they're in to PNG.
+/// The `corrupt` textures, which can't be decoded, are left as they are.
+pub fn process(
+    dir: &Path,
+    options: &RunOptions,
+    config: &WebifyConfig,
+    corrupt: &HashSet<PathBuf>,
+    cancellation_token: &CancellationToken,
+    report: &mut RunReport,
+) -> std::result::Result<(), std::io::Error> {
+    let (mut images, scan) = scan_dir_for_images(dir, &texture_extensions(options, config))?;
+    // Mip levels written by a previous run go along with their texture
+    let stems: HashSet<PathBuf> = images
+        .iter()
+        .map(|image| image.path.with_extension(""))
+        .collect();
+    images.retain(
+        |image| !matches!(mip_level(&image.path), Some((base, _)) if stems.contains(&base)),
+    );
+    images.retain(|image| !corrupt.contains(&image.path));
+    let marker = ProcessedMarker::new(options, config);
+    // Rendered once, and scaled to each texture
+    let watermark = match &options.watermark {
+        Some(mark) if options.stages.is_enabled(Stage::Convert) => Some(render_watermark(mark)?),
+        _ => None,
+    };
+    // Textures a previous run marked with the same version and settings need no
+    // work, only their checks and stats: they're measured apart, without a pass
+    // through every stage, so a time budget goes to the others
+    let mut pending = Vec::new();
+    let mut up_to_date = Vec::new();
+    for image in images {
+        match read_marker(&image.path)?.filter(|m| m.same_settings(&marker)) {
+            Some(previous_marker) => up_to_date.push((image, previous_marker.encoding)),
+            None => pending.push(image),
+        }
+    }
+    let mut images = pending;
+    prioritize(&mut images, dir, &options.first, |image| &image.path);
+    report.scan = Some(ScanStats {
+        up_to_date: up_to_date.len() as u64,
+        ..scan
+    });
+    for (image, encoding) in up_to_date {
+        measure_texture(&image, encoding, dir, options, report)?;
+    }
     let image_bar = create_progress_bar(images.len() as u64);
+    let mut max_sizes: HashMap<PathBuf, Option<u32>> = HashMap::new();
+    let paths: Vec<PathBuf> = images.iter().map(|image| image.path.clone()).collect();
+    // Decoded once, then handed from conversion to downscaling to encoding. Within a
+    // memory budget, half of it is left to the copies the stages work on.
+    let mut decode_cache = DecodeCache::new(
+        options
+            .memory_budget
+            .map_or(DEFAULT_DECODE_CACHE_BUDGET, |budget| {
+                (budget / 2).min(DEFAULT_DECODE_CACHE_BUDGET)
+            }),
+    );
 
     image_bar.set_prefix("Texture Move");
-    for image in images {
+    for (index, image) in images.into_iter().enumerate() {
+        if cancellation_token.is_cancelled() {
+            image_bar.abandon_with_message("Image processing cancelled.");
+            report.remaining = paths[index..].to_vec();
+            return Ok(());
+        }
+
+        // Only the stages of the same texture share decoded images
+        decode_cache.clear();
         image_bar.inc(1);
+        let image_path = image.path.clone();
         let styled_path = style(image.path.to_string_lossy()).dim().to_string();
 
-        image_bar.set_message(&format!("Moving {} to textures directory...", styled_path));
-        let moved_image = move_to_textures_dir(image, dir)?;
+        // How the file was written, for the audit trail in the manifest. Files only
+        // moved around have none, kept as they came.
+        let mut encoding = None;
+        // The original as it came, for the audit log
+        let source_hash = match report.audit_log.is_some() {
+            true => Some(hash_file(&image_path)?),
+            false => None,
+        };
+
+        // Left where and as they are, animated or not, rather than losing frames
+        if options.gif == GifPolicy::Skip && image.extension == "gif" {
+            report.warnings.push(Warning::new(
+                ErrorCode::UnconvertedFormat,
+                "PNG Conversion",
+                image_path,
+                "left as is, GIFs are skipped with --gif skip",
+            ));
+            continue;
+        }
+
+        let moved_image = if options.stages.is_enabled(Stage::Move) {
+            image_bar.set_prefix("Texture Move");
+            image_bar.set_message(&format!("Moving {} to textures directory...", styled_path));
+            match run_step(
+                "Texture Move",
+                ErrorCode::TextureMove,
+                &image_path,
+                options,
+                report,
+                || move_to_textures_dir(image, dir),
+            )? {
+                Some(moved_image) => moved_image,
+                None => continue,
+            }
+        } else {
+            image
+        };
         let moved_image_path = style(moved_image.path.to_string_lossy()).dim().to_string();
         image_bar.set_message(&format!("Moved {} to {}", styled_path, moved_image_path));
 
-        image_bar.set_prefix("PNG Conversion");
-        if moved_image.extension == "png" {
+        let final_image = if !options.stages.is_enabled(Stage::Convert) {
+            moved_image
+        } else if moved_image.extension == "png" {
+            image_bar.set_prefix("PNG Conversion");
             image_bar.set_message(&format!("{} already in PNG, skipping", moved_image_path));
+            moved_image
         } else {
+            image_bar.set_prefix("PNG Conversion");
             image_bar.set_message(&format!("Converting {}...", moved_image_path));
-            convert_to_png(moved_image)?;
-            image_bar.set_message(&format!("{} converted!", moved_image_path));
+            let moved_path = moved_image.path.clone();
+            let converted = match run_step(
+                "PNG Conversion",
+                ErrorCode::Decode,
+                &moved_path,
+                options,
+                report,
+                || match (options.gif, moved_image.extension.as_str()) {
+                    (GifPolicy::SpriteSheet, "gif") => convert_gif_to_sprite_sheet(
+                        moved_image,
+                        &options.removal,
+                        &mut decode_cache,
+                    ),
+                    _ => convert_to_png(
+                        moved_image,
+                        &texture_hdr_settings(&moved_path, options, config),
+                        &options.removal,
+                        &mut decode_cache,
+                    ),
+                },
+            )? {
+                Some(converted) => converted,
+                None => continue,
+            };
+
+            if converted.path == moved_path {
+                report.warnings.push(Warning::new(
+                    ErrorCode::UnconvertedFormat,
+                    "PNG Conversion",
+                    moved_path,
+                    "left as is, this format is not converted yet",
+                ));
+            } else {
+                image_bar.set_message(&format!("{} converted!", moved_image_path));
+                encoding = Some(Encoding::png());
+            }
+            converted
+        };
+
+        if options.stages.is_enabled(Stage::Downscale) && final_image.extension == "png" {
+            image_bar.set_prefix("Downscale");
+            let downscaled = run_step(
+                "Downscale",
+                ErrorCode::Downscale,
+                &final_image.path,
+                options,
+                report,
+                || {
+                    let category_max =
+                        category_max_size(&final_image.path, dir, config, &mut max_sizes)?;
+                    match max_texture_size(
+                        category_max,
+                        options.max_dim,
+                        options.oversized_textures,
+                    ) {
+                        Some((max_size, forced)) => Ok(downscale_texture(
+                            &final_image.path,
+                            max_size,
+                            options.resize_filter,
+                            &mut decode_cache,
+                        )?
+                        .map(|resized| (resized, forced))),
+                        None => Ok(None),
+                    }
+                },
+            )?;
+            match downscaled {
+                Some(Some(((from, to), forced))) => {
+                    if forced {
+                        report.warnings.push(Warning::new(
+                            ErrorCode::ForcedDownscale,
+                            "Downscale",
+                            final_image.path.clone(),
+                            format!(
+                                "downscaled from {}x{} to {}x{}, larger than the {}px some GPUs can upload",
+                                from.0, from.1, to.0, to.1, WEBGL_MAX_TEXTURE_DIMENSION
+                            ),
+                        ));
+                    }
+                    image_bar.set_message(&format!(
+                        "Downscaled {} from {}x{} to {}x{}",
+                        style(final_image.path.to_string_lossy()).dim(),
+                        from.0,
+                        from.1,
+                        to.0,
+                        to.1
+                    ));
+                    encoding = Some(Encoding::png());
+                }
+                Some(None) => (),
+                None => continue,
+            }
+        }
+
+        if final_image.extension == "png" {
+            if let Some(mode) = options.power_of_two {
+                image_bar.set_prefix("Power of Two");
+                let resized = run_step(
+                    "Power of Two",
+                    ErrorCode::Downscale,
+                    &final_image.path,
+                    options,
+                    report,
+                    || {
+                        resize_to_power_of_two(
+                            &final_image.path,
+                            mode,
+                            options.resize_filter,
+                            &mut decode_cache,
+                        )
+                    },
+                )?;
+                match resized {
+                    Some(Some((from, to))) => {
+                        report.warnings.push(Warning::new(
+                            ErrorCode::PowerOfTwo,
+                            "Power of Two",
+                            final_image.path.clone(),
+                            format!("resized from {}x{} to {}x{}", from.0, from.1, to.0, to.1),
+                        ));
+                        image_bar.set_message(&format!(
+                            "Resized {} from {}x{} to {}x{}",
+                            style(final_image.path.to_string_lossy()).dim(),
+                            from.0,
+                            from.1,
+                            to.0,
+                            to.1
+                        ));
+                        encoding = Some(Encoding::png());
+                    }
+                    Some(None) => (),
+                    None => continue,
+                }
+            }
+        }
+
+        // An alpha channel every pixel is opaque in is a quarter of an RGBA texture
+        // spent on nothing
+        if options.stages.is_enabled(Stage::Convert) && final_image.extension == "png" {
+            image_bar.set_prefix("Alpha Strip");
+            match run_step(
+                "Alpha Strip",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || strip_opaque_alpha(&final_image.path, &mut decode_cache),
+            )? {
+                Some(true) => {
+                    image_bar.set_message(&format!(
+                        "Stripped the opaque alpha of {}",
+                        style(final_image.path.to_string_lossy()).dim()
+                    ));
+                    encoding = Some(Encoding::png());
+                }
+                Some(false) => (),
+                None => continue,
+            }
+        }
+
+        // The colors of a batch that all came out wrong, like the dark scans of a
+        // vendor, are corrected by the `[color_adjustments]` of `webify.toml`. Data
+        // maps and normal maps hold values rather than colors and are left alone.
+        if let Some(adjustment) =
+            texture_color_adjustment(&final_image.path, dir, config).filter(|_| {
+                options.stages.is_enabled(Stage::Convert)
+                    && final_image.extension == "png"
+                    && texture_colorspace(&final_image.path, false, config) == Colorspace::Srgb
+                    && !has_normal_map_name(&final_image.path)
+            })
+        {
+            image_bar.set_prefix("Color Adjust");
+            match run_step(
+                "Color Adjust",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || match is_normal_map(&final_image.path, &mut decode_cache)? {
+                    true => Ok(None),
+                    false => adjust_colors(&final_image.path, adjustment, &mut decode_cache)?
+                        .map(|(before, after)| {
+                            Ok((thumbnail_data_uri(&before)?, thumbnail_data_uri(&after)?))
+                        })
+                        .transpose(),
+                },
+            )? {
+                Some(Some((before, after))) => {
+                    report.color_adjustments.push(ColorAdjustmentPreview {
+                        path: final_image.path.clone(),
+                        adjustment: adjustment.describe(),
+                        before,
+                        after,
+                    });
+                    image_bar.set_message(&format!(
+                        "Adjusted the colors of {}",
+                        style(final_image.path.to_string_lossy()).dim()
+                    ));
+                    encoding = Some(Encoding::png());
+                }
+                Some(None) => (),
+                None => continue,
+            }
+        }
+
+        // Preview builds mark what they show, the colors of the textures. Data maps
+        // and normal maps would shade the mark into the surface, and are left alone.
+        if let Some(mark) = watermark.as_ref().filter(|_| {
+            final_image.extension == "png"
+                && texture_colorspace(&final_image.path, false, config) == Colorspace::Srgb
+                && !has_normal_map_name(&final_image.path)
+        }) {
+            image_bar.set_prefix("Watermark");
+            match run_step(
+                "Watermark",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || match is_normal_map(&final_image.path, &mut decode_cache)? {
+                    true => Ok(false),
+                    false => watermark_texture(
+                        &final_image.path,
+                        mark,
+                        &options.watermark_settings,
+                        &mut decode_cache,
+                    ),
+                },
+            )? {
+                Some(true) => {
+                    image_bar.set_message(&format!(
+                        "Watermarked {}",
+                        style(final_image.path.to_string_lossy()).dim()
+                    ));
+                    encoding = Some(Encoding::png());
+                }
+                Some(false) => (),
+                None => continue,
+            }
+        }
+
+        // Each role may be written its own way, e.g. lossy WebP for colors and
+        // lossless PNG for data, by the `[formats]` of `webify.toml`
+        let role = TextureRole::from_name(&final_image.path);
+        let (mut format, mut avif, mut webp, mut channels) = texture_format(role, options, config);
+
+        // Data maps like roughness and occlusion are often stored as RGB with the
+        // same value in every channel, two thirds of the PNG spent on copies. The
+        // channels of a `[formats]` rule are left to it.
+        let grayscale = if format == OutputFormat::Png
+            && channels.is_none()
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            image_bar.set_prefix("Grayscale");
+            match run_step(
+                "Grayscale",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || collapse_gray_channels(&final_image.path, &mut decode_cache),
+            )? {
+                Some(true) => {
+                    image_bar.set_message(&format!(
+                        "Wrote {} as grayscale, its channels being identical",
+                        style(final_image.path.to_string_lossy()).dim()
+                    ));
+                    encoding = Some(Encoding::grayscale_png());
+                    true
+                }
+                Some(false) => false,
+                None => continue,
+            }
+        } else {
+            false
+        };
+
+        // Flat-colored textures, like signage and decals, fit in a palette that
+        // keeps them looking the same. Normal maps hold directions a palette would
+        // snap, and other formats or channels would expand the palette again.
+        if options.quantize
+            && !grayscale
+            && format == OutputFormat::Png
+            && channels.is_none()
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+            && !has_normal_map_name(&final_image.path)
+        {
+            image_bar.set_prefix("Palette Quantize");
+            match run_step(
+                "Palette Quantize",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || match is_normal_map(&final_image.path, &mut decode_cache)? {
+                    true => Ok(None),
+                    false => {
+                        quantize_palette(&final_image.path, &options.palette, &mut decode_cache)
+                    }
+                },
+            )? {
+                Some(Some(colors)) => {
+                    image_bar.set_message(&format!(
+                        "Quantized {} to {} colors",
+                        style(final_image.path.to_string_lossy()).dim(),
+                        colors
+                    ));
+                    encoding = Some(Encoding::indexed_png(colors));
+                }
+                Some(None) => (),
+                None => continue,
+            }
+        }
+
+        // 16 bit grayscale textures, usually heightmaps, stay PNGs rather than
+        // losing their precision to an 8 bit format
+        let keeps_depth = if format != OutputFormat::Png
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            match run_step(
+                "Depth Check",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || is_16_bit_grayscale(&final_image.path),
+            )? {
+                Some(true) => {
+                    image_bar.set_message(&format!(
+                        "Kept {} as a 16 bit PNG",
+                        style(final_image.path.to_string_lossy()).dim()
+                    ));
+                    true
+                }
+                Some(false) => false,
+                None => continue,
+            }
+        } else {
+            false
+        };
+
+        // Normal maps hold directions rather than colors, which sRGB conversions and
+        // lossy color quantization visibly band: they're encoded losslessly or in
+        // linear space, and kept as PNGs rather than AVIF's YCbCr. PNGs are told too,
+        // or one not named after a normal map would be tagged sRGB.
+        let normal_map = if !keeps_depth
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            match run_step(
+                "Normal Map Check",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || is_normal_map(&final_image.path, &mut decode_cache),
+            )? {
+                Some(normal_map) => normal_map,
+                None => continue,
+            }
+        } else {
+            false
+        };
+        // Normal maps not named after one only turn out to be with their pixels
+        if normal_map && role != Some(TextureRole::Normal) {
+            (format, avif, webp, channels) =
+                texture_format(Some(TextureRole::Normal), options, config);
+        }
+        if normal_map && format == OutputFormat::Avif {
+            image_bar.set_message(&format!(
+                "Kept {} as a PNG normal map",
+                style(final_image.path.to_string_lossy()).dim()
+            ));
+        }
+
+        if let Some(channels) = channels
+            .filter(|_| options.stages.is_enabled(Stage::Convert) && final_image.extension == "png")
+        {
+            image_bar.set_prefix("Channel Reduce");
+            match run_step(
+                "Channel Reduce",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || reduce_channels(&final_image.path, channels, &mut decode_cache),
+            )? {
+                Some(true) => {
+                    image_bar.set_message(&format!(
+                        "Wrote {} with {} channels",
+                        style(final_image.path.to_string_lossy()).dim(),
+                        channels
+                    ));
+                    encoding = Some(Encoding::png());
+                }
+                Some(false) => (),
+                None => continue,
+            }
+        }
+
+        // Colors are sampled through the sRGB curve, data such as normals and
+        // roughness as it is
+        let colorspace = texture_colorspace(&final_image.path, normal_map, config);
+
+        // KTX2 embeds the mip chain, the other formats get a pyramid of PNGs from the
+        // texture before it's encoded
+        let embeds_mips = !keeps_depth
+            && format == OutputFormat::Ktx2
+            && options.stages.is_enabled(Stage::Convert);
+        if options.mipmaps && !embeds_mips && final_image.extension == "png" {
+            image_bar.set_prefix("Mip Chain");
+            match run_step(
+                "Mip Chain",
+                ErrorCode::MipChain,
+                &final_image.path,
+                options,
+                report,
+                || generate_mip_chain(&final_image.path, options.resize_filter, &mut decode_cache),
+            )? {
+                Some(levels) => image_bar.set_message(&format!(
+                    "Generated {} mip levels of {}",
+                    levels.len(),
+                    style(final_image.path.to_string_lossy()).dim()
+                )),
+                None => continue,
+            }
+        }
+
+        let webp_settings = match normal_map {
+            true => WebpSettings {
+                lossless: true,
+                ..webp
+            },
+            false => webp,
+        };
+        let ktx2_settings = match normal_map {
+            // ETC1S quantizes the colors of whole regions to a few endpoints
+            true => Ktx2Settings {
+                codec: BasisCodec::Uastc,
+                ..texture_ktx2_settings(&final_image.path, options, config)
+            },
+            false => texture_ktx2_settings(&final_image.path, options, config),
+        };
+        // The pixels the texture is encoded from, encoded again when the file is
+        // over `--max-bytes`
+        let budget_source = match options.max_bytes {
+            Some(_)
+                if options.stages.is_enabled(Stage::Convert) && final_image.extension == "png" =>
+            {
+                decode_cache
+                    .take_or_open(&final_image.path)
+                    .ok()
+                    .inspect(|decoded| decode_cache.insert(&final_image.path, decoded.clone()))
+            }
+            _ => None,
+        };
+
+        let final_image = if !keeps_depth
+            && !normal_map
+            && format == OutputFormat::Avif
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            image_bar.set_prefix("AVIF Encode");
+            image_bar.set_message(&format!(
+                "Encoding {}...",
+                style(final_image.path.to_string_lossy()).dim()
+            ));
+            let png_path = final_image.path.clone();
+            match run_step(
+                "AVIF Encode",
+                ErrorCode::Encode,
+                &png_path,
+                options,
+                report,
+                || encode_avif(final_image, &avif, &options.removal, &mut decode_cache),
+            )? {
+                Some((encoded, None)) => {
+                    encoding = Some(Encoding::avif(&avif));
+                    encoded
+                }
+                Some((kept, Some(reason))) => {
+                    report.warnings.push(Warning::new(
+                        ErrorCode::EncodeFallback,
+                        "AVIF Encode",
+                        png_path,
+                        reason,
+                    ));
+                    kept
+                }
+                None => continue,
+            }
+        } else if !keeps_depth
+            && format == OutputFormat::Webp
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            image_bar.set_prefix("WebP Conversion");
+            image_bar.set_message(&format!(
+                "Converting {}...",
+                style(final_image.path.to_string_lossy()).dim()
+            ));
+            let png_path = final_image.path.clone();
+            match run_step(
+                "WebP Conversion",
+                ErrorCode::Encode,
+                &png_path,
+                options,
+                report,
+                || {
+                    convert_to_webp(
+                        final_image,
+                        &webp_settings,
+                        &options.removal,
+                        &mut decode_cache,
+                    )
+                },
+            )? {
+                Some((converted, None)) => {
+                    encoding = Some(Encoding::webp(&webp_settings));
+                    converted
+                }
+                Some((kept, Some(reason))) => {
+                    report.warnings.push(Warning::new(
+                        ErrorCode::EncodeFallback,
+                        "WebP Conversion",
+                        png_path,
+                        reason,
+                    ));
+                    kept
+                }
+                None => continue,
+            }
+        } else if !keeps_depth
+            && format == OutputFormat::Ktx2
+            && options.stages.is_enabled(Stage::Convert)
+            && final_image.extension == "png"
+        {
+            image_bar.set_prefix("KTX2 Encode");
+            image_bar.set_message(&format!(
+                "Encoding {}...",
+                style(final_image.path.to_string_lossy()).dim()
+            ));
+            let png_path = final_image.path.clone();
+            match run_step(
+                "KTX2 Encode",
+                ErrorCode::Encode,
+                &png_path,
+                options,
+                report,
+                || {
+                    encode_ktx2(
+                        final_image,
+                        &ktx2_settings,
+                        normal_map,
+                        colorspace,
+                        &options.removal,
+                        &mut decode_cache,
+                    )
+                },
+            )? {
+                Some((encoded, None)) => {
+                    encoding = Some(Encoding::ktx2(&ktx2_settings));
+                    encoded
+                }
+                Some((kept, Some(reason))) => {
+                    report.warnings.push(Warning::new(
+                        ErrorCode::EncodeFallback,
+                        "KTX2 Encode",
+                        png_path,
+                        reason,
+                    ));
+                    kept
+                }
+                None => continue,
+            }
+        } else {
+            final_image
+        };
+
+        // Encoded again at a lower quality, then smaller, until the file fits, with
+        // the intermediate PNGs deleted whatever `--removal` says
+        let final_image = match (options.max_bytes, budget_source) {
+            (Some(max_bytes), Some(source)) => {
+                image_bar.set_prefix("Byte Budget");
+                let quality = encoding.as_ref().and_then(|encoding| encoding.quality);
+                let budget_image = final_image.clone();
+                let fit = run_step(
+                    "Byte Budget",
+                    ErrorCode::ByteBudget,
+                    &final_image.path,
+                    options,
+                    report,
+                    || {
+                        let extension = budget_image.extension.clone();
+                        fit_byte_budget(
+                            budget_image,
+                            &source,
+                            max_bytes,
+                            quality,
+                            options.resize_filter,
+                            |png, quality| {
+                                if options.mipmaps && !embeds_mips {
+                                    generate_mip_chain(
+                                        &png.path,
+                                        options.resize_filter,
+                                        &mut decode_cache,
+                                    )?;
+                                }
+                                let encoded = match extension.as_str() {
+                                    "avif" => {
+                                        let settings = AvifSettings {
+                                            quality: quality.unwrap_or(avif.quality),
+                                            ..avif
+                                        };
+                                        encode_avif(
+                                            png,
+                                            &settings,
+                                            &Removal::Delete,
+                                            &mut decode_cache,
+                                        )?
+                                    }
+                                    "webp" => {
+                                        let settings = WebpSettings {
+                                            quality: quality.unwrap_or(webp_settings.quality),
+                                            ..webp_settings
+                                        };
+                                        convert_to_webp(
+                                            png,
+                                            &settings,
+                                            &Removal::Delete,
+                                            &mut decode_cache,
+                                        )?
+                                    }
+                                    "ktx2" => {
+                                        let settings = Ktx2Settings {
+                                            quality: quality.unwrap_or(ktx2_settings.quality),
+                                            ..ktx2_settings
+                                        };
+                                        encode_ktx2(
+                                            png,
+                                            &settings,
+                                            normal_map,
+                                            colorspace,
+                                            &Removal::Delete,
+                                            &mut decode_cache,
+                                        )?
+                                    }
+                                    _ => (png, None),
+                                };
+                                Ok(encoded.0)
+                            },
+                        )
+                    },
+                )?;
+                match fit {
+                    Some(Some(fit)) => {
+                        report.warnings.push(Warning::new(
+                            ErrorCode::ByteBudget,
+                            "Byte Budget",
+                            fit.image.path.clone(),
+                            format!(
+                                "{} to fit under {}, from {} to {}",
+                                fit.describe(),
+                                format_bytes(max_bytes),
+                                format_bytes(fit.bytes.0),
+                                format_bytes(fit.bytes.1)
+                            ),
+                        ));
+                        image_bar.set_message(&format!(
+                            "Shrank {} to {}",
+                            style(fit.image.path.to_string_lossy()).dim(),
+                            format_bytes(fit.bytes.1)
+                        ));
+                        // The encoder may have kept the PNG this time, and the
+                        // palette of a quantized PNG isn't kept
+                        encoding = match (fit.image.extension.as_str(), encoding) {
+                            ("png", Some(encoding)) if encoding.is_grayscale() => {
+                                Some(Encoding::grayscale_png())
+                            }
+                            ("png", _) => Some(Encoding::png()),
+                            (_, encoding) => encoding.map(|encoding| Encoding {
+                                quality: fit.quality.1,
+                                ..encoding
+                            }),
+                        };
+                        fit.image
+                    }
+                    Some(None) => final_image,
+                    None => continue,
+                }
+            }
+            _ => final_image,
+        };
+
+        if options.stages.is_enabled(Stage::Convert) {
+            let tagged = run_step(
+                "Colorspace Tag",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || {
+                    let mut files = vec![final_image.path.clone()];
+                    files.extend(find_mip_levels(&final_image.path));
+                    files
+                        .iter()
+                        .map(|file| tag_colorspace(file, colorspace))
+                        .collect::<std::result::Result<Vec<bool>, std::io::Error>>()
+                },
+            )?;
+            if tagged.is_none() {
+                continue;
+            }
+            if colorspace == Colorspace::Linear {
+                encoding = encoding.map(Encoding::linear);
+            }
+        }
+
+        if options.strip_metadata && options.stages.is_enabled(Stage::Convert) {
+            image_bar.set_prefix("Metadata Strip");
+            let stripped = match run_step(
+                "Metadata Strip",
+                ErrorCode::Encode,
+                &final_image.path,
+                options,
+                report,
+                || strip_metadata(&final_image.path),
+            )? {
+                Some(stripped) => stripped,
+                None => continue,
+            };
+            if !stripped.is_empty() {
+                image_bar.set_message(&format!(
+                    "Stripped {} from {}",
+                    stripped.join(", "),
+                    style(final_image.path.to_string_lossy()).dim()
+                ));
+            }
+        }
+
+        if !measure_texture(&final_image, encoding.clone(), dir, options, report)? {
+            continue;
+        }
+
+        let marker = ProcessedMarker {
+            encoding,
+            ..marker.clone()
+        };
+        write_marker(&final_image.path, &marker)?;
+        if let (Some(audit_log), Some(source_hash)) = (&mut report.audit_log, source_hash) {
+            audit_log.record(
+                "Texture Processing",
+                &image_path,
+                source_hash,
+                &final_image.path,
+            )?;
         }
     }
     image_bar.finish_with_message("Images webified!");
 
+    if let Some(gpu_budget) = options.gpu_budget {
+        for (model, gpu_bytes) in report.gpu_bytes_by_model() {
+            if gpu_bytes > gpu_budget {
+                let textures: Vec<&TextureStats> = report
+                    .textures
+                    .iter()
+                    .filter(|texture| texture.model.as_ref() == Some(&model))
+                    .collect();
+                let suggestions = suggest_downscales(&model, &textures, gpu_bytes - gpu_budget);
+                report.budget_suggestions.extend(suggestions);
+
+                let failure = Failure::new(
+                    ErrorCode::GpuBudget,
+                    "GPU Budget",
+                    model,
+                    format!(
+                        "estimated GPU memory of {} exceeds the budget of {}",
+                        format_bytes(gpu_bytes),
+                        format_bytes(gpu_budget)
+                    ),
+                );
+                handle_failure(options.error_policy, report, failure)?;
+            }
+        }
+    }
+
     Ok(())
 }
+
+/// Check the size of the texture and record its stats with the encoding it was
+/// written with. Returns false for formats the image crate doesn't know, like
+/// unconverted PSDs, which can't be measured, and when the size check failed.
+fn measure_texture(
+    image: &Image,
+    encoding: Option<Encoding>,
+    dir: &Path,
+    options: &RunOptions,
+    report: &mut RunReport,
+) -> std::result::Result<bool, std::io::Error> {
+    if ImageFormat::from_extension(&image.extension).is_none()
+        && image.extension != "avif"
+        && image.extension != "ktx2"
+    {
+        return Ok(false);
+    }
+
+    match run_step(
+        "Size Check",
+        ErrorCode::Measure,
+        &image.path,
+        options,
+        report,
+        || check_texture_size(&image.path),
+    )? {
+        Some(Some(message)) => report.warnings.push(Warning::new(
+            ErrorCode::TextureSize,
+            "Size Check",
+            image.path.clone(),
+            message,
+        )),
+        Some(None) => (),
+        None => return Ok(false),
+    }
+
+    if let Some(stats) = run_step(
+        "GPU Estimate",
+        ErrorCode::Measure,
+        &image.path,
+        options,
+        report,
+        || record_texture_stats(&image.path, dir),
+    )? {
+        report.textures.push(TextureStats { encoding, ..stats });
+    }
+
+    Ok(true)
+}
+
+/// Largest texture size allowed, the smallest of the category preset of the model the
+/// texture belongs to and `--max-dim`, capped to what every GPU can upload when
+/// oversized textures are downscaled. Also tells whether the cap is what applies.
+fn max_texture_size(
+    category_max: Option<u32>,
+    max_dim: Option<u32>,
+    oversized_textures: OversizedTextures,
+) -> Option<(u32, bool)> {
+    let max_size = match (category_max, max_dim) {
+        (Some(category_max), Some(max_dim)) => Some(category_max.min(max_dim)),
+        (category_max, max_dim) => category_max.or(max_dim),
+    };
+    match (max_size, oversized_textures) {
+        (Some(max_size), OversizedTextures::Downscale)
+            if max_size > WEBGL_MAX_TEXTURE_DIMENSION =>
+        {
+            Some((WEBGL_MAX_TEXTURE_DIMENSION, true))
+        }
+        (None, OversizedTextures::Downscale) => Some((WEBGL_MAX_TEXTURE_DIMENSION, true)),
+        (Some(max_size), _) => Some((max_size, false)),
+        (None, OversizedTextures::Keep) => None,
+    }
+}
+
+/// Largest texture size allowed by the category preset of the model the texture
+/// belongs to, cached per model directory
+fn category_max_size(
+    path: &Path,
+    dir: &Path,
+    config: &WebifyConfig,
+    max_sizes: &mut HashMap<PathBuf, Option<u32>>,
+) -> std::result::Result<Option<u32>, std::io::Error> {
+    if config.categories.is_empty() {
+        return Ok(None);
+    }
+
+    // Shared collection textures don't belong to a single model's category
+    let model_dir = match locate_file(path, dir)? {
+        FileLocation::Model(model_dir) => model_dir,
+        FileLocation::Collection(_) => return Ok(None),
+    };
+    if let Some(max_size) = max_sizes.get(&model_dir) {
+        return Ok(*max_size);
+    }
+
+    let max_size = resolve_category(&model_dir, config)?
+        .and_then(|category| config.categories.get(&category))
+        .map(|preset| preset.max_size);
+    max_sizes.insert(model_dir, max_size);
+
+    Ok(max_size)
+}
+
+/// Tonemapping settings of a texture, the command line ones with the `[hdr_textures]`
+/// override of `webify.toml` named after the texture applied
+fn texture_hdr_settings(path: &Path, options: &RunOptions, config: &WebifyConfig) -> HdrSettings {
+    path.file_stem()
+        .and_then(|stem| config.hdr_textures.get(stem.to_string_lossy().as_ref()))
+        .map_or(options.hdr, |hdr_override| hdr_override.apply(&options.hdr))
+}
+
+/// Color adjustment of the texture, the `[color_adjustments]` entry of the closest
+/// directory above it within the processed one, unless it changes nothing
+fn texture_color_adjustment<'a>(
+    path: &Path,
+    dir: &Path,
+    config: &'a WebifyConfig,
+) -> Option<&'a ColorAdjustment> {
+    if config.color_adjustments.is_empty() {
+        return None;
+    }
+
+    path.strip_prefix(dir)
+        .ok()?
+        .parent()?
+        .ancestors()
+        .filter_map(Path::file_name)
+        .find_map(|name| {
+            config
+                .color_adjustments
+                .get(name.to_string_lossy().as_ref())
+        })
+        .filter(|adjustment| !adjustment.is_identity())
+}
+
+/// Colorspace of a texture: the one of its role, told by its name or, for normal
+/// maps, by its pixels, with the `[colorspaces]` override of `webify.toml` applied.
+/// Textures of no known role hold colors. The `colorspace` of the `[ktx2_textures]`
+/// entry named after the texture wins over all of them.
+fn texture_colorspace(path: &Path, normal_map: bool, config: &WebifyConfig) -> Colorspace {
+    let texture_override = path
+        .file_stem()
+        .and_then(|stem| config.ktx2_textures.get(stem.to_string_lossy().as_ref()))
+        .and_then(|ktx2_override| ktx2_override.colorspace);
+    if let Some(colorspace) = texture_override {
+        return colorspace;
+    }
+
+    let role = match normal_map {
+        true => Some(TextureRole::Normal),
+        false => TextureRole::from_name(path),
+    };
+    role.map_or(Colorspace::Srgb, |role| {
+        config
+            .colorspaces
+            .get(&role)
+            .copied()
+            .unwrap_or_else(|| role.default_colorspace())
+    })
+}
+
+/// Output format of the textures of a role and the knobs of its encoders, the
+/// command line ones with the `[formats]` rule of `webify.toml` for the role applied,
+/// along with the channels PNG textures of the role are written with
+fn texture_format(
+    role: Option<TextureRole>,
+    options: &RunOptions,
+    config: &WebifyConfig,
+) -> (OutputFormat, AvifSettings, WebpSettings, Option<u8>) {
+    match role.and_then(|role| config.formats.get(&role)) {
+        Some(rule) => {
+            let (format, avif, webp) = rule.apply(options.format, &options.avif, &options.webp);
+            (format, avif, webp, rule.channels)
+        }
+        None => (options.format, options.avif, options.webp, None),
+    }
+}
+
+/// KTX2 settings of a texture, the command line ones with the `[ktx2_textures]`
+/// override of `webify.toml` named after the texture applied
+fn texture_ktx2_settings(path: &Path, options: &RunOptions, config: &WebifyConfig) -> Ktx2Settings {
+    path.file_stem()
+        .and_then(|stem| config.ktx2_textures.get(stem.to_string_lossy().as_ref()))
+        .map_or(options.ktx2, |ktx2_override| {
+            ktx2_override.apply(&options.ktx2)
+        })
+}
+
+#[cfg(test)]
+mod max_texture_size_tests {
+    use super::*;
+
+    #[test]
+    fn it_caps_the_category_size_when_downscaling_oversized_textures() {
+        assert_eq!(max_texture_size(None, None, OversizedTextures::Keep), None);
+        assert_eq!(
+            max_texture_size(Some(16384), None, OversizedTextures::Keep),
+            Some((16384, false))
+        );
+        assert_eq!(
+            max_texture_size(None, None, OversizedTextures::Downscale),
+            Some((WEBGL_MAX_TEXTURE_DIMENSION, true))
+        );
+        assert_eq!(
+            max_texture_size(Some(16384), None, OversizedTextures::Downscale),
+            Some((WEBGL_MAX_TEXTURE_DIMENSION, true))
+        );
+        assert_eq!(
+            max_texture_size(Some(512), None, OversizedTextures::Downscale),
+            Some((512, false))
+        );
+    }
+
+    #[test]
+    fn it_takes_the_smallest_of_the_category_size_and_max_dim() {
+        assert_eq!(
+            max_texture_size(None, Some(2048), OversizedTextures::Keep),
+            Some((2048, false))
+        );
+        assert_eq!(
+            max_texture_size(Some(512), Some(2048), OversizedTextures::Keep),
+            Some((512, false))
+        );
+        assert_eq!(
+            max_texture_size(Some(4096), Some(2048), OversizedTextures::Downscale),
+            Some((2048, false))
+        );
+        assert_eq!(
+            max_texture_size(None, Some(16384), OversizedTextures::Downscale),
+            Some((WEBGL_MAX_TEXTURE_DIMENSION, true))
+        );
+    }
+}
+
+#[cfg(test)]
+mod texture_colorspace_tests {
+    use super::*;
+
+    use crate::config::Ktx2Override;
+
+    #[test]
+    fn it_takes_the_colorspace_of_the_texture_over_the_one_of_its_role() {
+        let mut config = WebifyConfig::default();
+        config
+            .colorspaces
+            .insert(TextureRole::Specular, Colorspace::Linear);
+        for (texture, colorspace) in [
+            ("lightmap", Colorspace::Linear),
+            ("wall_normal", Colorspace::Srgb),
+        ]
+        .iter()
+        {
+            config.ktx2_textures.insert(
+                texture.to_string(),
+                Ktx2Override {
+                    colorspace: Some(*colorspace),
+                    ..Ktx2Override::default()
+                },
+            );
+        }
+
+        let colorspace = |name: &str, normal_map| {
+            texture_colorspace(&Path::new("textures").join(name), normal_map, &config)
+        };
+        assert_eq!(colorspace("lightmap.png", false), Colorspace::Linear);
+        assert_eq!(colorspace("wall_normal.png", true), Colorspace::Srgb);
+        assert_eq!(colorspace("floor_normal.png", true), Colorspace::Linear);
+        assert_eq!(colorspace("floor_spec.png", false), Colorspace::Linear);
+        assert_eq!(colorspace("floor.png", false), Colorspace::Srgb);
+    }
+}
@@ -0,0 +1,225 @@
+//! Quantize a texture to an indexed PNG, for flat-colored ones like signage
+
+use std::{collections::HashSet, fs, io::Error, path::Path, result::Result};
+
+use color_quant::NeuQuant;
+use image::{DynamicImage, RgbaImage};
+
+use crate::image_processing::DecodeCache;
+use crate::options::PaletteSettings;
+
+/// Sampling factor of NeuQuant, from 1 (every pixel, slowest) to 30
+const NEUQUANT_SAMPLING: i32 = 10;
+
+/// Rewrite the 8 bit PNG texture in place as an indexed PNG of at most
+/// `settings.colors` colors, alpha included, when the palette keeps it within
+/// `settings.min_psnr` of the original and makes the file smaller. Textures with few
+/// enough colors get an exact palette, the others one learned by NeuQuant, each
+/// pixel taking its closest color. Returns the size of the palette when it was
+/// rewritten.
+pub fn quantize_palette(
+    path: &Path,
+    settings: &PaletteSettings,
+    cache: &mut DecodeCache,
+) -> Result<Option<usize>, Error> {
+    let img = cache
+        .take_or_open(path)
+        .map_err(|e| Error::other(format!("Failed to open {:?} to quantize: {:?}", path, e)))?;
+    let has_alpha = match img {
+        DynamicImage::ImageRgba8(_) | DynamicImage::ImageLumaA8(_) => true,
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageLuma8(_) => false,
+        // 16 bit textures are data a palette would flatten
+        _ => {
+            cache.insert(path, img);
+            return Ok(None);
+        }
+    };
+
+    let rgba = img.to_rgba8();
+    let max_colors = usize::from(settings.colors.clamp(2, 256));
+    let (palette, indices) = match exact_palette(&rgba, max_colors) {
+        Some(exact) => exact,
+        None => learned_palette(&rgba, max_colors),
+    };
+
+    let mut quantized = RgbaImage::new(rgba.width(), rgba.height());
+    for (pixel, &index) in quantized.pixels_mut().zip(indices.iter()) {
+        pixel.0 = palette[usize::from(index)];
+    }
+    if psnr(&rgba, &quantized) < settings.min_psnr {
+        cache.insert(path, img);
+        return Ok(None);
+    }
+
+    let png = encode_indexed_png(rgba.width(), rgba.height(), &palette, &indices)?;
+    if png.len() as u64 >= fs::metadata(path)?.len() {
+        cache.insert(path, img);
+        return Ok(None);
+    }
+    fs::write(path, png)?;
+    let quantized = DynamicImage::ImageRgba8(quantized);
+    cache.insert(
+        path,
+        match has_alpha {
+            true => quantized,
+            false => DynamicImage::ImageRgb8(quantized.to_rgb8()),
+        },
+    );
+
+    Ok(Some(palette.len()))
+}
+
+/// The colors of the image and the index of each pixel in them, when it has no
+/// more than `max_colors`
+fn exact_palette(rgba: &RgbaImage, max_colors: usize) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    let mut colors: HashSet<[u8; 4]> = HashSet::new();
+    for pixel in rgba.pixels() {
+        if colors.insert(pixel.0) && colors.len() > max_colors {
+            return None;
+        }
+    }
+
+    let mut palette: Vec<[u8; 4]> = colors.into_iter().collect();
+    palette.sort_unstable();
+    let indices = rgba
+        .pixels()
+        .map(|pixel| palette.binary_search(&pixel.0).unwrap_or(0) as u8)
+        .collect();
+    Some((palette, indices))
+}
+
+/// A palette of `max_colors` learned by NeuQuant, and the index of the closest
+/// color of each pixel
+fn learned_palette(rgba: &RgbaImage, max_colors: usize) -> (Vec<[u8; 4]>, Vec<u8>) {
+    let quantizer = NeuQuant::new(NEUQUANT_SAMPLING, max_colors, rgba.as_raw());
+    let palette = quantizer
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|color| [color[0], color[1], color[2], color[3]])
+        .collect();
+    let indices = rgba
+        .pixels()
+        .map(|pixel| quantizer.index_of(&pixel.0) as u8)
+        .collect();
+    (palette, indices)
+}
+
+/// Peak signal-to-noise ratio of the quantized image against the original, over
+/// every channel, in dB
+fn psnr(original: &RgbaImage, quantized: &RgbaImage) -> f64 {
+    let squared_error: f64 = original
+        .as_raw()
+        .iter()
+        .zip(quantized.as_raw())
+        .map(|(&a, &b)| (f64::from(a) - f64::from(b)).powi(2))
+        .sum();
+    let mse = squared_error / original.as_raw().len().max(1) as f64;
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// An 8 bit indexed PNG, with a `tRNS` chunk when some colors aren't opaque
+fn encode_indexed_png(
+    width: u32,
+    height: u32,
+    palette: &[[u8; 4]],
+    indices: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+    encoder.set_palette(
+        palette
+            .iter()
+            .flat_map(|color| color[..3].to_vec())
+            .collect(),
+    );
+    // Trailing opaque entries can be left out of tRNS
+    let alphas: Vec<u8> = palette.iter().map(|color| color[3]).collect();
+    let transparent = alphas.iter().rposition(|&alpha| alpha != u8::MAX);
+    if let Some(last) = transparent {
+        encoder.set_trns(alphas[..=last].to_vec());
+    }
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(indices))
+        .map_err(Error::other)?;
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod quantize_palette_tests {
+    use super::*;
+
+    use image::{ColorType, Rgba};
+
+    fn setup(name: &str) -> Result<std::path::PathBuf, Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join(format!("test_run_quantize_palette_{}", name));
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn it_writes_flat_colored_textures_as_indexed_pngs() -> Result<(), Error> {
+        let dir = setup("sign")?;
+        let path = dir.join("exit_sign.png");
+        // Stripes of flat colors, the last one see-through
+        let sign = RgbaImage::from_fn(64, 32, |x, _| match x / 16 {
+            0 => Rgba([20, 160, 60, 255]),
+            1 => Rgba([250, 250, 250, 255]),
+            2 => Rgba([200, 30, 30, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        });
+        sign.save(&path).map_err(Error::other)?;
+
+        let mut cache = DecodeCache::default();
+        let colors = quantize_palette(&path, &PaletteSettings::default(), &mut cache)?;
+
+        assert_eq!(colors, Some(4));
+        let mut decoder = png::Decoder::new(fs::File::open(&path)?);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let (info, _) = decoder.read_info().map_err(Error::other)?;
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        let decoded = image::open(&path).map_err(Error::other)?;
+        assert_eq!(decoded.color(), ColorType::Rgba8);
+        assert_eq!(decoded.to_rgba8(), sign);
+        assert_eq!(cache.take(&path).map(|image| image.to_rgba8()), Some(sign));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_textures_a_palette_would_degrade() -> Result<(), Error> {
+        let dir = setup("photo")?;
+        let path = dir.join("noise.png");
+        // Every pixel a different color, like a photo
+        let mut state = 12345u32;
+        let noise = RgbaImage::from_fn(128, 128, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let [r, g, b, _] = state.to_be_bytes();
+            Rgba([r, g, b, 255])
+        });
+        noise.save(&path).map_err(Error::other)?;
+        let original = fs::read(&path)?;
+
+        let colors = quantize_palette(
+            &path,
+            &PaletteSettings::default(),
+            &mut DecodeCache::default(),
+        )?;
+
+        assert_eq!(colors, None);
+        assert_eq!(fs::read(&path)?, original);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Read the dimensions and channels of an AVIF texture from its header
+
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{Error, ErrorKind, Read},
+    path::Path,
+    result::Result,
+};
+
+/// How much of the file is read, the metadata comes before the image data
+const HEADER_LEN: u64 = 64 * 1024;
+
+/// URN of the auxiliary image holding the transparency
+const ALPHA_URN: &[u8] = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha";
+
+/// Width, height and number of channels of an AVIF file, read from the image
+/// spatial extents (`ispe`) of the primary image and the presence of an alpha
+/// auxiliary image, since the image crate can't decode AVIF
+pub fn read_avif_info(path: &Path) -> Result<(u32, u32, u8), Error> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(HEADER_LEN)
+        .read_to_end(&mut header)?;
+
+    read_avif_info_bytes(&header).map_err(|e| Error::new(e.kind(), format!("{:?} {}", path, e)))
+}
+
+/// Width, height and number of channels from the start of AVIF file contents
+pub fn read_avif_info_bytes(header: &[u8]) -> Result<(u32, u32, u8), Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "isn't a valid AVIF");
+    if header.get(4..8) != Some(b"ftyp") {
+        return Err(invalid());
+    }
+
+    // Box type, then 4 bytes of version and flags, then the width and height
+    let ispe = find(header, b"ispe").ok_or_else(invalid)?;
+    let read_u32 = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+    };
+    let width = read_u32(ispe + 8).ok_or_else(invalid)?;
+    let height = read_u32(ispe + 12).ok_or_else(invalid)?;
+    let channels = if find(header, ALPHA_URN).is_some() {
+        4
+    } else {
+        3
+    };
+
+    Ok((width, height, channels))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod read_avif_info_tests {
+    use super::*;
+
+    use std::fs;
+
+    use ravif::{Encoder, Img, RGBA8};
+
+    #[test]
+    fn it_reads_the_header() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_read_avif_info");
+        fs::create_dir_all(&dir)?;
+        let encoder = Encoder::new().with_speed(10);
+        let opaque = vec![RGBA8::new(10, 20, 30, 255); 6 * 4];
+        let transparent = vec![RGBA8::new(10, 20, 30, 128); 6 * 4];
+        let encode = |pixels: &[RGBA8]| encoder.encode_rgba(Img::new(pixels, 6, 4)).unwrap();
+        fs::write(dir.join("opaque.avif"), encode(&opaque).avif_file)?;
+        fs::write(dir.join("transparent.avif"), encode(&transparent).avif_file)?;
+
+        assert_eq!(read_avif_info(&dir.join("opaque.avif"))?, (6, 4, 3));
+        assert_eq!(read_avif_info(&dir.join("transparent.avif"))?, (6, 4, 4));
+        assert!(read_avif_info(
+            &Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg")
+        )
+        .is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Read the ICC profile embedded in a JPEG
+
+/// Marker of the APP2 segments holding the profile
+const APP2: u8 = 0xE2;
+
+/// Identifier of the APP2 segments holding the profile
+const ICC_PROFILE: &[u8] = b"ICC_PROFILE\0";
+
+/// The ICC profile of the JPEG, put back together from the APP2 segments it is
+/// split into, up to 64 KiB each, in the order of their sequence numbers. `None`
+/// when the JPEG has none, or some of its chunks are missing.
+pub fn read_jpeg_icc_profile(jpeg: &[u8]) -> Option<Vec<u8>> {
+    if jpeg.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut chunks: Vec<(u8, &[u8])> = Vec::new();
+    let mut count = 0;
+    let mut position = 2;
+    // The profile sits in the segments before the image data
+    while let (Some(0xFF), Some(&marker)) = (jpeg.get(position), jpeg.get(position + 1)) {
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let length = usize::from(u16::from_be_bytes([
+            *jpeg.get(position + 2)?,
+            *jpeg.get(position + 3)?,
+        ]));
+        let segment = jpeg.get(position + 4..position + 2 + length)?;
+        if marker == APP2 && segment.starts_with(ICC_PROFILE) && segment.len() > 14 {
+            count = segment[13];
+            chunks.push((segment[12], &segment[14..]));
+        }
+        position += 2 + length;
+    }
+
+    if chunks.is_empty() || chunks.len() != usize::from(count) {
+        return None;
+    }
+    chunks.sort_by_key(|(sequence, _)| *sequence);
+    Some(
+        chunks
+            .into_iter()
+            .flat_map(|(_, chunk)| chunk.to_vec())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod read_jpeg_icc_profile_tests {
+    use super::*;
+
+    fn app2(sequence: u8, count: u8, chunk: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, APP2];
+        segment
+            .extend_from_slice(&((2 + ICC_PROFILE.len() + 2 + chunk.len()) as u16).to_be_bytes());
+        segment.extend_from_slice(ICC_PROFILE);
+        segment.extend_from_slice(&[sequence, count]);
+        segment.extend_from_slice(chunk);
+        segment
+    }
+
+    #[test]
+    fn it_reassembles_the_chunks_of_the_profile() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(vec![0xFF, 0xE0, 0, 4, 0, 0]);
+        jpeg.extend(app2(2, 2, b"second"));
+        jpeg.extend(app2(1, 2, b"first "));
+        jpeg.extend(vec![0xFF, 0xDA, 0, 2, 0xFF, 0xD9]);
+
+        assert_eq!(
+            read_jpeg_icc_profile(&jpeg).as_deref(),
+            Some(&b"first second"[..])
+        );
+
+        // A chunk missing
+        let mut truncated = vec![0xFF, 0xD8];
+        truncated.extend(app2(1, 2, b"first "));
+        assert_eq!(read_jpeg_icc_profile(&truncated), None);
+        assert_eq!(read_jpeg_icc_profile(&[0xFF, 0xD8, 0xFF, 0xDA]), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Read the dimensions, channels and mip levels of a KTX2 texture from its header
+
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{Error, ErrorKind, Read},
+    path::Path,
+    result::Result,
+};
+
+use crate::image_processing::KTX2_IDENTIFIER;
+
+/// How much of the file is read, the header and data format descriptor come first
+const HEADER_LEN: u64 = 64 * 1024;
+
+/// Color models of the data format descriptor written for Basis Universal
+const KHR_DF_MODEL_UASTC: u8 = 166;
+
+/// Channel id of the alpha samples, and of the UASTC samples with alpha
+const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+const UASTC_CHANNELS_WITH_ALPHA: [u8; 2] = [3, 5];
+
+/// What the header of a KTX2 texture tells about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ktx2Info {
+    pub width: u32,
+    pub height: u32,
+    /// Number of color channels, 4 when the texture has alpha
+    pub channels: u8,
+    /// Number of mip levels stored, 1 for none
+    pub levels: u32,
+}
+
+/// Dimensions, channels and mip levels of a KTX2 file, read from its header and
+/// data format descriptor, since the image crate can't decode KTX2
+pub fn read_ktx2_info(path: &Path) -> Result<Ktx2Info, Error> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(HEADER_LEN)
+        .read_to_end(&mut header)?;
+
+    read_ktx2_info_bytes(&header).map_err(|e| Error::new(e.kind(), format!("{:?} {}", path, e)))
+}
+
+/// Dimensions, channels and mip levels from the start of KTX2 file contents
+pub fn read_ktx2_info_bytes(header: &[u8]) -> Result<Ktx2Info, Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "isn't a valid KTX2");
+    if header.get(..KTX2_IDENTIFIER.len()) != Some(&KTX2_IDENTIFIER[..]) {
+        return Err(invalid());
+    }
+
+    let read_u32 = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(invalid)
+    };
+    let width = read_u32(20)?;
+    let height = read_u32(24)?;
+    let levels = read_u32(40)?.max(1);
+
+    // The basic descriptor block: the color model, then 16 bytes per sample with
+    // the channel id in the low bits of their fourth byte
+    let dfd = read_u32(48)? as usize;
+    let block = dfd + 4;
+    let block_size = usize::from(
+        header
+            .get(block + 6..block + 8)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(invalid)?,
+    );
+    let model = *header.get(block + 8).ok_or_else(invalid)?;
+    let samples = block_size.saturating_sub(24) / 16;
+    let has_alpha = (0..samples).any(|sample| {
+        match header
+            .get(block + 24 + sample * 16 + 3)
+            .map(|byte| byte & 0xF)
+        {
+            Some(KHR_DF_CHANNEL_ALPHA) => true,
+            Some(channel) => {
+                model == KHR_DF_MODEL_UASTC && UASTC_CHANNELS_WITH_ALPHA.contains(&channel)
+            }
+            None => false,
+        }
+    });
+
+    Ok(Ktx2Info {
+        width,
+        height,
+        channels: if has_alpha { 4 } else { 3 },
+        levels,
+    })
+}
+
+#[cfg(test)]
+mod read_ktx2_info_tests {
+    use super::*;
+
+    #[test]
+    fn it_errors_on_other_formats() {
+        let path = Path::new("tests")
+            .join("image_processing")
+            .join("images")
+            .join("example.jpg");
+        assert!(read_ktx2_info(&path).is_err());
+    }
+}
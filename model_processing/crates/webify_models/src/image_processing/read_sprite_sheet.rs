@@ -0,0 +1,14 @@
+//! Read the layout of a texture that is a sprite sheet
+
+use std::{fs, path::Path};
+
+use crate::image_processing::SpriteSheetMetadata;
+
+/// The `SpriteSheetMetadata` next to the texture, when it's a sprite sheet an
+/// animated GIF became with `--gif sprite-sheet`. The JSON of an atlas, impostor
+/// or ORM texture isn't one.
+pub fn read_sprite_sheet(texture: &Path) -> Option<SpriteSheetMetadata> {
+    fs::read_to_string(texture.with_extension("json"))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
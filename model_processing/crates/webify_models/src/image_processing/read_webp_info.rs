@@ -0,0 +1,72 @@
+//! Read the dimensions and channels of a WebP texture from its header
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read},
+    path::Path,
+    result::Result,
+};
+
+use webp::BitstreamFeatures;
+
+/// How much of the file is read, the features are in the first chunks
+const HEADER_LEN: u64 = 64 * 1024;
+
+/// Width, height and number of channels of a WebP file, read by libwebp from its
+/// header since the image crate can't decode lossless WebP
+pub fn read_webp_info(path: &Path) -> Result<(u32, u32, u8), Error> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(HEADER_LEN)
+        .read_to_end(&mut header)?;
+
+    let features = BitstreamFeatures::new(&header).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?} isn't a valid WebP", path),
+        )
+    })?;
+    let channels = if features.has_alpha() { 4 } else { 3 };
+
+    Ok((features.width(), features.height(), channels))
+}
+
+#[cfg(test)]
+mod read_webp_info_tests {
+    use super::*;
+
+    use std::fs;
+
+    use webp::Encoder;
+
+    #[test]
+    fn it_reads_the_header() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_read_webp_info");
+        fs::create_dir_all(&dir)?;
+        let opaque = [10, 20, 30].repeat(6 * 4);
+        let transparent = [10, 20, 30, 128].repeat(6 * 4);
+        fs::write(
+            dir.join("opaque.webp"),
+            &*Encoder::from_rgb(&opaque, 6, 4).encode(80.0),
+        )?;
+        fs::write(
+            dir.join("transparent.webp"),
+            &*Encoder::from_rgba(&transparent, 6, 4).encode_lossless(),
+        )?;
+
+        assert_eq!(read_webp_info(&dir.join("opaque.webp"))?, (6, 4, 3));
+        assert_eq!(read_webp_info(&dir.join("transparent.webp"))?, (6, 4, 4));
+        assert!(read_webp_info(
+            &Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg")
+        )
+        .is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
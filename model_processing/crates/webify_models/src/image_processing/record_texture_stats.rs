@@ -0,0 +1,106 @@
+//! Gather the final on-disk and GPU size of a processed texture
+
+use std::{
+    fs::{self, File},
+    io::Error,
+    path::Path,
+    result::Result,
+};
+
+use image::{codecs::png::PngDecoder, ImageDecoder};
+
+use crate::image_processing::{
+    estimate_gpu_memory, read_avif_info, read_ktx2_info, read_webp_info,
+    TRANSCODED_BYTES_PER_PIXEL, UNCOMPRESSED_BYTES_PER_PIXEL,
+};
+use crate::model::find_model_dir;
+use crate::report::TextureStats;
+
+/// Gather the final on-disk and estimated GPU size of a processed texture
+pub fn record_texture_stats(path: &Path, base_path: &Path) -> Result<TextureStats, Error> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    if extension == Some("ktx2") {
+        return record_ktx2_stats(path, base_path);
+    }
+
+    let (width, height, channels) = if extension == Some("avif") {
+        read_avif_info(path)?
+    } else if extension == Some("webp") {
+        read_webp_info(path)?
+    } else {
+        let (width, height) = image::image_dimensions(path).map_err(|e| {
+            Error::other(format!("Could not read dimensions of {:?}: {:?}", path, e))
+        })?;
+        (width, height, read_channels(path)?)
+    };
+
+    Ok(TextureStats {
+        path: path.to_path_buf(),
+        model: find_model_dir(path, base_path).ok(),
+        width,
+        height,
+        channels,
+        file_bytes: fs::metadata(path)?.len(),
+        gpu_bytes: estimate_gpu_memory(width, height, UNCOMPRESSED_BYTES_PER_PIXEL),
+        encoding: None,
+    })
+}
+
+/// Stats of a KTX2 texture, which stays block compressed on the GPU and only has
+/// the mip levels stored in the file, the renderer can't generate them
+fn record_ktx2_stats(path: &Path, base_path: &Path) -> Result<TextureStats, Error> {
+    let info = read_ktx2_info(path)?;
+    let gpu_bytes = if info.levels > 1 {
+        estimate_gpu_memory(info.width, info.height, TRANSCODED_BYTES_PER_PIXEL)
+    } else {
+        u64::from(info.width) * u64::from(info.height) * TRANSCODED_BYTES_PER_PIXEL
+    };
+
+    Ok(TextureStats {
+        path: path.to_path_buf(),
+        model: find_model_dir(path, base_path).ok(),
+        width: info.width,
+        height: info.height,
+        channels: info.channels,
+        file_bytes: fs::metadata(path)?.len(),
+        gpu_bytes,
+        encoding: None,
+    })
+}
+
+/// Number of color channels of the image, read from the PNG header when possible
+/// rather than decoding the whole image
+fn read_channels(path: &Path) -> Result<u8, Error> {
+    let error = |e| Error::other(format!("Could not read color type of {:?}: {:?}", path, e));
+
+    let color_type = if path.extension().and_then(|e| e.to_str()) == Some("png") {
+        PngDecoder::new(File::open(path)?)
+            .map_err(error)?
+            .color_type()
+    } else {
+        image::open(path).map_err(error)?.color()
+    };
+
+    Ok(color_type.channel_count())
+}
+
+#[cfg(test)]
+mod record_texture_stats_tests {
+    use super::*;
+
+    #[test]
+    fn it_records_the_texture_stats() {
+        let base_path = Path::new("tests").join("image_processing");
+        let path = base_path.join("images").join("example.jpg");
+
+        let stats = record_texture_stats(&path, &base_path).unwrap();
+        assert_eq!(stats.model, Some(base_path.join("images")));
+        assert_eq!((stats.width, stats.height), (640, 427));
+        assert_eq!(stats.channels, 3);
+        assert_eq!(stats.file_bytes, 34837);
+        assert_eq!(
+            stats.gpu_bytes,
+            estimate_gpu_memory(640, 427, UNCOMPRESSED_BYTES_PER_PIXEL)
+        );
+    }
+}
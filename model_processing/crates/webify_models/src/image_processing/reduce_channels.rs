@@ -0,0 +1,103 @@
+//! Write a texture with the number of channels its role needs
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::{ColorType, DynamicImage};
+
+use crate::image_processing::DecodeCache;
+
+/// Rewrite the texture in place with `channels` channels, from 1 to 4: grayscale,
+/// grayscale and alpha, RGB or RGBA, at the same depth. Colors become grayscale by
+/// their luminance, which keeps the values of data maps stored as gray RGB, like
+/// roughness. Returns whether it was rewritten.
+pub fn reduce_channels(path: &Path, channels: u8, cache: &mut DecodeCache) -> Result<bool, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to change its channels: {:?}",
+            path, e
+        ))
+    })?;
+    if img.color().channel_count() == channels {
+        cache.insert(path, img);
+        return Ok(false);
+    }
+
+    let sixteen_bit = matches!(
+        img.color(),
+        ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16
+    );
+    let reduced = match (channels, sixteen_bit) {
+        (1, false) => DynamicImage::ImageLuma8(img.to_luma8()),
+        (1, true) => DynamicImage::ImageLuma16(img.to_luma16()),
+        (2, false) => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        (2, true) => DynamicImage::ImageLumaA16(img.to_luma_alpha16()),
+        (3, false) => DynamicImage::ImageRgb8(img.to_rgb8()),
+        (3, true) => DynamicImage::ImageRgb16(img.to_rgb16()),
+        (4, false) => DynamicImage::ImageRgba8(img.to_rgba8()),
+        (4, true) => DynamicImage::ImageRgba16(img.to_rgba16()),
+        _ => {
+            return Err(Error::other(format!(
+                "Textures have 1 to 4 channels, got {}",
+                channels
+            )))
+        }
+    };
+
+    reduced.save(path).map_err(|e| {
+        Error::other(format!(
+            "Could not save {:?} with {} channels: {:?}",
+            path, channels, e
+        ))
+    })?;
+    cache.insert(path, reduced);
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod reduce_channels_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn it_writes_gray_data_maps_with_a_single_channel() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_reduce_channels");
+        fs::create_dir_all(&dir)?;
+        let roughness = dir.join("floor_roughness.png");
+        ImageBuffer::from_fn(8, 4, |x, _| Rgb([x as u8 * 30; 3]))
+            .save(&roughness)
+            .map_err(Error::other)?;
+
+        let mut cache = DecodeCache::default();
+        assert!(reduce_channels(&roughness, 1, &mut cache)?);
+        assert!(!reduce_channels(
+            &roughness,
+            1,
+            &mut DecodeCache::default()
+        )?);
+
+        let reduced = image::open(&roughness).map_err(Error::other)?;
+        assert_eq!(reduced.color(), ColorType::L8);
+        assert_eq!(
+            reduced
+                .to_luma8()
+                .pixels()
+                .map(|p| p[0])
+                .collect::<Vec<u8>>()[..8],
+            [0, 30, 60, 90, 120, 150, 180, 210]
+        );
+        assert_eq!(
+            cache.take(&roughness).map(|image| image.color()),
+            Some(ColorType::L8)
+        );
+        assert!(reduce_channels(&roughness, 5, &mut cache).is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+//! Remove the copies of textures once nothing references them anymore
+
+use std::{fs, io::Error, result::Result};
+
+use crate::image_processing::find_mip_levels;
+use crate::options::Removal;
+use crate::provenance::sidecar_path;
+use crate::report::DuplicateTexture;
+use crate::trash::remove_file;
+
+/// Remove every duplicate texture as asked, along with its sidecar and the mip
+/// levels written next to it, which are copies of those of the canonical texture.
+/// Their references have to point at the canonical copies first.
+pub fn remove_duplicate_textures(
+    duplicates: &[DuplicateTexture],
+    removal: &Removal,
+) -> Result<(), Error> {
+    for duplicate in duplicates {
+        for level in find_mip_levels(&duplicate.path) {
+            remove_file(&level, removal)?;
+        }
+        if duplicate.path.is_file() {
+            remove_file(&duplicate.path, removal)?;
+        }
+        if sidecar_path(&duplicate.path).is_file() {
+            fs::remove_file(sidecar_path(&duplicate.path))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Rename processed textures along with their sidecars
+
+use std::{fs, io::Error, path::PathBuf, result::Result};
+
+use crate::options::Removal;
+use crate::provenance::sidecar_path;
+use crate::trash::remove_file;
+
+/// Rename each texture to its new path, with the sidecar marking it processed so
+/// reruns still skip it. A texture whose new path is taken, by a texture with the
+/// same contents since names are content hashes, is removed instead, as asked.
+pub fn rename_textures(renames: &[(PathBuf, PathBuf)], removal: &Removal) -> Result<(), Error> {
+    for (from, to) in renames {
+        for (from, to) in [
+            (from.clone(), to.clone()),
+            (sidecar_path(from), sidecar_path(to)),
+        ] {
+            if !from.is_file() {
+                continue;
+            }
+            match to.exists() {
+                true => remove_file(&from, removal)?,
+                false => fs::rename(&from, &to)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod rename_textures_tests {
+    use super::*;
+
+    use std::path::Path;
+
+    #[test]
+    fn it_renames_the_textures_and_keeps_the_duplicates_as_asked() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_rename_textures");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("wood.png"), "wood")?;
+        fs::write(dir.join("oak.png"), "wood")?;
+        let renames = vec![
+            (dir.join("wood.png"), dir.join("1f2e3d.png")),
+            (dir.join("oak.png"), dir.join("1f2e3d.png")),
+        ];
+
+        let renamed = rename_textures(&renames, &Removal::Orig);
+        let hashed = fs::read_to_string(dir.join("1f2e3d.png"));
+        let kept = fs::read_to_string(dir.join("oak.png.orig"));
+        let leftovers = dir.join("wood.png").exists() || dir.join("oak.png").exists();
+        fs::remove_dir_all(&dir)?;
+
+        renamed?;
+        assert_eq!(hashed?, "wood");
+        assert_eq!(kept?, "wood");
+        assert!(!leftovers);
+        Ok(())
+    }
+}
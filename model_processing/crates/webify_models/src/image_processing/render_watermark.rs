@@ -0,0 +1,244 @@
+//! Render the watermark of preview builds, once for every texture
+
+use std::{io::Error, result::Result};
+
+use image::{Rgba, RgbaImage};
+
+use crate::options::WatermarkMark;
+
+/// Width and height of the glyphs of the built-in font, in pixels
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// The watermark as an RGBA image, laid over textures with its alpha. Text is drawn
+/// with a built-in 5x7 pixel font, in white with a black outline, uppercase, and
+/// characters the font doesn't have are drawn as `?`. Overlays are opened as they
+/// are.
+pub fn render_watermark(mark: &WatermarkMark) -> Result<RgbaImage, Error> {
+    match mark {
+        WatermarkMark::Text(text) => render_text(text),
+        WatermarkMark::Overlay(path) => image::open(path)
+            .map(|overlay| overlay.to_rgba8())
+            .map_err(|e| {
+                Error::other(format!(
+                    "Failed to open the watermark overlay {:?}: {}",
+                    path, e
+                ))
+            }),
+    }
+}
+
+/// The text in white glyphs a pixel apart, outlined in black and padded by a pixel
+/// for the outline
+fn render_text(text: &str) -> Result<RgbaImage, Error> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(Error::other("The watermark text is empty"));
+    }
+
+    let count = text.chars().count() as u32;
+    let width = count * (GLYPH_WIDTH + 1) + 1;
+    let height = GLYPH_HEIGHT + 2;
+    let mut filled = vec![false; (width * height) as usize];
+    for (index, c) in text.chars().enumerate() {
+        let left = 1 + index as u32 * (GLYPH_WIDTH + 1);
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    filled[((row as u32 + 1) * width + left + column) as usize] = true;
+                }
+            }
+        }
+    }
+
+    let is_filled = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < i64::from(width)
+            && y < i64::from(height)
+            && filled[(y * i64::from(width) + x) as usize]
+    };
+    Ok(RgbaImage::from_fn(width, height, |x, y| {
+        let (x, y) = (i64::from(x), i64::from(y));
+        if is_filled(x, y) {
+            return Rgba([255, 255, 255, 255]);
+        }
+        let outline = (-1..=1).any(|dy| (-1..=1).any(|dx| is_filled(x + dx, y + dy)));
+        match outline {
+            true => Rgba([0, 0, 0, 255]),
+            false => Rgba([0, 0, 0, 0]),
+        }
+    }))
+}
+
+/// Rows of the glyph of the character, top to bottom, the leftmost pixel in the
+/// highest of the 5 bits
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'B' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+        'C' => [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+        'D' => [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+        'E' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+        'F' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'H' => [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'I' => [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        'J' => [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+        'K' => [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+        'L' => [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        'N' => [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+        'O' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'P' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'Q' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+        'R' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+        'S' => [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+        'T' => [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'U' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'V' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+        'W' => [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+        'X' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+        'Y' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'Z' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        ' ' => [0; 7],
+        '.' => [0, 0, 0, 0, 0, 0b01100, 0b01100],
+        ',' => [0, 0, 0, 0, 0b01100, 0b00100, 0b01000],
+        '-' => [0, 0, 0, 0b11111, 0, 0, 0],
+        '_' => [0, 0, 0, 0, 0, 0, 0b11111],
+        ':' => [0, 0b01100, 0b01100, 0, 0b01100, 0b01100, 0],
+        '/' => [
+            0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000,
+        ],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0, 0b00100],
+        '\'' => [0b00100, 0b00100, 0b01000, 0, 0, 0, 0],
+        '(' => [
+            0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+        ],
+        ')' => [
+            0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+        ],
+        '&' => [
+            0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101,
+        ],
+        '+' => [0, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0],
+        '@' => [
+            0b01110, 0b10001, 0b10111, 0b10101, 0b10111, 0b10000, 0b01110,
+        ],
+        '#' => [
+            0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+        ],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0, 0b00100],
+    }
+}
+
+#[cfg(test)]
+mod render_watermark_tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_outlined_text() -> Result<(), Error> {
+        let mark = render_watermark(&WatermarkMark::Text(String::from("Hi")))?;
+
+        assert_eq!(mark.dimensions(), (13, 9));
+        // The top left pixel of the H, outlined above and padded around
+        assert_eq!(mark.get_pixel(1, 1), &Rgba([255, 255, 255, 255]));
+        assert_eq!(mark.get_pixel(1, 0), &Rgba([0, 0, 0, 255]));
+        assert_eq!(mark.get_pixel(12, 0), &Rgba([0, 0, 0, 0]));
+        // Inside the H, outlined next to its left side
+        assert_eq!(mark.get_pixel(2, 2), &Rgba([0, 0, 0, 255]));
+        assert_eq!(mark.get_pixel(3, 2), &Rgba([0, 0, 0, 0]));
+        assert_eq!(glyph('é'), glyph('?'));
+        assert!(render_watermark(&WatermarkMark::Text(String::from("  "))).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_on_missing_overlays() {
+        let mark = WatermarkMark::Overlay("tests/image_processing/missing_logo.png".into());
+
+        assert!(render_watermark(&mark).is_err());
+    }
+}
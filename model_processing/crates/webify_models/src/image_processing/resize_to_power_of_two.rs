@@ -0,0 +1,81 @@
+//! Resize a texture so both its sides are powers of two
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::GenericImageView;
+
+use crate::image_processing::{DecodeCache, Dimensions};
+use crate::options::{PowerOfTwo, ResizeFilter};
+
+/// Resize the texture in place so its width and height are each rounded to a
+/// power of two as `mode` says, resampled with `filter`. The aspect ratio isn't
+/// kept, UVs span the texture whatever its size. Returns the original and new
+/// dimensions when it was resized.
+pub fn resize_to_power_of_two(
+    path: &Path,
+    mode: PowerOfTwo,
+    filter: ResizeFilter,
+    cache: &mut DecodeCache,
+) -> Result<Option<(Dimensions, Dimensions)>, Error> {
+    let img = cache
+        .take_or_open(path)
+        .map_err(|e| Error::other(format!("Failed to open {:?} to resize: {:?}", path, e)))?;
+    let (width, height) = img.dimensions();
+    let dimensions = (mode.round(width), mode.round(height));
+    if dimensions == (width, height) {
+        cache.insert(path, img);
+        return Ok(None);
+    }
+
+    let resized = img.resize_exact(dimensions.0, dimensions.1, filter.filter_type());
+    resized
+        .save(path)
+        .map_err(|e| Error::other(format!("Could not save resized {:?}: {:?}", path, e)))?;
+    cache.insert(path, resized);
+
+    Ok(Some(((width, height), dimensions)))
+}
+
+#[cfg(test)]
+mod resize_to_power_of_two_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ImageBuffer, Luma};
+
+    #[test]
+    fn it_resizes_each_side_to_a_power_of_two() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_resize_to_power_of_two");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("heightmap.png");
+        ImageBuffer::from_pixel(100, 64, Luma([40_000u16]))
+            .save(&path)
+            .map_err(Error::other)?;
+
+        let floor = resize_to_power_of_two(
+            &path,
+            PowerOfTwo::Floor,
+            ResizeFilter::Triangle,
+            &mut DecodeCache::default(),
+        );
+        let again = resize_to_power_of_two(
+            &path,
+            PowerOfTwo::Nearest,
+            ResizeFilter::Triangle,
+            &mut DecodeCache::default(),
+        );
+        let resized = image::open(&path).map_err(Error::other);
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(floor?, Some(((100, 64), (64, 64))));
+        assert_eq!(again?, None);
+        // 16 bit samples are kept
+        let resized = resized?;
+        assert_eq!(resized.color(), image::ColorType::L16);
+        assert_eq!(resized.dimensions(), (64, 64));
+        Ok(())
+    }
+}
@@ -1,15 +1,19 @@
 use console::style;
-use std::io::Result;
-use std::{fs, path::Path};
+use std::io::{Error, Result};
+use std::path::Path;
+use std::{thread, time::Duration, time::Instant};
 
+use crate::cli::create_spinner;
 use crate::image_processing::Image;
+use crate::report::{format_bytes, ScanStats};
+use crate::walk::{walk_dir_with_progress, WalkProgress};
 
-const TEXTURE_IMAGE_TYPES: [&str; 7] = [
-    r#"tif"#, r#"tga"#, r#"tiff"#, r#"jpeg"#, r#"jpg"#, r#"gif"#, r#"png"#,
-];
+/// How often the scan counter is refreshed
+const SCAN_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
 
-/// Find texture images in the specified path
-pub fn scan_dir_for_images(dir: &Path) -> Result<Vec<Image>> {
+/// Find texture images with one of the extensions in the specified path, showing a
+/// live count of what was scanned so far and returning it along with the images
+pub fn scan_dir_for_images(dir: &Path, extensions: &[String]) -> Result<(Vec<Image>, ScanStats)> {
     println!("\nScanning for images to webify...");
     println!(
     "{}",
@@ -19,53 +23,90 @@ pub fn scan_dir_for_images(dir: &Path) -> Result<Vec<Image>> {
     .on_red()
   );
 
-    let mut images = match recursive_scan(dir, Vec::new()) {
-        Ok(image_list) => image_list,
-        Err(error) => panic!("Failed to scan all directories for images: {:?}", error),
-    };
+    let started = Instant::now();
+    let progress = WalkProgress::new();
+    let spinner = create_spinner();
+    spinner.set_prefix("Texture Scan");
+    let scanned = thread::scope(|scope| {
+        let scan = scope.spawn(|| recursive_scan(dir, extensions, Vec::new(), &progress));
+        while !scan.is_finished() {
+            spinner.set_message(&format!(
+                "{} directories, {} textures ({})",
+                progress.directories(),
+                progress.files(),
+                format_bytes(progress.bytes())
+            ));
+            spinner.tick();
+            thread::sleep(SCAN_REFRESH_INTERVAL);
+        }
+        scan.join().unwrap()
+    });
+    spinner.finish_and_clear();
+
+    let mut images = scanned.map_err(|error| {
+        Error::other(format!(
+            "Failed to scan all directories for images: {:?}",
+            error
+        ))
+    })?;
     images.sort_by(|a, b| b.extension.cmp(&a.extension));
 
     println!("Images found: {}\n", style(images.len()).bold().blue());
 
-    Ok(images)
+    let stats = ScanStats {
+        directories: progress.directories(),
+        candidates: progress.files(),
+        bytes: progress.bytes(),
+        duration: started.elapsed(),
+        up_to_date: 0,
+    };
+    Ok((images, stats))
 }
 
 /// Recursively scan the directory and only return files that qualify
 /// as the images we're looking for
-fn recursive_scan(dir: &Path, mut images: Vec<Image>) -> Result<Vec<Image>> {
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir)? {
-            let e = entry?;
-            let path = e.path();
-
-            if path.is_dir() {
-                images = recursive_scan(&path, images.clone())?;
-            } else {
-                let extension = match path.extension() {
-                    Some(ext) => ext.to_str().unwrap(),
-                    _ => "",
-                };
-
-                if TEXTURE_IMAGE_TYPES.contains(&extension) {
-                    images.push(Image {
-                        path: path.clone(),
-                        extension: extension.to_string(),
-                    });
-                };
-            }
-        }
+fn recursive_scan(
+    dir: &Path,
+    extensions: &[String],
+    mut images: Vec<Image>,
+    progress: &WalkProgress,
+) -> Result<Vec<Image>> {
+    let is_image = |path: &Path| extensions.iter().any(|e| e == extension_of(path));
+    for path in walk_dir_with_progress(dir, &is_image, progress)? {
+        images.push(Image {
+            extension: extension_of(&path).to_string(),
+            path,
+        });
     }
 
     Ok(images)
 }
 
+/// Extension of the file, or an empty string
+fn extension_of(path: &Path) -> &str {
+    path.extension().and_then(|ext| ext.to_str()).unwrap_or("")
+}
+
 #[cfg(test)]
 mod scan_dir_for_images_tests {
+    use super::*;
+
+    use crate::image_processing::TEXTURE_IMAGE_TYPES;
 
     #[test]
-    #[ignore = "not yet implemented"]
-    fn it_scans_the_dir() {
-        assert!(false);
+    fn it_scans_the_dir() -> Result<()> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("image_scan");
+        let extensions: Vec<String> = TEXTURE_IMAGE_TYPES.iter().map(|e| e.to_string()).collect();
+        let (images, stats) = scan_dir_for_images(&dir, &extensions)?;
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].extension, "jpg");
+        assert_eq!(stats.candidates, 1);
+        assert!(stats.directories >= 2);
+
+        Ok(())
     }
 }
 
@@ -73,17 +114,22 @@ mod scan_dir_for_images_tests {
 mod recursive_scan_tests {
     use super::*;
 
+    use crate::image_processing::TEXTURE_IMAGE_TYPES;
+
     #[test]
     fn it_recursively_scans_the_dir() {
         let dir = &Path::new("tests")
             .join("image_processing")
             .join("image_scan");
-        let results = recursive_scan(dir, Vec::new()).unwrap();
+        let progress = WalkProgress::new();
+        let extensions: Vec<String> = TEXTURE_IMAGE_TYPES.iter().map(|e| e.to_string()).collect();
+        let results = recursive_scan(dir, &extensions, Vec::new(), &progress).unwrap();
 
         assert_eq!(results.len(), 1);
         assert_eq!(
             results[0].path,
             dir.join("textures").join("materials").join("example.jpg")
         );
+        assert_eq!(progress.files(), 1);
     }
 }
@@ -0,0 +1,19 @@
+//! Layout and timing of a sprite sheet an animated GIF became
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Grid of a sprite sheet, frames laid out left to right then top to bottom in
+/// cells of equal size. Frame `i` spans the UVs from `(i % columns / columns,
+/// i / columns / rows)`, from the top, over `1 / columns` by `1 / rows`, which
+/// downscaling the sheet leaves the same.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteSheetMetadata {
+    pub columns: u32,
+    pub rows: u32,
+    /// Number of frames, the last row being partly empty when fewer than cells
+    pub frames: u32,
+    /// How long each frame is shown, in milliseconds
+    pub delays: Vec<u32>,
+}
@@ -0,0 +1,206 @@
+//! Strip the metadata of textures, like the camera and location they were shot with
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+/// Signature every PNG starts with
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// PNG chunks carrying metadata: EXIF (with its GPS tags), text, which XMP is
+/// stored in, and the time of the last modification
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// WebP chunks carrying metadata
+const WEBP_METADATA_CHUNKS: [&[u8; 4]; 2] = [b"EXIF", b"XMP "];
+
+/// Flags of the `VP8X` chunk of WebPs telling an `EXIF` or `XMP ` chunk follows
+const WEBP_EXIF_FLAG: u8 = 0x08;
+const WEBP_XMP_FLAG: u8 = 0x04;
+
+/// Bytes of a file without its metadata chunks, and the kinds of the chunks
+type Stripped = (Vec<u8>, Vec<String>);
+
+/// Strip the metadata chunks of the texture, EXIF, XMP, text and timestamps, so
+/// the camera serial numbers and GPS locations of photos don't end up in the
+/// bundle. The colorspace chunks and the pixels are left as they are. Only PNGs and
+/// WebPs are stripped: the AVIF and KTX2 encoders don't write any metadata, and
+/// other formats are converted before. Returns the kinds of the chunks stripped,
+/// the file is left alone when there are none.
+pub fn strip_metadata(path: &Path) -> Result<Vec<String>, Error> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let strip: fn(&[u8]) -> Result<Stripped, Error> = match extension.as_str() {
+        "png" => strip_png,
+        "webp" => strip_webp,
+        _ => return Ok(Vec::new()),
+    };
+
+    let bytes = fs::read(path)?;
+    let (stripped, kinds) = strip(&bytes)
+        .map_err(|e| Error::other(format!("Failed to strip the metadata of {:?}: {}", path, e)))?;
+    if !kinds.is_empty() {
+        fs::write(path, stripped)?;
+    }
+    Ok(kinds)
+}
+
+/// The PNG without its metadata chunks, and their kinds
+fn strip_png(png: &[u8]) -> Result<Stripped, Error> {
+    if !png.starts_with(&PNG_SIGNATURE) {
+        return Err(Error::other("not a PNG"));
+    }
+
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    let mut kinds = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+    while offset < png.len() {
+        let length = png
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| Error::other("truncated PNG chunk"))?;
+        let end = offset + 12 + length;
+        let chunk = png
+            .get(offset..end)
+            .ok_or_else(|| Error::other("truncated PNG chunk"))?;
+        let kind = &chunk[4..8];
+
+        match PNG_METADATA_CHUNKS.iter().any(|tag| kind == &tag[..]) {
+            true => push_kind(&mut kinds, kind),
+            false => stripped.extend_from_slice(chunk),
+        }
+        offset = end;
+    }
+
+    Ok((stripped, kinds))
+}
+
+/// The WebP without its metadata chunks, and their kinds. The `VP8X` flags telling
+/// they follow are cleared, and the size of the RIFF container updated.
+fn strip_webp(webp: &[u8]) -> Result<Stripped, Error> {
+    if webp.len() < 12 || &webp[0..4] != b"RIFF" || &webp[8..12] != b"WEBP" {
+        return Err(Error::other("not a WebP"));
+    }
+
+    let mut stripped = webp[0..12].to_vec();
+    let mut kinds = Vec::new();
+    let mut offset = 12;
+    while offset < webp.len() {
+        let size = webp
+            .get(offset + 4..offset + 8)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| Error::other("truncated WebP chunk"))?;
+        if offset + 8 + size > webp.len() {
+            return Err(Error::other("truncated WebP chunk"));
+        }
+        // Chunks are padded to an even size, which some writers leave out at the end
+        let end = (offset + 8 + size + size % 2).min(webp.len());
+        let chunk = &webp[offset..end];
+        let kind = &chunk[0..4];
+
+        if WEBP_METADATA_CHUNKS.iter().any(|tag| kind == &tag[..]) {
+            push_kind(&mut kinds, kind);
+        } else {
+            stripped.extend_from_slice(chunk);
+        }
+        offset = end;
+    }
+
+    if let Some(vp8x) = stripped.windows(4).position(|window| window == b"VP8X") {
+        if let Some(flags) = stripped.get_mut(vp8x + 8) {
+            *flags &= !(WEBP_EXIF_FLAG | WEBP_XMP_FLAG);
+        }
+    }
+    let riff_size = (stripped.len() - 8) as u32;
+    stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    Ok((stripped, kinds))
+}
+
+/// Add the kind of chunk to the kinds stripped, once
+fn push_kind(kinds: &mut Vec<String>, kind: &[u8]) {
+    let kind = String::from_utf8_lossy(kind).trim_end().to_string();
+    if !kinds.contains(&kind) {
+        kinds.push(kind);
+    }
+}
+
+#[cfg(test)]
+mod strip_metadata_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use image::{Rgba, RgbaImage};
+
+    use crate::image_processing::tag_colorspace;
+    use crate::options::Colorspace;
+
+    /// The PNG with a chunk of the kind with the data inserted right after its header
+    fn with_png_chunk(png: &[u8], kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let header_end = PNG_SIGNATURE.len() + 25;
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        // A made up CRC, stripping doesn't check it
+        chunk.extend_from_slice(&[0; 4]);
+
+        [&png[..header_end], &chunk[..], &png[header_end..]].concat()
+    }
+
+    #[test]
+    fn it_strips_the_metadata_of_pngs_without_touching_their_pixels() -> Result<(), Error> {
+        let dir = PathBuf::from("tests")
+            .join("image_processing")
+            .join("test_run_strip_metadata");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("storefront.png");
+        let pixels = RgbaImage::from_fn(8, 8, |x, y| Rgba([x as u8 * 30, y as u8 * 30, 7, 255]));
+        pixels.save(&path).map_err(Error::other)?;
+        tag_colorspace(&path, Colorspace::Srgb)?;
+        let png = fs::read(&path)?;
+        let png = with_png_chunk(&png, b"eXIf", b"MM\0*serial 4815162342");
+        let png = with_png_chunk(&png, b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>");
+        fs::write(&path, &png)?;
+
+        let stripped = strip_metadata(&path);
+        let stripped_png = fs::read(&path);
+        let again = strip_metadata(&path);
+        let decoded = image::open(&path).map(|img| img.to_rgba8());
+        let ktx2 = strip_metadata(&dir.join("storefront.ktx2"));
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(stripped?, ["iTXt", "eXIf"]);
+        let stripped_png = stripped_png?;
+        assert!(!stripped_png.windows(6).any(|window| window == b"serial"));
+        assert!(!stripped_png.windows(9).any(|window| window == b"x:xmpmeta"));
+        assert!(stripped_png.windows(4).any(|window| window == b"gAMA"));
+        assert!(again?.is_empty());
+        assert_eq!(decoded.map_err(Error::other)?, pixels);
+        assert!(ktx2?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_strips_the_metadata_of_webps() -> Result<(), Error> {
+        let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+        webp.extend_from_slice(b"VP8X\x0a\0\0\0");
+        webp.extend_from_slice(&[WEBP_EXIF_FLAG | WEBP_XMP_FLAG, 0, 0, 0, 7, 0, 0, 7, 0, 0]);
+        webp.extend_from_slice(b"VP8L\x03\0\0\0abc\0");
+        webp.extend_from_slice(b"EXIF\x05\0\0\0GPS42\0");
+        webp.extend_from_slice(b"XMP \x02\0\0\0<>");
+        let riff_size = (webp.len() - 8) as u32;
+        webp[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let (stripped, kinds) = strip_webp(&webp)?;
+
+        assert_eq!(kinds, ["EXIF", "XMP"]);
+        let mut expected = b"RIFF\x22\0\0\0WEBP".to_vec();
+        expected.extend_from_slice(b"VP8X\x0a\0\0\0");
+        expected.extend_from_slice(&[0, 0, 0, 0, 7, 0, 0, 7, 0, 0]);
+        expected.extend_from_slice(b"VP8L\x03\0\0\0abc\0");
+        assert_eq!(stripped, expected);
+        assert!(strip_webp(b"RIFF\0\0\0\0AVI ").is_err());
+        Ok(())
+    }
+}
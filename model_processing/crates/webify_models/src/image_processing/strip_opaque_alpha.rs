@@ -0,0 +1,89 @@
+//! Drop the alpha channel of a texture when every pixel is opaque
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::{DynamicImage, Pixel};
+
+use crate::image_processing::DecodeCache;
+
+/// Rewrite the texture in place without its alpha channel when every pixel is
+/// fully opaque, RGBA becoming RGB and gray with alpha plain gray, at the same
+/// depth. Returns whether it was rewritten.
+pub fn strip_opaque_alpha(path: &Path, cache: &mut DecodeCache) -> Result<bool, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to check its alpha: {:?}",
+            path, e
+        ))
+    })?;
+
+    let stripped = match &img {
+        DynamicImage::ImageRgba8(rgba) if rgba.pixels().all(|p| p.channels()[3] == u8::MAX) => {
+            DynamicImage::ImageRgb8(img.to_rgb8())
+        }
+        DynamicImage::ImageLumaA8(gray) if gray.pixels().all(|p| p.channels()[1] == u8::MAX) => {
+            DynamicImage::ImageLuma8(img.to_luma8())
+        }
+        DynamicImage::ImageRgba16(rgba) if rgba.pixels().all(|p| p.channels()[3] == u16::MAX) => {
+            DynamicImage::ImageRgb16(img.to_rgb16())
+        }
+        DynamicImage::ImageLumaA16(gray) if gray.pixels().all(|p| p.channels()[1] == u16::MAX) => {
+            DynamicImage::ImageLuma16(img.to_luma16())
+        }
+        _ => {
+            cache.insert(path, img);
+            return Ok(false);
+        }
+    };
+
+    stripped
+        .save(path)
+        .map_err(|e| Error::other(format!("Could not save {:?} without alpha: {:?}", path, e)))?;
+    cache.insert(path, stripped);
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod strip_opaque_alpha_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ColorType, ImageBuffer, LumaA, Rgba};
+
+    #[test]
+    fn it_strips_the_alpha_of_opaque_textures_only() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_strip_opaque_alpha");
+        fs::create_dir_all(&dir)?;
+        let opaque = dir.join("wall.png");
+        let pixels = ImageBuffer::from_fn(8, 4, |x, y| Rgba([x as u8 * 30, y as u8 * 60, 7, 255]));
+        pixels.save(&opaque).map_err(Error::other)?;
+        let cutout = dir.join("leaves.png");
+        ImageBuffer::from_fn(8, 4, |x, _| {
+            LumaA([90u16 * 256, if x == 3 { 0 } else { 65535 }])
+        })
+        .save(&cutout)
+        .map_err(Error::other)?;
+
+        let mut cache = DecodeCache::default();
+        assert!(strip_opaque_alpha(&opaque, &mut cache)?);
+        assert!(!strip_opaque_alpha(&cutout, &mut cache)?);
+        assert!(!strip_opaque_alpha(&opaque, &mut DecodeCache::default())?);
+
+        let stripped = image::open(&opaque).map_err(Error::other)?;
+        assert_eq!(stripped.color(), ColorType::Rgb8);
+        assert_eq!(stripped.to_rgba8(), pixels);
+        assert_eq!(
+            cache.take(&opaque).map(|image| image.color()),
+            Some(ColorType::Rgb8)
+        );
+        let kept = image::open(&cutout).map_err(Error::other)?;
+        assert_eq!(kept.color(), ColorType::La16);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
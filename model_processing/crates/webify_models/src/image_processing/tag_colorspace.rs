@@ -0,0 +1,213 @@
+//! Tag textures with the colorspace of their pixels
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::options::Colorspace;
+
+/// Signature every PNG starts with
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// PNG chunks describing the colorspace, replaced by the tag
+const PNG_COLORSPACE_CHUNKS: [&[u8; 4]; 4] = [b"sRGB", b"gAMA", b"iCCP", b"cHRM"];
+
+/// `gAMA` of sRGB, 1 / 2.2 scaled by 100000 as the PNG specification recommends
+/// alongside `sRGB` for older decoders
+const PNG_SRGB_GAMMA: u32 = 45455;
+
+/// `gAMA` of linear data
+const PNG_LINEAR_GAMMA: u32 = 100_000;
+
+/// Transfer characteristics of the `nclx` color box of AVIF, from ISO/IEC 23091-2
+const AVIF_TRANSFER_SRGB: u16 = 13;
+const AVIF_TRANSFER_LINEAR: u16 = 8;
+
+/// Tag the texture with the colorspace its pixels are in, so the renderer samples
+/// them right: PNGs get an `sRGB` chunk, or a `gAMA` of 1 when linear, in place of
+/// any colorspace chunk they had, and AVIFs get the transfer characteristics of
+/// their `colr` box set. The pixels are left as they are. Other formats have no tag
+/// to write: KTX2 is tagged when it's encoded, and WebP and JPEG only carry ICC
+/// profiles. Returns whether the file was rewritten, it's left alone when it
+/// already has the tag.
+pub fn tag_colorspace(path: &Path, colorspace: Colorspace) -> Result<bool, Error> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let tag: fn(&[u8], Colorspace) -> Result<Vec<u8>, Error> = match extension.as_str() {
+        "png" => tag_png,
+        "avif" => tag_avif,
+        _ => return Ok(false),
+    };
+
+    let bytes = fs::read(path)?;
+    let tagged = tag(&bytes, colorspace)
+        .map_err(|e| Error::other(format!("Failed to tag the colorspace of {:?}: {}", path, e)))?;
+    if tagged == bytes {
+        return Ok(false);
+    }
+    fs::write(path, tagged)?;
+    Ok(true)
+}
+
+/// The PNG with its colorspace chunks replaced by the ones of the colorspace,
+/// right after its header
+fn tag_png(png: &[u8], colorspace: Colorspace) -> Result<Vec<u8>, Error> {
+    if !png.starts_with(&PNG_SIGNATURE) {
+        return Err(Error::other("not a PNG"));
+    }
+
+    let mut tagged = PNG_SIGNATURE.to_vec();
+    let mut offset = PNG_SIGNATURE.len();
+    while offset < png.len() {
+        let length = png
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| Error::other("truncated PNG chunk"))?;
+        let end = offset + 12 + length;
+        let chunk = png
+            .get(offset..end)
+            .ok_or_else(|| Error::other("truncated PNG chunk"))?;
+        let kind = &chunk[4..8];
+
+        if !PNG_COLORSPACE_CHUNKS.iter().any(|tag| kind == &tag[..]) {
+            tagged.extend_from_slice(chunk);
+        }
+        if kind == b"IHDR" {
+            match colorspace {
+                Colorspace::Srgb => {
+                    // Perceptual rendering intent
+                    write_png_chunk(&mut tagged, b"sRGB", &[0]);
+                    write_png_chunk(&mut tagged, b"gAMA", &PNG_SRGB_GAMMA.to_be_bytes());
+                }
+                Colorspace::Linear => {
+                    write_png_chunk(&mut tagged, b"gAMA", &PNG_LINEAR_GAMMA.to_be_bytes())
+                }
+            }
+        }
+        offset = end;
+    }
+
+    Ok(tagged)
+}
+
+/// Append a chunk of the kind with the data to the PNG
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&[&kind[..], data].concat()).to_be_bytes());
+}
+
+/// CRC-32 of PNG chunks, the ISO 3309 one
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+/// The AVIF with the transfer characteristics of its `nclx` color box set to the
+/// ones of the colorspace. The AV1 sequence header keeps the sRGB ones ravif
+/// writes, browsers go by the box.
+fn tag_avif(avif: &[u8], colorspace: Colorspace) -> Result<Vec<u8>, Error> {
+    let colr = avif
+        .windows(8)
+        .position(|window| window == b"colrnclx")
+        .ok_or_else(|| Error::other("AVIF without an nclx color box"))?;
+    let transfer = match colorspace {
+        Colorspace::Srgb => AVIF_TRANSFER_SRGB,
+        Colorspace::Linear => AVIF_TRANSFER_LINEAR,
+    };
+
+    // The box type and color type, then the color primaries before the transfer
+    let offset = colr + 10;
+    let mut tagged = avif.to_vec();
+    tagged
+        .get_mut(offset..offset + 2)
+        .ok_or_else(|| Error::other("truncated AVIF color box"))?
+        .copy_from_slice(&transfer.to_be_bytes());
+    Ok(tagged)
+}
+
+#[cfg(test)]
+mod tag_colorspace_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use image::{Rgba, RgbaImage};
+
+    /// Kinds of the chunks of the PNG, in order
+    fn chunk_kinds(png: &[u8]) -> Vec<String> {
+        let mut kinds = Vec::new();
+        let mut offset = PNG_SIGNATURE.len();
+        while offset < png.len() {
+            let length = u32::from_be_bytes([
+                png[offset],
+                png[offset + 1],
+                png[offset + 2],
+                png[offset + 3],
+            ]) as usize;
+            kinds.push(String::from_utf8_lossy(&png[offset + 4..offset + 8]).into_owned());
+            offset += 12 + length;
+        }
+        kinds
+    }
+
+    #[test]
+    fn it_tags_pngs_without_touching_their_pixels() -> Result<(), Error> {
+        let dir = PathBuf::from("tests")
+            .join("image_processing")
+            .join("test_run_tag_colorspace");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("floor_roughness.png");
+        let pixels = RgbaImage::from_fn(8, 8, |x, y| Rgba([x as u8 * 30, y as u8 * 30, 7, 255]));
+        pixels.save(&path).map_err(Error::other)?;
+
+        let linear = tag_colorspace(&path, Colorspace::Linear);
+        let linear_png = fs::read(&path);
+        let again = tag_colorspace(&path, Colorspace::Linear);
+        let srgb = tag_colorspace(&path, Colorspace::Srgb);
+        let srgb_png = fs::read(&path);
+        let decoded = image::open(&path).map(|img| img.to_rgba8());
+        let webp = tag_colorspace(&dir.join("floor.webp"), Colorspace::Linear);
+        fs::remove_dir_all(&dir)?;
+
+        assert!(linear?);
+        let linear_png = linear_png?;
+        assert_eq!(chunk_kinds(&linear_png)[..2], ["IHDR", "gAMA"]);
+        assert_eq!(linear_png[41..45], 100_000u32.to_be_bytes());
+        assert!(!again?);
+        assert!(srgb?);
+        assert_eq!(chunk_kinds(&srgb_png?)[..3], ["IHDR", "sRGB", "gAMA"]);
+        assert_eq!(decoded.map_err(Error::other)?, pixels);
+        assert!(!webp?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_sets_the_transfer_of_avifs() -> Result<(), Error> {
+        let mut avif = b"....ftypavif....colrnclx".to_vec();
+        avif.extend_from_slice(&[0, 1, 0, 13, 0, 1, 0x80]);
+
+        let tagged = tag_avif(&avif, Colorspace::Linear)?;
+
+        assert_eq!(tagged[26..28], [0, 8]);
+        assert_eq!(tag_avif(&tagged, Colorspace::Srgb)?, avif);
+        assert!(tag_avif(b"....ftypavif", Colorspace::Srgb).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_computes_the_crc_of_png_chunks() {
+        // The IEND chunk every PNG ends with
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+}
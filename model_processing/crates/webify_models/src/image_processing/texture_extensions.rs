@@ -0,0 +1,46 @@
+//! Which file extensions are scanned as textures
+
+use crate::config::WebifyConfig;
+use crate::options::RunOptions;
+
+/// Extensions always scanned as textures
+pub const TEXTURE_IMAGE_TYPES: [&str; 15] = [
+    r#"tif"#, r#"tga"#, r#"tiff"#, r#"jpeg"#, r#"jpg"#, r#"gif"#, r#"png"#, r#"bmp"#, r#"psd"#,
+    r#"avif"#, r#"webp"#, r#"ktx2"#, r#"dds"#, r#"exr"#, r#"hdr"#,
+];
+
+/// Every extension scanned as a texture: the defaults, plus the `extensions` of
+/// `webify.toml` and `--extensions`, lowercased and without a leading dot
+pub fn texture_extensions(options: &RunOptions, config: &WebifyConfig) -> Vec<String> {
+    let mut extensions: Vec<String> = TEXTURE_IMAGE_TYPES.iter().map(|e| e.to_string()).collect();
+    let added = config.extensions.iter().chain(&options.extensions);
+    for extension in added.map(|e| e.trim().trim_start_matches('.').to_lowercase()) {
+        if !extension.is_empty() && !extensions.contains(&extension) {
+            extensions.push(extension);
+        }
+    }
+
+    extensions
+}
+
+#[cfg(test)]
+mod texture_extensions_tests {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_configured_extensions() {
+        let options = RunOptions {
+            extensions: vec![String::from(".KTX"), String::from("png")],
+            ..RunOptions::default()
+        };
+        let config = WebifyConfig {
+            extensions: vec![String::from("webp"), String::from("ktx")],
+            ..WebifyConfig::default()
+        };
+
+        let extensions = texture_extensions(&options, &config);
+        let defaults = TEXTURE_IMAGE_TYPES.len();
+        assert_eq!(&extensions[..defaults], &TEXTURE_IMAGE_TYPES);
+        assert_eq!(&extensions[defaults..], &["ktx"]);
+    }
+}
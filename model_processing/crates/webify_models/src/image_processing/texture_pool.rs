@@ -0,0 +1,107 @@
+//! Keep track of which models reference each texture of a collection's shared pool
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::options::Removal;
+use crate::trash::remove_file;
+
+/// Textures pooled in the shared textures directory of collections, along with the
+/// models referencing them. A pooled texture is only deleted once the last model
+/// referencing it lets go of it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TexturePool {
+    references: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl TexturePool {
+    pub fn new() -> TexturePool {
+        TexturePool::default()
+    }
+
+    /// Record that the model uses the pooled texture
+    pub fn add_reference(&mut self, pooled: &Path, model_dir: &Path) {
+        self.references
+            .entry(pooled.to_path_buf())
+            .or_default()
+            .insert(model_dir.to_path_buf());
+    }
+
+    /// Number of models using the pooled texture
+    pub fn reference_count(&self, pooled: &Path) -> usize {
+        self.references.get(pooled).map_or(0, BTreeSet::len)
+    }
+
+    /// Every pooled texture, with the models using it
+    pub fn textures(&self) -> impl Iterator<Item = (&PathBuf, &BTreeSet<PathBuf>)> {
+        self.references.iter()
+    }
+
+    /// Drop the model's reference to the pooled texture, deleting the pooled copy
+    /// as asked once no model uses it anymore. Returns whether the pooled copy was
+    /// deleted.
+    pub fn release(
+        &mut self,
+        pooled: &Path,
+        model_dir: &Path,
+        removal: &Removal,
+    ) -> std::result::Result<bool, std::io::Error> {
+        let models = match self.references.get_mut(pooled) {
+            Some(models) => models,
+            None => return Ok(false),
+        };
+        models.remove(model_dir);
+        if !models.is_empty() {
+            return Ok(false);
+        }
+
+        self.references.remove(pooled);
+        if pooled.exists() {
+            remove_file(pooled, removal)?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod texture_pool_tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn it_keeps_pooled_textures_until_the_last_reference_is_released(
+    ) -> std::result::Result<(), std::io::Error> {
+        let pool_dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_texture_pool");
+        fs::create_dir_all(&pool_dir)?;
+        let pooled = pool_dir.join("wood.png");
+        fs::write(&pooled, "wood")?;
+
+        let mut pool = TexturePool::new();
+        pool.add_reference(&pooled, Path::new("lamp"));
+        pool.add_reference(&pooled, Path::new("desk"));
+        pool.add_reference(&pooled, Path::new("desk"));
+        assert_eq!(pool.reference_count(&pooled), 2);
+
+        assert!(!pool.release(&pooled, Path::new("lamp"), &Removal::Delete)?);
+        assert!(pooled.exists());
+
+        assert!(pool.release(&pooled, Path::new("desk"), &Removal::Delete)?);
+        assert!(!pooled.exists());
+        assert_eq!(pool.reference_count(&pooled), 0);
+
+        fs::write(&pooled, "wood")?;
+        pool.add_reference(&pooled, Path::new("lamp"));
+        assert!(pool.release(&pooled, Path::new("lamp"), &Removal::Orig)?);
+        assert!(!pooled.exists());
+        assert!(pool_dir.join("wood.png.orig").exists());
+
+        fs::remove_dir_all(pool_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! What a texture is sampled for by the materials of a model
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::image_processing::has_normal_map_name;
+use crate::options::Colorspace;
+
+/// What a texture is sampled for, telling the colorspace its pixels are in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureRole {
+    /// Base color, e.g. `wall_diffuse` or `wall_albedo`
+    Diffuse,
+    /// Light given off, e.g. `screen_emissive`
+    Emissive,
+    /// Color of the reflections, e.g. `tile_specular`
+    Specular,
+    /// Tangent space normals, e.g. `wall_normal`
+    Normal,
+    /// Roughness or glossiness, e.g. `floor_roughness`
+    Roughness,
+    /// Metalness, e.g. `pipe_metallic`
+    Metalness,
+    /// Ambient occlusion, e.g. `crate_ao`
+    Occlusion,
+    /// Heights and displacements, e.g. `rock_height`
+    Height,
+    /// Occlusion, roughness and metalness packed in red, green and blue, e.g.
+    /// `crate_orm`
+    Orm,
+}
+
+impl TextureRole {
+    /// Every role
+    pub const ALL: [TextureRole; 9] = [
+        TextureRole::Diffuse,
+        TextureRole::Emissive,
+        TextureRole::Specular,
+        TextureRole::Normal,
+        TextureRole::Roughness,
+        TextureRole::Metalness,
+        TextureRole::Occlusion,
+        TextureRole::Height,
+        TextureRole::Orm,
+    ];
+
+    /// Name of the role in `webify.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            TextureRole::Diffuse => "diffuse",
+            TextureRole::Emissive => "emissive",
+            TextureRole::Specular => "specular",
+            TextureRole::Normal => "normal",
+            TextureRole::Roughness => "roughness",
+            TextureRole::Metalness => "metalness",
+            TextureRole::Occlusion => "occlusion",
+            TextureRole::Height => "height",
+            TextureRole::Orm => "orm",
+        }
+    }
+
+    /// Last words of the file stems of textures of the role, after a `_`, `-`, `.`
+    /// or space. Normal maps are told by `has_normal_map_name`.
+    fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            TextureRole::Diffuse => &[
+                "diffuse",
+                "diff",
+                "albedo",
+                "basecolor",
+                "color",
+                "colour",
+                "col",
+            ],
+            TextureRole::Emissive => &["emissive", "emission", "emit", "glow"],
+            TextureRole::Specular => &["specular", "spec"],
+            TextureRole::Normal => &[],
+            TextureRole::Roughness => &["roughness", "rough", "glossiness", "gloss"],
+            TextureRole::Metalness => &["metalness", "metallic", "metal"],
+            TextureRole::Occlusion => &["ao", "occlusion", "ambientocclusion"],
+            TextureRole::Height => &["height", "heightmap", "displacement", "disp", "bump"],
+            TextureRole::Orm => &["orm"],
+        }
+    }
+
+    /// Role of the texture by its file name, `None` when it's named after none
+    pub fn from_name(path: &Path) -> Option<TextureRole> {
+        if has_normal_map_name(path) {
+            return Some(TextureRole::Normal);
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let mut words = stem.split(['_', '-', '.', ' ']);
+        let last = words.next_back().unwrap_or_default();
+        // A stem of a single word is only a suffix when it's spelled out
+        if words.next().is_none() && last.len() < 4 {
+            return None;
+        }
+        TextureRole::ALL
+            .iter()
+            .find(|role| role.suffixes().contains(&last))
+            .copied()
+    }
+
+    /// Colorspace of the textures of the role: colors are sRGB, data is linear
+    pub fn default_colorspace(self) -> Colorspace {
+        match self {
+            TextureRole::Diffuse | TextureRole::Emissive | TextureRole::Specular => {
+                Colorspace::Srgb
+            }
+            _ => Colorspace::Linear,
+        }
+    }
+}
+
+#[cfg(test)]
+mod texture_role_tests {
+    use super::*;
+
+    #[test]
+    fn it_tells_the_role_of_textures_by_their_name() {
+        for (name, role) in [
+            ("wall_diffuse.png", Some(TextureRole::Diffuse)),
+            ("Wall-BaseColor.jpg", Some(TextureRole::Diffuse)),
+            ("screen_emissive.png", Some(TextureRole::Emissive)),
+            ("wall_nrm.png", Some(TextureRole::Normal)),
+            ("floor_Roughness.png", Some(TextureRole::Roughness)),
+            ("pipe_metallic.png", Some(TextureRole::Metalness)),
+            ("crate_ao.png", Some(TextureRole::Occlusion)),
+            ("roughness.png", Some(TextureRole::Roughness)),
+            ("rock height.tga", Some(TextureRole::Height)),
+            ("crate_orm.png", Some(TextureRole::Orm)),
+            ("wood.png", None),
+            ("ao.png", None),
+            ("wall_colors.png", None),
+        ] {
+            assert_eq!(TextureRole::from_name(Path::new(name)), role, "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_reads_colors_in_srgb_and_data_in_linear() {
+        assert_eq!(TextureRole::Diffuse.default_colorspace(), Colorspace::Srgb);
+        assert_eq!(
+            TextureRole::Roughness.default_colorspace(),
+            Colorspace::Linear
+        );
+        assert_eq!(TextureRole::Normal.default_colorspace(), Colorspace::Linear);
+    }
+}
@@ -0,0 +1,64 @@
+//! Embed a small image in a page or a report as a data URI
+
+use std::{io::Error, result::Result};
+
+use image::{DynamicImage, ImageOutputFormat};
+
+/// Characters of the 64 values of base64
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The image as a `data:image/png;base64,` URI, for thumbnails small enough to
+/// embed where files can't be referenced
+pub fn thumbnail_data_uri(image: &DynamicImage) -> Result<String, Error> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .map_err(|e| Error::other(format!("Could not encode thumbnail: {:?}", e)))?;
+
+    Ok(format!("data:image/png;base64,{}", base64(&png)))
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => encoded
+                    .push(BASE64_ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod thumbnail_data_uri_tests {
+    use super::*;
+
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn it_encodes_base64_with_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn it_embeds_the_image_as_a_png() {
+        let image = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(2, 2, Rgb([1u8, 2, 3])));
+        let uri = thumbnail_data_uri(&image).unwrap();
+
+        // The PNG signature, base64 encoded
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+}
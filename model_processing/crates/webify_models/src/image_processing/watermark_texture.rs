@@ -0,0 +1,226 @@
+//! Watermark a texture of a preview build
+
+use std::{io::Error, path::Path, result::Result};
+
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+
+use crate::image_processing::DecodeCache;
+use crate::options::{WatermarkPlacement, WatermarkSettings};
+
+/// Share of the width, and of the height at most, of the texture the watermark
+/// spans
+const MARK_SHARE: u32 = 4;
+
+/// Lay the rendered watermark over the 8-bit texture in place, scaled to a quarter
+/// of its width, at the placement and opacity of the settings. The alpha of the
+/// texture is left as it is, so transparent cutouts stay transparent. Returns false
+/// for 16-bit textures, which are left as they are.
+pub fn watermark_texture(
+    path: &Path,
+    mark: &RgbaImage,
+    settings: &WatermarkSettings,
+    cache: &mut DecodeCache,
+) -> Result<bool, Error> {
+    let img = cache.take_or_open(path).map_err(|e| {
+        Error::other(format!(
+            "Failed to open {:?} to watermark it: {:?}",
+            path, e
+        ))
+    })?;
+    match img {
+        DynamicImage::ImageLuma8(_)
+        | DynamicImage::ImageLumaA8(_)
+        | DynamicImage::ImageRgb8(_)
+        | DynamicImage::ImageRgba8(_) => (),
+        _ => {
+            cache.insert(path, img);
+            return Ok(false);
+        }
+    }
+
+    let mut pixels = img.to_rgba8();
+    let (width, height) = pixels.dimensions();
+    let mark = scale_mark(mark, width, height);
+    let (mark_width, mark_height) = mark.dimensions();
+    let positions: Vec<(i64, i64)> = match settings.placement {
+        WatermarkPlacement::Corner => {
+            let margin = width.min(height) / 32;
+            vec![(
+                i64::from(width) - i64::from(mark_width + margin),
+                i64::from(height) - i64::from(mark_height + margin),
+            )]
+        }
+        WatermarkPlacement::Tiled => {
+            let step_x = i64::from(mark_width + mark_width / 2);
+            let step_y = i64::from(mark_height * 3);
+            (0..)
+                .map(|row| row * step_y + i64::from(mark_height))
+                .take_while(|&y| y < i64::from(height))
+                .enumerate()
+                .flat_map(|(row, y)| {
+                    let shift = (row as i64 % 2) * step_x / 2;
+                    (0..)
+                        .map(move |column| (column * step_x - shift, y))
+                        .take_while(|&(x, _)| x < i64::from(width))
+                })
+                .collect()
+        }
+    };
+    for (left, top) in positions {
+        blend_mark(&mut pixels, &mark, left, top, settings.opacity);
+    }
+
+    let pixels = DynamicImage::ImageRgba8(pixels);
+    let watermarked = match img {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageLuma8(pixels.to_luma8()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageLumaA8(pixels.to_luma_alpha8()),
+        DynamicImage::ImageRgb8(_) => DynamicImage::ImageRgb8(pixels.to_rgb8()),
+        _ => pixels,
+    };
+    watermarked
+        .save(path)
+        .map_err(|e| Error::other(format!("Could not save watermarked {:?}: {:?}", path, e)))?;
+    cache.insert(path, watermarked);
+
+    Ok(true)
+}
+
+/// The mark resized to span a quarter of the width of the texture, or a quarter of
+/// its height when it's tall, keeping its aspect ratio. Marks grown larger keep
+/// their pixels sharp, like the pixel font of text.
+fn scale_mark(mark: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let (mark_width, mark_height) = (u64::from(mark.width()), u64::from(mark.height()));
+    let max_width = u64::from((width / MARK_SHARE).max(1));
+    let max_height = u64::from((height / MARK_SHARE).max(1));
+    let (scaled_width, scaled_height) = match max_width * mark_height / mark_width > max_height {
+        true => ((max_height * mark_width / mark_height).max(1), max_height),
+        false => (max_width, (max_width * mark_height / mark_width).max(1)),
+    };
+
+    let filter = match scaled_width > mark_width {
+        true => FilterType::Nearest,
+        false => FilterType::Triangle,
+    };
+    image::imageops::resize(mark, scaled_width as u32, scaled_height as u32, filter)
+}
+
+/// Blend the mark over the pixels with its top left corner at `left`, `top`, the
+/// parts outside of the texture cut off
+fn blend_mark(pixels: &mut RgbaImage, mark: &RgbaImage, left: i64, top: i64, opacity: f64) {
+    let (width, height) = pixels.dimensions();
+    for (x, y, mark_pixel) in mark.enumerate_pixels() {
+        let (px, py) = (left + i64::from(x), top + i64::from(y));
+        if px < 0 || py < 0 || px >= i64::from(width) || py >= i64::from(height) {
+            continue;
+        }
+        let alpha = f64::from(mark_pixel[3]) / 255.0 * opacity;
+        let pixel = pixels.get_pixel_mut(px as u32, py as u32);
+        for channel in 0..3 {
+            let blended =
+                f64::from(pixel[channel]) * (1.0 - alpha) + f64::from(mark_pixel[channel]) * alpha;
+            pixel[channel] = blended.round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod watermark_texture_tests {
+    use super::*;
+
+    use std::{fs, path::PathBuf};
+
+    use image::{ImageBuffer, Luma, Rgb, Rgba};
+
+    use crate::image_processing::{render_watermark, DEFAULT_DECODE_CACHE_BUDGET};
+    use crate::options::WatermarkMark;
+
+    fn watermark(
+        test_run_id: &str,
+        image: DynamicImage,
+        placement: WatermarkPlacement,
+    ) -> Result<(bool, DynamicImage), Error> {
+        let dir = PathBuf::from("tests")
+            .join("image_processing")
+            .join(test_run_id);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("storefront.png");
+        image.save(&path).map_err(Error::other)?;
+        let mark = render_watermark(&WatermarkMark::Text(String::from("PREVIEW")))?;
+        let settings = WatermarkSettings {
+            placement,
+            opacity: 1.0,
+        };
+
+        let watermarked = watermark_texture(
+            &path,
+            &mark,
+            &settings,
+            &mut DecodeCache::new(DEFAULT_DECODE_CACHE_BUDGET),
+        );
+        let saved = image::open(&path).map_err(Error::other);
+        fs::remove_dir_all(&dir)?;
+
+        Ok((watermarked?, saved?))
+    }
+
+    fn changed_pixels(before: &DynamicImage, after: &DynamicImage) -> Vec<(u32, u32)> {
+        let (before, after) = (before.to_rgba8(), after.to_rgba8());
+        before
+            .enumerate_pixels()
+            .filter(|(x, y, pixel)| after.get_pixel(*x, *y) != *pixel)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    #[test]
+    fn it_watermarks_the_corner() -> Result<(), Error> {
+        let texture =
+            DynamicImage::ImageRgba8(ImageBuffer::from_pixel(128, 128, Rgba([90, 60, 30, 128])));
+
+        let (watermarked, saved) = watermark(
+            "test_run_watermark_texture_corner",
+            texture.clone(),
+            WatermarkPlacement::Corner,
+        )?;
+
+        assert!(watermarked);
+        let changed = changed_pixels(&texture, &saved);
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, y)| x >= 64 && y >= 64));
+        assert!(saved.to_rgba8().pixels().all(|pixel| pixel[3] == 128));
+        Ok(())
+    }
+
+    #[test]
+    fn it_tiles_the_watermark_over_the_whole_texture() -> Result<(), Error> {
+        let texture = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(128, 128, Rgb([90, 60, 30])));
+
+        let (watermarked, saved) = watermark(
+            "test_run_watermark_texture_tiled",
+            texture.clone(),
+            WatermarkPlacement::Tiled,
+        )?;
+
+        assert!(watermarked);
+        assert!(saved.as_rgb8().is_some());
+        let changed = changed_pixels(&texture, &saved);
+        assert!(changed.iter().any(|&(x, y)| x < 32 && y < 32));
+        assert!(changed.iter().any(|&(x, y)| x >= 96 && y >= 96));
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_16_bit_textures_alone() -> Result<(), Error> {
+        let texture = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(64, 64, Luma([40000u16])));
+
+        let (watermarked, saved) = watermark(
+            "test_run_watermark_texture_16_bit",
+            texture.clone(),
+            WatermarkPlacement::Corner,
+        )?;
+
+        assert!(!watermarked);
+        assert_eq!(saved.as_luma16(), texture.as_luma16());
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Render the impostor billboard of a model
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::impostor::{
+    find_impostor, impostor_stem, read_sdf_visuals, render_atlas, visual_triangles, Triangle,
+};
+use crate::model::model_sdfs;
+use crate::options::RunOptions;
+use crate::provenance::SIDECAR_EXTENSION;
+use crate::trash::remove_file;
+
+/// Render the visuals of the model's first SDF into an impostor atlas, written to
+/// its textures directory as `<model>_impostor.png` with the layout in
+/// `<model>_impostor.json`. The atlas then goes through the texture pipeline like
+/// any texture. Returns the atlas written, `None` when the model already has an
+/// impostor with the same settings or has nothing to render. Meshes other than
+/// DAE, and the ones that can't be found, are left out of the impostor.
+pub fn generate_impostor(
+    model_dir: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    options: &RunOptions,
+) -> Result<Option<PathBuf>, Error> {
+    // Metadata that doesn't parse is rewritten along with the atlas
+    if let Some((_, metadata)) = find_impostor(model_dir).ok().flatten() {
+        if metadata.views == options.impostor.views
+            && metadata.frame_size == options.impostor.frame_size
+        {
+            return Ok(None);
+        }
+    }
+
+    let sdf = match model_sdfs(model_dir)?.into_iter().next() {
+        Some(sdf) => sdf,
+        None => return Ok(None),
+    };
+    let mut meshes: HashMap<PathBuf, Vec<Triangle>> = HashMap::new();
+    let mut triangles = Vec::new();
+    for visual in read_sdf_visuals(&sdf)? {
+        triangles.extend(visual_triangles(&visual, &sdf, models, &mut meshes)?);
+    }
+
+    let (atlas, metadata) = match render_atlas(&triangles, &options.impostor) {
+        Some(rendered) => rendered,
+        None => return Ok(None),
+    };
+
+    let textures_dir = model_dir.join("materials").join("textures");
+    let stem = impostor_stem(model_dir);
+    if textures_dir.is_dir() {
+        // The previous atlas may have been converted to another format
+        for entry in fs::read_dir(&textures_dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let file = name.trim_end_matches(&format!(".{}", SIDECAR_EXTENSION));
+            if Path::new(file).file_stem().and_then(|s| s.to_str()) == Some(stem.as_str()) {
+                remove_file(&path, &options.removal)?;
+            }
+        }
+    }
+
+    fs::create_dir_all(&textures_dir)?;
+    let atlas_path = textures_dir.join(format!("{}.png", stem));
+    atlas
+        .save(&atlas_path)
+        .map_err(|e| Error::other(format!("Could not save {:?}: {:?}", atlas_path, e)))?;
+    fs::write(
+        textures_dir.join(format!("{}.json", stem)),
+        serde_json::to_string_pretty(&metadata)?,
+    )?;
+
+    Ok(Some(atlas_path))
+}
+
+#[cfg(test)]
+mod generate_impostor_tests {
+    use super::*;
+
+    use crate::dependencies::index_models;
+    use crate::options::ImpostorSettings;
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_renders_the_model_once_per_settings() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("impostor")
+            .join("test_run_generate_impostor");
+        let model_dir = dir.join("crate");
+        copy_tree(
+            &Path::new("tests").join("impostor").join("crate"),
+            &model_dir,
+        )?;
+        let models = index_models(&dir)?;
+        let textures_dir = model_dir.join("materials").join("textures");
+
+        let atlas = generate_impostor(&model_dir, &models, &RunOptions::default())?;
+
+        assert_eq!(atlas, Some(textures_dir.join("crate_impostor.png")));
+        assert_eq!(
+            image::image_dimensions(textures_dir.join("crate_impostor.png")).unwrap(),
+            (384, 384)
+        );
+        let (_, metadata) = find_impostor(&model_dir)?.unwrap();
+        assert_eq!((metadata.views, metadata.columns, metadata.rows), (8, 3, 3));
+        assert!(generate_impostor(&model_dir, &models, &RunOptions::default())?.is_none());
+
+        // A previous atlas converted to another format is replaced
+        fs::rename(
+            textures_dir.join("crate_impostor.png"),
+            textures_dir.join("crate_impostor.webp"),
+        )?;
+        fs::write(textures_dir.join("crate_impostor.webp.webify.json"), "{}")?;
+        let options = RunOptions {
+            impostor: ImpostorSettings {
+                views: 4,
+                frame_size: 64,
+            },
+            ..RunOptions::default()
+        };
+        assert!(generate_impostor(&model_dir, &models, &options)?.is_some());
+        assert!(!textures_dir.join("crate_impostor.webp").exists());
+        assert!(!textures_dir
+            .join("crate_impostor.webp.webify.json")
+            .exists());
+        assert!(textures_dir.join("wood.png").exists());
+        assert_eq!(
+            image::image_dimensions(textures_dir.join("crate_impostor.png")).unwrap(),
+            (128, 128)
+        );
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_models_without_visuals() -> Result<(), Error> {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+
+        assert!(generate_impostor(&model_dir, &BTreeMap::new(), &RunOptions::default())?.is_none());
+        assert!(!model_dir.join("materials").exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+//! Layout of an impostor atlas, for the viewer drawing it
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How an impostor atlas is laid out and where its billboard stands. View `i` is
+/// the model seen from `360 * i / views` degrees around +Z, starting from +X and
+/// looking at `center`, and sits at column `i % columns`, row `i / columns` of
+/// the atlas.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ImpostorMetadata {
+    pub views: u8,
+    /// Side of each view in the atlas, in pixels
+    pub frame_size: u32,
+    pub columns: u32,
+    pub rows: u32,
+    /// Side of the square billboard, in meters
+    pub size: f64,
+    /// Center of the billboard in the frame of the model, in meters
+    pub center: [f64; 3],
+}
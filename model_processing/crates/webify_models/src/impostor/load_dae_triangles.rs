@@ -0,0 +1,474 @@
+//! Load the triangles of a DAE mesh, colored by their materials
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use image::GenericImageView;
+
+use crate::image_processing::decode_texture;
+use crate::impostor::{Transform, Triangle};
+use crate::options::HdrSettings;
+use crate::xml::XmlElement;
+
+/// Color of the triangles whose material can't be worked out
+pub const DEFAULT_COLOR: [f32; 3] = [0.7, 0.7, 0.7];
+
+/// Deepest `<instance_node>` chain followed, so nodes instancing themselves end
+const MAX_NODE_DEPTH: usize = 32;
+
+/// Vertex positions of a `<mesh>`, by source id
+type Sources = HashMap<String, Vec<[f64; 3]>>;
+
+/// Triangles of the DAE mesh, in meters with Z up, as the visual scene places
+/// them. Polygons, fans and strips are split into triangles, lines are left out.
+/// A textured material takes the average color of its texture, when it decodes.
+pub fn load_dae_triangles(mesh: &Path) -> Result<Vec<Triangle>, Error> {
+    let contents = fs::read_to_string(mesh)?;
+    let root = XmlElement::parse(&contents)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {:?}", mesh, e)))?;
+
+    let mut ids = HashMap::new();
+    index_ids(&root, &mut ids);
+    let mut loader = DaeLoader {
+        dir: mesh.parent().unwrap_or_else(|| Path::new("")).to_path_buf(),
+        ids,
+        texture_colors: HashMap::new(),
+        triangles: Vec::new(),
+    };
+
+    let meter = root
+        .find(&["asset", "unit"])
+        .and_then(|unit| unit.attribute("meter"))
+        .and_then(|meter| meter.parse().ok())
+        .unwrap_or(1.0);
+    let up_axis = match root.find(&["asset", "up_axis"]).map(|a| a.text.as_str()) {
+        Some("X_UP") => Transform::rotation([0.0, 1.0, 0.0], -90.0),
+        Some("Z_UP") => Transform::identity(),
+        _ => Transform::rotation([1.0, 0.0, 0.0], 90.0),
+    };
+    let root_transform = up_axis * Transform::scale(meter, meter, meter);
+
+    let scene = root
+        .find(&["scene", "instance_visual_scene"])
+        .and_then(|instance| loader.lookup(instance.attribute("url")))
+        .or_else(|| root.descendants_named("visual_scene").into_iter().next());
+    match scene {
+        Some(scene) => {
+            for node in scene.children_named("node") {
+                loader.load_node(node, root_transform, 0);
+            }
+        }
+        None => {
+            for geometry in root.descendants_named("geometry") {
+                loader.load_geometry(geometry, root_transform, &HashMap::new());
+            }
+        }
+    }
+
+    Ok(loader.triangles)
+}
+
+/// Index every element with an `id`
+fn index_ids<'a>(element: &'a XmlElement, ids: &mut HashMap<String, &'a XmlElement>) {
+    if let Some(id) = element.attribute("id") {
+        ids.insert(id.to_string(), element);
+    }
+    for child in &element.children {
+        index_ids(child, ids);
+    }
+}
+
+struct DaeLoader<'a> {
+    dir: PathBuf,
+    ids: HashMap<String, &'a XmlElement>,
+    /// Average color of the textures decoded so far, `None` when they don't decode
+    texture_colors: HashMap<PathBuf, Option<[f32; 3]>>,
+    triangles: Vec<Triangle>,
+}
+
+impl<'a> DaeLoader<'a> {
+    /// Element a `#id` URL points at
+    fn lookup(&self, url: Option<&str>) -> Option<&'a XmlElement> {
+        self.ids.get(url?.trim_start_matches('#')).copied()
+    }
+
+    /// Load the geometries of the node and its children, with the transforms of
+    /// the node applied in document order
+    fn load_node(&mut self, node: &'a XmlElement, parent: Transform, depth: usize) {
+        if depth > MAX_NODE_DEPTH {
+            return;
+        }
+
+        let mut transform = parent;
+        for child in &node.children {
+            let values: Vec<f64> = child.numbers();
+            transform = transform
+                * match (child.name.as_str(), values.as_slice()) {
+                    ("matrix", _) => match Transform::from_row_major(&values) {
+                        Some(matrix) => matrix,
+                        None => continue,
+                    },
+                    ("translate", [x, y, z]) => Transform::translation(*x, *y, *z),
+                    ("rotate", [x, y, z, angle]) => Transform::rotation([*x, *y, *z], *angle),
+                    ("scale", [x, y, z]) => Transform::scale(*x, *y, *z),
+                    _ => continue,
+                };
+        }
+
+        for child in &node.children {
+            match child.name.as_str() {
+                "instance_geometry" => {
+                    if let Some(geometry) = self.lookup(child.attribute("url")) {
+                        let materials = bound_materials(child);
+                        self.load_geometry(geometry, transform, &materials);
+                    }
+                }
+                "instance_node" => {
+                    if let Some(instanced) = self.lookup(child.attribute("url")) {
+                        self.load_node(instanced, transform, depth + 1);
+                    }
+                }
+                "node" => self.load_node(child, transform, depth + 1),
+                _ => (),
+            }
+        }
+    }
+
+    /// Load the triangles of a `<geometry>`, its material symbols bound to the
+    /// `<material>` ids of `materials`
+    fn load_geometry(
+        &mut self,
+        geometry: &'a XmlElement,
+        transform: Transform,
+        materials: &HashMap<String, String>,
+    ) {
+        let mesh = match geometry.child("mesh") {
+            Some(mesh) => mesh,
+            None => return,
+        };
+        let sources = mesh_positions(mesh);
+
+        for primitive in &mesh.children {
+            let polygons = match primitive_polygons(primitive, &sources) {
+                Some(polygons) => polygons,
+                None => continue,
+            };
+            let material = primitive
+                .attribute("material")
+                .map(|symbol| materials.get(symbol).map(String::as_str).unwrap_or(symbol));
+            let color = self.material_color(material);
+
+            for polygon in polygons {
+                for vertices in polygon {
+                    self.triangles.push(Triangle {
+                        vertices: [
+                            transform.apply(vertices[0]),
+                            transform.apply(vertices[1]),
+                            transform.apply(vertices[2]),
+                        ],
+                        color,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Diffuse color of the `<material>`, or the average color of its texture
+    fn material_color(&mut self, material: Option<&str>) -> [f32; 3] {
+        let diffuse = material
+            .and_then(|id| self.ids.get(id).copied())
+            .and_then(|material| material.child("instance_effect"))
+            .and_then(|instance| self.lookup(instance.attribute("url")))
+            .and_then(|effect| {
+                effect
+                    .descendants_named("diffuse")
+                    .into_iter()
+                    .next()
+                    .map(|diffuse| (effect, diffuse))
+            });
+        let (effect, diffuse) = match diffuse {
+            Some(found) => found,
+            None => return DEFAULT_COLOR,
+        };
+
+        if let Some(color) = diffuse.child("color") {
+            return match color.numbers::<f32>().as_slice() {
+                [r, g, b, ..] => [*r, *g, *b],
+                _ => DEFAULT_COLOR,
+            };
+        }
+        let texture = diffuse
+            .child("texture")
+            .and_then(|texture| texture.attribute("texture"))
+            .and_then(|sampler| self.texture_path(effect, sampler));
+        match texture {
+            Some(texture) => self.texture_color(&texture).unwrap_or(DEFAULT_COLOR),
+            None => DEFAULT_COLOR,
+        }
+    }
+
+    /// File of the image a texture attribute names: a sampler of the effect, whose
+    /// surface points at the image, or the image itself
+    fn texture_path(&self, effect: &XmlElement, sampler: &str) -> Option<PathBuf> {
+        let param = |sid: &str| {
+            effect
+                .descendants_named("newparam")
+                .into_iter()
+                .find(|param| param.attribute("sid") == Some(sid))
+        };
+        let image_id = match param(sampler)
+            .and_then(|param| param.find(&["sampler2D", "source"]))
+            .and_then(|source| param(&source.text))
+        {
+            Some(surface) => surface.find(&["surface", "init_from"])?.text.clone(),
+            None => match param(sampler).and_then(|p| p.find(&["sampler2D", "instance_image"])) {
+                Some(instance) => instance
+                    .attribute("url")?
+                    .trim_start_matches('#')
+                    .to_string(),
+                None => sampler.to_string(),
+            },
+        };
+
+        let init_from = self.ids.get(image_id.as_str())?.child("init_from")?;
+        let reference = match init_from.child("ref") {
+            Some(reference) => &reference.text,
+            None => &init_from.text,
+        };
+        Some(self.dir.join(reference.trim_start_matches("file://")))
+    }
+
+    /// Average color of the texture, weighted by its alpha, cached per file
+    fn texture_color(&mut self, texture: &Path) -> Option<[f32; 3]> {
+        if let Some(color) = self.texture_colors.get(texture) {
+            return *color;
+        }
+
+        let color = decode_texture(texture, &HdrSettings::default())
+            .ok()
+            .and_then(|image| {
+                // The average doesn't need every pixel of a large texture
+                let image = match image.width() > 64 || image.height() > 64 {
+                    true => image.thumbnail(64, 64),
+                    false => image,
+                };
+                let mut sums = [0.0f64; 4];
+                for (_, _, pixel) in image.pixels() {
+                    let alpha = f64::from(pixel[3]) / 255.0;
+                    for (sum, channel) in sums.iter_mut().zip(pixel.0.iter()).take(3) {
+                        *sum += f64::from(*channel) / 255.0 * alpha;
+                    }
+                    sums[3] += alpha;
+                }
+                match sums[3] > 0.0 {
+                    true => Some([
+                        (sums[0] / sums[3]) as f32,
+                        (sums[1] / sums[3]) as f32,
+                        (sums[2] / sums[3]) as f32,
+                    ]),
+                    false => None,
+                }
+            });
+
+        self.texture_colors.insert(texture.to_path_buf(), color);
+        color
+    }
+}
+
+/// Material ids the `<instance_geometry>` binds its material symbols to
+fn bound_materials(instance: &XmlElement) -> HashMap<String, String> {
+    instance
+        .descendants_named("instance_material")
+        .into_iter()
+        .filter_map(|binding| {
+            Some((
+                binding.attribute("symbol")?.to_string(),
+                binding
+                    .attribute("target")?
+                    .trim_start_matches('#')
+                    .to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Positions of the `<source>`s of the mesh, also indexed by the id of the
+/// `<vertices>` pointing at them
+fn mesh_positions(mesh: &XmlElement) -> Sources {
+    let mut sources = Sources::new();
+    for source in mesh.children_named("source") {
+        let values: Vec<f64> = match source.child("float_array") {
+            Some(array) => array.numbers(),
+            None => continue,
+        };
+        let stride = source
+            .find(&["technique_common", "accessor"])
+            .and_then(|accessor| accessor.attribute("stride"))
+            .and_then(|stride| stride.parse().ok())
+            .unwrap_or(3usize)
+            .max(3);
+        let positions = values
+            .chunks_exact(stride)
+            .map(|v| [v[0], v[1], v[2]])
+            .collect();
+        sources.insert(
+            source.attribute("id").unwrap_or_default().to_string(),
+            positions,
+        );
+    }
+
+    for vertices in mesh.children_named("vertices") {
+        let position = vertices
+            .children_named("input")
+            .find(|input| input.attribute("semantic") == Some("POSITION"))
+            .and_then(|input| input.attribute("source"))
+            .and_then(|source| sources.get(source.trim_start_matches('#')).cloned());
+        if let (Some(id), Some(position)) = (vertices.attribute("id"), position) {
+            sources.insert(id.to_string(), position);
+        }
+    }
+
+    sources
+}
+
+/// Triangles of a primitive element, grouped by polygon, `None` for elements that
+/// aren't made of triangles
+fn primitive_polygons(
+    primitive: &XmlElement,
+    sources: &Sources,
+) -> Option<Vec<Vec<[[f64; 3]; 3]>>> {
+    let kind = primitive.name.as_str();
+    if !["triangles", "polylist", "polygons", "trifans", "tristrips"].contains(&kind) {
+        return None;
+    }
+
+    let inputs: Vec<&XmlElement> = primitive.children_named("input").collect();
+    let stride = inputs
+        .iter()
+        .filter_map(|input| input.attribute("offset")?.parse::<usize>().ok())
+        .max()
+        .map_or(1, |offset| offset + 1);
+    let vertex = inputs
+        .iter()
+        .find(|input| input.attribute("semantic") == Some("VERTEX"))?;
+    let offset: usize = vertex
+        .attribute("offset")
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0);
+    let positions = sources.get(vertex.attribute("source")?.trim_start_matches('#'))?;
+
+    // Positions of the vertices of each `<p>`
+    let lists: Vec<Vec<[f64; 3]>> = primitive
+        .children_named("p")
+        .map(|p| {
+            p.numbers::<usize>()
+                .chunks_exact(stride)
+                .filter_map(|indices| positions.get(indices[offset]).copied())
+                .collect()
+        })
+        .collect();
+
+    let polygons = match kind {
+        "triangles" => lists
+            .iter()
+            .flat_map(|list| list.chunks_exact(3))
+            .map(|corners| vec![[corners[0], corners[1], corners[2]]])
+            .collect(),
+        "polylist" => {
+            let list = lists.into_iter().next().unwrap_or_default();
+            let mut start = 0;
+            let mut polygons = Vec::new();
+            for count in primitive
+                .child("vcount")
+                .map(|vcount| vcount.numbers::<usize>())
+                .unwrap_or_default()
+            {
+                if start + count > list.len() {
+                    break;
+                }
+                polygons.push(fan(&list[start..start + count]));
+                start += count;
+            }
+            polygons
+        }
+        "tristrips" => lists.iter().map(|list| strip(list)).collect(),
+        _ => lists.iter().map(|list| fan(list)).collect(),
+    };
+
+    Some(polygons)
+}
+
+/// Triangles of a fan, or of a convex polygon
+fn fan(vertices: &[[f64; 3]]) -> Vec<[[f64; 3]; 3]> {
+    (2..vertices.len())
+        .map(|i| [vertices[0], vertices[i - 1], vertices[i]])
+        .collect()
+}
+
+/// Triangles of a strip
+fn strip(vertices: &[[f64; 3]]) -> Vec<[[f64; 3]; 3]> {
+    (2..vertices.len())
+        .map(|i| [vertices[i - 2], vertices[i - 1], vertices[i]])
+        .collect()
+}
+
+#[cfg(test)]
+mod load_dae_triangles_tests {
+    use super::*;
+
+    fn bounds(triangles: &[Triangle]) -> ([f64; 3], [f64; 3]) {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for vertex in triangles.iter().flat_map(|t| t.vertices.iter()) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+        }
+        (min, max)
+    }
+
+    #[test]
+    fn it_loads_the_scene_triangles() -> Result<(), Error> {
+        let mesh = Path::new("tests")
+            .join("impostor")
+            .join("crate")
+            .join("meshes")
+            .join("crate.dae");
+
+        let triangles = load_dae_triangles(&mesh)?;
+
+        // A cube of 12 triangles, half of it as quads, and a 3 triangle strip
+        assert_eq!(triangles.len(), 15);
+        let (min, max) = bounds(&triangles[..12]);
+        // Modeled in centimeters with Y up, lifted by 50 along Y
+        for axis in 0..3 {
+            let expected = if axis == 2 { [0.0, 1.0] } else { [-0.5, 0.5] };
+            assert!((min[axis] - expected[0]).abs() < 1e-9, "{:?}", min);
+            assert!((max[axis] - expected[1]).abs() < 1e-9, "{:?}", max);
+        }
+
+        // Average of the texture's two halves
+        let wood = triangles[0].color;
+        assert!((wood[0] - 0.6).abs() < 0.01, "{:?}", wood);
+        assert!((wood[1] - 0.4).abs() < 0.01, "{:?}", wood);
+        assert!((wood[2] - 0.2).abs() < 0.01, "{:?}", wood);
+        assert_eq!(triangles[6].color, [0.0, 0.0, 1.0]);
+        assert_eq!(triangles[14].color, DEFAULT_COLOR);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_unreadable_meshes() {
+        let dir = Path::new("tests").join("impostor").join("crate");
+
+        assert!(load_dae_triangles(&dir.join("meshes").join("missing.dae")).is_err());
+        assert!(load_dae_triangles(&dir.join("materials")).is_err());
+    }
+}
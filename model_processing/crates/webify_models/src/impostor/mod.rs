@@ -0,0 +1,27 @@
+//! Billboard impostors: each model rendered from several angles around its
+//! vertical axis into a small atlas, which the viewer draws in place of distant
+//! models
+
+mod find_impostor;
+mod generate_impostor;
+mod impostor_metadata;
+mod load_dae_triangles;
+mod read_sdf_visuals;
+mod render_atlas;
+mod sdf_visual;
+mod shape_triangles;
+mod transform;
+mod triangle;
+mod visual_triangles;
+
+pub use self::find_impostor::{find_impostor, impostor_stem};
+pub use self::generate_impostor::generate_impostor;
+pub use self::impostor_metadata::ImpostorMetadata;
+pub use self::load_dae_triangles::{load_dae_triangles, DEFAULT_COLOR};
+pub use self::read_sdf_visuals::{read_model_shapes, read_sdf_visuals, sdf_pose};
+pub use self::render_atlas::render_atlas;
+pub use self::sdf_visual::{SdfVisual, ShapeKind, VisualGeometry};
+pub use self::shape_triangles::shape_triangles;
+pub use self::transform::Transform;
+pub use self::triangle::Triangle;
+pub use self::visual_triangles::visual_triangles;
@@ -0,0 +1,200 @@
+//! Read the visuals or collisions of the models described by an SDF
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::impostor::{SdfVisual, ShapeKind, Transform, VisualGeometry};
+use crate::xml::XmlElement;
+
+/// Visuals of every link of the SDF's model and its nested models, posed in the
+/// frame of the model. Poses are taken relative to their parent element, planes
+/// are left out since they're grounds rather than part of the model, and so are
+/// `<include>`d models, which have visuals of their own.
+pub fn read_sdf_visuals(sdf: &Path) -> Result<Vec<SdfVisual>, Error> {
+    let contents = fs::read_to_string(sdf)?;
+    let root = XmlElement::parse(&contents)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {:?}", sdf, e)))?;
+
+    let mut visuals = Vec::new();
+    for model in root.children_named("model") {
+        read_model_shapes(
+            model,
+            Transform::identity(),
+            ShapeKind::Visual,
+            &mut visuals,
+        );
+    }
+    visuals.retain(|visual| !matches!(visual.geometry, VisualGeometry::Plane { .. }));
+
+    Ok(visuals)
+}
+
+/// Read the shapes of the kind of every link of the model and its nested models,
+/// posed by `frame`. Shapes that can't be triangulated are left out.
+pub fn read_model_shapes(
+    model: &XmlElement,
+    frame: Transform,
+    kind: ShapeKind,
+    shapes: &mut Vec<SdfVisual>,
+) {
+    for link in model.children_named("link") {
+        let link_frame = frame * sdf_pose(link);
+        for visual in link.children_named(kind.element()) {
+            if let Some(geometry) = visual.child("geometry").and_then(geometry) {
+                shapes.push(SdfVisual {
+                    pose: link_frame * sdf_pose(visual),
+                    geometry,
+                    color: visual
+                        .find(&["material", "diffuse"])
+                        .map(|diffuse| diffuse.numbers::<f32>())
+                        .filter(|rgba| rgba.len() >= 3)
+                        .map(|rgba| [rgba[0], rgba[1], rgba[2]]),
+                });
+            }
+        }
+    }
+    for nested in model.children_named("model") {
+        read_model_shapes(nested, frame * sdf_pose(nested), kind, shapes);
+    }
+}
+
+/// Transform of the element's `<pose>`, relative to its parent, identity without
+/// one
+pub fn sdf_pose(element: &XmlElement) -> Transform {
+    match element.child("pose") {
+        Some(pose) => {
+            let mut values: Vec<f64> = pose.numbers();
+            if pose.attribute("degrees") == Some("true") {
+                for angle in values.iter_mut().skip(3) {
+                    *angle = angle.to_radians();
+                }
+            }
+            Transform::from_pose(&values)
+        }
+        None => Transform::identity(),
+    }
+}
+
+/// Shape of a `<geometry>`, `None` for the shapes that aren't rendered
+fn geometry(geometry: &XmlElement) -> Option<VisualGeometry> {
+    let number = |element: &XmlElement, name: &str| {
+        element
+            .child(name)
+            .and_then(|child| child.numbers::<f64>().first().copied())
+    };
+
+    if let Some(mesh) = geometry.child("mesh") {
+        let uri = mesh.child("uri")?.text.clone();
+        let scale = match mesh.child("scale").map(|s| s.numbers::<f64>()) {
+            Some(scale) if scale.len() == 3 => [scale[0], scale[1], scale[2]],
+            _ => [1.0; 3],
+        };
+        Some(VisualGeometry::Mesh { uri, scale })
+    } else if let Some(shape) = geometry.child("box") {
+        match shape.child("size")?.numbers::<f64>().as_slice() {
+            [x, y, z] => Some(VisualGeometry::Box { size: [*x, *y, *z] }),
+            _ => None,
+        }
+    } else if let Some(shape) = geometry.child("cylinder") {
+        Some(VisualGeometry::Cylinder {
+            radius: number(shape, "radius")?,
+            length: number(shape, "length")?,
+        })
+    } else if let Some(shape) = geometry.child("sphere") {
+        Some(VisualGeometry::Sphere {
+            radius: number(shape, "radius")?,
+        })
+    } else if let Some(shape) = geometry.child("plane") {
+        let normal = match shape.child("normal").map(|n| n.numbers::<f64>()) {
+            Some(normal) if normal.len() == 3 => [normal[0], normal[1], normal[2]],
+            _ => [0.0, 0.0, 1.0],
+        };
+        // SDF's default size
+        let size = match shape.child("size").map(|s| s.numbers::<f64>()) {
+            Some(size) if size.len() == 2 => [size[0], size[1]],
+            _ => [1.0; 2],
+        };
+        Some(VisualGeometry::Plane { normal, size })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod read_sdf_visuals_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_posed_visuals() -> Result<(), Error> {
+        let sdf = Path::new("tests")
+            .join("impostor")
+            .join("crate")
+            .join("model.sdf");
+
+        let visuals = read_sdf_visuals(&sdf)?;
+
+        assert_eq!(visuals.len(), 3);
+        assert_eq!(
+            visuals[0].geometry,
+            VisualGeometry::Mesh {
+                uri: String::from("model://crate/meshes/crate.dae"),
+                scale: [1.0, 1.0, 2.0]
+            }
+        );
+        assert_eq!(visuals[0].color, None);
+        // The link is lifted by 1, the visual by 0.5 more
+        let origin = visuals[0].pose.apply([0.0, 0.0, 0.0]);
+        assert!((origin[2] - 1.5).abs() < 1e-9);
+
+        assert_eq!(
+            visuals[1].geometry,
+            VisualGeometry::Box {
+                size: [0.2, 0.2, 1.0]
+            }
+        );
+        assert_eq!(visuals[1].color, Some([1.0, 0.0, 0.0]));
+
+        // The nested model's lid is posed by the nested model then its link
+        assert_eq!(visuals[2].geometry, VisualGeometry::Sphere { radius: 0.1 });
+        let origin = visuals[2].pose.apply([0.0, 0.0, 0.0]);
+        assert!((origin[0] - 2.0).abs() < 1e-9);
+        assert!((origin[2] - 3.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_collisions_planes_included() {
+        let contents = r#"<sdf version="1.6">
+  <model name="room">
+    <link name="floor">
+      <pose>1 0 0 0 0 0</pose>
+      <collision name="ground"><geometry><plane><normal>0 0 1</normal><size>10 8</size></plane></geometry></collision>
+      <collision name="step"><pose>0 0 0.1 0 0 0</pose><geometry><box><size>1 1 0.2</size></box></geometry></collision>
+      <visual name="carpet"><geometry><box><size>2 2 0.01</size></box></geometry></visual>
+    </link>
+  </model>
+</sdf>"#;
+        let root = XmlElement::parse(contents).unwrap();
+        let model = root.child("model").unwrap();
+
+        let mut collisions = Vec::new();
+        read_model_shapes(
+            model,
+            Transform::identity(),
+            ShapeKind::Collision,
+            &mut collisions,
+        );
+
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(
+            collisions[0].geometry,
+            VisualGeometry::Plane {
+                normal: [0.0, 0.0, 1.0],
+                size: [10.0, 8.0]
+            }
+        );
+        let origin = collisions[1].pose.apply([0.0, 0.0, 0.0]);
+        assert!((origin[0] - 1.0).abs() < 1e-9);
+        assert!((origin[2] - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,281 @@
+//! Render the views of an impostor into an atlas
+
+use std::f64::consts::PI;
+
+use image::{Rgba, RgbaImage};
+
+use crate::impostor::{ImpostorMetadata, Triangle};
+use crate::options::ImpostorSettings;
+
+/// Light received by faces turned away from the sun
+const AMBIENT: f32 = 0.35;
+
+/// Direction the light comes from, the same for every view so the views match
+const SUN: [f64; 3] = [0.36, 0.48, 0.8];
+
+/// Samples per pixel along each axis, for antialiased edges
+const SUPERSAMPLING: u32 = 2;
+
+/// Margin around the model in its views, so edges aren't cut off
+const MARGIN: f64 = 1.02;
+
+/// Render the triangles from `settings.views` angles around the vertical axis, in
+/// orthographic projection, into a grid of square views with a transparent
+/// background. Every view has the same scale, framing the model from any angle.
+/// `None` when there's nothing to see.
+pub fn render_atlas(
+    triangles: &[Triangle],
+    settings: &ImpostorSettings,
+) -> Option<(RgbaImage, ImpostorMetadata)> {
+    let (min, max) = bounds(triangles)?;
+    let center = [
+        (min[0] + max[0]) / 2.0,
+        (min[1] + max[1]) / 2.0,
+        (min[2] + max[2]) / 2.0,
+    ];
+    let diagonal = (max[0] - min[0]).hypot(max[1] - min[1]);
+    let size = diagonal.max(max[2] - min[2]) * MARGIN;
+    if size <= 0.0 {
+        return None;
+    }
+
+    let views = u32::from(settings.views.max(1));
+    let columns = (f64::from(views).sqrt().ceil()) as u32;
+    let rows = views.div_ceil(columns);
+    let frame_size = settings.frame_size;
+    let mut atlas = RgbaImage::new(columns * frame_size, rows * frame_size);
+
+    for view in 0..views {
+        let azimuth = 2.0 * PI * f64::from(view) / f64::from(views);
+        let frame = render_view(triangles, center, size, azimuth, frame_size);
+        let (x, y) = ((view % columns) * frame_size, (view / columns) * frame_size);
+        for (px, py, pixel) in frame.enumerate_pixels() {
+            atlas.put_pixel(x + px, y + py, *pixel);
+        }
+    }
+
+    let metadata = ImpostorMetadata {
+        views: views as u8,
+        frame_size,
+        columns,
+        rows,
+        size,
+        center,
+    };
+    Some((atlas, metadata))
+}
+
+/// Smallest and largest coordinates of the vertices, `None` without triangles
+fn bounds(triangles: &[Triangle]) -> Option<([f64; 3], [f64; 3])> {
+    let mut vertices = triangles.iter().flat_map(|t| t.vertices.iter());
+    let first = *vertices.next()?;
+    let (mut min, mut max) = (first, first);
+    for vertex in vertices {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(vertex[axis]);
+            max[axis] = max[axis].max(vertex[axis]);
+        }
+    }
+    Some((min, max))
+}
+
+/// Render the model seen from `azimuth` radians around +Z, at a frame of
+/// `frame_size` pixels covering `size` meters around `center`
+fn render_view(
+    triangles: &[Triangle],
+    center: [f64; 3],
+    size: f64,
+    azimuth: f64,
+    frame_size: u32,
+) -> RgbaImage {
+    let resolution = frame_size * SUPERSAMPLING;
+    let side = resolution as usize;
+    let mut depths = vec![f64::MIN; side * side];
+    let mut colors: Vec<Option<[f32; 3]>> = vec![None; side * side];
+
+    // The camera stands along `toward`, looking back at the center
+    let (sin, cos) = azimuth.sin_cos();
+    let toward = [cos, sin, 0.0];
+    let right = [-sin, cos, 0.0];
+    let scale = f64::from(resolution) / size;
+    let project = |vertex: &[f64; 3]| {
+        let offset = [
+            vertex[0] - center[0],
+            vertex[1] - center[1],
+            vertex[2] - center[2],
+        ];
+        [
+            (dot(offset, right) * scale) + f64::from(resolution) / 2.0,
+            f64::from(resolution) / 2.0 - offset[2] * scale,
+            dot(offset, toward),
+        ]
+    };
+
+    for triangle in triangles {
+        let [a, b, c] = [
+            project(&triangle.vertices[0]),
+            project(&triangle.vertices[1]),
+            project(&triangle.vertices[2]),
+        ];
+        let area = edge(a, b, c);
+        if area.abs() < f64::EPSILON {
+            continue;
+        }
+        let color = shade(triangle);
+
+        let x_start = a[0].min(b[0]).min(c[0]).floor().max(0.0) as usize;
+        let x_end = (a[0].max(b[0]).max(c[0]).ceil().max(0.0) as usize).min(side);
+        let y_start = a[1].min(b[1]).min(c[1]).floor().max(0.0) as usize;
+        let y_end = (a[1].max(b[1]).max(c[1]).ceil().max(0.0) as usize).min(side);
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let sample = [x as f64 + 0.5, y as f64 + 0.5, 0.0];
+                let weights = [
+                    edge(b, c, sample) / area,
+                    edge(c, a, sample) / area,
+                    edge(a, b, sample) / area,
+                ];
+                if weights.iter().any(|weight| *weight < 0.0) {
+                    continue;
+                }
+                let depth = weights[0] * a[2] + weights[1] * b[2] + weights[2] * c[2];
+                let index = y * side + x;
+                if depth > depths[index] {
+                    depths[index] = depth;
+                    colors[index] = Some(color);
+                }
+            }
+        }
+    }
+
+    // Average the samples of each pixel, the empty ones only lowering the alpha
+    RgbaImage::from_fn(frame_size, frame_size, |x, y| {
+        let mut sum = [0.0f32; 3];
+        let mut covered = 0;
+        for sy in 0..SUPERSAMPLING {
+            for sx in 0..SUPERSAMPLING {
+                let index =
+                    ((y * SUPERSAMPLING + sy) as usize) * side + (x * SUPERSAMPLING + sx) as usize;
+                if let Some(color) = colors[index] {
+                    for (total, channel) in sum.iter_mut().zip(color.iter()) {
+                        *total += channel;
+                    }
+                    covered += 1;
+                }
+            }
+        }
+        if covered == 0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let channel = |total: f32| (total / covered as f32 * 255.0).round().clamp(0.0, 255.0) as u8;
+        let alpha = covered * 255 / (SUPERSAMPLING * SUPERSAMPLING);
+        Rgba([
+            channel(sum[0]),
+            channel(sum[1]),
+            channel(sum[2]),
+            alpha as u8,
+        ])
+    })
+}
+
+/// Color of the triangle lit by the sun, on both of its sides
+fn shade(triangle: &Triangle) -> [f32; 3] {
+    let [a, b, c] = triangle.vertices;
+    let normal = cross(
+        [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+        [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+    );
+    let length = dot(normal, normal).sqrt();
+    let lambert = match length > 0.0 {
+        true => (dot(normal, SUN) / length).abs() as f32,
+        false => 0.0,
+    };
+    let light = AMBIENT + (1.0 - AMBIENT) * lambert;
+
+    [
+        triangle.color[0] * light,
+        triangle.color[1] * light,
+        triangle.color[2] * light,
+    ]
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p` on screen
+fn edge(a: [f64; 3], b: [f64; 3], p: [f64; 3]) -> f64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod render_atlas_tests {
+    use super::*;
+
+    /// A wall 2 wide along Y and 1 high, facing +X, red, and a green post behind
+    /// it at -X
+    fn scene() -> Vec<Triangle> {
+        let wall = |vertices| Triangle {
+            vertices,
+            color: [1.0, 0.0, 0.0],
+        };
+        let post = |vertices| Triangle {
+            vertices,
+            color: [0.0, 1.0, 0.0],
+        };
+        vec![
+            wall([[0.0, -1.0, 0.0], [0.0, 1.0, 0.0], [0.0, 1.0, 1.0]]),
+            wall([[0.0, -1.0, 0.0], [0.0, 1.0, 1.0], [0.0, -1.0, 1.0]]),
+            post([[-0.5, -0.1, 0.0], [-0.5, 0.1, 0.0], [-0.5, 0.1, 1.0]]),
+            post([[-0.5, -0.1, 0.0], [-0.5, 0.1, 1.0], [-0.5, -0.1, 1.0]]),
+        ]
+    }
+
+    #[test]
+    fn it_lays_out_the_views_in_a_grid() {
+        let settings = ImpostorSettings {
+            views: 5,
+            frame_size: 32,
+        };
+
+        let (atlas, metadata) = render_atlas(&scene(), &settings).unwrap();
+
+        assert_eq!((metadata.columns, metadata.rows), (3, 2));
+        assert_eq!(atlas.dimensions(), (96, 64));
+        assert_eq!(metadata.center, [-0.25, 0.0, 0.5]);
+        assert!((metadata.size - 0.5f64.hypot(2.0) * MARGIN).abs() < 1e-9);
+        // The last cell of the grid has no view
+        assert!(atlas
+            .enumerate_pixels()
+            .all(|(x, y, pixel)| x < 64 || y < 32 || pixel[3] == 0));
+        assert!(render_atlas(&[], &settings).is_none());
+    }
+
+    #[test]
+    fn it_hides_what_is_behind() {
+        let settings = ImpostorSettings {
+            views: 2,
+            frame_size: 32,
+        };
+
+        let (atlas, _) = render_atlas(&scene(), &settings).unwrap();
+
+        // From +X the wall hides the post, from -X the post stands in front
+        let front = atlas.get_pixel(16, 16);
+        assert_eq!(front[3], 255);
+        assert!(front[0] > 0 && front[1] == 0, "{:?}", front);
+        let back = atlas.get_pixel(48, 16);
+        assert!(back[1] > 0 && back[0] == 0, "{:?}", back);
+        // The corners of the frames are empty
+        assert_eq!(atlas.get_pixel(0, 0)[3], 0);
+        assert_eq!(atlas.get_pixel(63, 31)[3], 0);
+    }
+}
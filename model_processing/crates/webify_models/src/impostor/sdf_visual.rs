@@ -0,0 +1,56 @@
+//! Visuals declared in an SDF
+
+use crate::impostor::Transform;
+
+/// Shape of a visual
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisualGeometry {
+    /// A mesh file, referenced as written in the SDF
+    Mesh {
+        uri: String,
+        scale: [f64; 3],
+    },
+    Box {
+        size: [f64; 3],
+    },
+    /// Cylinder along Z, centered on the origin
+    Cylinder {
+        radius: f64,
+        length: f64,
+    },
+    Sphere {
+        radius: f64,
+    },
+    /// Plane through the origin, `size` along the two axes across its normal
+    Plane {
+        normal: [f64; 3],
+        size: [f64; 2],
+    },
+}
+
+/// Which of the shapes of a link are read: the ones drawn or the ones collided
+/// with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind {
+    Visual,
+    Collision,
+}
+
+impl ShapeKind {
+    /// Name of the SDF element declaring the shapes of this kind
+    pub fn element(self) -> &'static str {
+        match self {
+            ShapeKind::Visual => "visual",
+            ShapeKind::Collision => "collision",
+        }
+    }
+}
+
+/// A visual or collision of a model, placed in the frame of the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfVisual {
+    pub pose: Transform,
+    pub geometry: VisualGeometry,
+    /// Diffuse color of its `<material>`, from 0 to 1
+    pub color: Option<[f32; 3]>,
+}
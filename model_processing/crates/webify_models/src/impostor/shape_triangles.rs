@@ -0,0 +1,191 @@
+//! Triangulate the shapes SDF visuals are made of
+
+use std::f64::consts::PI;
+
+use crate::impostor::VisualGeometry;
+
+/// Segments around the cylinders and spheres
+const SEGMENTS: usize = 16;
+
+/// Triangles of a box, cylinder, sphere or plane, centered on the origin. Meshes
+/// have none, they're loaded from their file.
+pub fn shape_triangles(geometry: &VisualGeometry) -> Vec<[[f64; 3]; 3]> {
+    match geometry {
+        VisualGeometry::Mesh { .. } => Vec::new(),
+        VisualGeometry::Box { size } => {
+            let [x, y, z] = [size[0] / 2.0, size[1] / 2.0, size[2] / 2.0];
+            let corner = |i: usize| {
+                [
+                    if i & 1 == 0 { -x } else { x },
+                    if i & 2 == 0 { -y } else { y },
+                    if i & 4 == 0 { -z } else { z },
+                ]
+            };
+            // Corners of each face by their index bits, counterclockwise seen from
+            // outside
+            [
+                [0, 2, 3, 1],
+                [4, 5, 7, 6],
+                [0, 1, 5, 4],
+                [2, 6, 7, 3],
+                [0, 4, 6, 2],
+                [1, 3, 7, 5],
+            ]
+            .iter()
+            .flat_map(|face| {
+                vec![
+                    [corner(face[0]), corner(face[1]), corner(face[2])],
+                    [corner(face[0]), corner(face[2]), corner(face[3])],
+                ]
+            })
+            .collect()
+        }
+        VisualGeometry::Cylinder { radius, length } => {
+            let half = length / 2.0;
+            let rim = |i: usize, z: f64| {
+                let angle = 2.0 * PI * i as f64 / SEGMENTS as f64;
+                [radius * angle.cos(), radius * angle.sin(), z]
+            };
+            (0..SEGMENTS)
+                .flat_map(|i| {
+                    vec![
+                        [rim(i, -half), rim(i + 1, -half), rim(i + 1, half)],
+                        [rim(i, -half), rim(i + 1, half), rim(i, half)],
+                        [[0.0, 0.0, half], rim(i, half), rim(i + 1, half)],
+                        [[0.0, 0.0, -half], rim(i + 1, -half), rim(i, -half)],
+                    ]
+                })
+                .collect()
+        }
+        VisualGeometry::Sphere { radius } => {
+            let rings = SEGMENTS / 2;
+            let point = |ring: usize, segment: usize| {
+                let polar = PI * ring as f64 / rings as f64;
+                let azimuth = 2.0 * PI * segment as f64 / SEGMENTS as f64;
+                [
+                    radius * polar.sin() * azimuth.cos(),
+                    radius * polar.sin() * azimuth.sin(),
+                    radius * polar.cos(),
+                ]
+            };
+            (0..rings)
+                .flat_map(|ring| (0..SEGMENTS).map(move |segment| (ring, segment)))
+                .flat_map(|(ring, segment)| {
+                    vec![
+                        [
+                            point(ring, segment),
+                            point(ring + 1, segment),
+                            point(ring + 1, segment + 1),
+                        ],
+                        [
+                            point(ring, segment),
+                            point(ring + 1, segment + 1),
+                            point(ring, segment + 1),
+                        ],
+                    ]
+                })
+                .collect()
+        }
+        VisualGeometry::Plane { normal, size } => {
+            let length = (normal[0].powi(2) + normal[1].powi(2) + normal[2].powi(2)).sqrt();
+            if length == 0.0 {
+                return Vec::new();
+            }
+            let normal = normal.map(|n| n / length);
+            // Axes across the normal, so that `u` x `v` is the normal and a plane
+            // facing up has its size along X then Y
+            let other = match normal[1].abs() < 0.9 {
+                true => [0.0, 1.0, 0.0],
+                false => [1.0, 0.0, 0.0],
+            };
+            let u = normalize(cross(other, normal));
+            let v = cross(normal, u);
+            let corner = |a: f64, b: f64| {
+                let (a, b) = (a * size[0] / 2.0, b * size[1] / 2.0);
+                [0, 1, 2].map(|axis| u[axis] * a + v[axis] * b)
+            };
+            vec![
+                [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0)],
+                [corner(-1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)],
+            ]
+        }
+    }
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(vector: [f64; 3]) -> [f64; 3] {
+    let length = (vector[0].powi(2) + vector[1].powi(2) + vector[2].powi(2)).sqrt();
+    vector.map(|value| value / length)
+}
+
+#[cfg(test)]
+mod shape_triangles_tests {
+    use super::*;
+
+    fn extent(triangles: &[[[f64; 3]; 3]]) -> [f64; 3] {
+        let mut extent = [0.0f64; 3];
+        for vertex in triangles.iter().flatten() {
+            for axis in 0..3 {
+                extent[axis] = extent[axis].max(vertex[axis].abs());
+            }
+        }
+        extent
+    }
+
+    #[test]
+    fn it_triangulates_shapes_around_the_origin() {
+        let cube = shape_triangles(&VisualGeometry::Box {
+            size: [1.0, 2.0, 4.0],
+        });
+        assert_eq!(cube.len(), 12);
+        assert_eq!(extent(&cube), [0.5, 1.0, 2.0]);
+        // Every face points outward
+        for [a, b, c] in &cube {
+            let normal = cross(
+                [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+                [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+            );
+            let center = [0, 1, 2].map(|axis| a[axis] + b[axis] + c[axis]);
+            assert!(normal.iter().zip(center).map(|(n, c)| n * c).sum::<f64>() > 0.0);
+        }
+
+        let cylinder = shape_triangles(&VisualGeometry::Cylinder {
+            radius: 0.5,
+            length: 3.0,
+        });
+        assert_eq!(cylinder.len(), 4 * SEGMENTS);
+        assert!((extent(&cylinder)[0] - 0.5).abs() < 1e-9);
+        assert!((extent(&cylinder)[2] - 1.5).abs() < 1e-9);
+
+        let sphere = shape_triangles(&VisualGeometry::Sphere { radius: 2.0 });
+        assert!((extent(&sphere)[2] - 2.0).abs() < 1e-9);
+
+        let ground = shape_triangles(&VisualGeometry::Plane {
+            normal: [0.0, 0.0, 2.0],
+            size: [10.0, 4.0],
+        });
+        assert_eq!(ground.len(), 2);
+        assert_eq!(extent(&ground), [5.0, 2.0, 0.0]);
+        // Counterclockwise seen from the normal side
+        let [a, b, c] = ground[0];
+        assert!(
+            cross(
+                [b[0] - a[0], b[1] - a[1], 0.0],
+                [c[0] - a[0], c[1] - a[1], 0.0]
+            )[2] > 0.0
+        );
+
+        assert!(shape_triangles(&VisualGeometry::Mesh {
+            uri: String::from("model://crate/meshes/crate.dae"),
+            scale: [1.0; 3]
+        })
+        .is_empty());
+    }
+}
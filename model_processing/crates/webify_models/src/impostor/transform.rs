@@ -0,0 +1,234 @@
+//! Rigid transforms and scales of SDF poses and COLLADA nodes
+
+use std::ops::Mul;
+
+/// A 4x4 affine transform, row-major, applied to column vectors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform([[f64; 4]; 4]);
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Transform from 16 values in row-major order, as in a COLLADA `<matrix>`
+    pub fn from_row_major(values: &[f64]) -> Option<Transform> {
+        if values.len() != 16 {
+            return None;
+        }
+        let mut matrix = [[0.0; 4]; 4];
+        for (i, value) in values.iter().enumerate() {
+            matrix[i / 4][i % 4] = *value;
+        }
+
+        Some(Transform(matrix))
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Transform {
+        let mut transform = Transform::identity();
+        transform.0[0][3] = x;
+        transform.0[1][3] = y;
+        transform.0[2][3] = z;
+        transform
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Transform {
+        let mut transform = Transform::identity();
+        transform.0[0][0] = x;
+        transform.0[1][1] = y;
+        transform.0[2][2] = z;
+        transform
+    }
+
+    /// Rotation of `degrees` around the axis, as in a COLLADA `<rotate>`
+    pub fn rotation(axis: [f64; 3], degrees: f64) -> Transform {
+        let length = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+        if length == 0.0 {
+            return Transform::identity();
+        }
+        let [x, y, z] = [axis[0] / length, axis[1] / length, axis[2] / length];
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        Transform([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Transform of an SDF `<pose>`: `x y z roll pitch yaw`, the angles in radians
+    /// and applied roll first, around the fixed axes
+    pub fn from_pose(pose: &[f64]) -> Transform {
+        let value = |i: usize| pose.get(i).copied().unwrap_or(0.0);
+
+        Transform::translation(value(0), value(1), value(2))
+            * Transform::rotation([0.0, 0.0, 1.0], value(5).to_degrees())
+            * Transform::rotation([0.0, 1.0, 0.0], value(4).to_degrees())
+            * Transform::rotation([1.0, 0.0, 0.0], value(3).to_degrees())
+    }
+
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let m = &self.0;
+        let mut transformed = [0.0; 3];
+        for (i, row) in m.iter().take(3).enumerate() {
+            transformed[i] = row[0] * point[0] + row[1] * point[1] + row[2] * point[2] + row[3];
+        }
+        transformed
+    }
+
+    /// Rotation of a rigid transform as a unit quaternion, `[x, y, z, w]` as glTF
+    /// orders it
+    pub fn quaternion(&self) -> [f64; 4] {
+        let m = &self.0;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            [
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                s / 4.0,
+            ]
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            [
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            ]
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            [
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            ]
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            [
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+                (m[1][0] - m[0][1]) / s,
+            ]
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    /// The transform applying `rhs` first, then `self`
+    fn mul(self, rhs: Transform) -> Transform {
+        let mut product = [[0.0; 4]; 4];
+        for (i, row) in product.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+        Transform(product)
+    }
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn it_applies_sdf_poses() {
+        let yaw = Transform::from_pose(&[1.0, 2.0, 3.0, 0.0, 0.0, std::f64::consts::FRAC_PI_2]);
+        assert_close(yaw.apply([1.0, 0.0, 0.0]), [1.0, 3.0, 3.0]);
+
+        // Roll is applied before yaw: the Y axis goes to Z, then stays there
+        let roll_yaw = Transform::from_pose(&[
+            0.0,
+            0.0,
+            0.0,
+            std::f64::consts::FRAC_PI_2,
+            0.0,
+            std::f64::consts::FRAC_PI_2,
+        ]);
+        assert_close(roll_yaw.apply([0.0, 1.0, 0.0]), [0.0, 0.0, 1.0]);
+        assert_close(roll_yaw.apply([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn it_reads_the_rotation_as_a_quaternion() {
+        let close = |a: [f64; 4], b: [f64; 4]| {
+            assert!(
+                a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-9),
+                "{:?} != {:?}",
+                a,
+                b
+            )
+        };
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+
+        close(Transform::identity().quaternion(), [0.0, 0.0, 0.0, 1.0]);
+        close(
+            Transform::from_pose(&[1.0, 2.0, 3.0, 0.0, 0.0, std::f64::consts::FRAC_PI_2])
+                .quaternion(),
+            [0.0, 0.0, half, half],
+        );
+        // Half a turn has no real part, it takes the other branches
+        close(
+            Transform::rotation([1.0, 0.0, 0.0], 180.0).quaternion(),
+            [1.0, 0.0, 0.0, 0.0],
+        );
+        close(
+            Transform::rotation([0.0, 0.0, 1.0], 180.0).quaternion(),
+            [0.0, 0.0, 1.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn it_composes_collada_transforms() {
+        let matrix = Transform::from_row_major(&[
+            2.0, 0.0, 0.0, 5.0, //
+            0.0, 2.0, 0.0, 0.0, //
+            0.0, 0.0, 2.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0,
+        ])
+        .unwrap();
+        let transform = matrix * Transform::rotation([0.0, 0.0, 1.0], 90.0);
+
+        assert_close(transform.apply([1.0, 0.0, 0.0]), [5.0, 2.0, 0.0]);
+        assert!(Transform::from_row_major(&[1.0; 12]).is_none());
+    }
+}
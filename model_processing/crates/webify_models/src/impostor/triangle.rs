@@ -0,0 +1,8 @@
+//! Flat-colored triangle, what the impostor renderer draws
+
+/// A triangle and its color, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub vertices: [[f64; 3]; 3],
+    pub color: [f32; 3],
+}
@@ -0,0 +1,60 @@
+//! Triangulate a visual or collision of an SDF
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::resolve_reference;
+use crate::impostor::{
+    load_dae_triangles, shape_triangles, SdfVisual, Transform, Triangle, VisualGeometry,
+    DEFAULT_COLOR,
+};
+
+/// Triangles of the shape declared in `sdf`, posed in the frame of its model.
+/// Meshes are loaded once into `meshes`; meshes other than DAE, and the ones that
+/// can't be found, have no triangles.
+pub fn visual_triangles(
+    visual: &SdfVisual,
+    sdf: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    meshes: &mut HashMap<PathBuf, Vec<Triangle>>,
+) -> Result<Vec<Triangle>, Error> {
+    match &visual.geometry {
+        VisualGeometry::Mesh { uri, scale } => {
+            let mesh = match resolve_reference(sdf, uri, models) {
+                Some(mesh) if is_dae(&mesh) => mesh,
+                _ => return Ok(Vec::new()),
+            };
+            if !meshes.contains_key(&mesh) {
+                meshes.insert(mesh.clone(), load_dae_triangles(&mesh)?);
+            }
+            let transform = visual.pose * Transform::scale(scale[0], scale[1], scale[2]);
+            Ok(meshes[&mesh]
+                .iter()
+                .map(|triangle| Triangle {
+                    vertices: triangle.vertices.map(|vertex| transform.apply(vertex)),
+                    color: triangle.color,
+                })
+                .collect())
+        }
+        shape => {
+            let color = visual.color.unwrap_or(DEFAULT_COLOR);
+            Ok(shape_triangles(shape)
+                .into_iter()
+                .map(|vertices| Triangle {
+                    vertices: vertices.map(|vertex| visual.pose.apply(vertex)),
+                    color,
+                })
+                .collect())
+        }
+    }
+}
+
+fn is_dae(mesh: &Path) -> bool {
+    mesh.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("dae"))
+}
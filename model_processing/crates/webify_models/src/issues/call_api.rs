@@ -0,0 +1,105 @@
+//! Call the API of an issue tracker with curl
+
+use std::{
+    io::{Error, Write},
+    process::{Command, Stdio},
+    result::Result,
+};
+
+use serde_json::Value;
+
+use crate::config::{IssueProvider, IssueTracker};
+
+/// Command-line client the API is called with, found on the `PATH`
+const CURL: &str = "curl";
+
+/// Send a `method` request to `url` of the tracker's API with the JSON body, and
+/// return the JSON it answers. The request goes to curl through its standard
+/// input rather than its arguments, so the token doesn't show in the process
+/// list. Answers other than 2xx are errors, with what the tracker said.
+pub fn call_api(
+    tracker: &IssueTracker,
+    token: &str,
+    method: &str,
+    url: &str,
+    body: Option<&Value>,
+) -> Result<Value, Error> {
+    let mut headers = match tracker.provider {
+        IssueProvider::Github => vec![
+            format!("Authorization: Bearer {}", token),
+            String::from("Accept: application/vnd.github+json"),
+            String::from("X-GitHub-Api-Version: 2022-11-28"),
+        ],
+        IssueProvider::Gitlab => vec![format!("PRIVATE-TOKEN: {}", token)],
+    };
+    let mut request = format!(
+        "url = {}\nrequest = {}\nwrite-out = \"\\n%{{http_code}}\"\n",
+        quote(url),
+        quote(method)
+    );
+    if let Some(body) = body {
+        headers.push(String::from("Content-Type: application/json"));
+        request.push_str(&format!("data-binary = {}\n", quote(&body.to_string())));
+    }
+    for header in &headers {
+        request.push_str(&format!("header = {}\n", quote(header)));
+    }
+
+    let mut curl = Command::new(CURL)
+        .args(["--silent", "--show-error", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::other(format!("Could not run {} to file issues: {}", CURL, e)))?;
+    if let Some(mut stdin) = curl.stdin.take() {
+        stdin.write_all(request.as_bytes())?;
+    }
+    let output = curl.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "{} {} failed: {}",
+            method,
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (answer, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    if !status.trim().starts_with('2') {
+        return Err(Error::other(format!(
+            "{} {} answered {}: {}",
+            method,
+            url,
+            status.trim(),
+            answer.trim()
+        )));
+    }
+
+    serde_json::from_str(answer).map_err(|e| {
+        Error::other(format!(
+            "{} {} answered something other than JSON: {}",
+            method, url, e
+        ))
+    })
+}
+
+/// Value quoted for a curl config file, whose quoted strings unescape backslashes
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod call_api_tests {
+    use super::*;
+
+    #[test]
+    fn it_quotes_values_for_curl() {
+        let body = serde_json::json!({ "body": "line \"one\"\nline two" });
+        assert_eq!(
+            quote(&body.to_string()),
+            r#""{\"body\":\"line \\\"one\\\"\\nline two\"}""#
+        );
+    }
+}
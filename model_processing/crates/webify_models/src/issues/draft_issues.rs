@@ -0,0 +1,173 @@
+//! Draft one issue per model from the failures of a run
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::issues::ModelIssue;
+use crate::model::{locate_file, FileLocation};
+use crate::report::{Failure, RunReport, Warning};
+
+/// Most lines of failures, then of warnings, quoted in an issue, which trackers
+/// limit the size of
+const MAX_EXCERPT_LINES: usize = 50;
+
+/// Draft an issue for each model with failures, quoting its failures and
+/// warnings with paths relative to `base`, the directory the run processed. Files
+/// outside of any model go to the issue of their collection.
+pub fn draft_issues(report: &RunReport, base: &Path) -> Vec<ModelIssue> {
+    let mut failures: BTreeMap<PathBuf, Vec<Failure>> = BTreeMap::new();
+    for failure in &report.failures {
+        failures
+            .entry(owner(&failure.path, base))
+            .or_default()
+            .push(Failure {
+                path: relative(&failure.path, base),
+                ..failure.clone()
+            });
+    }
+
+    failures
+        .into_iter()
+        .map(|(model, failures)| {
+            let warnings: Vec<Warning> = report
+                .warnings
+                .iter()
+                .filter(|warning| owner(&warning.path, base) == model)
+                .map(|warning| Warning {
+                    path: relative(&warning.path, base),
+                    ..warning.clone()
+                })
+                .collect();
+            let name = match model.strip_prefix(base) {
+                Ok(name) if !name.as_os_str().is_empty() => name.to_path_buf(),
+                _ => PathBuf::from(base.file_name().unwrap_or(base.as_os_str())),
+            };
+
+            let mut body = format!(
+                "webify_models {} failed to process {} file(s) of `{}`.\n\n**Failures**\n\n{}",
+                env!("CARGO_PKG_VERSION"),
+                failures.len(),
+                name.to_string_lossy(),
+                excerpt(&failures)
+            );
+            if !warnings.is_empty() {
+                body.push_str(&format!("\n**Warnings**\n\n{}", excerpt(&warnings)));
+            }
+
+            ModelIssue {
+                title: format!(
+                    "webify_models: {} failed to process",
+                    name.to_string_lossy()
+                ),
+                model,
+                body,
+            }
+        })
+        .collect()
+}
+
+/// Model or collection the file belongs to, the base itself when neither can be
+/// told
+fn owner(path: &Path, base: &Path) -> PathBuf {
+    match locate_file(path, base) {
+        Ok(FileLocation::Model(dir)) | Ok(FileLocation::Collection(dir)) => dir,
+        Err(_) => base.to_path_buf(),
+    }
+}
+
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    path.strip_prefix(base).unwrap_or(path).to_path_buf()
+}
+
+/// The problems one per line in a code block, the ones past the limit counted
+fn excerpt(problems: &[impl Display]) -> String {
+    let mut lines: Vec<String> = problems
+        .iter()
+        .take(MAX_EXCERPT_LINES)
+        .map(|problem| problem.to_string())
+        .collect();
+    if problems.len() > MAX_EXCERPT_LINES {
+        lines.push(format!(
+            "... and {} more",
+            problems.len() - MAX_EXCERPT_LINES
+        ));
+    }
+
+    format!("```text\n{}\n```\n", lines.join("\n"))
+}
+
+#[cfg(test)]
+mod draft_issues_tests {
+    use super::*;
+
+    use std::{fs, io::Error};
+
+    use crate::report::ErrorCode;
+
+    #[test]
+    fn it_drafts_one_issue_per_failing_model() -> Result<(), Error> {
+        let base = Path::new("tests")
+            .join("issues")
+            .join("test_run_draft_issues");
+        for model in ["kitchen_table", "lamp"].iter() {
+            fs::create_dir_all(base.join(model).join("materials").join("textures"))?;
+            fs::write(base.join(model).join("model.config"), "<model/>")?;
+        }
+        let texture = |model: &str, name: &str| {
+            base.join(model)
+                .join("materials")
+                .join("textures")
+                .join(name)
+        };
+
+        let mut report = RunReport::default();
+        for name in ["top.tga", "legs.tga"].iter() {
+            report.failures.push(Failure::new(
+                ErrorCode::Decode,
+                "PNG Conversion",
+                texture("kitchen_table", name),
+                "truncated file",
+            ));
+        }
+        report.warnings.push(Warning::new(
+            ErrorCode::PowerOfTwo,
+            "Power of Two",
+            texture("kitchen_table", "wood.png"),
+            "resized from 100x100 to 128x128",
+        ));
+        report.warnings.push(Warning::new(
+            ErrorCode::PowerOfTwo,
+            "Power of Two",
+            texture("lamp", "shade.png"),
+            "resized from 100x100 to 128x128",
+        ));
+
+        let issues = draft_issues(&report, &base);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].model, base.join("kitchen_table"));
+        assert_eq!(
+            issues[0].title,
+            "webify_models: kitchen_table failed to process"
+        );
+        assert!(issues[0]
+            .body
+            .contains("failed to process 2 file(s) of `kitchen_table`"));
+        let top = Path::new("kitchen_table")
+            .join("materials")
+            .join("textures")
+            .join("top.tga");
+        assert!(issues[0].body.contains(&format!(
+            "[PNG Conversion] {}: truncated file",
+            top.to_string_lossy()
+        )));
+        assert!(issues[0].body.contains("**Warnings**"));
+        assert!(issues[0].body.contains("wood.png"));
+        assert!(!issues[0].body.contains("shade.png"));
+
+        fs::remove_dir_all(base)?;
+        Ok(())
+    }
+}
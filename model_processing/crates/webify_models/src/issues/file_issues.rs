@@ -0,0 +1,81 @@
+//! Open the issues drafted for the failing models
+
+use std::{collections::HashSet, io::Error, result::Result};
+
+use serde_json::json;
+
+use crate::config::{IssueProvider, IssueTracker};
+use crate::issues::{call_api, FiledIssue, ModelIssue};
+
+/// Open each issue in the tracker, unless one of its 100 most recently updated
+/// open issues has the same title, so a model failing run after run has a single
+/// issue. Returns what became of each issue, in order; only failing to list the
+/// open issues is an error.
+pub fn file_issues(
+    tracker: &IssueTracker,
+    token: &str,
+    issues: &[ModelIssue],
+) -> Result<Vec<FiledIssue>, Error> {
+    let issues_url = match tracker.provider {
+        IssueProvider::Github => {
+            format!("{}/repos/{}/issues", tracker.api_url(), tracker.repository)
+        }
+        IssueProvider::Gitlab => format!(
+            "{}/projects/{}/issues",
+            tracker.api_url(),
+            tracker.repository.replace('/', "%2F")
+        ),
+    };
+    let state = match tracker.provider {
+        IssueProvider::Github => "open",
+        IssueProvider::Gitlab => "opened",
+    };
+
+    let open = call_api(
+        tracker,
+        token,
+        "GET",
+        &format!("{}?state={}&per_page=100", issues_url, state),
+        None,
+    )?;
+    let open_titles: HashSet<&str> = open
+        .as_array()
+        .map(|open| {
+            open.iter()
+                .filter_map(|issue| issue["title"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(issues
+        .iter()
+        .map(|issue| {
+            if open_titles.contains(issue.title.as_str()) {
+                return FiledIssue::AlreadyOpen;
+            }
+
+            let (body, url_field) = match tracker.provider {
+                IssueProvider::Github => (
+                    json!({ "title": issue.title, "body": issue.body, "labels": tracker.labels }),
+                    "html_url",
+                ),
+                IssueProvider::Gitlab => (
+                    json!({
+                        "title": issue.title,
+                        "description": issue.body,
+                        "labels": tracker.labels.join(","),
+                    }),
+                    "web_url",
+                ),
+            };
+            match call_api(tracker, token, "POST", &issues_url, Some(&body)) {
+                Ok(opened) => FiledIssue::Opened(
+                    opened[url_field]
+                        .as_str()
+                        .map_or_else(|| issues_url.clone(), String::from),
+                ),
+                Err(e) => FiledIssue::Failed(e.to_string()),
+            }
+        })
+        .collect::<Vec<FiledIssue>>())
+}
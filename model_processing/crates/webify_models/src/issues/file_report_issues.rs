@@ -0,0 +1,46 @@
+//! File the failures of a run as issues and print what became of them
+
+use std::path::Path;
+
+use console::style;
+
+use crate::config::IssueTracker;
+use crate::issues::{draft_issues, file_issues, FiledIssue};
+use crate::report::RunReport;
+
+/// File an issue for each model of `base` the run failed on, and print the URL
+/// of the issues opened. Issues that can't be filed are printed as errors rather
+/// than failing a run that's over.
+pub fn file_report_issues(tracker: &IssueTracker, token: &str, report: &RunReport, base: &Path) {
+    let issues = draft_issues(report, base);
+    if issues.is_empty() {
+        return;
+    }
+
+    println!(
+        "\n{} {} model(s) to {}",
+        style("Filing issues").bold(),
+        issues.len(),
+        tracker.repository
+    );
+    let filed = match file_issues(tracker, token, &issues) {
+        Ok(filed) => filed,
+        Err(e) => {
+            println!(
+                "  {}",
+                style(format!("Could not list the open issues: {}", e)).red()
+            );
+            return;
+        }
+    };
+    for (issue, filed) in issues.iter().zip(filed) {
+        let model = issue.model.to_string_lossy();
+        match filed {
+            FiledIssue::Opened(url) => println!("  {} {}", model, url),
+            FiledIssue::AlreadyOpen => {
+                println!("  {} {}", model, style("already has an open issue").dim())
+            }
+            FiledIssue::Failed(e) => println!("  {} {}", model, style(e).red()),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Structure that represents what became of the issue of a model
+
+/// What filing the issue of a model came to
+#[derive(Debug, Clone, PartialEq)]
+pub enum FiledIssue {
+    /// Opened, at this URL
+    Opened(String),
+    /// Not filed again, an open issue has the same title
+    AlreadyOpen,
+    /// The tracker refused it, for this reason
+    Failed(String),
+}
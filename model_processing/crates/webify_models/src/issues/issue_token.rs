@@ -0,0 +1,22 @@
+//! Read the token issues are filed with
+
+use std::{env, io::Error, result::Result};
+
+/// Environment variable holding the API token issues are filed with
+pub const ISSUES_TOKEN_VARIABLE: &str = "WEBIFY_ISSUES_TOKEN";
+
+/// Token in `WEBIFY_ISSUES_TOKEN`, a GitHub token allowed to write issues or a
+/// GitLab token with the `api` scope. Kept out of `webify.toml`, which is shared
+/// with the library.
+pub fn issue_token() -> Result<String, Error> {
+    env::var(ISSUES_TOKEN_VARIABLE)
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .map(|token| token.trim().to_string())
+        .ok_or_else(|| {
+            Error::other(format!(
+                "--file-issues needs an API token in {}",
+                ISSUES_TOKEN_VARIABLE
+            ))
+        })
+}
@@ -0,0 +1,16 @@
+//! Read the issue tracker of a library from its configuration
+
+use std::{io::Error, path::Path, result::Result};
+
+use crate::config::{load_config, IssueTracker, CONFIG_FILE_NAME};
+
+/// Issue tracker of the `[issues]` of the library's `webify.toml`, which
+/// `--file-issues` can't do without
+pub fn load_issue_tracker(dir: &Path) -> Result<IssueTracker, Error> {
+    load_config(dir)?.issues.ok_or_else(|| {
+        Error::other(format!(
+            "--file-issues needs an [issues] section in {:?}",
+            dir.join(CONFIG_FILE_NAME)
+        ))
+    })
+}
@@ -0,0 +1,20 @@
+//! File the failures of a run as issues of the library's tracker, one per model,
+//! for the maintainers of the models to pick up
+
+mod call_api;
+mod draft_issues;
+mod file_issues;
+mod file_report_issues;
+mod filed_issue;
+mod issue_token;
+mod load_issue_tracker;
+mod model_issue;
+
+pub use self::call_api::call_api;
+pub use self::draft_issues::draft_issues;
+pub use self::file_issues::file_issues;
+pub use self::file_report_issues::file_report_issues;
+pub use self::filed_issue::FiledIssue;
+pub use self::issue_token::{issue_token, ISSUES_TOKEN_VARIABLE};
+pub use self::load_issue_tracker::load_issue_tracker;
+pub use self::model_issue::ModelIssue;
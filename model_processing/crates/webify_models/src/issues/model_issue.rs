@@ -0,0 +1,15 @@
+//! Structure that represents the issue of a model that failed to process
+
+use std::path::PathBuf;
+
+/// Issue drafted for a model with failures, with the excerpt of the report about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelIssue {
+    /// Root directory of the model, or of the collection for shared files
+    pub model: PathBuf,
+    /// Title of the issue, the same from run to run so open issues aren't filed
+    /// again
+    pub title: String,
+    /// Markdown body, the failures and warnings of the model
+    pub body: String,
+}
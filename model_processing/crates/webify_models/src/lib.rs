@@ -0,0 +1,38 @@
+//! Library side of webify_models, so the pipeline can be embedded in other
+//! applications (a server, a GUI) as well as driven from the command line.
+
+pub mod atlas;
+pub mod audio;
+pub mod audit;
+pub mod cli;
+pub mod compare;
+pub mod config;
+pub mod dependencies;
+pub mod doctor;
+pub mod floorplan;
+pub mod hashing;
+pub mod image_processing;
+pub mod impostor;
+pub mod issues;
+pub mod manifest;
+pub mod mesh_update;
+pub mod model;
+pub mod navmesh;
+pub mod options;
+pub mod orm;
+pub mod pipeline;
+pub mod preflight;
+pub mod provenance;
+pub mod rename;
+pub mod report;
+pub mod rotate;
+pub mod schema;
+pub mod self_test;
+pub mod sensor;
+pub mod similarity;
+pub mod terrain;
+pub mod trash;
+pub mod trends;
+pub mod walk;
+pub mod world;
+pub mod xml;
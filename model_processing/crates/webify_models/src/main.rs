@@ -10,24 +10,203 @@ use std::process::exit;
 
 use console::style;
 
-mod cli;
-mod image_processing;
-mod mesh_update;
+use webify_models::{
+    audit, cli, compare, config, dependencies, doctor, floorplan, issues, pipeline::Pipeline,
+    rename, report, rotate, schema, self_test, similarity, trends,
+};
 
 fn main() -> std::result::Result<(), std::io::Error> {
-    println!("{}", style("Roboverse").underlined().bold().white());
-
     let args: Vec<String> = env::args().collect();
-    let path = match cli::parse_args_for_path(&args) {
-        Ok(p) => p,
+    let command = cli::parse_command(&args);
+
+    // Schemas and graphs are printed alone, so they can be redirected to a file
+    if !matches!(
+        command,
+        Ok(cli::Command::Schema { .. }) | Ok(cli::Command::Graph { output: None, .. })
+    ) {
+        println!("{}", style("Roboverse").underlined().bold().white());
+    }
+    let command = match command {
+        Ok(c) => c,
         Err(e) => {
             println!("{}", e);
             exit(1)
         }
     };
 
-    image_processing::process(path)?;
-    mesh_update::process(path)?;
+    match command {
+        cli::Command::Process { path, options } => {
+            // Checked before the run rather than once it's over. A dry run files nothing.
+            let issue_tracker = match options.file_issues && !options.dry_run {
+                true => match issues::load_issue_tracker(&path)
+                    .and_then(|tracker| Ok((tracker, issues::issue_token()?)))
+                {
+                    Ok(issue_tracker) => Some(issue_tracker),
+                    Err(e) => {
+                        println!("{}", e);
+                        exit(1)
+                    }
+                },
+                false => None,
+            };
+            let pipeline = Pipeline::new(options);
+
+            // First Ctrl-C finishes the current file and stops cleanly
+            let cancellation_token = pipeline.cancellation_token();
+            ctrlc::set_handler(move || cancellation_token.cancel())
+                .map_err(std::io::Error::other)?;
+
+            let run_report = match pipeline.run(&path) {
+                Ok(run_report) => run_report,
+                // A failure that aborted the run exits with the status of its class
+                Err(e) => match e
+                    .get_ref()
+                    .and_then(|e| e.downcast_ref::<report::Failure>())
+                {
+                    Some(failure) => {
+                        println!("{}", style(failure).red());
+                        exit(failure.code.exit_status())
+                    }
+                    None => return Err(e),
+                },
+            };
+            if let Some(json_report) = &pipeline.options().json_report {
+                report::write_json_report(json_report, &run_report)?;
+            }
+            if let Some(html_report) = &pipeline.options().html_report {
+                report::write_html_report(html_report, &run_report)?;
+            }
+
+            report::print_summary(&run_report, pipeline.options());
+            if let Some((tracker, token)) = &issue_tracker {
+                let base = pipeline.options().output.as_deref().unwrap_or(&path);
+                issues::file_report_issues(tracker, token, &run_report, base);
+            }
+            if run_report.out_of_time {
+                // Stopping on time is the plan, not a failure: the next run resumes
+                println!(
+                    "{}",
+                    style(format!(
+                        "Time budget elapsed with {} texture(s) left, run again to resume.",
+                        run_report.remaining.len()
+                    ))
+                    .yellow()
+                );
+            } else if run_report.cancelled {
+                println!("{}", style("Run cancelled before completion.").yellow());
+                exit(130)
+            }
+            let exit_status = run_report.exit_status(pipeline.options().deny_warnings);
+            if exit_status != 0 {
+                exit(exit_status)
+            }
+        }
+        cli::Command::Compare {
+            tree_a,
+            tree_b,
+            json,
+        } => {
+            let diff = compare::process(&tree_a, &tree_b, json.as_deref())?;
+            if !diff.is_empty() {
+                exit(1)
+            }
+        }
+        cli::Command::ShowConfig { path, options } => {
+            let values =
+                config::resolve_config(&path, &options, config::user_config_path().as_deref())?;
+            config::print_config(&path, &values);
+        }
+        cli::Command::Doctor { path, options } => {
+            let doctor_report = doctor::run_doctor(&path, &options)?;
+            doctor::print_doctor_report(&path, &doctor_report);
+            if !doctor_report.is_healthy() {
+                exit(1)
+            }
+        }
+        cli::Command::CheckDependencies { library } => {
+            let issues = dependencies::process(&library)?;
+            if !issues.is_empty() {
+                exit(1)
+            }
+        }
+        cli::Command::Graph {
+            library,
+            format,
+            output,
+        } => {
+            dependencies::export_graph(&library, format, output.as_deref())?;
+        }
+        cli::Command::SimilarTextures {
+            library,
+            max_distance,
+            json,
+            memory_budget,
+        } => {
+            similarity::process(&library, max_distance, json.as_deref(), memory_budget)?;
+        }
+        cli::Command::History { file } => {
+            let history = audit::file_history(&file)?;
+            audit::print_history(&file, &history);
+            if history.tampered.is_some() {
+                exit(1)
+            }
+        }
+        cli::Command::Trends { library, json } => {
+            trends::process(&library, json.as_deref())?;
+        }
+        cli::Command::Summarize {
+            event_log,
+            json_report,
+        } => {
+            let run_report = report::replay_event_log(&event_log)?;
+            if let Some(json_report) = &json_report {
+                report::write_json_report(json_report, &run_report)?;
+            }
+
+            report::print_summary(&run_report, &Default::default());
+            if run_report.cancelled && !run_report.out_of_time {
+                println!(
+                    "{}",
+                    style("The run didn't complete, this covers the files it got through.")
+                        .yellow()
+                );
+            }
+        }
+        cli::Command::RenameModel {
+            model_dir,
+            new_name,
+            library,
+        } => {
+            rename::process(&library, &model_dir, &new_name)?;
+        }
+        cli::Command::Rotate { texture, rotation } => {
+            rotate::process(&texture, rotation)?;
+        }
+        cli::Command::Floorplan {
+            world,
+            library,
+            output,
+            settings,
+        } => {
+            floorplan::process(&world, &library, output.as_deref(), &settings)?;
+        }
+        cli::Command::Schema { name } => schema::print_schema(name.as_deref())?,
+        cli::Command::SelfTest => {
+            let mismatches = self_test::run_self_test()?;
+            if !mismatches.is_empty() {
+                println!(
+                    "\n{} {} mismatch(es) with the golden outputs:",
+                    style("Self-test failed,").red().bold(),
+                    mismatches.len()
+                );
+                for mismatch in &mismatches {
+                    println!("  {}", mismatch);
+                }
+                exit(1)
+            }
+            println!("\n{}", style("Self-test passed.").green().bold());
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,361 @@
+//! Build the web manifest from the textures processed during a run
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::atlas::find_atlas;
+use crate::audio::is_sound;
+use crate::config::WebifyConfig;
+use crate::hashing::hash_file;
+use crate::image_processing::{find_mip_levels, read_sprite_sheet};
+use crate::impostor::find_impostor;
+use crate::manifest::{
+    material_variants, percent_encode_path, AtlasEntry, ImpostorEntry, ModelComplexity, ModelEntry,
+    ModelManifest, OrmEntry, SoundEntry, TextureEntry, WorldEntry, MATERIAL_VARIANTS,
+};
+use crate::model::{find_model_dirs, resolve_category};
+use crate::options::RunOptions;
+use crate::orm::{find_orm_textures, OrmMetadata};
+use crate::report::{MeshStats, RunReport, TextureStats};
+use crate::terrain::terrain_paths;
+use crate::world::WorldScene;
+
+/// Build the manifest of every model under `dir`, with the textures recorded in
+/// the report, the sounds of its `sounds` directory, the complexity of the model's
+/// meshes and textures, its impostor, its texture atlas, its ORM textures and the
+/// texture sets of its material variants, then the worlds with the scene read from them and their baked
+/// terrain. References are percent-encoded when `--url-encode-references` is on.
+pub fn build_manifest(
+    dir: &Path,
+    config: &WebifyConfig,
+    report: &RunReport,
+    worlds: &BTreeMap<PathBuf, WorldScene>,
+    options: &RunOptions,
+) -> Result<ModelManifest, Error> {
+    let mut models: BTreeMap<PathBuf, Vec<&TextureStats>> = find_model_dirs(dir)?
+        .into_iter()
+        .map(|model_dir| (model_dir, Vec::new()))
+        .collect();
+    let mut shared_textures = Vec::new();
+
+    for stats in &report.textures {
+        match &stats.model {
+            Some(model_dir) => models.entry(model_dir.clone()).or_default().push(stats),
+            None => shared_textures.push(texture_entry(stats, dir, options)?),
+        }
+    }
+
+    let mut variant_words: Vec<String> = MATERIAL_VARIANTS.iter().map(|w| w.to_string()).collect();
+    for word in &config.material_variants {
+        if !variant_words
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(word))
+        {
+            variant_words.push(word.clone());
+        }
+    }
+
+    let mut manifest = ModelManifest {
+        shared_textures,
+        ..ModelManifest::default()
+    };
+    for (model_dir, textures) in models {
+        let meshes: Vec<&MeshStats> = report
+            .meshes
+            .iter()
+            .filter(|mesh| mesh.model.as_ref() == Some(&model_dir))
+            .collect();
+        let complexity = ModelComplexity::new(
+            meshes.iter().map(|mesh| mesh.complexity.triangles).sum(),
+            meshes.iter().map(|mesh| mesh.complexity.draw_calls).sum(),
+            textures.iter().map(|texture| texture.file_bytes).sum(),
+            meshes.iter().map(|mesh| mesh.complexity.nodes).sum(),
+        );
+        let textures = textures
+            .into_iter()
+            .map(|stats| texture_entry(stats, &model_dir, options))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let variants = material_variants(&textures, &variant_words);
+        let orm = orm_entries(&model_dir, &textures, options)?;
+        manifest.models.push(ModelEntry {
+            name: model_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: reference(&model_dir, dir, options),
+            category: resolve_category(&model_dir, config)?,
+            complexity,
+            textures,
+            sounds: sound_entries(&model_dir, options)?,
+            impostor: find_impostor(&model_dir)?.map(|(atlas, metadata)| ImpostorEntry {
+                atlas: reference(&atlas, &model_dir, options),
+                metadata,
+            }),
+            atlas: find_atlas(&model_dir)?.map(|(atlas, metadata)| AtlasEntry {
+                atlas: reference(&atlas, &model_dir, options),
+                metadata,
+            }),
+            orm,
+            variants,
+        });
+    }
+
+    manifest.worlds = worlds
+        .iter()
+        .map(|(world, scene)| {
+            let (terrain, _) = terrain_paths(world);
+            WorldEntry {
+                name: world
+                    .file_stem()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: reference(world, dir, options),
+                terrain: match terrain.is_file() {
+                    true => Some(reference(&terrain, dir, options)),
+                    false => None,
+                },
+                scene: scene.clone(),
+            }
+        })
+        .collect();
+
+    Ok(manifest)
+}
+
+/// Manifest entry of a texture, referenced relative to `root`
+fn texture_entry(
+    stats: &TextureStats,
+    root: &Path,
+    options: &RunOptions,
+) -> Result<TextureEntry, Error> {
+    Ok(TextureEntry {
+        path: reference(&stats.path, root, options),
+        width: stats.width,
+        height: stats.height,
+        channels: stats.channels,
+        file_bytes: stats.file_bytes,
+        gpu_bytes: stats.gpu_bytes,
+        hash: hash_file(&stats.path)?,
+        encoding: stats.encoding.clone(),
+        mips: find_mip_levels(&stats.path)
+            .iter()
+            .map(|level| reference(level, root, options))
+            .collect(),
+        sprite_sheet: read_sprite_sheet(&stats.path),
+    })
+}
+
+/// Manifest entries of the ORM textures of the model, the maps packed in them
+/// referenced as the textures of the model they became when one has their name
+fn orm_entries(
+    model_dir: &Path,
+    textures: &[TextureEntry],
+    options: &RunOptions,
+) -> Result<Vec<OrmEntry>, Error> {
+    let current = |map: Option<String>| {
+        map.map(|map| {
+            let map = reference(Path::new(&map), Path::new(""), options);
+            let stem = Path::new(&map).file_stem().map(|stem| stem.to_os_string());
+            textures
+                .iter()
+                .find(|texture| {
+                    Path::new(&texture.path)
+                        .file_stem()
+                        .map(|s| s.to_os_string())
+                        == stem
+                })
+                .map_or(map, |texture| texture.path.clone())
+        })
+    };
+
+    Ok(find_orm_textures(model_dir)?
+        .into_iter()
+        .map(|(texture, metadata)| OrmEntry {
+            texture: reference(&texture, model_dir, options),
+            metadata: OrmMetadata {
+                occlusion: current(metadata.occlusion),
+                roughness: current(metadata.roughness),
+                metalness: current(metadata.metalness),
+                ..metadata
+            },
+        })
+        .collect())
+}
+
+/// Manifest entries of the sounds in the `sounds` directory of the model, sorted
+fn sound_entries(model_dir: &Path, options: &RunOptions) -> Result<Vec<SoundEntry>, Error> {
+    let sounds_dir = model_dir.join("sounds");
+    if !sounds_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut sounds: Vec<PathBuf> = fs::read_dir(&sounds_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, Error>>()?;
+    sounds.retain(|path| path.is_file() && is_sound(path));
+    sounds.sort();
+
+    sounds
+        .iter()
+        .map(|sound| {
+            Ok(SoundEntry {
+                path: reference(sound, model_dir, options),
+                file_bytes: fs::metadata(sound)?.len(),
+                hash: hash_file(sound)?,
+            })
+        })
+        .collect()
+}
+
+/// Reference to the path relative to `root`, with forward slashes as URLs expect
+fn reference(path: &Path, root: &Path, options: &RunOptions) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let reference = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if options.url_encode_references {
+        percent_encode_path(&reference)
+    } else {
+        reference
+    }
+}
+
+#[cfg(test)]
+mod build_manifest_tests {
+    use super::*;
+
+    use crate::mesh_update::MeshComplexity;
+
+    fn texture(path: PathBuf, model: Option<PathBuf>) -> TextureStats {
+        TextureStats {
+            path,
+            model,
+            width: 64,
+            height: 32,
+            channels: 4,
+            file_bytes: 100,
+            gpu_bytes: 200,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn it_lists_models_and_their_textures() -> Result<(), Error> {
+        let dir = Path::new("tests").join("model").join("collection");
+        let lamp = dir.join("lamp");
+        let report = RunReport {
+            textures: vec![
+                texture(
+                    lamp.join("materials")
+                        .join("textures")
+                        .join("Lamp Shade.png"),
+                    Some(lamp.clone()),
+                ),
+                texture(
+                    dir.join("media")
+                        .join("materials")
+                        .join("textures")
+                        .join("wood.png"),
+                    None,
+                ),
+            ],
+            meshes: vec![MeshStats {
+                path: lamp.join("meshes").join("lamp.dae"),
+                model: Some(lamp.clone()),
+                file_bytes: 1000,
+                complexity: MeshComplexity {
+                    triangles: 5000,
+                    draw_calls: 2,
+                    nodes: 3,
+                },
+            }],
+            ..RunReport::default()
+        };
+
+        let worlds = vec![(
+            dir.join("worlds").join("office.world"),
+            WorldScene::default(),
+        )]
+        .into_iter()
+        .collect();
+        let manifest = build_manifest(
+            &dir,
+            &WebifyConfig::default(),
+            &report,
+            &worlds,
+            &RunOptions::default(),
+        )?;
+
+        assert_eq!(manifest.models.len(), 2);
+        assert_eq!(manifest.models[0].name, "desk");
+        assert!(manifest.models[0].textures.is_empty());
+        assert_eq!(manifest.models[1].path, "lamp");
+        assert_eq!(
+            manifest.models[1].textures[0].path,
+            "materials/textures/Lamp Shade.png"
+        );
+        assert_eq!(manifest.models[1].textures[0].width, 64);
+        assert_eq!(
+            manifest.shared_textures[0].path,
+            "media/materials/textures/wood.png"
+        );
+        assert_eq!(
+            manifest.models[1].complexity,
+            ModelComplexity::new(5000, 2, 100, 3)
+        );
+        assert_eq!(manifest.models[0].complexity, ModelComplexity::default());
+        assert_eq!(
+            manifest.worlds,
+            vec![WorldEntry {
+                name: String::from("office"),
+                path: String::from("worlds/office.world"),
+                terrain: None,
+                scene: WorldScene::default(),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_percent_encodes_references() -> Result<(), Error> {
+        let dir = Path::new("tests").join("model").join("collection");
+        let lamp = dir.join("lamp");
+        let report = RunReport {
+            textures: vec![texture(
+                lamp.join("materials")
+                    .join("textures")
+                    .join("Lamp Shade.png"),
+                Some(lamp.clone()),
+            )],
+            ..RunReport::default()
+        };
+        let options = RunOptions {
+            url_encode_references: true,
+            ..RunOptions::default()
+        };
+
+        let manifest = build_manifest(
+            &dir,
+            &WebifyConfig::default(),
+            &report,
+            &BTreeMap::new(),
+            &options,
+        )?;
+
+        assert_eq!(
+            manifest.models[1].textures[0].path,
+            "materials/textures/Lamp%20Shade.png"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! Find the texture sets of the material variants of a model
+
+use std::collections::BTreeMap;
+
+use crate::manifest::{MaterialVariantEntry, TextureEntry};
+
+/// Words naming the texture sets of material variants, on top of those of
+/// `material_variants` in `webify.toml`
+pub const MATERIAL_VARIANTS: [&str; 5] = ["clean", "dirty", "worn", "damaged", "rusty"];
+
+/// The material variants of a model, from the references of its textures: a
+/// texture belongs to the variant named by a word of its file name, after a `_`,
+/// `-`, `.` or space, or by one of its directories, whatever the case. Textures of
+/// different variants stand in for one another when their references are the same
+/// once the variant is left out, like `crate_clean_diffuse.png` and
+/// `crate_worn_diffuse.png`. Variants are listed in the order of `words`, and only
+/// when the model has at least two.
+pub fn material_variants(textures: &[TextureEntry], words: &[String]) -> Vec<MaterialVariantEntry> {
+    let mut variants: BTreeMap<usize, BTreeMap<String, String>> = BTreeMap::new();
+    for texture in textures {
+        if let Some((variant, slot)) = variant_slot(&texture.path, words) {
+            variants
+                .entry(variant)
+                .or_default()
+                .insert(slot, texture.path.clone());
+        }
+    }
+    if variants.len() < 2 {
+        return Vec::new();
+    }
+
+    variants
+        .into_iter()
+        .map(|(variant, textures)| MaterialVariantEntry {
+            name: words[variant].clone(),
+            textures,
+        })
+        .collect()
+}
+
+/// Index of the variant the reference belongs to, and the reference without it
+fn variant_slot(reference: &str, words: &[String]) -> Option<(usize, String)> {
+    let is_variant = |word: &str| {
+        words
+            .iter()
+            .position(|variant| variant.eq_ignore_ascii_case(word))
+    };
+
+    let mut components: Vec<&str> = reference.split('/').collect();
+    let file_name = components.pop()?;
+    if let Some((i, variant)) = components
+        .iter()
+        .enumerate()
+        .find_map(|(i, component)| is_variant(component).map(|variant| (i, variant)))
+    {
+        components.remove(i);
+        components.push(file_name);
+        return Some((variant, components.join("/")));
+    }
+
+    let stem_end = file_name.rfind('.').unwrap_or(file_name.len());
+    let mut start = 0;
+    for (end, separator) in file_name[..stem_end]
+        .char_indices()
+        .filter(|(_, c)| ['_', '-', '.', ' '].contains(c))
+        .map(|(i, _)| (i, true))
+        .chain(std::iter::once((stem_end, false)))
+    {
+        if let Some(variant) = is_variant(&file_name[start..end]) {
+            // Drop the word with the separator before it, or after it when first
+            let name = match (start, separator) {
+                (0, true) => file_name[end + 1..].to_string(),
+                (0, false) => return None,
+                _ => format!("{}{}", &file_name[..start - 1], &file_name[end..]),
+            };
+            components.push(&name);
+            return Some((variant, components.join("/")));
+        }
+        start = end + 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod material_variants_tests {
+    use super::*;
+
+    fn texture(path: &str) -> TextureEntry {
+        TextureEntry {
+            path: String::from(path),
+            ..TextureEntry::default()
+        }
+    }
+
+    fn words() -> Vec<String> {
+        MATERIAL_VARIANTS
+            .iter()
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn it_pairs_the_textures_of_each_variant() {
+        let textures = [
+            texture("materials/textures/crate_Worn_diffuse.png"),
+            texture("materials/textures/crate_clean_diffuse.png"),
+            texture("materials/textures/clean-crate_normal.png"),
+            texture("materials/textures/worn/crate_normal.png"),
+            texture("materials/textures/crate_roughness.png"),
+        ];
+
+        let variants = material_variants(&textures, &words());
+
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].name, "clean");
+        assert_eq!(
+            variants[0].textures,
+            vec![
+                (
+                    String::from("materials/textures/crate_diffuse.png"),
+                    String::from("materials/textures/crate_clean_diffuse.png")
+                ),
+                (
+                    String::from("materials/textures/crate_normal.png"),
+                    String::from("materials/textures/clean-crate_normal.png")
+                ),
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(variants[1].name, "worn");
+        assert_eq!(
+            variants[1]
+                .textures
+                .get("materials/textures/crate_normal.png"),
+            Some(&String::from("materials/textures/worn/crate_normal.png"))
+        );
+        assert_eq!(
+            variants[1]
+                .textures
+                .get("materials/textures/crate_diffuse.png"),
+            Some(&String::from("materials/textures/crate_Worn_diffuse.png"))
+        );
+    }
+
+    #[test]
+    fn it_needs_two_variants() {
+        let textures = [
+            texture("materials/textures/dirty_floor.png"),
+            texture("materials/textures/dirty.png"),
+        ];
+        assert!(material_variants(&textures, &words()).is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+//! The `models.json` web manifest, listing every processed model and its textures
+//! so the web viewer knows what to fetch
+
+mod build_manifest;
+mod material_variants;
+mod model_complexity;
+mod model_manifest;
+mod percent_encode_path;
+mod write_manifest;
+
+pub use self::build_manifest::build_manifest;
+pub use self::material_variants::{material_variants, MATERIAL_VARIANTS};
+pub use self::model_complexity::ModelComplexity;
+pub use self::model_manifest::{
+    AtlasEntry, ImpostorEntry, MaterialVariantEntry, ModelEntry, ModelManifest, OrmEntry,
+    SoundEntry, TextureEntry, WorldEntry,
+};
+pub use self::percent_encode_path::percent_encode_path;
+pub use self::write_manifest::{write_manifest, MANIFEST_FILE_NAME};
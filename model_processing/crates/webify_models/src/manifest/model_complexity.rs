@@ -0,0 +1,63 @@
+//! How heavy a model is to stream and render
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What each point of the score stands for, a score of 1 being as heavy as any
+/// one of these
+const TRIANGLES_PER_POINT: f64 = 10_000.0;
+const DRAW_CALLS_PER_POINT: f64 = 10.0;
+const TEXTURE_BYTES_PER_POINT: f64 = 4.0 * 1024.0 * 1024.0;
+const NODES_PER_POINT: f64 = 100.0;
+
+/// Complexity of a model, for the web viewer to pick what to stream at which
+/// quality tier
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModelComplexity {
+    /// Triangles of the model's meshes, each geometry instance counted
+    pub triangles: u64,
+    /// Primitive groups drawn, one per material of each geometry instance
+    pub draw_calls: u64,
+    /// Size of the model's textures on disk, what streaming it downloads, in bytes
+    pub texture_bytes: u64,
+    /// Nodes of the visual scenes of the model's meshes
+    pub nodes: u64,
+    /// Sum of the above relative to 10,000 triangles, 10 draw calls, 4 MiB of
+    /// textures and 100 nodes, rounded to two decimals. A model scoring 2 is about
+    /// twice as heavy as one scoring 1.
+    pub score: f64,
+}
+
+impl ModelComplexity {
+    pub fn new(triangles: u64, draw_calls: u64, texture_bytes: u64, nodes: u64) -> ModelComplexity {
+        let score = triangles as f64 / TRIANGLES_PER_POINT
+            + draw_calls as f64 / DRAW_CALLS_PER_POINT
+            + texture_bytes as f64 / TEXTURE_BYTES_PER_POINT
+            + nodes as f64 / NODES_PER_POINT;
+
+        ModelComplexity {
+            triangles,
+            draw_calls,
+            texture_bytes,
+            nodes,
+            score: (score * 100.0).round() / 100.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod model_complexity_tests {
+    use super::*;
+
+    #[test]
+    fn it_weighs_each_measure() {
+        assert_eq!(ModelComplexity::new(0, 0, 0, 0).score, 0.0);
+        assert_eq!(ModelComplexity::new(10_000, 0, 0, 0).score, 1.0);
+        assert_eq!(
+            ModelComplexity::new(5_000, 10, 8 * 1024 * 1024, 50).score,
+            4.0
+        );
+        assert_eq!(ModelComplexity::new(1, 0, 0, 0).score, 0.0);
+        assert_eq!(ModelComplexity::new(1234, 0, 0, 0).score, 0.12);
+    }
+}
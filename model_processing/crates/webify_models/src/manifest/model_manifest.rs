@@ -0,0 +1,154 @@
+//! Structures of the `models.json` web manifest
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::atlas::AtlasMetadata;
+use crate::image_processing::SpriteSheetMetadata;
+use crate::impostor::ImpostorMetadata;
+use crate::manifest::ModelComplexity;
+use crate::orm::OrmMetadata;
+use crate::provenance::Encoding;
+use crate::world::WorldScene;
+
+/// Every processed model, the textures shared by collections, and the worlds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModelManifest {
+    pub models: Vec<ModelEntry>,
+    /// Textures shared by the models of a collection, referenced relative to the
+    /// root of the processed directory
+    pub shared_textures: Vec<TextureEntry>,
+    /// Worlds of the library, with what the viewer needs to replicate their scene
+    #[serde(default)]
+    pub worlds: Vec<WorldEntry>,
+}
+
+/// A processed model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModelEntry {
+    /// Name of the model directory
+    pub name: String,
+    /// Reference to the model directory, relative to the root of the processed directory
+    pub path: String,
+    /// Category from webify.toml, when the model has one
+    pub category: Option<String>,
+    /// How heavy the model is to stream and render
+    pub complexity: ModelComplexity,
+    /// Textures of the model, referenced relative to the model directory
+    pub textures: Vec<TextureEntry>,
+    /// Sounds of the model, referenced relative to the model directory
+    #[serde(default)]
+    pub sounds: Vec<SoundEntry>,
+    /// Billboard drawn in place of the model from afar, when it has one
+    pub impostor: Option<ImpostorEntry>,
+    /// Atlas the model's small textures were packed into, when it has one
+    #[serde(default)]
+    pub atlas: Option<AtlasEntry>,
+    /// Textures the model's occlusion, roughness and metalness maps were packed
+    /// into with `--pack-orm`
+    #[serde(default)]
+    pub orm: Vec<OrmEntry>,
+    /// Skins of the model, like clean and worn, the viewer can switch its
+    /// materials between, as `KHR_materials_variants` does for glTF
+    #[serde(default)]
+    pub variants: Vec<MaterialVariantEntry>,
+}
+
+/// A processed texture
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TextureEntry {
+    /// Reference the viewer fetches the texture with
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// Number of color channels, so the loader can preallocate
+    pub channels: u8,
+    /// Size of the file on disk, in bytes
+    pub file_bytes: u64,
+    /// Estimated decompressed size on the GPU, mip chain included, in bytes
+    pub gpu_bytes: u64,
+    /// Hex-encoded BLAKE3 hash of the texture, for cache busting
+    pub hash: String,
+    /// Encoder and settings the texture was written with, or `None` when it was
+    /// left as it came
+    pub encoding: Option<Encoding>,
+    /// Levels of the mip chain below the full size one, from the largest, written
+    /// with `--mipmaps` for textures that don't embed it
+    #[serde(default)]
+    pub mips: Vec<String>,
+    /// Layout and timing of the frames, for a GIF converted with `--gif sprite-sheet`
+    #[serde(default)]
+    pub sprite_sheet: Option<SpriteSheetMetadata>,
+}
+
+/// A world of the library
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldEntry {
+    /// File name of the world without its extension
+    pub name: String,
+    /// Reference to the world file, relative to the root of the processed directory
+    pub path: String,
+    /// Reference to the glTF terrain baked from the world's heightmaps with
+    /// `--terrain`, relative to the root of the processed directory
+    #[serde(default)]
+    pub terrain: Option<String>,
+    #[serde(flatten)]
+    pub scene: WorldScene,
+}
+
+/// A material variant of a model: the textures its materials use in this skin
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MaterialVariantEntry {
+    /// Name of the variant, e.g. `worn`
+    pub name: String,
+    /// Texture of the variant, referenced relative to the model directory, keyed
+    /// by its reference with the variant's name left out: the textures of other
+    /// variants with the same key are the ones it swaps with
+    pub textures: BTreeMap<String, String>,
+}
+
+/// A sound of a model, for the web experience to play
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SoundEntry {
+    /// Reference the viewer fetches the sound with
+    pub path: String,
+    /// Size of the file on disk, in bytes
+    pub file_bytes: u64,
+    /// Hex-encoded BLAKE3 hash of the sound, for cache busting
+    pub hash: String,
+}
+
+/// The texture atlas of a model and where each texture went in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AtlasEntry {
+    /// Reference to the atlas, relative to the model directory. It's also listed
+    /// with the textures.
+    pub atlas: String,
+    #[serde(flatten)]
+    pub metadata: AtlasMetadata,
+}
+
+/// An ORM texture of a model and the maps packed in it, for its materials to
+/// sample it in their place
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OrmEntry {
+    /// Reference to the ORM texture, relative to the model directory. It's also
+    /// listed with the textures.
+    pub texture: String,
+    /// The maps packed in each channel, referenced as they are now when the run
+    /// still has them under the same name, and as they were packed otherwise
+    #[serde(flatten)]
+    pub metadata: OrmMetadata,
+}
+
+/// The impostor atlas of a model and how it's laid out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ImpostorEntry {
+    /// Reference to the atlas, relative to the model directory. It's also listed
+    /// with the textures.
+    pub atlas: String,
+    #[serde(flatten)]
+    pub metadata: ImpostorMetadata,
+}
@@ -0,0 +1,36 @@
+//! Percent-encode a reference so loaders fetch the right URL
+
+/// Percent-encode every byte of the reference that isn't allowed as is in a URL
+/// path, keeping `/` as the separator. `Wood Panel #2.png` becomes
+/// `Wood%20Panel%20%232.png`.
+pub fn percent_encode_path(reference: &str) -> String {
+    let mut encoded = String::with_capacity(reference.len());
+    for byte in reference.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod percent_encode_path_tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_special_characters() {
+        assert_eq!(
+            percent_encode_path("materials/textures/Wood Panel #2.png"),
+            "materials/textures/Wood%20Panel%20%232.png"
+        );
+        assert_eq!(percent_encode_path("100%_ü.png"), "100%25_%C3%BC.png");
+        assert_eq!(
+            percent_encode_path("materials/textures/wood.png"),
+            "materials/textures/wood.png"
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Write the web manifest at the root of the processed directory
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::manifest::ModelManifest;
+
+/// Name of the manifest written at the root of the processed directory
+pub const MANIFEST_FILE_NAME: &str = "models.json";
+
+/// Write the manifest as `models.json` at the root of the processed directory
+pub fn write_manifest(dir: &Path, manifest: &ModelManifest) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(manifest).map_err(Error::other)?;
+    fs::write(dir.join(MANIFEST_FILE_NAME), contents)?;
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Find texture references in a DAE mesh that don't point at an existing file
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+/// List every `<init_from>` reference in the mesh that doesn't resolve to a file,
+/// relative to the mesh's directory
+pub fn find_missing_references(mesh: &Path) -> Result<Vec<String>, Error> {
+    let contents = fs::read_to_string(mesh)?;
+    let mesh_dir = mesh.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut missing = Vec::new();
+    let mut rest = contents.as_str();
+    while let Some(start) = rest.find("<init_from>") {
+        rest = &rest[start + "<init_from>".len()..];
+        let end = match rest.find("</init_from>") {
+            Some(end) => end,
+            None => break,
+        };
+
+        let reference = rest[..end].trim();
+        let reference_path = reference.trim_start_matches("file://");
+        if !reference.is_empty() && !mesh_dir.join(reference_path).is_file() {
+            missing.push(reference.to_string());
+        }
+        rest = &rest[end..];
+    }
+
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod find_missing_references_tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_missing_textures() {
+        let mesh = Path::new("tests")
+            .join("mesh_update")
+            .join("already_png")
+            .join("meshes")
+            .join("already_png.dae");
+        assert_eq!(
+            find_missing_references(&mesh).unwrap(),
+            vec![String::from("test_diffuse.png")]
+        );
+    }
+
+    #[test]
+    fn it_resolves_relative_references() {
+        let mesh = Path::new("tests")
+            .join("mesh_update")
+            .join("resolved")
+            .join("meshes")
+            .join("resolved.dae");
+        assert!(find_missing_references(&mesh).unwrap().is_empty());
+    }
+}
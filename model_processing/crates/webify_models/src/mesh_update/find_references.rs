@@ -0,0 +1,73 @@
+//! Find the values of a mesh, SDF or MTL material that could be paths to other files
+
+use std::{
+    io::{self, BufRead, Error},
+    path::Path,
+    result::Result,
+};
+
+use crate::mesh_update::file_name;
+use crate::xml::edit_text_nodes_streaming;
+
+/// Extensions of the files that can hold texture or mesh references
+pub const REFERENCE_FILE_EXTENSIONS: &[&str] = &["dae", "sdf", "world", "mtl"];
+
+/// Every value in the file that could be a path: the text of XML elements for DAE
+/// and SDF files, and the file of map statements for MTL materials. The contents
+/// are streamed, so large worlds never have to be loaded whole.
+pub fn find_references(file: &Path, contents: impl BufRead) -> Result<Vec<String>, Error> {
+    let mut references: Vec<String> = Vec::new();
+    let mut add = |reference: &str| {
+        let reference = reference.trim();
+        if Path::new(file_name(reference)).extension().is_some()
+            && !references.iter().any(|r| r == reference)
+        {
+            references.push(reference.to_string());
+        }
+    };
+
+    if file.extension().and_then(|e| e.to_str()) == Some("mtl") {
+        for line in contents.lines() {
+            let line = line?;
+            let mut tokens = line.split_whitespace();
+            let statement = tokens.next().unwrap_or("");
+            if statement.starts_with("map_")
+                || ["bump", "disp", "decal", "refl", "norm"].contains(&statement)
+            {
+                if let Some(reference) = tokens.last() {
+                    add(reference);
+                }
+            }
+        }
+    } else {
+        edit_text_nodes_streaming(contents, io::empty(), io::sink(), |text| {
+            add(text);
+            None
+        })?;
+    }
+
+    Ok(references)
+}
+
+#[cfg(test)]
+mod find_references_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_xml_references() {
+        let contents = "<mesh>\n  <uri>meshes/lamp.dae</uri>\n  <scale>1 1 1</scale>\n</mesh>";
+        assert_eq!(
+            find_references(Path::new("model.sdf"), contents.as_bytes()).unwrap(),
+            vec![String::from("meshes/lamp.dae")]
+        );
+    }
+
+    #[test]
+    fn it_finds_mtl_map_references() {
+        let contents = "newmtl wood\nKd 1 1 1\nmap_Kd -s 1 1 Wood.JPG\nbump wood_normal.png\n";
+        assert_eq!(
+            find_references(Path::new("lamp.mtl"), contents.as_bytes()).unwrap(),
+            vec![String::from("Wood.JPG"), String::from("wood_normal.png")]
+        );
+    }
+}
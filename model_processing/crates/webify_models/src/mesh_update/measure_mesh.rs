@@ -0,0 +1,206 @@
+//! Count the triangles, draw calls and nodes of a DAE mesh
+
+use std::{collections::HashMap, fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::mesh_update::MeshComplexity;
+
+/// Primitive elements of a COLLADA mesh, each drawn with a call of its own
+const PRIMITIVES: [&[u8]; 7] = [
+    b"triangles",
+    b"polylist",
+    b"polygons",
+    b"trifans",
+    b"tristrips",
+    b"lines",
+    b"linestrips",
+];
+
+/// Triangles and primitive groups of one `<geometry>`
+#[derive(Debug, Clone, Copy, Default)]
+struct Geometry {
+    triangles: u64,
+    primitives: u64,
+}
+
+/// Primitive element being read
+#[derive(Debug, Default)]
+struct Primitive {
+    kind: Vec<u8>,
+    count: u64,
+    /// Indices per vertex, one more than the largest input offset
+    stride: u64,
+    triangles: u64,
+}
+
+/// Count what the DAE mesh costs to render. Geometries are counted once per
+/// `<instance_geometry>` of the visual scene, or once each when the file has no
+/// scene. Polygons, strips and fans are counted as the triangles they're split into.
+pub fn measure_mesh(mesh: &Path) -> Result<MeshComplexity, Error> {
+    let contents = fs::read_to_string(mesh)?;
+    let mut reader = Reader::from_str(&contents);
+    reader.config_mut().trim_text(true);
+
+    let mut geometries: HashMap<String, Geometry> = HashMap::new();
+    let mut instances: Vec<String> = Vec::new();
+    let mut nodes = 0;
+    let mut geometry: Option<(String, Geometry)> = None;
+    let mut primitive: Option<Primitive> = None;
+    let mut text_of: Option<Vec<u8>> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::other(format!("Failed to parse {:?}: {:?}", mesh, e)))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let name = e.local_name().as_ref().to_vec();
+                match name.as_slice() {
+                    b"geometry" => geometry = Some((attribute(e, b"id"), Geometry::default())),
+                    b"node" => nodes += 1,
+                    b"instance_geometry" => {
+                        instances.push(attribute(e, b"url").trim_start_matches('#').to_string())
+                    }
+                    b"input" => {
+                        if let Some(primitive) = primitive.as_mut() {
+                            let offset = attribute(e, b"offset").parse().unwrap_or(0);
+                            primitive.stride = primitive.stride.max(offset + 1);
+                        }
+                    }
+                    b"vcount" | b"p" if primitive.is_some() => text_of = Some(name.clone()),
+                    kind if PRIMITIVES.contains(&kind) && geometry.is_some() => {
+                        primitive = Some(Primitive {
+                            kind: name.clone(),
+                            count: attribute(e, b"count").parse().unwrap_or(0),
+                            ..Primitive::default()
+                        });
+                    }
+                    _ => (),
+                }
+                if matches!(event, Event::Empty(_)) {
+                    end_element(&name, &mut geometry, &mut primitive, &mut geometries);
+                    text_of = None;
+                }
+            }
+            Event::Text(t) => {
+                if let (Some(element), Some(primitive)) = (&text_of, primitive.as_mut()) {
+                    let text = t.unescape().map_err(|e| Error::other(e.to_string()))?;
+                    let values = text.split_whitespace();
+                    match (element.as_slice(), primitive.kind.as_slice()) {
+                        // Vertices of each polygon
+                        (b"vcount", b"polylist") => {
+                            primitive.triangles += values
+                                .filter_map(|count| count.parse::<u64>().ok())
+                                .map(|count| count.saturating_sub(2))
+                                .sum::<u64>();
+                        }
+                        // One polygon, strip or fan per <p>
+                        (b"p", b"polygons") | (b"p", b"trifans") | (b"p", b"tristrips") => {
+                            let vertices = values.count() as u64 / primitive.stride.max(1);
+                            primitive.triangles += vertices.saturating_sub(2);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            Event::End(ref e) => {
+                let name = e.local_name().as_ref().to_vec();
+                end_element(&name, &mut geometry, &mut primitive, &mut geometries);
+                text_of = None;
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    let mut complexity = MeshComplexity {
+        nodes,
+        ..MeshComplexity::default()
+    };
+    let counted: Vec<&Geometry> = if instances.is_empty() {
+        geometries.values().collect()
+    } else {
+        instances
+            .iter()
+            .filter_map(|id| geometries.get(id))
+            .collect()
+    };
+    for geometry in counted {
+        complexity.triangles += geometry.triangles;
+        complexity.draw_calls += geometry.primitives;
+    }
+
+    Ok(complexity)
+}
+
+/// Add the primitive or geometry the element closes to its parent
+fn end_element(
+    name: &[u8],
+    geometry: &mut Option<(String, Geometry)>,
+    primitive: &mut Option<Primitive>,
+    geometries: &mut HashMap<String, Geometry>,
+) {
+    if name == b"geometry" {
+        if let Some((id, geometry)) = geometry.take() {
+            geometries.insert(id, geometry);
+        }
+    } else if primitive.as_ref().map(|p| p.kind.as_slice()) == Some(name) {
+        let primitive = primitive.take().unwrap();
+        if let Some((_, geometry)) = geometry.as_mut() {
+            geometry.primitives += 1;
+            geometry.triangles += match primitive.kind.as_slice() {
+                b"triangles" => primitive.count,
+                b"lines" | b"linestrips" => 0,
+                _ => primitive.triangles,
+            };
+        }
+    }
+}
+
+/// Value of the attribute, empty when it's missing
+fn attribute(element: &BytesStart, name: &[u8]) -> String {
+    element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.as_ref() == name)
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod measure_mesh_tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_each_geometry_instance() {
+        let mesh = Path::new("tests")
+            .join("mesh_update")
+            .join("complexity")
+            .join("meshes")
+            .join("shelf.dae");
+        assert_eq!(
+            measure_mesh(&mesh).unwrap(),
+            MeshComplexity {
+                // Two boxes of 12, a quad and a triangle, and a pentagon
+                triangles: 12 * 2 + 3 + 3,
+                draw_calls: 4,
+                nodes: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn it_counts_nothing_without_geometry() {
+        let mesh = Path::new("tests")
+            .join("mesh_update")
+            .join("test")
+            .join("meshes")
+            .join("test.dae");
+        assert_eq!(measure_mesh(&mesh).unwrap(), MeshComplexity::default());
+    }
+}
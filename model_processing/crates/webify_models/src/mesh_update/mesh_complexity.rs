@@ -0,0 +1,16 @@
+//! Structure that records what a mesh costs to render
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a DAE mesh costs to render, counted over its visual scene so geometries
+/// instanced several times count each time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MeshComplexity {
+    /// Triangles once polygons, strips and fans are triangulated
+    pub triangles: u64,
+    /// Primitive groups drawn, one per material of each geometry instance
+    pub draw_calls: u64,
+    /// Nodes of the visual scene
+    pub nodes: u64,
+}
@@ -1,10 +1,36 @@
 //! After renaming the files, we also need to update the references inside the meshes
 //! to make sure that they are pointing at the right spot.
 
+mod find_missing_references;
+mod find_references;
+mod measure_mesh;
+mod mesh_complexity;
 mod process;
+mod redirect_references;
 mod rename_image_references;
+mod repair_absolute_references;
+mod repair_reference_case;
+mod repair_reference_format;
+mod repair_replaced_references;
+mod replace_references;
+mod resolve_case_insensitive;
 mod scan_dir_for_meshes;
+mod scan_dir_for_reference_files;
 
+pub use self::find_missing_references::find_missing_references;
+pub use self::find_references::{find_references, REFERENCE_FILE_EXTENSIONS};
+pub use self::measure_mesh::measure_mesh;
+pub use self::mesh_complexity::MeshComplexity;
 pub use self::process::process;
+pub use self::redirect_references::redirect_references;
 pub use self::rename_image_references::rename_image_references;
+pub use self::repair_absolute_references::{
+    file_name, is_foreign_path, relative_path, repair_absolute_references,
+};
+pub use self::repair_reference_case::repair_reference_case;
+pub use self::repair_reference_format::repair_reference_format;
+pub use self::repair_replaced_references::repair_replaced_references;
+pub use self::replace_references::replace_references;
+pub use self::resolve_case_insensitive::resolve_case_insensitive;
 pub use self::scan_dir_for_meshes::scan_dir_for_meshes;
+pub use self::scan_dir_for_reference_files::scan_dir_for_reference_files;
@@ -1,25 +1,135 @@
 //! Orchestrator to run the mesh updater
 
-use std::path::Path;
+use std::{fs, path::Path};
 
 use crate::cli::create_progress_bar;
-use crate::mesh_update::{rename_image_references, scan_dir_for_meshes};
+use crate::config::WebifyConfig;
+use crate::image_processing::texture_extensions;
+use crate::mesh_update::{
+    find_missing_references, measure_mesh, rename_image_references, repair_absolute_references,
+    repair_reference_case, repair_reference_format, scan_dir_for_meshes,
+    scan_dir_for_reference_files,
+};
+use crate::model::find_model_dir;
+use crate::options::RunOptions;
+use crate::pipeline::CancellationToken;
+use crate::report::{run_step, ErrorCode, MeshStats, RunReport, Warning};
+use crate::walk::prioritize;
 
 /// Orchestrator to run the mesh updater
-pub fn process(dir: &Path) -> std::result::Result<(), std::io::Error> {
-    let meshes = scan_dir_for_meshes(dir).unwrap();
+pub fn process(
+    dir: &Path,
+    options: &RunOptions,
+    config: &WebifyConfig,
+    cancellation_token: &CancellationToken,
+    report: &mut RunReport,
+) -> std::result::Result<(), std::io::Error> {
+    // Absolute paths from the original author's machine first, so the rest of the
+    // update works on references relative to the model
+    let mut files = scan_dir_for_reference_files(dir)?;
+    prioritize(&mut files, dir, &options.first, |file| file);
+    for file in &files {
+        if cancellation_token.is_cancelled() {
+            return Ok(());
+        }
+
+        if let Some(unresolved) = run_step(
+            "Reference Repair",
+            ErrorCode::ReferenceRepair,
+            file,
+            options,
+            report,
+            || {
+                let unresolved = repair_absolute_references(file, dir)?;
+                repair_reference_case(file)?;
+                Ok(unresolved)
+            },
+        )? {
+            for reference in unresolved {
+                report.warnings.push(Warning::new(
+                    ErrorCode::UnresolvedReference,
+                    "Reference Repair",
+                    file.clone(),
+                    format!("unresolvable absolute reference {}", reference),
+                ));
+            }
+        }
+    }
+
+    let extensions = texture_extensions(options, config);
+    let mut meshes = scan_dir_for_meshes(dir)?;
+    prioritize(&mut meshes, dir, &options.first, |mesh| mesh);
     let mesh_bar = create_progress_bar(meshes.len() as u64);
 
     mesh_bar.set_prefix("Mesh Update");
     for mesh in meshes {
+        if cancellation_token.is_cancelled() {
+            mesh_bar.abandon_with_message("Mesh update cancelled.");
+            return Ok(());
+        }
+
         mesh_bar.inc(1);
         mesh_bar.set_message(&format!("Updating {:?}...", &mesh));
-        rename_image_references(&mesh)?;
+        if run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            &mesh,
+            options,
+            report,
+            || {
+                rename_image_references(&mesh, dir, &extensions)?;
+                repair_reference_format(&mesh)
+            },
+        )?
+        .is_none()
+        {
+            continue;
+        }
+
+        for reference in find_missing_references(&mesh)? {
+            report.warnings.push(Warning::new(
+                ErrorCode::MissingReference,
+                "Mesh Update",
+                mesh.clone(),
+                format!("missing texture reference {}", reference),
+            ));
+        }
+
+        let complexity = run_step(
+            "Mesh Measure",
+            ErrorCode::Measure,
+            &mesh,
+            options,
+            report,
+            || measure_mesh(&mesh),
+        )?
+        .unwrap_or_default();
+        report.meshes.push(MeshStats {
+            model: find_model_dir(&mesh, dir).ok(),
+            file_bytes: fs::metadata(&mesh)?.len(),
+            complexity,
+            path: mesh,
+        });
     }
 
     // TODO: Update image references in material, txt, and sdf
 
     mesh_bar.finish_with_message("Meshes webified!");
 
+    // Textures encoded to another format, for references outside of meshes
+    for file in &files {
+        if cancellation_token.is_cancelled() {
+            return Ok(());
+        }
+        run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            file,
+            options,
+            report,
+            || repair_reference_format(file),
+        )?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,103 @@
+//! Point the references to files that are going away at the files replacing them
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Error},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::{model_uri_name, resolve_reference};
+use crate::mesh_update::{find_references, relative_path, replace_references};
+
+/// Rewrite the references of the file to the files of `redirects`, keyed by their
+/// canonicalized path, to the files replacing them, like the copy of a duplicate
+/// texture that is kept. The files must still be there for their references to
+/// resolve. `model://` URIs stay URIs when the replacement belongs to a model,
+/// other references become relative to the file. Returns whether the file changed.
+pub fn redirect_references(
+    file: &Path,
+    redirects: &BTreeMap<PathBuf, PathBuf>,
+    models: &BTreeMap<String, PathBuf>,
+) -> Result<bool, Error> {
+    if redirects.is_empty() {
+        return Ok(false);
+    }
+    let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut replacements = BTreeMap::new();
+    for reference in find_references(file, BufReader::new(File::open(file)?))? {
+        let target = match resolve_reference(file, &reference, models)
+            .and_then(|resolved| resolved.canonicalize().ok())
+            .and_then(|resolved| redirects.get(&resolved))
+        {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let model = models
+            .iter()
+            .find(|(_, model_dir)| target.starts_with(model_dir));
+        let replacement = match (model_uri_name(&reference), model) {
+            (Some(_), Some((name, model_dir))) => {
+                format!("model://{}/{}", name, relative_path(model_dir, target))
+            }
+            _ => relative_path(file_dir, target),
+        };
+        replacements.insert(reference, replacement);
+    }
+
+    replace_references(file, &replacements)
+}
+
+#[cfg(test)]
+mod redirect_references_tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn it_points_references_at_the_canonical_copy() -> Result<(), Error> {
+        let library = Path::new("tests")
+            .join("mesh_update")
+            .join("test_run_redirect_references");
+        let textures = |model: &str| library.join(model).join("materials").join("textures");
+        for model in ["desk", "lamp"] {
+            fs::create_dir_all(textures(model))?;
+            fs::create_dir_all(library.join(model).join("meshes"))?;
+        }
+        fs::write(textures("desk").join("oak.png"), "oak")?;
+        fs::write(textures("lamp").join("wood.png"), "oak")?;
+        let mesh = library.join("lamp").join("meshes").join("lamp.dae");
+        fs::write(
+            &mesh,
+            "<COLLADA><library_images><image><init_from>../materials/textures/wood.png</init_from></image></library_images></COLLADA>",
+        )?;
+        let sdf = library.join("lamp").join("model.sdf");
+        fs::write(
+            &sdf,
+            "<sdf><model><link><visual><material><pbr><metal><albedo_map>model://lamp/materials/textures/wood.png</albedo_map></metal></pbr></material></visual></link></model></sdf>",
+        )?;
+
+        let mut duplicates = BTreeMap::new();
+        duplicates.insert(
+            textures("lamp").join("wood.png").canonicalize()?,
+            textures("desk").join("oak.png"),
+        );
+        let mut models = BTreeMap::new();
+        models.insert(String::from("desk"), library.join("desk"));
+        models.insert(String::from("lamp"), library.join("lamp"));
+
+        assert!(redirect_references(&mesh, &duplicates, &models)?);
+        assert!(fs::read_to_string(&mesh)?
+            .contains("<init_from>../../desk/materials/textures/oak.png</init_from>"));
+        assert!(redirect_references(&sdf, &duplicates, &models)?);
+        assert!(fs::read_to_string(&sdf)?
+            .contains("<albedo_map>model://desk/materials/textures/oak.png</albedo_map>"));
+        assert!(!redirect_references(&mesh, &duplicates, &models)?);
+
+        fs::remove_dir_all(library)?;
+        Ok(())
+    }
+}
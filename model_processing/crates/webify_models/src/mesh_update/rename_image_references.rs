@@ -4,40 +4,133 @@
 use std::path::PathBuf;
 use std::{
     fs::{self},
-    path::Path,
+    path::{Component, Path},
 };
 
 use aho_corasick::AhoCorasickBuilder;
 
-/// Orchestrator to rename image references in a DAE mesh
-pub fn rename_image_references(mesh: &PathBuf) -> std::result::Result<(), std::io::Error> {
-    let result = find_and_rename_image_references(mesh)?;
-    let final_result = update_texture_path(result)?;
+use crate::image_processing::is_decodable;
+use crate::mesh_update::resolve_case_insensitive;
+use crate::model::{locate_file, shared_textures_dir, FileLocation};
+
+/// Orchestrator to rename image references in a DAE mesh, for textures with one of
+/// the scanned extensions
+pub fn rename_image_references(
+    mesh: &PathBuf,
+    base_path: &Path,
+    extensions: &[String],
+) -> std::result::Result<(), std::io::Error> {
+    let result = find_and_rename_image_references(mesh, extensions)?;
+    let resolve_reference = texture_reference_resolver(mesh, base_path)?;
+    let final_result = update_texture_path(result, resolve_reference)?;
     fs::write(mesh, final_result)?;
 
     Ok(())
 }
 
-/// Rename all occurences of supported image types to PNG
-fn find_and_rename_image_references(mesh: &PathBuf) -> std::result::Result<String, std::io::Error> {
-    let patterns = &[
-        ".tga", "_tga", ".jpg", "_jpg", ".jpeg", "_jpeg", ".gif", "_gif",
-    ];
+/// Build the function that turns a texture file name into the reference the mesh
+/// should use: the model's own materials/textures when the texture is there, or the
+/// collection's shared textures when the model belongs to a collection sharing it
+fn texture_reference_resolver(
+    mesh: &Path,
+    base_path: &Path,
+) -> std::result::Result<impl Fn(&str) -> String, std::io::Error> {
+    let mesh_dir = mesh.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+    let model_dir = match locate_file(mesh, base_path)? {
+        FileLocation::Model(model_dir) => model_dir,
+        FileLocation::Collection(collection_dir) => collection_dir,
+    };
+    let model_textures_dir = model_dir.join("materials").join("textures");
+    let model_prefix = relative_prefix(&mesh_dir, &model_dir)
+        .join("materials")
+        .join("textures");
+
+    // Collections keep their shared textures next to their models
+    let shared = model_dir
+        .parent()
+        .filter(|collection_dir| collection_dir.starts_with(base_path))
+        .map(|collection_dir| {
+            let shared_dir = shared_textures_dir(collection_dir);
+            let shared_prefix = relative_prefix(&mesh_dir, collection_dir)
+                .join(shared_dir.strip_prefix(collection_dir).unwrap());
+            (shared_dir, shared_prefix)
+        });
+
+    Ok(move |texture_name: &str| {
+        let file_name = Path::new(texture_name)
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| texture_name.to_string());
+
+        // Use the name as it is on disk, references often get the case wrong
+        let on_disk = |dir: &Path| {
+            resolve_case_insensitive(&dir.join(&file_name))
+                .filter(|path| path.is_file())
+                .and_then(|path| path.file_name().map(|f| f.to_string_lossy().to_string()))
+        };
+
+        match (on_disk(&model_textures_dir), &shared) {
+            (Some(name), _) => model_prefix.join(name).to_string_lossy().to_string(),
+            (None, Some((shared_dir, shared_prefix))) if on_disk(shared_dir).is_some() => {
+                let name = on_disk(shared_dir).unwrap();
+                shared_prefix.join(name).to_string_lossy().to_string()
+            }
+            _ => model_prefix.join(&file_name).to_string_lossy().to_string(),
+        }
+    })
+}
+
+/// Relative path that climbs from `dir` back up to its ancestor `root`, e.g. `../..`
+fn relative_prefix(dir: &Path, root: &Path) -> PathBuf {
+    let depth = dir
+        .strip_prefix(root)
+        .map(|relative| {
+            relative
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .count()
+        })
+        .unwrap_or(1);
+
+    (0..depth).map(|_| "..").collect()
+}
+
+/// Rename all occurences of the image types converted to PNG
+fn find_and_rename_image_references(
+    mesh: &PathBuf,
+    extensions: &[String],
+) -> std::result::Result<String, std::io::Error> {
+    let mut patterns = Vec::new();
+    let mut replacements = Vec::new();
+    for extension in extensions.iter().filter(|e| is_converted(e)) {
+        patterns.push(format!(".{}", extension));
+        replacements.push(".png");
+        patterns.push(format!("_{}", extension));
+        replacements.push("_png");
+    }
     let f = fs::read_to_string(mesh)?;
 
-    let ac = AhoCorasickBuilder::new().build(patterns);
-    let result = ac.replace_all(
-        &f,
-        &[
-            ".png", "_png", ".png", "_png", ".png", "_png", ".png", "_png",
-        ],
-    );
+    // Extensions like `.JPG` are just as common as `.jpg`
+    let ac = AhoCorasickBuilder::new()
+        .ascii_case_insensitive(true)
+        .build(&patterns);
+    let result = ac.replace_all(&f, &replacements);
 
     Ok(result)
 }
 
-/// Update all texture paths to point to the relative path for textures/materials
-fn update_texture_path(contents: String) -> std::result::Result<String, std::io::Error> {
+/// Whether textures with the extension end up converted to PNG. Formats the
+/// conversion can't decode are left as they are.
+fn is_converted(extension: &str) -> bool {
+    extension != "png" && is_decodable(extension)
+}
+
+/// Update all texture paths to point to the relative path for textures/materials,
+/// as worked out by `resolve_reference` from the texture name
+fn update_texture_path(
+    contents: String,
+    resolve_reference: impl Fn(&str) -> String,
+) -> std::result::Result<String, std::io::Error> {
     let texture_element = &["<init_from>", "</init_from>"];
     let ac = AhoCorasickBuilder::new().build(texture_element);
     let texture_path = Path::new("materials").join("textures");
@@ -55,18 +148,9 @@ fn update_texture_path(contents: String) -> std::result::Result<String, std::io:
                 let texture_name = &line[start..mat.start()];
                 // Prefix image reference with relative directory path to textures
                 if texture_name.ends_with(".png")
-                    && !texture_name.contains(&texture_path.to_str().unwrap())
+                    && !texture_name.contains(texture_path.to_str().unwrap())
                 {
-                    // TODO: Properly find the root path of the mesh, rather than assuming
-                    new_line = line.replace(
-                        texture_name,
-                        Path::new("..")
-                            .join("materials")
-                            .join("textures")
-                            .join(texture_name)
-                            .to_str()
-                            .unwrap(),
-                    );
+                    new_line = line.replace(texture_name, &resolve_reference(texture_name));
                 }
             }
         }
@@ -92,12 +176,12 @@ mod rename_image_references_tests {
         let example_path = Path::new("tests").join("mesh_update").join("test");
         let destination_path = Path::new("tests").join("mesh_update").join(test_run_id);
 
-        fs::create_dir_all(&destination_path.join("meshes"))?;
-        fs::create_dir_all(&destination_path.join("materials").join("textures"))?;
+        fs::create_dir_all(destination_path.join("meshes"))?;
+        fs::create_dir_all(destination_path.join("materials").join("textures"))?;
 
         fs::copy(
             example_path.join("meshes").join("test.dae"),
-            &destination_path.join("meshes").join("test.dae"),
+            destination_path.join("meshes").join("test.dae"),
         )?;
 
         Ok(())
@@ -114,14 +198,18 @@ mod rename_image_references_tests {
     fn it_renamed_and_updated_texture_references() -> std::result::Result<(), std::io::Error> {
         let test_run_id = "rename_and_update_texture_1";
 
-        setup(&test_run_id)?;
+        setup(test_run_id)?;
 
         let destination_path = Path::new("tests")
             .join("mesh_update")
-            .join(&test_run_id)
+            .join(test_run_id)
             .join("meshes")
             .join("test.dae");
-        rename_image_references(&destination_path)?;
+        rename_image_references(
+            &destination_path,
+            &Path::new("tests").join("mesh_update"),
+            &default_extensions(),
+        )?;
 
         let mut file = File::open(destination_path)?;
         let mut contents = String::new();
@@ -129,12 +217,48 @@ mod rename_image_references_tests {
 
         assert_eq!(contents, "<!-- This is not a valid DAE, just a test file -->\n\n<image id=\"Test_Diffuse_png\">\n  <init_from>../materials/textures/test_diffuse.png</init_from>\n</image>\n");
 
-        teardown(&test_run_id)?;
+        teardown(test_run_id)?;
 
         Ok(())
     }
 }
 
+#[cfg(test)]
+mod texture_reference_resolver_tests {
+    use super::*;
+
+    #[test]
+    fn it_points_at_collection_shared_textures() -> std::result::Result<(), std::io::Error> {
+        let base_path = Path::new("tests").join("model").join("collection");
+        let mesh = base_path.join("lamp").join("meshes").join("lamp.dae");
+
+        let resolve_reference = texture_reference_resolver(&mesh, &base_path)?;
+
+        assert_eq!(
+            resolve_reference("wood.png"),
+            "../../media/materials/textures/wood.png"
+        );
+        assert_eq!(
+            resolve_reference("Wood.png"),
+            "../../media/materials/textures/wood.png"
+        );
+        assert_eq!(
+            resolve_reference("shade.png"),
+            "../materials/textures/shade.png"
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn default_extensions() -> Vec<String> {
+    crate::image_processing::TEXTURE_IMAGE_TYPES
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+
 #[cfg(test)]
 mod find_and_rename_image_references_tests {
     use super::*;
@@ -146,11 +270,21 @@ mod find_and_rename_image_references_tests {
             .join("test")
             .join("meshes")
             .join("test.dae");
-        let result = find_and_rename_image_references(&destination_path)?;
+        let result = find_and_rename_image_references(&destination_path, &default_extensions())?;
         assert_eq!(result, "<!-- This is not a valid DAE, just a test file -->\n\n<image id=\"Test_Diffuse_png\">\n  <init_from>test_diffuse.png</init_from>\n</image>\n");
 
         Ok(())
     }
+
+    #[test]
+    fn it_only_renames_converted_extensions() {
+        assert!(is_converted("jpg"));
+        assert!(is_converted("bmp"));
+        assert!(is_converted("psd"));
+        assert!(!is_converted("png"));
+        assert!(is_converted("tif"));
+        assert!(!is_converted("ktx2"));
+    }
 }
 
 #[cfg(test)]
@@ -172,7 +306,9 @@ mod update_texture_path_tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        let result = update_texture_path(contents)?;
+        let result = update_texture_path(contents, |texture_name| {
+            format!("../materials/textures/{}", texture_name)
+        })?;
         assert_eq!(result, "<!-- This is not a valid DAE, just a test file -->\n\n<image id=\"Test_Diffuse_png\">\n  <init_from>../materials/textures/test_diffuse.png</init_from>\n</image>\n");
 
         Ok(())
@@ -0,0 +1,187 @@
+//! Rewrite absolute paths left over from the original author's machine to relative
+//! ones, in meshes, SDFs and MTL materials
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufReader, Error},
+    path::{Component, Path, PathBuf},
+    result::Result,
+};
+
+use crate::mesh_update::{find_references, replace_references};
+use crate::model::{locate_file, FileLocation};
+
+/// Rewrite every absolute or foreign reference in the file to the relative path of
+/// the file with the same name inside its model, and return the references that
+/// couldn't be resolved, which are left untouched
+pub fn repair_absolute_references(file: &Path, base_path: &Path) -> Result<Vec<String>, Error> {
+    let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+    let model_dir = match locate_file(file, base_path)? {
+        FileLocation::Model(model_dir) => model_dir,
+        FileLocation::Collection(collection_dir) => collection_dir,
+    };
+
+    let mut replacements = BTreeMap::new();
+    let mut unresolved = Vec::new();
+    for reference in find_references(file, BufReader::new(File::open(file)?))? {
+        if !is_foreign_path(&reference) {
+            continue;
+        }
+
+        match find_file_by_name(&model_dir, file_name(&reference))? {
+            Some(target) => {
+                let relative = relative_path(file_dir, &target);
+                replacements.insert(reference, relative);
+            }
+            None => unresolved.push(reference),
+        }
+    }
+
+    if !replacements.is_empty() {
+        replace_references(file, &replacements)?;
+    }
+
+    Ok(unresolved)
+}
+
+/// Whether the reference is an absolute path, or a path from another machine
+pub fn is_foreign_path(reference: &str) -> bool {
+    let path = reference.trim_start_matches("file://");
+    let bytes = path.as_bytes();
+    let windows_drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+
+    path.starts_with('/') || path.starts_with('\\') || path.starts_with("~/") || windows_drive
+}
+
+/// Last segment of a reference, whichever separator it uses
+pub fn file_name(reference: &str) -> &str {
+    reference.rsplit(['/', '\\']).next().unwrap_or(reference)
+}
+
+/// Find a file by name anywhere inside the directory, picking the first one in path
+/// order when there are several
+fn find_file_by_name(dir: &Path, name: &str) -> Result<Option<PathBuf>, Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, Error>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if let Some(found) = find_file_by_name(&path, name)? {
+                return Ok(Some(found));
+            }
+        } else if path.file_name().and_then(|f| f.to_str()) == Some(name) {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Path of `target` relative to `from_dir`, with forward slashes as loaders expect
+pub fn relative_path(from_dir: &Path, target: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut segments: Vec<String> = (common..from.len()).map(|_| String::from("..")).collect();
+    segments.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod repair_absolute_references_tests {
+    use super::*;
+
+    fn setup(test_run_id: &str) -> Result<PathBuf, Error> {
+        let example_path = Path::new("tests")
+            .join("mesh_update")
+            .join("absolute_paths")
+            .join("model");
+        let destination_path = Path::new("tests")
+            .join("mesh_update")
+            .join("absolute_paths")
+            .join(test_run_id);
+
+        for file in &[
+            Path::new("model.config").to_path_buf(),
+            Path::new("model.sdf").to_path_buf(),
+            Path::new("meshes").join("absolute.dae"),
+            Path::new("meshes").join("absolute.mtl"),
+            Path::new("materials").join("textures").join("wood.png"),
+        ] {
+            fs::create_dir_all(destination_path.join(file).parent().unwrap())?;
+            fs::copy(example_path.join(file), destination_path.join(file))?;
+        }
+
+        Ok(destination_path)
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(
+            Path::new("tests")
+                .join("mesh_update")
+                .join("absolute_paths")
+                .join(test_run_id),
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_repairs_absolute_references() -> Result<(), Error> {
+        let test_run_id = "test_run_repair_absolute_references";
+        let model_dir = setup(test_run_id)?;
+        let base_path = Path::new("tests")
+            .join("mesh_update")
+            .join("absolute_paths");
+
+        let mesh = model_dir.join("meshes").join("absolute.dae");
+        assert_eq!(
+            repair_absolute_references(&mesh, &base_path)?,
+            vec![String::from("/home/author/textures/lost.png")]
+        );
+        let contents = fs::read_to_string(&mesh)?;
+        assert!(contents.contains("<init_from>../materials/textures/wood.png</init_from>"));
+        assert!(contents.contains("<init_from>/home/author/textures/lost.png</init_from>"));
+
+        let material = model_dir.join("meshes").join("absolute.mtl");
+        assert!(repair_absolute_references(&material, &base_path)?.is_empty());
+        assert!(fs::read_to_string(&material)?.contains("map_Kd ../materials/textures/wood.png"));
+
+        let sdf = model_dir.join("model.sdf");
+        assert!(repair_absolute_references(&sdf, &base_path)?.is_empty());
+        assert!(fs::read_to_string(&sdf)?.contains("<uri>meshes/absolute.dae</uri>"));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod is_foreign_path_tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_foreign_paths() {
+        assert!(is_foreign_path("/home/author/wood.png"));
+        assert!(is_foreign_path("file:///home/author/wood.png"));
+        assert!(is_foreign_path("C:\\Users\\author\\wood.png"));
+        assert!(is_foreign_path("d:/textures/wood.png"));
+        assert!(is_foreign_path("\\\\share\\textures\\wood.png"));
+
+        assert!(!is_foreign_path("../materials/textures/wood.png"));
+        assert!(!is_foreign_path("model://absolute/meshes/absolute.dae"));
+        assert!(!is_foreign_path("wood.png"));
+    }
+}
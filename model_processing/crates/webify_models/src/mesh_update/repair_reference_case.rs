@@ -0,0 +1,114 @@
+//! Correct references whose case doesn't match the file on disk
+
+use std::{
+    fs::File,
+    io::{BufReader, Error},
+    path::Path,
+    result::Result,
+};
+
+use crate::mesh_update::{
+    find_references, is_foreign_path, replace_references, resolve_case_insensitive,
+};
+
+/// Rewrite every relative reference in the file that only resolves when ignoring
+/// case, like `Wood.JPG` for `wood.jpg`, to the name on disk. Works on Windows, but
+/// not on Linux or the web. Returns the corrections made, as `(from, to)`.
+pub fn repair_reference_case(file: &Path) -> Result<Vec<(String, String)>, Error> {
+    let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut corrections = Vec::new();
+    for reference in find_references(file, BufReader::new(File::open(file)?))? {
+        if reference.contains("://") || is_foreign_path(&reference) {
+            continue;
+        }
+
+        let reference_path = file_dir.join(reference.replace('\\', "/"));
+        if reference_path.exists() {
+            continue;
+        }
+        if let Some(resolved) = resolve_case_insensitive(&reference_path) {
+            // Only the case differs, so the corrected reference keeps the same
+            // length and separators, taking its letters from the path on disk
+            let resolved = resolved.to_string_lossy();
+            let suffix = match resolved
+                .len()
+                .checked_sub(reference.len())
+                .and_then(|start| resolved.get(start..))
+            {
+                Some(suffix) if suffix.eq_ignore_ascii_case(&reference.replace('\\', "/")) => {
+                    suffix
+                }
+                _ => continue,
+            };
+            let corrected: String = reference
+                .chars()
+                .zip(suffix.chars())
+                .map(|(r, s)| if r == '\\' { r } else { s })
+                .collect();
+            corrections.push((reference, corrected));
+        }
+    }
+
+    if !corrections.is_empty() {
+        let replacements = corrections.iter().cloned().collect();
+        replace_references(file, &replacements)?;
+    }
+
+    Ok(corrections)
+}
+
+#[cfg(test)]
+mod repair_reference_case_tests {
+    use super::*;
+
+    use std::{fs, path::PathBuf};
+
+    fn setup(test_run_id: &str) -> Result<PathBuf, Error> {
+        let destination_path = Path::new("tests").join("mesh_update").join(test_run_id);
+        fs::create_dir_all(destination_path.join("meshes"))?;
+        fs::create_dir_all(destination_path.join("materials").join("textures"))?;
+        fs::write(
+            destination_path
+                .join("materials")
+                .join("textures")
+                .join("wood.png"),
+            "not really a png",
+        )?;
+
+        Ok(destination_path)
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(Path::new("tests").join("mesh_update").join(test_run_id))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_corrects_the_case_of_references() -> Result<(), Error> {
+        let test_run_id = "test_run_repair_reference_case";
+        let model_dir = setup(test_run_id)?;
+
+        let material = model_dir.join("meshes").join("lamp.mtl");
+        fs::write(
+            &material,
+            "newmtl wood\nmap_Kd ../Materials/Textures/Wood.PNG\nmap_Ks ../materials/textures/DarkWood.PNG\n",
+        )?;
+
+        assert_eq!(
+            repair_reference_case(&material)?,
+            vec![(
+                String::from("../Materials/Textures/Wood.PNG"),
+                String::from("../materials/textures/wood.png")
+            )]
+        );
+        assert_eq!(
+            fs::read_to_string(&material)?,
+            "newmtl wood\nmap_Kd ../materials/textures/wood.png\nmap_Ks ../materials/textures/DarkWood.PNG\n"
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Point references at the format their texture ended up in
+
+use std::{
+    fs::File,
+    io::{BufReader, Error},
+    path::Path,
+    result::Result,
+};
+
+use crate::image_processing::is_decodable;
+use crate::mesh_update::{find_references, is_foreign_path, replace_references};
+use crate::options::OutputFormat;
+
+/// Rewrite every relative texture reference in the file whose texture doesn't exist
+/// but was written in another output format, like `wood.png` for a texture that
+/// was encoded to `wood.avif`. Returns the corrections made, as `(from, to)`.
+pub fn repair_reference_format(file: &Path) -> Result<Vec<(String, String)>, Error> {
+    let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut corrections = Vec::new();
+    for reference in find_references(file, BufReader::new(File::open(file)?))? {
+        if reference.contains("://") || is_foreign_path(&reference) {
+            continue;
+        }
+
+        let reference_path = Path::new(&reference);
+        let is_texture = reference_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|extension| {
+                is_decodable(extension)
+                    || OutputFormat::ALL.iter().any(|f| f.extension() == extension)
+            });
+        if !is_texture || file_dir.join(reference.replace('\\', "/")).exists() {
+            continue;
+        }
+
+        let written = OutputFormat::ALL
+            .iter()
+            .map(|format| reference_path.with_extension(format.extension()))
+            .find(|candidate| {
+                let candidate = candidate.to_string_lossy().replace('\\', "/");
+                file_dir.join(candidate).is_file()
+            });
+        if let Some(written) = written {
+            corrections.push((reference.clone(), written.to_string_lossy().to_string()));
+        }
+    }
+
+    if !corrections.is_empty() {
+        let replacements = corrections.iter().cloned().collect();
+        replace_references(file, &replacements)?;
+    }
+
+    Ok(corrections)
+}
+
+#[cfg(test)]
+mod repair_reference_format_tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn it_points_references_at_the_written_format() -> Result<(), Error> {
+        let model_dir = Path::new("tests")
+            .join("mesh_update")
+            .join("test_run_repair_reference_format");
+        let textures_dir = model_dir.join("materials").join("textures");
+        fs::create_dir_all(model_dir.join("meshes"))?;
+        fs::create_dir_all(&textures_dir)?;
+        fs::write(textures_dir.join("wood.avif"), "not really an avif")?;
+        fs::write(textures_dir.join("metal.png"), "not really a png")?;
+
+        let mesh = model_dir.join("meshes").join("lamp.dae");
+        fs::write(
+            &mesh,
+            "<image><init_from>../materials/textures/wood.png</init_from></image>\n<image><init_from>../materials/textures/metal.png</init_from></image>\n",
+        )?;
+
+        assert_eq!(
+            repair_reference_format(&mesh)?,
+            vec![(
+                String::from("../materials/textures/wood.png"),
+                String::from("../materials/textures/wood.avif")
+            )]
+        );
+        assert!(fs::read_to_string(&mesh)?.contains("textures/wood.avif<"));
+        assert!(fs::read_to_string(&mesh)?.contains("textures/metal.png<"));
+
+        fs::remove_dir_all(model_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! Point references at the textures that replaced theirs, for runs that stop early
+
+use std::{
+    fs::File,
+    io::{BufReader, Error},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::image_processing::is_decodable;
+use crate::mesh_update::{
+    file_name, find_references, is_foreign_path, relative_path, replace_references,
+};
+use crate::model::{locate_file, shared_textures_dir, FileLocation};
+use crate::options::OutputFormat;
+
+/// Rewrite every relative texture reference in the file whose texture was removed
+/// to the texture that replaced it: the same name in one of the output formats,
+/// next to where it was or moved to the textures directory of its model or
+/// collection. References to textures still there are left alone, so a run that
+/// stopped early only points the textures it processed at their replacements.
+/// Returns the corrections made, as `(from, to)`.
+pub fn repair_replaced_references(
+    file: &Path,
+    base_path: &Path,
+) -> Result<Vec<(String, String)>, Error> {
+    let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+    let textures_dir = match locate_file(file, base_path) {
+        Ok(FileLocation::Model(model_dir)) => Some(model_dir.join("materials").join("textures")),
+        Ok(FileLocation::Collection(collection_dir)) => {
+            Some(shared_textures_dir(&collection_dir))
+        }
+        Err(_) => None,
+    };
+
+    let mut corrections = Vec::new();
+    for reference in find_references(file, BufReader::new(File::open(file)?))? {
+        if reference.contains("://") || is_foreign_path(&reference) {
+            continue;
+        }
+
+        let reference_path = PathBuf::from(reference.replace('\\', "/"));
+        let is_texture = reference_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(is_decodable);
+        if !is_texture || file_dir.join(&reference_path).exists() {
+            continue;
+        }
+
+        let name = Path::new(file_name(&reference)).to_path_buf();
+        let dirs = [
+            Some(file_dir.join(reference_path.parent().unwrap_or_else(|| Path::new("")))),
+            textures_dir.clone(),
+        ];
+        let replacement = dirs.iter().flatten().find_map(|dir| {
+            OutputFormat::ALL
+                .iter()
+                .map(|format| dir.join(name.with_extension(format.extension())))
+                .find(|candidate| candidate.is_file())
+        });
+        if let Some(replacement) = replacement {
+            corrections.push((reference.clone(), relative_path(file_dir, &replacement)));
+        }
+    }
+
+    if !corrections.is_empty() {
+        let replacements = corrections.iter().cloned().collect();
+        replace_references(file, &replacements)?;
+    }
+
+    Ok(corrections)
+}
+
+#[cfg(test)]
+mod repair_replaced_references_tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn it_points_references_at_the_replacements_only() -> Result<(), Error> {
+        let base_path = Path::new("tests")
+            .join("mesh_update")
+            .join("test_run_repair_replaced_references");
+        let model_dir = base_path.join("lamp");
+        let textures_dir = model_dir.join("materials").join("textures");
+        fs::create_dir_all(model_dir.join("meshes"))?;
+        fs::create_dir_all(&textures_dir)?;
+        fs::write(model_dir.join("model.config"), "<model/>")?;
+        fs::write(textures_dir.join("wood.png"), "converted in place")?;
+        fs::write(textures_dir.join("brass.webp"), "moved, then converted")?;
+        fs::write(model_dir.join("meshes").join("glass.jpg"), "not processed yet")?;
+
+        let mesh = model_dir.join("meshes").join("lamp.dae");
+        fs::write(
+            &mesh,
+            "<image><init_from>../materials/textures/wood.jpg</init_from></image>\n<image><init_from>../brass.jpg</init_from></image>\n<image><init_from>glass.jpg</init_from></image>\n<image><init_from>missing.jpg</init_from></image>\n",
+        )?;
+
+        let corrections = repair_replaced_references(&mesh, &base_path);
+        let contents = fs::read_to_string(&mesh);
+        fs::remove_dir_all(&base_path)?;
+
+        assert_eq!(
+            corrections?,
+            vec![
+                (
+                    String::from("../materials/textures/wood.jpg"),
+                    String::from("../materials/textures/wood.png")
+                ),
+                (
+                    String::from("../brass.jpg"),
+                    String::from("../materials/textures/brass.webp")
+                ),
+            ]
+        );
+        let contents = contents?;
+        assert!(contents.contains(">glass.jpg<"));
+        assert!(contents.contains(">missing.jpg<"));
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+//! Swap references in a mesh, SDF or MTL material without touching anything else
+
+use std::{collections::BTreeMap, fs, io::Error, path::Path, result::Result};
+
+use crate::xml::edit_file_text_nodes;
+
+/// Replace the references of the file, keyed by their current value, and return
+/// whether it changed. XML files are streamed and only get the matching text nodes
+/// changed, and MTL materials only get whole values replaced, so `Wood.JPG` doesn't
+/// also rewrite `DarkWood.JPG`.
+pub fn replace_references(
+    file: &Path,
+    replacements: &BTreeMap<String, String>,
+) -> Result<bool, Error> {
+    if replacements.is_empty() {
+        return Ok(false);
+    }
+
+    if file.extension().and_then(|e| e.to_str()) != Some("mtl") {
+        return edit_file_text_nodes(file, |text| replacements.get(text).cloned());
+    }
+
+    let contents = fs::read_to_string(file)?;
+    let replaced = replace_whole_values(&contents, replacements);
+    if replaced == contents {
+        return Ok(false);
+    }
+    fs::write(file, replaced)?;
+
+    Ok(true)
+}
+
+/// Replace the values only where they're surrounded by whitespace
+fn replace_whole_values(contents: &str, replacements: &BTreeMap<String, String>) -> String {
+    let is_boundary = |c: Option<char>| c.is_none_or(char::is_whitespace);
+    let mut replaced = contents.to_string();
+    for (reference, replacement) in replacements {
+        let mut result = String::new();
+        let mut rest = replaced.as_str();
+        while let Some(start) = rest.find(reference.as_str()) {
+            let end = start + reference.len();
+            result.push_str(&rest[..start]);
+            if is_boundary(rest[..start].chars().last()) && is_boundary(rest[end..].chars().next())
+            {
+                result.push_str(replacement);
+            } else {
+                result.push_str(reference);
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        replaced = result;
+    }
+
+    replaced
+}
+
+#[cfg(test)]
+mod replace_whole_values_tests {
+    use super::*;
+
+    #[test]
+    fn it_replaces_whole_mtl_values() {
+        let replacements: BTreeMap<String, String> =
+            vec![(String::from("Wood.PNG"), String::from("wood.png"))]
+                .into_iter()
+                .collect();
+        let contents = "map_Kd Wood.PNG\nmap_Ks DarkWood.PNG\n";
+
+        assert_eq!(
+            replace_whole_values(contents, &replacements),
+            "map_Kd wood.png\nmap_Ks DarkWood.PNG\n"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Find the file a reference points at, even when the case of its name doesn't match
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Resolve the path as is when it exists, otherwise look for a path that only
+/// differs in case, one component at a time. Returns the path as it is on disk.
+pub fn resolve_case_insensitive(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        let name = match component {
+            Component::Normal(name) => name,
+            _ => {
+                resolved.push(component);
+                continue;
+            }
+        };
+
+        let candidate = resolved.join(name);
+        if candidate.exists() {
+            resolved = candidate;
+            continue;
+        }
+
+        let dir = if resolved.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            resolved.as_path()
+        };
+        let name = name.to_string_lossy();
+        let on_disk = fs::read_dir(dir)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name())
+            .find(|entry| entry.to_string_lossy().eq_ignore_ascii_case(&name))?;
+        resolved.push(on_disk);
+    }
+
+    Some(resolved)
+}
+
+#[cfg(test)]
+mod resolve_case_insensitive_tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_paths_with_the_wrong_case() {
+        let textures = Path::new("tests")
+            .join("mesh_update")
+            .join("resolved")
+            .join("materials");
+
+        assert_eq!(
+            resolve_case_insensitive(&textures.join("Textures").join("RESOLVED.png")),
+            Some(textures.join("textures").join("resolved.png"))
+        );
+        assert_eq!(
+            resolve_case_insensitive(&textures.join("textures").join("resolved.png")),
+            Some(textures.join("textures").join("resolved.png"))
+        );
+        assert_eq!(
+            resolve_case_insensitive(&textures.join("textures").join("missing.png")),
+            None
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Scan all files recursively in the specified path for files holding references
+
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::mesh_update::REFERENCE_FILE_EXTENSIONS;
+use crate::walk::walk_dir;
+
+/// Recursively list the meshes, SDFs and MTL materials in the directory
+pub fn scan_dir_for_reference_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    walk_dir(dir, &|path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| REFERENCE_FILE_EXTENSIONS.contains(&ext))
+    })
+}
+
+#[cfg(test)]
+mod scan_dir_for_reference_files_tests {
+    use super::*;
+
+    #[test]
+    fn it_returns_meshes_sdfs_and_materials() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("mesh_update")
+            .join("absolute_paths")
+            .join("model");
+        let files = scan_dir_for_reference_files(&dir)?;
+
+        assert_eq!(
+            files,
+            vec![
+                dir.join("meshes").join("absolute.dae"),
+                dir.join("meshes").join("absolute.mtl"),
+                dir.join("model.sdf"),
+            ]
+        );
+
+        Ok(())
+    }
+}
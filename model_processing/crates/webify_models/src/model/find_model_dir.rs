@@ -0,0 +1,54 @@
+//! Find the root directory of the model a file belongs to
+
+use std::{io::Error, path::Path, path::PathBuf, result::Result};
+
+use crate::model::{locate_file, FileLocation};
+
+/// Find the root directory of the model a file belongs to, erroring for files
+/// shared by a whole collection of models
+pub fn find_model_dir(path: &Path, base_path: &Path) -> Result<PathBuf, Error> {
+    match locate_file(path, base_path)? {
+        FileLocation::Model(model_dir) => Ok(model_dir),
+        FileLocation::Collection(collection_dir) => Err(Error::other(format!(
+            "{:?} is shared by the {:?} collection rather than belonging to a model",
+            path, collection_dir
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod find_model_dir_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_top_level_directory() {
+        let base_path = Path::new("some").join("random").join("path");
+        let path = base_path.join("foo_test").join("meshes").join("foo.jpg");
+
+        assert_eq!(
+            find_model_dir(&path, &base_path).unwrap(),
+            base_path.join("foo_test")
+        );
+    }
+
+    #[test]
+    fn it_errors_on_files_outside_a_model() {
+        let base_path = Path::new("some").join("random").join("path");
+
+        assert!(find_model_dir(&base_path.join("foo.jpg"), &base_path).is_err());
+        assert!(find_model_dir(Path::new("elsewhere/foo/foo.jpg"), &base_path).is_err());
+    }
+
+    #[test]
+    fn it_errors_on_files_shared_by_a_collection() {
+        let base_path = Path::new("tests").join("model");
+        let path = base_path
+            .join("collection")
+            .join("media")
+            .join("materials")
+            .join("textures")
+            .join("wood.png");
+
+        assert!(find_model_dir(&path, &base_path).is_err());
+    }
+}
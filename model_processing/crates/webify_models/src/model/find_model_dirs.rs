@@ -0,0 +1,39 @@
+//! List every model under a directory
+
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::walk::walk_dir;
+
+/// Recursively list the directories with a model.config under `dir`, in path order
+pub fn find_model_dirs(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let model_configs = walk_dir(dir, &|path: &Path| {
+        path.file_name().is_some_and(|name| name == "model.config")
+    })?;
+
+    Ok(model_configs
+        .into_iter()
+        .filter_map(|model_config| model_config.parent().map(Path::to_path_buf))
+        .filter(|model_dir| model_dir != dir)
+        .collect())
+}
+
+#[cfg(test)]
+mod find_model_dirs_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_models_of_a_collection() -> Result<(), Error> {
+        let collection_dir = Path::new("tests").join("model").join("collection");
+
+        assert_eq!(
+            find_model_dirs(&collection_dir)?,
+            vec![collection_dir.join("desk"), collection_dir.join("lamp")]
+        );
+
+        Ok(())
+    }
+}
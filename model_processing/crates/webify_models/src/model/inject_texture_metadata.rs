@@ -0,0 +1,212 @@
+//! Record the dimensions of a model's textures as custom metadata in its SDF
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{escape::escape, events::Event, Reader};
+
+use crate::report::TextureStats;
+
+/// Namespace of the custom elements webify_models adds to SDF files
+pub const WEBIFY_SDF_NAMESPACE: &str = "https://habitatzero.org/webify";
+
+/// Add (or refresh) a `<webify:textures>` block at the end of the `<model>` of the
+/// SDF, listing every texture of the model with its dimensions and channels, so the
+/// simulator's loader can preallocate. Gazebo ignores namespaced custom elements.
+/// The rest of the file is left as is. Returns whether the file changed.
+pub fn inject_texture_metadata(
+    sdf: &Path,
+    model_dir: &Path,
+    textures: &[&TextureStats],
+) -> Result<bool, Error> {
+    let contents = fs::read_to_string(sdf)?;
+    let edited = inject_texture_metadata_contents(&contents, model_dir, textures)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", sdf, e)))?;
+    match edited {
+        Some(edited) => fs::write(sdf, edited)?,
+        None => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Add (or refresh) the texture metadata in the contents of an SDF. Returns the
+/// edited contents, or `None` when they're already up to date or have no `<model>`.
+pub fn inject_texture_metadata_contents(
+    contents: &str,
+    model_dir: &Path,
+    textures: &[&TextureStats],
+) -> Result<Option<String>, Error> {
+    let layout = read_layout(contents).map_err(|e| Error::other(e.to_string()))?;
+    let model_end = match layout.model_end {
+        Some(model_end) => model_end,
+        None => return Ok(None),
+    };
+
+    let indent = &layout.indent;
+    let mut block = String::from("<webify:textures>\n");
+    for texture in textures {
+        let path = texture
+            .path
+            .strip_prefix(model_dir)
+            .unwrap_or(&texture.path);
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        block.push_str(&format!(
+            "{}  <webify:texture path=\"{}\" width=\"{}\" height=\"{}\" channels=\"{}\"/>\n",
+            indent,
+            escape(path.as_str()),
+            texture.width,
+            texture.height,
+            texture.channels
+        ));
+    }
+    block.push_str(&format!("{}</webify:textures>", indent));
+
+    // (start, end, replacement), applied from the end so the offsets stay valid
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    match layout.textures {
+        Some((start, end)) if contents[start..end] == block => return Ok(None),
+        Some((start, end)) => edits.push((start, end, block)),
+        None => {
+            let line_start = contents[..model_end].rfind('\n').map_or(0, |i| i + 1);
+            if contents[line_start..model_end].trim().is_empty() {
+                edits.push((line_start, line_start, format!("{}{}\n", indent, block)));
+            } else {
+                edits.push((model_end, model_end, block));
+            }
+        }
+    }
+    if let Some(position) = layout.namespace_insertion {
+        let declaration = format!(" xmlns:webify=\"{}\"", WEBIFY_SDF_NAMESPACE);
+        edits.push((position, position, declaration));
+    }
+
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+    let mut edited = contents.to_string();
+    for (start, end, replacement) in edits {
+        edited.replace_range(start..end, &replacement);
+    }
+
+    Ok(Some(edited))
+}
+
+/// Byte offsets of the parts of an SDF the injection cares about
+struct Layout {
+    /// Where to declare the webify namespace on `<sdf>`, when it isn't yet
+    namespace_insertion: Option<usize>,
+    /// Start of the `</model>` closing tag
+    model_end: Option<usize>,
+    /// Indentation of the children of `<model>`
+    indent: String,
+    /// Span of an existing `<webify:textures>` block
+    textures: Option<(usize, usize)>,
+}
+
+/// Find where things are in the SDF
+fn read_layout(contents: &str) -> Result<Layout, quick_xml::Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut layout = Layout {
+        namespace_insertion: None,
+        model_end: None,
+        indent: String::from("  "),
+        textures: None,
+    };
+
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut textures_start = None;
+    let mut seen_first_child = false;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event()?;
+        let end = reader.buffer_position() as usize;
+
+        match event {
+            Event::Start(ref e) => {
+                let name = e.name().as_ref().to_vec();
+                if path.is_empty() && name == b"sdf" {
+                    let has_namespace = e
+                        .attributes()
+                        .flatten()
+                        .any(|a| a.key.as_ref() == b"xmlns:webify");
+                    if !has_namespace {
+                        layout.namespace_insertion = Some(end - 1);
+                    }
+                }
+                if path == [b"sdf".to_vec(), b"model".to_vec()] {
+                    if !seen_first_child {
+                        seen_first_child = true;
+                        let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+                        if contents[line_start..start].trim().is_empty() {
+                            layout.indent = contents[line_start..start].to_string();
+                        }
+                    }
+                    if name == b"webify:textures" {
+                        textures_start = Some(start);
+                    }
+                }
+                path.push(name);
+            }
+            Event::End(ref e) => {
+                path.pop();
+                let name = e.name();
+                if path == [b"sdf".to_vec()] && name.as_ref() == b"model" {
+                    layout.model_end = Some(start);
+                }
+                if path == [b"sdf".to_vec(), b"model".to_vec()]
+                    && name.as_ref() == b"webify:textures"
+                {
+                    layout.textures = textures_start.map(|s| (s, end));
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod inject_texture_metadata_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_injects_texture_metadata_once() -> Result<(), Error> {
+        let model_dir = Path::new("tests")
+            .join("model")
+            .join("test_run_inject_texture_metadata");
+        fs::create_dir_all(&model_dir)?;
+        let sdf = model_dir.join("model.sdf");
+        let original = "<?xml version=\"1.0\"?>\n<sdf version=\"1.6\">\n  <model name=\"lamp\">\n    <!-- hand-edited -->\n    <static>true</static>\n  </model>\n</sdf>\n";
+        fs::write(&sdf, original)?;
+
+        let texture = TextureStats {
+            path: model_dir
+                .join("materials")
+                .join("textures")
+                .join("shade.png"),
+            model: Some(PathBuf::from(&model_dir)),
+            width: 512,
+            height: 256,
+            channels: 4,
+            file_bytes: 1,
+            gpu_bytes: 1,
+            encoding: None,
+        };
+
+        assert!(inject_texture_metadata(&sdf, &model_dir, &[&texture])?);
+        assert_eq!(
+            fs::read_to_string(&sdf)?,
+            "<?xml version=\"1.0\"?>\n<sdf version=\"1.6\" xmlns:webify=\"https://habitatzero.org/webify\">\n  <model name=\"lamp\">\n    <!-- hand-edited -->\n    <static>true</static>\n    <webify:textures>\n      <webify:texture path=\"materials/textures/shade.png\" width=\"512\" height=\"256\" channels=\"4\"/>\n    </webify:textures>\n  </model>\n</sdf>\n"
+        );
+        assert!(!inject_texture_metadata(&sdf, &model_dir, &[&texture])?);
+
+        fs::remove_dir_all(model_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Work out whether a file belongs to a model or is shared by a collection of models
+
+use std::{
+    fs,
+    io::Error,
+    path::{Component, Path, PathBuf},
+    result::Result,
+};
+
+/// Who a file in the processed directory belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileLocation {
+    /// The file belongs to the model rooted at this directory
+    Model(PathBuf),
+    /// The file sits outside of any model, in a collection directory that groups
+    /// several models and shares its media between them
+    Collection(PathBuf),
+}
+
+/// Work out who a file belongs to:
+///
+/// - the closest ancestor directory with a model.config is its model
+/// - otherwise, the closest ancestor directory that has models as children is a
+///   collection sharing the file between them
+/// - otherwise, for trees without any model.config, the top-level directory under
+///   the base path is assumed to be the model
+pub fn locate_file(path: &Path, base_path: &Path) -> Result<FileLocation, Error> {
+    let relative_path = path
+        .strip_prefix(base_path)
+        .map_err(|_| Error::other(format!("{:?} is not inside {:?}", path, base_path)))?;
+
+    let ancestors = || {
+        path.ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(base_path))
+    };
+
+    if let Some(model_dir) = ancestors().find(|dir| is_model_dir(dir)) {
+        return Ok(FileLocation::Model(model_dir.to_path_buf()));
+    }
+
+    for dir in ancestors() {
+        if has_model_children(dir)? {
+            return Ok(FileLocation::Collection(dir.to_path_buf()));
+        }
+    }
+
+    let mut components = relative_path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(model)), Some(_)) => Ok(FileLocation::Model(base_path.join(model))),
+        _ => Err(Error::other(format!(
+            "{:?} does not belong to a model",
+            path
+        ))),
+    }
+}
+
+/// Whether the directory is the root of a model
+fn is_model_dir(dir: &Path) -> bool {
+    dir.join("model.config").is_file()
+}
+
+/// Whether any direct child of the directory is the root of a model
+fn has_model_children(dir: &Path) -> Result<bool, Error> {
+    if !dir.is_dir() {
+        return Ok(false);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && is_model_dir(&path) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod locate_file_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_closest_model_config() {
+        let base_path = Path::new("tests").join("model");
+        let path = base_path
+            .join("collection")
+            .join("lamp")
+            .join("meshes")
+            .join("lamp.dae");
+
+        assert_eq!(
+            locate_file(&path, &base_path).unwrap(),
+            FileLocation::Model(base_path.join("collection").join("lamp"))
+        );
+    }
+
+    #[test]
+    fn it_finds_textures_shared_by_a_collection() {
+        let base_path = Path::new("tests").join("model");
+        let path = base_path
+            .join("collection")
+            .join("media")
+            .join("materials")
+            .join("textures")
+            .join("wood.png");
+
+        assert_eq!(
+            locate_file(&path, &base_path).unwrap(),
+            FileLocation::Collection(base_path.join("collection"))
+        );
+    }
+
+    #[test]
+    fn it_falls_back_to_the_top_level_directory() {
+        let base_path = Path::new("some").join("random").join("path");
+        let path = base_path.join("foo_test").join("meshes").join("foo.jpg");
+
+        assert_eq!(
+            locate_file(&path, &base_path).unwrap(),
+            FileLocation::Model(base_path.join("foo_test"))
+        );
+    }
+
+    #[test]
+    fn it_errors_on_files_outside_a_model() {
+        let base_path = Path::new("some").join("random").join("path");
+
+        assert!(locate_file(&base_path.join("foo.jpg"), &base_path).is_err());
+        assert!(locate_file(Path::new("elsewhere/foo/foo.jpg"), &base_path).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Mark a model as processed in its model.config, keeping the file's formatting
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::config::ModelConfigMark;
+
+/// Mark the model as processed in its model.config, as configured by `mark`. The
+/// file is edited in place rather than rewritten, so comments, indentation and
+/// attribute order are kept. Returns whether the file changed.
+pub fn mark_model_config(model_dir: &Path, mark: ModelConfigMark) -> Result<bool, Error> {
+    let config_path = model_dir.join("model.config");
+    if mark == ModelConfigMark::None || !config_path.is_file() {
+        return Ok(false);
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    let edited = mark_model_config_contents(&contents, mark)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", config_path, e)))?;
+    match edited {
+        Some(edited) => fs::write(config_path, edited)?,
+        None => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Mark the contents of a model.config as processed. Returns the edited contents,
+/// or `None` when they're already marked.
+pub fn mark_model_config_contents(
+    contents: &str,
+    mark: ModelConfigMark,
+) -> Result<Option<String>, Error> {
+    if mark == ModelConfigMark::None {
+        return Ok(None);
+    }
+    let layout = read_layout(contents).map_err(|e| Error::other(e.to_string()))?;
+
+    let element = format!("<webified version=\"{}\"/>", env!("CARGO_PKG_VERSION"));
+    if let Some((start, end)) = layout.webified {
+        if contents[start..end] == element {
+            return Ok(None);
+        }
+    }
+
+    // (start, end, replacement), applied from the end so the offsets stay valid
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    match layout.webified {
+        Some((start, end)) => edits.push((start, end, element)),
+        None => {
+            let line_start = contents[..layout.model_end]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            if contents[line_start..layout.model_end].trim().is_empty() {
+                let insertion = format!("{}{}\n", layout.indent, element);
+                edits.push((line_start, line_start, insertion));
+            } else {
+                edits.push((layout.model_end, layout.model_end, element));
+            }
+        }
+    }
+    if mark == ModelConfigMark::Version {
+        if let Some((start, end)) = layout.version {
+            edits.push((start, end, bump_version(contents[start..end].trim())));
+        }
+    }
+
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+    let mut edited = contents.to_string();
+    for (start, end, replacement) in edits {
+        edited.replace_range(start..end, &replacement);
+    }
+
+    Ok(Some(edited))
+}
+
+/// Byte offsets of the parts of a model.config the marking cares about
+struct Layout {
+    /// Start of the `</model>` closing tag
+    model_end: usize,
+    /// Indentation of the children of `<model>`
+    indent: String,
+    /// Span of an existing `<webified>` element
+    webified: Option<(usize, usize)>,
+    /// Span of the text of `<version>`
+    version: Option<(usize, usize)>,
+}
+
+/// Find where things are in the model.config
+fn read_layout(contents: &str) -> Result<Layout, quick_xml::Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut layout = Layout {
+        model_end: contents.len(),
+        indent: String::from("  "),
+        webified: None,
+        version: None,
+    };
+
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut webified_start = None;
+    let mut seen_first_child = false;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event()?;
+        let end = reader.buffer_position() as usize;
+
+        let is_model_child = path.len() == 1 && path[0] == b"model";
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if is_model_child => {
+                if !seen_first_child {
+                    seen_first_child = true;
+                    let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+                    if contents[line_start..start].trim().is_empty() {
+                        layout.indent = contents[line_start..start].to_string();
+                    }
+                }
+                if e.name().as_ref() == b"webified" {
+                    match event {
+                        Event::Empty(_) => layout.webified = Some((start, end)),
+                        _ => webified_start = Some(start),
+                    }
+                }
+                if let Event::Start(ref e) = event {
+                    path.push(e.name().as_ref().to_vec());
+                }
+            }
+            Event::Start(ref e) => path.push(e.name().as_ref().to_vec()),
+            Event::Text(_) if path == [b"model".to_vec(), b"version".to_vec()] => {
+                layout.version = Some((start, end));
+            }
+            Event::End(ref e) => {
+                path.pop();
+                if path.is_empty() && e.name().as_ref() == b"model" {
+                    layout.model_end = start;
+                }
+                if path.len() == 1 && e.name().as_ref() == b"webified" {
+                    layout.webified = webified_start.map(|s| (s, end));
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(layout)
+}
+
+/// Bump the last number of the version: `1.0` becomes `1.1`, `2` becomes `3`, and a
+/// version that doesn't end in a number gets `.1` appended
+fn bump_version(version: &str) -> String {
+    let (prefix, last) = match version.rfind('.') {
+        Some(i) => (&version[..=i], &version[i + 1..]),
+        None => ("", version),
+    };
+
+    match last.parse::<u64>().ok().and_then(|n| n.checked_add(1)) {
+        Some(number) => format!("{}{}", prefix, number),
+        None => format!("{}.1", version),
+    }
+}
+
+#[cfg(test)]
+mod mark_model_config_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn setup(test_run_id: &str) -> Result<PathBuf, Error> {
+        let destination_path = Path::new("tests").join("model").join(test_run_id);
+        fs::create_dir_all(&destination_path)?;
+        fs::copy(
+            Path::new("tests")
+                .join("model")
+                .join("ceiling_lamp")
+                .join("model.config"),
+            destination_path.join("model.config"),
+        )?;
+
+        Ok(destination_path)
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(Path::new("tests").join("model").join(test_run_id))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_adds_the_webified_element_once() -> Result<(), Error> {
+        let test_run_id = "test_run_mark_model_config_element";
+        let model_dir = setup(test_run_id)?;
+        let original = fs::read_to_string(model_dir.join("model.config"))?;
+
+        assert!(mark_model_config(&model_dir, ModelConfigMark::Element)?);
+        let marked = fs::read_to_string(model_dir.join("model.config"))?;
+        assert_eq!(
+            marked,
+            original.replace(
+                "</model>",
+                &format!(
+                    "  <webified version=\"{}\"/>\n</model>",
+                    env!("CARGO_PKG_VERSION")
+                )
+            )
+        );
+
+        assert!(!mark_model_config(&model_dir, ModelConfigMark::Element)?);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_bumps_the_version_once() -> Result<(), Error> {
+        let test_run_id = "test_run_mark_model_config_version";
+        let model_dir = setup(test_run_id)?;
+
+        assert!(mark_model_config(&model_dir, ModelConfigMark::Version)?);
+        assert!(!mark_model_config(&model_dir, ModelConfigMark::Version)?);
+
+        let marked = fs::read_to_string(model_dir.join("model.config"))?;
+        assert!(marked.contains("  <version>1.1</version>\n"));
+        assert!(marked.contains("<webified version="));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_the_model_config_alone_when_disabled() -> Result<(), Error> {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert!(!mark_model_config(&model_dir, ModelConfigMark::None)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_malformed_contents() {
+        let contents = "<?xml version=\"1.0\"?>\n<model>\n  <name>Lamp</nam";
+        assert!(mark_model_config_contents(contents, ModelConfigMark::Element).is_err());
+    }
+}
+
+#[cfg(test)]
+mod bump_version_tests {
+    use super::*;
+
+    #[test]
+    fn it_bumps_the_last_number() {
+        assert_eq!(bump_version("1.0"), "1.1");
+        assert_eq!(bump_version("1.9"), "1.10");
+        assert_eq!(bump_version("2"), "3");
+        assert_eq!(bump_version("1.0-beta"), "1.0-beta.1");
+    }
+
+    #[test]
+    fn it_appends_to_a_number_that_cant_be_bumped() {
+        assert_eq!(
+            bump_version("1.18446744073709551615"),
+            "1.18446744073709551615.1"
+        );
+    }
+}
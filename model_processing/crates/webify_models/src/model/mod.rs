@@ -0,0 +1,26 @@
+//! Model-level information: where a model lives, which collection it belongs to
+//! and how it's described in its model.config
+
+mod find_model_dir;
+mod find_model_dirs;
+mod inject_texture_metadata;
+mod locate_file;
+mod mark_model_config;
+mod model_sdfs;
+mod read_model_tags;
+mod read_webified_version;
+mod resolve_category;
+mod shared_textures_dir;
+
+pub use self::find_model_dir::find_model_dir;
+pub use self::find_model_dirs::find_model_dirs;
+pub use self::inject_texture_metadata::{
+    inject_texture_metadata, inject_texture_metadata_contents, WEBIFY_SDF_NAMESPACE,
+};
+pub use self::locate_file::{locate_file, FileLocation};
+pub use self::mark_model_config::{mark_model_config, mark_model_config_contents};
+pub use self::model_sdfs::model_sdfs;
+pub use self::read_model_tags::read_model_tags;
+pub use self::read_webified_version::read_webified_version;
+pub use self::resolve_category::resolve_category;
+pub use self::shared_textures_dir::shared_textures_dir;
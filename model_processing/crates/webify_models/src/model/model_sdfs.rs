@@ -0,0 +1,22 @@
+//! List the SDF files of a model
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+/// SDF files at the root of the model directory, sorted
+pub fn model_sdfs(model_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut sdfs = Vec::new();
+    for entry in fs::read_dir(model_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("sdf") {
+            sdfs.push(path);
+        }
+    }
+    sdfs.sort();
+
+    Ok(sdfs)
+}
@@ -0,0 +1,69 @@
+//! Read the category and tags of a model from its model.config
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{events::Event, Reader};
+
+/// Elements of model.config whose text is read as tags
+const TAG_ELEMENTS: [&[u8]; 3] = [b"category", b"tags", b"tag"];
+
+/// Read the `<category>`, `<tags>` and `<tag>` values of the model.config in the
+/// model directory, in document order. `<tags>` may hold several comma or space
+/// separated values. Models without a model.config have no tags.
+pub fn read_model_tags(model_dir: &Path) -> Result<Vec<String>, Error> {
+    let config_path = model_dir.join("model.config");
+    if !config_path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    let mut reader = Reader::from_str(&contents);
+    reader.config_mut().trim_text(true);
+
+    let mut tags = Vec::new();
+    let mut in_tag_element = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => in_tag_element = TAG_ELEMENTS.contains(&e.name().as_ref()),
+            Ok(Event::End(_)) => in_tag_element = false,
+            Ok(Event::Text(t)) if in_tag_element => {
+                let text = t.unescape().map_err(|e| Error::other(e.to_string()))?;
+                tags.extend(
+                    text.split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|tag| !tag.is_empty())
+                        .map(String::from),
+                );
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Failed to parse {:?}: {:?}",
+                    config_path, e
+                )))
+            }
+            _ => (),
+        }
+    }
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod read_model_tags_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_category_and_tags() {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert_eq!(
+            read_model_tags(&model_dir).unwrap(),
+            vec!["ceiling_fixture", "lighting", "interior"]
+        );
+    }
+
+    #[test]
+    fn it_has_no_tags_without_a_model_config() {
+        let model_dir = Path::new("tests").join("model");
+        assert!(read_model_tags(&model_dir).unwrap().is_empty());
+    }
+}
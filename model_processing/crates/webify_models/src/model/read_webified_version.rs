@@ -0,0 +1,69 @@
+//! Read which version of webify_models marked a model as processed
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{events::Event, Reader};
+
+/// Version of the `<webified version="..."/>` mark in the model.config of the model
+/// directory, or `None` when the model isn't marked as processed
+pub fn read_webified_version(model_dir: &Path) -> Result<Option<String>, Error> {
+    let config_path = model_dir.join("model.config");
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    let mut reader = Reader::from_str(&contents);
+    let mut depth = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                if depth == 1 && e.name().as_ref() == b"webified" =>
+            {
+                let version = e
+                    .try_get_attribute("version")
+                    .map_err(|e| Error::other(e.to_string()))?
+                    .map(|attribute| attribute.unescape_value().map(|v| v.into_owned()))
+                    .transpose()
+                    .map_err(|e| Error::other(e.to_string()))?;
+                return Ok(Some(version.unwrap_or_default()));
+            }
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Eof) => return Ok(None),
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Failed to parse {:?}: {:?}",
+                    config_path, e
+                )))
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod read_webified_version_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_mark() -> Result<(), Error> {
+        let model_dir = Path::new("tests")
+            .join("dependencies")
+            .join("library")
+            .join("models")
+            .join("desk");
+        assert_eq!(
+            read_webified_version(&model_dir)?,
+            Some(String::from("0.1.0"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_has_no_version_for_unmarked_models() -> Result<(), Error> {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert_eq!(read_webified_version(&model_dir)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Work out which configured category a model belongs to
+
+use std::{io::Error, path::Path, result::Result};
+
+use crate::config::WebifyConfig;
+use crate::model::read_model_tags;
+
+/// Work out the category of a model: the config table wins, otherwise the first
+/// model.config tag that names a configured category
+pub fn resolve_category(model_dir: &Path, config: &WebifyConfig) -> Result<Option<String>, Error> {
+    if config.categories.is_empty() {
+        return Ok(None);
+    }
+
+    let model_name = model_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if let Some(category) = config.model_categories.get(model_name) {
+        return Ok(Some(category.clone()));
+    }
+
+    Ok(read_model_tags(model_dir)?
+        .into_iter()
+        .find(|tag| config.categories.contains_key(tag)))
+}
+
+#[cfg(test)]
+mod resolve_category_tests {
+    use super::*;
+
+    use crate::config::CategoryPreset;
+
+    fn config() -> WebifyConfig {
+        let mut config = WebifyConfig::default();
+        config.categories.insert(
+            String::from("ceiling_fixture"),
+            CategoryPreset { max_size: 512 },
+        );
+        config
+            .categories
+            .insert(String::from("hero"), CategoryPreset { max_size: 4096 });
+        config
+    }
+
+    #[test]
+    fn it_uses_the_model_config_tags() {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert_eq!(
+            resolve_category(&model_dir, &config()).unwrap(),
+            Some(String::from("ceiling_fixture"))
+        );
+    }
+
+    #[test]
+    fn it_prefers_the_config_table() {
+        let mut config = config();
+        config
+            .model_categories
+            .insert(String::from("ceiling_lamp"), String::from("hero"));
+
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert_eq!(
+            resolve_category(&model_dir, &config).unwrap(),
+            Some(String::from("hero"))
+        );
+    }
+
+    #[test]
+    fn it_has_no_category_without_a_match() {
+        let model_dir = Path::new("tests").join("model").join("ceiling_lamp");
+        assert_eq!(
+            resolve_category(&model_dir, &WebifyConfig::default()).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Where a collection keeps the textures shared by its models
+
+use std::path::{Path, PathBuf};
+
+/// Where a collection keeps the textures shared by its models, following the
+/// `media/materials/textures` layout Gazebo collections use
+pub fn shared_textures_dir(collection_dir: &Path) -> PathBuf {
+    collection_dir
+        .join("media")
+        .join("materials")
+        .join("textures")
+}
@@ -0,0 +1,501 @@
+//! Extract the walkable area of a set of triangles
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Error,
+    result::Result,
+};
+
+use crate::impostor::Triangle;
+use crate::navmesh::{Heightfield, WorldNavmesh};
+use crate::options::NavmeshSettings;
+
+/// Smallest island of walkable area kept, in square meters, so table tops and the
+/// gaps between furniture don't end up in the navmesh
+const MIN_ISLAND_AREA: f64 = 0.5;
+
+/// Walkable cells this close in height merge into the same rectangle, in meters
+const HEIGHT_TOLERANCE: f64 = 0.01;
+
+/// Steps to the neighbors of a cell: +X, +Y, -X, -Y
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Walkable surface of a cell: the top of a span with room for the agent above
+#[derive(Debug, Clone)]
+struct Floor {
+    z: f64,
+    ceiling: f64,
+    /// Floor of the neighbor in each direction the agent can step to
+    links: [Option<usize>; 4],
+    /// Whether the floor is still part of the navmesh
+    kept: bool,
+}
+
+/// Navmesh of the triangles, voxelized the way Recast does: the triangles are
+/// rasterized into a heightfield of solid spans, the tops of the spans flat
+/// enough with headroom for the agent become walkable, ledges and the cells
+/// closer to an obstacle than the agent's radius are dropped along with small
+/// islands, then the rest is merged into rectangles.
+pub fn build_navmesh(
+    triangles: &[Triangle],
+    settings: &NavmeshSettings,
+) -> Result<WorldNavmesh, Error> {
+    let mut heightfield = Heightfield::covering(triangles, settings.cell_size, settings.max_climb)?;
+    let min_normal_z = settings.max_slope.to_radians().cos();
+    for triangle in triangles {
+        let [a, b, c] = triangle.vertices;
+        let normal = cross(
+            [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+            [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+        );
+        let length = (normal[0].powi(2) + normal[1].powi(2) + normal[2].powi(2)).sqrt();
+        if length > 0.0 {
+            heightfield.rasterize(&triangle.vertices, normal[2] / length >= min_normal_z);
+        }
+    }
+
+    let mut floors = walkable_floors(&heightfield, settings);
+    link_floors(&mut floors, &heightfield, settings);
+    erode(&mut floors, &heightfield, settings);
+    remove_islands(&mut floors, &heightfield);
+
+    Ok(merge_rectangles(&floors, &heightfield, settings))
+}
+
+/// Walkable floors of each cell: tops of walkable spans with room for the agent up
+/// to the next span, that aren't at the edge of a drop
+fn walkable_floors(heightfield: &Heightfield, settings: &NavmeshSettings) -> Vec<Vec<Floor>> {
+    let mut floors = Vec::with_capacity(heightfield.columns.len());
+    for y in 0..heightfield.depth {
+        for x in 0..heightfield.width {
+            let spans = heightfield.spans(x, y);
+            let mut cell = Vec::new();
+            let mut previous: Option<(f64, bool)> = None;
+            for (i, span) in spans.iter().enumerate() {
+                // Low obstacles, like curbs, are walked over from the walkable
+                // surface right below
+                let walkable = span.walkable
+                    || previous.is_some_and(|(max, walkable)| {
+                        walkable && span.max - max <= settings.max_climb
+                    });
+                previous = Some((span.max, span.walkable));
+
+                let ceiling = spans.get(i + 1).map_or(f64::INFINITY, |next| next.min);
+                if walkable
+                    && ceiling - span.max >= settings.agent_height
+                    && !is_ledge(heightfield, x, y, span.max, ceiling, settings)
+                {
+                    cell.push(Floor {
+                        z: span.max,
+                        ceiling,
+                        links: [None; 4],
+                        kept: true,
+                    });
+                }
+            }
+            floors.push(cell);
+        }
+    }
+    floors
+}
+
+/// Whether the agent standing on the floor could fall off a side: a neighbor is
+/// out of the heightfield or has an opening, with room for the agent, lower than
+/// a step down
+fn is_ledge(
+    heightfield: &Heightfield,
+    x: usize,
+    y: usize,
+    z: f64,
+    ceiling: f64,
+    settings: &NavmeshSettings,
+) -> bool {
+    DIRECTIONS.iter().any(|&direction| {
+        let (nx, ny) = match neighbor_cell(heightfield, x, y, direction) {
+            Some(cell) => cell,
+            None => return true,
+        };
+        let spans = heightfield.spans(nx, ny);
+        // Openings of the neighbor: below its first span, then above each span
+        let bottom = (
+            f64::NEG_INFINITY,
+            spans.first().map_or(f64::INFINITY, |span| span.min),
+        );
+        let openings = spans.iter().enumerate().map(|(i, span)| {
+            (
+                span.max,
+                spans.get(i + 1).map_or(f64::INFINITY, |next| next.min),
+            )
+        });
+        std::iter::once(bottom).chain(openings).any(|(floor, top)| {
+            ceiling.min(top) - z.max(floor) >= settings.agent_height
+                && floor < z - settings.max_climb
+        })
+    })
+}
+
+/// Link each floor to the floor of each neighbor it can step to, the closest in
+/// height when there are several
+fn link_floors(floors: &mut [Vec<Floor>], heightfield: &Heightfield, settings: &NavmeshSettings) {
+    for y in 0..heightfield.depth {
+        for x in 0..heightfield.width {
+            for i in 0..floors[x + y * heightfield.width].len() {
+                let floor = floors[x + y * heightfield.width][i].clone();
+                for (d, &direction) in DIRECTIONS.iter().enumerate() {
+                    let (nx, ny) = match neighbor_cell(heightfield, x, y, direction) {
+                        Some(cell) => cell,
+                        None => continue,
+                    };
+                    let link = floors[nx + ny * heightfield.width]
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, other)| {
+                            (other.z - floor.z).abs() <= settings.max_climb
+                                && floor.ceiling.min(other.ceiling) - floor.z.max(other.z)
+                                    >= settings.agent_height
+                        })
+                        .min_by(|(_, a), (_, b)| {
+                            (a.z - floor.z).abs().total_cmp(&(b.z - floor.z).abs())
+                        })
+                        .map(|(j, _)| j);
+                    floors[x + y * heightfield.width][i].links[d] = link;
+                }
+            }
+        }
+    }
+}
+
+/// Drop the floors closer to an obstacle or an edge than the agent's radius,
+/// counting distances in steps between cells
+fn erode(floors: &mut [Vec<Floor>], heightfield: &Heightfield, settings: &NavmeshSettings) {
+    let radius = (settings.agent_radius / settings.cell_size).ceil() as usize;
+    if radius == 0 {
+        return;
+    }
+
+    let mut distances: Vec<Vec<usize>> = floors
+        .iter()
+        .map(|cell| vec![usize::MAX; cell.len()])
+        .collect();
+    let mut queue = VecDeque::new();
+    for (c, cell) in floors.iter().enumerate() {
+        for (i, floor) in cell.iter().enumerate() {
+            if floor.links.iter().any(Option::is_none) {
+                distances[c][i] = 0;
+                queue.push_back((c, i));
+            }
+        }
+    }
+    while let Some((c, i)) = queue.pop_front() {
+        let distance = distances[c][i];
+        for (n, j) in links(floors, heightfield, c, i) {
+            if distances[n][j] == usize::MAX {
+                distances[n][j] = distance + 1;
+                queue.push_back((n, j));
+            }
+        }
+    }
+
+    for (cell, cell_distances) in floors.iter_mut().zip(distances) {
+        for (floor, distance) in cell.iter_mut().zip(cell_distances) {
+            floor.kept = distance >= radius;
+        }
+    }
+}
+
+/// Drop the groups of connected floors smaller than `MIN_ISLAND_AREA`
+fn remove_islands(floors: &mut [Vec<Floor>], heightfield: &Heightfield) {
+    let cell_area = heightfield.cell_size * heightfield.cell_size;
+    let mut visited: Vec<Vec<bool>> = floors.iter().map(|cell| vec![false; cell.len()]).collect();
+    for c in 0..floors.len() {
+        for i in 0..floors[c].len() {
+            if visited[c][i] || !floors[c][i].kept {
+                continue;
+            }
+            visited[c][i] = true;
+            let mut island = vec![(c, i)];
+            let mut next = 0;
+            while next < island.len() {
+                let (c, i) = island[next];
+                next += 1;
+                for (n, j) in links(floors, heightfield, c, i) {
+                    if !visited[n][j] {
+                        visited[n][j] = true;
+                        island.push((n, j));
+                    }
+                }
+            }
+            if (island.len() as f64) * cell_area < MIN_ISLAND_AREA {
+                for (c, i) in island {
+                    floors[c][i].kept = false;
+                }
+            }
+        }
+    }
+}
+
+/// Merge the floors left into rectangles of the same height, grown along X then Y
+fn merge_rectangles(
+    floors: &[Vec<Floor>],
+    heightfield: &Heightfield,
+    settings: &NavmeshSettings,
+) -> WorldNavmesh {
+    let width = heightfield.width;
+    let mut used: Vec<Vec<bool>> = floors.iter().map(|cell| vec![false; cell.len()]).collect();
+    let mut vertex_indices: HashMap<(usize, usize, i64), u32> = HashMap::new();
+    let mut navmesh = WorldNavmesh {
+        cell_size: settings.cell_size,
+        agent_height: settings.agent_height,
+        agent_radius: settings.agent_radius,
+        max_climb: settings.max_climb,
+        max_slope: settings.max_slope,
+        area: 0.0,
+        vertices: Vec::new(),
+        triangles: Vec::new(),
+    };
+
+    let mergeable = |used: &[Vec<bool>], c: usize, i: usize, z: f64| {
+        floors[c][i].kept && !used[c][i] && (floors[c][i].z - z).abs() <= HEIGHT_TOLERANCE
+    };
+    let step = |c: usize, i: usize, d: usize| {
+        let (dx, dy) = DIRECTIONS[d];
+        let n = (c as isize + dx + dy * width as isize) as usize;
+        floors[c][i].links[d].filter(|&j| floors[n][j].kept)
+    };
+
+    for y in 0..heightfield.depth {
+        for x in 0..width {
+            let cell = x + y * width;
+            for (i, floor) in floors[cell].iter().enumerate() {
+                let z = floor.z;
+                if !mergeable(&used, cell, i, z) {
+                    continue;
+                }
+
+                // Floors of the first row, then of each row the rectangle grows by
+                let mut row = vec![i];
+                while let Some(j) = step(cell + row.len() - 1, row[row.len() - 1], 0)
+                    .filter(|&j| mergeable(&used, cell + row.len(), j, z))
+                {
+                    row.push(j);
+                }
+                let mut rows = vec![row];
+                'grow: loop {
+                    let last = &rows[rows.len() - 1];
+                    let first_cell = cell + (rows.len() - 1) * width;
+                    let mut next = Vec::with_capacity(last.len());
+                    for (k, &j) in last.iter().enumerate() {
+                        match step(first_cell + k, j, 1)
+                            .filter(|&n| mergeable(&used, first_cell + width + k, n, z))
+                        {
+                            // The row has to be walkable across, like the first one
+                            Some(n)
+                                if k == 0
+                                    || step(first_cell + width + k - 1, next[k - 1], 0)
+                                        == Some(n) =>
+                            {
+                                next.push(n)
+                            }
+                            _ => break 'grow,
+                        }
+                    }
+                    rows.push(next);
+                }
+
+                for (r, row) in rows.iter().enumerate() {
+                    for (k, &j) in row.iter().enumerate() {
+                        used[cell + r * width + k][j] = true;
+                    }
+                }
+                let (columns, row_count) = (rows[0].len(), rows.len());
+                navmesh.area += (columns * row_count) as f64 * settings.cell_size.powi(2);
+                let mut corner = |gx: usize, gy: usize| {
+                    let key = (gx, gy, (z * 1000.0).round() as i64);
+                    *vertex_indices.entry(key).or_insert_with(|| {
+                        navmesh.vertices.push([
+                            round(heightfield.origin[0] + gx as f64 * settings.cell_size),
+                            round(heightfield.origin[1] + gy as f64 * settings.cell_size),
+                            round(z),
+                        ]);
+                        (navmesh.vertices.len() - 1) as u32
+                    })
+                };
+                let a = corner(x, y);
+                let b = corner(x + columns, y);
+                let c = corner(x + columns, y + row_count);
+                let d = corner(x, y + row_count);
+                navmesh.triangles.push([a, b, c]);
+                navmesh.triangles.push([a, c, d]);
+            }
+        }
+    }
+
+    navmesh.area = round(navmesh.area);
+    navmesh
+}
+
+/// Kept floors linked to the floor `i` of cell `c`
+fn links(
+    floors: &[Vec<Floor>],
+    heightfield: &Heightfield,
+    c: usize,
+    i: usize,
+) -> Vec<(usize, usize)> {
+    let (x, y) = (c % heightfield.width, c / heightfield.width);
+    DIRECTIONS
+        .iter()
+        .zip(floors[c][i].links.iter())
+        .filter_map(|(&direction, link)| {
+            let (nx, ny) = neighbor_cell(heightfield, x, y, direction)?;
+            let n = nx + ny * heightfield.width;
+            link.filter(|&j| floors[n][j].kept).map(|j| (n, j))
+        })
+        .collect()
+}
+
+fn neighbor_cell(
+    heightfield: &Heightfield,
+    x: usize,
+    y: usize,
+    (dx, dy): (isize, isize),
+) -> Option<(usize, usize)> {
+    let nx = x
+        .checked_add_signed(dx)
+        .filter(|&nx| nx < heightfield.width)?;
+    let ny = y
+        .checked_add_signed(dy)
+        .filter(|&ny| ny < heightfield.depth)?;
+    Some((nx, ny))
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Round to the millimeter, which keeps the JSON small
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod build_navmesh_tests {
+    use super::*;
+
+    use crate::impostor::{shape_triangles, Transform, VisualGeometry};
+
+    fn shape(geometry: VisualGeometry, pose: Transform) -> Vec<Triangle> {
+        shape_triangles(&geometry)
+            .into_iter()
+            .map(|vertices| Triangle {
+                vertices: vertices.map(|vertex| pose.apply(vertex)),
+                color: [1.0; 3],
+            })
+            .collect()
+    }
+
+    fn floor(size: f64) -> Vec<Triangle> {
+        shape(
+            VisualGeometry::Plane {
+                normal: [0.0, 0.0, 1.0],
+                size: [size, size],
+            },
+            Transform::translation(size / 2.0, size / 2.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn it_keeps_the_agent_radius_from_the_edges() -> Result<(), Error> {
+        let navmesh = build_navmesh(&floor(4.0), &NavmeshSettings::default())?;
+
+        // The edge cells are ledges, then 3 more cells are eroded on each side
+        assert_eq!(navmesh.area, 10.24);
+        assert_eq!(navmesh.triangles.len(), 2);
+        assert_eq!(
+            navmesh.vertices,
+            vec![
+                [0.4, 0.4, 0.0],
+                [3.6, 0.4, 0.0],
+                [3.6, 3.6, 0.0],
+                [0.4, 3.6, 0.0]
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_walks_up_steps_but_not_under_low_ceilings() -> Result<(), Error> {
+        let mut triangles = floor(4.0);
+        // A 10 cm platform on the left half, a 1 m high shelf on the right
+        triangles.extend(shape(
+            VisualGeometry::Box {
+                size: [2.0, 4.0, 0.1],
+            },
+            Transform::translation(1.0, 2.0, 0.05),
+        ));
+        triangles.extend(shape(
+            VisualGeometry::Box {
+                size: [1.0, 1.0, 0.05],
+            },
+            Transform::translation(3.0, 3.0, 1.0),
+        ));
+
+        let navmesh = build_navmesh(&triangles, &NavmeshSettings::default())?;
+
+        assert!(navmesh.vertices.iter().any(|v| v[2] == 0.1));
+        assert!(navmesh.vertices.iter().any(|v| v[2] == 0.0));
+        // Under the shelf, and on it since it's too small, is left out
+        for triangle in &navmesh.triangles {
+            let center = [0, 1].map(|axis| {
+                triangle
+                    .iter()
+                    .map(|&v| navmesh.vertices[v as usize][axis])
+                    .sum::<f64>()
+                    / 3.0
+            });
+            let under_shelf = center.iter().all(|&c| (2.5..3.5).contains(&c));
+            assert!(!under_shelf, "{:?}", center);
+        }
+        // The platform and the floor stay connected, so their area is only
+        // eroded from the outer edges and the shelf
+        assert!(
+            navmesh.area > 6.0 && navmesh.area < 10.24,
+            "{}",
+            navmesh.area
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_out_steep_slopes() -> Result<(), Error> {
+        let ramp = shape(
+            VisualGeometry::Plane {
+                normal: [0.0, -1.2, 1.0],
+                size: [4.0, 4.0],
+            },
+            Transform::translation(2.0, 2.0, 0.0),
+        );
+        let settings = NavmeshSettings::default();
+
+        assert_eq!(build_navmesh(&ramp, &settings)?.area, 0.0);
+        let settings = NavmeshSettings {
+            max_slope: 55.0,
+            ..settings
+        };
+        assert!(build_navmesh(&ramp, &settings)?.area > 3.0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_too_many_cells() {
+        let settings = NavmeshSettings {
+            cell_size: 0.01,
+            ..NavmeshSettings::default()
+        };
+
+        assert!(build_navmesh(&floor(100.0), &settings).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Extract the navmesh of a world
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::impostor::ShapeKind;
+use crate::navmesh::build_navmesh;
+use crate::options::RunOptions;
+use crate::world::compose_world;
+
+/// Files the navmesh of the world is written to, next to it: the JSON navmesh
+/// and its OBJ preview
+pub fn navmesh_paths(world: &Path) -> (PathBuf, PathBuf) {
+    let stem = world
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (
+        world.with_file_name(format!("{}.navmesh.json", stem)),
+        world.with_file_name(format!("{}.navmesh.obj", stem)),
+    )
+}
+
+/// Extract the navmesh of the world from the collisions of its models, the
+/// included ones resolved in `models`, and write it next to the world as
+/// `<world>.navmesh.json` and `<world>.navmesh.obj`. Returns the JSON file
+/// written, `None` when the world has no collisions to walk on.
+pub fn generate_navmesh(
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    options: &RunOptions,
+) -> Result<Option<PathBuf>, Error> {
+    let triangles = compose_world(world, models, ShapeKind::Collision)?;
+    if triangles.is_empty() {
+        return Ok(None);
+    }
+
+    let navmesh = build_navmesh(&triangles, &options.navmesh)?;
+    let (json, obj) = navmesh_paths(world);
+    fs::write(&json, serde_json::to_string(&navmesh)?)?;
+    fs::write(&obj, navmesh.to_obj())?;
+
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod generate_navmesh_tests {
+    use super::*;
+
+    use crate::dependencies::index_models;
+    use crate::navmesh::WorldNavmesh;
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_writes_the_walkable_area_around_the_furniture() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("navmesh")
+            .join("test_run_generate_navmesh");
+        copy_tree(&Path::new("tests").join("world"), &dir)?;
+        let models = index_models(&dir)?;
+        let world = dir.join("worlds").join("apartment.world");
+
+        let json = generate_navmesh(&world, &models, &RunOptions::default())?;
+
+        let (json_path, obj_path) = navmesh_paths(&world);
+        assert_eq!(json, Some(json_path.clone()));
+        assert!(fs::read_to_string(&obj_path)?.starts_with("# Navmesh"));
+        let navmesh: WorldNavmesh = serde_json::from_str(&fs::read_to_string(&json_path)?)?;
+        let centers = navmesh.triangles.iter().map(|triangle| {
+            [0, 1, 2].map(|axis| {
+                triangle
+                    .iter()
+                    .map(|&v| navmesh.vertices[v as usize][axis])
+                    .sum::<f64>()
+                    / 3.0
+            })
+        });
+        for center in centers {
+            // Off the wall, and off and under the table
+            assert!(center[0].abs() > 0.4, "{:?}", center);
+            let under_table = (center[0] + 1.5).abs() < 0.8 && center[1].abs() < 0.45;
+            assert!(!under_table, "{:?}", center);
+            assert_eq!(center[2], 0.0);
+        }
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_worlds_without_collisions() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("navmesh")
+            .join("test_run_skip_navmesh");
+        fs::create_dir_all(&dir)?;
+        let world = dir.join("empty.world");
+        fs::write(&world, "<sdf version=\"1.6\"><world name=\"empty\"/></sdf>")?;
+
+        assert!(generate_navmesh(&world, &BTreeMap::new(), &RunOptions::default())?.is_none());
+        assert!(!navmesh_paths(&world).0.exists());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+//! Solid heightfield the triangles of a world are voxelized into
+
+use std::io::{Error, ErrorKind};
+
+use crate::impostor::Triangle;
+use crate::navmesh::triangle_cells;
+
+/// Most cells along each side, so a huge world or a tiny cell size fails rather
+/// than running out of memory
+const MAX_CELLS: usize = 8192;
+
+/// Solid run of a column, from `min` to `max` meters high
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub min: f64,
+    pub max: f64,
+    /// Whether the top of the span is a walkable surface
+    pub walkable: bool,
+}
+
+/// Grid of columns over the XY bounds of the world, each with the solid spans the
+/// triangles covering it make, sorted from the bottom up and never overlapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightfield {
+    /// Corner of the first cell, in meters
+    pub origin: [f64; 2],
+    pub cell_size: f64,
+    /// Cells along X
+    pub width: usize,
+    /// Cells along Y
+    pub depth: usize,
+    /// Spans of each cell, row by row
+    pub columns: Vec<Vec<Span>>,
+    /// Spans whose tops are this close merge their walkable flags
+    max_climb: f64,
+}
+
+impl Heightfield {
+    /// Empty heightfield covering the triangles
+    pub fn covering(
+        triangles: &[Triangle],
+        cell_size: f64,
+        max_climb: f64,
+    ) -> Result<Heightfield, Error> {
+        let mut min = [f64::INFINITY; 2];
+        let mut max = [f64::NEG_INFINITY; 2];
+        for vertex in triangles
+            .iter()
+            .flat_map(|triangle| triangle.vertices.iter())
+        {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+        }
+        if triangles.is_empty() {
+            min = [0.0; 2];
+            max = [0.0; 2];
+        }
+
+        let cells = |axis: usize| ((max[axis] - min[axis]) / cell_size).ceil().max(1.0);
+        let (width, depth) = (cells(0), cells(1));
+        if width > MAX_CELLS as f64 || depth > MAX_CELLS as f64 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{:.0} by {:.0} cells of {} m is more than the {} cells a side the navmesh allows, raise --navmesh-cell-size",
+                    width, depth, cell_size, MAX_CELLS
+                ),
+            ));
+        }
+
+        Ok(Heightfield {
+            origin: min,
+            cell_size,
+            width: width as usize,
+            depth: depth as usize,
+            columns: vec![Vec::new(); width as usize * depth as usize],
+            max_climb,
+        })
+    }
+
+    /// Add the spans the triangle covers in each cell its footprint overlaps
+    pub fn rasterize(&mut self, vertices: &[[f64; 3]; 3], walkable: bool) {
+        let (origin, cell_size, size) = (self.origin, self.cell_size, (self.width, self.depth));
+        triangle_cells(vertices, origin, cell_size, size, |x, y, min, max| {
+            self.add_span(x + y * self.width, Span { min, max, walkable })
+        });
+    }
+
+    /// Add the span to the column, merged with the spans it touches
+    fn add_span(&mut self, column: usize, mut span: Span) {
+        let spans = &mut self.columns[column];
+        let mut kept = Vec::with_capacity(spans.len() + 1);
+        for other in spans.drain(..) {
+            if other.max < span.min || other.min > span.max {
+                kept.push(other);
+                continue;
+            }
+            // Recast's rule: tops close enough to step between are walkable if
+            // either is, otherwise the higher top decides
+            if (other.max - span.max).abs() <= self.max_climb {
+                span.walkable |= other.walkable;
+            } else if other.max > span.max {
+                span.walkable = other.walkable;
+            }
+            span.min = span.min.min(other.min);
+            span.max = span.max.max(other.max);
+        }
+        let index = kept.partition_point(|other| other.min < span.min);
+        kept.insert(index, span);
+        *spans = kept;
+    }
+
+    /// Spans of the cell
+    pub fn spans(&self, x: usize, y: usize) -> &[Span] {
+        &self.columns[x + y * self.width]
+    }
+}
+
+#[cfg(test)]
+mod heightfield_tests {
+    use super::*;
+
+    fn triangle(vertices: [[f64; 3]; 3]) -> Triangle {
+        Triangle {
+            vertices,
+            color: [1.0; 3],
+        }
+    }
+
+    #[test]
+    fn it_merges_touching_spans() -> Result<(), Error> {
+        let floor = triangle([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        let mut heightfield = Heightfield::covering(&[floor], 0.5, 0.25)?;
+        assert_eq!((heightfield.width, heightfield.depth), (2, 2));
+
+        heightfield.rasterize(&floor.vertices, true);
+        // Only the corner of the cell across the hypotenuse is touched
+        assert_eq!(heightfield.spans(1, 1).len(), 1);
+        // A wall standing on the floor hides it, a curb on it doesn't
+        heightfield.rasterize(&[[0.1, 0.1, 0.0], [0.2, 0.1, 0.0], [0.1, 0.1, 2.0]], false);
+        heightfield.rasterize(&[[0.6, 0.1, 0.0], [0.7, 0.1, 0.0], [0.6, 0.1, 0.1]], false);
+        // A shelf above the floor is a span of its own
+        heightfield.rasterize(&[[0.0, 0.6, 1.0], [0.4, 0.6, 1.0], [0.0, 0.9, 1.0]], true);
+
+        assert_eq!(
+            heightfield.spans(0, 0),
+            &[Span {
+                min: 0.0,
+                max: 2.0,
+                walkable: false
+            }]
+        );
+        assert_eq!(
+            heightfield.spans(1, 0),
+            &[Span {
+                min: 0.0,
+                max: 0.1,
+                walkable: true
+            }]
+        );
+        assert_eq!(heightfield.spans(0, 1).len(), 2);
+        assert_eq!(heightfield.spans(0, 1)[1].min, 1.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Navmeshes: the walkable area of each world, extracted from its collisions by
+//! voxelizing them the way Recast does, for the web experience to preview
+
+mod build_navmesh;
+mod generate_navmesh;
+mod heightfield;
+mod triangle_cells;
+mod world_navmesh;
+
+pub use self::build_navmesh::build_navmesh;
+pub use self::generate_navmesh::{generate_navmesh, navmesh_paths};
+pub use self::heightfield::{Heightfield, Span};
+pub use self::triangle_cells::triangle_cells;
+pub use self::world_navmesh::WorldNavmesh;
@@ -0,0 +1,91 @@
+//! Walk the cells of a grid a triangle covers
+
+/// Visit each cell of the grid, of `size` cells along X and Y from `origin`, that
+/// the footprint of the triangle overlaps, with the lowest and highest points of
+/// the part of the triangle above the cell. Parts outside the grid are left out.
+pub fn triangle_cells(
+    vertices: &[[f64; 3]; 3],
+    origin: [f64; 2],
+    cell_size: f64,
+    (width, depth): (usize, usize),
+    mut visit: impl FnMut(usize, usize, f64, f64),
+) {
+    let cell = |value: f64, origin: f64, cells: usize| {
+        (((value - origin) / cell_size).floor().max(0.0) as usize).min(cells - 1)
+    };
+    let (min_x, max_x) = min_max(vertices.iter().map(|v| v[0]));
+    let (min_y, max_y) = min_max(vertices.iter().map(|v| v[1]));
+
+    for y in cell(min_y, origin[1], depth)..=cell(max_y, origin[1], depth) {
+        let y0 = origin[1] + y as f64 * cell_size;
+        let row = clip(vertices.to_vec(), 1, y0, y0 + cell_size);
+        if row.is_empty() {
+            continue;
+        }
+        for x in cell(min_x, origin[0], width)..=cell(max_x, origin[0], width) {
+            let x0 = origin[0] + x as f64 * cell_size;
+            let polygon = clip(row.clone(), 0, x0, x0 + cell_size);
+            if !polygon.is_empty() {
+                let (min, max) = min_max(polygon.iter().map(|v| v[2]));
+                visit(x, y, min, max);
+            }
+        }
+    }
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
+
+/// Clip the polygon to the slab from `min` to `max` along the axis
+fn clip(polygon: Vec<[f64; 3]>, axis: usize, min: f64, max: f64) -> Vec<[f64; 3]> {
+    let polygon = clip_side(polygon, |v| v[axis] - min);
+    clip_side(polygon, |v| max - v[axis])
+}
+
+/// Keep the part of the polygon where `distance` is positive
+fn clip_side(polygon: Vec<[f64; 3]>, distance: impl Fn(&[f64; 3]) -> f64) -> Vec<[f64; 3]> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let next = &polygon[(i + 1) % polygon.len()];
+        let (d_current, d_next) = (distance(current), distance(next));
+        if d_current >= 0.0 {
+            clipped.push(*current);
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            let t = d_current / (d_current - d_next);
+            clipped.push([0, 1, 2].map(|i| current[i] + (next[i] - current[i]) * t));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod triangle_cells_tests {
+    use super::*;
+
+    #[test]
+    fn it_visits_the_cells_under_the_triangle() {
+        let mut cells = Vec::new();
+        triangle_cells(
+            &[[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 2.0]],
+            [0.0, 0.0],
+            1.0,
+            (2, 2),
+            |x, y, min, max| cells.push((x, y, min, max)),
+        );
+
+        // The cell across the hypotenuse is only touched at its corner
+        assert_eq!(
+            cells,
+            vec![
+                (0, 0, 0.0, 1.0),
+                (1, 0, 0.0, 1.0),
+                (0, 1, 1.0, 2.0),
+                (1, 1, 1.0, 1.0)
+            ]
+        );
+    }
+}
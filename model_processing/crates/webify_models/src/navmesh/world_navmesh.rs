@@ -0,0 +1,68 @@
+//! Navmesh of a world, for the web experience previewing where one can walk
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Walkable area of a world as a triangle mesh, in the frame of the world with Z
+/// up, along with the settings it was extracted with. Walkable surfaces are
+/// merged into rectangles of the same height; neighboring rectangles meet along
+/// their edges without sharing vertices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldNavmesh {
+    /// Side of the cells the world was voxelized into, in meters
+    pub cell_size: f64,
+    /// Headroom of the agent, in meters
+    pub agent_height: f64,
+    /// Distance the walkable area keeps from walls and ledges, in meters
+    pub agent_radius: f64,
+    /// Highest step the agent walks up or down, in meters
+    pub max_climb: f64,
+    /// Steepest walkable slope, in degrees
+    pub max_slope: f64,
+    /// Walkable area, in square meters
+    pub area: f64,
+    /// Vertex positions, in meters
+    pub vertices: Vec<[f64; 3]>,
+    /// Vertex indices of each triangle, counterclockwise seen from above
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl WorldNavmesh {
+    /// The mesh as a Wavefront OBJ, for previewing in 3D tools
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::from("# Navmesh written by webify_models\n");
+        for [x, y, z] in &self.vertices {
+            writeln!(obj, "v {} {} {}", x, y, z).unwrap();
+        }
+        for [a, b, c] in &self.triangles {
+            writeln!(obj, "f {} {} {}", a + 1, b + 1, c + 1).unwrap();
+        }
+        obj
+    }
+}
+
+#[cfg(test)]
+mod world_navmesh_tests {
+    use super::*;
+
+    #[test]
+    fn it_writes_one_based_obj_faces() {
+        let navmesh = WorldNavmesh {
+            cell_size: 0.1,
+            agent_height: 1.8,
+            agent_radius: 0.3,
+            max_climb: 0.25,
+            max_slope: 45.0,
+            area: 0.5,
+            vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.5]],
+            triangles: vec![[0, 1, 2]],
+        };
+
+        assert_eq!(
+            navmesh.to_obj(),
+            "# Navmesh written by webify_models\nv 0 0 0\nv 1 0 0\nv 1 1 0.5\nf 1 2 3\n"
+        );
+    }
+}
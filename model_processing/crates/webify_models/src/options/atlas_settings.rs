@@ -0,0 +1,19 @@
+//! Knobs of the texture atlases
+
+/// Knobs of the texture atlases packed for each model with `--atlas`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasSettings {
+    /// Largest width or height of the textures packed, larger ones are left alone
+    pub max_texture_size: u32,
+    /// Largest width or height of an atlas, textures that don't fit are left alone
+    pub size: u32,
+}
+
+impl Default for AtlasSettings {
+    fn default() -> AtlasSettings {
+        AtlasSettings {
+            max_texture_size: 256,
+            size: 2048,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Knobs of the sound transcoding
+
+use std::path::PathBuf;
+
+/// Knobs of the sound transcoding to Ogg Opus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSettings {
+    /// Bitrate of the Opus stream, in kbit/s
+    pub bitrate: u16,
+    /// FFmpeg executable the sounds are transcoded with, looked up on the PATH
+    /// when it's a bare name
+    pub ffmpeg: PathBuf,
+}
+
+impl Default for AudioSettings {
+    fn default() -> AudioSettings {
+        AudioSettings {
+            bitrate: 96,
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+}
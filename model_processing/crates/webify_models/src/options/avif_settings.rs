@@ -0,0 +1,19 @@
+//! Knobs of the AVIF encoder
+
+/// Knobs of the AVIF encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvifSettings {
+    /// 1 to 100, higher is better looking and larger
+    pub quality: u8,
+    /// 1 to 10, 1 is the slowest and smallest, 10 the fastest and largest
+    pub speed: u8,
+}
+
+impl Default for AvifSettings {
+    fn default() -> AvifSettings {
+        AvifSettings {
+            quality: 80,
+            speed: 6,
+        }
+    }
+}
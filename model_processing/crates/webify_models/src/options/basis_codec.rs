@@ -0,0 +1,58 @@
+//! Block compression Basis Universal encodes KTX2 textures with
+
+use std::{io::Error, str::FromStr};
+
+use serde::Deserialize;
+
+/// Block compression Basis Universal encodes KTX2 textures with, both transcoded
+/// by the web renderer to whatever compressed format the GPU supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BasisCodec {
+    /// Small files with visible artifacts, fine for most diffuse textures
+    #[default]
+    Etc1s,
+    /// Larger files close to the source, for normal maps and hero textures
+    Uastc,
+}
+
+impl BasisCodec {
+    /// Every codec
+    pub const ALL: [BasisCodec; 2] = [BasisCodec::Etc1s, BasisCodec::Uastc];
+
+    /// Name of the codec on the command line and in `webify.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            BasisCodec::Etc1s => "etc1s",
+            BasisCodec::Uastc => "uastc",
+        }
+    }
+}
+
+impl FromStr for BasisCodec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "etc1s" => Ok(BasisCodec::Etc1s),
+            "uastc" => Ok(BasisCodec::Uastc),
+            _ => Err(Error::other(format!(
+                "Unknown KTX2 codec {:?}, expected etc1s or uastc.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod basis_codec_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_codec() {
+        for codec in BasisCodec::ALL.iter() {
+            assert_eq!(codec.name().parse::<BasisCodec>().unwrap(), *codec);
+        }
+        assert!("astc".parse::<BasisCodec>().is_err());
+    }
+}
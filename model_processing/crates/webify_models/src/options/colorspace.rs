@@ -0,0 +1,23 @@
+//! How the values of a texture's pixels are to be read
+
+use serde::Deserialize;
+
+/// Whether a texture holds colors, stored with the sRGB curve and converted to
+/// linear when sampled, or data such as directions and amounts, sampled as is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Colorspace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+impl Colorspace {
+    /// Name of the colorspace in `webify.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            Colorspace::Srgb => "srgb",
+            Colorspace::Linear => "linear",
+        }
+    }
+}
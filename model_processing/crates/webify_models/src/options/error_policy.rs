@@ -0,0 +1,57 @@
+//! What to do when a single file fails to process
+
+use std::{io::Error, str::FromStr};
+
+/// What to do when a single file fails to process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Abort the whole run on the first failure
+    #[default]
+    Strict,
+    /// Collect the failure, keep going and report everything at the end
+    Permissive,
+    /// Ask the user whether to keep going after each failure
+    Interactive,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(ErrorPolicy::Strict),
+            "permissive" => Ok(ErrorPolicy::Permissive),
+            "interactive" => Ok(ErrorPolicy::Interactive),
+            _ => Err(Error::other(format!(
+                "Unknown error policy {:?}, expected strict, permissive or interactive.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod error_policy_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_policy() {
+        assert_eq!(
+            "strict".parse::<ErrorPolicy>().unwrap(),
+            ErrorPolicy::Strict
+        );
+        assert_eq!(
+            "permissive".parse::<ErrorPolicy>().unwrap(),
+            ErrorPolicy::Permissive
+        );
+        assert_eq!(
+            "interactive".parse::<ErrorPolicy>().unwrap(),
+            ErrorPolicy::Interactive
+        );
+    }
+
+    #[test]
+    fn it_errors_on_unknown_policies() {
+        assert!("lenient".parse::<ErrorPolicy>().is_err());
+    }
+}
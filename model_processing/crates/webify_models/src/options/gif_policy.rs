@@ -0,0 +1,62 @@
+//! What happens to GIF textures, which may be animated
+
+use std::{io::Error, str::FromStr};
+
+/// What happens to GIF textures, whose animation a PNG can't hold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GifPolicy {
+    /// Convert the first frame, as shown before the animation starts
+    #[default]
+    FirstFrame,
+    /// Convert every frame into a grid, with the layout and timing next to it
+    SpriteSheet,
+    /// Leave them as they are, with a warning
+    Skip,
+}
+
+impl GifPolicy {
+    /// Every policy
+    pub const ALL: [GifPolicy; 3] = [
+        GifPolicy::FirstFrame,
+        GifPolicy::SpriteSheet,
+        GifPolicy::Skip,
+    ];
+
+    /// Name of the policy on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            GifPolicy::FirstFrame => "first-frame",
+            GifPolicy::SpriteSheet => "sprite-sheet",
+            GifPolicy::Skip => "skip",
+        }
+    }
+}
+
+impl FromStr for GifPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-frame" => Ok(GifPolicy::FirstFrame),
+            "sprite-sheet" => Ok(GifPolicy::SpriteSheet),
+            "skip" => Ok(GifPolicy::Skip),
+            _ => Err(Error::other(format!(
+                "Unknown GIF policy {:?}, expected first-frame, sprite-sheet or skip.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod gif_policy_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_policy() {
+        for policy in GifPolicy::ALL.iter() {
+            assert_eq!(policy.name().parse::<GifPolicy>().unwrap(), *policy);
+        }
+        assert!("all-frames".parse::<GifPolicy>().is_err());
+    }
+}
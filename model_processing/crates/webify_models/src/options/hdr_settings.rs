@@ -0,0 +1,12 @@
+//! Knobs of the conversion of HDR textures
+
+use crate::options::Tonemap;
+
+/// Knobs of the conversion of EXR and HDR textures to 8 bits, which `webify.toml`
+/// can override per texture
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HdrSettings {
+    pub tonemap: Tonemap,
+    /// Stops the light is scaled by before tonemapping, negative to darken
+    pub exposure: f32,
+}
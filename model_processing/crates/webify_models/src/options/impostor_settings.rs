@@ -0,0 +1,19 @@
+//! Knobs of the impostor billboards
+
+/// Knobs of the impostor billboards rendered for each model with `--impostors`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImpostorSettings {
+    /// Angles the model is rendered from, evenly spread around its vertical axis
+    pub views: u8,
+    /// Side of each view in the atlas, in pixels
+    pub frame_size: u32,
+}
+
+impl Default for ImpostorSettings {
+    fn default() -> ImpostorSettings {
+        ImpostorSettings {
+            views: 8,
+            frame_size: 128,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Knobs of the KTX2 encoder
+
+use crate::options::BasisCodec;
+
+/// Knobs of the KTX2 encoder, which `webify.toml` can override per texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ktx2Settings {
+    pub codec: BasisCodec,
+    /// ETC1S quality, 1 to 255, higher is better looking and larger
+    pub quality: u8,
+    /// UASTC effort, 0 to 4, higher is better looking and slower
+    pub uastc_level: u8,
+    /// Whether the mip chain is stored in the file, since the renderer can't
+    /// generate mipmaps for compressed textures at load time
+    pub mipmaps: bool,
+}
+
+impl Default for Ktx2Settings {
+    fn default() -> Ktx2Settings {
+        Ktx2Settings {
+            codec: BasisCodec::Etc1s,
+            quality: 128,
+            uastc_level: 2,
+            mipmaps: true,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Settings that control how a webify run behaves, as provided on the command line
+
+mod atlas_settings;
+mod audio_settings;
+mod avif_settings;
+mod basis_codec;
+mod colorspace;
+mod error_policy;
+mod gif_policy;
+mod hdr_settings;
+mod impostor_settings;
+mod ktx2_settings;
+mod navmesh_settings;
+mod output_format;
+mod oversized_textures;
+mod palette_settings;
+mod power_of_two;
+mod removal;
+mod resize_filter;
+mod run_options;
+mod stage;
+mod stage_selection;
+mod terrain_settings;
+mod tonemap;
+mod watermark_mark;
+mod watermark_placement;
+mod watermark_settings;
+mod webp_settings;
+mod white_balance;
+
+pub use self::atlas_settings::AtlasSettings;
+pub use self::audio_settings::AudioSettings;
+pub use self::avif_settings::AvifSettings;
+pub use self::basis_codec::BasisCodec;
+pub use self::colorspace::Colorspace;
+pub use self::error_policy::ErrorPolicy;
+pub use self::gif_policy::GifPolicy;
+pub use self::hdr_settings::HdrSettings;
+pub use self::impostor_settings::ImpostorSettings;
+pub use self::ktx2_settings::Ktx2Settings;
+pub use self::navmesh_settings::NavmeshSettings;
+pub use self::output_format::OutputFormat;
+pub use self::oversized_textures::OversizedTextures;
+pub use self::palette_settings::PaletteSettings;
+pub use self::power_of_two::PowerOfTwo;
+pub use self::removal::Removal;
+pub use self::resize_filter::ResizeFilter;
+pub use self::run_options::RunOptions;
+pub use self::stage::Stage;
+pub use self::stage_selection::StageSelection;
+pub use self::terrain_settings::TerrainSettings;
+pub use self::tonemap::Tonemap;
+pub use self::watermark_mark::WatermarkMark;
+pub use self::watermark_placement::WatermarkPlacement;
+pub use self::watermark_settings::WatermarkSettings;
+pub use self::webp_settings::WebpSettings;
+pub use self::white_balance::WhiteBalance;
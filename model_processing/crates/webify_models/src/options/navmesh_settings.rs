@@ -0,0 +1,29 @@
+//! Knobs of the navmesh extraction
+
+/// Knobs of the navmeshes extracted from each world with `--navmesh`, sized for a
+/// person by default
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavmeshSettings {
+    /// Side of the cells the world is voxelized into, in meters
+    pub cell_size: f64,
+    /// Headroom the agent needs, in meters
+    pub agent_height: f64,
+    /// How far the walkable area stays from walls and ledges, in meters
+    pub agent_radius: f64,
+    /// Highest step the agent walks up or down, in meters
+    pub max_climb: f64,
+    /// Steepest walkable slope, in degrees
+    pub max_slope: f64,
+}
+
+impl Default for NavmeshSettings {
+    fn default() -> NavmeshSettings {
+        NavmeshSettings {
+            cell_size: 0.1,
+            agent_height: 1.8,
+            agent_radius: 0.3,
+            max_climb: 0.25,
+            max_slope: 45.0,
+        }
+    }
+}
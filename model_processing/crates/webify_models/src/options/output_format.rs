@@ -0,0 +1,69 @@
+//! Format textures are written in
+
+use std::{io::Error, str::FromStr};
+
+use serde::Deserialize;
+
+/// Format textures are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    /// PNG first, then AVIF when it encodes and is worth it, per texture
+    Avif,
+    /// PNG first, then lossy or lossless WebP, per texture
+    Webp,
+    /// PNG first, then KTX2 compressed with Basis Universal, per texture
+    Ktx2,
+}
+
+impl OutputFormat {
+    /// Every output format
+    pub const ALL: [OutputFormat; 4] = [
+        OutputFormat::Png,
+        OutputFormat::Avif,
+        OutputFormat::Webp,
+        OutputFormat::Ktx2,
+    ];
+
+    /// Extension of the files written in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Avif => "avif",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Ktx2 => "ktx2",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(OutputFormat::Png),
+            "avif" => Ok(OutputFormat::Avif),
+            "webp" => Ok(OutputFormat::Webp),
+            "ktx2" => Ok(OutputFormat::Ktx2),
+            _ => Err(Error::other(format!(
+                "Unknown output format {:?}, expected png, avif, webp or ktx2.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod output_format_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_format() {
+        for format in OutputFormat::ALL.iter() {
+            assert_eq!(format.extension().parse::<OutputFormat>().unwrap(), *format);
+        }
+        assert!("jxl".parse::<OutputFormat>().is_err());
+    }
+}
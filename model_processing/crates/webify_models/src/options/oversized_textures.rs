@@ -0,0 +1,55 @@
+//! What happens to textures larger than some GPUs can upload
+
+use std::{io::Error, str::FromStr};
+
+/// What happens to textures larger than `WEBGL_MAX_TEXTURE_DIMENSION` on their
+/// longest side, which fail to upload on some GPUs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizedTextures {
+    /// Keep them as they are, with a warning
+    #[default]
+    Keep,
+    /// Downscale them to the limit, with a warning
+    Downscale,
+}
+
+impl OversizedTextures {
+    /// Every policy
+    pub const ALL: [OversizedTextures; 2] = [OversizedTextures::Keep, OversizedTextures::Downscale];
+
+    /// Name of the policy on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            OversizedTextures::Keep => "keep",
+            OversizedTextures::Downscale => "downscale",
+        }
+    }
+}
+
+impl FromStr for OversizedTextures {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(OversizedTextures::Keep),
+            "downscale" => Ok(OversizedTextures::Downscale),
+            _ => Err(Error::other(format!(
+                "Unknown oversized textures policy {:?}, expected keep or downscale.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod oversized_textures_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_policy() {
+        for policy in OversizedTextures::ALL.iter() {
+            assert_eq!(policy.name().parse::<OversizedTextures>().unwrap(), *policy);
+        }
+        assert!("tile".parse::<OversizedTextures>().is_err());
+    }
+}
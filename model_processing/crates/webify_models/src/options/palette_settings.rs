@@ -0,0 +1,21 @@
+//! Knobs of the palette quantization
+
+/// Knobs of the palette quantization of textures with `--quantize`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteSettings {
+    /// Most colors of the palette, from 2 to 256
+    pub colors: u16,
+    /// Lowest peak signal-to-noise ratio of the quantized texture against the
+    /// original, in dB: textures the palette would visibly degrade are left as they
+    /// are
+    pub min_psnr: f64,
+}
+
+impl Default for PaletteSettings {
+    fn default() -> PaletteSettings {
+        PaletteSettings {
+            colors: 256,
+            min_psnr: 40.0,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! How textures are resized to power of two dimensions
+
+use std::{io::Error, str::FromStr};
+
+/// Which power of two each side of a texture is resized to, for the WebGL paths
+/// that need power of two textures to mipmap and repeat them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerOfTwo {
+    /// The closest one, the larger on a tie
+    Nearest,
+    /// The largest one that isn't larger than the side, never upscaling
+    Floor,
+}
+
+impl PowerOfTwo {
+    /// Every mode
+    pub const ALL: [PowerOfTwo; 2] = [PowerOfTwo::Nearest, PowerOfTwo::Floor];
+
+    /// Name of the mode on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerOfTwo::Nearest => "nearest",
+            PowerOfTwo::Floor => "floor",
+        }
+    }
+
+    /// Power of two a side of `size` pixels is resized to
+    pub fn round(self, size: u32) -> u32 {
+        if size.is_power_of_two() {
+            return size;
+        }
+        let floor = 1 << (31 - size.leading_zeros());
+        match self {
+            PowerOfTwo::Floor => floor,
+            PowerOfTwo::Nearest if size - floor < floor * 2 - size => floor,
+            PowerOfTwo::Nearest => floor * 2,
+        }
+    }
+}
+
+impl FromStr for PowerOfTwo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(PowerOfTwo::Nearest),
+            "floor" => Ok(PowerOfTwo::Floor),
+            _ => Err(Error::other(format!(
+                "Unknown power of two mode {:?}, expected nearest or floor.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod power_of_two_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_mode() {
+        for mode in PowerOfTwo::ALL.iter() {
+            assert_eq!(mode.name().parse::<PowerOfTwo>().unwrap(), *mode);
+        }
+        assert!("ceil".parse::<PowerOfTwo>().is_err());
+    }
+
+    #[test]
+    fn it_rounds_to_a_power_of_two() {
+        assert_eq!(PowerOfTwo::Nearest.round(1), 1);
+        assert_eq!(PowerOfTwo::Nearest.round(512), 512);
+        assert_eq!(PowerOfTwo::Nearest.round(700), 512);
+        assert_eq!(PowerOfTwo::Nearest.round(768), 1024);
+        assert_eq!(PowerOfTwo::Nearest.round(1000), 1024);
+        assert_eq!(PowerOfTwo::Nearest.round(3), 4);
+        assert_eq!(PowerOfTwo::Floor.round(1000), 512);
+        assert_eq!(PowerOfTwo::Floor.round(3), 2);
+        assert_eq!(PowerOfTwo::Floor.round(1024), 1024);
+    }
+}
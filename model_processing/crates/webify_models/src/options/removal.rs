@@ -0,0 +1,33 @@
+//! What happens to the files the pipeline deletes
+
+use std::path::{Path, PathBuf};
+
+/// What happens to the files the pipeline deletes, like originals converted to PNG
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Removal {
+    /// Remove the files for good
+    #[default]
+    Delete,
+    /// Move the files to the trash of the operating system
+    Trash,
+    /// Move the files to this directory
+    TrashDir(PathBuf),
+    /// Move the files into `dir` at the path they had in `root`, mirroring its
+    /// tree. The pipeline sets `root` to the directory it processes.
+    BackupDir { dir: PathBuf, root: PathBuf },
+    /// Rename the files where they are, with `.orig` appended to their name
+    Orig,
+}
+
+impl Removal {
+    /// The removal with the tree mirrored by `--backup-dir` rooted at `root`
+    pub fn rooted_at(self, root: &Path) -> Removal {
+        match self {
+            Removal::BackupDir { dir, .. } => Removal::BackupDir {
+                dir,
+                root: root.to_path_buf(),
+            },
+            removal => removal,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Filter textures are resized with when downscaled
+
+use std::{io::Error, str::FromStr};
+
+use image::imageops::FilterType;
+
+/// Resampling filter of downscaled textures, from the fastest and blockiest to the
+/// slowest and sharpest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Nearest neighbor, keeps hard pixel edges, e.g. for pixel art or masks
+    Nearest,
+    /// Linear, smooth and a little blurry
+    Triangle,
+    /// Cubic, sharper than linear
+    CatmullRom,
+    /// Gaussian, the softest
+    Gaussian,
+    /// Lanczos with a window of 3, the sharpest
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    /// Every filter
+    pub const ALL: [ResizeFilter; 5] = [
+        ResizeFilter::Nearest,
+        ResizeFilter::Triangle,
+        ResizeFilter::CatmullRom,
+        ResizeFilter::Gaussian,
+        ResizeFilter::Lanczos3,
+    ];
+
+    /// Name of the filter on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            ResizeFilter::Nearest => "nearest",
+            ResizeFilter::Triangle => "triangle",
+            ResizeFilter::CatmullRom => "catmull-rom",
+            ResizeFilter::Gaussian => "gaussian",
+            ResizeFilter::Lanczos3 => "lanczos3",
+        }
+    }
+
+    /// Filter of the image crate
+    pub fn filter_type(self) -> FilterType {
+        match self {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl FromStr for ResizeFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResizeFilter::ALL
+            .iter()
+            .find(|filter| filter.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                Error::other(format!(
+                    "Unknown resize filter {:?}, expected nearest, triangle, catmull-rom, gaussian or lanczos3.",
+                    s
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod resize_filter_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_filter() {
+        for filter in ResizeFilter::ALL.iter() {
+            assert_eq!(filter.name().parse::<ResizeFilter>().unwrap(), *filter);
+        }
+        assert!("bicubic".parse::<ResizeFilter>().is_err());
+    }
+}
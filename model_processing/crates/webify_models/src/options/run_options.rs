@@ -0,0 +1,143 @@
+//! Options for a processing run
+
+use std::{path::PathBuf, time::Duration};
+
+use crate::options::{
+    AtlasSettings, AudioSettings, AvifSettings, ErrorPolicy, GifPolicy, HdrSettings,
+    ImpostorSettings, Ktx2Settings, NavmeshSettings, OutputFormat, OversizedTextures,
+    PaletteSettings, PowerOfTwo, Removal, ResizeFilter, StageSelection, TerrainSettings,
+    WatermarkMark, WatermarkSettings, WebpSettings,
+};
+
+/// Options for a processing run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunOptions {
+    /// What to do when a single file fails to process
+    pub error_policy: ErrorPolicy,
+    /// Fail the run when any warning was collected
+    pub deny_warnings: bool,
+    /// Record how long every file takes in every stage
+    pub timings: bool,
+    /// Largest estimated GPU memory a single model may use, in bytes
+    pub gpu_budget: Option<u64>,
+    /// Largest memory the decoded textures of a run may take at once, in bytes
+    pub memory_budget: Option<u64>,
+    /// Stages of the pipeline that should run
+    pub stages: StageSelection,
+    /// Move textures duplicated across the models of a collection to its shared pool
+    pub shared_textures: bool,
+    /// Keep one copy of byte-identical textures across the library, pointing the
+    /// references of the others at it
+    pub dedup_textures: bool,
+    /// Rename the processed textures to the SHA-256 of their contents, for CDNs to
+    /// cache them for good
+    pub hash_names: bool,
+    /// Percent-encode the references written to the web manifest, for names that
+    /// can't be sanitized
+    pub url_encode_references: bool,
+    /// Record the dimensions of each model's textures as custom metadata in its SDF
+    pub sdf_texture_metadata: bool,
+    /// Render a billboard impostor of each model, an atlas of views for distant
+    /// rendering
+    pub impostors: bool,
+    /// Knobs of the impostor billboards, with `--impostors`
+    pub impostor: ImpostorSettings,
+    /// Pack the small textures of each model into an atlas, remapping the UVs of its
+    /// meshes
+    pub atlases: bool,
+    /// Knobs of the texture atlases, with `--atlas`
+    pub atlas: AtlasSettings,
+    /// Pack the occlusion, roughness and metalness maps sharing a name into ORM
+    /// textures
+    pub pack_orm: bool,
+    /// Extract a navmesh of the walkable area of each world from its collisions
+    pub navmeshes: bool,
+    /// Knobs of the navmesh extraction, with `--navmesh`
+    pub navmesh: NavmeshSettings,
+    /// Bake the heightmaps of each world into tiled terrain meshes with levels of
+    /// detail, written as glTF
+    pub terrains: bool,
+    /// Knobs of the terrain baking, with `--terrain`
+    pub terrain: TerrainSettings,
+    /// Copy the models here and process the copy, leaving the source untouched
+    pub output: Option<PathBuf>,
+    /// Process a scratch copy of the models and report how their text files would
+    /// be rewritten, leaving the source untouched
+    pub dry_run: bool,
+    /// Write the run report here as JSON
+    pub json_report: Option<PathBuf>,
+    /// Write the run report here as an HTML page, with the thumbnails of the
+    /// color adjustments
+    pub html_report: Option<PathBuf>,
+    /// Append the report here as NDJSON events while the run goes, so a crash
+    /// doesn't lose it
+    pub event_log: Option<PathBuf>,
+    /// Append the operations of the run on each file to the signed audit log of the
+    /// library
+    pub audit: bool,
+    /// Name the last git committer of the file of each failure and warning in the
+    /// report
+    pub git_authors: bool,
+    /// Open an issue for each model that failed in the tracker of `[issues]`
+    pub file_issues: bool,
+    /// Append the size of the library and the failures of the run to the trends
+    /// log of the library
+    pub record_trends: bool,
+    /// Allow destructive runs on system paths with elevated permissions
+    pub allow_system_paths: bool,
+    /// Halt the run after this many failures in a row, which points at the run
+    /// itself being broken rather than the files
+    pub max_consecutive_failures: Option<usize>,
+    /// Stop the run cleanly once it has been going for this long, textures whose
+    /// settings changed going first; the next run picks up where it stopped
+    pub time_budget: Option<Duration>,
+    /// What happens to the files the pipeline deletes
+    pub removal: Removal,
+    /// Directories processed before the rest of the batch, in order. The pipeline
+    /// resolves them relative to the processed directory.
+    pub first: Vec<PathBuf>,
+    /// Extensions scanned as textures on top of the defaults and `webify.toml`
+    pub extensions: Vec<String>,
+    /// Format textures are written in
+    pub format: OutputFormat,
+    /// Knobs of the AVIF encoder, with `--format avif`
+    pub avif: AvifSettings,
+    /// Knobs of the WebP encoder, with `--format webp`
+    pub webp: WebpSettings,
+    /// Knobs of the KTX2 encoder, with `--format ktx2`
+    pub ktx2: Ktx2Settings,
+    /// How EXR and HDR textures are brought down to 8 bits
+    pub hdr: HdrSettings,
+    /// What happens to textures too large for some GPUs
+    pub oversized_textures: OversizedTextures,
+    /// What happens to GIF textures, which may be animated
+    pub gif: GifPolicy,
+    /// Largest width or height of any texture, larger ones are downscaled to it
+    pub max_dim: Option<u32>,
+    /// Largest file size of any converted texture, in bytes, larger ones are
+    /// encoded at a lower quality and downscaled until they fit
+    pub max_bytes: Option<u64>,
+    /// Filter downscaled textures are resized with
+    pub resize_filter: ResizeFilter,
+    /// Resize textures to power of two dimensions, rounding each side this way
+    pub power_of_two: Option<PowerOfTwo>,
+    /// Write the mip chain of textures that aren't KTX2, which embeds it, as a
+    /// `name_mipN.png` pyramid next to them
+    pub mipmaps: bool,
+    /// Quantize PNG textures to an indexed palette when it doesn't visibly degrade
+    /// them, for flat-colored ones like signage and decals
+    pub quantize: bool,
+    /// Knobs of the palette quantization, with `--quantize`
+    pub palette: PaletteSettings,
+    /// Strip EXIF, XMP, text and timestamp metadata from the converted textures, so
+    /// camera serial numbers and GPS locations of photos aren't published
+    pub strip_metadata: bool,
+    /// Text or PNG overlay textures are watermarked with, for public preview
+    /// builds. Only runs writing to `--output` can watermark, never the library
+    /// itself.
+    pub watermark: Option<WatermarkMark>,
+    /// Knobs of the watermark, with `--watermark`
+    pub watermark_settings: WatermarkSettings,
+    /// Knobs of the sound transcoding
+    pub audio: AudioSettings,
+}
@@ -0,0 +1,88 @@
+//! The stages that make up the `all` pipeline
+
+use std::{fmt, io::Error, str::FromStr};
+
+/// A stage of the `all` pipeline, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Move stray textures into the model's materials/textures directory
+    Move,
+    /// Convert textures to PNG
+    Convert,
+    /// Shrink textures down to their category's maximum size
+    Downscale,
+    /// Rewrite texture references inside meshes
+    Rewrite,
+}
+
+impl Stage {
+    /// Every stage, in the order they run
+    pub const ALL: [Stage; 4] = [
+        Stage::Move,
+        Stage::Convert,
+        Stage::Downscale,
+        Stage::Rewrite,
+    ];
+
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Move => "move",
+            Stage::Convert => "convert",
+            Stage::Downscale => "downscale",
+            Stage::Rewrite => "rewrite",
+        }
+    }
+
+    /// Stages whose output this stage relies on. Rewriting points mesh references
+    /// at `materials/textures/*.png`, which only exist once textures were moved
+    /// and converted.
+    pub fn dependencies(self) -> &'static [Stage] {
+        match self {
+            Stage::Rewrite => &[Stage::Move, Stage::Convert],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Stage {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Stage::ALL
+            .iter()
+            .find(|stage| stage.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Stage::ALL.iter().map(|stage| stage.name()).collect();
+                Error::other(format!(
+                    "Unknown stage {:?}, expected one of {}.",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod stage_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_stage_name() {
+        for stage in Stage::ALL.iter() {
+            assert_eq!(stage.name().parse::<Stage>().unwrap(), *stage);
+        }
+    }
+
+    #[test]
+    fn it_errors_on_unknown_stages() {
+        assert!("optimize".parse::<Stage>().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Which stages of the `all` pipeline are enabled
+
+use std::{collections::BTreeSet, io::Error};
+
+use crate::options::Stage;
+
+/// Which stages of the `all` pipeline are enabled, every stage by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageSelection {
+    enabled: BTreeSet<Stage>,
+}
+
+impl Default for StageSelection {
+    fn default() -> Self {
+        StageSelection {
+            enabled: Stage::ALL.iter().copied().collect(),
+        }
+    }
+}
+
+impl StageSelection {
+    /// Every stage except the skipped ones
+    pub fn skipping(skipped: &[Stage]) -> Result<StageSelection, Error> {
+        let selection = StageSelection {
+            enabled: Stage::ALL
+                .iter()
+                .filter(|stage| !skipped.contains(stage))
+                .copied()
+                .collect(),
+        };
+        selection.validate()?;
+
+        Ok(selection)
+    }
+
+    /// Only the listed stages
+    pub fn only(stages: &[Stage]) -> Result<StageSelection, Error> {
+        let selection = StageSelection {
+            enabled: stages.iter().copied().collect(),
+        };
+        selection.validate()?;
+
+        Ok(selection)
+    }
+
+    /// Whether the stage should run
+    pub fn is_enabled(&self, stage: Stage) -> bool {
+        self.enabled.contains(&stage)
+    }
+
+    /// Make sure every enabled stage has the stages it depends on enabled too
+    fn validate(&self) -> Result<(), Error> {
+        for stage in &self.enabled {
+            if let Some(missing) = stage
+                .dependencies()
+                .iter()
+                .find(|dependency| !self.enabled.contains(dependency))
+            {
+                return Err(Error::other(format!(
+                    "The {} stage depends on the {} stage, enable {} or disable {} as well.",
+                    stage, missing, missing, stage
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod stage_selection_tests {
+    use super::*;
+
+    #[test]
+    fn it_enables_everything_by_default() {
+        let selection = StageSelection::default();
+        assert!(Stage::ALL.iter().all(|stage| selection.is_enabled(*stage)));
+    }
+
+    #[test]
+    fn it_skips_stages() {
+        let selection = StageSelection::skipping(&[Stage::Downscale]).unwrap();
+        assert!(!selection.is_enabled(Stage::Downscale));
+        assert!(selection.is_enabled(Stage::Convert));
+    }
+
+    #[test]
+    fn it_only_enables_listed_stages() {
+        let selection = StageSelection::only(&[Stage::Convert, Stage::Downscale]).unwrap();
+        assert!(selection.is_enabled(Stage::Convert));
+        assert!(!selection.is_enabled(Stage::Move));
+        assert!(!selection.is_enabled(Stage::Rewrite));
+    }
+
+    #[test]
+    fn it_rejects_stages_missing_their_dependencies() {
+        assert!(StageSelection::skipping(&[Stage::Move]).is_err());
+        assert!(StageSelection::only(&[Stage::Rewrite, Stage::Convert]).is_err());
+        assert!(StageSelection::skipping(&[Stage::Move, Stage::Rewrite]).is_ok());
+    }
+}
@@ -0,0 +1,20 @@
+//! Knobs of the terrain baking
+
+/// Knobs of the terrains baked from the heightmaps of each world with `--terrain`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainSettings {
+    /// Quads along each side of a tile at full detail, a power of two
+    pub tile_size: u32,
+    /// Levels of detail of each tile, every one with half the quads per side of the
+    /// previous one
+    pub lods: u8,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> TerrainSettings {
+        TerrainSettings {
+            tile_size: 64,
+            lods: 3,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Operator bringing HDR textures down to 8 bits
+
+use std::{io::Error, str::FromStr};
+
+use serde::Deserialize;
+
+/// Curve mapping the unbounded light of HDR textures to the 0 to 1 range of 8 bit
+/// formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tonemap {
+    /// `x / (1 + x)`, soft and a little flat
+    Reinhard,
+    /// The filmic curve of ACES, with more contrast and saturated highlights
+    #[default]
+    Aces,
+}
+
+impl Tonemap {
+    /// Every operator
+    pub const ALL: [Tonemap; 2] = [Tonemap::Reinhard, Tonemap::Aces];
+
+    /// Name of the operator on the command line and in `webify.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            Tonemap::Reinhard => "reinhard",
+            Tonemap::Aces => "aces",
+        }
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reinhard" => Ok(Tonemap::Reinhard),
+            "aces" => Ok(Tonemap::Aces),
+            _ => Err(Error::other(format!(
+                "Unknown tonemapping operator {:?}, expected reinhard or aces.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tonemap_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_operator() {
+        for tonemap in Tonemap::ALL.iter() {
+            assert_eq!(tonemap.name().parse::<Tonemap>().unwrap(), *tonemap);
+        }
+        assert!("filmic".parse::<Tonemap>().is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! What the watermark of preview builds is made of
+
+use std::{io::Error, path::PathBuf, str::FromStr};
+
+/// What textures are watermarked with in preview builds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatermarkMark {
+    /// A line of text, drawn in white with a black outline so it shows on any
+    /// texture
+    Text(String),
+    /// A PNG laid over the texture with its alpha, like a logo
+    Overlay(PathBuf),
+}
+
+impl WatermarkMark {
+    /// The text, or the path of the overlay, as given on the command line
+    pub fn describe(&self) -> String {
+        match self {
+            WatermarkMark::Text(text) => text.clone(),
+            WatermarkMark::Overlay(path) => path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// A value ending in `.png` is the path of an overlay, anything else is text
+impl FromStr for WatermarkMark {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(Error::other("The watermark can't be empty."));
+        }
+        match s.to_lowercase().ends_with(".png") {
+            true => Ok(WatermarkMark::Overlay(PathBuf::from(s))),
+            false => Ok(WatermarkMark::Text(String::from(s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod watermark_mark_tests {
+    use super::*;
+
+    #[test]
+    fn it_tells_overlays_from_text() {
+        assert_eq!(
+            "branding/preview.PNG".parse::<WatermarkMark>().unwrap(),
+            WatermarkMark::Overlay(PathBuf::from("branding/preview.PNG"))
+        );
+        assert_eq!(
+            "PREVIEW build".parse::<WatermarkMark>().unwrap(),
+            WatermarkMark::Text(String::from("PREVIEW build"))
+        );
+        assert!(" ".parse::<WatermarkMark>().is_err());
+    }
+}
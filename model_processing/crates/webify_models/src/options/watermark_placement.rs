@@ -0,0 +1,59 @@
+//! Where the watermark goes on textures
+
+use std::{io::Error, str::FromStr};
+
+/// Where the watermark of preview builds goes on textures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatermarkPlacement {
+    /// Once, in the bottom right corner
+    #[default]
+    Corner,
+    /// Repeated over the whole texture, every other row shifted by half a mark,
+    /// so it can't be cropped out
+    Tiled,
+}
+
+impl WatermarkPlacement {
+    /// Every placement
+    pub const ALL: [WatermarkPlacement; 2] =
+        [WatermarkPlacement::Corner, WatermarkPlacement::Tiled];
+
+    /// Name of the placement on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            WatermarkPlacement::Corner => "corner",
+            WatermarkPlacement::Tiled => "tiled",
+        }
+    }
+}
+
+impl FromStr for WatermarkPlacement {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "corner" => Ok(WatermarkPlacement::Corner),
+            "tiled" => Ok(WatermarkPlacement::Tiled),
+            _ => Err(Error::other(format!(
+                "Unknown watermark placement {:?}, expected corner or tiled.",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod watermark_placement_tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_every_placement() {
+        for placement in WatermarkPlacement::ALL.iter() {
+            assert_eq!(
+                placement.name().parse::<WatermarkPlacement>().unwrap(),
+                *placement
+            );
+        }
+        assert!("center".parse::<WatermarkPlacement>().is_err());
+    }
+}
@@ -0,0 +1,21 @@
+//! Knobs of the watermark of preview builds
+
+use crate::options::WatermarkPlacement;
+
+/// Knobs of the watermark of textures with `--watermark`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatermarkSettings {
+    /// Where the watermark goes
+    pub placement: WatermarkPlacement,
+    /// How opaque the watermark is over the texture, from 0 to 1
+    pub opacity: f64,
+}
+
+impl Default for WatermarkSettings {
+    fn default() -> WatermarkSettings {
+        WatermarkSettings {
+            placement: WatermarkPlacement::default(),
+            opacity: 0.5,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Knobs of the WebP encoder
+
+/// Knobs of the WebP encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebpSettings {
+    /// 1 to 100, higher is better looking and larger, ignored when lossless
+    pub quality: u8,
+    /// Whether the pixels are kept exactly, at the cost of larger files
+    pub lossless: bool,
+}
+
+impl Default for WebpSettings {
+    fn default() -> WebpSettings {
+        WebpSettings {
+            quality: 80,
+            lossless: false,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Light a batch of scans was shot under, corrected by a white balance preset
+
+use serde::Deserialize;
+
+/// Light scans were shot under with the camera balanced for daylight, whose cast
+/// the color adjustment takes out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhiteBalance {
+    /// Incandescent bulbs, around 3200K, leaving an orange cast
+    Tungsten,
+    /// Office tubes, around 4000K, leaving a yellow-green cast
+    Fluorescent,
+    /// Overcast sky, around 7000K, leaving a blue cast
+    Cloudy,
+    /// Open shade, around 8000K, leaving a stronger blue cast
+    Shade,
+}
+
+impl WhiteBalance {
+    /// Every preset
+    pub const ALL: [WhiteBalance; 4] = [
+        WhiteBalance::Tungsten,
+        WhiteBalance::Fluorescent,
+        WhiteBalance::Cloudy,
+        WhiteBalance::Shade,
+    ];
+
+    /// Name of the preset in `webify.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            WhiteBalance::Tungsten => "tungsten",
+            WhiteBalance::Fluorescent => "fluorescent",
+            WhiteBalance::Cloudy => "cloudy",
+            WhiteBalance::Shade => "shade",
+        }
+    }
+
+    /// Gains of the red, green and blue linear light taking out the cast, green
+    /// left as it is so the brightness barely moves
+    pub fn gains(self) -> [f32; 3] {
+        match self {
+            WhiteBalance::Tungsten => [0.72, 1.0, 1.55],
+            WhiteBalance::Fluorescent => [0.9, 0.94, 1.2],
+            WhiteBalance::Cloudy => [1.08, 1.0, 0.9],
+            WhiteBalance::Shade => [1.16, 1.0, 0.82],
+        }
+    }
+}
+
+#[cfg(test)]
+mod white_balance_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_every_preset_by_name() {
+        for white_balance in WhiteBalance::ALL.iter() {
+            let toml = format!("preset = {:?}", white_balance.name());
+            let table: std::collections::BTreeMap<String, WhiteBalance> =
+                toml::from_str(&toml).unwrap();
+            assert_eq!(table["preset"], *white_balance);
+        }
+    }
+
+    #[test]
+    fn it_warms_cold_light_and_cools_warm_light() {
+        let [red, _, blue] = WhiteBalance::Tungsten.gains();
+        assert!(red < 1.0 && blue > 1.0);
+        let [red, _, blue] = WhiteBalance::Shade.gains();
+        assert!(red > 1.0 && blue < 1.0);
+    }
+}
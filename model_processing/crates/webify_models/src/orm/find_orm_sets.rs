@@ -0,0 +1,64 @@
+//! Find the occlusion, roughness and metalness maps of a model that share a name
+
+use std::{collections::BTreeMap, io::Error, path::Path, result::Result};
+
+use crate::image_processing::{TextureRole, TEXTURE_IMAGE_TYPES};
+use crate::orm::OrmSet;
+use crate::walk::walk_dir;
+
+/// Last words of roughness maps that hold glossiness instead
+const GLOSSINESS_SUFFIXES: [&str; 2] = ["glossiness", "gloss"];
+
+/// Group the occlusion, roughness and metalness maps of the model by the name
+/// they share once their role suffix is dropped, whatever the case and wherever
+/// they are in the model, keeping the groups of at least two maps. The first map
+/// of a role, by path, wins when a name has several.
+pub fn find_orm_sets(model_dir: &Path) -> Result<Vec<OrmSet>, Error> {
+    let textures = walk_dir(model_dir, &|path: &Path| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| TEXTURE_IMAGE_TYPES.contains(&e.to_lowercase().as_str()))
+    })?;
+
+    let mut sets: BTreeMap<String, OrmSet> = BTreeMap::new();
+    for texture in textures {
+        let role = TextureRole::from_name(&texture);
+        if !matches!(
+            role,
+            Some(TextureRole::Occlusion)
+                | Some(TextureRole::Roughness)
+                | Some(TextureRole::Metalness)
+        ) {
+            continue;
+        }
+        let stem = texture
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (base, suffix) = match stem.rfind(['_', '-', '.', ' ']) {
+            Some(index) if index > 0 => (&stem[..index], stem[index + 1..].to_lowercase()),
+            _ => continue,
+        };
+
+        let set = sets.entry(base.to_lowercase()).or_insert_with(|| OrmSet {
+            base: base.to_string(),
+            ..OrmSet::default()
+        });
+        let map = match role {
+            Some(TextureRole::Occlusion) => &mut set.occlusion,
+            Some(TextureRole::Roughness) => &mut set.roughness,
+            _ => &mut set.metalness,
+        };
+        if map.is_none() {
+            *map = Some(texture.clone());
+            if role == Some(TextureRole::Roughness) {
+                set.glossiness = GLOSSINESS_SUFFIXES.contains(&suffix.as_str());
+            }
+        }
+    }
+
+    Ok(sets
+        .into_values()
+        .filter(|set| set.map_count() >= 2)
+        .collect())
+}
@@ -0,0 +1,58 @@
+//! Find the ORM textures of a model and the maps packed in them
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::image_processing::TEXTURE_IMAGE_TYPES;
+use crate::orm::OrmMetadata;
+
+/// The ORM textures of the model, in whichever format the texture pipeline left
+/// them, each with the metadata next to it, in order. Metadata without its
+/// texture is skipped.
+pub fn find_orm_textures(model_dir: &Path) -> Result<Vec<(PathBuf, OrmMetadata)>, Error> {
+    let textures_dir = model_dir.join("materials").join("textures");
+    if !textures_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&textures_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, Error>>()?;
+    files.sort();
+
+    let mut orm_textures = Vec::new();
+    for metadata_path in &files {
+        let stem = match metadata_path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) if stem.ends_with("_orm") => stem,
+            _ => continue,
+        };
+        if metadata_path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let texture = files.iter().find(|path| {
+            path.file_stem().and_then(|s| s.to_str()) == Some(stem)
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| TEXTURE_IMAGE_TYPES.contains(&e.to_lowercase().as_str()))
+        });
+        let texture = match texture {
+            Some(texture) => texture.clone(),
+            None => continue,
+        };
+
+        let metadata = serde_json::from_str(&fs::read_to_string(metadata_path)?).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid ORM metadata {:?}: {}", metadata_path, e),
+            )
+        })?;
+        orm_textures.push((texture, metadata));
+    }
+
+    Ok(orm_textures)
+}
@@ -0,0 +1,174 @@
+//! Pack the occlusion, roughness and metalness maps of a model into ORM textures
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use image::{imageops, GrayImage, Rgb, RgbImage};
+
+use crate::image_processing::decode_texture;
+use crate::options::RunOptions;
+use crate::orm::{find_orm_sets, OrmMetadata};
+
+/// Pack each set of occlusion, roughness and metalness maps of the model sharing a
+/// name into `<name>_orm.png` in its textures directory, occlusion in red,
+/// roughness in green and metalness in blue, with the maps packed in each channel
+/// in `<name>_orm.json`. Maps are read as grayscale, glossiness inverted into
+/// roughness, and resized to the largest of them. The maps stay as they are for
+/// the materials sampling them, and the ORM textures then go through the texture
+/// pipeline like any texture. Returns the ORM textures written; sets packed by a
+/// previous run, and those with a map the converter can't read, are left alone.
+pub fn generate_orm(model_dir: &Path, options: &RunOptions) -> Result<Vec<PathBuf>, Error> {
+    let textures_dir = model_dir.join("materials").join("textures");
+    let mut written = Vec::new();
+    for set in find_orm_sets(model_dir)? {
+        let stem = format!("{}_orm", set.base);
+        let metadata_path = textures_dir.join(format!("{}.json", stem));
+        if metadata_path.is_file() {
+            continue;
+        }
+
+        let maps: Vec<Option<GrayImage>> = match [&set.occlusion, &set.roughness, &set.metalness]
+            .iter()
+            .map(|map| {
+                map.as_ref()
+                    .map(|path| decode_texture(path, &options.hdr).map(|image| image.to_luma8()))
+                    .transpose()
+            })
+            .collect()
+        {
+            Ok(maps) => maps,
+            Err(_) => continue,
+        };
+        let width = maps
+            .iter()
+            .flatten()
+            .map(|map| map.width())
+            .max()
+            .unwrap_or(0);
+        let height = maps
+            .iter()
+            .flatten()
+            .map(|map| map.height())
+            .max()
+            .unwrap_or(0);
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let maps: Vec<Option<GrayImage>> = maps
+            .into_iter()
+            .map(|map| {
+                map.map(|map| match map.dimensions() == (width, height) {
+                    true => map,
+                    false => {
+                        imageops::resize(&map, width, height, options.resize_filter.filter_type())
+                    }
+                })
+            })
+            .collect();
+
+        let channel = |index: usize, x: u32, y: u32, absent: u8| {
+            maps[index]
+                .as_ref()
+                .map_or(absent, |map| map.get_pixel(x, y)[0])
+        };
+        let orm = RgbImage::from_fn(width, height, |x, y| {
+            let roughness = channel(1, x, y, u8::MAX);
+            Rgb([
+                channel(0, x, y, u8::MAX),
+                match set.glossiness {
+                    true => u8::MAX - roughness,
+                    false => roughness,
+                },
+                channel(2, x, y, 0),
+            ])
+        });
+
+        let metadata = OrmMetadata {
+            occlusion: set
+                .occlusion
+                .as_deref()
+                .map(|p| relative_reference(p, model_dir)),
+            roughness: set
+                .roughness
+                .as_deref()
+                .map(|p| relative_reference(p, model_dir)),
+            metalness: set
+                .metalness
+                .as_deref()
+                .map(|p| relative_reference(p, model_dir)),
+            inverted_glossiness: set.glossiness,
+        };
+        fs::create_dir_all(&textures_dir)?;
+        let orm_path = textures_dir.join(format!("{}.png", stem));
+        orm.save(&orm_path)
+            .map_err(|e| Error::other(format!("Could not save {:?}: {:?}", orm_path, e)))?;
+        fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+        written.push(orm_path);
+    }
+
+    Ok(written)
+}
+
+/// Path of the file relative to the model directory, with `/` separators
+fn relative_reference(path: &Path, model_dir: &Path) -> String {
+    path.strip_prefix(model_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod generate_orm_tests {
+    use super::*;
+
+    use image::Luma;
+
+    #[test]
+    fn it_packs_the_maps_sharing_a_name() -> Result<(), Error> {
+        let model_dir = Path::new("tests")
+            .join("orm")
+            .join("test_run_generate_orm")
+            .join("crate");
+        let textures_dir = model_dir.join("materials").join("textures");
+        fs::create_dir_all(&textures_dir)?;
+        let save = |name: &str, width: u32, value: u8| {
+            GrayImage::from_pixel(width, 4, Luma([value]))
+                .save(textures_dir.join(name))
+                .map_err(Error::other)
+        };
+        save("Crate_AO.png", 8, 200)?;
+        save("crate_gloss.png", 4, 60)?;
+        // Alone of its name, nothing to pack it with
+        save("lid_metallic.png", 8, 255)?;
+
+        let written = generate_orm(&model_dir, &RunOptions::default())?;
+
+        let orm_path = textures_dir.join("Crate_orm.png");
+        assert_eq!(written, vec![orm_path.clone()]);
+        let orm = image::open(&orm_path).map_err(Error::other)?.to_rgb8();
+        assert_eq!(orm.dimensions(), (8, 4));
+        assert_eq!(orm.get_pixel(3, 2), &Rgb([200, 195, 0]));
+        let metadata: OrmMetadata =
+            serde_json::from_str(&fs::read_to_string(textures_dir.join("Crate_orm.json"))?)?;
+        assert_eq!(
+            metadata,
+            OrmMetadata {
+                occlusion: Some(String::from("materials/textures/Crate_AO.png")),
+                roughness: Some(String::from("materials/textures/crate_gloss.png")),
+                metalness: None,
+                inverted_glossiness: true,
+            }
+        );
+        assert!(textures_dir.join("crate_gloss.png").is_file());
+        assert!(generate_orm(&model_dir, &RunOptions::default())?.is_empty());
+
+        fs::remove_dir_all(model_dir.parent().unwrap())?;
+        Ok(())
+    }
+}
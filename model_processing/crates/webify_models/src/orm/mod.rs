@@ -0,0 +1,14 @@
+//! ORM textures: the occlusion, roughness and metalness maps of a material packed
+//! in the red, green and blue of a single texture, the layout glTF samples them in
+
+mod find_orm_sets;
+mod find_orm_textures;
+mod generate_orm;
+mod orm_metadata;
+mod orm_set;
+
+pub use self::find_orm_sets::find_orm_sets;
+pub use self::find_orm_textures::find_orm_textures;
+pub use self::generate_orm::generate_orm;
+pub use self::orm_metadata::OrmMetadata;
+pub use self::orm_set::OrmSet;
@@ -0,0 +1,19 @@
+//! Mapping of an ORM texture, the maps packed in each of its channels
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Maps packed in an ORM texture, by the path they had relative to the model
+/// directory, for materials sampling them to be pointed at the ORM texture.
+/// Channels without a map hold the value glTF takes in their absence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OrmMetadata {
+    /// Map in the red channel, 255 (unoccluded) without one
+    pub occlusion: Option<String>,
+    /// Map in the green channel, 255 (fully rough) without one
+    pub roughness: Option<String>,
+    /// Map in the blue channel, 0 (dielectric) without one
+    pub metalness: Option<String>,
+    /// Whether the roughness map held glossiness, inverted in the green channel
+    pub inverted_glossiness: bool,
+}
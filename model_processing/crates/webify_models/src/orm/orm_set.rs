@@ -0,0 +1,27 @@
+//! Structure that represents the maps of a material that can be packed together
+
+use std::path::PathBuf;
+
+/// Occlusion, roughness and metalness maps sharing a base name, e.g. `crate_ao`,
+/// `crate_roughness` and `crate_metallic`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrmSet {
+    /// Name the maps share, without their role suffix, e.g. `crate`
+    pub base: String,
+    pub occlusion: Option<PathBuf>,
+    pub roughness: Option<PathBuf>,
+    pub metalness: Option<PathBuf>,
+    /// Whether the roughness map holds glossiness, e.g. `crate_gloss`, and is
+    /// inverted when packed
+    pub glossiness: bool,
+}
+
+impl OrmSet {
+    /// Number of the three maps the set has
+    pub fn map_count(&self) -> usize {
+        [&self.occlusion, &self.roughness, &self.metalness]
+            .iter()
+            .filter(|map| map.is_some())
+            .count()
+    }
+}
@@ -0,0 +1,75 @@
+//! Token used to cancel an in-flight run from another thread
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+/// Cheap to clone handle that can cancel a run from another thread. The pipeline
+/// checks it between files and between stages, so the file currently being
+/// processed is always finished cleanly before stopping.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Arc<Mutex<Option<Instant>>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Ask the run to stop at the next file or stage boundary
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Ask the run to stop at the first file or stage boundary past `deadline`
+    pub fn cancel_at(&self, deadline: Instant) {
+        if let Ok(mut current) = self.deadline.lock() {
+            *current = Some(deadline);
+        }
+    }
+
+    /// Whether the run was asked to stop, or its deadline passed
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst) || self.is_past_deadline()
+    }
+
+    /// Whether the deadline given to `cancel_at` passed
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .lock()
+            .is_ok_and(|deadline| deadline.is_some_and(|deadline| Instant::now() >= deadline))
+    }
+}
+
+#[cfg(test)]
+mod cancellation_token_tests {
+    use super::*;
+
+    #[test]
+    fn it_shares_cancellation_between_clones() {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        assert!(!token.is_cancelled());
+
+        handle.cancel();
+        assert!(token.is_cancelled());
+        assert!(!token.is_past_deadline());
+    }
+
+    #[test]
+    fn it_cancels_once_the_deadline_passes() {
+        let token = CancellationToken::new();
+        token.cancel_at(Instant::now() + std::time::Duration::from_secs(3600));
+        assert!(!token.is_cancelled());
+
+        token.clone().cancel_at(Instant::now());
+        assert!(token.is_cancelled());
+        assert!(token.is_past_deadline());
+    }
+}
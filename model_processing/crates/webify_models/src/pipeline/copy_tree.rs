@@ -0,0 +1,72 @@
+//! Copy a source tree to the output directory, for non-destructive runs
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::preflight::check_output_dir;
+use crate::walk::walk_dir;
+
+/// Copy every file of `source` into `output`, keeping the layout, so the pipeline
+/// can work on the copy and leave the source untouched. The output directory must
+/// be new or empty, and apart from the source.
+pub fn copy_tree(source: &Path, output: &Path) -> Result<(), Error> {
+    check_output_dir(source, output)?;
+
+    for file in walk_dir(source, &|_: &Path| true)? {
+        let destination = output.join(file.strip_prefix(source).unwrap());
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&file, &destination)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod copy_tree_tests {
+    use super::*;
+
+    #[test]
+    fn it_copies_the_tree() -> Result<(), Error> {
+        let source = Path::new("tests").join("model").join("collection");
+        let output = Path::new("tests")
+            .join("pipeline")
+            .join("test_run_copy_tree");
+
+        copy_tree(&source, &output)?;
+        assert_eq!(
+            fs::read_to_string(output.join("lamp").join("model.config"))?,
+            fs::read_to_string(source.join("lamp").join("model.config"))?
+        );
+        assert!(output
+            .join("media")
+            .join("materials")
+            .join("textures")
+            .join("wood.png")
+            .is_file());
+
+        fs::remove_dir_all(output)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_an_output_inside_the_source() -> Result<(), Error> {
+        let source = Path::new("tests").join("model");
+        let absolute = std::env::current_dir()?.join(&source);
+
+        assert!(copy_tree(&source, &source.join("output")).is_err());
+        assert!(copy_tree(&source, &Path::new(".").join(&source).join("output")).is_err());
+        assert!(copy_tree(&source, &absolute.join("output")).is_err());
+        assert!(copy_tree(&absolute, &source.join("output")).is_err());
+        assert!(!source.join("output").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_an_output_containing_the_source() {
+        let source = Path::new("tests").join("model").join("collection");
+
+        assert!(copy_tree(&source, &Path::new("tests").join("model")).is_err());
+        assert!(!source.join("collection").exists());
+    }
+}
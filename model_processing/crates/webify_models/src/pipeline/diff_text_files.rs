@@ -0,0 +1,108 @@
+//! Diff the text files of a tree against a processed copy of it
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use similar::TextDiff;
+
+use crate::mesh_update::REFERENCE_FILE_EXTENSIONS;
+use crate::report::TextChange;
+use crate::walk::walk_dir;
+
+/// Lines of context around each hunk, as `diff -u` does
+const CONTEXT_LINES: usize = 3;
+
+/// Unified diff of every SDF, mesh, MTL material and model.config of `source` that
+/// was rewritten in `processed`. Files the processing removed or added aren't text
+/// rewrites, so they're left out.
+pub fn diff_text_files(source: &Path, processed: &Path) -> Result<Vec<TextChange>, Error> {
+    let mut changes = Vec::new();
+    for file in walk_dir(source, &is_text_file)? {
+        let path = file.strip_prefix(source).unwrap().to_path_buf();
+        let rewritten = processed.join(&path);
+        if !rewritten.is_file() {
+            continue;
+        }
+
+        let before = fs::read_to_string(&file)?;
+        let after = fs::read_to_string(&rewritten)?;
+        if before == after {
+            continue;
+        }
+
+        let name = path_for_header(&path);
+        let diff = TextDiff::from_lines(&before, &after)
+            .unified_diff()
+            .context_radius(CONTEXT_LINES)
+            .header(&format!("a/{}", name), &format!("b/{}", name))
+            .to_string();
+        changes.push(TextChange { path, diff });
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(changes)
+}
+
+fn is_text_file(path: &Path) -> bool {
+    path.file_name() == Some("model.config".as_ref())
+        || path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| REFERENCE_FILE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Path with forward slashes, as diff headers have them on every platform
+fn path_for_header(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod diff_text_files_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_diffs_the_rewritten_text_files() -> Result<(), Error> {
+        let base_path = Path::new("tests")
+            .join("pipeline")
+            .join("test_run_diff_text_files");
+        let (source, processed) = (base_path.join("source"), base_path.join("processed"));
+        for dir in [&source, &processed] {
+            fs::create_dir_all(dir.join("meshes"))?;
+            fs::write(dir.join("meshes").join("lamp.png"), "not text")?;
+        }
+        fs::write(
+            source.join("meshes").join("lamp.mtl"),
+            "newmtl lamp\nKd 1 1 1\nmap_Kd lamp.jpg\n",
+        )?;
+        fs::write(
+            processed.join("meshes").join("lamp.mtl"),
+            "newmtl lamp\nKd 1 1 1\nmap_Kd ../materials/textures/lamp.png\n",
+        )?;
+        fs::write(source.join("model.config"), "<model/>\n")?;
+        fs::write(processed.join("model.config"), "<model/>\n")?;
+
+        let changes = diff_text_files(&source, &processed)?;
+        fs::remove_dir_all(base_path)?;
+
+        assert_eq!(
+            changes,
+            vec![TextChange {
+                path: PathBuf::from("meshes").join("lamp.mtl"),
+                diff: String::from(
+                    "--- a/meshes/lamp.mtl\n\
+                     +++ b/meshes/lamp.mtl\n\
+                     @@ -1,3 +1,3 @@\n \
+                     newmtl lamp\n \
+                     Kd 1 1 1\n\
+                     -map_Kd lamp.jpg\n\
+                     +map_Kd ../materials/textures/lamp.png\n"
+                ),
+            }]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! Tell whether a path is within a directory, however the two are spelled
+
+use std::{
+    env,
+    io::Error,
+    path::{Component, Path},
+    result::Result,
+};
+
+/// Whether `path` is `dir` or inside it once both are resolved: against the
+/// current directory, through symlinks, `.` and `..`. The part of `path` that
+/// doesn't exist yet is resolved from its nearest existing ancestor.
+pub fn is_within(path: &Path, dir: &Path) -> Result<bool, Error> {
+    let dir = dir.canonicalize()?;
+    let path = env::current_dir()?.join(path);
+
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(component)) => {
+                missing.push(component);
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing.canonicalize()?;
+    for component in missing.into_iter().rev() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => resolved.push(name),
+            _ => (),
+        }
+    }
+
+    Ok(resolved.starts_with(dir))
+}
+
+#[cfg(test)]
+mod is_within_tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_both_paths() -> Result<(), Error> {
+        let dir = Path::new("tests").join("model");
+        let absolute = env::current_dir()?.join(&dir);
+
+        assert!(is_within(&dir.join("backup"), &dir)?);
+        assert!(is_within(&Path::new(".").join(&dir).join("backup"), &dir)?);
+        assert!(is_within(&absolute.join("backup").join("textures"), &dir)?);
+        assert!(is_within(&dir.join("backup"), &absolute)?);
+        assert!(is_within(&dir, &dir)?);
+        assert!(!is_within(&dir.join("..").join("backup"), &dir)?);
+        assert!(!is_within(&Path::new("tests").join("backup"), &dir)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_follows_symlinks() -> Result<(), Error> {
+        let link = Path::new("tests")
+            .join("pipeline")
+            .join("test_run_is_within_link");
+        std::fs::create_dir_all(Path::new("tests").join("pipeline"))?;
+        std::os::unix::fs::symlink(env::current_dir()?.join("tests").join("model"), &link)?;
+
+        let within = is_within(&link.join("backup"), &Path::new("tests").join("model"));
+        std::fs::remove_file(&link)?;
+
+        assert!(within?);
+        Ok(())
+    }
+}
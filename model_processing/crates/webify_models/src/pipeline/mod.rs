@@ -0,0 +1,17 @@
+//! Public entry point to run the whole webify pipeline on a directory
+
+mod cancellation_token;
+mod copy_tree;
+mod diff_text_files;
+mod is_within;
+mod resolve_first;
+mod schedule_models;
+mod webify_pipeline;
+
+pub use self::cancellation_token::CancellationToken;
+pub use self::copy_tree::copy_tree;
+pub use self::diff_text_files::diff_text_files;
+pub use self::is_within::is_within;
+pub use self::resolve_first::resolve_first;
+pub use self::schedule_models::schedule_models;
+pub use self::webify_pipeline::Pipeline;
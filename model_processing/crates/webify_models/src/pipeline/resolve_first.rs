@@ -0,0 +1,62 @@
+//! Resolve the directories given to `--first`
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+/// Resolve the directories to process first, given relative to the source
+/// directory or to the current one, into paths relative to the source, so they
+/// still match once the source is copied with `--output`
+pub fn resolve_first(source: &Path, first: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    if first.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let canonical_source = fs::canonicalize(source)?;
+    first
+        .iter()
+        .map(|path| {
+            let candidate = match source.join(path).exists() {
+                true => source.join(path),
+                false => path.clone(),
+            };
+            let canonical = fs::canonicalize(&candidate)
+                .map_err(|_| Error::other(format!("--first {:?} doesn't exist.", path)))?;
+            match canonical.strip_prefix(&canonical_source) {
+                Ok(relative) => Ok(relative.to_path_buf()),
+                Err(_) => Err(Error::other(format!(
+                    "--first {:?} isn't inside {:?}.",
+                    path, source
+                ))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod resolve_first_tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_paths_relative_to_the_source_or_not() -> Result<(), Error> {
+        let source = Path::new("tests").join("model").join("collection");
+        let first = vec![PathBuf::from("lamp"), source.join("desk")];
+
+        assert_eq!(
+            resolve_first(&source, &first)?,
+            vec![PathBuf::from("lamp"), PathBuf::from("desk")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_paths_outside_the_source() {
+        let source = Path::new("tests").join("model").join("collection");
+
+        assert!(resolve_first(&source, &[PathBuf::from("missing")]).is_err());
+        assert!(resolve_first(&source, &[PathBuf::from("tests")]).is_err());
+    }
+}
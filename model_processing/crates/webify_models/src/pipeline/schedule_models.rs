@@ -0,0 +1,100 @@
+//! Order the models of a run so included models are processed before the models
+//! including them
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::dependencies::DependencyGraph;
+
+/// Directories to process ahead of the rest, relative to `dir`: the `--first`
+/// ones, each preceded by the models its models include, then every model of the
+/// dependency graph after the models it includes. This way a model's reference
+/// rewriting always sees the final paths of the models it includes.
+pub fn schedule_models(dir: &Path, first: &[PathBuf], graph: &DependencyGraph) -> Vec<PathBuf> {
+    let order = graph.processing_order();
+    let mut scheduled: Vec<PathBuf> = Vec::new();
+    let push = |path: &Path, scheduled: &mut Vec<PathBuf>| {
+        if !scheduled.iter().any(|s| path.starts_with(s)) {
+            scheduled.push(path.to_path_buf());
+        }
+    };
+
+    for first_dir in first {
+        let included = included_models(graph, &dir.join(first_dir));
+        for model in order.iter().filter(|model| included.contains(model)) {
+            push(model.strip_prefix(dir).unwrap_or(model), &mut scheduled);
+        }
+        push(first_dir, &mut scheduled);
+    }
+    for model in &order {
+        push(model.strip_prefix(dir).unwrap_or(model), &mut scheduled);
+    }
+
+    scheduled
+}
+
+/// Every model included, directly or not, by the models under `first_dir`, outside
+/// of it
+fn included_models<'a>(graph: &'a DependencyGraph, first_dir: &Path) -> BTreeSet<&'a PathBuf> {
+    let mut included: BTreeSet<&PathBuf> = BTreeSet::new();
+    let mut pending: Vec<&PathBuf> = graph
+        .models()
+        .filter(|model| model.starts_with(first_dir))
+        .collect();
+    while let Some(model) = pending.pop() {
+        for dependency in graph.dependencies(model) {
+            if !dependency.starts_with(first_dir) && included.insert(dependency) {
+                pending.push(dependency);
+            }
+        }
+    }
+
+    included
+}
+
+#[cfg(test)]
+mod schedule_models_tests {
+    use super::*;
+
+    fn graph() -> DependencyGraph {
+        let dir = Path::new("library");
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(&dir.join("office"), &dir.join("desk"));
+        graph.add_dependency(&dir.join("desk"), &dir.join("drawer"));
+        graph.add_dependency(&dir.join("lobby"), &dir.join("sofa"));
+        graph
+    }
+
+    #[test]
+    fn it_schedules_included_models_first() {
+        let scheduled = schedule_models(Path::new("library"), &[], &graph());
+
+        let expected: Vec<PathBuf> = ["drawer", "desk", "sofa", "lobby", "office"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(scheduled, expected);
+    }
+
+    #[test]
+    fn it_brings_the_included_models_along_with_first() {
+        let first = vec![PathBuf::from("office")];
+        let scheduled = schedule_models(Path::new("library"), &first, &graph());
+
+        let expected: Vec<PathBuf> = ["drawer", "desk", "office", "sofa", "lobby"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(scheduled, expected);
+
+        let first = vec![PathBuf::from("lobby")];
+        let scheduled = schedule_models(Path::new("library"), &first, &graph());
+        let expected: Vec<PathBuf> = ["sofa", "lobby", "drawer", "desk", "office"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(scheduled, expected);
+    }
+}
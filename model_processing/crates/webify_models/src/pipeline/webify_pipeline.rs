@@ -0,0 +1,2239 @@
+//! Run every stage of the webify pipeline on a directory
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Instant,
+};
+
+use crate::atlas::generate_atlas;
+use crate::audio;
+use crate::audit::AuditLog;
+use crate::config::{load_config, load_user_config, user_config_path};
+use crate::dependencies::{index_models, DependencyGraph};
+use crate::hashing::hash_file;
+use crate::image_processing::{self, texture_extensions, HashedNames};
+use crate::impostor::generate_impostor;
+use crate::manifest::{build_manifest, write_manifest};
+use crate::mesh_update;
+use crate::model::{find_model_dirs, inject_texture_metadata, mark_model_config, model_sdfs};
+use crate::navmesh::generate_navmesh;
+use crate::options::{Removal, RunOptions, Stage};
+use crate::orm::generate_orm;
+use crate::pipeline::{
+    copy_tree, diff_text_files, is_within, resolve_first, schedule_models, CancellationToken,
+};
+use crate::preflight::{
+    check_allowed_roots, check_output_dir, check_system_path, check_writable,
+    find_corrupt_images,
+};
+use crate::provenance::ProcessedMarker;
+use crate::report::{
+    attribute_authors, handle_failure, run_step, ErrorCode, EventLog, Failure, RunReport,
+    TextureStats, Warning,
+};
+use crate::sensor::replace_sensor_visuals;
+use crate::terrain::generate_terrain;
+use crate::trends::{measure_library, record_trend};
+use crate::walk::prioritize;
+use crate::world::{find_worlds, read_world_scene};
+
+/// The webify pipeline: texture move, PNG conversion and downscaling, followed by
+/// the mesh update and the web manifest
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    options: RunOptions,
+    cancellation_token: CancellationToken,
+}
+
+impl Pipeline {
+    pub fn new(options: RunOptions) -> Pipeline {
+        Pipeline {
+            options,
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+
+    /// Options the pipeline runs with
+    pub fn options(&self) -> &RunOptions {
+        &self.options
+    }
+
+    /// Handle that cancels the run when triggered, from any thread
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    /// Run every stage on the directory, or on a copy of it with `--output`. A
+    /// cancelled run still returns its report, covering the files processed before
+    /// it stopped. With `--dry-run`, a scratch copy is processed then deleted, and
+    /// the report records how the text files would be rewritten. With
+    /// `--time-budget`, the run is cancelled once the budget elapses. With
+    /// `--event-log`, the report is appended to the log as the run goes. With
+    /// `--git-authors`, failures and warnings name who last committed their file.
+    /// With `--record-trends`, a complete run appends the size of the library and
+    /// its failures to the trends log.
+    pub fn run(&self, source: &Path) -> std::result::Result<RunReport, std::io::Error> {
+        if let Some(time_budget) = self.options.time_budget {
+            self.cancellation_token
+                .cancel_at(Instant::now() + time_budget);
+        }
+        let options = RunOptions {
+            first: resolve_first(source, &self.options.first)?,
+            ..self.options.clone()
+        };
+        let mut report = match options.dry_run {
+            true => self.dry_run(source, options)?,
+            false => self.run_tree(source, options)?,
+        };
+        report.finish_events()?;
+
+        Ok(report)
+    }
+
+    /// Process the directory, or a copy of it with `--output`, once the source is
+    /// checked to be safe to process
+    fn run_tree(
+        &self,
+        source: &Path,
+        options: RunOptions,
+    ) -> std::result::Result<RunReport, std::io::Error> {
+        let dir = match &options.output {
+            Some(output) => {
+                check_output_dir(source, output)?;
+                fs::create_dir_all(output)?;
+                check_destination(output, &options)?;
+                copy_tree(source, output)?;
+                output.as_path()
+            }
+            None => {
+                if options.watermark.is_some() {
+                    return Err(std::io::Error::other(
+                        "--watermark only marks preview builds written with --output, never the library itself.",
+                    ));
+                }
+                check_destination(source, &options)?;
+                source
+            }
+        };
+
+        let mut report = self.process(dir, &options)?;
+        if options.git_authors {
+            attribute_authors(&mut report, dir, source);
+        }
+        // A run that stopped early only failed on part of the library
+        if options.record_trends && !report.cancelled {
+            record_trend(dir, &measure_library(dir, &report)?)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Process a scratch copy of the directory, then diff its text files with the
+    /// source. Deleted files are removed for good, the scratch copy isn't worth
+    /// trashing.
+    fn dry_run(
+        &self,
+        source: &Path,
+        options: RunOptions,
+    ) -> std::result::Result<RunReport, std::io::Error> {
+        let scratch = env::temp_dir().join(format!("webify_dry_run_{}", process::id()));
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch)?;
+        }
+        let options = RunOptions {
+            removal: Removal::Delete,
+            ..options
+        };
+
+        let result = copy_tree(source, &scratch)
+            .and_then(|_| self.process(&scratch, &options))
+            .and_then(|mut report| {
+                report.text_changes = diff_text_files(source, &scratch)?;
+                if options.git_authors {
+                    attribute_authors(&mut report, &scratch, source);
+                }
+                Ok(report)
+            });
+        fs::remove_dir_all(&scratch)?;
+
+        result
+    }
+
+    /// Run every stage on `dir`, in place
+    fn process(
+        &self,
+        dir: &Path,
+        options: &RunOptions,
+    ) -> std::result::Result<RunReport, std::io::Error> {
+        // Trashed and backed up files are kept outside of the processed tree, where
+        // they aren't processed again
+        let kept_dir = match &options.removal {
+            Removal::TrashDir(trash_dir) => Some(("trash", trash_dir)),
+            Removal::BackupDir {
+                dir: backup_dir, ..
+            } => Some(("backup", backup_dir)),
+            _ => None,
+        };
+        if let Some((kind, kept_dir)) = kept_dir {
+            if is_within(kept_dir, dir)? {
+                return Err(std::io::Error::other(format!(
+                    "The {} directory {:?} can't be inside the processed directory {:?}.",
+                    kind, kept_dir, dir
+                )));
+            }
+        }
+        let options = &RunOptions {
+            removal: options.removal.clone().rooted_at(dir),
+            ..options.clone()
+        };
+
+        let mut report = RunReport {
+            event_log: options
+                .event_log
+                .as_deref()
+                .map(EventLog::create)
+                .transpose()?,
+            audit_log: match options.audit {
+                true => Some(AuditLog::open(dir)?),
+                false => None,
+            },
+            ..RunReport::default()
+        };
+        let config = load_config(dir)?;
+
+        // Every texture is decoded once before anything is touched, so a corrupt one
+        // fails a strict run while the originals are all there, or is otherwise left
+        // out of the run, as it is, rather than failing halfway through it
+        let mut corrupt = HashSet::new();
+        if options.stages.is_enabled(Stage::Convert) {
+            for (path, reason) in find_corrupt_images(
+                dir,
+                &texture_extensions(options, &config),
+                &options.hdr,
+                &ProcessedMarker::new(options, &config),
+                options.memory_budget,
+            )? {
+                let failure = Failure::new(
+                    ErrorCode::Decode,
+                    "Image Validation",
+                    path.clone(),
+                    format!("left as is, it can't be decoded: {}", reason),
+                );
+                handle_failure(options.error_policy, &mut report, failure)?;
+                corrupt.insert(path);
+            }
+        }
+
+        // Models included by other models go first, so the reference rewriting of
+        // the including models sees their final paths
+        let graph = DependencyGraph::build(dir)?;
+        for cycle in graph.cycles() {
+            let names: Vec<String> = cycle
+                .iter()
+                .map(|model| {
+                    model
+                        .strip_prefix(dir)
+                        .unwrap_or(model)
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            report.warnings.push(Warning::new(
+                ErrorCode::CircularDependency,
+                "Dependency Order",
+                cycle[0].clone(),
+                format!("includes itself through {}", names.join(" -> ")),
+            ));
+        }
+        let options = &RunOptions {
+            first: schedule_models(dir, &options.first, &graph),
+            ..options.clone()
+        };
+
+        // Impostors are rendered first, while the textures of the meshes are still
+        // in formats the renderer decodes, and their atlases are then processed
+        // like the other textures
+        if options.impostors {
+            let models = index_models(dir)?;
+            let mut model_dirs = find_model_dirs(dir)?;
+            prioritize(&mut model_dirs, dir, &options.first, |model_dir| model_dir);
+            for model_dir in model_dirs {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "Impostor",
+                    ErrorCode::Impostor,
+                    &model_dir,
+                    options,
+                    &mut report,
+                    || generate_impostor(&model_dir, &models, options),
+                )?;
+            }
+        }
+
+        if options.navmeshes {
+            let models = index_models(dir)?;
+            for world in find_worlds(dir)? {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "Navmesh",
+                    ErrorCode::Navmesh,
+                    &world,
+                    options,
+                    &mut report,
+                    || generate_navmesh(&world, &models, options),
+                )?;
+            }
+        }
+
+        // Atlases are packed before the textures are processed too, so the packed
+        // textures are still decodable and the atlas is encoded like the others
+        if options.atlases {
+            let models = index_models(dir)?;
+            let mut model_dirs = find_model_dirs(dir)?;
+            prioritize(&mut model_dirs, dir, &options.first, |model_dir| model_dir);
+            for model_dir in model_dirs {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "Texture Atlas",
+                    ErrorCode::Atlas,
+                    &model_dir,
+                    options,
+                    &mut report,
+                    || generate_atlas(&model_dir, &models, options),
+                )?;
+            }
+        }
+
+        // ORM textures are packed from the maps as they came, then encoded like the
+        // other textures, the maps staying for the materials sampling them
+        if options.pack_orm {
+            let mut model_dirs = find_model_dirs(dir)?;
+            prioritize(&mut model_dirs, dir, &options.first, |model_dir| model_dir);
+            for model_dir in model_dirs {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "ORM Pack",
+                    ErrorCode::OrmPack,
+                    &model_dir,
+                    options,
+                    &mut report,
+                    || generate_orm(&model_dir, options),
+                )?;
+            }
+        }
+
+        image_processing::process(
+            dir,
+            options,
+            &config,
+            &corrupt,
+            &self.cancellation_token,
+            &mut report,
+        )?;
+        audio::process(dir, options, &self.cancellation_token, &mut report)?;
+        if self.cancellation_token.is_cancelled() {
+            if options.stages.is_enabled(Stage::Rewrite) {
+                repair_replaced_library_references(dir, options, &mut report)?;
+            }
+            report.cancelled = true;
+            report.out_of_time = self.cancellation_token.is_past_deadline();
+            return Ok(report);
+        }
+
+        if options.shared_textures && options.stages.is_enabled(Stage::Move) {
+            image_processing::pool_shared_textures(dir, &options.removal)?;
+        }
+
+        if options.stages.is_enabled(Stage::Rewrite) {
+            // The files as they were, for the audit log
+            let mut sources = BTreeMap::new();
+            if report.audit_log.is_some() {
+                for file in mesh_update::scan_dir_for_reference_files(dir)? {
+                    let hash = hash_file(&file)?;
+                    sources.insert(file, hash);
+                }
+            }
+            mesh_update::process(dir, options, &config, &self.cancellation_token, &mut report)?;
+            if self.cancellation_token.is_cancelled() {
+                repair_replaced_library_references(dir, options, &mut report)?;
+            }
+            if let Some(audit_log) = &mut report.audit_log {
+                for (file, hash) in sources {
+                    if file.is_file() && hash_file(&file)? != hash {
+                        audit_log.record("Reference Rewrite", &file, hash, &file)?;
+                    }
+                }
+            }
+        }
+
+        // Copies are only removed once every reference to them was pointed at the
+        // canonical copy, so a file that can't be rewritten never loses its texture
+        if options.dedup_textures
+            && options.stages.is_enabled(Stage::Rewrite)
+            && !self.cancellation_token.is_cancelled()
+        {
+            let textures = report.textures.clone();
+            let duplicates = run_step(
+                "Texture Dedup",
+                ErrorCode::Dedup,
+                dir,
+                options,
+                &mut report,
+                || image_processing::find_duplicate_textures(&textures),
+            )?
+            .unwrap_or_default();
+            let mut canonicals = BTreeMap::new();
+            for duplicate in &duplicates {
+                canonicals.insert(duplicate.path.canonicalize()?, duplicate.canonical.clone());
+            }
+            let rewritten = redirect_library_references(
+                dir,
+                "Texture Dedup",
+                ErrorCode::Dedup,
+                &canonicals,
+                options,
+                &mut report,
+            )?;
+
+            if rewritten
+                && run_step(
+                    "Texture Dedup",
+                    ErrorCode::Dedup,
+                    dir,
+                    options,
+                    &mut report,
+                    || image_processing::remove_duplicate_textures(&duplicates, &options.removal),
+                )?
+                .is_some()
+            {
+                if let Some(audit_log) = &mut report.audit_log {
+                    for duplicate in &duplicates {
+                        let hash = hash_file(&duplicate.canonical)?;
+                        audit_log.record(
+                            "Texture Dedup",
+                            &duplicate.path,
+                            hash,
+                            &duplicate.canonical,
+                        )?;
+                    }
+                }
+                report
+                    .textures
+                    .retain(|texture| !duplicates.iter().any(|d| d.path == texture.path));
+                report.duplicates = duplicates;
+            }
+        }
+
+        if !config.sensor_visuals.is_empty() && options.stages.is_enabled(Stage::Rewrite) {
+            let mut files = Vec::new();
+            for model_dir in find_model_dirs(dir)? {
+                files.extend(model_sdfs(&model_dir)?);
+            }
+            files.extend(find_worlds(dir)?);
+            for file in files {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "Sensor Visuals",
+                    ErrorCode::SensorVisuals,
+                    &file,
+                    options,
+                    &mut report,
+                    || replace_sensor_visuals(&file, &config.sensor_visuals),
+                )?;
+            }
+        }
+
+        // Textures are named after their contents once every other stage is done
+        // with their references, and like duplicates only renamed once every
+        // reference follows
+        if options.hash_names
+            && options.stages.is_enabled(Stage::Rewrite)
+            && !self.cancellation_token.is_cancelled()
+        {
+            let textures = report.textures.clone();
+            let renames = run_step(
+                "Hash Rename",
+                ErrorCode::HashName,
+                dir,
+                options,
+                &mut report,
+                || image_processing::content_hash_names(&textures),
+            )?
+            .unwrap_or_default();
+            let mut redirects = BTreeMap::new();
+            for (from, to) in &renames {
+                redirects.insert(from.canonicalize()?, to.clone());
+            }
+            let rewritten = redirect_library_references(
+                dir,
+                "Hash Rename",
+                ErrorCode::HashName,
+                &redirects,
+                options,
+                &mut report,
+            )?;
+
+            if rewritten
+                && run_step(
+                    "Hash Rename",
+                    ErrorCode::HashName,
+                    dir,
+                    options,
+                    &mut report,
+                    || image_processing::rename_textures(&renames, &options.removal),
+                )?
+                .is_some()
+            {
+                if let Some(audit_log) = &mut report.audit_log {
+                    for (from, to) in &renames {
+                        audit_log.record("Hash Rename", from, hash_file(to)?, to)?;
+                    }
+                }
+                let mut seen = HashSet::new();
+                report.textures.retain_mut(|texture| {
+                    if let Some((_, to)) = renames.iter().find(|(from, _)| *from == texture.path) {
+                        texture.path = to.clone();
+                    }
+                    // Textures with the same contents in a directory end up as one
+                    seen.insert(texture.path.clone())
+                });
+                let mut names = HashedNames::read(dir)?;
+                names.record(dir, &renames);
+                names.write(dir)?;
+            }
+        }
+
+        // Terrains are baked once the references of the worlds are final, so their
+        // layers point at the processed textures
+        if options.terrains {
+            let models = index_models(dir)?;
+            for world in find_worlds(dir)? {
+                if self.cancellation_token.is_cancelled() {
+                    break;
+                }
+                run_step(
+                    "Terrain",
+                    ErrorCode::Terrain,
+                    &world,
+                    options,
+                    &mut report,
+                    || generate_terrain(&world, &models, options),
+                )?;
+            }
+        }
+        report.cancelled = self.cancellation_token.is_cancelled();
+        report.out_of_time = report.cancelled && self.cancellation_token.is_past_deadline();
+        if !report.cancelled {
+            let mut model_dirs = find_model_dirs(dir)?;
+            prioritize(&mut model_dirs, dir, &options.first, |model_dir| model_dir);
+            for model_dir in model_dirs {
+                run_step(
+                    "Model Config",
+                    ErrorCode::ModelConfig,
+                    &model_dir,
+                    options,
+                    &mut report,
+                    || mark_model_config(&model_dir, config.mark_model_config),
+                )?;
+
+                if options.sdf_texture_metadata {
+                    let textures: Vec<TextureStats> = report
+                        .textures
+                        .iter()
+                        .filter(|t| t.model.as_ref() == Some(&model_dir))
+                        .cloned()
+                        .collect();
+                    let textures: Vec<&TextureStats> = textures.iter().collect();
+                    for sdf in model_sdfs(&model_dir)? {
+                        run_step(
+                            "SDF Metadata",
+                            ErrorCode::SdfMetadata,
+                            &sdf,
+                            options,
+                            &mut report,
+                            || inject_texture_metadata(&sdf, &model_dir, &textures),
+                        )?;
+                    }
+                }
+            }
+
+            // Worlds whose scene can't be read are left out of the manifest
+            let models = index_models(dir)?;
+            let mut worlds = BTreeMap::new();
+            for world in find_worlds(dir)? {
+                if let Some(scene) = run_step(
+                    "World Scene",
+                    ErrorCode::WorldScene,
+                    &world,
+                    options,
+                    &mut report,
+                    || read_world_scene(&world, &models),
+                )? {
+                    worlds.insert(world, scene);
+                }
+            }
+
+            let manifest = build_manifest(dir, &config, &report, &worlds, options)?;
+            write_manifest(dir, &manifest)?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Check the directory the run writes to is safe to modify: not a system path, in
+/// the allowed roots, and writable
+fn check_destination(dir: &Path, options: &RunOptions) -> std::result::Result<(), std::io::Error> {
+    if !options.allow_system_paths {
+        check_system_path(dir)?;
+    }
+    if let Some(config_path) = user_config_path() {
+        check_allowed_roots(dir, &load_user_config(&config_path)?.allowed_roots)?;
+    }
+    check_writable(dir)
+}
+
+/// Point the references of every mesh, SDF, world and MTL material of the library
+/// to textures removed by a run that stopped early at the textures replacing them,
+/// so the models it processed aren't left broken until the next run
+fn repair_replaced_library_references(
+    dir: &Path,
+    options: &RunOptions,
+    report: &mut RunReport,
+) -> std::result::Result<(), std::io::Error> {
+    for file in mesh_update::scan_dir_for_reference_files(dir)? {
+        run_step(
+            "Reference Repair",
+            ErrorCode::ReferenceRepair,
+            &file,
+            options,
+            report,
+            || mesh_update::repair_replaced_references(&file, dir),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Point the references of every mesh, SDF, world and MTL material of the library
+/// at the files replacing the ones of `redirects`, and return whether every file
+/// was rewritten
+fn redirect_library_references(
+    dir: &Path,
+    stage: &'static str,
+    code: ErrorCode,
+    redirects: &BTreeMap<PathBuf, PathBuf>,
+    options: &RunOptions,
+    report: &mut RunReport,
+) -> std::result::Result<bool, std::io::Error> {
+    let models = index_models(dir)?;
+    let mut rewritten = true;
+    for file in mesh_update::scan_dir_for_reference_files(dir)? {
+        rewritten &= run_step(stage, code, &file, options, report, || {
+            mesh_update::redirect_references(&file, redirects, &models)
+        })?
+        .is_some();
+    }
+
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod pipeline_tests {
+    use super::*;
+
+    use std::{env, fs, io::Error, path::PathBuf};
+
+    use crate::image_processing::{decode_webp, estimate_gpu_memory, TRANSCODED_BYTES_PER_PIXEL};
+    use crate::manifest::{ModelManifest, MANIFEST_FILE_NAME};
+    use crate::options::{
+        AudioSettings, ErrorPolicy, GifPolicy, OutputFormat, OversizedTextures, PowerOfTwo,
+        WatermarkMark, WebpSettings,
+    };
+    use crate::provenance::Encoding;
+    use crate::report::Warning;
+    use crate::walk::walk_dir;
+
+    fn setup(test_run_id: &str) -> Result<(), Error> {
+        let destination_path = Path::new("tests").join("pipeline").join(test_run_id);
+        fs::create_dir_all(destination_path.join("model"))?;
+        fs::copy(
+            Path::new("tests")
+                .join("image_processing")
+                .join("images")
+                .join("example.jpg"),
+            destination_path.join("model").join("example.jpg"),
+        )?;
+
+        Ok(())
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(Path::new("tests").join("pipeline").join(test_run_id))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_stops_before_touching_files_when_cancelled() -> Result<(), Error> {
+        let test_run_id = "test_run_it_stops_when_cancelled";
+        setup(test_run_id)?;
+
+        let pipeline = Pipeline::new(RunOptions::default());
+        pipeline.cancellation_token().cancel();
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let report = pipeline.run(&dir)?;
+
+        assert!(report.cancelled);
+        assert!(dir.join("model").join("example.jpg").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_checks_every_texture_decodes_before_touching_files() -> Result<(), Error> {
+        let test_run_id = "test_run_it_checks_every_texture_decodes";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let model = dir.join("model");
+        let jpeg = fs::read(model.join("example.jpg"))?;
+        fs::write(model.join("broken.jpg"), &jpeg[..jpeg.len() / 2])?;
+
+        // A strict run stops with every original where it was
+        assert!(Pipeline::new(RunOptions::default()).run(&dir).is_err());
+        assert!(model.join("example.jpg").is_file());
+        assert!(!model.join("materials").exists());
+
+        // Any other leaves the corrupt texture out, as it is
+        let options = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].stage, "Image Validation");
+        assert_eq!(report.failures[0].path, model.join("broken.jpg"));
+        assert!(model.join("broken.jpg").is_file());
+        assert!(model
+            .join("materials")
+            .join("textures")
+            .join("example.png")
+            .is_file());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_stops_when_the_time_budget_elapses_and_resumes() -> Result<(), Error> {
+        let test_run_id = "test_run_it_stops_when_the_time_budget_elapses";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let options = RunOptions {
+            time_budget: Some(std::time::Duration::ZERO),
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.cancelled);
+        assert!(report.out_of_time);
+        assert_eq!(
+            report.remaining,
+            vec![dir.join("model").join("example.jpg")]
+        );
+        assert!(!dir.join(MANIFEST_FILE_NAME).exists());
+
+        let options = RunOptions {
+            time_budget: Some(std::time::Duration::from_secs(3600)),
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+        assert!(!report.cancelled && !report.out_of_time);
+        assert!(report.remaining.is_empty());
+        assert!(dir.join(MANIFEST_FILE_NAME).exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_the_references_of_processed_textures_when_the_budget_expires(
+    ) -> Result<(), Error> {
+        let test_run_id = "test_run_it_keeps_the_references_when_the_budget_expires";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let model = dir.join("model");
+        let textures_dir = model.join("materials").join("textures");
+        fs::create_dir_all(&textures_dir)?;
+        fs::create_dir_all(model.join("meshes"))?;
+        fs::write(model.join("model.config"), "<model/>")?;
+        let mut mesh = String::new();
+        for index in 0..24 {
+            fs::copy(
+                model.join("example.jpg"),
+                textures_dir.join(format!("tile_{}.jpg", index)),
+            )?;
+            mesh.push_str(&format!(
+                "<image><init_from>../materials/textures/tile_{}.jpg</init_from></image>\n",
+                index
+            ));
+        }
+        fs::remove_file(model.join("example.jpg"))?;
+        let mesh_path = model.join("meshes").join("floor.dae");
+        fs::write(&mesh_path, mesh)?;
+
+        let pipeline = Pipeline::new(RunOptions {
+            time_budget: Some(std::time::Duration::from_secs(3600)),
+            ..RunOptions::default()
+        });
+        // The budget runs out as soon as the first texture is converted
+        let token = pipeline.cancellation_token();
+        let watched = textures_dir.clone();
+        let expire = std::thread::spawn(move || {
+            let started = Instant::now();
+            while started.elapsed() < std::time::Duration::from_secs(60) {
+                if watched.join("tile_0.png").exists() {
+                    token.cancel_at(Instant::now());
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+        });
+        let report = pipeline.run(&dir);
+        expire.join().unwrap();
+        let report = report?;
+        let missing = mesh_update::find_missing_references(&mesh_path)?;
+        let contents = fs::read_to_string(&mesh_path)?;
+
+        teardown(test_run_id)?;
+        assert!(report.out_of_time);
+        assert!(!report.remaining.is_empty());
+        assert!(missing.is_empty());
+        assert!(contents.contains("textures/tile_0.png<"));
+        assert!(contents.contains(".jpg<"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_runs_every_stage() -> Result<(), Error> {
+        let test_run_id = "test_run_it_runs_every_stage";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        assert!(!report.cancelled);
+        assert!(report.is_success());
+        assert!(dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("example.png")
+            .exists());
+        assert!(dir.join(MANIFEST_FILE_NAME).exists());
+        assert!(dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("example.png.webify.json")
+            .exists());
+
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        assert_eq!(
+            manifest.models[0].textures[0].encoding,
+            Some(Encoding::png())
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_textures_to_webp() -> Result<(), Error> {
+        let test_run_id = "test_run_it_converts_textures_to_webp";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let options = RunOptions {
+            format: OutputFormat::Webp,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(textures.join("example.webp").exists());
+        assert!(!textures.join("example.png").exists());
+        assert_eq!(report.textures[0].path, textures.join("example.webp"));
+        assert_eq!(
+            report.textures[0].encoding,
+            Some(Encoding::webp(&options.webp))
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_picks_the_format_of_textures_by_their_role() -> Result<(), Error> {
+        let test_run_id = "test_run_it_picks_the_format_of_textures_by_their_role";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        fs::rename(
+            dir.join("model").join("example.jpg"),
+            dir.join("model").join("wall_diffuse.jpg"),
+        )?;
+        let roughness = image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([(x * y) as u8; 3]));
+        roughness
+            .save(dir.join("model").join("floor_roughness.png"))
+            .map_err(Error::other)?;
+        fs::write(
+            dir.join("webify.toml"),
+            "[formats]\ndiffuse = { format = \"webp\", quality = 85 }\nroughness = { format = \"png\", channels = 1 }\n",
+        )?;
+
+        let options = RunOptions {
+            format: OutputFormat::Avif,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        let diffuse = report
+            .textures
+            .iter()
+            .find(|texture| texture.path == textures.join("wall_diffuse.webp"))
+            .unwrap();
+        let q85 = WebpSettings {
+            quality: 85,
+            ..options.webp
+        };
+        assert_eq!(diffuse.encoding, Some(Encoding::webp(&q85)));
+        let reduced = image::open(textures.join("floor_roughness.png")).map_err(Error::other)?;
+        assert_eq!(reduced.color(), image::ColorType::L8);
+        assert!(!textures.join("floor_roughness.avif").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_one_copy_of_identical_textures() -> Result<(), Error> {
+        let test_run_id = "test_run_it_keeps_one_copy_of_identical_textures";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        for (model, texture) in [("desk", "oak.jpg"), ("lamp", "wood.jpg")].iter() {
+            fs::create_dir_all(dir.join(model))?;
+            fs::write(
+                dir.join(model).join("model.config"),
+                "<?xml version=\"1.0\"?>\n<model>\n  <sdf>model.sdf</sdf>\n</model>\n",
+            )?;
+            fs::copy(
+                dir.join("model").join("example.jpg"),
+                dir.join(model).join(texture),
+            )?;
+        }
+        fs::remove_dir_all(dir.join("model"))?;
+        fs::write(
+            dir.join("lamp").join("lamp.dae"),
+            "<COLLADA>\n  <image>\n    <init_from>wood.jpg</init_from>\n  </image>\n</COLLADA>\n",
+        )?;
+
+        let options = RunOptions {
+            dedup_textures: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let oak = dir
+            .join("desk")
+            .join("materials")
+            .join("textures")
+            .join("oak.png");
+        let wood = dir
+            .join("lamp")
+            .join("materials")
+            .join("textures")
+            .join("wood.png");
+        assert!(oak.exists());
+        assert!(!wood.exists());
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].path, wood);
+        assert_eq!(report.duplicates[0].canonical, oak);
+        assert!(report.duplicates[0].file_bytes > 0);
+        assert!(report.textures.iter().all(|texture| texture.path != wood));
+        assert!(fs::read_to_string(dir.join("lamp").join("lamp.dae"))?
+            .contains("<init_from>../desk/materials/textures/oak.png</init_from>"));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_packs_orm_textures() -> Result<(), Error> {
+        let test_run_id = "test_run_it_packs_orm_textures";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let model_dir = dir.join("model");
+        fs::write(
+            model_dir.join("model.config"),
+            "<?xml version=\"1.0\"?>\n<model>\n  <sdf>model.sdf</sdf>\n</model>\n",
+        )?;
+        image::GrayImage::from_pixel(8, 8, image::Luma([200]))
+            .save(model_dir.join("crate_ao.jpg"))
+            .map_err(Error::other)?;
+        image::GrayImage::from_fn(8, 8, |x, _| image::Luma([if x < 4 { 0 } else { 255 }]))
+            .save(model_dir.join("crate_metallic.png"))
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            pack_orm: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures_dir = model_dir.join("materials").join("textures");
+        let orm = image::open(textures_dir.join("crate_orm.png"))
+            .map_err(Error::other)?
+            .to_rgb8();
+        assert_eq!(orm.get_pixel(6, 0).0[1..], [255, 255]);
+        assert_eq!(orm.get_pixel(1, 0).0[1..], [255, 0]);
+        assert!(textures_dir.join("crate_ao.png").exists());
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let entry = &manifest.models[0].orm[0];
+        assert_eq!(entry.texture, "materials/textures/crate_orm.png");
+        assert_eq!(
+            entry.metadata.occlusion.as_deref(),
+            Some("materials/textures/crate_ao.png")
+        );
+        assert_eq!(
+            entry.metadata.metalness.as_deref(),
+            Some("materials/textures/crate_metallic.png")
+        );
+        assert_eq!(entry.metadata.roughness, None);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_the_trends_of_the_library() -> Result<(), Error> {
+        let test_run_id = "test_run_it_records_the_trends_of_the_library";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        fs::write(dir.join("model").join("model.config"), "<model/>")?;
+        let options = RunOptions {
+            record_trends: true,
+            ..RunOptions::default()
+        };
+        Pipeline::new(options.clone()).run(&dir)?;
+        Pipeline::new(options).run(&dir)?;
+
+        let points = crate::trends::read_trends(&dir.join(crate::trends::TRENDS_FILE_NAME))?;
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1].models, 1);
+        assert!(points[1].library_bytes > 0);
+        assert_eq!(points[1].average_model_bytes, points[1].library_bytes);
+        assert_eq!(points[1].failures, 0);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    fn write_animated_gif(path: &Path) -> Result<(), Error> {
+        let frames = [[200, 0, 0, 255], [0, 0, 200, 255]].iter().map(|color| {
+            image::Frame::from_parts(
+                image::RgbaImage::from_pixel(4, 4, image::Rgba(*color)),
+                0,
+                0,
+                image::Delay::from_numer_denom_ms(50, 1),
+            )
+        });
+        image::codecs::gif::GifEncoder::new(fs::File::create(path)?)
+            .encode_frames(frames)
+            .map_err(Error::other)
+    }
+
+    #[test]
+    fn it_converts_animated_gifs_to_sprite_sheets() -> Result<(), Error> {
+        let test_run_id = "test_run_it_converts_animated_gifs_to_sprite_sheets";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let model_dir = dir.join("model");
+        fs::write(
+            model_dir.join("model.config"),
+            "<?xml version=\"1.0\"?>\n<model>\n  <sdf>model.sdf</sdf>\n</model>\n",
+        )?;
+        write_animated_gif(&model_dir.join("screen.gif"))?;
+
+        let options = RunOptions {
+            gif: GifPolicy::SpriteSheet,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures_dir = model_dir.join("materials").join("textures");
+        let sheet = image::open(textures_dir.join("screen.png"))
+            .map_err(Error::other)?
+            .to_rgb8();
+        assert_eq!(sheet.dimensions(), (8, 4));
+        assert_eq!(sheet.get_pixel(6, 1).0, [0, 0, 200]);
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let entry = manifest.models[0]
+            .textures
+            .iter()
+            .find(|texture| texture.path == "materials/textures/screen.png")
+            .unwrap();
+        let sprite_sheet = entry.sprite_sheet.as_ref().unwrap();
+        assert_eq!((sprite_sheet.columns, sprite_sheet.rows), (2, 1));
+        assert_eq!(sprite_sheet.delays, vec![50, 50]);
+        assert!(manifest.models[0]
+            .textures
+            .iter()
+            .all(|texture| texture.path == entry.path || texture.sprite_sheet.is_none()));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_gifs_as_is_when_skipping_them() -> Result<(), Error> {
+        let test_run_id = "test_run_it_leaves_gifs_as_is_when_skipping_them";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        write_animated_gif(&dir.join("model").join("screen.gif"))?;
+
+        let options = RunOptions {
+            gif: GifPolicy::Skip,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        assert!(dir.join("model").join("screen.gif").exists());
+        assert!(report.warnings.iter().any(|warning| {
+            warning.code == ErrorCode::UnconvertedFormat
+                && warning.path == dir.join("model").join("screen.gif")
+        }));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_names_textures_after_their_contents() -> Result<(), Error> {
+        let test_run_id = "test_run_it_names_textures_after_their_contents";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        fs::write(
+            dir.join("model").join("example.dae"),
+            "<COLLADA>\n  <image>\n    <init_from>example.jpg</init_from>\n  </image>\n</COLLADA>\n",
+        )?;
+
+        let options = RunOptions {
+            hash_names: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(!textures.join("example.png").exists());
+        assert_eq!(report.textures.len(), 1);
+        let hashed = &report.textures[0].path;
+        let hash = format!(
+            "{:x}",
+            <sha2::Sha256 as sha2::Digest>::digest(fs::read(hashed)?)
+        );
+        assert_eq!(*hashed, textures.join(format!("{}.png", hash)));
+        assert!(
+            fs::read_to_string(dir.join("model").join("example.dae"))?.contains(&format!(
+                "<init_from>materials/textures/{}.png</init_from>",
+                hash
+            ))
+        );
+        let names = HashedNames::read(&dir)?;
+        assert_eq!(
+            names.0.get("model/materials/textures/example.png"),
+            Some(&format!("model/materials/textures/{}.png", hash))
+        );
+
+        // Named after their contents already, the textures stay as they are
+        let report = Pipeline::new(options).run(&dir)?;
+        assert_eq!(report.textures[0].path, *hashed);
+        assert_eq!(HashedNames::read(&dir)?, names);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_16_bit_heightmaps_as_png() -> Result<(), Error> {
+        let test_run_id = "test_run_it_keeps_16_bit_heightmaps_as_png";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let samples: Vec<u16> = (0..8 * 8).map(|i| i * 1000 + 7).collect();
+        tiff::encoder::TiffEncoder::new(fs::File::create(dir.join("model").join("heightmap.tif"))?)
+            .and_then(|mut tiff| {
+                tiff.write_image::<tiff::encoder::colortype::Gray16>(8, 8, &samples)
+            })
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            format: OutputFormat::Webp,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(textures.join("example.webp").exists());
+        let heightmap = image::open(textures.join("heightmap.png")).map_err(Error::other)?;
+        assert!(!textures.join("heightmap.webp").exists());
+        assert_eq!(heightmap.into_luma16().into_raw(), samples);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_normal_maps_losslessly() -> Result<(), Error> {
+        let test_run_id = "test_run_it_encodes_normal_maps_losslessly";
+        setup(test_run_id)?;
+
+        // Named like a color texture, told by its pixels
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let bumps = image::RgbImage::from_fn(16, 16, |x, y| {
+            image::Rgb([118 + (x % 4) as u8 * 6, 122 + (y % 3) as u8 * 6, 250])
+        });
+        bumps
+            .save(dir.join("model").join("bricks_bump.png"))
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            format: OutputFormat::Webp,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        let normal_map = report
+            .textures
+            .iter()
+            .find(|texture| texture.path == textures.join("bricks_bump.webp"))
+            .unwrap();
+        let lossless = WebpSettings {
+            lossless: true,
+            ..options.webp
+        };
+        assert_eq!(
+            normal_map.encoding,
+            Some(Encoding::webp(&lossless).linear())
+        );
+        let decoded = decode_webp(&textures.join("bricks_bump.webp"))?;
+        assert_eq!(decoded.to_rgb8(), bumps);
+        let example = report
+            .textures
+            .iter()
+            .find(|texture| texture.path == textures.join("example.webp"))
+            .unwrap();
+        assert_eq!(example.encoding, Some(Encoding::webp(&options.webp)));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_tags_the_colorspace_of_textures() -> Result<(), Error> {
+        let test_run_id = "test_run_it_tags_the_colorspace_of_textures";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let gray = image::GrayImage::from_fn(8, 8, |x, y| image::Luma([(x * y) as u8]));
+        for name in ["floor_roughness.png", "tile_specular.png"] {
+            gray.save(dir.join("model").join(name))
+                .map_err(Error::other)?;
+        }
+        fs::write(
+            dir.join("webify.toml"),
+            "[colorspaces]\nspecular = \"linear\"\n",
+        )?;
+
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        let has_chunk = |name: &str, chunk: &[u8]| -> Result<bool, Error> {
+            let png = fs::read(textures.join(name))?;
+            Ok(png.windows(chunk.len()).any(|window| window == chunk))
+        };
+        let linear_gamma = [&b"gAMA"[..], &100_000u32.to_be_bytes()].concat();
+        assert!(has_chunk("floor_roughness.png", &linear_gamma)?);
+        assert!(!has_chunk("floor_roughness.png", b"sRGB")?);
+        assert!(has_chunk("tile_specular.png", &linear_gamma)?);
+        assert!(has_chunk("example.png", b"sRGB")?);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_tags_normal_maps_told_by_their_pixels_as_linear() -> Result<(), Error> {
+        let test_run_id = "test_run_it_tags_normal_maps_told_by_their_pixels_as_linear";
+        setup(test_run_id)?;
+
+        // Named like a color texture, and written as PNG like every texture
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        image::RgbImage::from_fn(16, 16, |x, y| {
+            image::Rgb([118 + (x % 4) as u8 * 6, 122 + (y % 3) as u8 * 6, 250])
+        })
+        .save(dir.join("model").join("cobbles.png"))
+        .map_err(Error::other)?;
+
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        assert!(report.is_success());
+        let png = fs::read(
+            dir.join("model")
+                .join("materials")
+                .join("textures")
+                .join("cobbles.png"),
+        )?;
+        let has_chunk = |chunk: &[u8]| png.windows(chunk.len()).any(|window| window == chunk);
+        assert!(!has_chunk(b"sRGB"));
+        assert!(has_chunk(
+            &[&b"gAMA"[..], &100_000u32.to_be_bytes()].concat()
+        ));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_encodes_textures_to_ktx2() -> Result<(), Error> {
+        let test_run_id = "test_run_it_encodes_textures_to_ktx2";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let options = RunOptions {
+            format: OutputFormat::Ktx2,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(textures.join("example.ktx2").exists());
+        assert!(!textures.join("example.png").exists());
+        assert_eq!(report.textures[0].path, textures.join("example.ktx2"));
+        assert_eq!(
+            report.textures[0].encoding,
+            Some(Encoding::ktx2(&options.ktx2))
+        );
+        assert_eq!(
+            report.textures[0].gpu_bytes,
+            estimate_gpu_memory(640, 427, TRANSCODED_BYTES_PER_PIXEL)
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_renders_impostors() -> Result<(), Error> {
+        let test_run_id = "test_run_it_renders_impostors";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(
+            &Path::new("tests").join("impostor").join("crate"),
+            &dir.join("crate"),
+        )?;
+        let options = RunOptions {
+            impostors: true,
+            format: OutputFormat::Webp,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("crate").join("materials").join("textures");
+        assert!(textures.join("crate_impostor.webp").exists());
+        assert!(textures.join("crate_impostor.json").exists());
+
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let impostor = manifest.models[0].impostor.as_ref().unwrap();
+        assert_eq!(impostor.atlas, "materials/textures/crate_impostor.webp");
+        assert_eq!(impostor.metadata.views, 8);
+        assert!(manifest.models[0]
+            .textures
+            .iter()
+            .any(|texture| texture.path == impostor.atlas));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_packs_texture_atlases() -> Result<(), Error> {
+        let test_run_id = "test_run_it_packs_texture_atlases";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(
+            &Path::new("tests").join("atlas").join("decals"),
+            &dir.join("decals"),
+        )?;
+        let options = RunOptions {
+            atlases: true,
+            format: OutputFormat::Webp,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let textures = dir.join("decals").join("materials").join("textures");
+        assert!(textures.join("decals_atlas.webp").exists());
+        assert!(!textures.join("stripe.webp").exists());
+        assert!(textures.join("floor.webp").exists());
+        let mesh = fs::read_to_string(dir.join("decals").join("meshes").join("decals.dae"))?;
+        assert!(mesh.contains("<init_from>../materials/textures/decals_atlas.webp</init_from>"));
+
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let atlas = manifest.models[0].atlas.as_ref().unwrap();
+        assert_eq!(atlas.atlas, "materials/textures/decals_atlas.webp");
+        assert_eq!(atlas.metadata.regions.len(), 2);
+        assert!(manifest.models[0]
+            .textures
+            .iter()
+            .any(|texture| texture.path == atlas.atlas));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_measures_textures_already_webified() -> Result<(), Error> {
+        let test_run_id = "test_run_it_only_measures_textures_already_webified";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let first = Pipeline::new(RunOptions::default()).run(&dir)?;
+        assert_eq!(first.scan.as_ref().map(|scan| scan.up_to_date), Some(0));
+        let png = dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("example.png");
+        let written = fs::metadata(&png)?.modified()?;
+
+        let second = Pipeline::new(RunOptions::default()).run(&dir)?;
+        assert!(second.is_success());
+        assert_eq!(second.scan.as_ref().map(|scan| scan.up_to_date), Some(1));
+        assert_eq!(fs::metadata(&png)?.modified()?, written);
+        // Still measured, for the manifest
+        assert_eq!(second.textures, first.textures);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_the_same_files_on_every_run() -> Result<(), Error> {
+        // Nothing in the pipeline is random, so there is no seed to pass around:
+        // two runs on the same files, impostors and atlases included, must match
+        let runs = [
+            "test_run_it_writes_the_same_files_first",
+            "test_run_it_writes_the_same_files_second",
+        ];
+        let mut outputs = Vec::new();
+        for test_run_id in runs.iter() {
+            setup(test_run_id)?;
+            let dir = Path::new("tests").join("pipeline").join(test_run_id);
+            copy_tree(
+                &Path::new("tests").join("impostor").join("crate"),
+                &dir.join("crate"),
+            )?;
+            copy_tree(
+                &Path::new("tests").join("atlas").join("decals"),
+                &dir.join("decals"),
+            )?;
+            let options = RunOptions {
+                impostors: true,
+                atlases: true,
+                format: OutputFormat::Webp,
+                ..RunOptions::default()
+            };
+            assert!(Pipeline::new(options).run(&dir)?.is_success());
+
+            let mut files = Vec::new();
+            for file in walk_dir(&dir, &|_| true)? {
+                let relative = file.strip_prefix(&dir).unwrap_or(&file).to_path_buf();
+                files.push((relative, fs::read(&file)?));
+            }
+            files.sort();
+            outputs.push(files);
+            teardown(test_run_id)?;
+        }
+
+        assert!(!outputs[0].is_empty());
+        assert_eq!(
+            outputs[0].iter().map(|(path, _)| path).collect::<Vec<_>>(),
+            outputs[1].iter().map(|(path, _)| path).collect::<Vec<_>>()
+        );
+        for ((path, first), (_, second)) in outputs[0].iter().zip(outputs[1].iter()) {
+            assert!(first == second, "{:?} differs between runs", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_extracts_navmeshes() -> Result<(), Error> {
+        let test_run_id = "test_run_it_extracts_navmeshes";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(&Path::new("tests").join("world"), &dir.join("library"))?;
+        let options = RunOptions {
+            navmeshes: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let worlds = dir.join("library").join("worlds");
+        assert!(worlds.join("apartment.navmesh.json").exists());
+        assert!(worlds.join("apartment.navmesh.obj").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_bakes_terrains() -> Result<(), Error> {
+        let test_run_id = "test_run_it_bakes_terrains";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(
+            &Path::new("tests").join("terrain").join("valley"),
+            &dir.join("library"),
+        )?;
+        let options = RunOptions {
+            terrains: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        let worlds = dir.join("library").join("worlds");
+        assert!(worlds.join("valley.terrain.gltf").exists());
+        assert!(worlds.join("valley.terrain.bin").exists());
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        assert_eq!(
+            manifest.worlds[0].terrain.as_deref(),
+            Some("library/worlds/valley.terrain.gltf")
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_lists_the_scene_of_worlds_in_the_manifest() -> Result<(), Error> {
+        let test_run_id = "test_run_it_lists_the_scene_of_worlds_in_the_manifest";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(&Path::new("tests").join("world"), &dir.join("library"))?;
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        assert!(report.is_success());
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        assert_eq!(manifest.worlds.len(), 1);
+        assert_eq!(manifest.worlds[0].path, "library/worlds/apartment.world");
+        let names: Vec<&str> = manifest.worlds[0]
+            .scene
+            .lights
+            .iter()
+            .map(|light| light.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["sun", "lamp"]);
+        let names: Vec<&str> = manifest.worlds[0]
+            .scene
+            .viewpoints
+            .iter()
+            .map(|viewpoint| viewpoint.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["user_camera", "kitchen"]);
+        let actors = &manifest.worlds[0].scene.actors;
+        assert_eq!(actors.len(), 1);
+        assert_eq!(actors[0].name, "resident");
+        assert_eq!(actors[0].trajectories[0].animation, "walking");
+        assert_eq!(actors[0].trajectories[0].waypoints.len(), 2);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_downscales_textures_too_large_for_some_gpus() -> Result<(), Error> {
+        let test_run_id = "test_run_it_downscales_textures_too_large_for_some_gpus";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        image::ImageBuffer::from_pixel(8200, 4, image::Rgb([200u8, 100, 50]))
+            .save(dir.join("model").join("strip.png"))
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            oversized_textures: OversizedTextures::Downscale,
+            error_policy: ErrorPolicy::Permissive,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        let strip = dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("strip.png");
+        assert_eq!(image::image_dimensions(&strip).unwrap().0, 8192);
+        let forced: Vec<&Warning> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.code == ErrorCode::ForcedDownscale)
+            .collect();
+        assert_eq!(forced.len(), 1);
+        assert_eq!(forced[0].path, strip);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_resizes_textures_to_powers_of_two() -> Result<(), Error> {
+        let test_run_id = "test_run_it_resizes_textures_to_powers_of_two";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        image::ImageBuffer::from_pixel(100, 60, image::Rgb([200u8, 100, 50]))
+            .save(dir.join("model").join("panel.png"))
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            power_of_two: Some(PowerOfTwo::Nearest),
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        let panel = dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("panel.png");
+        assert_eq!(image::image_dimensions(&panel).unwrap(), (128, 64));
+        let resized: Vec<&Warning> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.code == ErrorCode::PowerOfTwo && warning.path == panel)
+            .collect();
+        assert_eq!(resized.len(), 1);
+        assert_eq!(resized[0].message, "resized from 100x60 to 128x64");
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_adjusts_the_colors_of_the_textures_of_a_directory() -> Result<(), Error> {
+        let test_run_id = "test_run_it_adjusts_the_colors_of_the_textures_of_a_directory";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        fs::write(
+            dir.join("webify.toml"),
+            "[color_adjustments]\nmodel = { gamma = 2.0 }\n",
+        )?;
+        for name in ["scan.png", "scan_roughness.png"] {
+            image::ImageBuffer::from_pixel(8, 8, image::Rgb([64u8, 64, 32]))
+                .save(dir.join("model").join(name))
+                .map_err(Error::other)?;
+        }
+
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        let textures = dir.join("model").join("materials").join("textures");
+        let scan = image::open(textures.join("scan.png")).unwrap().to_rgb8();
+        assert_eq!(scan.get_pixel(0, 0), &image::Rgb([128, 128, 90]));
+        // Roughness is data, not colors
+        let roughness = image::open(textures.join("scan_roughness.png"))
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(roughness.get_pixel(0, 0), &image::Rgb([64, 64, 32]));
+        let preview = report
+            .color_adjustments
+            .iter()
+            .find(|preview| preview.path == textures.join("scan.png"))
+            .unwrap();
+        assert_eq!(preview.adjustment, "gamma 2");
+        assert!(preview.before.starts_with("data:image/png;base64,"));
+        assert!(report
+            .color_adjustments
+            .iter()
+            .all(|preview| preview.path != textures.join("scan_roughness.png")));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_shrinks_textures_over_the_byte_budget() -> Result<(), Error> {
+        let test_run_id = "test_run_it_shrinks_textures_over_the_byte_budget";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        // The example photo would be shrunk too, slowly in a debug build
+        fs::remove_file(dir.join("model").join("example.jpg"))?;
+        // Noise, which WebP can't make much smaller at any quality
+        image::ImageBuffer::from_fn(64, 64, |x, y| {
+            let seed = x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503);
+            let value = seed.wrapping_mul(seed) >> 8;
+            image::Rgb([value as u8, (value >> 8) as u8, (value >> 16) as u8])
+        })
+        .save(dir.join("model").join("noise.png"))
+        .map_err(Error::other)?;
+
+        let options = RunOptions {
+            format: OutputFormat::Webp,
+            max_bytes: Some(1024),
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        let textures = dir.join("model").join("materials").join("textures");
+        let noise = textures.join("noise.webp");
+        assert!(fs::metadata(&noise)?.len() <= 1024);
+        assert!(!textures.join("noise.png").exists());
+        let (width, height) = image::image_dimensions(&noise).unwrap();
+        assert!(width < 64 && width == height);
+        let shrunk: Vec<&Warning> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.code == ErrorCode::ByteBudget && warning.path == noise)
+            .collect();
+        assert_eq!(shrunk.len(), 1);
+        assert!(shrunk[0].message.starts_with(&format!(
+            "quality lowered from 80 to 40 and downscaled from 64x64 to {}x{}",
+            width, height
+        )));
+        let stats = report.textures.iter().find(|t| t.path == noise).unwrap();
+        assert_eq!(stats.encoding.as_ref().unwrap().quality, Some(40));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_the_mip_chain_of_textures_next_to_them() -> Result<(), Error> {
+        let test_run_id = "test_run_it_writes_the_mip_chain_of_textures_next_to_them";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        image::ImageBuffer::from_pixel(16, 8, image::Rgb([200u8, 100, 50]))
+            .save(dir.join("model").join("panel.png"))
+            .map_err(Error::other)?;
+
+        let options = RunOptions {
+            format: OutputFormat::Webp,
+            mipmaps: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(textures.join("panel.webp").is_file());
+        let dimensions: Vec<(u32, u32)> = (1..=4)
+            .map(|level| image::image_dimensions(textures.join(format!("panel_mip{}.png", level))))
+            .collect::<Result<_, _>>()
+            .map_err(Error::other)?;
+        assert_eq!(dimensions, vec![(8, 4), (4, 2), (2, 1), (1, 1)]);
+        assert!(!textures.join("panel_mip5.png").exists());
+
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let panel = manifest
+            .models
+            .iter()
+            .flat_map(|model| &model.textures)
+            .find(|texture| texture.path == "materials/textures/panel.webp")
+            .unwrap();
+        assert_eq!(
+            panel.mips,
+            vec![
+                "materials/textures/panel_mip1.png",
+                "materials/textures/panel_mip2.png",
+                "materials/textures/panel_mip3.png",
+                "materials/textures/panel_mip4.png"
+            ]
+        );
+
+        // The levels aren't taken for textures of their own on the next run
+        let rerun = Pipeline::new(options).run(&dir)?;
+        assert_eq!(rerun.textures.len(), report.textures.len());
+        assert!(!textures.join("panel_mip1.webp").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_moves_sounds_next_to_their_model() -> Result<(), Error> {
+        let test_run_id = "test_run_it_moves_sounds_next_to_their_model";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        copy_tree(
+            &Path::new("tests").join("audio").join("door"),
+            &dir.join("door"),
+        )?;
+        let options = RunOptions {
+            audio: AudioSettings {
+                ffmpeg: PathBuf::from("webify-missing-ffmpeg"),
+                ..AudioSettings::default()
+            },
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        let sounds = dir.join("door").join("sounds");
+        assert!(sounds.join("creak.WAV").is_file());
+        let kept: Vec<&Warning> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.code == ErrorCode::AudioFallback)
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].path, sounds.join("creak.WAV"));
+        assert!(fs::read_to_string(dir.join("door").join("model.sdf"))?
+            .contains("<uri>model://door/sounds/creak.WAV</uri>"));
+
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?)?;
+        let door = manifest
+            .models
+            .iter()
+            .find(|model| model.name == "door")
+            .unwrap();
+        let paths: Vec<&str> = door
+            .sounds
+            .iter()
+            .map(|sound| sound.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["sounds/ambient.ogg", "sounds/creak.WAV"]);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_warns_about_models_including_each_other() -> Result<(), Error> {
+        let test_run_id = "test_run_it_warns_about_models_including_each_other";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        for (model, included) in [("desk", "drawer"), ("drawer", "desk")].iter() {
+            fs::create_dir_all(dir.join(model))?;
+            fs::write(
+                dir.join(model).join("model.config"),
+                "<?xml version=\"1.0\"?>\n<model>\n  <sdf>model.sdf</sdf>\n</model>\n",
+            )?;
+            fs::write(
+                dir.join(model).join("model.sdf"),
+                format!(
+                    "<sdf>\n  <include><uri>model://{}</uri></include>\n</sdf>\n",
+                    included
+                ),
+            )?;
+        }
+
+        let report = Pipeline::new(RunOptions::default()).run(&dir)?;
+
+        let cycles: Vec<&Warning> = report
+            .warnings
+            .iter()
+            .filter(|warning| warning.code == ErrorCode::CircularDependency)
+            .collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].path, dir.join("desk"));
+        assert_eq!(
+            cycles[0].message,
+            "includes itself through desk -> drawer -> desk"
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_the_source_untouched_with_an_output() -> Result<(), Error> {
+        let test_run_id = "test_run_it_writes_to_the_output";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let source = dir.join("source");
+        let output = dir.join("output");
+        fs::create_dir(&source)?;
+        fs::rename(dir.join("model"), source.join("model"))?;
+
+        let options = RunOptions {
+            output: Some(output.clone()),
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&source)?;
+
+        assert!(report.is_success());
+        assert!(source.join("model").join("example.jpg").exists());
+        assert!(!source.join(MANIFEST_FILE_NAME).exists());
+        assert!(output
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("example.png")
+            .exists());
+        assert!(output.join(MANIFEST_FILE_NAME).exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_outputs_holding_the_source_or_other_files() -> Result<(), Error> {
+        let test_run_id = "test_run_it_refuses_outputs_holding_the_source_or_other_files";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let source = dir.join("source");
+        let stale_output = dir.join("stale_output");
+        fs::create_dir(&source)?;
+        fs::rename(dir.join("model"), source.join("model"))?;
+        fs::create_dir(&stale_output)?;
+        fs::write(stale_output.join("notes.txt"), "keep")?;
+
+        let containing = Pipeline::new(RunOptions {
+            output: Some(dir.clone()),
+            ..RunOptions::default()
+        })
+        .run(&source);
+        let not_empty = Pipeline::new(RunOptions {
+            output: Some(stale_output.clone()),
+            ..RunOptions::default()
+        })
+        .run(&source);
+        let untouched = source.join("model").join("example.jpg").exists();
+        let merged = stale_output.join("model").exists();
+
+        teardown(test_run_id)?;
+        assert!(containing.is_err());
+        assert!(not_empty.is_err());
+        assert!(untouched);
+        assert!(!merged);
+        Ok(())
+    }
+
+    #[test]
+    fn it_backs_up_the_originals_into_a_mirror_tree() -> Result<(), Error> {
+        let test_run_id = "test_run_it_backs_up_the_originals_into_a_mirror_tree";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let source = dir.join("source");
+        let backup_dir = dir.join("backup");
+        fs::create_dir(&source)?;
+        fs::rename(dir.join("model"), source.join("model"))?;
+        let original = fs::read(source.join("model").join("example.jpg"))?;
+
+        let inside = [
+            source.join("backup"),
+            Path::new(".").join(&source).join("backup"),
+            env::current_dir()?.join(&source).join("backup"),
+        ]
+        .iter()
+        .map(|backup_dir| {
+            Pipeline::new(RunOptions {
+                removal: Removal::BackupDir {
+                    dir: backup_dir.clone(),
+                    root: PathBuf::new(),
+                },
+                ..RunOptions::default()
+            })
+            .run(&source)
+        })
+        .collect::<Vec<_>>();
+        let report = Pipeline::new(RunOptions {
+            removal: Removal::BackupDir {
+                dir: backup_dir.clone(),
+                root: PathBuf::new(),
+            },
+            ..RunOptions::default()
+        })
+        .run(&source)?;
+
+        assert!(inside.iter().all(|run| run.is_err()));
+        assert!(!source.join("backup").exists());
+        assert!(report.is_success());
+        let textures = Path::new("model").join("materials").join("textures");
+        assert!(source.join(&textures).join("example.png").exists());
+        assert!(!source.join(&textures).join("example.jpg").exists());
+        assert_eq!(
+            fs::read(backup_dir.join(&textures).join("example.jpg"))?,
+            original
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_a_trash_dir_inside_the_processed_tree() -> Result<(), Error> {
+        let test_run_id = "test_run_it_refuses_a_trash_dir_inside_the_processed_tree";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let runs = [
+            dir.join("trash"),
+            Path::new(".").join(&dir).join("trash"),
+            env::current_dir()?.join(&dir).join("trash"),
+        ]
+        .iter()
+        .map(|trash_dir| {
+            Pipeline::new(RunOptions {
+                removal: Removal::TrashDir(trash_dir.clone()),
+                ..RunOptions::default()
+            })
+            .run(&dir)
+        })
+        .collect::<Vec<_>>();
+        let untouched = dir.join("model").join("example.jpg").exists();
+        let trashed = dir.join("trash").exists();
+
+        teardown(test_run_id)?;
+        assert!(runs.iter().all(|run| run.is_err()));
+        assert!(untouched);
+        assert!(!trashed);
+        Ok(())
+    }
+
+    #[test]
+    fn it_renames_the_originals_to_orig() -> Result<(), Error> {
+        let test_run_id = "test_run_it_renames_the_originals_to_orig";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let options = RunOptions {
+            removal: Removal::Orig,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options.clone()).run(&dir)?;
+        let rerun = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        assert!(rerun.is_success());
+        let textures = dir.join("model").join("materials").join("textures");
+        assert!(textures.join("example.png").exists());
+        assert!(textures.join("example.jpg.orig").exists());
+        // Originals kept aside aren't textures of their own
+        assert!(!textures.join("example.jpg.orig.png").exists());
+        assert!(!textures.join("example.jpg.2.orig").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_watermarks_only_preview_builds() -> Result<(), Error> {
+        let test_run_id = "test_run_it_watermarks_only_preview_builds";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let source = dir.join("source");
+        let output = dir.join("output");
+        fs::create_dir(&source)?;
+        fs::rename(dir.join("model"), source.join("model"))?;
+        for name in ["storefront.png", "storefront_roughness.png"] {
+            image::ImageBuffer::from_pixel(64, 64, image::Rgb([90u8, 60, 30]))
+                .save(source.join("model").join(name))
+                .map_err(Error::other)?;
+        }
+
+        let options = RunOptions {
+            watermark: Some(WatermarkMark::Text(String::from("PREVIEW"))),
+            ..RunOptions::default()
+        };
+        let in_place = Pipeline::new(options.clone()).run(&source);
+        let report = Pipeline::new(RunOptions {
+            output: Some(output.clone()),
+            ..options
+        })
+        .run(&source)?;
+
+        assert!(in_place.is_err());
+        assert!(report.is_success());
+        let plain = image::Rgb([90u8, 60, 30]);
+        let original = image::open(source.join("model").join("storefront.png"))
+            .unwrap()
+            .to_rgb8();
+        assert!(original.pixels().all(|pixel| *pixel == plain));
+        let textures = output.join("model").join("materials").join("textures");
+        let storefront = image::open(textures.join("storefront.png"))
+            .unwrap()
+            .to_rgb8();
+        assert!(storefront.pixels().any(|pixel| *pixel != plain));
+        // Roughness is data, not colors
+        let roughness = image::open(textures.join("storefront_roughness.png"))
+            .unwrap()
+            .to_rgb8();
+        assert!(roughness.pixels().all(|pixel| *pixel == plain));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_the_operations_in_the_audit_log() -> Result<(), Error> {
+        let test_run_id = "test_run_it_records_the_operations_in_the_audit_log";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let source = dir.join("model").join("example.jpg");
+        let source_hash = hash_file(&source)?;
+        let options = RunOptions {
+            audit: true,
+            ..RunOptions::default()
+        };
+        Pipeline::new(options).run(&dir)?;
+
+        let result = dir
+            .join("model")
+            .join("materials")
+            .join("textures")
+            .join("example.png");
+        let history = crate::audit::file_history(&source)?;
+        assert_eq!(history.tampered, None);
+        assert_eq!(history.entries.len(), 1);
+        let entry = &history.entries[0];
+        assert_eq!(entry.operation, "Texture Processing");
+        assert_eq!(entry.source, Path::new("model").join("example.jpg"));
+        assert_eq!(entry.source_hash, source_hash);
+        assert_eq!(entry.result_hash, hash_file(&result)?);
+        assert_eq!(
+            crate::audit::file_history(&result)?.entries,
+            history.entries
+        );
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_appends_the_report_to_the_event_log() -> Result<(), Error> {
+        let test_run_id = "test_run_it_appends_the_report_to_the_event_log";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let event_log = dir.join("events.ndjson");
+        let options = RunOptions {
+            event_log: Some(event_log.clone()),
+            timings: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        let replayed = crate::report::replay_event_log(&event_log)?;
+        assert_eq!(
+            replayed,
+            RunReport {
+                event_log: None,
+                ..report
+            }
+        );
+        assert_eq!(replayed.textures.len(), 1);
+        assert!(!replayed.cancelled);
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_the_text_changes_of_a_dry_run() -> Result<(), Error> {
+        let test_run_id = "test_run_it_reports_the_text_changes_of_a_dry_run";
+        setup(test_run_id)?;
+
+        let dir = Path::new("tests").join("pipeline").join(test_run_id);
+        let mesh = "<COLLADA>\n  <image>\n    <init_from>example.jpg</init_from>\n  </image>\n</COLLADA>\n";
+        fs::write(dir.join("model").join("example.dae"), mesh)?;
+
+        let options = RunOptions {
+            dry_run: true,
+            ..RunOptions::default()
+        };
+        let report = Pipeline::new(options).run(&dir)?;
+
+        assert!(report.is_success());
+        assert!(dir.join("model").join("example.jpg").exists());
+        assert!(!dir.join(MANIFEST_FILE_NAME).exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("model").join("example.dae"))?,
+            mesh
+        );
+        assert_eq!(report.text_changes.len(), 1);
+        assert_eq!(
+            report.text_changes[0].path,
+            Path::new("model").join("example.dae")
+        );
+        assert!(report.text_changes[0]
+            .diff
+            .contains("+    <init_from>materials/textures/example.png</init_from>\n"));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
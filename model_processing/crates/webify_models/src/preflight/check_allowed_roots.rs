@@ -0,0 +1,53 @@
+//! Keep destructive runs inside the approved library roots
+
+use std::{
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+/// Refuse to modify a directory outside every allowed root, e.g. a home directory
+/// full of photos. Every directory is allowed when there are no roots.
+pub fn check_allowed_roots(dir: &Path, allowed_roots: &[PathBuf]) -> Result<(), Error> {
+    if allowed_roots.is_empty() {
+        return Ok(());
+    }
+
+    let dir = dir.canonicalize()?;
+    for root in allowed_roots {
+        match root.canonicalize() {
+            Ok(root) if dir.starts_with(&root) => return Ok(()),
+            Ok(_) => {}
+            // A root that doesn't exist (e.g. an unmounted drive) can't contain the directory
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(Error::other(format!(
+        "{:?} isn't inside any of the allowed roots {:?}. Add it to allowed_roots in the user configuration if it really is a model library.",
+        dir, allowed_roots
+    )))
+}
+
+#[cfg(test)]
+mod check_allowed_roots_tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_dirs_inside_an_allowed_root() {
+        let roots = vec![PathBuf::from("/does/not/exist"), PathBuf::from("tests")];
+        assert!(check_allowed_roots(&Path::new("tests").join("model"), &roots).is_ok());
+    }
+
+    #[test]
+    fn it_refuses_dirs_outside_the_allowed_roots() {
+        let roots = vec![Path::new("tests").join("model")];
+        assert!(check_allowed_roots(&Path::new("tests").join("compare"), &roots).is_err());
+    }
+
+    #[test]
+    fn it_allows_everything_without_roots() {
+        assert!(check_allowed_roots(&Path::new("tests").join("compare"), &[]).is_ok());
+    }
+}
@@ -0,0 +1,81 @@
+//! Keep the output directory of a run apart from its source
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::pipeline::is_within;
+
+/// Refuse an output directory inside the source or containing it, where the run
+/// would process the source too, or one that already holds files the run would
+/// merge into. The output directory must be new or empty.
+pub fn check_output_dir(source: &Path, output: &Path) -> Result<(), Error> {
+    if is_within(output, source)? {
+        return Err(Error::other(format!(
+            "The output directory {:?} can't be inside the source directory {:?}.",
+            output, source
+        )));
+    }
+    if !output.exists() {
+        return Ok(());
+    }
+    if is_within(source, output)? {
+        return Err(Error::other(format!(
+            "The output directory {:?} can't contain the source directory {:?}.",
+            output, source
+        )));
+    }
+    if fs::read_dir(output)?.next().is_some() {
+        return Err(Error::other(format!(
+            "The output directory {:?} isn't empty, pass a new or empty directory.",
+            output
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod check_output_dir_tests {
+    use super::*;
+
+    use std::env;
+
+    #[test]
+    fn it_refuses_an_output_inside_the_source() -> Result<(), Error> {
+        let source = Path::new("tests").join("model");
+        let absolute = env::current_dir()?.join(&source);
+
+        assert!(check_output_dir(&source, &source.join("output")).is_err());
+        assert!(check_output_dir(&source, &Path::new(".").join(&source).join("output")).is_err());
+        assert!(check_output_dir(&source, &absolute.join("output")).is_err());
+        assert!(check_output_dir(&absolute, &source.join("output")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_an_output_containing_the_source() -> Result<(), Error> {
+        let source = Path::new("tests").join("model").join("collection");
+
+        assert!(check_output_dir(&source, Path::new("tests")).is_err());
+        assert!(check_output_dir(&source, &env::current_dir()?.join("tests")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_accepts_only_new_or_empty_outputs() -> Result<(), Error> {
+        let source = Path::new("tests").join("model");
+        let output = Path::new("tests")
+            .join("pipeline")
+            .join("test_run_check_output_dir");
+        fs::create_dir_all(&output)?;
+
+        let empty = check_output_dir(&source, &output);
+        fs::write(output.join("stale.png"), "stale")?;
+        let not_empty = check_output_dir(&source, &output);
+        fs::remove_dir_all(&output)?;
+
+        assert!(empty.is_ok());
+        assert!(not_empty.is_err());
+        assert!(check_output_dir(&source, &output).is_ok());
+        Ok(())
+    }
+}
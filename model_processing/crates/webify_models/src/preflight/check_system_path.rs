@@ -0,0 +1,89 @@
+//! Guard against a mistyped base path pointing an elevated run at system files
+
+use std::{
+    io::Error,
+    path::{Component, Path, PathBuf},
+    result::Result,
+};
+
+/// Directories that belong to the system, along with every drive root
+#[cfg(not(windows))]
+const SYSTEM_DIRS: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/sbin", "/sys", "/usr", "/System",
+];
+#[cfg(windows)]
+const SYSTEM_DIRS: &[&str] = &[
+    "\\windows",
+    "\\program files",
+    "\\program files (x86)",
+    "\\programdata",
+];
+
+/// Refuse to modify a system path when running as root or Administrator, where
+/// nothing else would stop the pipeline from deleting files it shouldn't touch
+pub fn check_system_path(dir: &Path) -> Result<(), Error> {
+    let dir = dir.canonicalize()?;
+    if is_system_path(&dir) && is_elevated() {
+        return Err(Error::other(format!(
+            "Refusing to webify {:?} with elevated permissions, it's a system path and webify_models deletes files. Double check the path, or pass --i-know-what-im-doing if this really is intended.",
+            dir
+        )));
+    }
+
+    Ok(())
+}
+
+/// Whether the absolute path is a drive root or inside a system directory
+fn is_system_path(path: &Path) -> bool {
+    // Drop the drive, so `C:\Windows` and `\\?\C:\Windows` both become `\Windows`
+    let rooted: PathBuf = path
+        .components()
+        .filter(|component| !matches!(component, Component::Prefix(_)))
+        .collect();
+    #[cfg(windows)]
+    let rooted = PathBuf::from(rooted.to_string_lossy().to_lowercase());
+
+    rooted.parent().is_none() || SYSTEM_DIRS.iter().any(|dir| rooted.starts_with(dir))
+}
+
+#[cfg(unix)]
+fn is_elevated() -> bool {
+    // Safe, geteuid can't fail and has no side effects
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(windows)]
+fn is_elevated() -> bool {
+    // Only Administrators can list the system profile
+    let system_root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+    Path::new(&system_root)
+        .join("System32")
+        .join("config")
+        .join("systemprofile")
+        .read_dir()
+        .is_ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_elevated() -> bool {
+    false
+}
+
+#[cfg(all(test, unix))]
+mod check_system_path_tests {
+    use super::*;
+
+    #[test]
+    fn it_recognises_system_paths() {
+        assert!(is_system_path(Path::new("/")));
+        assert!(is_system_path(Path::new("/usr")));
+        assert!(is_system_path(Path::new("/usr/share/textures")));
+        assert!(!is_system_path(Path::new("/home/user/models")));
+        assert!(!is_system_path(Path::new("/usrmodels")));
+    }
+
+    #[test]
+    fn it_accepts_regular_paths() {
+        assert!(check_system_path(&Path::new("tests").join("model")).is_ok());
+    }
+}
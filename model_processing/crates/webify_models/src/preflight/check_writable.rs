@@ -0,0 +1,56 @@
+//! Make sure the directory can be modified in place
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+/// Name of the file briefly created to check the directory is writable
+const PROBE_FILE_NAME: &str = ".webify-write-check";
+
+/// Fail when the directory can't be written to, e.g. a source tree mounted
+/// read-only, rather than erroring on the first texture move
+pub fn check_writable(dir: &Path) -> Result<(), Error> {
+    let probe = dir.join(PROBE_FILE_NAME);
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => fs::remove_file(probe),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(()),
+        Err(e) if is_read_only(&e) => Err(Error::new(
+            e.kind(),
+            format!(
+                "{:?} is read-only, and webify_models modifies models in place. Pass --output <dir> to write the processed models somewhere else.",
+                dir
+            ),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether the error means the directory can't be written to
+fn is_read_only(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+    )
+}
+
+#[cfg(test)]
+mod check_writable_tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_writable_dirs() {
+        let dir = Path::new("tests").join("compare");
+        assert!(check_writable(&dir).is_ok());
+        assert!(!dir.join(PROBE_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn it_recognises_read_only_errors() {
+        assert!(is_read_only(&Error::from(ErrorKind::ReadOnlyFilesystem)));
+        assert!(is_read_only(&Error::from(ErrorKind::PermissionDenied)));
+        assert!(!is_read_only(&Error::from(ErrorKind::NotFound)));
+    }
+}
@@ -0,0 +1,113 @@
+//! Find the textures that can't be decoded before anything is touched
+
+use std::{
+    io::Error,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    result::Result,
+    sync::Mutex,
+};
+
+use rayon::prelude::*;
+
+use crate::cli::create_progress_bar;
+use crate::image_processing::{decode_texture, estimate_decode_memory, is_decodable, MemoryBudget};
+use crate::options::HdrSettings;
+use crate::provenance::{read_marker, ProcessedMarker};
+use crate::walk::walk_dir;
+
+/// Decode every texture of the directory with one of the extensions in parallel,
+/// within the memory budget, and return the ones that can't be with the reason,
+/// sorted by path: truncated or corrupt files, including those the decoders panic
+/// on. Textures a previous run processed with the same settings as `marker` decoded
+/// then and are skipped, as are the formats the converter leaves as they are.
+/// Nothing is changed.
+pub fn find_corrupt_images(
+    dir: &Path,
+    extensions: &[String],
+    hdr: &HdrSettings,
+    marker: &ProcessedMarker,
+    memory_budget: Option<u64>,
+) -> Result<Vec<(PathBuf, String)>, Error> {
+    let is_texture = |path: &Path| {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        extensions.contains(&extension) && is_decodable(&extension)
+    };
+    let mut paths = Vec::new();
+    for path in walk_dir(dir, &is_texture)? {
+        if read_marker(&path)?.is_none_or(|previous| !previous.same_settings(marker)) {
+            paths.push(path);
+        }
+    }
+
+    let bar = create_progress_bar(paths.len() as u64);
+    bar.set_prefix("Image Validation");
+    let corrupt = Mutex::new(Vec::new());
+    let memory_budget = memory_budget.map(MemoryBudget::new);
+    paths.par_iter().for_each(|path| {
+        let _reservation = memory_budget
+            .as_ref()
+            .map(|budget| budget.reserve(estimate_decode_memory(path)));
+        bar.inc(1);
+        let reason = match catch_unwind(AssertUnwindSafe(|| decode_texture(path, hdr))) {
+            Ok(Ok(_)) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => String::from("the decoder panicked on it"),
+        };
+        corrupt.lock().unwrap().push((path.clone(), reason));
+    });
+    bar.finish_and_clear();
+
+    let mut corrupt = corrupt.into_inner().unwrap();
+    corrupt.sort();
+    Ok(corrupt)
+}
+
+#[cfg(test)]
+mod find_corrupt_images_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{ImageBuffer, Rgb};
+
+    use crate::config::WebifyConfig;
+    use crate::options::RunOptions;
+
+    #[test]
+    fn it_finds_truncated_textures() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("image_processing")
+            .join("test_run_find_corrupt_images");
+        fs::create_dir_all(dir.join("model"))?;
+        ImageBuffer::from_pixel(16, 16, Rgb([200u8, 100, 50]))
+            .save(dir.join("model").join("wood.jpg"))
+            .map_err(Error::other)?;
+        let jpeg = fs::read(dir.join("model").join("wood.jpg"))?;
+        fs::write(
+            dir.join("model").join("broken.jpg"),
+            &jpeg[..jpeg.len() / 3],
+        )?;
+        fs::write(dir.join("model").join("empty.png"), b"")?;
+
+        let extensions = vec![String::from("jpg"), String::from("png")];
+        let marker = ProcessedMarker::new(&RunOptions::default(), &WebifyConfig::default());
+        let corrupt =
+            find_corrupt_images(&dir, &extensions, &HdrSettings::default(), &marker, None);
+        fs::remove_dir_all(&dir)?;
+
+        let paths: Vec<PathBuf> = corrupt?.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("model").join("broken.jpg"),
+                dir.join("model").join("empty.png")
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Checks run before anything is touched, so a run that can't succeed or shouldn't
+//! happen fails fast with a clear message
+
+mod check_allowed_roots;
+mod check_output_dir;
+mod check_system_path;
+mod check_writable;
+mod find_corrupt_images;
+
+pub use self::check_allowed_roots::check_allowed_roots;
+pub use self::check_output_dir::check_output_dir;
+pub use self::check_system_path::check_system_path;
+pub use self::check_writable::check_writable;
+pub use self::find_corrupt_images::find_corrupt_images;
@@ -0,0 +1,122 @@
+//! Structure that records how a texture file was encoded
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::options::{AvifSettings, BasisCodec, Ktx2Settings, WebpSettings};
+
+/// End of the color space of textures whose identical color channels were written
+/// as one
+const GRAYSCALE: &str = "as 8-bit grayscale";
+
+/// Encoder and settings a texture file was written with, so a bad-looking texture
+/// can be traced back to them months later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Encoding {
+    /// Library that wrote the file, with its version
+    pub encoder: String,
+    /// Format of the file, e.g. `png`, `avif`, `webp` or `ktx2`
+    pub format: String,
+    /// Quality of lossy formats, from 1 to 100, or 1 to 255 for KTX2 ETC1S
+    pub quality: Option<u8>,
+    /// Encoder speed from 1 (most effort) to 10 (least effort), or the UASTC effort
+    /// from 0 (least) to 4 (most) for KTX2, when it has one
+    pub speed: Option<u8>,
+    /// Color space of the input and how the file stores it
+    pub color_space: String,
+}
+
+impl Encoding {
+    /// Lossless PNG written by the image crate
+    pub fn png() -> Encoding {
+        Encoding {
+            encoder: String::from("image 0.23"),
+            format: String::from("png"),
+            quality: None,
+            speed: None,
+            color_space: String::from("sRGB"),
+        }
+    }
+
+    /// Indexed PNG of a palette of `colors` colors, learned by NeuQuant when the
+    /// texture had more
+    pub fn indexed_png(colors: usize) -> Encoding {
+        Encoding {
+            encoder: String::from("png 0.16 (color_quant 1.1)"),
+            format: String::from("png"),
+            quality: None,
+            speed: None,
+            color_space: format!("sRGB as a palette of {} colors", colors),
+        }
+    }
+
+    /// PNG of a texture whose color channels were identical, written by the image
+    /// crate with a single gray channel
+    pub fn grayscale_png() -> Encoding {
+        Encoding {
+            color_space: format!("sRGB {}", GRAYSCALE),
+            ..Encoding::png()
+        }
+    }
+
+    /// Whether the texture was written with a single gray channel, its color
+    /// channels having been identical
+    pub fn is_grayscale(&self) -> bool {
+        self.color_space.ends_with(GRAYSCALE)
+    }
+
+    /// AVIF written by ravif, which stores the sRGB input as 10-bit YCbCr
+    pub fn avif(settings: &AvifSettings) -> Encoding {
+        Encoding {
+            encoder: String::from("ravif 0.11"),
+            format: String::from("avif"),
+            quality: Some(settings.quality),
+            speed: Some(settings.speed),
+            color_space: String::from("sRGB as 10-bit YCbCr (BT.601)"),
+        }
+    }
+
+    /// WebP written by libwebp, lossy WebP stores the sRGB input as 8-bit YUV 4:2:0
+    pub fn webp(settings: &WebpSettings) -> Encoding {
+        Encoding {
+            encoder: String::from("libwebp (webp 0.3)"),
+            format: String::from("webp"),
+            quality: if settings.lossless {
+                None
+            } else {
+                Some(settings.quality)
+            },
+            speed: None,
+            color_space: String::from(if settings.lossless {
+                "sRGB"
+            } else {
+                "sRGB as 8-bit YUV 4:2:0"
+            }),
+        }
+    }
+
+    /// KTX2 written by Basis Universal, which stores the sRGB input as ETC1S or
+    /// UASTC blocks transcoded to the GPU's format at load time
+    pub fn ktx2(settings: &Ktx2Settings) -> Encoding {
+        let (quality, speed) = match settings.codec {
+            BasisCodec::Etc1s => (Some(settings.quality), None),
+            BasisCodec::Uastc => (None, Some(settings.uastc_level)),
+        };
+        Encoding {
+            encoder: String::from("basis-universal 0.3 (Basis Universal 1.16)"),
+            format: String::from("ktx2"),
+            quality,
+            speed,
+            color_space: format!("sRGB as {} blocks", settings.codec.name().to_uppercase()),
+        }
+    }
+
+    /// The same encoding of a normal map, whose input is linear data rather than
+    /// sRGB colors
+    pub fn linear(self) -> Encoding {
+        Encoding {
+            color_space: self.color_space.replacen("sRGB", "linear", 1),
+            ..self
+        }
+    }
+}
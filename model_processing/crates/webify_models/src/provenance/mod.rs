@@ -0,0 +1,12 @@
+//! Sidecar files marking processed files with the pipeline version and settings
+//! they were webified with, so a rerun can tell they're already done
+
+mod encoding;
+mod processed_marker;
+mod read_marker;
+mod write_marker;
+
+pub use self::encoding::Encoding;
+pub use self::processed_marker::{sidecar_path, ProcessedMarker, SIDECAR_EXTENSION};
+pub use self::read_marker::read_marker;
+pub use self::write_marker::write_marker;
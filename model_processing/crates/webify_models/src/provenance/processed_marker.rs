@@ -0,0 +1,141 @@
+//! Structure recorded in the sidecar of a processed file
+
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::WebifyConfig;
+use crate::options::RunOptions;
+use crate::provenance::Encoding;
+
+/// Extension appended to a processed file's name for its sidecar
+pub const SIDECAR_EXTENSION: &str = "webify.json";
+
+/// What a file was processed with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessedMarker {
+    /// Version of webify_models that processed the file
+    pub pipeline_version: String,
+    /// Hash of the settings that change the output, so a run with other settings
+    /// processes the file again
+    pub settings_hash: String,
+    /// How the file was encoded, or `None` when it was left as it came
+    #[serde(default)]
+    pub encoding: Option<Encoding>,
+}
+
+impl ProcessedMarker {
+    /// Marker for files processed by this version with these settings. Settings that
+    /// don't change the output, like the error policy or timings, are left out.
+    pub fn new(options: &RunOptions, config: &WebifyConfig) -> ProcessedMarker {
+        let settings = format!(
+            "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+            options.stages,
+            options.shared_textures,
+            config,
+            options.format,
+            options.avif,
+            options.webp,
+            options.ktx2,
+            options.max_dim,
+            options.max_bytes,
+            options.resize_filter,
+            options.power_of_two,
+            options.mipmaps,
+            options.quantize.then_some(options.palette),
+            options.strip_metadata,
+            options
+                .watermark
+                .as_ref()
+                .map(|mark| (mark, options.watermark_settings)),
+            options.audio.bitrate
+        );
+
+        ProcessedMarker {
+            pipeline_version: String::from(env!("CARGO_PKG_VERSION")),
+            settings_hash: blake3::hash(settings.as_bytes()).to_hex().to_string(),
+            encoding: None,
+        }
+    }
+
+    /// Whether the other marker was written by the same version with the same
+    /// settings, whatever encoding its file ended up with
+    pub fn same_settings(&self, other: &ProcessedMarker) -> bool {
+        self.pipeline_version == other.pipeline_version && self.settings_hash == other.settings_hash
+    }
+}
+
+/// Sidecar of a file, next to it: `wood.png` gets `wood.png.webify.json`
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(SIDECAR_EXTENSION);
+
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod processed_marker_tests {
+    use super::*;
+
+    use crate::config::CategoryPreset;
+    use crate::options::{ErrorPolicy, OutputFormat, Stage, StageSelection};
+
+    #[test]
+    fn it_compares_settings_whatever_the_encoding() {
+        let marker = ProcessedMarker::new(&RunOptions::default(), &WebifyConfig::default());
+        let encoded = ProcessedMarker {
+            encoding: Some(Encoding::png()),
+            ..marker.clone()
+        };
+        assert!(encoded.same_settings(&marker));
+
+        let avif = RunOptions {
+            format: OutputFormat::Avif,
+            ..RunOptions::default()
+        };
+        assert!(!ProcessedMarker::new(&avif, &WebifyConfig::default()).same_settings(&marker));
+    }
+
+    #[test]
+    fn it_only_hashes_settings_that_change_the_output() {
+        let marker = ProcessedMarker::new(&RunOptions::default(), &WebifyConfig::default());
+
+        let verbose = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            timings: true,
+            ..RunOptions::default()
+        };
+        assert_eq!(
+            ProcessedMarker::new(&verbose, &WebifyConfig::default()),
+            marker
+        );
+
+        let no_downscale = RunOptions {
+            stages: StageSelection::skipping(&[Stage::Downscale]).unwrap(),
+            ..RunOptions::default()
+        };
+        assert_ne!(
+            ProcessedMarker::new(&no_downscale, &WebifyConfig::default()),
+            marker
+        );
+
+        let mut config = WebifyConfig::default();
+        config
+            .categories
+            .insert(String::from("hero"), CategoryPreset { max_size: 512 });
+        assert_ne!(
+            ProcessedMarker::new(&RunOptions::default(), &config),
+            marker
+        );
+    }
+
+    #[test]
+    fn it_puts_the_sidecar_next_to_the_file() {
+        assert_eq!(
+            sidecar_path(&Path::new("textures").join("wood.png")),
+            Path::new("textures").join("wood.png.webify.json")
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Read the sidecar of a processed file
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::provenance::{sidecar_path, ProcessedMarker};
+
+/// Marker of the file, or `None` when it has no sidecar. A sidecar that can't be
+/// parsed is treated as missing, so the file simply gets processed again.
+pub fn read_marker(path: &Path) -> Result<Option<ProcessedMarker>, Error> {
+    let sidecar = sidecar_path(path);
+    if !sidecar.is_file() {
+        return Ok(None);
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(sidecar)?).ok())
+}
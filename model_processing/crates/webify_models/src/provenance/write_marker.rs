@@ -0,0 +1,41 @@
+//! Write the sidecar of a processed file
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::provenance::{sidecar_path, ProcessedMarker};
+
+/// Mark the file as processed, writing its sidecar next to it
+pub fn write_marker(path: &Path, marker: &ProcessedMarker) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(marker).map_err(Error::other)?;
+    fs::write(sidecar_path(path), contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod write_marker_tests {
+    use super::*;
+
+    use crate::config::WebifyConfig;
+    use crate::options::RunOptions;
+    use crate::provenance::read_marker;
+
+    #[test]
+    fn it_round_trips_the_marker() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("provenance")
+            .join("test_run_write_marker");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("wood.png");
+        fs::write(&path, "not really a png")?;
+
+        assert_eq!(read_marker(&path)?, None);
+
+        let marker = ProcessedMarker::new(&RunOptions::default(), &WebifyConfig::default());
+        write_marker(&path, &marker)?;
+        assert_eq!(read_marker(&path)?, Some(marker));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Rename a model directory and point every `model://` URI of the library at its
+//! new name, so worlds and models including it keep working
+
+mod process;
+mod rename_model_config_name;
+mod rename_model_uri;
+mod validate_model_name;
+
+pub use self::process::process;
+pub use self::rename_model_config_name::{
+    rename_model_config_name, rename_model_config_name_contents,
+};
+pub use self::rename_model_uri::{rename_model_uri, MODEL_URI_FILE_EXTENSIONS};
+pub use self::validate_model_name::validate_model_name;
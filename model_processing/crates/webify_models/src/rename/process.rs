@@ -0,0 +1,156 @@
+//! Orchestrator to rename a model across a library
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use console::style;
+
+use crate::config::{load_user_config, user_config_path};
+use crate::preflight::{check_allowed_roots, check_writable};
+use crate::rename::{
+    rename_model_config_name, rename_model_uri, validate_model_name, MODEL_URI_FILE_EXTENSIONS,
+};
+use crate::walk::walk_dir;
+use crate::xml::edit_file_text_nodes;
+
+/// Rename the model directory to `new_name`, along with its name in model.config
+/// and every `model://` URI pointing at it in the library's SDFs, worlds and
+/// meshes. Returns the files that were rewritten.
+pub fn process(
+    library: &Path,
+    model_dir: &Path,
+    new_name: &str,
+) -> std::result::Result<Vec<PathBuf>, std::io::Error> {
+    validate_model_name(new_name)?;
+    if !model_dir.join("model.config").is_file() {
+        return Err(Error::other(format!(
+            "{:?} isn't a model directory, it has no model.config.",
+            model_dir
+        )));
+    }
+    if !model_dir
+        .canonicalize()?
+        .starts_with(library.canonicalize()?)
+    {
+        return Err(Error::other(format!(
+            "{:?} isn't inside the library {:?}.",
+            model_dir, library
+        )));
+    }
+    let old_name = model_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::other(format!("{:?} has no usable name.", model_dir)))?
+        .to_string();
+    let new_dir = model_dir.with_file_name(new_name);
+    if new_dir.exists() {
+        return Err(Error::other(format!("{:?} already exists.", new_dir)));
+    }
+    if let Some(config_path) = user_config_path() {
+        check_allowed_roots(library, &load_user_config(&config_path)?.allowed_roots)?;
+    }
+    check_writable(library)?;
+
+    println!(
+        "\nRenaming {} to {}...",
+        style(model_dir.to_string_lossy()).dim(),
+        style(new_dir.to_string_lossy()).dim()
+    );
+    fs::rename(model_dir, &new_dir)?;
+
+    let mut rewritten = Vec::new();
+    if rename_model_config_name(&new_dir, &old_name, new_name)? {
+        rewritten.push(new_dir.join("model.config"));
+    }
+    let files = walk_dir(library, &|path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MODEL_URI_FILE_EXTENSIONS.contains(&ext))
+    })?;
+    for file in files {
+        if edit_file_text_nodes(&file, |value| rename_model_uri(value, &old_name, new_name))? {
+            rewritten.push(file);
+        }
+    }
+
+    for file in &rewritten {
+        println!("  updated {}", style(file.to_string_lossy()).dim());
+    }
+    println!(
+        "{} {} file(s) updated.",
+        style("Model renamed,").green().bold(),
+        rewritten.len()
+    );
+
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod rename_tests {
+    use super::*;
+
+    use crate::pipeline::copy_tree;
+
+    fn setup(test_run_id: &str) -> Result<PathBuf, Error> {
+        let library = Path::new("tests").join("rename").join(test_run_id);
+        copy_tree(&Path::new("tests").join("rename").join("library"), &library)?;
+
+        Ok(library)
+    }
+
+    fn teardown(test_run_id: &str) -> Result<(), Error> {
+        fs::remove_dir_all(Path::new("tests").join("rename").join(test_run_id))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_renames_the_model_across_the_library() -> Result<(), Error> {
+        let test_run_id = "test_run_it_renames_the_model_across_the_library";
+        let library = setup(test_run_id)?;
+
+        let rewritten = process(
+            &library,
+            &library.join("models").join("desk"),
+            "office_desk",
+        )?;
+
+        let model_dir = library.join("models").join("office_desk");
+        assert!(!library.join("models").join("desk").exists());
+        assert_eq!(
+            rewritten,
+            vec![
+                model_dir.join("model.config"),
+                model_dir.join("model.sdf"),
+                library.join("worlds").join("office.world"),
+            ]
+        );
+        assert!(fs::read_to_string(model_dir.join("model.config"))?
+            .contains("<name>office_desk</name>"));
+        assert!(fs::read_to_string(model_dir.join("model.sdf"))?
+            .contains("<uri>model://office_desk/meshes/desk.dae</uri>"));
+        let world = fs::read_to_string(library.join("worlds").join("office.world"))?;
+        assert!(world.contains("<uri>model://office_desk</uri>"));
+        assert!(world.contains("<uri>model://desk_lamp</uri>"));
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuses_names_already_taken() -> Result<(), Error> {
+        let test_run_id = "test_run_it_refuses_names_already_taken";
+        let library = setup(test_run_id)?;
+
+        let result = process(&library, &library.join("models").join("desk"), "desk_lamp");
+
+        assert!(result.is_err());
+        assert!(library.join("models").join("desk").exists());
+
+        teardown(test_run_id)?;
+        Ok(())
+    }
+}
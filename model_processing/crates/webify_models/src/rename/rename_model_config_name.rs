@@ -0,0 +1,97 @@
+//! Rename a model in its model.config, keeping the file's formatting
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{escape::escape, events::Event, Reader};
+
+/// Set the `<name>` of the model.config in the model directory to `new` when it's
+/// `old`, the directory name. Display names like `Office Desk` are left alone.
+/// Returns whether the file changed.
+pub fn rename_model_config_name(model_dir: &Path, old: &str, new: &str) -> Result<bool, Error> {
+    let config_path = model_dir.join("model.config");
+    if !config_path.is_file() {
+        return Ok(false);
+    }
+
+    let contents = fs::read_to_string(&config_path)?;
+    let edited = rename_model_config_name_contents(&contents, old, new)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", config_path, e)))?;
+    match edited {
+        Some(edited) => fs::write(config_path, edited)?,
+        None => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Rename the model in the contents of a model.config. Returns the edited contents,
+/// or `None` when its name isn't `old`.
+pub fn rename_model_config_name_contents(
+    contents: &str,
+    old: &str,
+    new: &str,
+) -> Result<Option<String>, Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader
+            .read_event()
+            .map_err(|e| Error::other(e.to_string()))?
+        {
+            Event::Start(e) => path.push(e.name().as_ref().to_vec()),
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(text) if path == [b"model".to_vec(), b"name".to_vec()] => {
+                let value = text.unescape().map_err(|e| Error::other(e.to_string()))?;
+                if value.trim() != old {
+                    return Ok(None);
+                }
+
+                // Keep the whitespace around the name
+                let end = reader.buffer_position() as usize;
+                let raw = &contents[start..end];
+                let leading = raw.len() - raw.trim_start().len();
+                let trailing = raw.len() - raw.trim_end().len();
+                let mut edited = contents.to_string();
+                edited.replace_range(start + leading..end - trailing, &escape(new));
+                return Ok(Some(edited));
+            }
+            Event::Eof => return Ok(None),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rename_model_config_name_tests {
+    use super::*;
+
+    #[test]
+    fn it_renames_the_model_named_after_its_directory() -> Result<(), Error> {
+        let contents = "<?xml version=\"1.0\"?>\n<model>\n  <!-- desk -->\n  <name> desk </name>\n  <sdf version=\"1.6\">model.sdf</sdf>\n</model>\n";
+
+        assert_eq!(
+            rename_model_config_name_contents(contents, "desk", "office_desk")?,
+            Some(contents.replace("<name> desk </name>", "<name> office_desk </name>"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_display_names() -> Result<(), Error> {
+        let contents = fs::read_to_string(
+            Path::new("tests")
+                .join("model")
+                .join("ceiling_lamp")
+                .join("model.config"),
+        )?;
+
+        assert_eq!(
+            rename_model_config_name_contents(&contents, "ceiling_lamp", "pendant_lamp")?,
+            None
+        );
+        Ok(())
+    }
+}
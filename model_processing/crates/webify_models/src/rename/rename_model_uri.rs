@@ -0,0 +1,46 @@
+//! Point a `model://` URI at a renamed model
+
+/// Extensions of the files whose text values may hold `model://` URIs
+pub const MODEL_URI_FILE_EXTENSIONS: &[&str] = &["dae", "sdf", "world"];
+
+/// The value pointed at the model named `new` when it's a `model://` URI of the
+/// model named `old` (`model://old` or anything under `model://old/`), `None`
+/// otherwise
+pub fn rename_model_uri(value: &str, old: &str, new: &str) -> Option<String> {
+    let rest = value.strip_prefix("model://")?.strip_prefix(old)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+
+    Some(format!("model://{}{}", new, rest))
+}
+
+#[cfg(test)]
+mod rename_model_uri_tests {
+    use super::*;
+
+    #[test]
+    fn it_renames_the_model_and_its_files() {
+        assert_eq!(
+            rename_model_uri("model://desk", "desk", "office_desk"),
+            Some(String::from("model://office_desk"))
+        );
+        assert_eq!(
+            rename_model_uri("model://desk/meshes/desk.dae", "desk", "office_desk"),
+            Some(String::from("model://office_desk/meshes/desk.dae"))
+        );
+    }
+
+    #[test]
+    fn it_leaves_other_models_alone() {
+        assert_eq!(
+            rename_model_uri("model://desk_lamp", "desk", "office_desk"),
+            None
+        );
+        assert_eq!(
+            rename_model_uri("meshes/desk.dae", "desk", "office_desk"),
+            None
+        );
+        assert_eq!(rename_model_uri("file://desk", "desk", "office_desk"), None);
+    }
+}
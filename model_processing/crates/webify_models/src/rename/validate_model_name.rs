@@ -0,0 +1,39 @@
+//! Check a new model name can be used as a directory name and in `model://` URIs
+
+use std::{io::Error, result::Result};
+
+/// Refuse names that aren't a single plain directory name, since the name ends
+/// up both as a directory and in `model://<name>/...` URIs
+pub fn validate_model_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c == ':' || c.is_whitespace() || c.is_control());
+
+    match valid {
+        true => Ok(()),
+        false => Err(Error::other(format!(
+            "{:?} can't be a model name, it must be a single directory name without spaces.",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod validate_model_name_tests {
+    use super::*;
+
+    #[test]
+    fn it_accepts_plain_directory_names() {
+        assert!(validate_model_name("office_desk-2").is_ok());
+    }
+
+    #[test]
+    fn it_refuses_paths_and_spaces() {
+        for name in ["", ".", "..", "models/desk", "office desk", "C:desk"].iter() {
+            assert!(validate_model_name(name).is_err(), "{:?}", name);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Annotate the failures and warnings of a report with who last changed the file
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::report::{last_committer, RunReport};
+
+/// Set the author of every failure and warning to the last committer of its file
+/// in the source. Paths under `processed`, the copy the run worked on with
+/// `--output` or `--dry-run`, are looked up at the same place in `source`. Each
+/// file is looked up once, however many problems it has.
+pub fn attribute_authors(report: &mut RunReport, processed: &Path, source: &Path) {
+    let mut authors: BTreeMap<PathBuf, Option<String>> = BTreeMap::new();
+    let mut author_of = |path: &Path| {
+        authors
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let original = match path.strip_prefix(processed) {
+                    Ok(relative) => source.join(relative),
+                    Err(_) => path.to_path_buf(),
+                };
+                last_committer(&original)
+            })
+            .clone()
+    };
+
+    for failure in &mut report.failures {
+        failure.author = author_of(&failure.path);
+    }
+    for warning in &mut report.warnings {
+        warning.author = author_of(&warning.path);
+    }
+}
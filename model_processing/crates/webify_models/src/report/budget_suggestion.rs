@@ -0,0 +1,42 @@
+//! Structure that suggests how to bring a model back under its GPU budget
+
+use std::{fmt, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::report::format_bytes;
+
+/// A texture of a model over its GPU budget, with the size that would help
+/// bring the model back under it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BudgetSuggestion {
+    /// Root directory of the model over budget
+    pub model: PathBuf,
+    /// Texture to downscale
+    pub texture: PathBuf,
+    /// Current width and height of the texture
+    pub from: (u32, u32),
+    /// Suggested width and height, keeping the aspect ratio
+    pub to: (u32, u32),
+    /// Estimated GPU memory saved, in bytes
+    pub saved_bytes: u64,
+}
+
+impl fmt::Display for BudgetSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "downscale {} from {}x{} to {}x{} saves ~{}",
+            self.texture
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            self.from.0,
+            self.from.1,
+            self.to.0,
+            self.to.1,
+            format_bytes(self.saved_bytes)
+        )
+    }
+}
@@ -0,0 +1,20 @@
+//! Structure that records a texture whose colors were corrected
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A texture corrected by the `[color_adjustments]` of `webify.toml`, with
+/// thumbnails to check the correction by eye
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ColorAdjustmentPreview {
+    /// Path of the texture when it was adjusted, as a PNG
+    pub path: PathBuf,
+    /// What was done, e.g. `white balance for tungsten, gamma 1.2`
+    pub adjustment: String,
+    /// Thumbnail before the adjustment, as a PNG data URI
+    pub before: String,
+    /// Thumbnail after the adjustment, as a PNG data URI
+    pub after: String,
+}
@@ -0,0 +1,18 @@
+//! Structure that records a texture removed as a copy of another
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A texture byte-identical to another one, removed with its references pointed
+/// at the copy that was kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicateTexture {
+    /// Path the removed copy had
+    pub path: PathBuf,
+    /// Path of the copy that was kept
+    pub canonical: PathBuf,
+    /// Size of the removed copy on disk, in bytes
+    pub file_bytes: u64,
+}
@@ -0,0 +1,244 @@
+//! Stable public codes of the failure classes
+
+use std::fmt;
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Class of a failure or warning, with a code that never changes meaning so CI can
+/// route problems to the right team. The hundreds group the classes by area:
+/// textures, references, model files, budgets, then the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorCode {
+    /// A texture couldn't be decoded or converted
+    Decode,
+    /// A texture couldn't be encoded to the output format
+    Encode,
+    /// A texture couldn't be moved to its textures directory
+    TextureMove,
+    /// A texture couldn't be downscaled
+    Downscale,
+    /// A texture couldn't be measured
+    Measure,
+    /// A texture was left in a format that isn't converted
+    UnconvertedFormat,
+    /// A texture was kept in PNG because the other format didn't pay off
+    EncodeFallback,
+    /// A texture has dimensions the web renderer handles badly
+    TextureSize,
+    /// A texture was downscaled to what every GPU can upload
+    ForcedDownscale,
+    /// A texture was resized to power of two dimensions
+    PowerOfTwo,
+    /// A texture's mip chain couldn't be generated
+    MipChain,
+    /// A texture's byte-identical copies couldn't be merged into one
+    Dedup,
+    /// A texture couldn't be renamed to the hash of its contents
+    HashName,
+    /// A model's occlusion, roughness and metalness maps couldn't be packed together
+    OrmPack,
+    /// A texture was shrunk to fit within the byte budget, or couldn't be
+    ByteBudget,
+    /// A mesh couldn't be updated
+    MeshUpdate,
+    /// The references of a file couldn't be repaired
+    ReferenceRepair,
+    /// A mesh references a texture that doesn't exist
+    MissingReference,
+    /// An absolute reference couldn't be resolved inside the library
+    UnresolvedReference,
+    /// Models include each other
+    CircularDependency,
+    /// A model.config couldn't be marked as processed
+    ModelConfig,
+    /// The texture metadata couldn't be added to an SDF
+    SdfMetadata,
+    /// A model's impostor billboard couldn't be rendered
+    Impostor,
+    /// A world's navmesh couldn't be extracted
+    Navmesh,
+    /// A world's lights and viewpoints couldn't be read
+    WorldScene,
+    /// A model's small textures couldn't be packed into an atlas
+    Atlas,
+    /// A world's heightmaps couldn't be baked into terrain meshes
+    Terrain,
+    /// A file's sensor visuals couldn't be stripped or substituted
+    SensorVisuals,
+    /// A sound couldn't be moved, transcoded or referenced
+    Audio,
+    /// A sound was kept in its format, e.g. without FFmpeg
+    AudioFallback,
+    /// A model's textures exceed the GPU memory budget
+    GpuBudget,
+    /// The file system refused an operation: a missing file, a denied permission
+    FileSystem,
+    /// A bug in webify_models, like a panic
+    Internal,
+}
+
+impl ErrorCode {
+    /// Every code, in order
+    pub const ALL: [ErrorCode; 33] = [
+        ErrorCode::Decode,
+        ErrorCode::Encode,
+        ErrorCode::TextureMove,
+        ErrorCode::Downscale,
+        ErrorCode::Measure,
+        ErrorCode::UnconvertedFormat,
+        ErrorCode::EncodeFallback,
+        ErrorCode::TextureSize,
+        ErrorCode::ForcedDownscale,
+        ErrorCode::PowerOfTwo,
+        ErrorCode::MipChain,
+        ErrorCode::Dedup,
+        ErrorCode::HashName,
+        ErrorCode::OrmPack,
+        ErrorCode::ByteBudget,
+        ErrorCode::MeshUpdate,
+        ErrorCode::ReferenceRepair,
+        ErrorCode::MissingReference,
+        ErrorCode::UnresolvedReference,
+        ErrorCode::CircularDependency,
+        ErrorCode::ModelConfig,
+        ErrorCode::SdfMetadata,
+        ErrorCode::Impostor,
+        ErrorCode::Navmesh,
+        ErrorCode::WorldScene,
+        ErrorCode::Atlas,
+        ErrorCode::Terrain,
+        ErrorCode::SensorVisuals,
+        ErrorCode::Audio,
+        ErrorCode::AudioFallback,
+        ErrorCode::GpuBudget,
+        ErrorCode::FileSystem,
+        ErrorCode::Internal,
+    ];
+
+    /// The public code, e.g. `E0101`
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorCode::Decode => "E0101",
+            ErrorCode::Encode => "E0102",
+            ErrorCode::TextureMove => "E0103",
+            ErrorCode::Downscale => "E0104",
+            ErrorCode::Measure => "E0105",
+            ErrorCode::UnconvertedFormat => "E0106",
+            ErrorCode::EncodeFallback => "E0107",
+            ErrorCode::TextureSize => "E0108",
+            ErrorCode::ForcedDownscale => "E0109",
+            ErrorCode::PowerOfTwo => "E0110",
+            ErrorCode::MipChain => "E0111",
+            ErrorCode::Dedup => "E0112",
+            ErrorCode::HashName => "E0113",
+            ErrorCode::OrmPack => "E0114",
+            ErrorCode::ByteBudget => "E0115",
+            ErrorCode::MeshUpdate => "E0201",
+            ErrorCode::ReferenceRepair => "E0202",
+            ErrorCode::MissingReference => "E0203",
+            ErrorCode::UnresolvedReference => "E0204",
+            ErrorCode::CircularDependency => "E0205",
+            ErrorCode::ModelConfig => "E0301",
+            ErrorCode::SdfMetadata => "E0302",
+            ErrorCode::Impostor => "E0303",
+            ErrorCode::Navmesh => "E0304",
+            ErrorCode::WorldScene => "E0305",
+            ErrorCode::Atlas => "E0306",
+            ErrorCode::Terrain => "E0307",
+            ErrorCode::SensorVisuals => "E0308",
+            ErrorCode::GpuBudget => "E0401",
+            ErrorCode::Audio => "E0501",
+            ErrorCode::AudioFallback => "E0502",
+            ErrorCode::FileSystem => "E0901",
+            ErrorCode::Internal => "E0902",
+        }
+    }
+
+    /// Exit status of a run failing with this code alone: 10 plus the hundreds of
+    /// the code, e.g. 11 for texture problems and 19 for the environment
+    pub fn exit_status(self) -> i32 {
+        10 + self.code()[1..3].parse::<i32>().unwrap()
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ErrorCode, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        ErrorCode::ALL
+            .iter()
+            .find(|c| c.code() == code)
+            .copied()
+            .ok_or_else(|| de::Error::custom(format!("unknown error code {}", code)))
+    }
+}
+
+impl JsonSchema for ErrorCode {
+    fn schema_name() -> String {
+        String::from("ErrorCode")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(ErrorCode::ALL.iter().map(|c| c.code().into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod error_code_tests {
+    use super::*;
+
+    #[test]
+    fn it_has_unique_codes() {
+        let mut codes: Vec<&str> = ErrorCode::ALL.iter().map(|c| c.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+    }
+
+    #[test]
+    fn it_maps_codes_to_exit_statuses() {
+        assert_eq!(ErrorCode::Decode.exit_status(), 11);
+        assert_eq!(ErrorCode::MissingReference.exit_status(), 12);
+        assert_eq!(ErrorCode::GpuBudget.exit_status(), 14);
+        assert_eq!(ErrorCode::FileSystem.exit_status(), 19);
+    }
+
+    #[test]
+    fn it_serializes_to_the_code() {
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::MissingReference).unwrap(),
+            r#""E0203""#
+        );
+    }
+
+    #[test]
+    fn it_deserializes_from_the_code() {
+        assert_eq!(
+            serde_json::from_str::<ErrorCode>(r#""E0112""#).unwrap(),
+            ErrorCode::Dedup
+        );
+        assert!(serde_json::from_str::<ErrorCode>(r#""E9999""#).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Append what happens during a run to disk as it happens
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{Error, Write},
+    path::{Path, PathBuf},
+    result::Result,
+    time::SystemTime,
+};
+
+use crate::report::{ReportEvent, RunReport};
+
+/// Event log of a run, a file of one JSON event per line (NDJSON) appended to after
+/// every step, so a run that crashes still leaves the report of the files it got
+/// through. Keeps count of the entries of the report already written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventLog {
+    path: PathBuf,
+    failures: usize,
+    warnings: usize,
+    timings: usize,
+    textures: usize,
+    meshes: usize,
+    duplicates: usize,
+    color_adjustments: usize,
+}
+
+impl EventLog {
+    /// Start the event log of a run at `path`, replacing the log of a previous run
+    pub fn create(path: &Path) -> Result<EventLog, Error> {
+        fs::write(path, "")?;
+        let log = EventLog {
+            path: path.to_path_buf(),
+            ..EventLog::default()
+        };
+        log.append(&[ReportEvent::Started {
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            started: SystemTime::now(),
+        }])?;
+
+        Ok(log)
+    }
+
+    /// Append the entries added to the report since the last flush. Textures removed
+    /// from the report, by deduplication, are covered by their `duplicate` events.
+    pub fn flush(&mut self, report: &RunReport) -> Result<(), Error> {
+        let textures = self.textures.min(report.textures.len());
+        let events: Vec<ReportEvent> = (report.failures[self.failures..].iter())
+            .cloned()
+            .map(ReportEvent::Failure)
+            .chain(
+                report.warnings[self.warnings..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::Warning),
+            )
+            .chain(
+                report.timings[self.timings..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::Timing),
+            )
+            .chain(
+                report.textures[textures..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::Texture),
+            )
+            .chain(
+                report.meshes[self.meshes..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::Mesh),
+            )
+            .chain(
+                report.duplicates[self.duplicates..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::Duplicate),
+            )
+            .chain(
+                report.color_adjustments[self.color_adjustments..]
+                    .iter()
+                    .cloned()
+                    .map(ReportEvent::ColorAdjustment),
+            )
+            .collect();
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.append(&events)?;
+
+        self.failures = report.failures.len();
+        self.warnings = report.warnings.len();
+        self.timings = report.timings.len();
+        self.textures = report.textures.len();
+        self.meshes = report.meshes.len();
+        self.duplicates = report.duplicates.len();
+        self.color_adjustments = report.color_adjustments.len();
+        Ok(())
+    }
+
+    /// Append what's left of the report once the run is over, which marks the run
+    /// as completed
+    pub fn finish(&mut self, report: &RunReport) -> Result<(), Error> {
+        self.flush(report)?;
+        self.append(&[ReportEvent::Finished {
+            scan: report.scan.clone(),
+            budget_suggestions: report.budget_suggestions.clone(),
+            text_changes: report.text_changes.clone(),
+            cancelled: report.cancelled,
+            out_of_time: report.out_of_time,
+            remaining: report.remaining.clone(),
+        }])
+    }
+
+    /// Write the events in a single append, so a crash can only cut the last line
+    fn append(&self, events: &[ReportEvent]) -> Result<(), Error> {
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event).map_err(Error::other)?);
+            lines.push('\n');
+        }
+        OpenOptions::new()
+            .append(true)
+            .open(&self.path)?
+            .write_all(lines.as_bytes())
+    }
+}
@@ -0,0 +1,59 @@
+//! Structure that represents a file that failed to process
+
+use std::{fmt, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::report::ErrorCode;
+
+/// A file that failed to process in one of the stages
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Failure {
+    /// Class of the problem, stable across versions
+    pub code: ErrorCode,
+    /// Name of the stage the failure happened in
+    pub stage: &'static str,
+    /// File that failed to process
+    pub path: PathBuf,
+    /// What went wrong
+    pub message: String,
+    /// Last committer of the file, with `--git-authors` when it's tracked by git
+    pub author: Option<String>,
+}
+
+impl Failure {
+    pub fn new(
+        code: ErrorCode,
+        stage: &'static str,
+        path: PathBuf,
+        error: impl fmt::Display,
+    ) -> Failure {
+        Failure {
+            code,
+            stage,
+            path,
+            message: error.to_string(),
+            author: None,
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            self.code,
+            self.stage,
+            self.path.to_string_lossy(),
+            self.message
+        )?;
+        match &self.author {
+            Some(author) => write!(f, " (last changed by {})", author),
+            None => Ok(()),
+        }
+    }
+}
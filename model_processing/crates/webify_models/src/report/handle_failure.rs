@@ -0,0 +1,72 @@
+//! Apply the error policy to a single failure
+
+use std::io::Error;
+
+use console::{style, Term};
+
+use crate::options::ErrorPolicy;
+use crate::report::{Failure, RunReport};
+
+/// Apply the error policy to a failure: either record it and keep going, or
+/// return an error to abort the run
+pub fn handle_failure(
+    policy: ErrorPolicy,
+    report: &mut RunReport,
+    failure: Failure,
+) -> std::result::Result<(), std::io::Error> {
+    match policy {
+        ErrorPolicy::Strict => Err(Error::other(failure)),
+        ErrorPolicy::Permissive => {
+            report.failures.push(failure);
+            Ok(())
+        }
+        ErrorPolicy::Interactive => {
+            let term = Term::stderr();
+            term.write_line(&format!("\n{}", style(&failure).red()))?;
+            term.write_str("Continue with the rest of the run? [y/N] ")?;
+            let answer = term.read_line()?;
+
+            if answer.trim().eq_ignore_ascii_case("y") {
+                report.failures.push(failure);
+                Ok(())
+            } else {
+                Err(Error::other(failure))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod handle_failure_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::report::ErrorCode;
+
+    fn failure() -> Failure {
+        Failure::new(
+            ErrorCode::Decode,
+            "PNG Conversion",
+            PathBuf::from("foo.jpg"),
+            "broken",
+        )
+    }
+
+    #[test]
+    fn it_aborts_when_strict() {
+        let mut report = RunReport::default();
+        let result = handle_failure(ErrorPolicy::Strict, &mut report, failure());
+
+        assert!(result.is_err());
+        assert!(report.failures.is_empty());
+    }
+
+    #[test]
+    fn it_collects_when_permissive() {
+        let mut report = RunReport::default();
+        handle_failure(ErrorPolicy::Permissive, &mut report, failure()).unwrap();
+
+        assert_eq!(report.failures, vec![failure()]);
+    }
+}
@@ -0,0 +1,16 @@
+//! A texture or mesh ranked by its size on disk
+
+use std::path::Path;
+
+use crate::dependencies::AssetKind;
+
+/// A processed texture or mesh, to rank the heaviest files of a run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeavyAsset<'a> {
+    pub kind: AssetKind,
+    pub path: &'a Path,
+    /// Root directory of the model the file belongs to
+    pub model: Option<&'a Path>,
+    /// Size of the file on disk after processing, in bytes
+    pub file_bytes: u64,
+}
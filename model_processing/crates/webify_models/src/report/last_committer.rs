@@ -0,0 +1,89 @@
+//! Find who last committed a file with git
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Name and email of the last committer of `path`, as `git log` shows them. A
+/// file the run converted, like a JPEG encoded to KTX2, is attributed through the
+/// files of the same name with any extension in its directory. `None` when git
+/// can't be run, the file isn't in a repository, or it was never committed.
+pub fn last_committer(path: &Path) -> Option<String> {
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => env::current_dir().ok()?.join(path),
+    };
+    // git runs from the closest directory that still exists
+    let dir = path.ancestors().skip(1).find(|dir| dir.is_dir())?;
+
+    git_last_author(dir, path.as_os_str().to_string_lossy().into_owned()).or_else(|| {
+        let stem = path.file_stem()?.to_str()?;
+        let siblings: PathBuf = path.with_file_name(format!("{}.*", stem));
+        git_last_author(dir, format!(":(glob){}", siblings.to_string_lossy()))
+    })
+}
+
+/// Author of the last commit touching `pathspec`, `None` when there's none
+fn git_last_author(dir: &Path, pathspec: String) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "-1", "--format=%an <%ae>", "--"])
+        .arg(pathspec)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let author = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    match author.is_empty() {
+        true => None,
+        false => Some(author),
+    }
+}
+
+#[cfg(test)]
+mod last_committer_tests {
+    use super::*;
+
+    use std::{fs, io::Error};
+
+    fn git(dir: &Path, args: &[&str]) -> Result<bool, Error> {
+        Ok(Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .output()?
+            .status
+            .success())
+    }
+
+    #[test]
+    fn it_finds_the_last_committer() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("report")
+            .join("test_run_last_committer");
+        let textures = dir.join("model").join("materials").join("textures");
+        fs::create_dir_all(&textures)?;
+        fs::write(textures.join("wood.jpg"), "wood")?;
+        fs::write(textures.join("untracked.png"), "untracked")?;
+
+        // Only checked where git is installed
+        if git(&dir, &["init", "-q"]).unwrap_or(false) {
+            assert!(git(&dir, &["add", "model/materials/textures/wood.jpg"])?);
+            assert!(git(&dir, &["commit", "-q", "-m", "Add wood"])?);
+
+            let author = Some(String::from("Ada <ada@example.com>"));
+            assert_eq!(last_committer(&textures.join("wood.jpg")), author);
+            assert_eq!(last_committer(&textures.join("wood.ktx2")), author);
+            assert_eq!(last_committer(&textures.join("untracked.png")), None);
+        }
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
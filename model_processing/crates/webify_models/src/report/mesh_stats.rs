@@ -0,0 +1,20 @@
+//! Structure that records the final size and complexity of a processed mesh
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh_update::MeshComplexity;
+
+/// Final size of a processed mesh on disk, and what it costs to render
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MeshStats {
+    /// Path of the processed mesh
+    pub path: PathBuf,
+    /// Root directory of the model the mesh belongs to
+    pub model: Option<PathBuf>,
+    /// Size of the file on disk, in bytes
+    pub file_bytes: u64,
+    pub complexity: MeshComplexity,
+}
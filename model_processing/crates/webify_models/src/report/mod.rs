@@ -0,0 +1,55 @@
+//! Collect what happened during a run so it can be reported at the end
+
+mod attribute_authors;
+mod budget_suggestion;
+mod color_adjustment_preview;
+mod duplicate_texture;
+mod error_code;
+mod event_log;
+mod failure;
+mod format_bytes;
+mod handle_failure;
+mod heavy_asset;
+mod last_committer;
+mod mesh_stats;
+mod print_summary;
+mod replay_event_log;
+mod report_event;
+mod run_report;
+mod run_step;
+mod scan_stats;
+mod suggest_downscales;
+mod text_change;
+mod texture_stats;
+mod time_file;
+mod timing;
+mod warning;
+mod write_html_report;
+mod write_json_report;
+
+pub use self::attribute_authors::attribute_authors;
+pub use self::budget_suggestion::BudgetSuggestion;
+pub use self::color_adjustment_preview::ColorAdjustmentPreview;
+pub use self::duplicate_texture::DuplicateTexture;
+pub use self::error_code::ErrorCode;
+pub use self::event_log::EventLog;
+pub use self::failure::Failure;
+pub use self::format_bytes::format_bytes;
+pub use self::handle_failure::handle_failure;
+pub use self::heavy_asset::HeavyAsset;
+pub use self::last_committer::last_committer;
+pub use self::mesh_stats::MeshStats;
+pub use self::print_summary::print_summary;
+pub use self::replay_event_log::replay_event_log;
+pub use self::report_event::ReportEvent;
+pub use self::run_report::RunReport;
+pub use self::run_step::run_step;
+pub use self::scan_stats::ScanStats;
+pub use self::suggest_downscales::suggest_downscales;
+pub use self::text_change::TextChange;
+pub use self::texture_stats::TextureStats;
+pub use self::time_file::time_file;
+pub use self::timing::Timing;
+pub use self::warning::Warning;
+pub use self::write_html_report::write_html_report;
+pub use self::write_json_report::write_json_report;
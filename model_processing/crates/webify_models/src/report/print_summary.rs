@@ -0,0 +1,213 @@
+//! Print what happened during the run
+
+use console::style;
+
+use crate::dependencies::AssetKind;
+use crate::options::RunOptions;
+use crate::report::{format_bytes, RunReport, TextureStats};
+
+/// How many entries the slowest-files table shows
+const SLOWEST_FILES_COUNT: usize = 10;
+
+/// How many models the GPU memory table shows
+const GPU_MEMORY_MODELS_COUNT: usize = 10;
+
+/// How many files the heaviest assets table shows
+const HEAVIEST_ASSETS_COUNT: usize = 20;
+
+/// Print the timings, warnings and failures collected during the run
+pub fn print_summary(report: &RunReport, options: &RunOptions) {
+    if options.dry_run {
+        print_text_changes(report);
+    }
+
+    if let Some(scan) = &report.scan {
+        println!(
+            "\n{} {} directories, {} textures ({}) in {:.1?}",
+            style("Scan").bold(),
+            scan.directories,
+            scan.candidates,
+            format_bytes(scan.bytes),
+            scan.duration
+        );
+        if scan.up_to_date > 0 {
+            println!(
+                "  {} already webified with the same settings, only measured",
+                scan.up_to_date
+            );
+        }
+    }
+
+    if !report.timings.is_empty() {
+        println!("\n{}", style("Timings").bold());
+        for timing in &report.timings {
+            println!("  {}", timing);
+        }
+
+        println!("\n{}", style("Slowest files").bold());
+        for timing in report.slowest(SLOWEST_FILES_COUNT) {
+            println!(
+                "  {:>12} {:<16} {}",
+                format!("{:.1?}", timing.duration),
+                timing.stage,
+                timing.path.to_string_lossy()
+            );
+        }
+    }
+
+    if !report.textures.is_empty() {
+        let mut models: Vec<_> = report.gpu_bytes_by_model().into_iter().collect();
+        models.sort_by_key(|(_, gpu_bytes)| std::cmp::Reverse(*gpu_bytes));
+        let total: u64 = report.textures.iter().map(|t| t.gpu_bytes).sum();
+
+        println!(
+            "\n{} (total {})",
+            style("Estimated GPU memory").bold(),
+            format_bytes(total)
+        );
+        for (model, gpu_bytes) in models.iter().take(GPU_MEMORY_MODELS_COUNT) {
+            let over_budget = options.gpu_budget.is_some_and(|budget| *gpu_bytes > budget);
+            let size = format!("{:>10}", format_bytes(*gpu_bytes));
+            println!(
+                "  {} {}",
+                if over_budget {
+                    style(size).red().bold()
+                } else {
+                    style(size)
+                },
+                model.to_string_lossy()
+            );
+        }
+    }
+
+    let grayscale: Vec<&TextureStats> = report
+        .textures
+        .iter()
+        .filter(|t| t.encoding.as_ref().is_some_and(|e| e.is_grayscale()))
+        .collect();
+    if !grayscale.is_empty() {
+        println!(
+            "\n{} {} texture(s) with identical color channels as grayscale:",
+            style("Wrote").bold(),
+            grayscale.len()
+        );
+        for texture in grayscale {
+            println!(
+                "  {:>10} {}",
+                format_bytes(texture.file_bytes),
+                texture.path.to_string_lossy()
+            );
+        }
+    }
+
+    if !report.duplicates.is_empty() {
+        println!(
+            "\n{} {} duplicate texture(s), saving {}",
+            style("Deduplicated").bold(),
+            report.duplicates.len(),
+            format_bytes(report.duplicates.iter().map(|d| d.file_bytes).sum())
+        );
+    }
+
+    if !report.color_adjustments.is_empty() {
+        println!(
+            "\n{} the colors of {} texture(s):",
+            style("Adjusted").bold(),
+            report.color_adjustments.len()
+        );
+        for preview in &report.color_adjustments {
+            println!(
+                "  {} ({})",
+                preview.path.to_string_lossy(),
+                preview.adjustment
+            );
+        }
+    }
+
+    if !report.budget_suggestions.is_empty() {
+        println!("\n{}", style("To fit the GPU budget").bold());
+        let mut model = None;
+        for suggestion in &report.budget_suggestions {
+            if model != Some(&suggestion.model) {
+                model = Some(&suggestion.model);
+                println!("  {}", suggestion.model.to_string_lossy());
+            }
+            println!("    {}", suggestion);
+        }
+    }
+
+    let heaviest = report.heaviest_assets(HEAVIEST_ASSETS_COUNT);
+    if !heaviest.is_empty() {
+        println!("\n{}", style("Heaviest assets").bold());
+        for asset in heaviest {
+            let kind = match asset.kind {
+                AssetKind::Mesh => "mesh",
+                AssetKind::Texture => "texture",
+            };
+            let model = asset
+                .model
+                .and_then(|model| model.file_name())
+                .map(|name| format!(" ({})", name.to_string_lossy()))
+                .unwrap_or_default();
+            println!(
+                "  {:>10} {:<8} {}{}",
+                format_bytes(asset.file_bytes),
+                kind,
+                asset.path.to_string_lossy(),
+                style(model).dim()
+            );
+        }
+    }
+
+    if !report.warnings.is_empty() {
+        println!(
+            "\n{} warning(s){}:",
+            style(report.warnings.len()).bold().yellow(),
+            if options.deny_warnings {
+                " (treated as errors by --deny-warnings)"
+            } else {
+                ""
+            }
+        );
+        for warning in &report.warnings {
+            println!("  {}", warning);
+        }
+    }
+
+    if report.is_success() {
+        return;
+    }
+
+    println!(
+        "\n{} file(s) failed to process:",
+        style(report.failures.len()).bold().red()
+    );
+    for failure in &report.failures {
+        println!("  {}", failure);
+    }
+}
+
+/// Print the diff of every text file a dry run would rewrite, colored like `git diff`
+fn print_text_changes(report: &RunReport) {
+    println!(
+        "\n{} {} text file(s) would be rewritten, nothing was written",
+        style("Dry run").bold(),
+        report.text_changes.len()
+    );
+    for change in &report.text_changes {
+        println!();
+        for line in change.diff.lines() {
+            if line.starts_with("---") || line.starts_with("+++") {
+                println!("{}", style(line).bold());
+            } else if line.starts_with("@@") {
+                println!("{}", style(line).cyan());
+            } else if line.starts_with('-') {
+                println!("{}", style(line).red());
+            } else if line.starts_with('+') {
+                println!("{}", style(line).green());
+            } else {
+                println!("{}", line);
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Rebuild the report of a run from its event log
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::report::{ReportEvent, RunReport};
+
+/// Rebuild the report of a run from the events of its log. A log without its
+/// `finished` event belongs to a run that crashed or was killed, whose report is
+/// marked cancelled. A last line cut short by the crash is left out.
+pub fn replay_event_log(path: &Path) -> Result<RunReport, Error> {
+    let contents = fs::read_to_string(path)?;
+    let lines: Vec<&str> = contents.lines().collect();
+
+    let mut report = RunReport {
+        cancelled: true,
+        ..RunReport::default()
+    };
+    for (index, line) in lines.iter().enumerate() {
+        let event = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(_) if index + 1 == lines.len() && !contents.ends_with('\n') => break,
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Invalid event on line {} of {:?}: {}",
+                    index + 1,
+                    path,
+                    e
+                )))
+            }
+        };
+        match event {
+            ReportEvent::Started { .. } => {}
+            ReportEvent::Failure(failure) => report.failures.push(failure),
+            ReportEvent::Warning(warning) => report.warnings.push(warning),
+            ReportEvent::Timing(timing) => report.timings.push(timing),
+            ReportEvent::Texture(texture) => report.textures.push(texture),
+            ReportEvent::Mesh(mesh) => report.meshes.push(mesh),
+            ReportEvent::Duplicate(duplicate) => {
+                report.textures.retain(|t| t.path != duplicate.path);
+                report.duplicates.push(duplicate);
+            }
+            ReportEvent::ColorAdjustment(preview) => report.color_adjustments.push(preview),
+            ReportEvent::Finished {
+                scan,
+                budget_suggestions,
+                text_changes,
+                cancelled,
+                out_of_time,
+                remaining,
+            } => {
+                report.scan = scan;
+                report.budget_suggestions = budget_suggestions;
+                report.text_changes = text_changes;
+                report.cancelled = cancelled;
+                report.out_of_time = out_of_time;
+                report.remaining = remaining;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod replay_event_log_tests {
+    use super::*;
+
+    use std::{
+        io::Write,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::report::{
+        ColorAdjustmentPreview, DuplicateTexture, ErrorCode, EventLog, Failure, ScanStats,
+        TextureStats, Timing, Warning,
+    };
+
+    fn texture(path: &str) -> TextureStats {
+        TextureStats {
+            path: PathBuf::from(path),
+            model: Some(PathBuf::from("desk")),
+            width: 4,
+            height: 4,
+            channels: 3,
+            file_bytes: 40,
+            gpu_bytes: 64,
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn it_rebuilds_the_report_of_a_completed_run() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("report")
+            .join("test_run_replay_completed");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("events.ndjson");
+
+        let mut report = RunReport::default();
+        let mut log = EventLog::create(&path)?;
+        report.textures = vec![texture("desk/oak.png"), texture("desk/wood.png")];
+        report.failures.push(Failure::new(
+            ErrorCode::Decode,
+            "PNG Conversion",
+            PathBuf::from("desk/broken.tga"),
+            "truncated file",
+        ));
+        log.flush(&report)?;
+        report.warnings.push(Warning::new(
+            ErrorCode::MissingReference,
+            "Mesh Update",
+            PathBuf::from("desk/desk.dae"),
+            "missing.png doesn't exist",
+        ));
+        report.timings.push(Timing {
+            stage: "Mesh Update",
+            path: PathBuf::from("desk/desk.dae"),
+            started: SystemTime::now(),
+            duration: Duration::from_millis(12),
+        });
+        report.duplicates.push(DuplicateTexture {
+            path: PathBuf::from("desk/wood.png"),
+            canonical: PathBuf::from("desk/oak.png"),
+            file_bytes: 40,
+        });
+        report.textures.pop();
+        report.color_adjustments.push(ColorAdjustmentPreview {
+            path: PathBuf::from("desk/oak.png"),
+            adjustment: String::from("gamma 1.2"),
+            before: String::from("data:image/png;base64,"),
+            after: String::from("data:image/png;base64,"),
+        });
+        report.scan = Some(ScanStats {
+            directories: 2,
+            candidates: 3,
+            bytes: 120,
+            duration: Duration::from_millis(3),
+            up_to_date: 1,
+        });
+        log.finish(&report)?;
+
+        let replayed = replay_event_log(&path)?;
+        fs::remove_dir_all(dir)?;
+
+        assert_eq!(replayed, report);
+        assert_eq!(replayed.failures[0].stage, "PNG Conversion");
+        Ok(())
+    }
+
+    #[test]
+    fn it_rebuilds_the_report_of_a_crashed_run() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("report")
+            .join("test_run_replay_crashed");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("events.ndjson");
+
+        let mut report = RunReport::default();
+        let mut log = EventLog::create(&path)?;
+        report.textures.push(texture("desk/oak.png"));
+        log.flush(&report)?;
+        // The process died halfway through writing the next event
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(br#"{"event":"texture","path":"desk/wo"#)?;
+
+        let replayed = replay_event_log(&path)?;
+        fs::remove_dir_all(dir)?;
+
+        assert_eq!(replayed.textures, report.textures);
+        assert!(replayed.cancelled);
+        Ok(())
+    }
+}
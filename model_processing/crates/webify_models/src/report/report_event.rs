@@ -0,0 +1,126 @@
+//! Structure of the lines of an event log
+
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::report::{
+    BudgetSuggestion, ColorAdjustmentPreview, DuplicateTexture, ErrorCode, Failure, MeshStats,
+    ScanStats, TextChange, TextureStats, Timing, Warning,
+};
+
+/// Something that happened during a run, one per line of the event log. Replaying
+/// the events of a log in order gives back the report of the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ReportEvent {
+    /// The run started
+    Started {
+        version: String,
+        started: SystemTime,
+    },
+    /// A file failed to process
+    #[serde(deserialize_with = "deserialize_failure")]
+    Failure(Failure),
+    /// A file looked suspicious
+    #[serde(deserialize_with = "deserialize_warning")]
+    Warning(Warning),
+    /// A stage was done with a file, with `--timings`
+    #[serde(deserialize_with = "deserialize_timing")]
+    Timing(Timing),
+    /// A texture was processed
+    Texture(TextureStats),
+    /// A mesh was processed
+    Mesh(MeshStats),
+    /// A texture was removed as a copy of another, and leaves the processed ones
+    Duplicate(DuplicateTexture),
+    /// A texture's colors were corrected
+    ColorAdjustment(ColorAdjustmentPreview),
+    /// The run completed, with what is only known at the end
+    Finished {
+        scan: Option<ScanStats>,
+        budget_suggestions: Vec<BudgetSuggestion>,
+        text_changes: Vec<TextChange>,
+        cancelled: bool,
+        out_of_time: bool,
+        remaining: Vec<PathBuf>,
+    },
+}
+
+/// Failures, warnings and timings name their stage with a `&'static str`, which
+/// can't borrow from the log, so they're read through these owned copies
+#[derive(Deserialize)]
+struct Problem {
+    code: ErrorCode,
+    stage: String,
+    path: PathBuf,
+    message: String,
+    #[serde(default)]
+    author: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StageTiming {
+    stage: String,
+    path: PathBuf,
+    started: SystemTime,
+    duration: Duration,
+}
+
+/// Stage names read so far, each leaked once
+static STAGES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// The stage name as the `&'static str` the report holds. Stages are a handful of
+/// names, so each distinct one is leaked once and shared afterwards.
+fn intern_stage(stage: String) -> &'static str {
+    let mut stages = STAGES.lock().unwrap();
+    match stages.get(stage.as_str()) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(stage.into_boxed_str());
+            stages.insert(interned);
+            interned
+        }
+    }
+}
+
+fn deserialize_failure<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Failure, D::Error> {
+    let problem = Problem::deserialize(deserializer)?;
+    Ok(Failure {
+        author: problem.author,
+        ..Failure::new(
+            problem.code,
+            intern_stage(problem.stage),
+            problem.path,
+            problem.message,
+        )
+    })
+}
+
+fn deserialize_warning<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Warning, D::Error> {
+    let problem = Problem::deserialize(deserializer)?;
+    Ok(Warning {
+        author: problem.author,
+        ..Warning::new(
+            problem.code,
+            intern_stage(problem.stage),
+            problem.path,
+            problem.message,
+        )
+    })
+}
+
+fn deserialize_timing<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timing, D::Error> {
+    let timing = StageTiming::deserialize(deserializer)?;
+    Ok(Timing {
+        stage: intern_stage(timing.stage),
+        path: timing.path,
+        started: timing.started,
+        duration: timing.duration,
+    })
+}
@@ -0,0 +1,297 @@
+//! Everything worth reporting about a run
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::audit::AuditLog;
+use crate::dependencies::AssetKind;
+use crate::report::{
+    BudgetSuggestion, ColorAdjustmentPreview, DuplicateTexture, ErrorCode, EventLog, Failure,
+    HeavyAsset, MeshStats, ScanStats, TextChange, TextureStats, Timing, Warning,
+};
+
+/// Everything worth reporting about a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct RunReport {
+    /// Files that failed to process and were skipped
+    pub failures: Vec<Failure>,
+    /// Suspicious files that were still processed
+    pub warnings: Vec<Warning>,
+    /// How long each file took in each stage, only recorded with `--timings`
+    pub timings: Vec<Timing>,
+    /// Final size of every processed texture
+    pub textures: Vec<TextureStats>,
+    /// Final size of every processed mesh
+    pub meshes: Vec<MeshStats>,
+    /// Textures removed as copies of others, only recorded with `--dedup-textures`
+    pub duplicates: Vec<DuplicateTexture>,
+    /// Textures corrected by the `[color_adjustments]` of `webify.toml`
+    pub color_adjustments: Vec<ColorAdjustmentPreview>,
+    /// Textures to downscale to bring the models over `--gpu-budget-mb` under it
+    pub budget_suggestions: Vec<BudgetSuggestion>,
+    /// What the texture scan went through, when it ran
+    pub scan: Option<ScanStats>,
+    /// How the text files would be rewritten, only recorded with `--dry-run`
+    pub text_changes: Vec<TextChange>,
+    /// Whether the run was cancelled before every stage completed
+    pub cancelled: bool,
+    /// Whether the run was cancelled because `--time-budget` elapsed
+    pub out_of_time: bool,
+    /// Textures the run was cancelled before processing, left for the next run
+    pub remaining: Vec<PathBuf>,
+    /// Failures in a row since the last step that succeeded
+    #[serde(skip)]
+    pub consecutive_failures: usize,
+    /// Where the report is appended to as the run goes, with `--event-log`
+    #[serde(skip)]
+    pub event_log: Option<EventLog>,
+    /// Where the operations on files are recorded, with `--audit`
+    #[serde(skip)]
+    pub audit_log: Option<AuditLog>,
+}
+
+impl RunReport {
+    /// Append what was added to the report since the last flush to the event log,
+    /// when there is one
+    pub fn flush_events(&mut self) -> Result<(), std::io::Error> {
+        match self.event_log.take() {
+            Some(mut log) => {
+                let flushed = log.flush(self);
+                self.event_log = Some(log);
+                flushed
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Append the rest of the report to the event log, when there is one, marking
+    /// the run as completed
+    pub fn finish_events(&mut self) -> Result<(), std::io::Error> {
+        match self.event_log.take() {
+            Some(mut log) => {
+                let finished = log.finish(self);
+                self.event_log = Some(log);
+                finished
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the run completed without any failure
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The `count` slowest file/stage timings, slowest first
+    pub fn slowest(&self, count: usize) -> Vec<&Timing> {
+        let mut timings: Vec<&Timing> = self.timings.iter().collect();
+        timings.sort_by_key(|t| std::cmp::Reverse(t.duration));
+        timings.truncate(count);
+
+        timings
+    }
+
+    /// The `count` largest textures and meshes on disk, largest first
+    pub fn heaviest_assets(&self, count: usize) -> Vec<HeavyAsset<'_>> {
+        let textures = self.textures.iter().map(|texture| HeavyAsset {
+            kind: AssetKind::Texture,
+            path: &texture.path,
+            model: texture.model.as_deref(),
+            file_bytes: texture.file_bytes,
+        });
+        let meshes = self.meshes.iter().map(|mesh| HeavyAsset {
+            kind: AssetKind::Mesh,
+            path: &mesh.path,
+            model: mesh.model.as_deref(),
+            file_bytes: mesh.file_bytes,
+        });
+        let mut assets: Vec<HeavyAsset> = textures.chain(meshes).collect();
+        assets.sort_by_key(|asset| std::cmp::Reverse(asset.file_bytes));
+        assets.truncate(count);
+
+        assets
+    }
+
+    /// Estimated GPU memory of every model, summed over its textures
+    pub fn gpu_bytes_by_model(&self) -> BTreeMap<PathBuf, u64> {
+        let mut models = BTreeMap::new();
+        for texture in &self.textures {
+            if let Some(model) = &texture.model {
+                *models.entry(model.clone()).or_insert(0) += texture.gpu_bytes;
+            }
+        }
+
+        models
+    }
+
+    /// Whether the run should exit successfully, counting warnings as errors
+    /// when `deny_warnings` is set
+    pub fn passes(&self, deny_warnings: bool) -> bool {
+        self.is_success() && (!deny_warnings || self.warnings.is_empty())
+    }
+
+    /// Exit status of the run: 0 when it passes, the status of the failure class
+    /// when every problem belongs to the same one (see `ErrorCode::exit_status`),
+    /// and 10 when they're mixed
+    pub fn exit_status(&self, deny_warnings: bool) -> i32 {
+        if self.passes(deny_warnings) {
+            return 0;
+        }
+
+        let mut codes: Vec<ErrorCode> = self.failures.iter().map(|f| f.code).collect();
+        if deny_warnings {
+            codes.extend(self.warnings.iter().map(|w| w.code));
+        }
+        let mut statuses: Vec<i32> = codes.iter().map(|c| c.exit_status()).collect();
+        statuses.sort_unstable();
+        statuses.dedup();
+        match statuses.as_slice() {
+            [status] => *status,
+            _ => 10,
+        }
+    }
+}
+
+#[cfg(test)]
+mod run_report_tests {
+    use super::*;
+
+    use std::time::{Duration, SystemTime};
+
+    use crate::mesh_update::MeshComplexity;
+
+    #[test]
+    fn it_only_fails_on_warnings_when_denied() {
+        let report = RunReport {
+            failures: Vec::new(),
+            warnings: vec![Warning::new(
+                ErrorCode::MissingReference,
+                "Mesh Update",
+                PathBuf::from("foo.dae"),
+                "hmm",
+            )],
+            ..Default::default()
+        };
+
+        assert!(report.passes(false));
+        assert!(!report.passes(true));
+    }
+
+    #[test]
+    fn it_always_fails_on_failures() {
+        let report = RunReport {
+            failures: vec![Failure::new(
+                ErrorCode::MeshUpdate,
+                "Mesh Update",
+                PathBuf::from("foo.dae"),
+                "no",
+            )],
+            ..Default::default()
+        };
+
+        assert!(!report.passes(false));
+    }
+
+    #[test]
+    fn it_maps_the_failure_classes_to_an_exit_status() {
+        let failure = |code: ErrorCode| Failure::new(code, "Mesh Update", PathBuf::new(), "no");
+        let mut report = RunReport::default();
+        assert_eq!(report.exit_status(false), 0);
+
+        report.failures = vec![failure(ErrorCode::Decode), failure(ErrorCode::Encode)];
+        assert_eq!(report.exit_status(false), 11);
+
+        report.failures.push(failure(ErrorCode::MeshUpdate));
+        assert_eq!(report.exit_status(false), 10);
+    }
+
+    #[test]
+    fn it_sorts_the_slowest_timings_first() {
+        let timing = |path: &str, millis: u64| Timing {
+            stage: "PNG Conversion",
+            path: PathBuf::from(path),
+            started: SystemTime::now(),
+            duration: Duration::from_millis(millis),
+        };
+        let report = RunReport {
+            timings: vec![timing("a.jpg", 5), timing("b.jpg", 50), timing("c.jpg", 20)],
+            ..Default::default()
+        };
+
+        let slowest: Vec<&str> = report
+            .slowest(2)
+            .iter()
+            .map(|t| t.path.to_str().unwrap())
+            .collect();
+        assert_eq!(slowest, vec!["b.jpg", "c.jpg"]);
+    }
+
+    #[test]
+    fn it_sums_gpu_memory_per_model() {
+        let texture = |path: &str, model: Option<&str>, gpu_bytes: u64| TextureStats {
+            path: PathBuf::from(path),
+            model: model.map(PathBuf::from),
+            width: 1,
+            height: 1,
+            channels: 4,
+            file_bytes: 1,
+            gpu_bytes,
+            encoding: None,
+        };
+        let report = RunReport {
+            textures: vec![
+                texture("table/a.png", Some("table"), 100),
+                texture("table/b.png", Some("table"), 50),
+                texture("lamp/c.png", Some("lamp"), 10),
+                texture("stray.png", None, 1000),
+            ],
+            ..Default::default()
+        };
+
+        let models = report.gpu_bytes_by_model();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[&PathBuf::from("table")], 150);
+        assert_eq!(models[&PathBuf::from("lamp")], 10);
+    }
+
+    #[test]
+    fn it_ranks_textures_and_meshes_by_size() {
+        let texture = |path: &str, file_bytes: u64| TextureStats {
+            path: PathBuf::from(path),
+            model: Some(PathBuf::from("table")),
+            width: 1,
+            height: 1,
+            channels: 4,
+            file_bytes,
+            gpu_bytes: 1,
+            encoding: None,
+        };
+        let mesh = |path: &str, file_bytes: u64| MeshStats {
+            path: PathBuf::from(path),
+            model: None,
+            file_bytes,
+            complexity: MeshComplexity::default(),
+        };
+        let report = RunReport {
+            textures: vec![texture("table/a.png", 30), texture("table/b.png", 5)],
+            meshes: vec![mesh("table/a.dae", 50), mesh("table/b.dae", 10)],
+            ..Default::default()
+        };
+
+        let heaviest: Vec<(AssetKind, &str)> = report
+            .heaviest_assets(3)
+            .iter()
+            .map(|asset| (asset.kind, asset.path.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            heaviest,
+            vec![
+                (AssetKind::Mesh, "table/a.dae"),
+                (AssetKind::Texture, "table/a.png"),
+                (AssetKind::Mesh, "table/b.dae"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Run one step of a stage on a file, with timing and the error policy applied
+
+use std::{
+    io::{Error, ErrorKind},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+use crate::options::RunOptions;
+use crate::report::{handle_failure, time_file, ErrorCode, Failure, RunReport};
+
+/// Run one step of a stage on a file, timing it when `--timings` is on. A failure
+/// goes through the error policy: `Ok(None)` means it was recorded and the file
+/// should be skipped, `Err` means the run should abort. A panic in the work, say a
+/// parser tripping on a malformed file, is a failure like any other. Failures get
+/// the step's `code`, unless the file system or a panic is to blame. The run halts
+/// after `--max-consecutive-failures` failures in a row. What the step added to the
+/// report is then appended to the event log, with `--event-log`.
+pub fn run_step<T>(
+    stage: &'static str,
+    code: ErrorCode,
+    path: &Path,
+    options: &RunOptions,
+    report: &mut RunReport,
+    work: impl FnOnce() -> std::result::Result<T, Error>,
+) -> std::result::Result<Option<T>, Error> {
+    let mut code = code;
+    let work = || {
+        catch_unwind(AssertUnwindSafe(work)).unwrap_or_else(|panic| {
+            code = ErrorCode::Internal;
+            Err(panicked(panic))
+        })
+    };
+    match time_file(options.timings, report, stage, path, work) {
+        Ok(result) => {
+            report.consecutive_failures = 0;
+            report.flush_events()?;
+            Ok(Some(result))
+        }
+        Err(e) => {
+            let code = match e.kind() {
+                ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::AlreadyExists => {
+                    ErrorCode::FileSystem
+                }
+                _ => code,
+            };
+            let message = e.to_string();
+            let failure = Failure::new(code, stage, path.to_path_buf(), e);
+            handle_failure(options.error_policy, report, failure)?;
+            report.flush_events()?;
+
+            report.consecutive_failures += 1;
+            match options.max_consecutive_failures {
+                Some(max) if report.consecutive_failures >= max => Err(Error::other(Failure::new(
+                    code,
+                    "Circuit Breaker",
+                    path.to_path_buf(),
+                    format!(
+                        "halted the run after {} consecutive failures, the last one in {}: {}. \
+                             When every file fails, the run itself is likely broken (a bad mount, \
+                             a codec missing from the build) rather than the files.",
+                        report.consecutive_failures, stage, message
+                    ),
+                ))),
+                _ => Ok(None),
+            }
+        }
+    }
+}
+
+fn panicked(panic: Box<dyn std::any::Any + Send>) -> Error {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown error"));
+
+    Error::other(format!("Panicked, {}", message))
+}
+
+#[cfg(test)]
+mod run_step_tests {
+    use super::*;
+
+    use crate::options::ErrorPolicy;
+
+    #[test]
+    fn it_returns_the_result_of_the_work() {
+        let mut report = RunReport::default();
+        let result = run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            Path::new("foo.dae"),
+            &RunOptions::default(),
+            &mut report,
+            || Ok(42),
+        );
+
+        assert_eq!(result.unwrap(), Some(42));
+    }
+
+    #[test]
+    fn it_skips_the_file_when_the_failure_is_collected() {
+        let mut report = RunReport::default();
+        let options = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            ..Default::default()
+        };
+        let result: Result<Option<()>, Error> = run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            Path::new("foo.dae"),
+            &options,
+            &mut report,
+            || Err(Error::other("broken")),
+        );
+
+        assert_eq!(result.unwrap(), None);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].code, ErrorCode::MeshUpdate);
+    }
+
+    #[test]
+    fn it_turns_a_panic_into_a_failure() {
+        let mut report = RunReport::default();
+        let options = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            ..Default::default()
+        };
+        let result: Result<Option<()>, Error> = run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            Path::new("foo.dae"),
+            &options,
+            &mut report,
+            || panic!("index out of bounds"),
+        );
+
+        assert_eq!(result.unwrap(), None);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].code, ErrorCode::Internal);
+    }
+
+    #[test]
+    fn it_blames_the_file_system_for_its_errors() {
+        let mut report = RunReport::default();
+        let options = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            ..Default::default()
+        };
+        let _: Result<Option<()>, Error> = run_step(
+            "Mesh Update",
+            ErrorCode::MeshUpdate,
+            Path::new("foo.dae"),
+            &options,
+            &mut report,
+            || Err(Error::from(ErrorKind::PermissionDenied)),
+        );
+
+        assert_eq!(report.failures[0].code, ErrorCode::FileSystem);
+    }
+
+    #[test]
+    fn it_halts_after_too_many_consecutive_failures() {
+        let mut report = RunReport::default();
+        let options = RunOptions {
+            error_policy: ErrorPolicy::Permissive,
+            max_consecutive_failures: Some(2),
+            ..Default::default()
+        };
+        let mut step = |fails: bool| -> Result<Option<()>, Error> {
+            run_step(
+                "Mesh Update",
+                ErrorCode::MeshUpdate,
+                Path::new("foo.dae"),
+                &options,
+                &mut report,
+                || match fails {
+                    true => Err(Error::other("broken")),
+                    false => Ok(()),
+                },
+            )
+        };
+
+        assert!(step(true).is_ok());
+        assert!(step(false).is_ok());
+        assert!(step(true).is_ok());
+        let halt = step(true).unwrap_err();
+        let failure = halt.get_ref().unwrap().downcast_ref::<Failure>().unwrap();
+        assert_eq!(failure.stage, "Circuit Breaker");
+        assert!(failure.message.contains("after 2 consecutive failures"));
+    }
+}
@@ -0,0 +1,21 @@
+//! Structure that records what the texture scan went through
+
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What the texture scan went through before any file was processed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScanStats {
+    /// Directories read
+    pub directories: u64,
+    /// Textures found
+    pub candidates: u64,
+    /// Total size of the textures found, in bytes
+    pub bytes: u64,
+    pub duration: Duration,
+    /// Textures a previous run webified with the same settings, only measured
+    #[serde(default)]
+    pub up_to_date: u64,
+}
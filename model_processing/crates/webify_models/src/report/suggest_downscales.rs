@@ -0,0 +1,109 @@
+//! Suggest which textures to downscale to bring a model under its GPU budget
+
+use std::path::Path;
+
+use crate::image_processing::{estimate_gpu_memory, UNCOMPRESSED_BYTES_PER_PIXEL};
+use crate::report::{BudgetSuggestion, TextureStats};
+
+/// Smallest longest side suggested, below that textures look blurry up close
+const MIN_SUGGESTED_SIZE: u32 = 256;
+
+/// Suggest textures of the model to downscale so it saves at least `over_by`
+/// bytes of GPU memory. The texture whose next halving saves the most is halved
+/// first, so a few large textures are suggested rather than many small ones.
+/// Returns the suggestions that save the most first, which may not be enough
+/// when every texture reaches the smallest suggested size.
+pub fn suggest_downscales(
+    model: &Path,
+    textures: &[&TextureStats],
+    over_by: u64,
+) -> Vec<BudgetSuggestion> {
+    let gpu_bytes = |(width, height): (u32, u32)| {
+        estimate_gpu_memory(width, height, UNCOMPRESSED_BYTES_PER_PIXEL)
+    };
+    let mut sizes: Vec<(u32, u32)> = textures.iter().map(|t| (t.width, t.height)).collect();
+
+    let mut saved = 0;
+    while saved < over_by {
+        let largest = sizes
+            .iter()
+            .enumerate()
+            .filter(|(_, (width, height))| (*width).max(*height) / 2 >= MIN_SUGGESTED_SIZE)
+            .max_by_key(|(_, size)| gpu_bytes(**size))
+            .map(|(index, _)| index);
+        let index = match largest {
+            Some(index) => index,
+            None => break,
+        };
+
+        let (width, height) = sizes[index];
+        let halved = ((width / 2).max(1), (height / 2).max(1));
+        saved += gpu_bytes((width, height)) - gpu_bytes(halved);
+        sizes[index] = halved;
+    }
+
+    let mut suggestions: Vec<BudgetSuggestion> = textures
+        .iter()
+        .zip(sizes)
+        .filter(|(texture, to)| (texture.width, texture.height) != *to)
+        .map(|(texture, to)| BudgetSuggestion {
+            model: model.to_path_buf(),
+            texture: texture.path.clone(),
+            from: (texture.width, texture.height),
+            to,
+            saved_bytes: texture.gpu_bytes - gpu_bytes(to),
+        })
+        .collect();
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.saved_bytes));
+
+    suggestions
+}
+
+#[cfg(test)]
+mod suggest_downscales_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn texture(path: &str, size: u32) -> TextureStats {
+        TextureStats {
+            path: PathBuf::from(path),
+            model: Some(PathBuf::from("car")),
+            width: size,
+            height: size,
+            channels: 4,
+            file_bytes: 1,
+            gpu_bytes: estimate_gpu_memory(size, size, UNCOMPRESSED_BYTES_PER_PIXEL),
+            encoding: None,
+        }
+    }
+
+    #[test]
+    fn it_halves_the_largest_textures_first() {
+        let body = texture("car/body.png", 4096);
+        let wheel = texture("car/wheel.png", 1024);
+        let textures = vec![&body, &wheel];
+
+        // Halving the body once saves three quarters of it, enough on its own
+        let suggestions = suggest_downscales(Path::new("car"), &textures, body.gpu_bytes / 2);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].texture, PathBuf::from("car/body.png"));
+        assert_eq!(suggestions[0].to, (2048, 2048));
+        assert_eq!(
+            suggestions[0].to_string(),
+            "downscale body.png from 4096x4096 to 2048x2048 saves ~64.0 MiB"
+        );
+
+        // Down to the wheel's size, then both of them
+        let suggestions = suggest_downscales(Path::new("car"), &textures, body.gpu_bytes);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].to, (512, 512));
+        assert_eq!(suggestions[1].to, (512, 512));
+    }
+
+    #[test]
+    fn it_stops_at_the_smallest_suggested_size() {
+        let small = texture("car/small.png", 256);
+        assert!(suggest_downscales(Path::new("car"), &[&small], u64::MAX).is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+//! Structure that records how a dry run would rewrite a text file
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A text file the run would rewrite, with the edit as a unified diff
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TextChange {
+    /// Path of the file, relative to the processed directory
+    pub path: PathBuf,
+    /// Unified diff from the current contents to the rewritten ones
+    pub diff: String,
+}
@@ -0,0 +1,27 @@
+//! Structure that records the final size of a processed texture
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::provenance::Encoding;
+
+/// Final size of a processed texture, on disk and once uploaded to the GPU
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TextureStats {
+    /// Path of the processed texture
+    pub path: PathBuf,
+    /// Root directory of the model the texture belongs to
+    pub model: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    /// Number of color channels, e.g. 3 for RGB and 4 for RGBA
+    pub channels: u8,
+    /// Size of the file on disk, in bytes
+    pub file_bytes: u64,
+    /// Estimated decompressed size on the GPU, mip chain included, in bytes
+    pub gpu_bytes: u64,
+    /// How the file was encoded, or `None` when it was left as it came
+    pub encoding: Option<Encoding>,
+}
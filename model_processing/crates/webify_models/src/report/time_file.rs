@@ -0,0 +1,69 @@
+//! Time how long a stage takes on a single file
+
+use std::{
+    path::Path,
+    time::{Instant, SystemTime},
+};
+
+use crate::report::{RunReport, Timing};
+
+/// Run `work` for a file and, when `enabled`, record how long it took in the report
+pub fn time_file<T>(
+    enabled: bool,
+    report: &mut RunReport,
+    stage: &'static str,
+    path: &Path,
+    work: impl FnOnce() -> T,
+) -> T {
+    if !enabled {
+        return work();
+    }
+
+    let started = SystemTime::now();
+    let clock = Instant::now();
+    let result = work();
+    report.timings.push(Timing {
+        stage,
+        path: path.to_path_buf(),
+        started,
+        duration: clock.elapsed(),
+    });
+
+    result
+}
+
+#[cfg(test)]
+mod time_file_tests {
+    use super::*;
+
+    #[test]
+    fn it_records_a_timing_when_enabled() {
+        let mut report = RunReport::default();
+        let result = time_file(
+            true,
+            &mut report,
+            "Mesh Update",
+            Path::new("foo.dae"),
+            || 42,
+        );
+
+        assert_eq!(result, 42);
+        assert_eq!(report.timings.len(), 1);
+        assert_eq!(report.timings[0].stage, "Mesh Update");
+        assert_eq!(report.timings[0].path, Path::new("foo.dae"));
+    }
+
+    #[test]
+    fn it_records_nothing_when_disabled() {
+        let mut report = RunReport::default();
+        time_file(
+            false,
+            &mut report,
+            "Mesh Update",
+            Path::new("foo.dae"),
+            || (),
+        );
+
+        assert!(report.timings.is_empty());
+    }
+}
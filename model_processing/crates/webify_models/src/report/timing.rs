@@ -0,0 +1,51 @@
+//! Structure that records how long a file took in a stage
+
+use std::{
+    fmt,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// How long a single file took in a single stage
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Timing {
+    /// Name of the stage that processed the file
+    pub stage: &'static str,
+    /// File that was processed
+    pub path: PathBuf,
+    /// When the stage started working on the file
+    pub started: SystemTime,
+    /// How long the stage took on the file
+    pub duration: Duration,
+}
+
+impl Timing {
+    /// When the stage finished working on the file
+    pub fn finished(&self) -> SystemTime {
+        self.started + self.duration
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {:.3} -> {:.3} ({:?})",
+            self.stage,
+            self.path.to_string_lossy(),
+            seconds_since_epoch(self.started),
+            seconds_since_epoch(self.finished()),
+            self.duration
+        )
+    }
+}
+
+/// Timestamps are printed as fractional seconds since the Unix epoch
+fn seconds_since_epoch(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
@@ -0,0 +1,57 @@
+//! Structure that represents something suspicious noticed during processing
+
+use std::{fmt, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::report::ErrorCode;
+
+/// Something suspicious about a file that didn't stop it from being processed
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Warning {
+    /// Class of the problem, stable across versions
+    pub code: ErrorCode,
+    /// Name of the stage that noticed the problem
+    pub stage: &'static str,
+    /// File the warning is about
+    pub path: PathBuf,
+    /// What looks wrong
+    pub message: String,
+    /// Last committer of the file, with `--git-authors` when it's tracked by git
+    pub author: Option<String>,
+}
+
+impl Warning {
+    pub fn new(
+        code: ErrorCode,
+        stage: &'static str,
+        path: PathBuf,
+        message: impl fmt::Display,
+    ) -> Warning {
+        Warning {
+            code,
+            stage,
+            path,
+            message: message.to_string(),
+            author: None,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            self.code,
+            self.stage,
+            self.path.to_string_lossy(),
+            self.message
+        )?;
+        match &self.author {
+            Some(author) => write!(f, " (last changed by {})", author),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Write the run report as an HTML page, for people to read in a browser
+
+use std::{fmt::Write, fs, io::Error, path::Path, result::Result};
+
+use crate::report::RunReport;
+
+/// Write the run report to `path` as a single HTML page: the failures and warnings,
+/// and the textures whose colors were adjusted with their thumbnails before and
+/// after side by side. The thumbnails are embedded, so the page can be moved or
+/// attached to a review on its own.
+pub fn write_html_report(path: &Path, report: &RunReport) -> Result<(), Error> {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>webify_models report</title>\n\
+         <style>\nbody { font-family: sans-serif; margin: 2em; }\n\
+         table { border-collapse: collapse; }\n\
+         td, th { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }\n\
+         img { image-rendering: pixelated; }\n</style>\n</head>\n<body>\n<h1>webify_models report</h1>\n",
+    );
+    writeln!(
+        html,
+        "<p>{} texture(s), {} mesh(es), {} failure(s), {} warning(s){}</p>",
+        report.textures.len(),
+        report.meshes.len(),
+        report.failures.len(),
+        report.warnings.len(),
+        if report.cancelled { ", cancelled" } else { "" }
+    )
+    .map_err(Error::other)?;
+
+    let failures = report
+        .failures
+        .iter()
+        .map(|f| (f.code, f.stage, &f.path, &f.message));
+    let warnings = report
+        .warnings
+        .iter()
+        .map(|w| (w.code, w.stage, &w.path, &w.message));
+    for (title, problems) in [
+        ("Failures", failures.collect::<Vec<_>>()),
+        ("Warnings", warnings.collect()),
+    ] {
+        if problems.is_empty() {
+            continue;
+        }
+        writeln!(
+            html,
+            "<h2>{}</h2>\n<table>\n<tr><th>Code</th><th>Stage</th><th>Path</th><th>Message</th></tr>",
+            title
+        )
+        .map_err(Error::other)?;
+        for (code, stage, path, message) in problems {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                code,
+                escape(stage),
+                escape(&path.to_string_lossy()),
+                escape(message)
+            )
+            .map_err(Error::other)?;
+        }
+        html.push_str("</table>\n");
+    }
+
+    if !report.color_adjustments.is_empty() {
+        html.push_str(
+            "<h2>Color adjustments</h2>\n<table>\n<tr><th>Texture</th><th>Before</th><th>After</th></tr>\n",
+        );
+        for preview in &report.color_adjustments {
+            writeln!(
+                html,
+                "<tr><td>{}<br>{}</td><td><img src=\"{}\" alt=\"before\"></td><td><img src=\"{}\" alt=\"after\"></td></tr>",
+                escape(&preview.path.to_string_lossy()),
+                escape(&preview.adjustment),
+                escape(&preview.before),
+                escape(&preview.after)
+            )
+            .map_err(Error::other)?;
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    fs::write(path, html)
+}
+
+/// The text with the characters HTML gives a meaning to escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod write_html_report_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::report::{ColorAdjustmentPreview, ErrorCode, Warning};
+
+    #[test]
+    fn it_writes_the_problems_and_the_color_adjustments() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("report")
+            .join("test_run_write_html_report");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("report.html");
+        let report = RunReport {
+            warnings: vec![Warning::new(
+                ErrorCode::MissingReference,
+                "Mesh Update",
+                PathBuf::from("desk/desk.dae"),
+                "<missing>.png doesn't exist",
+            )],
+            color_adjustments: vec![ColorAdjustmentPreview {
+                path: PathBuf::from("desk/scan.png"),
+                adjustment: String::from("gamma 1.2"),
+                before: String::from("data:image/png;base64,AAAA"),
+                after: String::from("data:image/png;base64,BBBB"),
+            }],
+            ..RunReport::default()
+        };
+
+        write_html_report(&path, &report)?;
+        let html = fs::read_to_string(&path)?;
+        fs::remove_dir_all(dir)?;
+
+        assert!(html.contains("<td>E0203</td><td>Mesh Update</td>"));
+        assert!(html.contains("&lt;missing&gt;.png doesn't exist"));
+        assert!(!html.contains("<h2>Failures</h2>"));
+        assert!(html.contains("desk/scan.png<br>gamma 1.2"));
+        assert!(html.contains("<img src=\"data:image/png;base64,AAAA\" alt=\"before\">"));
+        assert!(html.contains("<img src=\"data:image/png;base64,BBBB\" alt=\"after\">"));
+        Ok(())
+    }
+}
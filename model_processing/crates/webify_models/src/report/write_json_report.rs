@@ -0,0 +1,51 @@
+//! Write the run report as JSON, for tooling that consumes it
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::report::RunReport;
+
+/// Write the run report to `path` as pretty-printed JSON, following the `report`
+/// schema of `webify_models schema`
+pub fn write_json_report(path: &Path, report: &RunReport) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(report).map_err(Error::other)?;
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod write_json_report_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::report::{ErrorCode, Failure};
+
+    #[test]
+    fn it_writes_the_report() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("report")
+            .join("test_run_write_json_report");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("report.json");
+        let report = RunReport {
+            failures: vec![Failure::new(
+                ErrorCode::Decode,
+                "Convert",
+                PathBuf::from("wood.tga"),
+                "truncated file",
+            )],
+            ..RunReport::default()
+        };
+
+        write_json_report(&path, &report)?;
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_dir_all(dir)?;
+
+        assert_eq!(json["failures"][0]["code"], "E0101");
+        assert_eq!(json["failures"][0]["stage"], "Convert");
+        assert_eq!(json["failures"][0]["message"], "truncated file");
+        assert_eq!(json["cancelled"], false);
+        Ok(())
+    }
+}
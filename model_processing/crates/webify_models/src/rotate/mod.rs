@@ -0,0 +1,12 @@
+//! Rotate a texture by quarter turns for hand fixes, along with its mip chain and
+//! its entries in the manifest
+
+mod process;
+mod rotate_manifest_entries;
+mod rotate_texture;
+mod rotation;
+
+pub use self::process::process;
+pub use self::rotate_manifest_entries::rotate_manifest_entries;
+pub use self::rotate_texture::rotate_texture;
+pub use self::rotation::Rotation;
@@ -0,0 +1,110 @@
+//! Rotate a texture an artist noted is the wrong way round
+
+use std::{io::Error, path::Path, result::Result};
+
+use console::style;
+
+use crate::hashing::hash_file;
+use crate::image_processing::find_mip_levels;
+use crate::rotate::{rotate_manifest_entries, rotate_texture, Rotation};
+
+/// Rotate the texture in place, then the levels of its mip chain, and update its
+/// entries in the manifest of the processed directory it's in. Meshes are left
+/// alone: their UVs keep sampling the same corners of the texture, which is what
+/// makes the rotation show. Returns whether every file kept its pixels exactly.
+pub fn process(texture: &Path, rotation: Rotation) -> Result<bool, Error> {
+    if !texture.is_file() {
+        return Err(Error::other(format!("{:?} isn't a file.", texture)));
+    }
+    let previous_hash = hash_file(texture)?;
+
+    let mut lossless = rotate_texture(texture, rotation)?;
+    let levels = find_mip_levels(texture);
+    for level in &levels {
+        lossless &= rotate_texture(level, rotation)?;
+    }
+    let entries = rotate_manifest_entries(texture, &previous_hash, rotation)?;
+
+    println!(
+        "\n{} {} by {}° clockwise{}",
+        style("Rotated").bold(),
+        texture.to_string_lossy(),
+        rotation.degrees(),
+        match levels.len() {
+            0 => String::new(),
+            count => format!(", with its {} mip levels", count),
+        }
+    );
+    if entries > 0 {
+        println!("  Updated {} entries of the manifest", entries);
+    }
+    if !lossless {
+        println!(
+            "  {}",
+            style("Encoded again in a lossy format, losing a little quality.").yellow()
+        );
+    }
+
+    Ok(lossless)
+}
+
+#[cfg(test)]
+mod process_tests {
+    use super::*;
+
+    use std::fs;
+
+    use image::{GrayImage, Luma};
+
+    use crate::manifest::{write_manifest, ModelEntry, ModelManifest, TextureEntry};
+
+    #[test]
+    fn it_rotates_the_texture_its_mips_and_its_manifest_entry() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("rotate")
+            .join("test_run_rotate_process");
+        let textures = dir.join("desk").join("materials").join("textures");
+        fs::create_dir_all(&textures)?;
+        let texture = textures.join("label.png");
+        GrayImage::from_fn(8, 4, |x, _| Luma([x as u8]))
+            .save(&texture)
+            .map_err(Error::other)?;
+        GrayImage::from_fn(4, 2, |x, _| Luma([x as u8]))
+            .save(textures.join("label_mip1.png"))
+            .map_err(Error::other)?;
+        let entry = TextureEntry {
+            path: String::from("materials/textures/label.png"),
+            width: 8,
+            height: 4,
+            hash: hash_file(&texture)?,
+            ..TextureEntry::default()
+        };
+        write_manifest(
+            &dir,
+            &ModelManifest {
+                models: vec![ModelEntry {
+                    textures: vec![entry],
+                    ..ModelEntry::default()
+                }],
+                ..ModelManifest::default()
+            },
+        )?;
+
+        assert!(process(&texture, Rotation::ThreeQuarters)?);
+        let rotated = image::open(&texture).map_err(Error::other)?.to_luma8();
+        assert_eq!(rotated.dimensions(), (4, 8));
+        // The top right corner is now the top left one
+        assert_eq!(rotated.get_pixel(0, 0).0, [7]);
+        let mip = image::open(textures.join("label_mip1.png")).map_err(Error::other)?;
+        assert_eq!(mip.to_luma8().dimensions(), (2, 4));
+        let manifest: ModelManifest =
+            serde_json::from_str(&fs::read_to_string(dir.join("models.json"))?)?;
+        let entry = &manifest.models[0].textures[0];
+        assert_eq!((entry.width, entry.height), (4, 8));
+        assert_eq!(entry.hash, hash_file(&texture)?);
+        assert_eq!(entry.file_bytes, fs::metadata(&texture)?.len());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
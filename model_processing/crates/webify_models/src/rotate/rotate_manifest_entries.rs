@@ -0,0 +1,53 @@
+//! Point the manifest at a rotated texture
+
+use std::{env, fs, io::Error, path::Path, result::Result};
+
+use crate::hashing::hash_file;
+use crate::manifest::{write_manifest, ModelManifest, MANIFEST_FILE_NAME};
+use crate::rotate::Rotation;
+
+/// Update the entries of the texture in the manifest of the processed directory
+/// it's in, the closest `models.json` up from it, found by `previous_hash`, the
+/// hash the texture had before it was rotated: their width and height swap with
+/// the rotation, and their size and hash become the rotated file's. Returns the
+/// number of entries updated, none without a manifest.
+pub fn rotate_manifest_entries(
+    texture: &Path,
+    previous_hash: &str,
+    rotation: Rotation,
+) -> Result<usize, Error> {
+    let texture = env::current_dir()?.join(texture);
+    let root = match texture
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(MANIFEST_FILE_NAME).is_file())
+    {
+        Some(root) => root,
+        None => return Ok(0),
+    };
+    let mut manifest: ModelManifest =
+        serde_json::from_str(&fs::read_to_string(root.join(MANIFEST_FILE_NAME))?)?;
+
+    let hash = hash_file(&texture)?;
+    let file_bytes = fs::metadata(&texture)?.len();
+    let mut updated = 0;
+    let entries = manifest.shared_textures.iter_mut().chain(
+        manifest
+            .models
+            .iter_mut()
+            .flat_map(|model| model.textures.iter_mut()),
+    );
+    for entry in entries.filter(|entry| entry.hash == previous_hash) {
+        if rotation.swaps_dimensions() {
+            std::mem::swap(&mut entry.width, &mut entry.height);
+        }
+        entry.hash = hash.clone();
+        entry.file_bytes = file_bytes;
+        updated += 1;
+    }
+    if updated > 0 {
+        write_manifest(root, &manifest)?;
+    }
+
+    Ok(updated)
+}
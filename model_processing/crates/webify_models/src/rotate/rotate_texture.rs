@@ -0,0 +1,115 @@
+//! Rotate a texture file in place, in its format
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Error},
+    path::Path,
+    result::Result,
+};
+
+use image::{GenericImageView, ImageOutputFormat};
+use webp::Encoder;
+
+use crate::image_processing::{decode_texture, decode_webp, tag_colorspace};
+use crate::options::{Colorspace, HdrSettings};
+use crate::provenance::read_marker;
+use crate::rotate::Rotation;
+
+/// Quality JPEGs are encoded again with
+const JPEG_QUALITY: u8 = 90;
+
+/// Rotate the texture in place, in the format it's in. PNG, BMP, TGA and TIFF
+/// files keep their pixels exactly, as do WebPs that were written lossless or
+/// that have no sidecar to tell; JPEGs and lossy WebPs are encoded again, at the
+/// quality of their sidecar for WebPs, losing a little more. PNGs with a sidecar
+/// get their colorspace tag back. Returns whether the pixels were kept exactly.
+/// Formats the converter only reads or moves, like AVIF and KTX2, can't be
+/// rotated: their source has to be, then processed again.
+pub fn rotate_texture(path: &Path, rotation: Rotation) -> Result<bool, Error> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let encoding = read_marker(path)?.and_then(|marker| marker.encoding);
+    let save_error = |e| Error::other(format!("Could not write {:?}: {:?}", path, e));
+
+    match extension.as_str() {
+        "png" | "bmp" | "tga" | "tif" | "tiff" => {
+            let image = rotation.apply(&decode_texture(path, &HdrSettings::default())?);
+            image.save(path).map_err(save_error)?;
+            if let (Some(encoding), "png") = (&encoding, extension.as_str()) {
+                let colorspace = match encoding.color_space.starts_with("linear") {
+                    true => Colorspace::Linear,
+                    false => Colorspace::Srgb,
+                };
+                tag_colorspace(path, colorspace)?;
+            }
+            Ok(true)
+        }
+        "jpg" | "jpeg" => {
+            let image = rotation.apply(&decode_texture(path, &HdrSettings::default())?);
+            image
+                .write_to(
+                    &mut BufWriter::new(File::create(path)?),
+                    ImageOutputFormat::Jpeg(JPEG_QUALITY),
+                )
+                .map_err(save_error)?;
+            Ok(false)
+        }
+        "webp" => {
+            let image = rotation.apply(&decode_webp(path)?);
+            let quality = encoding.and_then(|encoding| encoding.quality);
+            let (width, height) = image.dimensions();
+            let pixels = match image.color().has_alpha() {
+                true => image.to_rgba8().into_raw(),
+                false => image.to_rgb8().into_raw(),
+            };
+            let encoder = match image.color().has_alpha() {
+                true => Encoder::from_rgba(&pixels, width, height),
+                false => Encoder::from_rgb(&pixels, width, height),
+            };
+            let webp = encoder
+                .encode_simple(quality.is_none(), f32::from(quality.unwrap_or(100)))
+                .map_err(|e| Error::other(format!("Could not encode {:?}: {:?}", path, e)))?;
+            fs::write(path, &*webp)?;
+            Ok(quality.is_none())
+        }
+        _ => Err(Error::other(format!(
+            "Can't rotate {:?}, {} files aren't written by the converter: rotate its source and process it again.",
+            path, extension
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod rotate_texture_tests {
+    use super::*;
+
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn it_rotates_pngs_losslessly() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("rotate")
+            .join("test_run_rotate_texture");
+        fs::create_dir_all(&dir)?;
+        let png = dir.join("sign.png");
+        RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 50, y as u8 * 50, 7]))
+            .save(&png)
+            .map_err(Error::other)?;
+
+        assert!(rotate_texture(&png, Rotation::Quarter)?);
+        let rotated = image::open(&png).map_err(Error::other)?.to_rgb8();
+        assert_eq!(rotated.dimensions(), (2, 3));
+        // The bottom left corner is now the top left one
+        assert_eq!(rotated.get_pixel(0, 0).0, [0, 50, 7]);
+        assert_eq!(rotated.get_pixel(1, 2).0, [100, 0, 7]);
+
+        fs::write(dir.join("sign.ktx2"), "KTX")?;
+        assert!(rotate_texture(&dir.join("sign.ktx2"), Rotation::Half).is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
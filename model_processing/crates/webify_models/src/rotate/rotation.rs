@@ -0,0 +1,63 @@
+//! Quarter turns a texture is rotated by
+
+use image::DynamicImage;
+
+/// Clockwise rotation by quarter turns, which moves pixels around without
+/// resampling them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    /// Rotation by `degrees` clockwise, counterclockwise when negative, when it's
+    /// a multiple of 90 that isn't a whole number of turns
+    pub fn from_degrees(degrees: i32) -> Option<Rotation> {
+        match degrees.rem_euclid(360) {
+            90 => Some(Rotation::Quarter),
+            180 => Some(Rotation::Half),
+            270 => Some(Rotation::ThreeQuarters),
+            _ => None,
+        }
+    }
+
+    /// Degrees of the rotation, clockwise
+    pub fn degrees(self) -> u32 {
+        match self {
+            Rotation::Quarter => 90,
+            Rotation::Half => 180,
+            Rotation::ThreeQuarters => 270,
+        }
+    }
+
+    /// Whether the width and height of the image swap
+    pub fn swaps_dimensions(self) -> bool {
+        self != Rotation::Half
+    }
+
+    /// The image rotated
+    pub fn apply(self, image: &DynamicImage) -> DynamicImage {
+        match self {
+            Rotation::Quarter => image.rotate90(),
+            Rotation::Half => image.rotate180(),
+            Rotation::ThreeQuarters => image.rotate270(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rotation_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_quarter_turns_in_either_direction() {
+        assert_eq!(Rotation::from_degrees(90), Some(Rotation::Quarter));
+        assert_eq!(Rotation::from_degrees(-90), Some(Rotation::ThreeQuarters));
+        assert_eq!(Rotation::from_degrees(540), Some(Rotation::Half));
+        assert_eq!(Rotation::from_degrees(0), None);
+        assert_eq!(Rotation::from_degrees(360), None);
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Generate the JSON Schema of one of the files webify_models writes
+
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::dependencies::AssetGraph;
+use crate::floorplan::FloorplanMetadata;
+use crate::image_processing::HashedNames;
+use crate::manifest::ModelManifest;
+use crate::navmesh::WorldNavmesh;
+use crate::provenance::ProcessedMarker;
+use crate::report::RunReport;
+use crate::trends::TrendSeries;
+
+/// Names of the schemas: the `floorplan` metadata, the `graph --format json` asset
+/// graph, the `--hash-names` mapping of the original texture names, the
+/// `models.json` manifest, the `--navmesh` navmeshes, the
+/// `--json-report` run report, the `.webify.json` sidecars recording how each
+/// file was processed and the `trends --json` series
+pub const SCHEMA_NAMES: [&str; 8] = [
+    "floorplan",
+    "graph",
+    "hashes",
+    "models",
+    "navmesh",
+    "report",
+    "sidecar",
+    "trends",
+];
+
+/// JSON Schema of the file called `name`, one of `SCHEMA_NAMES`
+pub fn json_schema(name: &str) -> Option<RootSchema> {
+    match name {
+        "floorplan" => Some(schema_for!(FloorplanMetadata)),
+        "graph" => Some(schema_for!(AssetGraph)),
+        "hashes" => Some(schema_for!(HashedNames)),
+        "models" => Some(schema_for!(ModelManifest)),
+        "navmesh" => Some(schema_for!(WorldNavmesh)),
+        "report" => Some(schema_for!(RunReport)),
+        "sidecar" => Some(schema_for!(ProcessedMarker)),
+        "trends" => Some(schema_for!(TrendSeries)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod json_schema_tests {
+    use super::*;
+
+    use std::{fs, path::Path};
+
+    #[test]
+    fn it_matches_the_published_schemas() {
+        for name in SCHEMA_NAMES.iter() {
+            let path = Path::new("schemas").join(format!("{}.schema.json", name));
+            let published: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            let generated = serde_json::to_value(json_schema(name).unwrap()).unwrap();
+
+            assert_eq!(
+                generated, published,
+                "{:?} is out of date, regenerate it with `webify_models schema {}`",
+                path, name
+            );
+        }
+    }
+
+    #[test]
+    fn it_returns_none_for_unknown_names() {
+        assert!(json_schema("cache").is_none());
+    }
+}
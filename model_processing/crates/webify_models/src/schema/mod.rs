@@ -0,0 +1,8 @@
+//! JSON Schema definitions of the files webify_models writes, generated from the
+//! Rust types so downstream tooling can validate against them
+
+mod json_schema;
+mod print_schema;
+
+pub use self::json_schema::{json_schema, SCHEMA_NAMES};
+pub use self::print_schema::print_schema;
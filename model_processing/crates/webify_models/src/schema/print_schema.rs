@@ -0,0 +1,22 @@
+//! Print JSON Schema definitions
+
+use std::{collections::BTreeMap, io::Error, result::Result};
+
+use crate::schema::{json_schema, SCHEMA_NAMES};
+
+/// Print the schema called `name` as JSON, or every schema keyed by name
+pub fn print_schema(name: Option<&str>) -> Result<(), Error> {
+    let json = match name {
+        Some(name) => serde_json::to_string_pretty(&json_schema(name)),
+        None => {
+            let schemas: BTreeMap<&str, _> = SCHEMA_NAMES
+                .iter()
+                .map(|name| (*name, json_schema(name)))
+                .collect();
+            serde_json::to_string_pretty(&schemas)
+        }
+    };
+    println!("{}", json.map_err(Error::other)?);
+
+    Ok(())
+}
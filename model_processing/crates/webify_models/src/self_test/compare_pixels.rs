@@ -0,0 +1,59 @@
+//! Compare an image with a golden one, allowing for small differences
+
+use std::{io::Error, result::Result};
+
+use image::DynamicImage;
+
+/// Describe how the image differs from the golden one, or `None` when their
+/// dimensions match and no channel of any pixel is off by more than `tolerance`
+pub fn compare_pixels(
+    image: &DynamicImage,
+    golden: &DynamicImage,
+    tolerance: u8,
+) -> Result<Option<String>, Error> {
+    let (image, golden) = (image.to_rgba8(), golden.to_rgba8());
+    if image.dimensions() != golden.dimensions() {
+        return Ok(Some(format!(
+            "is {}x{}, expected {}x{}",
+            image.width(),
+            image.height(),
+            golden.width(),
+            golden.height()
+        )));
+    }
+
+    let largest_difference = image
+        .pixels()
+        .zip(golden.pixels())
+        .flat_map(|(a, b)| a.0.iter().zip(b.0.iter()).map(|(a, b)| a.abs_diff(*b)))
+        .max()
+        .unwrap_or(0);
+    if largest_difference > tolerance {
+        return Ok(Some(format!(
+            "has channels off by up to {}, more than the tolerance of {}",
+            largest_difference, tolerance
+        )));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod compare_pixels_tests {
+    use super::*;
+
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn it_allows_differences_within_the_tolerance() -> Result<(), Error> {
+        let golden = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([100; 4])));
+        let close = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([103; 4])));
+        let smaller = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 2, Rgba([100; 4])));
+
+        assert_eq!(compare_pixels(&close, &golden, 3)?, None);
+        assert!(compare_pixels(&close, &golden, 2)?.is_some());
+        assert!(compare_pixels(&smaller, &golden, 255)?.is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! The fixture library and the outputs expected from processing it
+
+/// Files of the fixture library, relative to its root
+pub const FIXTURE_FILES: &[(&str, &[u8])] = &[
+    (
+        "lamp/model.config",
+        include_bytes!("../../self_test/library/lamp/model.config"),
+    ),
+    (
+        "lamp/model.sdf",
+        include_bytes!("../../self_test/library/lamp/model.sdf"),
+    ),
+    (
+        "lamp/meshes/lamp.dae",
+        include_bytes!("../../self_test/library/lamp/meshes/lamp.dae"),
+    ),
+    (
+        "lamp/wood.jpg",
+        include_bytes!("../../self_test/library/lamp/wood.jpg"),
+    ),
+    (
+        "lamp/materials/textures/shade.tga",
+        include_bytes!("../../self_test/library/lamp/materials/textures/shade.tga"),
+    ),
+];
+
+/// What a file of the processed library should look like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Golden {
+    /// Byte for byte the same, compared by hash
+    Exact(&'static [u8]),
+    /// An image with the same dimensions, every channel of every pixel within
+    /// `tolerance` of the golden PNG, since decoders may round differently
+    Pixels { png: &'static [u8], tolerance: u8 },
+    /// Removed by the run
+    Absent,
+}
+
+/// Files of the processed library, relative to its root, with what they should be
+pub const GOLDEN_FILES: &[(&str, Golden)] = &[
+    (
+        "lamp/materials/textures/wood.png",
+        Golden::Pixels {
+            png: include_bytes!("../../self_test/golden/lamp/materials/textures/wood.png"),
+            tolerance: 8,
+        },
+    ),
+    (
+        "lamp/materials/textures/shade.png",
+        Golden::Pixels {
+            png: include_bytes!("../../self_test/golden/lamp/materials/textures/shade.png"),
+            tolerance: 0,
+        },
+    ),
+    (
+        "lamp/meshes/lamp.dae",
+        Golden::Exact(include_bytes!(
+            "../../self_test/golden/lamp/meshes/lamp.dae"
+        )),
+    ),
+    (
+        "lamp/model.sdf",
+        Golden::Exact(include_bytes!("../../self_test/library/lamp/model.sdf")),
+    ),
+    ("lamp/wood.jpg", Golden::Absent),
+    ("lamp/materials/textures/shade.tga", Golden::Absent),
+];
@@ -0,0 +1,10 @@
+//! Process a fixture library bundled in the binary and compare the result with
+//! golden outputs, so users can validate their build and codec stack
+
+mod compare_pixels;
+mod fixture;
+mod run_self_test;
+
+pub use self::compare_pixels::compare_pixels;
+pub use self::fixture::{Golden, FIXTURE_FILES, GOLDEN_FILES};
+pub use self::run_self_test::run_self_test;
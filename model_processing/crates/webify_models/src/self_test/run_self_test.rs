@@ -0,0 +1,85 @@
+//! Run the pipeline on the bundled fixture library and check its output
+
+use std::{env, fs, io::Error, path::Path, process, result::Result};
+
+use crate::options::RunOptions;
+use crate::pipeline::Pipeline;
+use crate::self_test::{compare_pixels, Golden, FIXTURE_FILES, GOLDEN_FILES};
+
+/// Write the fixture library to a temporary directory, webify a copy of it with
+/// the default options and compare the result with the golden files. Returns a
+/// description of every mismatch, so an empty list means this build works.
+pub fn run_self_test() -> Result<Vec<String>, Error> {
+    let dir = env::temp_dir().join(format!("webify_self_test_{}", process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+
+    let result = self_test_in(&dir);
+    fs::remove_dir_all(&dir)?;
+
+    result
+}
+
+fn self_test_in(dir: &Path) -> Result<Vec<String>, Error> {
+    let library = dir.join("library");
+    for (path, contents) in FIXTURE_FILES {
+        let file = library.join(path);
+        fs::create_dir_all(file.parent().unwrap())?;
+        fs::write(file, contents)?;
+    }
+
+    let output = dir.join("output");
+    let options = RunOptions {
+        output: Some(output.clone()),
+        ..RunOptions::default()
+    };
+    let report = Pipeline::new(options).run(&library)?;
+
+    let mut mismatches: Vec<String> = report.failures.iter().map(|f| f.to_string()).collect();
+    for (path, golden) in GOLDEN_FILES {
+        if let Some(mismatch) = check_file(&output.join(path), golden)? {
+            mismatches.push(format!("{} {}", path, mismatch));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Describe how the file differs from what it should be, or `None` when it matches
+fn check_file(file: &Path, golden: &Golden) -> Result<Option<String>, Error> {
+    match (golden, file.is_file()) {
+        (Golden::Absent, true) => Ok(Some(String::from("should have been removed"))),
+        (Golden::Absent, false) => Ok(None),
+        (_, false) => Ok(Some(String::from("is missing"))),
+        (Golden::Exact(expected), true) => {
+            let (hash, expected_hash) = (blake3::hash(&fs::read(file)?), blake3::hash(expected));
+            if hash == expected_hash {
+                Ok(None)
+            } else {
+                Ok(Some(format!(
+                    "has hash {}, expected {}",
+                    hash.to_hex(),
+                    expected_hash.to_hex()
+                )))
+            }
+        }
+        (Golden::Pixels { png, tolerance }, true) => {
+            let image = image::open(file)
+                .map_err(|e| Error::other(format!("Could not open {:?}: {:?}", file, e)))?;
+            let golden = image::load_from_memory(png).map_err(Error::other)?;
+            compare_pixels(&image, &golden, *tolerance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod run_self_test_tests {
+    use super::*;
+
+    #[test]
+    fn it_passes_on_this_build() -> Result<(), Error> {
+        assert_eq!(run_self_test()?, Vec::<String>::new());
+        Ok(())
+    }
+}
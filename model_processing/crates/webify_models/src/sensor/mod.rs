@@ -0,0 +1,8 @@
+//! Sensors: the visuals of lidars, cameras and the like, stripped or swapped for
+//! simple shapes as `webify.toml` asks, since they clutter the web scene
+
+mod replace_sensor_visuals;
+
+pub use self::replace_sensor_visuals::{
+    replace_sensor_visuals, replace_sensor_visuals_contents, ANY_SENSOR,
+};
@@ -0,0 +1,303 @@
+//! Strip or substitute the visuals of sensors in SDF and world files
+
+use std::{collections::BTreeMap, fs, io::Error, path::Path, result::Result};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::config::SensorVisualRule;
+
+/// Key of the rule of the sensor types without one of their own
+pub const ANY_SENSOR: &str = "*";
+
+/// Apply the rules, keyed by sensor type, to the visuals of the links holding a
+/// sensor in the SDF or world file: strip them, or swap their geometry for the
+/// rule's. A link goes by the rule of its first sensor with one, its type's or
+/// else the `"*"` one. Sensors of joints, like force-torque ones, have no visuals
+/// and are left out. The rest of the file is left as is. Returns whether the file
+/// changed.
+pub fn replace_sensor_visuals(
+    file: &Path,
+    rules: &BTreeMap<String, SensorVisualRule>,
+) -> Result<bool, Error> {
+    let contents = fs::read_to_string(file)?;
+    let edited = replace_sensor_visuals_contents(&contents, rules)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {}", file, e)))?;
+    match edited {
+        Some(edited) => fs::write(file, edited)?,
+        None => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Apply the rules to the contents of an SDF or world file. Returns the edited
+/// contents, or `None` when no visual changes.
+pub fn replace_sensor_visuals_contents(
+    contents: &str,
+    rules: &BTreeMap<String, SensorVisualRule>,
+) -> Result<Option<String>, Error> {
+    if rules.is_empty() {
+        return Ok(None);
+    }
+    let links = read_links(contents).map_err(|e| Error::other(e.to_string()))?;
+
+    // (start, end, replacement), applied from the end so the offsets stay valid
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    for link in links {
+        let rule = link
+            .sensors
+            .iter()
+            .find_map(|sensor| rules.get(sensor).or_else(|| rules.get(ANY_SENSOR)));
+        match rule {
+            None | Some(SensorVisualRule::Keep) => (),
+            Some(SensorVisualRule::Strip) => {
+                for visual in link.visuals {
+                    let (start, end) = whole_lines(contents, visual.span);
+                    edits.push((start, end, String::new()));
+                }
+            }
+            Some(rule) => {
+                let geometry = rule.geometry().unwrap_or_default();
+                for visual in link.visuals {
+                    match visual.geometry {
+                        Some((start, end)) if contents[start..end] != geometry => {
+                            edits.push((start, end, geometry.clone()))
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+    if edits.is_empty() {
+        return Ok(None);
+    }
+
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.0));
+    let mut edited = contents.to_string();
+    for (start, end, replacement) in edits {
+        edited.replace_range(start..end, &replacement);
+    }
+
+    Ok(Some(edited))
+}
+
+/// Byte offsets of the parts of a `<link>` the rules care about
+#[derive(Default)]
+struct LinkLayout {
+    /// Types of the sensors of the link, in order
+    sensors: Vec<String>,
+    visuals: Vec<VisualLayout>,
+}
+
+/// Byte offsets of a `<visual>` and of its `<geometry>`
+struct VisualLayout {
+    span: (usize, usize),
+    geometry: Option<(usize, usize)>,
+}
+
+/// Find the sensors and visuals of every link of the file
+fn read_links(contents: &str) -> Result<Vec<LinkLayout>, quick_xml::Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut links: Vec<LinkLayout> = Vec::new();
+    // Elements open around the current one, with where they start
+    let mut path: Vec<(Vec<u8>, usize)> = Vec::new();
+    let mut open_links: Vec<usize> = Vec::new();
+    let mut visual: Option<VisualLayout> = None;
+    let mut geometry_start = None;
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event()?;
+        let end = reader.buffer_position() as usize;
+
+        let (element, is_empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                let (name, open) = path.pop().unwrap_or_default();
+                if name.as_slice() != e.name().as_ref() {
+                    continue;
+                }
+                let parent = path.last().map(|(name, _)| name.as_slice());
+                close(
+                    &name,
+                    parent,
+                    (open, end),
+                    &mut links,
+                    &mut open_links,
+                    &mut visual,
+                    &mut geometry_start,
+                );
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let name = element.name().as_ref().to_vec();
+        let parent = path.last().map(|(name, _)| name.as_slice());
+        match (name.as_slice(), parent) {
+            (b"link", _) => {
+                links.push(LinkLayout::default());
+                open_links.push(links.len() - 1);
+            }
+            (b"sensor", Some(b"link")) => {
+                let kind = element
+                    .attributes()
+                    .flatten()
+                    .find(|attribute| attribute.key.as_ref() == b"type")
+                    .map(|attribute| String::from_utf8_lossy(&attribute.value).into_owned());
+                if let (Some(kind), Some(&link)) = (kind, open_links.last()) {
+                    links[link].sensors.push(kind);
+                }
+            }
+            (b"visual", Some(b"link")) => {
+                visual = Some(VisualLayout {
+                    span: (start, end),
+                    geometry: None,
+                });
+            }
+            (b"geometry", Some(b"visual")) if visual.is_some() => geometry_start = Some(start),
+            _ => (),
+        }
+
+        if is_empty {
+            close(
+                &name,
+                parent,
+                (start, end),
+                &mut links,
+                &mut open_links,
+                &mut visual,
+                &mut geometry_start,
+            );
+        } else {
+            path.push((name, start));
+        }
+    }
+
+    Ok(links)
+}
+
+/// Record the element of the name closed at `span`, its parent named `parent`
+fn close(
+    name: &[u8],
+    parent: Option<&[u8]>,
+    span: (usize, usize),
+    links: &mut [LinkLayout],
+    open_links: &mut Vec<usize>,
+    visual: &mut Option<VisualLayout>,
+    geometry_start: &mut Option<usize>,
+) {
+    match (name, parent) {
+        (b"link", _) => {
+            open_links.pop();
+        }
+        (b"visual", Some(b"link")) => {
+            if let (Some(mut closed), Some(&link)) = (visual.take(), open_links.last()) {
+                closed.span = span;
+                links[link].visuals.push(closed);
+            }
+        }
+        (b"geometry", Some(b"visual")) => {
+            if let (Some(visual), Some(start)) = (visual.as_mut(), geometry_start.take()) {
+                visual.geometry = Some((start, span.1));
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The span grown to the whole lines it covers when nothing else is on them, so
+/// removing it leaves no blank line behind
+fn whole_lines(contents: &str, (start, end): (usize, usize)) -> (usize, usize) {
+    let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = contents[end..]
+        .find('\n')
+        .map_or(contents.len(), |i| end + i + 1);
+    if contents[line_start..start].trim().is_empty() && contents[end..line_end].trim().is_empty() {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+#[cfg(test)]
+mod replace_sensor_visuals_tests {
+    use super::*;
+
+    const ROBOT: &str = r#"<?xml version="1.0"?>
+<sdf version="1.6">
+  <model name="rover">
+    <link name="chassis">
+      <visual name="body"><geometry><box><size>1 0.6 0.3</size></box></geometry></visual>
+      <sensor name="imu" type="imu"/>
+    </link>
+    <link name="lidar">
+      <!-- spinning head -->
+      <visual name="head">
+        <geometry><mesh><uri>model://rover/meshes/lidar.dae</uri></mesh></geometry>
+      </visual>
+      <visual name="base"><geometry><cylinder><radius>0.05</radius><length>0.02</length></cylinder></geometry></visual>
+      <sensor name="scan" type="gpu_ray"><update_rate>10</update_rate></sensor>
+    </link>
+    <link name="camera">
+      <visual name="housing"><geometry><mesh><uri>model://rover/meshes/camera.dae</uri></mesh></geometry></visual>
+      <sensor name="front" type="camera"/>
+    </link>
+    <joint name="wheel" type="revolute"><sensor name="torque" type="force_torque"/></joint>
+  </model>
+</sdf>
+"#;
+
+    fn rules(rules: &[(&str, SensorVisualRule)]) -> BTreeMap<String, SensorVisualRule> {
+        rules
+            .iter()
+            .map(|(sensor, rule)| (sensor.to_string(), rule.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn it_strips_and_substitutes_sensor_visuals() -> Result<(), Error> {
+        let rules = rules(&[
+            ("gpu_ray", SensorVisualRule::Strip),
+            ("camera", SensorVisualRule::Box([0.05, 0.05, 0.05])),
+        ]);
+
+        let edited = replace_sensor_visuals_contents(ROBOT, &rules)?.unwrap();
+
+        assert!(!edited.contains("lidar.dae"));
+        assert!(!edited.contains("<cylinder>"));
+        assert!(edited.contains("      <!-- spinning head -->\n      <sensor name=\"scan\""));
+        assert!(edited.contains(
+            "<visual name=\"housing\"><geometry><box><size>0.05 0.05 0.05</size></box></geometry></visual>"
+        ));
+        assert!(edited.contains("<size>1 0.6 0.3</size>"));
+        assert_eq!(replace_sensor_visuals_contents(&edited, &rules)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_falls_back_to_the_rule_of_any_sensor() -> Result<(), Error> {
+        let rules = rules(&[
+            (ANY_SENSOR, SensorVisualRule::Sphere(0.1)),
+            ("imu", SensorVisualRule::Keep),
+        ]);
+
+        let edited = replace_sensor_visuals_contents(ROBOT, &rules)?.unwrap();
+
+        assert_eq!(
+            edited
+                .matches("<geometry><sphere><radius>0.1</radius></sphere></geometry>")
+                .count(),
+            3
+        );
+        assert!(edited.contains("<size>1 0.6 0.3</size>"));
+        assert_eq!(
+            replace_sensor_visuals_contents(ROBOT, &BTreeMap::new())?,
+            None
+        );
+        Ok(())
+    }
+}
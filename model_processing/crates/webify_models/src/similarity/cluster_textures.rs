@@ -0,0 +1,102 @@
+//! Group the textures that look alike
+
+use crate::similarity::{hash_distance, HashedTexture, TextureCluster};
+
+/// Group textures whose perceptual hashes are at most `max_distance` bits apart,
+/// directly or through other textures of the group. Textures alike to none are left
+/// out. The clusters saving the most bytes come first.
+pub fn cluster_textures(textures: &[HashedTexture], max_distance: u32) -> Vec<TextureCluster> {
+    // Union-find over the texture indices
+    let mut parents: Vec<usize> = (0..textures.len()).collect();
+    for i in 0..textures.len() {
+        for j in (i + 1)..textures.len() {
+            if hash_distance(textures[i].hash, textures[j].hash) <= max_distance {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[root_j] = root_i;
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<&HashedTexture>> = vec![Vec::new(); textures.len()];
+    for (i, texture) in textures.iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        groups[root].push(texture);
+    }
+
+    let mut clusters: Vec<TextureCluster> = groups
+        .iter()
+        .filter_map(|group| TextureCluster::new(group))
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.saved_bytes
+            .cmp(&a.saved_bytes)
+            .then(a.keep.path.cmp(&b.keep.path))
+    });
+    clusters
+}
+
+/// Root of the set of the index, compressing the path on the way
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+#[cfg(test)]
+mod cluster_textures_tests {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+
+    fn texture(name: &str, size: u32, file_bytes: u64, hash: u64) -> HashedTexture {
+        HashedTexture {
+            path: Path::new("textures").join(name),
+            model: None,
+            width: size,
+            height: size,
+            file_bytes,
+            hash,
+        }
+    }
+
+    #[test]
+    fn it_groups_textures_within_the_distance() {
+        let textures = vec![
+            texture("brick_small.png", 512, 300, 0b0000_1111),
+            texture("brick.png", 1024, 900, 0b0000_0111),
+            texture("wood.png", 1024, 800, u64::MAX),
+            texture("brick_old.png", 1024, 700, 0b0000_0011),
+        ];
+
+        let clusters = cluster_textures(&textures, 1);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].keep.path,
+            Path::new("textures").join("brick.png")
+        );
+        let similar: Vec<(PathBuf, u32)> = clusters[0]
+            .similar
+            .iter()
+            .map(|texture| (texture.path.clone(), texture.distance))
+            .collect();
+        assert_eq!(
+            similar,
+            vec![
+                (Path::new("textures").join("brick_old.png"), 1),
+                (Path::new("textures").join("brick_small.png"), 1),
+            ]
+        );
+        assert_eq!(clusters[0].saved_bytes, 1000);
+    }
+
+    #[test]
+    fn it_leaves_out_textures_alike_to_none() {
+        let textures = vec![
+            texture("brick.png", 1024, 900, 0),
+            texture("wood.png", 1024, 800, u64::MAX),
+        ];
+        assert!(cluster_textures(&textures, 10).is_empty());
+    }
+}
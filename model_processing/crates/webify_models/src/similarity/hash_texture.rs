@@ -0,0 +1,43 @@
+//! Decode a texture and compute its perceptual hash
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use image::GenericImageView;
+
+use crate::image_processing::decode_texture;
+use crate::model::find_model_dir;
+use crate::options::HdrSettings;
+use crate::similarity::perceptual_hash;
+
+/// A texture of the library with its perceptual hash
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashedTexture {
+    pub path: PathBuf,
+    /// Model directory the texture belongs to, `None` for shared textures
+    pub model: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    pub file_bytes: u64,
+    pub hash: u64,
+}
+
+/// Decode the texture and hash it. Formats the converter can't decode, like KTX2,
+/// can't be hashed.
+pub fn hash_texture(path: &Path, library: &Path) -> Result<HashedTexture, Error> {
+    let image = decode_texture(path, &HdrSettings::default())?;
+    let (width, height) = image.dimensions();
+
+    Ok(HashedTexture {
+        path: path.to_path_buf(),
+        model: find_model_dir(path, library).ok(),
+        width,
+        height,
+        file_bytes: fs::metadata(path)?.len(),
+        hash: perceptual_hash(&image),
+    })
+}
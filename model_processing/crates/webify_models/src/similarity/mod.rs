@@ -0,0 +1,19 @@
+//! Find visually similar textures across a library (perceptual hash distance) and
+//! report which ones could be consolidated into one, for atlas and shared pool
+//! decisions
+
+mod cluster_textures;
+mod hash_texture;
+mod perceptual_hash;
+mod print_clusters;
+mod process;
+mod similarity_report;
+mod texture_cluster;
+
+pub use self::cluster_textures::cluster_textures;
+pub use self::hash_texture::{hash_texture, HashedTexture};
+pub use self::perceptual_hash::{hash_distance, perceptual_hash};
+pub use self::print_clusters::print_clusters;
+pub use self::process::{process, DEFAULT_MAX_DISTANCE};
+pub use self::similarity_report::SimilarityReport;
+pub use self::texture_cluster::{SimilarTexture, TextureCluster};
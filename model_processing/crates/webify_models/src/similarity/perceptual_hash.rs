@@ -0,0 +1,105 @@
+//! Perceptual hash of an image, close for images that look alike
+
+use std::f64::consts::PI;
+
+use image::{imageops::FilterType, DynamicImage};
+
+/// Side of the grayscale thumbnail the hash is computed from
+const THUMBNAIL_SIZE: usize = 32;
+
+/// Side of the block of lowest frequencies kept, one bit each
+const HASH_SIZE: usize = 8;
+
+/// 64-bit perceptual hash (pHash) of the image: the lowest frequencies of the
+/// discrete cosine transform of a 32x32 grayscale thumbnail, each compared to their
+/// median. Resizing, recompression and small color shifts barely change it.
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = image
+        .resize_exact(
+            THUMBNAIL_SIZE as u32,
+            THUMBNAIL_SIZE as u32,
+            FilterType::Triangle,
+        )
+        .to_luma8();
+    let pixels: Vec<f64> = thumbnail.pixels().map(|p| f64::from(p[0])).collect();
+
+    let mut coefficients = Vec::with_capacity(HASH_SIZE * HASH_SIZE);
+    for v in 0..HASH_SIZE {
+        for u in 0..HASH_SIZE {
+            coefficients.push(dct_coefficient(&pixels, u, v));
+        }
+    }
+
+    // The first coefficient is the average brightness, it says nothing of the looks
+    let mut sorted: Vec<f64> = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let median = sorted[sorted.len() / 2];
+
+    coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient > median)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Number of bits that differ between two hashes, 0 for images that look the same
+/// and around 32 for unrelated ones
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Coefficient (u, v) of the 2D DCT-II of the thumbnail, without normalization
+fn dct_coefficient(pixels: &[f64], u: usize, v: usize) -> f64 {
+    let n = THUMBNAIL_SIZE as f64;
+    let mut sum = 0.0;
+    for y in 0..THUMBNAIL_SIZE {
+        let cos_y = ((2 * y + 1) as f64 * v as f64 * PI / (2.0 * n)).cos();
+        for x in 0..THUMBNAIL_SIZE {
+            let cos_x = ((2 * x + 1) as f64 * u as f64 * PI / (2.0 * n)).cos();
+            sum += pixels[y * THUMBNAIL_SIZE + x] * cos_x * cos_y;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod perceptual_hash_tests {
+    use super::*;
+
+    use image::{imageops, Rgb, RgbImage};
+
+    /// Smooth random blobs, the same at any size for the same seed
+    fn blobs(size: u32, seed: u32) -> DynamicImage {
+        let mut state = seed;
+        let grid = RgbImage::from_fn(8, 8, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let value = (state >> 16) as u8;
+            Rgb([value, value / 2, 255 - value])
+        });
+        DynamicImage::ImageRgb8(imageops::resize(&grid, size, size, FilterType::Triangle))
+    }
+
+    #[test]
+    fn it_barely_changes_when_resized_or_brightened() {
+        let original = blobs(256, 1);
+        let smaller = original.resize_exact(64, 64, FilterType::Triangle);
+        let brighter = DynamicImage::ImageRgb8(imageops::brighten(&original.to_rgb8(), 20));
+
+        let hash = perceptual_hash(&original);
+        assert!(hash_distance(hash, perceptual_hash(&smaller)) <= 4);
+        assert!(hash_distance(hash, perceptual_hash(&brighter)) <= 4);
+    }
+
+    #[test]
+    fn it_tells_different_images_apart() {
+        let hash = perceptual_hash(&blobs(256, 1));
+        let other = perceptual_hash(&blobs(256, 2));
+        assert!(hash_distance(hash, other) > 16);
+    }
+
+    #[test]
+    fn it_counts_differing_bits() {
+        assert_eq!(hash_distance(0b1011, 0b0110), 3);
+        assert_eq!(hash_distance(u64::MAX, u64::MAX), 0);
+    }
+}
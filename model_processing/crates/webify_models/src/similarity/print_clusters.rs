@@ -0,0 +1,65 @@
+//! Human-readable output for a texture similarity analysis
+
+use console::style;
+
+use crate::report::format_bytes;
+use crate::similarity::{SimilarTexture, SimilarityReport};
+
+/// Print the clusters of similar textures to the console
+pub fn print_clusters(report: &SimilarityReport) {
+    for path in &report.unreadable {
+        println!(
+            "{} {} couldn't be decoded, left out",
+            style("!").yellow().bold(),
+            path.to_string_lossy()
+        );
+    }
+
+    for cluster in &report.clusters {
+        println!(
+            "\n{} {}, saves {}",
+            style("keep").green().bold(),
+            describe(&cluster.keep),
+            style(format_bytes(cluster.saved_bytes)).bold()
+        );
+        for texture in &cluster.similar {
+            println!(
+                "  {} {}, {} bit(s) apart",
+                style("~").yellow().bold(),
+                describe(texture),
+                texture.distance
+            );
+        }
+    }
+
+    if report.clusters.is_empty() {
+        println!(
+            "\n{}",
+            style(format!(
+                "No similar textures among the {} compared.",
+                report.textures
+            ))
+            .green()
+            .bold()
+        );
+    } else {
+        println!(
+            "\n{} cluster(s) of similar textures among the {} compared: consolidating them would drop {} texture(s) and save {}.",
+            style(report.clusters.len()).bold().blue(),
+            report.textures,
+            style(report.redundant_textures()).bold(),
+            style(format_bytes(report.saved_bytes())).bold()
+        );
+    }
+}
+
+/// Path, dimensions and size of a texture
+fn describe(texture: &SimilarTexture) -> String {
+    format!(
+        "{} ({}x{}, {})",
+        texture.path.to_string_lossy(),
+        texture.width,
+        texture.height,
+        format_bytes(texture.file_bytes)
+    )
+}
@@ -0,0 +1,149 @@
+//! Orchestrator to find similar textures across a library
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use console::style;
+use rayon::prelude::*;
+
+use crate::cli::create_progress_bar;
+use crate::config::load_config;
+use crate::image_processing::{
+    estimate_decode_memory, is_decodable, texture_extensions, MemoryBudget,
+};
+use crate::options::RunOptions;
+use crate::similarity::{
+    cluster_textures, hash_texture, print_clusters, HashedTexture, SimilarityReport,
+};
+use crate::walk::walk_dir;
+
+/// Default largest hash distance of textures clustered together, out of 64 bits.
+/// Textures up to it look alike at a glance, beyond it they start to merely share
+/// colors or a layout.
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+/// Hash every texture of the library in parallel and cluster the ones that look
+/// alike, printing the result and optionally writing it out as JSON. Nothing in the
+/// library is changed. With a memory budget, large textures wait for the decodes in
+/// flight to finish rather than all being decoded at once.
+pub fn process(
+    library: &Path,
+    max_distance: u32,
+    json: Option<&Path>,
+    memory_budget: Option<u64>,
+) -> std::result::Result<SimilarityReport, std::io::Error> {
+    println!("\nScanning for textures to compare...");
+    let extensions = texture_extensions(&RunOptions::default(), &load_config(library)?);
+    let is_texture = |path: &Path| {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        extensions.contains(&extension) && is_decodable(&extension)
+    };
+    let paths = walk_dir(library, &is_texture)?;
+
+    let bar = create_progress_bar(paths.len() as u64);
+    bar.set_prefix("Texture Hash");
+    let unreadable = Mutex::new(Vec::new());
+    let memory_budget = memory_budget.map(MemoryBudget::new);
+    let textures: Vec<HashedTexture> = paths
+        .par_iter()
+        .filter_map(|path| {
+            let _reservation = memory_budget
+                .as_ref()
+                .map(|budget| budget.reserve(estimate_decode_memory(path)));
+            bar.inc(1);
+            match hash_texture(path, library) {
+                Ok(texture) => Some(texture),
+                Err(_) => {
+                    unreadable.lock().unwrap().push(path.clone());
+                    None
+                }
+            }
+        })
+        .collect();
+    bar.finish_and_clear();
+
+    let mut unreadable: Vec<PathBuf> = unreadable.into_inner().unwrap();
+    unreadable.sort();
+    let report = SimilarityReport {
+        library: library.to_path_buf(),
+        max_distance,
+        textures: textures.len(),
+        unreadable,
+        clusters: cluster_textures(&textures, max_distance),
+    };
+    print_clusters(&report);
+
+    if let Some(json_path) = json {
+        let contents = serde_json::to_string_pretty(&report).map_err(Error::other)?;
+        fs::write(json_path, contents)?;
+        println!(
+            "JSON report written to {}",
+            style(json_path.to_string_lossy()).dim()
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod process_tests {
+    use super::*;
+
+    use image::{imageops, imageops::FilterType, Rgb, RgbImage};
+
+    /// Smooth random blobs, the same at any size for the same seed
+    fn write_texture(path: &Path, size: u32, seed: u32) -> Result<(), Error> {
+        fs::create_dir_all(path.parent().unwrap())?;
+        let mut state = seed;
+        let grid = RgbImage::from_fn(8, 8, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let value = (state >> 16) as u8;
+            Rgb([value, 120, value / 2])
+        });
+        imageops::resize(&grid, size, size, FilterType::Triangle)
+            .save(path)
+            .map_err(Error::other)
+    }
+
+    #[test]
+    fn it_clusters_similar_textures_across_models() -> Result<(), Error> {
+        let library = Path::new("tests")
+            .join("similarity")
+            .join("test_run_it_clusters_similar_textures");
+        let textures = |model: &str| library.join(model).join("materials").join("textures");
+        write_texture(&textures("chair").join("fabric.png"), 256, 1)?;
+        write_texture(&textures("table").join("fabric_copy.jpg"), 128, 1)?;
+        write_texture(&textures("lamp").join("waves.png"), 256, 2)?;
+
+        let report = process(&library, DEFAULT_MAX_DISTANCE, None, None)?;
+
+        assert_eq!(report.textures, 3);
+        assert_eq!(report.clusters.len(), 1);
+        assert_eq!(
+            report.clusters[0].keep.path,
+            textures("chair").join("fabric.png")
+        );
+        assert_eq!(report.clusters[0].keep.model, Some(library.join("chair")));
+        assert_eq!(report.clusters[0].similar.len(), 1);
+        assert_eq!(
+            report.clusters[0].similar[0].path,
+            textures("table").join("fabric_copy.jpg")
+        );
+
+        // A budget too small for any texture decodes them one at a time, to the same result
+        let budgeted = process(&library, DEFAULT_MAX_DISTANCE, None, Some(1))?;
+        assert_eq!(budgeted.textures, 3);
+        assert_eq!(budgeted.clusters.len(), 1);
+
+        fs::remove_dir_all(&library)?;
+        Ok(())
+    }
+}
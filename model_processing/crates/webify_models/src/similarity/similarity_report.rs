@@ -0,0 +1,40 @@
+//! Result of a texture similarity analysis
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::similarity::TextureCluster;
+
+/// Clusters of similar textures found in a library
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarityReport {
+    pub library: PathBuf,
+    /// Largest perceptual hash distance, in bits out of 64, of textures clustered
+    /// together
+    pub max_distance: u32,
+    /// Number of textures compared
+    pub textures: usize,
+    /// Textures that couldn't be decoded, and so weren't compared
+    pub unreadable: Vec<PathBuf>,
+    /// Clusters saving the most bytes first
+    pub clusters: Vec<TextureCluster>,
+}
+
+impl SimilarityReport {
+    /// Bytes on disk saved by consolidating every cluster
+    pub fn saved_bytes(&self) -> u64 {
+        self.clusters
+            .iter()
+            .map(|cluster| cluster.saved_bytes)
+            .sum()
+    }
+
+    /// Number of textures that would no longer be needed once consolidated
+    pub fn redundant_textures(&self) -> usize {
+        self.clusters
+            .iter()
+            .map(|cluster| cluster.similar.len())
+            .sum()
+    }
+}
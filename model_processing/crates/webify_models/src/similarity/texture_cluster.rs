@@ -0,0 +1,69 @@
+//! Group of textures that look alike
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::similarity::{hash_distance, HashedTexture};
+
+/// A texture of a cluster
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarTexture {
+    pub path: PathBuf,
+    /// Model directory the texture belongs to, `None` for shared textures
+    pub model: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    pub file_bytes: u64,
+    /// Bits of its perceptual hash that differ from the kept texture's, out of 64
+    pub distance: u32,
+}
+
+/// Textures that look alike, and could all use the one kept instead
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextureCluster {
+    /// Largest texture of the cluster, the one the others could be replaced with
+    pub keep: SimilarTexture,
+    /// The other textures, closest to the kept one first
+    pub similar: Vec<SimilarTexture>,
+    /// Bytes on disk the similar textures take, saved by consolidating them
+    pub saved_bytes: u64,
+}
+
+impl TextureCluster {
+    /// Cluster of the textures, keeping the one with the most pixels, then the
+    /// largest file. `None` for fewer than two textures.
+    pub fn new(textures: &[&HashedTexture]) -> Option<TextureCluster> {
+        if textures.len() < 2 {
+            return None;
+        }
+        let kept = textures.iter().max_by_key(|texture| {
+            (
+                u64::from(texture.width) * u64::from(texture.height),
+                texture.file_bytes,
+                std::cmp::Reverse(&texture.path),
+            )
+        })?;
+
+        let to_similar = |texture: &HashedTexture| SimilarTexture {
+            path: texture.path.clone(),
+            model: texture.model.clone(),
+            width: texture.width,
+            height: texture.height,
+            file_bytes: texture.file_bytes,
+            distance: hash_distance(texture.hash, kept.hash),
+        };
+        let mut similar: Vec<SimilarTexture> = textures
+            .iter()
+            .filter(|texture| texture.path != kept.path)
+            .map(|texture| to_similar(texture))
+            .collect();
+        similar.sort_by(|a, b| a.distance.cmp(&b.distance).then(a.path.cmp(&b.path)));
+
+        Some(TextureCluster {
+            keep: to_similar(kept),
+            saved_bytes: similar.iter().map(|texture| texture.file_bytes).sum(),
+            similar,
+        })
+    }
+}
@@ -0,0 +1,278 @@
+//! Bake a heightmap into tiles of triangles with levels of detail
+
+use std::{io::Error, iter, result::Result};
+
+use image::{ImageBuffer, Luma};
+
+use crate::options::TerrainSettings;
+use crate::terrain::{SdfHeightmap, TerrainLayer, TerrainPatch, TerrainTile};
+
+/// Most texture layers blended per vertex, two glTF `VEC4` weights
+pub const MAX_TERRAIN_LAYERS: usize = 8;
+
+/// Bake the heightmap into tiles of `tile_size` quads per side, a vertex per pixel
+/// at full detail and every other one in each next level. Pixels are heights from
+/// 0 to the heightmap's `size.z`, the image spans its `size.x` and `size.y` around
+/// `pos` with its top towards +Y, as Gazebo lays it. Vertices carry their normal,
+/// their coordinates over the whole terrain and the weight of every texture layer,
+/// each painted over the lower ones as it fades in from its `min_height`.
+pub fn bake_terrain(
+    heightmap: &SdfHeightmap,
+    pixels: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    settings: &TerrainSettings,
+) -> Result<Vec<TerrainTile>, Error> {
+    let (width, height) = pixels.dimensions();
+    if width < 2 || height < 2 {
+        return Err(Error::other(format!(
+            "Heightmap {} is {}x{}, too small for a terrain",
+            heightmap.uri, width, height
+        )));
+    }
+    if heightmap.layers.len() > MAX_TERRAIN_LAYERS {
+        return Err(Error::other(format!(
+            "Heightmap {} has {} texture layers, at most {} can be blended",
+            heightmap.uri,
+            heightmap.layers.len(),
+            MAX_TERRAIN_LAYERS
+        )));
+    }
+
+    let grid = Grid {
+        heightmap,
+        pixels,
+        spacing: [
+            heightmap.size[0] / f64::from(width - 1),
+            heightmap.size[1] / f64::from(height - 1),
+        ],
+    };
+    let tile = settings.tile_size;
+    let mut tiles = Vec::new();
+    for row in 0..(height - 1).div_ceil(tile) {
+        for column in 0..(width - 1).div_ceil(tile) {
+            let columns = (column * tile, ((column + 1) * tile).min(width - 1));
+            let rows = (row * tile, ((row + 1) * tile).min(height - 1));
+            let lods = (0..settings.lods)
+                .map(|level| 1 << level)
+                .take_while(|&step| step <= tile)
+                .map(|step| grid.patch(columns, rows, step))
+                .collect();
+            tiles.push(TerrainTile { column, row, lods });
+        }
+    }
+
+    Ok(tiles)
+}
+
+/// The vertices a heightmap's pixels stand for
+struct Grid<'a> {
+    heightmap: &'a SdfHeightmap,
+    pixels: &'a ImageBuffer<Luma<u16>, Vec<u16>>,
+    /// Distance between pixels along X and Y, in meters
+    spacing: [f64; 2],
+}
+
+impl Grid<'_> {
+    /// Height of the pixel in the frame of the heightmap
+    fn elevation(&self, column: u32, row: u32) -> f64 {
+        let value = f64::from(self.pixels.get_pixel(column, row).0[0]) / f64::from(u16::MAX);
+        value * self.heightmap.size[2] + self.heightmap.pos[2]
+    }
+
+    /// Vertex of the pixel in the frame of the heightmap
+    fn point(&self, column: u32, row: u32) -> [f64; 3] {
+        let [size_x, size_y, _] = self.heightmap.size;
+        let [x, y, _] = self.heightmap.pos;
+        [
+            x - size_x / 2.0 + f64::from(column) * self.spacing[0],
+            y + size_y / 2.0 - f64::from(row) * self.spacing[1],
+            self.elevation(column, row),
+        ]
+    }
+
+    /// Normal of the surface at the pixel, from the slopes to its neighbors, in the
+    /// frame of the heightmap
+    fn normal(&self, column: u32, row: u32) -> [f64; 3] {
+        let (width, height) = self.pixels.dimensions();
+        let (left, right) = (column.saturating_sub(1), (column + 1).min(width - 1));
+        let (up, down) = (row.saturating_sub(1), (row + 1).min(height - 1));
+        let slope_x = (self.elevation(right, row) - self.elevation(left, row))
+            / (f64::from(right - left) * self.spacing[0]);
+        // Rows go towards -Y
+        let slope_y = (self.elevation(column, up) - self.elevation(column, down))
+            / (f64::from(down - up) * self.spacing[1]);
+
+        normalize([-slope_x, -slope_y, 1.0])
+    }
+
+    /// Triangles of the pixels from `columns.0` to `columns.1` and `rows.0` to
+    /// `rows.1`, every `step` of them, the last ones kept so neighbors meet
+    fn patch(&self, columns: (u32, u32), rows: (u32, u32), step: u32) -> TerrainPatch {
+        let samples = |(start, end): (u32, u32)| -> Vec<u32> {
+            (start..end)
+                .step_by(step as usize)
+                .chain(iter::once(end))
+                .collect()
+        };
+        let (columns, rows) = (samples(columns), samples(rows));
+        let (width, height) = self.pixels.dimensions();
+        let pose = &self.heightmap.pose;
+        let origin = pose.apply([0.0; 3]);
+
+        let mut patch = TerrainPatch::default();
+        for &row in &rows {
+            for &column in &columns {
+                let point = self.point(column, row);
+                let position = pose.apply(point);
+                let normal = pose.apply(self.normal(column, row));
+                patch.positions.push(position.map(|value| value as f32));
+                patch
+                    .normals
+                    .push([0, 1, 2].map(|axis| (normal[axis] - origin[axis]) as f32));
+                patch.uvs.push([
+                    column as f32 / (width - 1) as f32,
+                    row as f32 / (height - 1) as f32,
+                ]);
+                patch
+                    .weights
+                    .push(blend_weights(&self.heightmap.layers, point[2]));
+            }
+        }
+
+        let stride = columns.len() as u32;
+        for j in 0..rows.len() as u32 - 1 {
+            for i in 0..stride - 1 {
+                let top_left = j * stride + i;
+                let bottom_left = top_left + stride;
+                patch.indices.extend([
+                    top_left,
+                    bottom_left,
+                    top_left + 1,
+                    top_left + 1,
+                    bottom_left,
+                    bottom_left + 1,
+                ]);
+            }
+        }
+
+        patch
+    }
+}
+
+/// Weights of the layers at the height, the way Gazebo blends them: each layer
+/// after the first fades in from its `min_height` over its `fade_dist`, painted
+/// over the layers below it
+pub fn blend_weights(layers: &[TerrainLayer], height: f64) -> [f32; MAX_TERRAIN_LAYERS] {
+    let mut weights = [0.0f64; MAX_TERRAIN_LAYERS];
+    if layers.is_empty() {
+        return [0.0; MAX_TERRAIN_LAYERS];
+    }
+
+    weights[0] = 1.0;
+    for (k, layer) in layers.iter().enumerate().skip(1) {
+        let alpha = if layer.fade_dist > 0.0 {
+            ((height - layer.min_height) / layer.fade_dist).clamp(0.0, 1.0)
+        } else if height >= layer.min_height {
+            1.0
+        } else {
+            0.0
+        };
+        for weight in &mut weights[..k] {
+            *weight *= 1.0 - alpha;
+        }
+        weights[k] = alpha;
+    }
+
+    weights.map(|weight| weight as f32)
+}
+
+/// The vector scaled to unit length
+fn normalize(vector: [f64; 3]) -> [f64; 3] {
+    let length = vector.iter().map(|value| value * value).sum::<f64>().sqrt();
+    vector.map(|value| value / length)
+}
+
+#[cfg(test)]
+mod bake_terrain_tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::impostor::Transform;
+
+    fn heightmap(layers: Vec<TerrainLayer>) -> SdfHeightmap {
+        SdfHeightmap {
+            file: PathBuf::from("hills.world"),
+            uri: String::from("hills.png"),
+            size: [16.0, 16.0, 4.0],
+            pos: [0.0, 0.0, 1.0],
+            pose: Transform::identity(),
+            layers,
+        }
+    }
+
+    fn layer(min_height: f64, fade_dist: f64) -> TerrainLayer {
+        TerrainLayer {
+            diffuse: String::from("ground.png"),
+            normal: None,
+            size: 1.0,
+            min_height,
+            fade_dist,
+        }
+    }
+
+    #[test]
+    fn it_tiles_the_heightmap_in_levels_of_detail() -> Result<(), Error> {
+        // A ramp rising towards +X, 17 pixels for 16 quads per side
+        let pixels = ImageBuffer::from_fn(17, 17, |x, _| Luma([(x * 4096).min(65535) as u16]));
+        let settings = TerrainSettings {
+            tile_size: 8,
+            lods: 3,
+        };
+
+        let tiles = bake_terrain(&heightmap(vec![layer(0.0, 0.0)]), &pixels, &settings)?;
+
+        assert_eq!(tiles.len(), 4);
+        assert_eq!((tiles[1].column, tiles[1].row), (1, 0));
+        let lods = &tiles[0].lods;
+        assert_eq!(lods.len(), 3);
+        assert_eq!(lods[0].positions.len(), 81);
+        assert_eq!(lods[0].indices.len(), 8 * 8 * 6);
+        assert_eq!(lods[1].positions.len(), 25);
+        assert_eq!(lods[2].positions.len(), 9);
+
+        // The top left pixel sits at the top left corner, lifted by `pos`
+        assert_eq!(lods[0].positions[0], [-8.0, 8.0, 1.0]);
+        assert_eq!(lods[0].uvs[0], [0.0, 0.0]);
+        assert_eq!(tiles[3].lods[0].uvs.last(), Some(&[1.0, 1.0]));
+        // Every level reaches the edge of the tile, so neighbors meet
+        assert_eq!(lods[2].positions[2][0], 0.0);
+        // The ramp leans the normals back towards -X
+        let normal = lods[0].normals[40];
+        assert!(normal[0] < -0.1 && normal[1].abs() < 1e-6 && normal[2] > 0.9);
+        assert_eq!(lods[0].weights[0], [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_blends_the_layers_by_height() {
+        let layers = vec![layer(0.0, 0.0), layer(2.0, 2.0), layer(5.0, 0.0)];
+
+        assert_eq!(blend_weights(&layers, 1.0)[..3], [1.0, 0.0, 0.0]);
+        assert_eq!(blend_weights(&layers, 3.0)[..3], [0.5, 0.5, 0.0]);
+        assert_eq!(blend_weights(&layers, 4.5)[..3], [0.0, 1.0, 0.0]);
+        assert_eq!(blend_weights(&layers, 6.0)[..3], [0.0, 0.0, 1.0]);
+        assert_eq!(blend_weights(&[], 6.0), [0.0; MAX_TERRAIN_LAYERS]);
+    }
+
+    #[test]
+    fn it_rejects_heightmaps_it_cant_bake() {
+        let pixels = ImageBuffer::from_pixel(1, 8, Luma([0]));
+        let settings = TerrainSettings::default();
+        assert!(bake_terrain(&heightmap(Vec::new()), &pixels, &settings).is_err());
+
+        let pixels = ImageBuffer::from_pixel(8, 8, Luma([0]));
+        let layers = vec![layer(0.0, 0.0); MAX_TERRAIN_LAYERS + 1];
+        assert!(bake_terrain(&heightmap(layers), &pixels, &settings).is_err());
+    }
+}
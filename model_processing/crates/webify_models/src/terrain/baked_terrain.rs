@@ -0,0 +1,48 @@
+//! Meshes baked from a heightmap
+
+use crate::terrain::TerrainLayer;
+
+/// The tiles of a heightmap along with the material they're rendered with
+#[derive(Debug, Clone, PartialEq)]
+pub struct BakedTerrain {
+    /// Name of the heightmap's nodes
+    pub name: String,
+    /// Tiles, row by row from the top of the heightmap image
+    pub tiles: Vec<TerrainTile>,
+    /// Extent of the terrain along X and Y, in meters
+    pub size: [f64; 2],
+    /// Texture layers, referenced relative to the glTF file when they're in the
+    /// library
+    pub layers: Vec<TerrainLayer>,
+    /// Diffuse texture of the lowest layer relative to the glTF file, when it's
+    /// in the library
+    pub base_color: Option<String>,
+    /// Normal map of the lowest layer relative to the glTF file, when it's in the
+    /// library
+    pub normal_map: Option<String>,
+}
+
+/// A square of the terrain, in every level of detail
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainTile {
+    /// Column of the tile, from the left of the heightmap image
+    pub column: u32,
+    /// Row of the tile, from the top of the heightmap image
+    pub row: u32,
+    /// Meshes of the tile, the full detail first and each next one with half the
+    /// quads per side
+    pub lods: Vec<TerrainPatch>,
+}
+
+/// Triangles of a tile at one level of detail, in the frame of the world
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TerrainPatch {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Coordinates over the whole terrain, `0, 0` at the top left of the heightmap
+    pub uvs: Vec<[f32; 2]>,
+    /// Weight of each texture layer, summing to 1
+    pub weights: Vec<[f32; 8]>,
+    /// Counterclockwise triangles seen from above
+    pub indices: Vec<u32>,
+}
@@ -0,0 +1,191 @@
+//! Bake the terrain of a world
+
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::resolve_reference;
+use crate::image_processing::decode_texture;
+use crate::mesh_update::relative_path;
+use crate::options::RunOptions;
+use crate::terrain::{
+    bake_terrain, read_world_heightmaps, write_terrain_gltf, BakedTerrain, TerrainLayer,
+};
+use crate::world::read_world;
+
+/// Files the terrain of the world is written to, next to it: the glTF scene and
+/// its binary buffer
+pub fn terrain_paths(world: &Path) -> (PathBuf, PathBuf) {
+    let stem = world
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (
+        world.with_file_name(format!("{}.terrain.gltf", stem)),
+        world.with_file_name(format!("{}.terrain.bin", stem)),
+    )
+}
+
+/// Bake the heightmaps of the world, the included models resolved in `models`,
+/// into tiled meshes with levels of detail and write them next to the world as
+/// `<world>.terrain.gltf` and `<world>.terrain.bin`. The texture layers are
+/// referenced relative to the glTF file, or as written when they're not in the
+/// library. Returns the glTF file written, `None` when the world has no heightmap.
+pub fn generate_terrain(
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    options: &RunOptions,
+) -> Result<Option<PathBuf>, Error> {
+    let heightmaps = read_world_heightmaps(&read_world(world)?, world, models)?;
+    if heightmaps.is_empty() {
+        return Ok(None);
+    }
+
+    let (gltf, bin) = terrain_paths(world);
+    let gltf_dir = gltf.parent().unwrap_or_else(|| Path::new(""));
+    let mut terrains = Vec::new();
+    for (i, heightmap) in heightmaps.iter().enumerate() {
+        let image =
+            resolve_reference(&heightmap.file, &heightmap.uri, models).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "Heightmap {} of {:?} not found",
+                        heightmap.uri, heightmap.file
+                    ),
+                )
+            })?;
+        let pixels = decode_texture(&image, &options.hdr)?.to_luma16();
+        let reference = |uri: &str| {
+            resolve_reference(&heightmap.file, uri, models)
+                .map(|path| relative_path(gltf_dir, &path))
+        };
+
+        terrains.push(BakedTerrain {
+            name: format!("terrain{}", i),
+            tiles: bake_terrain(heightmap, &pixels, &options.terrain)?,
+            size: [heightmap.size[0], heightmap.size[1]],
+            layers: heightmap
+                .layers
+                .iter()
+                .map(|layer| TerrainLayer {
+                    diffuse: reference(&layer.diffuse).unwrap_or_else(|| layer.diffuse.clone()),
+                    normal: layer
+                        .normal
+                        .as_ref()
+                        .map(|normal| reference(normal).unwrap_or_else(|| normal.clone())),
+                    ..layer.clone()
+                })
+                .collect(),
+            base_color: heightmap
+                .layers
+                .first()
+                .and_then(|layer| reference(&layer.diffuse)),
+            normal_map: heightmap
+                .layers
+                .first()
+                .and_then(|layer| layer.normal.as_deref())
+                .and_then(reference),
+        });
+    }
+
+    write_terrain_gltf(&terrains, &gltf, &bin)?;
+    Ok(Some(gltf))
+}
+
+#[cfg(test)]
+mod generate_terrain_tests {
+    use super::*;
+
+    use std::fs;
+
+    use serde_json::Value;
+
+    use crate::dependencies::index_models;
+    use crate::pipeline::copy_tree;
+
+    #[test]
+    fn it_writes_the_terrain_of_the_world_as_gltf() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("terrain")
+            .join("test_run_generate_terrain");
+        copy_tree(&Path::new("tests").join("terrain").join("valley"), &dir)?;
+        let models = index_models(&dir)?;
+        let world = dir.join("worlds").join("valley.world");
+        let options = RunOptions {
+            terrain: crate::options::TerrainSettings {
+                tile_size: 16,
+                lods: 2,
+            },
+            ..RunOptions::default()
+        };
+
+        let gltf = generate_terrain(&world, &models, &options);
+        let (gltf_path, bin_path) = terrain_paths(&world);
+        let document: Result<Value, Error> = fs::read_to_string(&gltf_path)
+            .and_then(|contents| serde_json::from_str(&contents).map_err(Error::from));
+        let bin_bytes = fs::metadata(&bin_path).map(|metadata| metadata.len());
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(gltf?, Some(gltf_path));
+        let document = document?;
+        assert_eq!(document["buffers"][0]["uri"], "valley.terrain.bin");
+        assert_eq!(document["buffers"][0]["byteLength"], bin_bytes?);
+        // 32 quads per side make 2 by 2 tiles, each with a coarser level
+        assert_eq!(document["scenes"][0]["nodes"].as_array().unwrap().len(), 4);
+        assert_eq!(document["nodes"].as_array().unwrap().len(), 8);
+        assert_eq!(document["nodes"][0]["extensions"]["MSFT_lod"]["ids"][0], 1);
+        assert_eq!(document["extensionsUsed"][0], "MSFT_lod");
+
+        // Posed by the include, Y up: the hill peaks 8 above the sunken ground
+        let position = &document["accessors"][0];
+        assert!(position["max"][1].as_f64().unwrap() > 6.0);
+        assert!(position["min"][1].as_f64().unwrap() < -0.9);
+        let attributes = &document["meshes"][0]["primitives"][0]["attributes"];
+        assert!(attributes.get("_WEIGHTS0").is_some());
+        assert!(attributes.get("_WEIGHTS1").is_none());
+
+        let material = &document["materials"][0];
+        assert_eq!(
+            document["images"][0]["uri"],
+            "../models/hills/materials/textures/dirt.png"
+        );
+        assert!(material.get("normalTexture").is_none());
+        let scale = &material["pbrMetallicRoughness"]["baseColorTexture"]["extensions"]
+            ["KHR_texture_transform"]["scale"];
+        assert_eq!(scale[0], 16.0);
+        let layers = &material["extras"]["layers"];
+        assert_eq!(
+            layers[1]["diffuse"],
+            "../models/hills/materials/textures/grass.png"
+        );
+        assert_eq!(
+            layers[1]["normal"],
+            "file://media/materials/textures/flat_normal.png"
+        );
+        assert_eq!(layers[1]["fade_dist"], 4.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_worlds_without_heightmaps() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("terrain")
+            .join("test_run_skip_terrain");
+        fs::create_dir_all(&dir)?;
+        let world = dir.join("empty.world");
+        fs::write(&world, "<sdf version=\"1.6\"><world name=\"empty\"/></sdf>")?;
+
+        let gltf = generate_terrain(&world, &BTreeMap::new(), &RunOptions::default());
+        let written = terrain_paths(&world).0.exists();
+        fs::remove_dir_all(&dir)?;
+
+        assert!(gltf?.is_none());
+        assert!(!written);
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! Terrains: the heightmaps of each world baked into tiled meshes with levels of
+//! detail, written as glTF since web renderers have no heightmap geometry
+
+mod bake_terrain;
+mod baked_terrain;
+mod generate_terrain;
+mod read_world_heightmaps;
+mod sdf_heightmap;
+mod write_terrain_gltf;
+
+pub use self::bake_terrain::{bake_terrain, blend_weights, MAX_TERRAIN_LAYERS};
+pub use self::baked_terrain::{BakedTerrain, TerrainPatch, TerrainTile};
+pub use self::generate_terrain::{generate_terrain, terrain_paths};
+pub use self::read_world_heightmaps::read_world_heightmaps;
+pub use self::sdf_heightmap::{SdfHeightmap, TerrainLayer};
+pub use self::write_terrain_gltf::write_terrain_gltf;
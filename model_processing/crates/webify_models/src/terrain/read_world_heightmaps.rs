@@ -0,0 +1,172 @@
+//! Read the heightmap terrains of a world
+
+use std::{
+    collections::BTreeMap,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::impostor::{sdf_pose, Transform};
+use crate::terrain::{SdfHeightmap, TerrainLayer};
+use crate::world::visit_world_models;
+use crate::xml::XmlElement;
+
+/// Heightmap visuals of the models of the world, the included ones resolved in
+/// `models`, posed in the world. Heightmaps without a `<uri>` are left out. Missing
+/// sizes and positions take SDF's defaults, and each texture after the first is
+/// blended in by the `<blend>` of the same rank minus one.
+pub fn read_world_heightmaps(
+    world_element: &XmlElement,
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+) -> Result<Vec<SdfHeightmap>, Error> {
+    let mut heightmaps = Vec::new();
+    visit_world_models(world_element, world, models, &mut |model, file, frame| {
+        read_model_heightmaps(model, file, frame, &mut heightmaps);
+        Ok(())
+    })?;
+
+    Ok(heightmaps)
+}
+
+/// Read the heightmaps of every link of the model and its nested models, posed by
+/// `frame`
+fn read_model_heightmaps(
+    model: &XmlElement,
+    file: &Path,
+    frame: Transform,
+    heightmaps: &mut Vec<SdfHeightmap>,
+) {
+    for link in model.children_named("link") {
+        let link_frame = frame * sdf_pose(link);
+        for visual in link.children_named("visual") {
+            let heightmap = match visual.find(&["geometry", "heightmap"]) {
+                Some(heightmap) => heightmap,
+                None => continue,
+            };
+            let uri = match heightmap.child("uri") {
+                Some(uri) if !uri.text.trim().is_empty() => uri.text.trim().to_string(),
+                _ => continue,
+            };
+
+            heightmaps.push(SdfHeightmap {
+                file: file.to_path_buf(),
+                uri,
+                size: vector(heightmap, "size", [1.0, 1.0, 1.0]),
+                pos: vector(heightmap, "pos", [0.0; 3]),
+                pose: link_frame * sdf_pose(visual),
+                layers: read_layers(heightmap),
+            });
+        }
+    }
+    for nested in model.children_named("model") {
+        read_model_heightmaps(nested, file, frame * sdf_pose(nested), heightmaps);
+    }
+}
+
+/// Texture layers of the heightmap, paired with their blends
+fn read_layers(heightmap: &XmlElement) -> Vec<TerrainLayer> {
+    let text = |element: &XmlElement, name: &str| {
+        element
+            .child(name)
+            .map(|child| child.text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+    let number = |element: &XmlElement, name: &str| {
+        element
+            .child(name)
+            .and_then(|child| child.numbers::<f64>().first().copied())
+    };
+
+    let blends: Vec<&XmlElement> = heightmap.children_named("blend").collect();
+    heightmap
+        .children_named("texture")
+        .enumerate()
+        .filter_map(|(i, texture)| {
+            let blend = i.checked_sub(1).and_then(|i| blends.get(i));
+            Some(TerrainLayer {
+                diffuse: text(texture, "diffuse")?,
+                normal: text(texture, "normal"),
+                size: number(texture, "size").unwrap_or(10.0),
+                min_height: blend
+                    .and_then(|blend| number(blend, "min_height"))
+                    .unwrap_or(0.0),
+                fade_dist: blend
+                    .and_then(|blend| number(blend, "fade_dist"))
+                    .unwrap_or(0.0),
+            })
+        })
+        .collect()
+}
+
+/// The three numbers of the child, `default` when it's missing or malformed
+fn vector(element: &XmlElement, name: &str, default: [f64; 3]) -> [f64; 3] {
+    match element.child(name).map(|child| child.numbers::<f64>()) {
+        Some(values) if values.len() == 3 => [values[0], values[1], values[2]],
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+mod read_world_heightmaps_tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_posed_heightmaps_and_their_layers() -> Result<(), Error> {
+        let contents = r#"<world name="hills">
+  <model name="ground">
+    <pose>10 0 0 0 0 0</pose>
+    <link name="terrain">
+      <visual name="terrain">
+        <pose>0 5 0 0 0 0</pose>
+        <geometry>
+          <heightmap>
+            <uri>file://media/hills.png</uri>
+            <size>129 129 10</size>
+            <pos>0 0 -2</pos>
+            <texture><diffuse>dirt.png</diffuse><normal>dirt_normal.png</normal><size>4</size></texture>
+            <texture><diffuse>grass.png</diffuse><size>2</size></texture>
+            <blend><min_height>1</min_height><fade_dist>3</fade_dist></blend>
+          </heightmap>
+        </geometry>
+      </visual>
+      <visual name="flat"><geometry><heightmap><size>1 1 1</size></heightmap></geometry></visual>
+    </link>
+  </model>
+</world>"#;
+        let world_element = XmlElement::parse(contents).unwrap();
+        let world = Path::new("worlds").join("hills.world");
+
+        let heightmaps = read_world_heightmaps(&world_element, &world, &BTreeMap::new())?;
+
+        assert_eq!(heightmaps.len(), 1);
+        let heightmap = &heightmaps[0];
+        assert_eq!(heightmap.file, world);
+        assert_eq!(heightmap.uri, "file://media/hills.png");
+        assert_eq!(heightmap.size, [129.0, 129.0, 10.0]);
+        assert_eq!(heightmap.pos, [0.0, 0.0, -2.0]);
+        assert_eq!(heightmap.pose.apply([0.0; 3]), [10.0, 5.0, 0.0]);
+        assert_eq!(
+            heightmap.layers,
+            vec![
+                TerrainLayer {
+                    diffuse: String::from("dirt.png"),
+                    normal: Some(String::from("dirt_normal.png")),
+                    size: 4.0,
+                    min_height: 0.0,
+                    fade_dist: 0.0,
+                },
+                TerrainLayer {
+                    diffuse: String::from("grass.png"),
+                    normal: None,
+                    size: 2.0,
+                    min_height: 1.0,
+                    fade_dist: 3.0,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Heightmap terrain of a world
+
+use std::path::PathBuf;
+
+use crate::impostor::Transform;
+
+/// A `<heightmap>` visual of a world: the image it's raised from and where it
+/// stands
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfHeightmap {
+    /// File the heightmap is declared in, which its references are relative to
+    pub file: PathBuf,
+    /// Reference to the heightmap image
+    pub uri: String,
+    /// Extent of the terrain along X and Y, and the height of the brightest pixel,
+    /// in meters
+    pub size: [f64; 3],
+    /// Offset of the terrain's center from the frame of its visual
+    pub pos: [f64; 3],
+    /// Frame of the visual in the world
+    pub pose: Transform,
+    /// Texture layers blended over the terrain, lowest first
+    pub layers: Vec<TerrainLayer>,
+}
+
+/// A `<texture>` of a heightmap, with the `<blend>` it's painted over the lower
+/// layers with
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainLayer {
+    /// Reference to the diffuse texture
+    pub diffuse: String,
+    /// Reference to the normal map
+    pub normal: Option<String>,
+    /// Side of the ground one repetition of the textures covers, in meters
+    pub size: f64,
+    /// Height the layer starts fading in at, in the frame of the heightmap
+    pub min_height: f64,
+    /// Height above `min_height` the layer takes over completely at
+    pub fade_dist: f64,
+}
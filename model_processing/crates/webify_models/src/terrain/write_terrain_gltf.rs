@@ -0,0 +1,275 @@
+//! Write baked terrains as glTF
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use serde_json::{json, Value};
+
+use crate::terrain::{BakedTerrain, TerrainPatch};
+
+/// glTF component type of 32-bit floats
+const FLOAT: u32 = 5126;
+/// glTF component type of 32-bit unsigned integers
+const UNSIGNED_INT: u32 = 5125;
+/// glTF buffer view target of vertex attributes
+const ARRAY_BUFFER: u32 = 34962;
+/// glTF buffer view target of indices
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+/// glTF wrap mode of tiled textures
+const REPEAT: u32 = 10497;
+
+/// Write the terrains as a glTF 2.0 scene to `gltf`, its vertices and indices to
+/// `bin` next to it. Positions are converted from the Z-up of SDF to the Y-up of
+/// glTF. Each tile is a node with its coarser levels of detail chained through
+/// `MSFT_lod`, each level switched to as the tile covers half as much of the
+/// screen. The layer weights are the `_WEIGHTS0` and `_WEIGHTS1` attributes, for
+/// the viewer's terrain shader to blend the layers listed in the `extras` of the
+/// material; the material itself shows the lowest layer, repeated by its size.
+pub fn write_terrain_gltf(terrains: &[BakedTerrain], gltf: &Path, bin: &Path) -> Result<(), Error> {
+    let mut document = Document::default();
+    let mut scene_nodes = Vec::new();
+
+    for terrain in terrains {
+        let material = document.material(terrain);
+        for tile in &terrain.tiles {
+            let name = format!("{}_{}_{}", terrain.name, tile.column, tile.row);
+            let nodes: Vec<Value> = tile
+                .lods
+                .iter()
+                .enumerate()
+                .map(|(level, patch)| {
+                    let mesh = document.mesh(patch, material, terrain.layers.len());
+                    json!({ "name": format!("{}_lod{}", name, level), "mesh": mesh })
+                })
+                .collect();
+            let first = document.nodes.len();
+            let mut nodes = nodes.into_iter();
+            if let Some(mut node) = nodes.next() {
+                if tile.lods.len() > 1 {
+                    let ids: Vec<usize> = (first + 1..first + tile.lods.len()).collect();
+                    let coverage: Vec<f64> = (1..=tile.lods.len())
+                        .map(|level| 0.5f64.powi(level as i32))
+                        .collect();
+                    node["extensions"] = json!({ "MSFT_lod": { "ids": ids } });
+                    node["extras"] = json!({ "MSFT_screencoverage": coverage });
+                }
+                scene_nodes.push(first);
+                document.nodes.push(node);
+            }
+            document.nodes.extend(nodes);
+        }
+    }
+
+    let mut extensions = Vec::new();
+    if terrains
+        .iter()
+        .any(|terrain| terrain.tiles.iter().any(|tile| tile.lods.len() > 1))
+    {
+        extensions.push("MSFT_lod");
+    }
+    if !document.textures.is_empty() {
+        extensions.push("KHR_texture_transform");
+    }
+
+    let bin_name = bin
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut root = json!({
+        "asset": {
+            "version": "2.0",
+            "generator": format!("webify_models {}", env!("CARGO_PKG_VERSION")),
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": scene_nodes }],
+        "nodes": document.nodes,
+        "meshes": document.meshes,
+        "materials": document.materials,
+        "accessors": document.accessors,
+        "bufferViews": document.buffer_views,
+        "buffers": [{ "uri": bin_name, "byteLength": document.buffer.len() }],
+    });
+    if !document.textures.is_empty() {
+        root["textures"] = json!(document.textures);
+        root["images"] = json!(document.images);
+        root["samplers"] = json!([{ "wrapS": REPEAT, "wrapT": REPEAT }]);
+    }
+    if !extensions.is_empty() {
+        root["extensionsUsed"] = json!(extensions);
+    }
+
+    fs::write(bin, &document.buffer)?;
+    fs::write(gltf, serde_json::to_string_pretty(&root)?)?;
+    Ok(())
+}
+
+/// The parts of the glTF gathered while adding the terrains
+#[derive(Default)]
+struct Document {
+    nodes: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    accessors: Vec<Value>,
+    buffer_views: Vec<Value>,
+    buffer: Vec<u8>,
+}
+
+impl Document {
+    /// Add the material of the terrain, returning its index
+    fn material(&mut self, terrain: &BakedTerrain) -> usize {
+        let repeat = |layer_size: f64| {
+            [terrain.size[0], terrain.size[1]].map(|size| match layer_size > 0.0 {
+                true => size / layer_size,
+                false => 1.0,
+            })
+        };
+        let scale = repeat(terrain.layers.first().map_or(0.0, |layer| layer.size));
+
+        let mut material = json!({
+            "name": terrain.name,
+            "pbrMetallicRoughness": { "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+            "extras": {
+                "layers": terrain.layers.iter().map(|layer| json!({
+                    "diffuse": layer.diffuse,
+                    "normal": layer.normal,
+                    "size": layer.size,
+                    "min_height": layer.min_height,
+                    "fade_dist": layer.fade_dist,
+                })).collect::<Vec<_>>(),
+            },
+        });
+        if let Some(uri) = &terrain.base_color {
+            material["pbrMetallicRoughness"]["baseColorTexture"] = self.texture(uri, scale);
+        }
+        if let Some(uri) = &terrain.normal_map {
+            material["normalTexture"] = self.texture(uri, scale);
+        }
+
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Add a texture of the image, returning its info repeated by `scale`
+    fn texture(&mut self, uri: &str, scale: [f64; 2]) -> Value {
+        self.images.push(json!({ "uri": uri }));
+        self.textures
+            .push(json!({ "source": self.images.len() - 1, "sampler": 0 }));
+        json!({
+            "index": self.textures.len() - 1,
+            "extensions": { "KHR_texture_transform": { "scale": scale } },
+        })
+    }
+
+    /// Add the mesh of the patch with the weights of its first `layers`, returning
+    /// its index
+    fn mesh(&mut self, patch: &TerrainPatch, material: usize, layers: usize) -> usize {
+        let positions: Vec<[f32; 3]> = patch.positions.iter().map(|&p| y_up(p)).collect();
+        let normals: Vec<[f32; 3]> = patch.normals.iter().map(|&n| y_up(n)).collect();
+        let (min, max) = bounds(&positions);
+
+        let mut attributes = json!({
+            "POSITION": self.accessor(&flatten(&positions), "VEC3", ARRAY_BUFFER, Some((min, max))),
+            "NORMAL": self.accessor(&flatten(&normals), "VEC3", ARRAY_BUFFER, None),
+            "TEXCOORD_0": self.accessor(&flatten(&patch.uvs), "VEC2", ARRAY_BUFFER, None),
+        });
+        // A weight per layer, four per attribute
+        for (i, offset) in (0..layers).step_by(4).enumerate() {
+            let weights: Vec<f32> = patch
+                .weights
+                .iter()
+                .flat_map(|weights| weights[offset..offset + 4].to_vec())
+                .collect();
+            attributes[format!("_WEIGHTS{}", i)] =
+                json!(self.accessor(&weights, "VEC4", ARRAY_BUFFER, None));
+        }
+
+        let indices = self.view(
+            patch.indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+            ELEMENT_ARRAY_BUFFER,
+        );
+        self.accessors.push(json!({
+            "bufferView": indices,
+            "componentType": UNSIGNED_INT,
+            "count": patch.indices.len(),
+            "type": "SCALAR",
+        }));
+
+        self.meshes.push(json!({
+            "primitives": [{
+                "attributes": attributes,
+                "indices": self.accessors.len() - 1,
+                "material": material,
+            }],
+        }));
+        self.meshes.len() - 1
+    }
+
+    /// Add an accessor of float vectors, returning its index
+    fn accessor(
+        &mut self,
+        values: &[f32],
+        kind: &str,
+        target: u32,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let components = match kind {
+            "VEC2" => 2,
+            "VEC3" => 3,
+            _ => 4,
+        };
+        let view = self.view(
+            values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            target,
+        );
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len() / components,
+            "type": kind,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Add a buffer view of the bytes, returning its index. Every component is 4
+    /// bytes, so views stay aligned.
+    fn view(&mut self, bytes: Vec<u8>, target: u32) -> usize {
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.buffer.extend(bytes);
+        self.buffer_views.len() - 1
+    }
+}
+
+/// A vector of SDF's Z-up frame in glTF's Y-up one
+fn y_up([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [x, z, -y]
+}
+
+/// Smallest and largest coordinates of the points
+fn bounds(points: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for point in points {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+    }
+    (min, max)
+}
+
+/// The components of the vectors, one after the other
+fn flatten<const N: usize>(vectors: &[[f32; N]]) -> Vec<f32> {
+    vectors.iter().flatten().copied().collect()
+}
@@ -0,0 +1,14 @@
+//! Delete files the way the user asked for, permanently or to a trash they can be
+//! recovered from
+
+mod move_to_dir;
+mod move_to_mirror;
+mod move_to_os_trash;
+mod remove_file;
+mod rename_to_orig;
+
+pub use self::move_to_dir::move_to_dir;
+pub use self::move_to_mirror::move_to_mirror;
+pub use self::move_to_os_trash::{format_utc_date, move_to_os_trash};
+pub use self::remove_file::remove_file;
+pub use self::rename_to_orig::rename_to_orig;
@@ -0,0 +1,77 @@
+//! Move a deleted file to a trash directory
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+/// Move the file into the directory, next to the files trashed before it, and
+/// return where it ended up. A file with the same name already in the directory
+/// is kept, the new one gets a numbered name (`wood.2.jpg`, `wood.3.jpg`, ...).
+pub fn move_to_dir(path: &Path, trash_dir: &Path) -> Result<PathBuf, Error> {
+    fs::create_dir_all(trash_dir)?;
+    let destination = unique_destination(path, trash_dir)?;
+
+    // Renaming fails across file systems, e.g. a trash on another drive
+    if fs::rename(path, &destination).is_err() {
+        fs::copy(path, &destination)?;
+        fs::remove_file(path)?;
+    }
+
+    Ok(destination)
+}
+
+/// A path in the directory with the file's name that isn't taken yet
+fn unique_destination(path: &Path, dir: &Path) -> Result<PathBuf, Error> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::other(format!("{:?} has no file name to trash", path)))?;
+    let destination = dir.join(file_name);
+    if !destination.exists() {
+        return Ok(destination);
+    }
+
+    let stem = path.file_stem().unwrap_or(file_name).to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let destination = (2..)
+        .map(|n| dir.join(format!("{}.{}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap();
+
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod move_to_dir_tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_previously_trashed_files() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trash")
+            .join("test_run_move_to_dir");
+        let trash_dir = dir.join("trash");
+        fs::create_dir_all(&dir)?;
+
+        fs::write(dir.join("wood.jpg"), "first")?;
+        assert_eq!(
+            move_to_dir(&dir.join("wood.jpg"), &trash_dir)?,
+            trash_dir.join("wood.jpg")
+        );
+        fs::write(dir.join("wood.jpg"), "second")?;
+        assert_eq!(
+            move_to_dir(&dir.join("wood.jpg"), &trash_dir)?,
+            trash_dir.join("wood.2.jpg")
+        );
+        assert_eq!(fs::read_to_string(trash_dir.join("wood.jpg"))?, "first");
+        assert_eq!(fs::read_to_string(trash_dir.join("wood.2.jpg"))?, "second");
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
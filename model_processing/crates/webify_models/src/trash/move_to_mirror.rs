@@ -0,0 +1,61 @@
+//! Move a deleted file into a backup tree mirroring the processed one
+
+use std::{
+    io::Error,
+    path::{Component, Path, PathBuf},
+    result::Result,
+};
+
+use crate::trash::move_to_dir;
+
+/// Move the file into `backup_dir` at the path it has in `root`, so
+/// `root/model/materials/textures/wood.jpg` ends up at
+/// `backup_dir/model/materials/textures/wood.jpg`, and return where it ended up.
+/// A file outside of `root` keeps its whole relative path. A backup already at that
+/// path is kept, the new one gets a numbered name (`wood.2.jpg`, ...).
+pub fn move_to_mirror(path: &Path, backup_dir: &Path, root: &Path) -> Result<PathBuf, Error> {
+    let relative: PathBuf = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    let parent = relative.parent().unwrap_or_else(|| Path::new(""));
+
+    move_to_dir(path, &backup_dir.join(parent))
+}
+
+#[cfg(test)]
+mod move_to_mirror_tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn it_mirrors_the_tree() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trash")
+            .join("test_run_move_to_mirror");
+        let root = dir.join("models");
+        let textures = root.join("chair").join("materials").join("textures");
+        let backup_dir = dir.join("backup");
+        fs::create_dir_all(&textures)?;
+
+        fs::write(textures.join("wood.jpg"), "first")?;
+        let first = move_to_mirror(&textures.join("wood.jpg"), &backup_dir, &root);
+        fs::write(textures.join("wood.jpg"), "second")?;
+        let second = move_to_mirror(&textures.join("wood.jpg"), &backup_dir, &root);
+        let mirrored = backup_dir.join("chair").join("materials").join("textures");
+        let contents = (
+            fs::read_to_string(mirrored.join("wood.jpg")),
+            fs::read_to_string(mirrored.join("wood.2.jpg")),
+        );
+        fs::remove_dir_all(dir)?;
+
+        assert_eq!(first?, mirrored.join("wood.jpg"));
+        assert_eq!(second?, mirrored.join("wood.2.jpg"));
+        assert_eq!(contents.0?, "first");
+        assert_eq!(contents.1?, "second");
+        Ok(())
+    }
+}
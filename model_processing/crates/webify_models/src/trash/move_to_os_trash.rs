@@ -0,0 +1,140 @@
+//! Move a deleted file to the trash of the operating system
+
+use std::{
+    env, fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::trash::move_to_dir;
+
+/// Move the file to the user's trash and return where it ended up. Follows the
+/// freedesktop.org trash specification on Linux and BSDs, so the desktop can
+/// restore the file, and uses `~/.Trash` on macOS. Windows needs a trash directory.
+pub fn move_to_os_trash(path: &Path) -> Result<PathBuf, Error> {
+    if cfg!(windows) {
+        return Err(Error::other(
+            "Moving files to the Recycle Bin isn't supported, pass --trash-dir <dir> instead.",
+        ));
+    }
+
+    let home = env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::other("Can't find the trash, $HOME isn't set."))?;
+    if cfg!(target_os = "macos") {
+        return move_to_dir(path, &home.join(".Trash"));
+    }
+
+    let trash_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local").join("share"))
+        .join("Trash");
+    move_to_freedesktop_trash(path, &trash_dir)
+}
+
+/// Move the file to `files/` and record where it came from in `info/`
+fn move_to_freedesktop_trash(path: &Path, trash_dir: &Path) -> Result<PathBuf, Error> {
+    let original = path.canonicalize()?;
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&info_dir)?;
+
+    let trashed = move_to_dir(path, &trash_dir.join("files"))?;
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&original),
+        format_utc_date(SystemTime::now())
+    );
+    let info_name = format!(
+        "{}.trashinfo",
+        trashed.file_name().unwrap().to_string_lossy()
+    );
+    fs::write(info_dir.join(info_name), info)?;
+
+    Ok(trashed)
+}
+
+/// Percent-encode the path as the specification asks, keeping the separators
+fn encode_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// `YYYY-MM-DDThh:mm:ss`, in UTC
+pub fn format_utc_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, time_of_day) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from the number of days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod move_to_os_trash_tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn it_records_where_the_file_came_from() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trash")
+            .join("test_run_move_to_os_trash");
+        let trash_dir = dir.join("Trash");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("old wood.jpg"), "original")?;
+        let original = dir.join("old wood.jpg").canonicalize()?;
+
+        let trashed = move_to_freedesktop_trash(&dir.join("old wood.jpg"), &trash_dir)?;
+        assert_eq!(trashed, trash_dir.join("files").join("old wood.jpg"));
+        let info = fs::read_to_string(trash_dir.join("info").join("old wood.jpg.trashinfo"))?;
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}\n", encode_path(&original))));
+        assert!(info.contains("%20wood.jpg\n"));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_formats_the_deletion_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(format_utc_date(time), "2024-02-29T12:34:56");
+        assert_eq!(format_utc_date(UNIX_EPOCH), "1970-01-01T00:00:00");
+    }
+}
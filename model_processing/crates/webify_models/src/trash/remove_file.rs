@@ -0,0 +1,44 @@
+//! Delete a file the way the user asked for
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::options::Removal;
+use crate::trash::{move_to_dir, move_to_mirror, move_to_os_trash, rename_to_orig};
+
+/// Delete the file, move it to the trash with `--use-trash`, or keep a backup of it
+/// with `--backup-dir` or `--backup-orig`
+pub fn remove_file(path: &Path, removal: &Removal) -> Result<(), Error> {
+    match removal {
+        Removal::Delete => fs::remove_file(path),
+        Removal::Trash => move_to_os_trash(path).map(|_| ()),
+        Removal::TrashDir(trash_dir) => move_to_dir(path, trash_dir).map(|_| ()),
+        Removal::BackupDir { dir, root } => move_to_mirror(path, dir, root).map(|_| ()),
+        Removal::Orig => rename_to_orig(path).map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod remove_file_tests {
+    use super::*;
+
+    #[test]
+    fn it_moves_the_file_to_the_trash_dir() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trash")
+            .join("test_run_remove_file");
+        let trash_dir = dir.join("trash");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("wood.jpg"), "original")?;
+
+        remove_file(&dir.join("wood.jpg"), &Removal::TrashDir(trash_dir.clone()))?;
+        assert!(!dir.join("wood.jpg").exists());
+        assert_eq!(fs::read_to_string(trash_dir.join("wood.jpg"))?, "original");
+
+        fs::write(dir.join("wood.jpg"), "original")?;
+        remove_file(&dir.join("wood.jpg"), &Removal::Delete)?;
+        assert!(!dir.join("wood.jpg").exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+//! Keep a deleted file next to where it was, renamed
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+/// Extension appended to the name of the originals kept with `--backup-orig`
+const ORIG_EXTENSION: &str = "orig";
+
+/// Rename the file with `.orig` appended to its name, `wood.jpg` to
+/// `wood.jpg.orig`, and return its new path. An `.orig` already there is kept, the
+/// new one gets a numbered name (`wood.jpg.2.orig`, ...). The extension keeps it
+/// from being scanned as a texture again.
+pub fn rename_to_orig(path: &Path) -> Result<PathBuf, Error> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::other(format!("{:?} has no file name to rename", path)))?
+        .to_string_lossy()
+        .to_string();
+    let destination = std::iter::once(format!("{}.{}", file_name, ORIG_EXTENSION))
+        .chain((2..).map(|n| format!("{}.{}.{}", file_name, n, ORIG_EXTENSION)))
+        .map(|name| path.with_file_name(name))
+        .find(|candidate| !candidate.exists())
+        .unwrap();
+    fs::rename(path, &destination)?;
+
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod rename_to_orig_tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_previous_originals() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trash")
+            .join("test_run_rename_to_orig");
+        fs::create_dir_all(&dir)?;
+
+        fs::write(dir.join("wood.jpg"), "first")?;
+        let first = rename_to_orig(&dir.join("wood.jpg"));
+        fs::write(dir.join("wood.jpg"), "second")?;
+        let second = rename_to_orig(&dir.join("wood.jpg"));
+        let contents = (
+            fs::read_to_string(dir.join("wood.jpg.orig")),
+            fs::read_to_string(dir.join("wood.jpg.2.orig")),
+        );
+        let original_left = dir.join("wood.jpg").exists();
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(first?, dir.join("wood.jpg.orig"));
+        assert_eq!(second?, dir.join("wood.jpg.2.orig"));
+        assert_eq!(contents.0?, "first");
+        assert_eq!(contents.1?, "second");
+        assert!(!original_left);
+        Ok(())
+    }
+}
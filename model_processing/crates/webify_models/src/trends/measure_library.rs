@@ -0,0 +1,82 @@
+//! Measure the library once a run is over
+
+use std::{
+    fs,
+    io::Error,
+    path::Path,
+    result::Result,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::model::find_model_dirs;
+use crate::report::RunReport;
+use crate::trends::TrendPoint;
+use crate::walk::walk_dir;
+
+/// Metrics of the library in `dir` as the run left it, with the failures and
+/// warnings of its report. Files of models nested in another are counted once.
+pub fn measure_library(dir: &Path, report: &RunReport) -> Result<TrendPoint, Error> {
+    let model_dirs = find_model_dirs(dir)?;
+    let mut library_bytes = 0;
+    for model_dir in model_dirs.iter().filter(|model_dir| {
+        !model_dirs
+            .iter()
+            .any(|other| other != *model_dir && model_dir.starts_with(other))
+    }) {
+        for file in walk_dir(model_dir, &|_| true)? {
+            library_bytes += fs::metadata(&file)?.len();
+        }
+    }
+
+    Ok(TrendPoint {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        models: model_dirs.len(),
+        library_bytes,
+        average_model_bytes: library_bytes
+            .checked_div(model_dirs.len() as u64)
+            .unwrap_or(0),
+        failures: report.failures.len(),
+        warnings: report.warnings.len(),
+    })
+}
+
+#[cfg(test)]
+mod measure_library_tests {
+    use super::*;
+
+    use crate::report::{ErrorCode, Failure};
+
+    #[test]
+    fn it_measures_the_models_of_the_library() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trends")
+            .join("test_run_measure_library");
+        for model in ["crate", "desk"].iter() {
+            fs::create_dir_all(dir.join(model).join("meshes"))?;
+            fs::write(dir.join(model).join("model.config"), "<model/>")?;
+        }
+        fs::write(dir.join("crate").join("meshes").join("crate.dae"), [0; 92])?;
+        fs::write(dir.join("README.md"), [0; 1000])?;
+        let report = RunReport {
+            failures: vec![Failure::new(
+                ErrorCode::Decode,
+                "PNG Conversion",
+                dir.join("desk"),
+                "broken",
+            )],
+            ..RunReport::default()
+        };
+
+        let point = measure_library(&dir, &report)?;
+        assert_eq!(point.models, 2);
+        assert_eq!(point.library_bytes, 8 + 92 + 8);
+        assert_eq!(point.average_model_bytes, 54);
+        assert_eq!(point.failures, 1);
+        assert_eq!(point.warnings, 0);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
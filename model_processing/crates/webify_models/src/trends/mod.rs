@@ -0,0 +1,18 @@
+//! Track how the library grows across runs: its size, the weight of its models and
+//! how many of them fail, recorded after every run and charted over time
+
+mod measure_library;
+mod print_trends;
+mod process;
+mod read_trends;
+mod record_trend;
+mod trend_point;
+mod trend_series;
+
+pub use self::measure_library::measure_library;
+pub use self::print_trends::print_trends;
+pub use self::process::process;
+pub use self::read_trends::read_trends;
+pub use self::record_trend::{record_trend, TRENDS_FILE_NAME};
+pub use self::trend_point::TrendPoint;
+pub use self::trend_series::TrendSeries;
@@ -0,0 +1,76 @@
+//! Chart what `webify_models trends` read, in the terminal
+
+use std::{
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use console::style;
+
+use crate::report::format_bytes;
+use crate::trash::format_utc_date;
+use crate::trends::TrendPoint;
+
+/// Width of the longest bar, in characters
+const BAR_WIDTH: u64 = 40;
+
+/// Chart the library size, the average model weight and the failures of each
+/// recorded run as horizontal bars, oldest run first
+pub fn print_trends(log: &Path, points: &[TrendPoint]) {
+    println!(
+        "\n{} ({})",
+        style("Trends").bold(),
+        style(log.to_string_lossy()).dim()
+    );
+    if points.is_empty() {
+        println!("  No run recorded.");
+        return;
+    }
+
+    print_chart(
+        "Models",
+        points,
+        |point| point.models as u64,
+        |models| models.to_string(),
+    );
+    print_chart(
+        "Library size",
+        points,
+        |point| point.library_bytes,
+        format_bytes,
+    );
+    print_chart(
+        "Average model weight",
+        points,
+        |point| point.average_model_bytes,
+        format_bytes,
+    );
+    print_chart(
+        "Failures",
+        points,
+        |point| point.failures as u64,
+        |failures| failures.to_string(),
+    );
+}
+
+/// One bar per run, scaled to the largest value of the metric
+fn print_chart(
+    title: &str,
+    points: &[TrendPoint],
+    metric: impl Fn(&TrendPoint) -> u64,
+    format: impl Fn(u64) -> String,
+) {
+    println!("\n  {}", style(title).bold());
+    let max = points.iter().map(&metric).max().unwrap_or(0);
+    for point in points {
+        let value = metric(point);
+        let width = (value * BAR_WIDTH).checked_div(max).unwrap_or(0) as usize;
+        println!(
+            "  {} UTC {:<width$} {}",
+            format_utc_date(UNIX_EPOCH + Duration::from_secs(point.time)),
+            "█".repeat(width),
+            format(value),
+            width = BAR_WIDTH as usize
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Chart how the library grew across the runs recorded with `--record-trends`
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use console::style;
+
+use crate::trends::{print_trends, read_trends, TrendPoint, TrendSeries, TRENDS_FILE_NAME};
+
+/// Read the trends log of the library and chart it, writing the metrics as JSON
+/// series to `json` when given
+pub fn process(library: &Path, json: Option<&Path>) -> Result<Vec<TrendPoint>, Error> {
+    let log = library.join(TRENDS_FILE_NAME);
+    if !log.is_file() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "No trends log in {:?}, runs record one with --record-trends.",
+                library
+            ),
+        ));
+    }
+    let points = read_trends(&log)?;
+    print_trends(&log, &points);
+
+    if let Some(json_path) = json {
+        let contents =
+            serde_json::to_string_pretty(&TrendSeries::new(&points)).map_err(Error::other)?;
+        fs::write(json_path, contents)?;
+        println!(
+            "\nJSON series written to {}",
+            style(json_path.to_string_lossy()).dim()
+        );
+    }
+
+    Ok(points)
+}
+
+#[cfg(test)]
+mod process_tests {
+    use super::*;
+
+    use crate::trends::record_trend;
+
+    #[test]
+    fn it_writes_the_metrics_as_series() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trends")
+            .join("test_run_trends_process");
+        fs::create_dir_all(&dir)?;
+        assert_eq!(process(&dir, None).unwrap_err().kind(), ErrorKind::NotFound);
+        for (time, library_bytes, failures) in [(100, 4096, 3), (200, 6144, 1)].iter() {
+            record_trend(
+                &dir,
+                &TrendPoint {
+                    time: *time,
+                    models: 2,
+                    library_bytes: *library_bytes,
+                    average_model_bytes: library_bytes / 2,
+                    failures: *failures,
+                    warnings: 0,
+                },
+            )?;
+        }
+
+        let points = process(&dir, Some(&dir.join("trends.json")))?;
+        assert_eq!(points.len(), 2);
+        let series: TrendSeries =
+            serde_json::from_str(&fs::read_to_string(dir.join("trends.json"))?)?;
+        assert_eq!(series.time, vec![100, 200]);
+        assert_eq!(series.average_model_bytes, vec![2048, 3072]);
+        assert_eq!(series.failures, vec![3, 1]);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! Read the points of a trends log
+
+use std::{fs, io::Error, path::Path, result::Result};
+
+use crate::trends::TrendPoint;
+
+/// Every point of the trends log, in the order the runs recorded them
+pub fn read_trends(path: &Path) -> Result<Vec<TrendPoint>, Error> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::other(format!(
+                    "Invalid point on line {} of {:?}: {}",
+                    index + 1,
+                    path,
+                    e
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod read_trends_tests {
+    use super::*;
+
+    use crate::trends::{record_trend, TRENDS_FILE_NAME};
+
+    #[test]
+    fn it_reads_back_the_recorded_points() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("trends")
+            .join("test_run_read_trends");
+        fs::create_dir_all(&dir)?;
+        let points: Vec<TrendPoint> = (1..=3)
+            .map(|run| TrendPoint {
+                time: 1_700_000_000 + run * 86_400,
+                models: run as usize * 10,
+                ..TrendPoint::default()
+            })
+            .collect();
+        for point in &points {
+            record_trend(&dir, point)?;
+        }
+
+        assert_eq!(read_trends(&dir.join(TRENDS_FILE_NAME))?, points);
+        fs::write(dir.join("broken.ndjson"), "{\"time\": 1}\nnot json\n")?;
+        assert!(read_trends(&dir.join("broken.ndjson")).is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
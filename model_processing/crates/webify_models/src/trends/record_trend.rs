@@ -0,0 +1,25 @@
+//! Append the metrics of a run to the trends log of the library
+
+use std::{
+    fs::OpenOptions,
+    io::{Error, Write},
+    path::Path,
+    result::Result,
+};
+
+use crate::trends::TrendPoint;
+
+/// Name of the trends log, at the root of the library
+pub const TRENDS_FILE_NAME: &str = "webify-trends.ndjson";
+
+/// Append the point to the trends log of the library in `dir`, one JSON object per
+/// line (NDJSON), creating the log on the first run
+pub fn record_trend(dir: &Path, point: &TrendPoint) -> Result<(), Error> {
+    let mut line = serde_json::to_string(point).map_err(Error::other)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(TRENDS_FILE_NAME))?
+        .write_all(line.as_bytes())
+}
@@ -0,0 +1,21 @@
+//! Metrics of the library after a run
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Size of the library and how the run went, one line of the trends log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TrendPoint {
+    /// When the run ended, in seconds since the Unix epoch
+    pub time: u64,
+    /// Number of models, directories with a `model.config`
+    pub models: usize,
+    /// Size on disk of every file of the models, in bytes
+    pub library_bytes: u64,
+    /// Average size on disk of a model, in bytes
+    pub average_model_bytes: u64,
+    /// Files the run failed to process
+    pub failures: usize,
+    /// Problems the run noticed without failing on them
+    pub warnings: usize,
+}
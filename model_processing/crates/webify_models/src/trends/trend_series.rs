@@ -0,0 +1,35 @@
+//! Metrics of the trends log as one series per metric, for charting elsewhere
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::trends::TrendPoint;
+
+/// Each metric of the recorded runs as a series, oldest run first, the values at
+/// the same index coming from the same run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TrendSeries {
+    /// When each run ended, in seconds since the Unix epoch
+    pub time: Vec<u64>,
+    pub models: Vec<usize>,
+    pub library_bytes: Vec<u64>,
+    pub average_model_bytes: Vec<u64>,
+    pub failures: Vec<usize>,
+    pub warnings: Vec<usize>,
+}
+
+impl TrendSeries {
+    pub fn new(points: &[TrendPoint]) -> TrendSeries {
+        TrendSeries {
+            time: points.iter().map(|point| point.time).collect(),
+            models: points.iter().map(|point| point.models).collect(),
+            library_bytes: points.iter().map(|point| point.library_bytes).collect(),
+            average_model_bytes: points
+                .iter()
+                .map(|point| point.average_model_bytes)
+                .collect(),
+            failures: points.iter().map(|point| point.failures).collect(),
+            warnings: points.iter().map(|point| point.warnings).collect(),
+        }
+    }
+}
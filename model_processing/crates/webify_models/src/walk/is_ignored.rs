@@ -0,0 +1,29 @@
+//! Entries every scan skips
+
+use std::path::Path;
+
+/// Whether the walk skips the entry: hidden files and directories (`.git`,
+/// `.DS_Store`, ...) and the temporary copies webify_models writes while editing
+pub fn is_ignored(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return false,
+    };
+
+    name.starts_with('.') || name.ends_with(".webify.tmp")
+}
+
+#[cfg(test)]
+mod is_ignored_tests {
+    use super::*;
+
+    #[test]
+    fn it_ignores_hidden_and_temporary_entries() {
+        assert!(is_ignored(Path::new("models/.git")));
+        assert!(is_ignored(Path::new("models/lamp/.DS_Store")));
+        assert!(is_ignored(Path::new("models/world.sdf.webify.tmp")));
+
+        assert!(!is_ignored(Path::new("models/lamp/model.config")));
+        assert!(!is_ignored(Path::new("../models")));
+    }
+}
@@ -0,0 +1,11 @@
+//! Walk directory trees in parallel, for libraries with hundreds of thousands of files
+
+mod is_ignored;
+mod prioritize;
+mod walk_dir;
+mod walk_progress;
+
+pub use self::is_ignored::is_ignored;
+pub use self::prioritize::prioritize;
+pub use self::walk_dir::{walk_dir, walk_dir_with_progress};
+pub use self::walk_progress::WalkProgress;
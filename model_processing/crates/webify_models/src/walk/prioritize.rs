@@ -0,0 +1,44 @@
+//! Put the files of some directories ahead of the rest
+
+use std::path::{Path, PathBuf};
+
+/// Move the items under one of the `first` directories, relative to `dir`, to the
+/// front, in the order the directories are listed. The order is kept otherwise, so
+/// walks stay in path order within each group.
+pub fn prioritize<T>(items: &mut [T], dir: &Path, first: &[PathBuf], path: impl Fn(&T) -> &Path) {
+    if first.is_empty() {
+        return;
+    }
+
+    items.sort_by_cached_key(|item| {
+        let path = path(item);
+        let relative = path.strip_prefix(dir).unwrap_or(path);
+        first
+            .iter()
+            .position(|first| relative.starts_with(first))
+            .unwrap_or(first.len())
+    });
+}
+
+#[cfg(test)]
+mod prioritize_tests {
+    use super::*;
+
+    #[test]
+    fn it_moves_the_listed_directories_first() {
+        let dir = Path::new("models");
+        let mut files: Vec<PathBuf> = ["chair/a.png", "desk/b.png", "lamp/c.png", "lamp/d.png"]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect();
+        let first = vec![PathBuf::from("lamp"), PathBuf::from("desk")];
+
+        prioritize(&mut files, dir, &first, |file| file.as_path());
+
+        let expected: Vec<PathBuf> = ["lamp/c.png", "lamp/d.png", "desk/b.png", "chair/a.png"]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect();
+        assert_eq!(files, expected);
+    }
+}
@@ -0,0 +1,127 @@
+//! Recursively list the files of a directory, walking subdirectories in parallel
+
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use rayon::prelude::*;
+
+use crate::walk::{is_ignored, WalkProgress};
+
+/// Every file under `dir` that `keep` accepts, sorted by path whatever order the
+/// threads finished in. Subdirectories are walked in parallel and the ignore rules
+/// are applied by each thread as it reads a directory, so ignored trees are never
+/// entered.
+pub fn walk_dir(dir: &Path, keep: &(impl Fn(&Path) -> bool + Sync)) -> Result<Vec<PathBuf>, Error> {
+    walk_dir_with_progress(dir, keep, &WalkProgress::new())
+}
+
+/// Same as `walk_dir`, counting the directories read and the files kept in
+/// `progress` as the threads go
+pub fn walk_dir_with_progress(
+    dir: &Path,
+    keep: &(impl Fn(&Path) -> bool + Sync),
+    progress: &WalkProgress,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut files = walk(dir, keep, progress)?;
+    files.sort();
+
+    Ok(files)
+}
+
+/// Walk one directory, fanning its subdirectories out to the thread pool
+fn walk(
+    dir: &Path,
+    keep: &(impl Fn(&Path) -> bool + Sync),
+    progress: &WalkProgress,
+) -> Result<Vec<PathBuf>, Error> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    progress.add_directory();
+
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_ignored(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if keep(&path) {
+            progress.add_file(fs::metadata(&path).map_or(0, |metadata| metadata.len()));
+            files.push(path);
+        }
+    }
+
+    let nested: Vec<Vec<PathBuf>> = subdirs
+        .par_iter()
+        .map(|subdir| walk(subdir, keep, progress))
+        .collect::<Result<_, Error>>()?;
+    files.extend(nested.into_iter().flatten());
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod walk_dir_tests {
+    use super::*;
+
+    #[test]
+    fn it_walks_the_tree_in_path_order() -> Result<(), Error> {
+        let dir = Path::new("tests").join("model").join("collection");
+        let files = walk_dir(&dir, &|path: &Path| {
+            path.file_name().is_some_and(|name| name == "model.config")
+        })?;
+
+        assert_eq!(
+            files,
+            vec![
+                dir.join("desk").join("model.config"),
+                dir.join("lamp").join("model.config"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_what_it_walks() -> Result<(), Error> {
+        let dir = Path::new("tests").join("model").join("collection");
+        let progress = WalkProgress::new();
+        let files = walk_dir_with_progress(
+            &dir,
+            &|path: &Path| path.file_name().is_some_and(|name| name == "model.config"),
+            &progress,
+        )?;
+
+        let bytes: u64 = files.iter().map(|f| fs::metadata(f).unwrap().len()).sum();
+        assert_eq!(progress.files(), 2);
+        assert_eq!(progress.bytes(), bytes);
+        assert!(progress.directories() >= 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_ignored_entries() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("walk")
+            .join("test_run_walk_dir_ignored");
+        fs::create_dir_all(dir.join(".git"))?;
+        fs::write(dir.join(".git").join("texture.png"), "")?;
+        fs::write(dir.join("texture.png"), "")?;
+
+        assert_eq!(
+            walk_dir(&dir, &|_: &Path| true)?,
+            vec![dir.join("texture.png")]
+        );
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
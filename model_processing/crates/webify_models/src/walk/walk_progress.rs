@@ -0,0 +1,44 @@
+//! Counters updated while a directory tree is walked, readable from another thread
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters updated by every walking thread, so a scan of a huge library can show
+/// live progress
+#[derive(Debug, Default)]
+pub struct WalkProgress {
+    directories: AtomicU64,
+    files: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl WalkProgress {
+    pub fn new() -> WalkProgress {
+        WalkProgress::default()
+    }
+
+    /// Record a directory that was read
+    pub fn add_directory(&self) {
+        self.directories.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a file that was kept, with its size
+    pub fn add_file(&self, bytes: u64) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Directories read so far
+    pub fn directories(&self) -> u64 {
+        self.directories.load(Ordering::Relaxed)
+    }
+
+    /// Files kept so far
+    pub fn files(&self) -> u64 {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    /// Total size of the files kept so far, in bytes
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,81 @@
+//! Compose the scene of a world out of its models
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::impostor::{read_model_shapes, visual_triangles, ShapeKind, Triangle};
+use crate::world::{read_world, visit_world_models};
+
+/// Triangles of the shapes of the kind of every model of the world, in the frame
+/// of the world: the models declared in it and the ones `<include>`d from the
+/// library through `model://` URIs, posed by their include, along with the
+/// models these include in turn. Includes of models missing from `models`, or
+/// without an SDF, are left out, like the meshes that can't be found.
+pub fn compose_world(
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    kind: ShapeKind,
+) -> Result<Vec<Triangle>, Error> {
+    let world_element = read_world(world)?;
+
+    // Triangles of each mesh loaded so far, in the frame of the mesh
+    let mut meshes = HashMap::new();
+    let mut triangles = Vec::new();
+    visit_world_models(&world_element, world, models, &mut |model, file, frame| {
+        let mut shapes = Vec::new();
+        read_model_shapes(model, frame, kind, &mut shapes);
+        for shape in &shapes {
+            triangles.extend(visual_triangles(shape, file, models, &mut meshes)?);
+        }
+        Ok(())
+    })?;
+
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod compose_world_tests {
+    use super::*;
+
+    use crate::dependencies::index_models;
+
+    #[test]
+    fn it_poses_the_included_models() -> Result<(), Error> {
+        let library = Path::new("tests").join("world");
+        let world = library.join("worlds").join("apartment.world");
+        let models = index_models(&library)?;
+
+        let triangles = compose_world(&world, &models, ShapeKind::Collision)?;
+
+        // The ground's 2, the wall's and the table's 12, the sofa is missing
+        assert_eq!(triangles.len(), 26);
+        let vertices: Vec<[f64; 3]> = triangles[2..14]
+            .iter()
+            .flat_map(|triangle| triangle.vertices)
+            .collect();
+        // The wall stands where it's included rather than at its own pose
+        assert!(vertices.iter().all(|v| (v[0].abs() - 0.1).abs() < 1e-9));
+        assert!(vertices.iter().any(|v| (v[2] - 2.5).abs() < 1e-9));
+        assert!(triangles[14..]
+            .iter()
+            .flat_map(|triangle| triangle.vertices)
+            .all(|v| (v[0] + 1.5).abs() <= 0.8 + 1e-9 && v[2] > 0.69));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_errors_on_files_without_a_world() {
+        let sdf = Path::new("tests")
+            .join("world")
+            .join("models")
+            .join("wall")
+            .join("model.sdf");
+
+        assert!(compose_world(&sdf, &BTreeMap::new(), ShapeKind::Visual).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! List every world under a directory
+
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::walk::walk_dir;
+
+/// Recursively list the `.world` files under `dir`, in path order
+pub fn find_worlds(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    walk_dir(dir, &|path: &Path| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("world"))
+    })
+}
+
+#[cfg(test)]
+mod find_worlds_tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_worlds_of_a_library() -> Result<(), Error> {
+        let dir = Path::new("tests").join("world");
+
+        assert_eq!(
+            find_worlds(&dir)?,
+            vec![dir.join("worlds").join("apartment.world")]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+//! Worlds: the scenes SDF `.world` files compose out of the models of the library
+
+mod compose_world;
+mod find_worlds;
+mod read_world;
+mod read_world_actors;
+mod read_world_lights;
+mod read_world_scene;
+mod read_world_viewpoints;
+mod visit_world_models;
+mod world_actor;
+mod world_light;
+mod world_scene;
+mod world_viewpoint;
+
+pub use self::compose_world::compose_world;
+pub use self::find_worlds::find_worlds;
+pub use self::read_world::read_world;
+pub use self::read_world_actors::read_world_actors;
+pub use self::read_world_lights::read_world_lights;
+pub use self::read_world_scene::read_world_scene;
+pub use self::read_world_viewpoints::read_world_viewpoints;
+pub use self::visit_world_models::visit_world_models;
+pub use self::world_actor::{ActorAnimation, ActorTrajectory, ActorWaypoint, WorldActor};
+pub use self::world_light::{LightKind, WorldLight};
+pub use self::world_scene::WorldScene;
+pub use self::world_viewpoint::WorldViewpoint;
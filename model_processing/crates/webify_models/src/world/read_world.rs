@@ -0,0 +1,31 @@
+//! Read the `<world>` element of a world file
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    result::Result,
+};
+
+use crate::xml::XmlElement;
+
+/// The `<world>` element of the file, either its root or a child of its `<sdf>`
+pub fn read_world(world: &Path) -> Result<XmlElement, Error> {
+    let mut root = XmlElement::parse(&fs::read_to_string(world)?)
+        .map_err(|e| Error::other(format!("Failed to parse {:?}: {:?}", world, e)))?;
+    if root.name == "world" {
+        return Ok(root);
+    }
+
+    let index = root
+        .children
+        .iter()
+        .position(|child| child.name == "world")
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?} has no <world> element", world),
+            )
+        })?;
+    Ok(root.children.swap_remove(index))
+}
@@ -0,0 +1,191 @@
+//! Read the animated actors of a world
+
+use crate::impostor::sdf_pose;
+use crate::world::{ActorAnimation, ActorTrajectory, ActorWaypoint, WorldActor};
+use crate::xml::XmlElement;
+
+/// Actors of the `<world>` element, with SDF's defaults for what they leave out.
+/// Trajectory waypoints are posed in the frame of the world, as Gazebo plays them,
+/// and sorted by time. Skins and animations are kept as the world references them,
+/// like the viewer's loader expects `model://` URIs.
+pub fn read_world_actors(world_element: &XmlElement) -> Vec<WorldActor> {
+    world_element
+        .children_named("actor")
+        .map(read_actor)
+        .collect()
+}
+
+fn read_actor(actor: &XmlElement) -> WorldActor {
+    let pose = sdf_pose(actor);
+    let script = actor.child("script");
+    let script_flag = |name: &str, default: bool| {
+        script
+            .and_then(|script| script.child(name))
+            .map_or(default, flag)
+    };
+
+    WorldActor {
+        name: actor.attribute("name").unwrap_or_default().to_string(),
+        skin: actor
+            .find(&["skin", "filename"])
+            .map(|filename| filename.text.trim().to_string()),
+        skin_scale: actor
+            .find(&["skin", "scale"])
+            .map_or(1.0, |scale| number(scale, 1.0)),
+        position: pose.apply([0.0; 3]),
+        rotation: pose.quaternion(),
+        animations: actor
+            .children_named("animation")
+            .map(|animation| ActorAnimation {
+                name: animation.attribute("name").unwrap_or_default().to_string(),
+                filename: animation
+                    .child("filename")
+                    .map(|filename| filename.text.trim().to_string())
+                    .unwrap_or_default(),
+                scale: animation
+                    .child("scale")
+                    .map_or(1.0, |scale| number(scale, 1.0)),
+                interpolate_x: animation.child("interpolate_x").is_some_and(flag),
+            })
+            .collect(),
+        looping: script_flag("loop", true),
+        delay_start: script
+            .and_then(|script| script.child("delay_start"))
+            .map_or(0.0, |delay| number(delay, 0.0)),
+        auto_start: script_flag("auto_start", true),
+        trajectories: script
+            .map(|script| {
+                script
+                    .children_named("trajectory")
+                    .map(read_trajectory)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn read_trajectory(trajectory: &XmlElement) -> ActorTrajectory {
+    let mut waypoints: Vec<ActorWaypoint> = trajectory
+        .children_named("waypoint")
+        .map(|waypoint| {
+            let pose = sdf_pose(waypoint);
+            ActorWaypoint {
+                time: waypoint.child("time").map_or(0.0, |time| number(time, 0.0)),
+                position: pose.apply([0.0; 3]),
+                rotation: pose.quaternion(),
+            }
+        })
+        .collect();
+    waypoints.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    ActorTrajectory {
+        id: trajectory
+            .attribute("id")
+            .and_then(|id| id.trim().parse().ok())
+            .unwrap_or_default(),
+        animation: trajectory.attribute("type").unwrap_or_default().to_string(),
+        tension: trajectory
+            .attribute("tension")
+            .and_then(|tension| tension.trim().parse().ok())
+            .unwrap_or(0.0),
+        waypoints,
+    }
+}
+
+/// First number of the element's text, or `default`
+fn number(element: &XmlElement, default: f64) -> f64 {
+    element.numbers().first().copied().unwrap_or(default)
+}
+
+/// SDF boolean of the element's text, `true` or `1`
+fn flag(element: &XmlElement) -> bool {
+    matches!(element.text.trim(), "true" | "1")
+}
+
+#[cfg(test)]
+mod read_world_actors_tests {
+    use super::*;
+
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn it_reads_the_skin_animations_and_script_of_actors() {
+        let world = XmlElement::parse(&format!(
+            r#"<world name="lobby">
+                <actor name="visitor">
+                    <pose>1 2 0 0 0 0</pose>
+                    <skin><filename>model://visitor/meshes/walk.dae</filename><scale>0.5</scale></skin>
+                    <animation name="walking">
+                        <filename>model://visitor/meshes/walk.dae</filename>
+                        <interpolate_x>true</interpolate_x>
+                    </animation>
+                    <script>
+                        <loop>false</loop>
+                        <delay_start>2.5</delay_start>
+                        <trajectory id="3" type="walking" tension="0.6">
+                            <waypoint><time>4</time><pose>4 0 1 0 0 {}</pose></waypoint>
+                            <waypoint><time>0</time><pose>0 0 1 0 0 0</pose></waypoint>
+                        </trajectory>
+                    </script>
+                </actor>
+                <actor name="statue"/>
+            </world>"#,
+            FRAC_PI_2
+        ))
+        .unwrap();
+
+        let actors = read_world_actors(&world);
+
+        assert_eq!(actors.len(), 2);
+        let visitor = &actors[0];
+        assert_eq!(visitor.name, "visitor");
+        assert_eq!(
+            visitor.skin.as_deref(),
+            Some("model://visitor/meshes/walk.dae")
+        );
+        assert_eq!(visitor.skin_scale, 0.5);
+        assert_close(&visitor.position, &[1.0, 2.0, 0.0]);
+        assert_close(&visitor.rotation, &[0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(
+            visitor.animations,
+            vec![ActorAnimation {
+                name: String::from("walking"),
+                filename: String::from("model://visitor/meshes/walk.dae"),
+                scale: 1.0,
+                interpolate_x: true,
+            }]
+        );
+        assert!(!visitor.looping);
+        assert_eq!(visitor.delay_start, 2.5);
+        assert!(visitor.auto_start);
+
+        let trajectory = &visitor.trajectories[0];
+        assert_eq!(trajectory.id, 3);
+        assert_eq!(trajectory.animation, "walking");
+        assert_eq!(trajectory.tension, 0.6);
+        let times: Vec<f64> = trajectory.waypoints.iter().map(|w| w.time).collect();
+        assert_eq!(times, vec![0.0, 4.0]);
+        assert_close(&trajectory.waypoints[1].position, &[4.0, 0.0, 1.0]);
+        assert_close(
+            &trajectory.waypoints[1].rotation,
+            &[0.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+        );
+
+        // SDF's defaults
+        let statue = &actors[1];
+        assert_eq!(statue.skin, None);
+        assert_eq!(statue.skin_scale, 1.0);
+        assert!(statue.looping);
+        assert!(statue.auto_start);
+        assert!(statue.trajectories.is_empty());
+    }
+}
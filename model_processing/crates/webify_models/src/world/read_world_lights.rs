@@ -0,0 +1,222 @@
+//! Read the lights of a world and convert them to the units of the web viewer
+
+use std::{
+    collections::BTreeMap,
+    f64::consts::{FRAC_PI_2, PI},
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::impostor::{sdf_pose, Transform};
+use crate::world::{visit_world_models, LightKind, WorldLight};
+use crate::xml::XmlElement;
+
+/// Lights of the `<world>` element, read from `world`, in the frame of the world:
+/// the ones declared in it and the ones of the links of its models, `<include>`d
+/// ones included. Lights of an unknown type are left out.
+///
+/// SDF lights have no physical units, Gazebo multiplies the diffuse color of
+/// surfaces by the diffuse color and intensity of the light. A directional light
+/// of brightness 1 becomes π lux, which lights a white surface facing it the same
+/// in the viewer, whose diffuse shading divides by π. Point and spot lights are
+/// matched the same way at one meter, through their attenuation there.
+pub fn read_world_lights(
+    world_element: &XmlElement,
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+) -> Result<Vec<WorldLight>, Error> {
+    let mut lights: Vec<WorldLight> = world_element
+        .children_named("light")
+        .filter_map(|light| read_light(light, Transform::identity()))
+        .collect();
+    visit_world_models(world_element, world, models, &mut |model, _, frame| {
+        read_model_lights(model, frame, &mut lights);
+        Ok(())
+    })?;
+
+    Ok(lights)
+}
+
+/// Read the lights of the model, of its links and of its nested models
+fn read_model_lights(model: &XmlElement, frame: Transform, lights: &mut Vec<WorldLight>) {
+    lights.extend(
+        model
+            .children_named("light")
+            .filter_map(|light| read_light(light, frame)),
+    );
+    for link in model.children_named("link") {
+        let link_frame = frame * sdf_pose(link);
+        lights.extend(
+            link.children_named("light")
+                .filter_map(|light| read_light(light, link_frame)),
+        );
+    }
+    for nested in model.children_named("model") {
+        read_model_lights(nested, frame * sdf_pose(nested), lights);
+    }
+}
+
+/// Light of a `<light>` posed in `frame`, with SDF's defaults for what it leaves out
+fn read_light(light: &XmlElement, frame: Transform) -> Option<WorldLight> {
+    let kind = match light.attribute("type")? {
+        "point" => LightKind::Point,
+        "spot" => LightKind::Spot,
+        "directional" => LightKind::Directional,
+        _ => return None,
+    };
+    let number = |path: &[&str], default: f64| {
+        light
+            .find(path)
+            .and_then(|element| element.numbers::<f64>().first().copied())
+            .unwrap_or(default)
+    };
+    let vector = |path: &[&str], default: [f64; 3]| match light.find(path).map(|e| e.numbers()) {
+        Some(values) if values.len() >= 3 => [values[0], values[1], values[2]],
+        _ => default,
+    };
+
+    let pose = frame * sdf_pose(light);
+    let position = pose.apply([0.0; 3]);
+
+    let diffuse = vector(&["diffuse"], [1.0; 3]);
+    let brightest = diffuse.iter().copied().fold(0.0, f64::max);
+    let (color, brightness) = if brightest > 0.0 {
+        let color = [
+            diffuse[0] / brightest,
+            diffuse[1] / brightest,
+            diffuse[2] / brightest,
+        ];
+        (color, brightest * number(&["intensity"], 1.0))
+    } else {
+        ([1.0; 3], 0.0)
+    };
+
+    let (intensity, range) = match kind {
+        LightKind::Directional => (PI * brightness, None),
+        LightKind::Point | LightKind::Spot => {
+            let at_one_meter = number(&["attenuation", "constant"], 1.0)
+                + number(&["attenuation", "linear"], 1.0)
+                + number(&["attenuation", "quadratic"], 0.0);
+            let intensity = if at_one_meter > 0.0 {
+                PI * brightness / at_one_meter
+            } else {
+                PI * brightness
+            };
+            (intensity, Some(number(&["attenuation", "range"], 10.0)))
+        }
+    };
+
+    let direction = match kind {
+        LightKind::Point => None,
+        LightKind::Spot | LightKind::Directional => {
+            let tip = pose.apply(vector(&["direction"], [0.0, 0.0, -1.0]));
+            normalize([
+                tip[0] - position[0],
+                tip[1] - position[1],
+                tip[2] - position[2],
+            ])
+        }
+    };
+
+    // SDF's angles are of the whole cone, glTF's from its axis
+    let (inner_cone_angle, outer_cone_angle) = match kind {
+        LightKind::Spot => {
+            let outer = (number(&["spot", "outer_angle"], 0.0) / 2.0).clamp(0.0, FRAC_PI_2);
+            let inner = (number(&["spot", "inner_angle"], 0.0) / 2.0).clamp(0.0, outer);
+            (Some(inner), Some(outer))
+        }
+        LightKind::Point | LightKind::Directional => (None, None),
+    };
+
+    Some(WorldLight {
+        name: light.attribute("name").unwrap_or_default().to_string(),
+        kind,
+        color,
+        intensity,
+        position,
+        direction,
+        range,
+        inner_cone_angle,
+        outer_cone_angle,
+        cast_shadows: light
+            .child("cast_shadows")
+            .is_some_and(|cast_shadows| cast_shadows.text.trim() == "true"),
+    })
+}
+
+/// Unit vector along `v`, `None` for the zero vector
+fn normalize(v: [f64; 3]) -> Option<[f64; 3]> {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length > 0.0 {
+        Some([v[0] / length, v[1] / length, v[2] / length])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod read_world_lights_tests {
+    use super::*;
+
+    use crate::dependencies::index_models;
+    use crate::world::read_world;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn it_converts_the_lights_of_the_world_and_its_models() -> Result<(), Error> {
+        let library = Path::new("tests").join("world");
+        let world = library.join("worlds").join("apartment.world");
+        let models = index_models(&library)?;
+
+        let lights = read_world_lights(&read_world(&world)?, &world, &models)?;
+
+        assert_eq!(lights.len(), 2);
+        let sun = &lights[0];
+        assert_eq!(
+            (sun.name.as_str(), sun.kind),
+            ("sun", LightKind::Directional)
+        );
+        assert_close(&sun.color, &[1.0, 1.0, 1.0]);
+        assert_close(&[sun.intensity], &[0.8 * PI]);
+        let length = (0.25f64 + 0.01 + 0.81).sqrt();
+        assert_close(
+            &sun.direction.unwrap(),
+            &[-0.5 / length, 0.1 / length, -0.9 / length],
+        );
+        assert_eq!(sun.range, None);
+        assert!(sun.cast_shadows);
+
+        // Posed by the link and the include of the table
+        let lamp = &lights[1];
+        assert_eq!((lamp.name.as_str(), lamp.kind), ("lamp", LightKind::Spot));
+        assert_close(&lamp.position, &[-1.5, 0.0, 1.925]);
+        assert_close(&lamp.direction.unwrap(), &[0.0, 0.0, -1.0]);
+        assert_close(&lamp.color, &[1.0, 0.5, 0.25]);
+        assert_close(&[lamp.intensity], &[2.0 * PI]);
+        assert_eq!(lamp.range, Some(5.0));
+        assert_close(
+            &[
+                lamp.inner_cone_angle.unwrap(),
+                lamp.outer_cone_angle.unwrap(),
+            ],
+            &[0.25, 0.5],
+        );
+        assert!(!lamp.cast_shadows);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_out_lights_of_unknown_types() {
+        let light = XmlElement::parse(r#"<light name="sky" type="ambient"/>"#).unwrap();
+
+        assert_eq!(read_light(&light, Transform::identity()), None);
+    }
+}
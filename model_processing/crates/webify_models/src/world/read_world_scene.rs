@@ -0,0 +1,27 @@
+//! Read what the viewer needs to replicate the scene of a world
+
+use std::{
+    collections::BTreeMap,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::world::{
+    read_world, read_world_actors, read_world_lights, read_world_viewpoints, WorldScene,
+};
+
+/// Lights, viewpoints and actors of the world, see `read_world_lights`,
+/// `read_world_viewpoints` and `read_world_actors`
+pub fn read_world_scene(
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+) -> Result<WorldScene, Error> {
+    let world_element = read_world(world)?;
+
+    Ok(WorldScene {
+        lights: read_world_lights(&world_element, world, models)?,
+        viewpoints: read_world_viewpoints(&world_element),
+        actors: read_world_actors(&world_element),
+    })
+}
@@ -0,0 +1,88 @@
+//! Read the places a world is meant to be looked at from
+
+use crate::impostor::{sdf_pose, Transform};
+use crate::world::WorldViewpoint;
+use crate::xml::XmlElement;
+
+/// Viewpoints of the `<world>` element: the camera of its `<gui>`, which the
+/// world opens on, then its named `<frame>`s, which is how worlds mark places
+/// like the kitchen or the airlock. Cameras look along the X axis of their pose,
+/// Z up, and so do frames. Poses are taken relative to the world, `relative_to`
+/// is ignored.
+pub fn read_world_viewpoints(world_element: &XmlElement) -> Vec<WorldViewpoint> {
+    let cameras = world_element
+        .children_named("gui")
+        .flat_map(|gui| gui.children_named("camera"))
+        .map(|camera| viewpoint(camera, "camera", true));
+    let frames = world_element
+        .children_named("frame")
+        .filter(|frame| frame.attribute("name").is_some_and(|name| !name.is_empty()))
+        .map(|frame| viewpoint(frame, "", false));
+
+    cameras.chain(frames).collect()
+}
+
+/// Viewpoint of the element's name and pose, named `fallback` without a name
+fn viewpoint(element: &XmlElement, fallback: &str, default: bool) -> WorldViewpoint {
+    let pose = sdf_pose(element);
+    let position = pose.apply([0.0; 3]);
+
+    WorldViewpoint {
+        name: element.attribute("name").unwrap_or(fallback).to_string(),
+        default,
+        position,
+        direction: axis(&pose, position, [1.0, 0.0, 0.0]),
+        up: axis(&pose, position, [0.0, 0.0, 1.0]),
+    }
+}
+
+/// Axis of the pose's frame, the pose being rigid
+fn axis(pose: &Transform, origin: [f64; 3], axis: [f64; 3]) -> [f64; 3] {
+    let tip = pose.apply(axis);
+    [tip[0] - origin[0], tip[1] - origin[1], tip[2] - origin[2]]
+}
+
+#[cfg(test)]
+mod read_world_viewpoints_tests {
+    use super::*;
+
+    use std::f64::consts::FRAC_PI_2;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn it_reads_the_gui_camera_then_the_named_frames() {
+        let world = XmlElement::parse(&format!(
+            r#"<world name="station">
+                <frame name="airlock"><pose>4 0 1.6 0 0 {}</pose></frame>
+                <frame><pose>1 1 1 0 0 0</pose></frame>
+                <gui><camera name="user_camera"><pose>-5 0 3 0 0.5 0</pose></camera></gui>
+            </world>"#,
+            FRAC_PI_2
+        ))
+        .unwrap();
+
+        let viewpoints = read_world_viewpoints(&world);
+
+        assert_eq!(viewpoints.len(), 2);
+        assert_eq!(viewpoints[0].name, "user_camera");
+        assert!(viewpoints[0].default);
+        assert_close(viewpoints[0].position, [-5.0, 0.0, 3.0]);
+        // Pitched down by half a radian
+        assert_close(
+            viewpoints[0].direction,
+            [0.5f64.cos(), 0.0, -(0.5f64.sin())],
+        );
+        assert_close(viewpoints[0].up, [0.5f64.sin(), 0.0, 0.5f64.cos()]);
+
+        assert_eq!(viewpoints[1].name, "airlock");
+        assert!(!viewpoints[1].default);
+        assert_close(viewpoints[1].position, [4.0, 0.0, 1.6]);
+        assert_close(viewpoints[1].direction, [0.0, 1.0, 0.0]);
+        assert_close(viewpoints[1].up, [0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,95 @@
+//! Walk the models a world is composed of, wherever they're declared
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+    result::Result,
+};
+
+use crate::dependencies::model_uri_name;
+use crate::impostor::{sdf_pose, Transform};
+use crate::model::model_sdfs;
+use crate::xml::XmlElement;
+
+/// Deepest chain of `<include>`s followed, so models including themselves end
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Call `visit` with every model of the world, the file it's declared in and its
+/// frame in the world: the models declared in `world_element`, read from `world`,
+/// and the ones `<include>`d from the library through `model://` URIs, posed by
+/// their include, along with the models these include in turn. Nested models are
+/// left to `visit`. Includes of models missing from `models`, or without an SDF,
+/// are left out.
+pub fn visit_world_models<F>(
+    world_element: &XmlElement,
+    world: &Path,
+    models: &BTreeMap<String, PathBuf>,
+    visit: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(&XmlElement, &Path, Transform) -> Result<(), Error>,
+{
+    for model in world_element.children_named("model") {
+        visit_model(model, world, sdf_pose(model), models, visit, 0)?;
+    }
+    visit_includes(world_element, Transform::identity(), models, visit, 0)
+}
+
+/// Visit the model declared in `file`, posed by `frame`, and the models it includes
+fn visit_model<F>(
+    model: &XmlElement,
+    file: &Path,
+    frame: Transform,
+    models: &BTreeMap<String, PathBuf>,
+    visit: &mut F,
+    depth: usize,
+) -> Result<(), Error>
+where
+    F: FnMut(&XmlElement, &Path, Transform) -> Result<(), Error>,
+{
+    visit(model, file, frame)?;
+    visit_includes(model, frame, models, visit, depth)
+}
+
+/// Visit the models `parent` includes, their poses relative to `frame`
+fn visit_includes<F>(
+    parent: &XmlElement,
+    frame: Transform,
+    models: &BTreeMap<String, PathBuf>,
+    visit: &mut F,
+    depth: usize,
+) -> Result<(), Error>
+where
+    F: FnMut(&XmlElement, &Path, Transform) -> Result<(), Error>,
+{
+    if depth >= MAX_INCLUDE_DEPTH {
+        return Ok(());
+    }
+
+    for include in parent.children_named("include") {
+        let model_dir = include
+            .child("uri")
+            .and_then(|uri| model_uri_name(uri.text.trim()))
+            .and_then(|name| models.get(name));
+        let sdf = match model_dir {
+            Some(model_dir) => model_sdfs(model_dir)?.into_iter().next(),
+            None => None,
+        };
+        let sdf = match sdf {
+            Some(sdf) => sdf,
+            None => continue,
+        };
+
+        // The pose of the include replaces the one of the included model
+        let include_frame = frame * sdf_pose(include);
+        let root = XmlElement::parse(&fs::read_to_string(&sdf)?)
+            .map_err(|e| Error::other(format!("Failed to parse {:?}: {:?}", sdf, e)))?;
+        for model in root.children_named("model") {
+            visit_model(model, &sdf, include_frame, models, visit, depth + 1)?;
+        }
+    }
+
+    Ok(())
+}
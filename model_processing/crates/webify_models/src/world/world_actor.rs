@@ -0,0 +1,66 @@
+//! Animated actor of a world, for the viewer to play back
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// An SDF `<actor>`: a skinned mesh playing its animations along scripted
+/// trajectories. Poses are in the frame of the world with Z up, rotations unit
+/// quaternions ordered `[x, y, z, w]` as in glTF animations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldActor {
+    pub name: String,
+    /// Skinned mesh as the world references it, a COLLADA or BVH file, e.g.
+    /// `model://walker/meshes/walk.dae`
+    pub skin: Option<String>,
+    pub skin_scale: f64,
+    /// Position of the actor when it has no trajectory, or before its script
+    /// starts, in meters
+    pub position: [f64; 3],
+    pub rotation: [f64; 4],
+    /// Skeletal animations, which trajectories play by name
+    pub animations: Vec<ActorAnimation>,
+    /// Whether the script starts over once its last trajectory ends
+    #[serde(rename = "loop")]
+    pub looping: bool,
+    /// Time before the script starts, in seconds
+    pub delay_start: f64,
+    /// Whether the script starts with the world, rather than when triggered
+    pub auto_start: bool,
+    /// Trajectories of the script, played one after the other
+    pub trajectories: Vec<ActorTrajectory>,
+}
+
+/// A skeletal animation of an actor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ActorAnimation {
+    pub name: String,
+    /// File of the animation as the world references it, a COLLADA or BVH file
+    pub filename: String,
+    pub scale: f64,
+    /// Whether the animation is synced to the distance walked along X, rather than
+    /// to time, so feet don't slide
+    pub interpolate_x: bool,
+}
+
+/// A trajectory of an actor's script, interpolated between its waypoints
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ActorTrajectory {
+    pub id: u32,
+    /// Name of the animation played along the trajectory
+    pub animation: String,
+    /// Tension of the spline through the waypoints, from 0 for a smooth curve to 1
+    /// for straight lines
+    pub tension: f64,
+    /// Waypoints, sorted by time
+    pub waypoints: Vec<ActorWaypoint>,
+}
+
+/// A keyframe of a trajectory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ActorWaypoint {
+    /// Time since the start of the trajectory, in seconds
+    pub time: f64,
+    /// Position in meters
+    pub position: [f64; 3],
+    pub rotation: [f64; 4],
+}
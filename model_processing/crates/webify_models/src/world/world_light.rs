@@ -0,0 +1,39 @@
+//! Light of a world, in the units of the web viewer
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A light of a world, posed in the frame of the world with Z up and converted to
+/// the physical units of glTF's `KHR_lights_punctual`, which the viewer uses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldLight {
+    pub name: String,
+    pub kind: LightKind,
+    /// Linear RGB, its brightest channel scaled to 1 and the rest of the diffuse
+    /// brightness moved into `intensity`
+    pub color: [f64; 3],
+    /// Luminous intensity in candela for point and spot lights, illuminance in lux
+    /// for directional lights
+    pub intensity: f64,
+    /// Position of the light, in meters
+    pub position: [f64; 3],
+    /// Unit vector the light shines along, for spot and directional lights
+    pub direction: Option<[f64; 3]>,
+    /// Distance beyond which point and spot lights have no effect, in meters
+    pub range: Option<f64>,
+    /// Angle from the axis of a spot light where its light starts falling off, in
+    /// radians
+    pub inner_cone_angle: Option<f64>,
+    /// Angle from the axis of a spot light where its light ends, in radians
+    pub outer_cone_angle: Option<f64>,
+    pub cast_shadows: bool,
+}
+
+/// Type of a light, as in the `type` attribute of SDF's `<light>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LightKind {
+    Point,
+    Spot,
+    Directional,
+}
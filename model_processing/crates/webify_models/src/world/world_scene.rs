@@ -0,0 +1,18 @@
+//! What the viewer needs to replicate the scene of a world
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{WorldActor, WorldLight, WorldViewpoint};
+
+/// Lights, viewpoints and actors of a world, in the frame of the world
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldScene {
+    /// Lights of the world and of its models
+    pub lights: Vec<WorldLight>,
+    /// Camera of the world's `<gui>` first, then its named frames
+    pub viewpoints: Vec<WorldViewpoint>,
+    /// Animated actors, with the trajectories of their scripts
+    #[serde(default)]
+    pub actors: Vec<WorldActor>,
+}
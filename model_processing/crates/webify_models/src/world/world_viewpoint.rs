@@ -0,0 +1,18 @@
+//! Viewpoint of a world, for the viewer to jump to
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A place to look at the world from, in the frame of the world with Z up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorldViewpoint {
+    pub name: String,
+    /// Whether it's the camera of the world's `<gui>`, the one to start from
+    pub default: bool,
+    /// Position of the eye, in meters
+    pub position: [f64; 3],
+    /// Unit vector the eye looks along
+    pub direction: [f64; 3],
+    /// Unit vector pointing up from the eye
+    pub up: [f64; 3],
+}
@@ -0,0 +1,84 @@
+//! Change the text of XML elements picked by where they sit in the document
+
+use std::{io::Error, result::Result};
+
+use quick_xml::{escape::escape, events::Event, Reader};
+
+use crate::xml::XmlElement;
+
+/// Call `edit` with the elements enclosing every non-blank text node, from the root
+/// down to the element holding it, without their children or text, and the
+/// (unescaped, trimmed) value of the text. The value it returns, if any, is swapped
+/// in. Like `edit_text_nodes`, everything else is kept byte for byte, but the edit
+/// can tell apart equal values held by different elements, like the `<init_from>`
+/// of an `<image>` and the one of a `<surface>` in a DAE mesh.
+pub fn edit_element_texts(
+    contents: &str,
+    mut edit: impl FnMut(&[XmlElement], &str) -> Option<String>,
+) -> Result<String, Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut ancestors: Vec<XmlElement> = Vec::new();
+    let mut edited = String::with_capacity(contents.len());
+    let mut copied = 0;
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) => ancestors.push(XmlElement::new(&e)),
+            Ok(Event::End(_)) => {
+                ancestors.pop();
+            }
+            Ok(Event::Text(text)) => {
+                let end = reader.buffer_position() as usize;
+                let raw = &contents[start..end];
+                let value_start = start + (raw.len() - raw.trim_start().len());
+                let value_end = end - (raw.len() - raw.trim_end().len());
+                if value_start >= value_end {
+                    continue;
+                }
+
+                let value = text.unescape().map_err(|e| Error::other(e.to_string()))?;
+                if let Some(replacement) = edit(&ancestors, value.trim()) {
+                    edited.push_str(&contents[copied..value_start]);
+                    edited.push_str(&escape(replacement.as_str()));
+                    copied = value_end;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Invalid XML at byte {}: {}",
+                    reader.error_position(),
+                    e
+                )))
+            }
+            _ => (),
+        }
+    }
+    edited.push_str(&contents[copied..]);
+
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod edit_element_texts_tests {
+    use super::*;
+
+    #[test]
+    fn it_edits_the_text_of_the_elements_asked_for() {
+        let contents = "<COLLADA>\n  <library_images>\n    <image id=\"decal\">\n      <init_from> decal.png </init_from>\n    </image>\n  </library_images>\n  <surface><init_from>decal.png</init_from></surface>\n</COLLADA>\n";
+
+        let edited = edit_element_texts(contents, |ancestors, text| {
+            let image = ancestors.iter().rev().nth(1)?;
+            (image.name == "image" && image.attribute("id") == Some("decal"))
+                .then(|| format!("{} & co", text))
+        })
+        .unwrap();
+
+        assert_eq!(
+            edited,
+            contents.replacen("decal.png", "decal.png &amp; co", 1)
+        );
+        assert_eq!(edit_element_texts(contents, |_, _| None).unwrap(), contents);
+    }
+}
@@ -0,0 +1,173 @@
+//! Change the text nodes of an XML document, leaving everything else byte for byte
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Error, Read, Write},
+    path::Path,
+    result::Result,
+};
+
+use quick_xml::{escape::escape, events::Event, Reader};
+
+/// Call `edit` with the (unescaped, trimmed) value of every non-blank text node and
+/// swap in the value it returns, if any. Only the value itself is replaced: tags,
+/// attributes, comments, indentation and the whitespace around the value are kept
+/// as they were, so hand-edited files stay readable.
+pub fn edit_text_nodes(
+    contents: &str,
+    edit: impl FnMut(&str) -> Option<String>,
+) -> Result<String, Error> {
+    let mut edited = Vec::with_capacity(contents.len());
+    edit_text_nodes_streaming(contents.as_bytes(), contents.as_bytes(), &mut edited, edit)?;
+
+    String::from_utf8(edited).map_err(Error::other)
+}
+
+/// Same as `edit_text_nodes`, on a file that is streamed rather than loaded, so
+/// world files of tens of MB don't have to fit in memory. The edited copy replaces
+/// the file only when something changed. Returns whether it did.
+pub fn edit_file_text_nodes(
+    path: &Path,
+    edit: impl FnMut(&str) -> Option<String>,
+) -> Result<bool, Error> {
+    let mut edited_path = path.as_os_str().to_os_string();
+    edited_path.push(".webify.tmp");
+
+    let changed = {
+        let parse = BufReader::new(File::open(path)?);
+        let raw = BufReader::new(File::open(path)?);
+        let mut out = BufWriter::new(File::create(&edited_path)?);
+        let changed = edit_text_nodes_streaming(parse, raw, &mut out, edit);
+        out.flush()?;
+        changed
+    };
+
+    match changed {
+        Ok(true) => fs::rename(&edited_path, path)?,
+        _ => fs::remove_file(&edited_path)?,
+    }
+
+    changed
+}
+
+/// Pull-parse the document from `parse` while copying the same document from `raw`
+/// to `out`, swapping edited text values on the way. Memory use doesn't depend on
+/// the size of the document.
+pub fn edit_text_nodes_streaming(
+    parse: impl BufRead,
+    mut raw: impl Read,
+    mut out: impl Write,
+    mut edit: impl FnMut(&str) -> Option<String>,
+) -> Result<bool, Error> {
+    let mut reader = Reader::from_reader(parse);
+    let mut buf = Vec::new();
+    let mut copied = 0;
+    let mut changed = false;
+
+    loop {
+        let start = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Text(text)) => {
+                let end = reader.buffer_position();
+                let leading = text.iter().take_while(|b| b.is_ascii_whitespace()).count();
+                if leading == text.len() {
+                    buf.clear();
+                    continue;
+                }
+                let trailing = text
+                    .iter()
+                    .rev()
+                    .take_while(|b| b.is_ascii_whitespace())
+                    .count();
+
+                let value = text.unescape().map_err(|e| Error::other(e.to_string()))?;
+                if let Some(replacement) = edit(value.trim()) {
+                    let value_start = start + leading as u64;
+                    let value_end = end - trailing as u64;
+                    io::copy(&mut (&mut raw).take(value_start - copied), &mut out)?;
+                    io::copy(
+                        &mut (&mut raw).take(value_end - value_start),
+                        &mut io::sink(),
+                    )?;
+                    out.write_all(escape(replacement.as_str()).as_bytes())?;
+                    copied = value_end;
+                    changed = true;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(Error::other(format!(
+                    "Invalid XML at byte {}: {}",
+                    reader.error_position(),
+                    e
+                )))
+            }
+            _ => (),
+        }
+        buf.clear();
+    }
+    io::copy(&mut raw, &mut out)?;
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod edit_text_nodes_tests {
+    use super::*;
+
+    #[test]
+    fn it_only_changes_the_text_it_is_asked_to() {
+        let contents = "<?xml version=\"1.0\"?>\n<!-- <uri>wood.jpg</uri> -->\n<sdf  version='1.6'>\n\t<uri>  wood.jpg </uri>\n  <uri>stone.jpg</uri>\n</sdf>\n";
+
+        let edited = edit_text_nodes(contents, |text| {
+            (text == "wood.jpg").then(|| String::from("../materials/textures/wood & oak.png"))
+        })
+        .unwrap();
+
+        assert_eq!(
+            edited,
+            "<?xml version=\"1.0\"?>\n<!-- <uri>wood.jpg</uri> -->\n<sdf  version='1.6'>\n\t<uri>  ../materials/textures/wood &amp; oak.png </uri>\n  <uri>stone.jpg</uri>\n</sdf>\n"
+        );
+    }
+
+    #[test]
+    fn it_leaves_untouched_documents_identical() {
+        let contents = "<model>\n  <name>Ceiling &amp; Lamp</name>\n</model>\n";
+        assert_eq!(edit_text_nodes(contents, |_| None).unwrap(), contents);
+    }
+}
+
+#[cfg(test)]
+mod edit_file_text_nodes_tests {
+    use super::*;
+
+    #[test]
+    fn it_streams_large_worlds() -> Result<(), Error> {
+        let dir = Path::new("tests")
+            .join("xml")
+            .join("test_run_edit_file_text_nodes");
+        fs::create_dir_all(&dir)?;
+        let world = dir.join("large.world");
+
+        // Big enough to need many buffer refills
+        let include = "  <include>\n    <uri>/home/author/models/lamp</uri>\n  </include>\n";
+        let mut contents = String::from("<sdf version=\"1.6\">\n<world name=\"large\">\n");
+        for _ in 0..20_000 {
+            contents.push_str(include);
+        }
+        contents.push_str("</world>\n</sdf>\n");
+        fs::write(&world, &contents)?;
+
+        assert!(!edit_file_text_nodes(&world, |_| None)?);
+        assert!(edit_file_text_nodes(&world, |text| {
+            (text == "/home/author/models/lamp").then(|| String::from("model://lamp"))
+        })?);
+        assert_eq!(
+            fs::read_to_string(&world)?,
+            contents.replace("/home/author/models/lamp", "model://lamp")
+        );
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+//! Edit XML files (SDFs, meshes, model.config) without reformatting them, and read
+//! the ones that are only read
+
+mod edit_element_texts;
+mod edit_text_nodes;
+mod xml_element;
+
+pub use self::edit_element_texts::edit_element_texts;
+pub use self::edit_text_nodes::{edit_file_text_nodes, edit_text_nodes, edit_text_nodes_streaming};
+pub use self::xml_element::XmlElement;
@@ -0,0 +1,156 @@
+//! Read-only tree of an XML document, for files read whole like DAE meshes
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+/// An element with its attributes, children and text, namespace prefixes dropped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    /// Text directly inside the element, trimmed
+    pub text: String,
+}
+
+impl XmlElement {
+    /// Parse the document, returning its root element
+    pub fn parse(contents: &str) -> Result<XmlElement, quick_xml::Error> {
+        let mut reader = Reader::from_str(contents);
+        reader.config_mut().trim_text(true);
+
+        // The document itself, holding the root
+        let mut stack = vec![XmlElement::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => stack.push(XmlElement::new(&e)),
+                Event::Empty(e) => {
+                    let element = XmlElement::new(&e);
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                Event::End(_) if stack.len() > 1 => {
+                    let element = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                Event::Text(t) => {
+                    let text = t.unescape()?;
+                    stack.last_mut().unwrap().text.push_str(&text);
+                }
+                Event::CData(t) => {
+                    let text = String::from_utf8_lossy(&t).to_string();
+                    stack.last_mut().unwrap().text.push_str(&text);
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        // Unclosed elements are closed at the end of the document
+        while stack.len() > 1 {
+            let element = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(element);
+        }
+        let document = stack.pop().unwrap();
+        Ok(document.children.into_iter().next().unwrap_or_default())
+    }
+
+    pub(crate) fn new(start: &BytesStart) -> XmlElement {
+        XmlElement {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+            attributes: start
+                .attributes()
+                .flatten()
+                .map(|attribute| {
+                    (
+                        String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+                        attribute
+                            .unescape_value()
+                            .map(|value| value.to_string())
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            ..XmlElement::default()
+        }
+    }
+
+    /// Value of the attribute, if the element has it
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// First child element with the name
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Child elements with the name, in document order
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Element at the end of a path of child names, e.g. `["asset", "unit"]`
+    pub fn find(&self, path: &[&str]) -> Option<&XmlElement> {
+        path.iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+
+    /// Every descendant element with the name, in document order
+    pub fn descendants_named<'a>(&'a self, name: &'a str) -> Vec<&'a XmlElement> {
+        let mut found = Vec::new();
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            found.extend(child.descendants_named(name));
+        }
+        found
+    }
+
+    /// Whitespace separated numbers of the text, skipping what doesn't parse
+    pub fn numbers<T: std::str::FromStr>(&self) -> Vec<T> {
+        self.text
+            .split_whitespace()
+            .filter_map(|value| value.parse().ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod xml_element_tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_tree() {
+        let root = XmlElement::parse(
+            r#"<?xml version="1.0"?>
+<sdf version="1.6">
+  <model name="lamp">
+    <pose>1 2 3 0 0 0</pose>
+    <link name="base"><visual name="shade"/></link>
+  </model>
+</sdf>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.name, "sdf");
+        assert_eq!(root.attribute("version"), Some("1.6"));
+        let pose = root.find(&["model", "pose"]).unwrap();
+        assert_eq!(pose.numbers::<f64>(), vec![1.0, 2.0, 3.0, 0.0, 0.0, 0.0]);
+        assert_eq!(
+            root.descendants_named("visual")[0].attribute("name"),
+            Some("shade")
+        );
+        assert!(root.find(&["model", "include"]).is_none());
+    }
+
+    #[test]
+    fn it_errors_on_malformed_documents() {
+        assert!(XmlElement::parse("<a><b></a>").is_err());
+    }
+}